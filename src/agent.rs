@@ -0,0 +1,2053 @@
+use crate::approval::{self, Answer, AuditRecord, Decision, LimitAnswer};
+use crate::args::{CallArgs, EditLinesArgs, SearchReplaceArgs};
+use crate::attach;
+use crate::autonomous::AutonomousRun;
+use crate::backend::{Backend, Chunk};
+use crate::branches::Branches;
+use crate::chats::ChatRegistry;
+use crate::checkpoint::{self, Checkpoint};
+use crate::config::{self, PolicyAction, Profile, Workflow};
+use crate::context::{self, ContextLedger, Section, Source};
+use crate::diff::unified_diff;
+use crate::eventlog::EventLog;
+use crate::events::{AgentEvent, ChatBackend, EventSink};
+use crate::exit::{self, ExitStatus};
+use crate::export::Conversation;
+use crate::format::{self, ResponseFormat, Style};
+use crate::highlight::StreamHighlighter;
+use crate::hooks::{self, ToolCallHook};
+use crate::memory::Memory;
+use crate::model::{CONTEXT_WINDOW_TOKENS, Model};
+use crate::observe::Broadcaster;
+use crate::pins::PinnedFiles;
+use crate::policy::{self, Verdict};
+use crate::protocol::{ToolCall, parse_tool_calls};
+use crate::recipe::Recipe;
+use crate::retry::{self, Failure};
+use crate::sessions::SessionRegistry;
+use crate::spinner::Spinner;
+use crate::stats::{RequestUsage, SessionStats, estimate_tokens, format_tokens};
+use crate::tools::{self, AgentMode, Artifact, ToolResult, execute_tool, tool_allowed};
+use crate::vars::Variables;
+use crate::{dryrun, envinfo, focus, hyperlink, instructions, mentions, notify, workspace};
+use anyhow::{Result, anyhow, bail};
+use colored::Colorize;
+use futures_util::future::{LocalBoxFuture, join_all};
+use futures_util::{Stream, StreamExt, pin_mut};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::fs;
+use tokio::sync::{Semaphore, broadcast};
+
+/// Tool calls from one reply that may run at the same time.
+const MAX_PARALLEL_TOOLS: usize = 4;
+
+/// Rate limits waited out for one request before giving up on it.
+const MAX_RATE_LIMIT_WAITS: u32 = 10;
+
+/// Share of the context window at which chats are compacted, unless the config says
+/// otherwise.
+const DEFAULT_COMPACT_AT_PERCENT: u8 = 80;
+
+const COMPACT_PROMPT: &str = "The conversation is about to be moved to a new chat to free up context, and your summary will be all that carries over. Summarize it: the user's goals and requests, decisions made, files read or changed and what was done to them, commands run and their outcomes, the current state, and what is left to do. Be specific (paths, names, numbers) and leave out pleasantries. Do not call any tools.";
+
+const DRY_RUN_ON_NOTE: &str = "Dry run is on: tools that change files or run commands are not executed. Their results say what they would have done; carry on as if they had, and the user will review the plan.";
+
+const DRY_RUN_OFF_NOTE: &str = "Dry run is off: tool calls are executed again. Calls made during the dry run were not; repeat any that are still needed.";
+
+/// What is appended to the results of a round of tool calls when they are sent back.
+pub const CONTINUE_PROMPT: &str = "Continue with the next step or provide the final answer.";
+
+/// The first message of a chat: the system prompt, then the user's message.
+#[must_use]
+pub fn first_prompt(system_prompt: &str, message: &str) -> String {
+    format!("{system_prompt}\n\nUser:\n{message}")
+}
+
+/// The message that sends the results of a round of tool calls back to the model.
+#[must_use]
+pub fn results_prompt(results: &[String]) -> String {
+    format!("{}\n\n{CONTINUE_PROMPT}", results.join("\n\n"))
+}
+
+/// How many times an empty reply is answered with [`empty_reply_prompt`] before the turn
+/// is given up.
+pub const MAX_EMPTY_REPROMPTS: usize = 3;
+
+/// The message sent after the `attempt`th empty reply in a row, counting from 1, each
+/// firmer than the last, or `None` once [`MAX_EMPTY_REPROMPTS`] have been sent.
+#[must_use]
+pub fn empty_reply_prompt(attempt: usize) -> Option<String> {
+    let warning = match attempt {
+        1 => {
+            "WARNING: Your previous response was empty. Please provide a meaningful response or use tools as appropriate."
+        }
+        2 => {
+            "WARNING: Your last two responses were empty. Reply now with either the next tool call or the final answer. If something is blocking you, say what it is."
+        }
+        3 => {
+            "WARNING: Your responses are still empty. Do not call any tools. Reply in plain text with where the task stands and what you need from the user to go on."
+        }
+        _ => return None,
+    };
+    Some(if attempt < MAX_EMPTY_REPROMPTS {
+        format!("{warning}\n\n{CONTINUE_PROMPT}")
+    } else {
+        warning.to_string()
+    })
+}
+
+/// Runs messages through the model and the tool calls in its replies, for the terminal
+/// chat and any interface that embeds it. What happens is reported to the session's
+/// [`EventSink`], which [`EventSink::with_callback`] hooks into; tools come from
+/// [`tools::registry`].
+pub struct Agent {
+    api: Backend,
+    /// Fired to stop the request in progress; each request takes a fresh receiver
+    interrupts: broadcast::Sender<()>,
+}
+
+impl Agent {
+    #[must_use]
+    pub fn new(api: Backend) -> Self {
+        let (interrupts, _) = broadcast::channel(1);
+        Self { api, interrupts }
+    }
+
+    /// Where chats and replies come from.
+    #[must_use]
+    pub fn api(&self) -> &Backend {
+        &self.api
+    }
+
+    /// Stops the request in progress, if any. A reply stopped partway can be finished with
+    /// /continue.
+    pub fn interrupt(&self) {
+        let _ = self.interrupts.send(());
+    }
+
+    /// Interrupts on every Ctrl+C from now on, as the terminal chat does. Must be called
+    /// from within the Tokio runtime.
+    pub fn interrupt_on_ctrl_c(&self) {
+        let interrupts = self.interrupts.clone();
+        tokio::spawn(async move {
+            loop {
+                if tokio::signal::ctrl_c().await.is_ok() {
+                    let _ = interrupts.send(());
+                }
+            }
+        });
+    }
+
+    /// Receives the interruptions made from now on.
+    #[must_use]
+    pub fn subscribe(&self) -> broadcast::Receiver<()> {
+        self.interrupts.subscribe()
+    }
+
+    /// Sends a message typed by the user and runs the assistant's turn, with the tool calls
+    /// it makes.
+    ///
+    /// # Errors
+    /// Returns an error if a request still fails after its retries.
+    pub async fn send_message(&self, session: &mut Session, message: &str) -> Result<()> {
+        send_message(&self.api, session, &self.interrupts, message).await
+    }
+
+    /// Sends a turn's prompt again, as /retry and /continue do, and runs the tool calls of
+    /// the reply. Returns the content of the first reply, or `None` if it was interrupted.
+    ///
+    /// # Errors
+    /// Returns an error if a request still fails after its retries.
+    pub async fn run_turn(&self, session: &mut Session, turn: &LastTurn) -> Result<Option<String>> {
+        run_turn(&self.api, session, &self.interrupts, turn).await
+    }
+
+    /// Moves the session to a new chat that starts from a summary of this one, leaving out
+    /// the things in `leave_out`. Returns whether it moved.
+    ///
+    /// # Errors
+    /// Returns an error if the summary cannot be had or the new chat cannot be created.
+    pub async fn compact(&self, session: &mut Session, leave_out: &[String]) -> Result<bool> {
+        compact_chat(&self.api, session, &self.interrupts, leave_out).await
+    }
+
+    /// Sends one prompt as a reply to `parent_id` and streams the reply, without running
+    /// its tool calls, for commands that bring their own prompt. Returns `None` if it was
+    /// interrupted.
+    ///
+    /// # Errors
+    /// Returns an error if the request still fails after its retries.
+    pub async fn complete(
+        &self,
+        session: &mut Session,
+        prompt: &str,
+        parent_id: Option<i64>,
+        files: &[String],
+    ) -> Result<Option<Reply>> {
+        let mut interrupts = self.interrupts.subscribe();
+        complete_with_retry(
+            &self.api,
+            session,
+            prompt,
+            parent_id,
+            files,
+            &mut interrupts,
+        )
+        .await
+    }
+
+    /// Uploads an image or document to be sent with the session's next message.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or uploaded.
+    pub async fn attach_file(&self, session: &mut Session, path: &Path) -> Result<()> {
+        attach_file(&self.api, session, path).await
+    }
+
+    /// Attaches a file's contents to the session's next message and watches it for edits.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or uploaded.
+    pub async fn pin_file(&self, session: &mut Session, path: &Path) -> Result<()> {
+        pin_file(&self.api, session, path).await
+    }
+}
+
+/// A session that interfaces other than the line-based REPL, such as `deepseek web` and
+/// `--tui`, send messages to. Messages are handled one at a time.
+pub struct SharedSession {
+    agent: Agent,
+    session: tokio::sync::Mutex<Session>,
+}
+
+impl SharedSession {
+    #[must_use]
+    pub fn new(agent: Agent, session: Session) -> Rc<Self> {
+        Rc::new(Self {
+            agent,
+            session: tokio::sync::Mutex::new(session),
+        })
+    }
+}
+
+impl ChatBackend for SharedSession {
+    fn send_message(&self, message: String, events: EventSink) -> LocalBoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            let mut session = self.session.lock().await;
+            session.events = session.attach(events);
+            let result = self.agent.send_message(&mut session, &message).await;
+            session.events = session.attach(EventSink::default());
+            result
+        })
+    }
+
+    fn interrupt(&self) {
+        self.agent.interrupt();
+    }
+}
+
+/// A conversation with the model: the chat it is attached to, and everything the agent
+/// keeps track of while working in it.
+pub struct Session {
+    pub chat_id: String,
+    pub parent_id: Option<i64>,
+    /// Branches taken with /fork, and the replies seen that they can be taken from
+    pub branches: Branches,
+    /// Files uploaded by commands such as /focus, sent along with the next message
+    pub pending_files: Vec<String>,
+    /// The most recent user turn, kept so that /retry can resend it
+    pub last_turn: Option<LastTurn>,
+    /// A reply stopped with Ctrl+C or cut short by the stream ending, with the content
+    /// streamed of it, for /continue
+    pub interrupted: Option<LastTurn>,
+    /// The last message the user sent, for /edit-last, whether or not its reply finished
+    pub last_message: Option<LastMessage>,
+    pub mode: AgentMode,
+    /// Notes for the model (e.g. mode changes) appended to the next message
+    pub pending_notes: Vec<String>,
+    /// Successful mutating tool calls, for /export script
+    pub recipe: Recipe,
+    /// Listener for turn events besides the terminal, such as the web UI
+    pub events: EventSink,
+    /// Set when the session is mirrored to `deepseek observe`
+    pub observers: Option<Broadcaster>,
+    pub stats: SessionStats,
+    /// What the current chat's context holds, for /context
+    pub context: ContextLedger,
+    /// Hide streamed thinking, showing only responses and tool status
+    pub quiet: bool,
+    /// Print the timing and tokens of each turn after it
+    pub turn_footer: bool,
+    /// Set by `--dry-run` or `/dryrun on`: tools that change things report what they would do
+    pub dry_run: bool,
+    /// Files attached with /focus, checked for edits before each message
+    pub pinned: PinnedFiles,
+    /// Set when the API was unreachable; messages are queued until it is back
+    pub offline: bool,
+    /// Messages typed while offline, oldest first
+    pub offline_queue: Vec<String>,
+    /// Template the session was started from, which may limit the tools
+    pub workflow: Option<Workflow>,
+    /// Role chosen with `--profile` or `/profile`, by name, which may limit the tools
+    pub profile: Option<(String, Profile)>,
+    /// Format replies are asked to follow, from `--format` or `/format`
+    pub format: Option<ResponseFormat>,
+    /// How replies are asked to be written, from `/style`
+    pub style: Option<Style>,
+    /// Length replies are asked to stay under, from `--max-output-tokens`
+    pub max_output_tokens: Option<u32>,
+    /// Rounds of tool calls a message may take before asking whether to go on
+    pub max_tool_iterations: u32,
+    /// Set when a message was stopped at that limit without a terminal to ask on
+    pub stopped_at_limit: Option<u32>,
+    /// Tool calls refused by a `[[policy]]` rule this session
+    pub policy_refusals: usize,
+    /// JSON Schema replies must match, from `--output-schema`
+    pub output_schema: Option<serde_json::Value>,
+    /// Model the next completions are requested from
+    pub model: Model,
+    /// Whether the next completions may search the web, from `/set search`
+    pub search: bool,
+    /// Substituted for `$NAME` in messages and workflow templates
+    pub vars: Variables,
+    /// Set when the user answered "all" to an approval prompt
+    pub approve_all: bool,
+    /// Set by `--autonomous`: the first task runs without approvals for a limited time
+    pub autonomous: Option<AutonomousRun>,
+    /// Checkpoints of the working tree taken this session, oldest first
+    pub checkpoints: Vec<Checkpoint>,
+    /// Name given with `--session`, pointed at the chat's latest message after each turn
+    pub name: Option<String>,
+    /// The turns seen this session, for /export md and /export html
+    pub conversation: Arc<Mutex<Conversation>>,
+    /// JSONL record of the session's events, for auditing
+    pub event_log: Option<Arc<EventLog>>,
+}
+
+/// A prompt as it was sent to the API, together with the reply it produced.
+pub struct LastTurn {
+    pub prompt: String,
+    pub files: Vec<String>,
+    /// The message the prompt was sent as a reply to
+    pub parent_id: Option<i64>,
+    /// Content of the assistant's first reply
+    pub response: String,
+}
+
+/// A reply from the model. One whose stream ended without the final message, as when
+/// the connection drops, is rebuilt from the content that streamed and marked truncated.
+pub struct Reply {
+    pub content: String,
+    pub message_id: Option<i64>,
+    pub truncated: bool,
+}
+
+/// A message as the user typed it, with where in the chat it was sent.
+#[derive(Clone)]
+pub struct LastMessage {
+    pub text: String,
+    /// The message it was sent as a reply to; an edited version is sent there too
+    pub parent_id: Option<i64>,
+    pub files: Vec<String>,
+}
+
+impl Session {
+    /// A session attached to `chat_id`, whose next message replies to `parent_id`, with
+    /// everything else as the config starts it.
+    #[must_use]
+    pub fn new(chat_id: String, parent_id: Option<i64>) -> Self {
+        let conversation = Arc::new(Mutex::new(Conversation::default()));
+        Self {
+            chat_id,
+            parent_id,
+            branches: Branches::new(parent_id),
+            pending_files: Vec::new(),
+            last_turn: None,
+            interrupted: None,
+            last_message: None,
+            mode: AgentMode::default(),
+            pending_notes: Vec::new(),
+            recipe: Recipe::default(),
+            events: EventSink::default().recording(conversation.clone()),
+            observers: None,
+            stats: SessionStats::default(),
+            context: ContextLedger::default(),
+            quiet: false,
+            turn_footer: config::get().turn_footer,
+            dry_run: false,
+            pinned: PinnedFiles::default(),
+            offline: false,
+            offline_queue: Vec::new(),
+            workflow: None,
+            profile: None,
+            format: None,
+            style: None,
+            max_output_tokens: None,
+            max_tool_iterations: default_max_tool_iterations(),
+            stopped_at_limit: None,
+            policy_refusals: 0,
+            output_schema: None,
+            model: Model::default(),
+            search: config::get().search.unwrap_or(true),
+            vars: Variables::default(),
+            approve_all: false,
+            autonomous: None,
+            checkpoints: Vec::new(),
+            name: None,
+            conversation,
+            event_log: None,
+        }
+    }
+
+    /// Creates a chat and a session attached to it.
+    ///
+    /// # Errors
+    /// Returns an error if the chat cannot be created.
+    pub async fn create(api: &Backend) -> Result<Self> {
+        let chat_id = api.create_chat().await?;
+        tracing::debug!(chat_id = %chat_id, "created chat");
+        eprintln!("Chat created with ID: {chat_id}");
+        Ok(Self::new(chat_id, None))
+    }
+
+    /// Attaches the session to another chat, keeping the mode and recorded actions.
+    pub fn switch_to(&mut self, other: Self) {
+        self.chat_id = other.chat_id;
+        self.parent_id = other.parent_id;
+        self.branches = other.branches;
+        self.pending_files = other.pending_files;
+        self.last_turn = None;
+        self.interrupted = None;
+        self.last_message = None;
+        self.pending_notes.clear();
+        self.pinned.clear();
+        self.stats.start_chat();
+        self.context.clear();
+        if let Some(log) = &self.event_log {
+            log.set_chat_id(&self.chat_id);
+        }
+        if let Some(observers) = &mut self.observers
+            && let Err(e) = observers.rebind(&self.chat_id)
+        {
+            eprintln!("{}", format!("Stopped sharing the session: {e}").red());
+            self.observers = None;
+        }
+        if self.mode == AgentMode::Plan && self.parent_id.is_some() {
+            self.pending_notes
+                .push(tools::PLAN_MODE_INSTRUCTIONS.to_string());
+        }
+    }
+
+    /// Moves to another point of the chat's message tree, after which the last turn can no
+    /// longer be retried, continued or edited.
+    pub fn move_to(&mut self, parent_id: Option<i64>) {
+        self.parent_id = parent_id;
+        self.last_turn = None;
+        self.interrupted = None;
+        self.last_message = None;
+    }
+
+    /// The parts of the system prompt sent with the first message of a chat, each with
+    /// where it came from and a label for /context: the prompt itself, then the
+    /// `DEEPSEEK.md` instructions that apply in the working directory, the remembered facts
+    /// and a snapshot of the environment, less those `/context drop` left out.
+    #[must_use]
+    pub fn system_prompt_parts(&self) -> Vec<(Source, String, String)> {
+        let profile = self.profile.as_ref().map(|(_, profile)| profile);
+        let role = profile.and_then(|p| p.system_prompt.as_deref());
+        let tools = self.tools();
+        let mut prompt = if tools.is_none() && role.is_none() {
+            self.mode.system_prompt()
+        } else {
+            tools::build_system_prompt(self.mode, tools.as_deref(), role)
+        };
+        if let Some(extra) = profile.and_then(|p| p.instructions.as_ref()) {
+            prompt.push_str("\n\n");
+            prompt.push_str(extra);
+        }
+        if let Some(extra) = self
+            .workflow
+            .as_ref()
+            .and_then(|w| w.system_prompt.as_ref())
+        {
+            prompt.push_str("\n\n");
+            prompt.push_str(&self.vars.expand(extra));
+        }
+        let mut parts = vec![(Source::SystemPrompt, "role and tools".to_string(), prompt)];
+        if !self.context.omits(Section::Instructions)
+            && let Ok(dir) = std::env::current_dir()
+        {
+            let files = instructions::discover(&dir, instructions::global_path().as_deref());
+            if let Some(section) = instructions::prompt_section(&files) {
+                let paths: Vec<String> =
+                    files.iter().map(|f| f.path.display().to_string()).collect();
+                parts.push((Source::Instructions, paths.join(", "), section));
+            }
+        }
+        if !self.context.omits(Section::Memory)
+            && let Ok(memory) = Memory::load()
+            && let Some(facts) = memory.prompt_section()
+        {
+            let label = format!("{} remembered fact(s)", memory.facts().len());
+            parts.push((Source::Memory, label, facts));
+        }
+        if !self.context.omits(Section::Environment) && config::get().environment.unwrap_or(true) {
+            let label = "OS, shell, working directory, git status and toolchains".to_string();
+            parts.push((Source::Environment, label, envinfo::snapshot().render()));
+        }
+        parts
+    }
+
+    /// The tools the session is limited to, if any: those of the workflow or failing that
+    /// the profile, less any the config leaves out.
+    #[must_use]
+    pub fn tools(&self) -> Option<Vec<String>> {
+        let config = config::get();
+        let listed = self
+            .workflow
+            .as_ref()
+            .and_then(|w| w.tools.as_ref())
+            .or_else(|| self.profile.as_ref()?.1.tools.as_ref());
+        match listed {
+            Some(listed) => Some(
+                listed
+                    .iter()
+                    .filter(|tool| config.allows_tool(tool))
+                    .cloned()
+                    .collect(),
+            ),
+            None => config.tools.clone(),
+        }
+    }
+
+    /// Turns dry-run mode on or off, telling the model with the next message.
+    pub fn set_dry_run(&mut self, on: bool) {
+        if self.dry_run == on {
+            return;
+        }
+        self.dry_run = on;
+        self.pending_notes.push(if on {
+            DRY_RUN_ON_NOTE.to_string()
+        } else {
+            DRY_RUN_OFF_NOTE.to_string()
+        });
+    }
+
+    /// Switches to the named profile, or back to none. A chat already under way is told
+    /// about the change with the next message.
+    ///
+    /// # Errors
+    /// Returns an error if the profile does not exist or lists an unknown tool.
+    pub fn set_profile(&mut self, name: Option<&str>) -> Result<()> {
+        let profile = match name {
+            Some(name) => {
+                let profile = config::get().profile(name)?;
+                if let Some(unknown) = profile
+                    .tools
+                    .iter()
+                    .flatten()
+                    .find(|tool| !tools::tool_exists(tool))
+                {
+                    bail!("Profile {name} lists an unknown tool: {unknown}");
+                }
+                Some((name.to_string(), profile))
+            }
+            None => None,
+        };
+        let previous = std::mem::replace(&mut self.profile, profile);
+        if self.parent_id.is_none() {
+            return Ok(());
+        }
+        let mut note = match (&self.profile, previous) {
+            (Some((name, profile)), _) => format!(
+                "The user switched to the {name} profile. {}",
+                profile
+                    .system_prompt
+                    .iter()
+                    .chain(&profile.instructions)
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+            (None, Some((name, _))) => {
+                format!("The user left the {name} profile, and its instructions no longer apply.")
+            }
+            (None, None) => return Ok(()),
+        };
+        note.push_str(&format!(
+            " The tools available now are:\n{}",
+            tools::tool_descriptions(self.mode, self.tools().as_deref())
+        ));
+        self.pending_notes.push(note.trim().to_string());
+        Ok(())
+    }
+
+    /// Starts the session from a workflow: pins its files and returns its first message.
+    ///
+    /// # Errors
+    /// Returns an error if the workflow does not exist, lists an unknown tool or names a
+    /// file that cannot be pinned.
+    pub async fn apply_workflow(&mut self, api: &Backend, name: &str) -> Result<Option<String>> {
+        let workflow = config::get().workflow(name)?.clone();
+        if let Some(unknown) = workflow
+            .tools
+            .iter()
+            .flatten()
+            .find(|tool| !tools::tool_exists(tool))
+        {
+            bail!("Workflow {name} lists an unknown tool: {unknown}");
+        }
+        for path in &workflow.pin {
+            let path = self.vars.expand(path);
+            pin_file(api, self, Path::new(&path))
+                .await
+                .map_err(|e| anyhow!("Workflow {name} could not pin {path}: {e}"))?;
+        }
+        eprintln!(
+            "{}",
+            format!(
+                "Started workflow {name} ({} pinned files)",
+                workflow.pin.len()
+            )
+            .cyan()
+        );
+        let instruction = workflow.instruction.clone();
+        self.workflow = Some(workflow);
+        Ok(instruction)
+    }
+
+    /// Connects `events` to the session's conversation record and event log.
+    #[must_use]
+    pub fn attach(&self, events: EventSink) -> EventSink {
+        let events = events.recording(self.conversation.clone());
+        match &self.event_log {
+            Some(log) => events.logging(log.clone()),
+            None => events,
+        }
+    }
+
+    /// Starts the session's event log in `dir`, or the default directory.
+    ///
+    /// # Errors
+    /// Returns an error if the log cannot be created.
+    pub fn start_event_log(&mut self, dir: Option<&Path>) -> Result<()> {
+        let dir = match dir {
+            Some(dir) => dir.to_path_buf(),
+            None => {
+                EventLog::default_dir().ok_or_else(|| anyhow!("No config directory available"))?
+            }
+        };
+        self.event_log = Some(Arc::new(EventLog::create(&dir, &self.chat_id)?));
+        self.events = self.attach(self.events.clone());
+        Ok(())
+    }
+
+    /// Mirrors the session, read-only, to `deepseek observe` in other terminals.
+    ///
+    /// # Errors
+    /// Returns an error if the session cannot be shared.
+    pub fn share(&mut self) -> Result<()> {
+        let (broadcaster, events) = Broadcaster::start(&self.chat_id)?;
+        self.events = self.attach(events);
+        self.observers = Some(broadcaster);
+        eprintln!(
+            "{}",
+            format!(
+                "Sharing this session: run `deepseek observe {}` in another terminal to watch",
+                self.chat_id
+            )
+            .cyan()
+        );
+        Ok(())
+    }
+
+    /// Attaches a session to chat `id`, replying to its latest message.
+    ///
+    /// # Errors
+    /// Returns an error if the chat cannot be found.
+    pub async fn resume(api: &Backend, id: String) -> Result<Self> {
+        eprintln!("Resuming chat with ID: {id}");
+        let current_message_id = api.current_message_id(&id).await?;
+        tracing::debug!(chat_id = %id, current_message_id = ?current_message_id, "resumed chat");
+        record_chat_activity(&id, None);
+        Ok(Self::new(id, current_message_id))
+    }
+}
+
+async fn handle_stream<S>(
+    stream: S,
+    ctrl_rx: &mut broadcast::Receiver<()>,
+    events: &EventSink,
+    quiet: bool,
+    usage: &mut RequestUsage,
+    partial: &mut String,
+    first_chunk: &mut Option<Instant>,
+) -> Result<Option<Reply>>
+where
+    S: Stream<Item = Result<Chunk>>,
+{
+    pin_mut!(stream);
+    // Bytes of thinking and content so far, kept in `usage` however the stream ends; the
+    // content itself is kept in `partial`
+    let mut generated = 0;
+    let echo = events.echo();
+    let mut highlighter = StreamHighlighter::new();
+    let mut final_message = None;
+    let mut thinking_started = false;
+    let mut content_started = false;
+    // Shown until the first output replaces it, and through thinking that is not shown
+    let mut spinner = echo.then(|| Spinner::start("Waiting for the response"));
+    loop {
+        tokio::select! {
+            maybe_chunk = stream.next() => {
+                match maybe_chunk {
+                    Some(chunk) => {
+                        match chunk? {
+                            Chunk::Thinking { text: thought } => {
+                                tracing::trace!(kind = "thinking", bytes = thought.len(), "stream chunk");
+                                first_chunk.get_or_insert_with(Instant::now);
+                                generated += thought.len();
+                                usage.completion_tokens = generated.div_ceil(4);
+                                events.emit(AgentEvent::Thinking { text: thought.to_string() });
+                                if !echo {
+                                    continue;
+                                }
+                                if quiet {
+                                    if let Some(spinner) = &spinner {
+                                        spinner.set_message("Thinking");
+                                    }
+                                    continue;
+                                }
+                                spinner = None;
+                                if !thinking_started {
+                                    println!("{}", "--- Thinking ---".yellow());
+                                    thinking_started = true;
+                                }
+                                print!("{}", thought.dimmed());
+                                std::io::stdout().flush()?;
+                            }
+                            Chunk::Content { text } => {
+                                tracing::trace!(kind = "content", bytes = text.len(), "stream chunk");
+                                first_chunk.get_or_insert_with(Instant::now);
+                                generated += text.len();
+                                usage.completion_tokens = generated.div_ceil(4);
+                                partial.push_str(&text);
+                                events.emit(AgentEvent::Content { text: text.to_string() });
+                                if !echo {
+                                    continue;
+                                }
+                                spinner = None;
+                                if !content_started {
+                                    if thinking_started {
+                                        println!("\n{}", "--- End of thinking ---".yellow());
+                                    }
+                                    println!("{}", "--- Response ---".green());
+                                    content_started = true;
+                                }
+                                print!("{}", highlighter.push(&text));
+                                std::io::stdout().flush()?;
+                            }
+                            Chunk::Message { content, message_id } => {
+                                tracing::trace!(kind = "message", message_id = ?message_id, bytes = content.len(), "stream chunk");
+                                events.emit(AgentEvent::Message {
+                                    content: content.clone(),
+                                    message_id,
+                                });
+                                spinner = None;
+                                if echo {
+                                    print!("{}", highlighter.finish());
+                                    if thinking_started && !content_started {
+                                        println!("\n{}", "--- End of thinking ---".yellow());
+                                    }
+                                    println!(); // newline after content
+                                }
+                                final_message = Some(Reply {
+                                    content,
+                                    message_id,
+                                    truncated: false,
+                                });
+                            }
+                        }
+                    }
+                    None => break,
+                }
+            }
+            _ = ctrl_rx.recv() => {
+                tracing::debug!("stream interrupted by the user");
+                drop(spinner);
+                if echo {
+                    print!("{}", highlighter.finish());
+                    if partial.is_empty() {
+                        println!("\n{}", "Stream interrupted by user".yellow());
+                    } else {
+                        println!(
+                            "\n{}",
+                            "--- Partial response, interrupted by user (/continue to finish it) ---"
+                                .yellow()
+                        );
+                    }
+                }
+                events.emit(AgentEvent::Interrupted);
+                return Ok(None);
+            }
+        }
+    }
+    if final_message.is_none() && !partial.is_empty() {
+        tracing::warn!(
+            bytes = partial.len(),
+            "stream ended without a final message"
+        );
+        events.emit(AgentEvent::Message {
+            content: partial.clone(),
+            message_id: None,
+        });
+        drop(spinner);
+        if echo {
+            print!("{}", highlighter.finish());
+            println!(
+                "\n{}",
+                "--- The stream ended early, so the response may be cut short (/continue to finish it) ---"
+                    .yellow()
+            );
+        }
+        return Ok(Some(Reply {
+            content: partial.clone(),
+            message_id: None,
+            truncated: true,
+        }));
+    }
+    Ok(final_message)
+}
+
+/// Points the session's name, if it has one, at the chat and the message it has got to.
+fn record_named_session(session: &Session) {
+    let Some(name) = &session.name else {
+        return;
+    };
+    let result = SessionRegistry::load().and_then(|mut registry| {
+        registry.set(name, &session.chat_id, session.parent_id);
+        registry.save()
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to update sessions: {e}");
+    }
+}
+
+/// Records activity in chat `chat_id` in the chat list, for `deepseek chats`.
+pub fn record_chat_activity(chat_id: &str, message: Option<&str>) {
+    let result = ChatRegistry::load().and_then(|mut registry| {
+        registry.touch(chat_id, message);
+        registry.save()
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to update chat list: {e}");
+    }
+}
+
+/// Prints an autonomous run's progress, taking a checkpoint of the working tree for it.
+fn report_autonomous_progress(session: &mut Session, title: &str) {
+    if session.autonomous.is_none() {
+        return;
+    }
+    let checkpoint = match checkpoint::create(Path::new("."), &format!("deepseek-cli: {title}")) {
+        Ok(checkpoint) => {
+            let summary = format!(
+                "{} {} (restore with: /checkpoint restore {})",
+                checkpoint.backend,
+                checkpoint.short_id(),
+                checkpoint.short_id()
+            );
+            session.checkpoints.push(checkpoint);
+            summary
+        }
+        Err(e) => format!("failed ({e})"),
+    };
+    let Some(run) = &session.autonomous else {
+        return;
+    };
+    let actions = session
+        .recipe
+        .actions()
+        .get(run.first_action()..)
+        .unwrap_or_default();
+    eprintln!(
+        "{}",
+        run.report(title, Instant::now(), actions, &checkpoint)
+            .yellow()
+    );
+}
+
+/// Sends a message typed by the user and runs the assistant's turn.
+async fn send_message(
+    api: &Backend,
+    session: &mut Session,
+    tx: &broadcast::Sender<()>,
+    full_input: &str,
+) -> Result<()> {
+    if full_input.is_empty() {
+        return Ok(());
+    }
+    let hooked = hooks::current()
+        .and_then(|hooks| hooks.on_user_message(full_input))
+        .unwrap_or_else(|e| {
+            eprintln!("{}", format!("Hook failed: {e}").yellow());
+            None
+        });
+    let full_input = hooked.as_deref().unwrap_or(full_input);
+    let compact_at = config::get()
+        .compact_at_percent
+        .unwrap_or(DEFAULT_COMPACT_AT_PERCENT);
+    if session.parent_id.is_some()
+        && session
+            .stats
+            .needs_compaction(CONTEXT_WINDOW_TOKENS, compact_at)
+    {
+        if session.events.echo() {
+            println!(
+                "{}",
+                format!(
+                    "The chat holds ~{} of {} tokens; compacting it before sending",
+                    format_tokens(session.stats.context_tokens()),
+                    format_tokens(CONTEXT_WINDOW_TOKENS)
+                )
+                .cyan()
+            );
+        }
+        if let Err(e) = compact_chat(api, session, tx, &[]).await {
+            eprintln!("{}", format!("Could not compact the chat: {e}").yellow());
+        }
+    }
+    record_chat_activity(&session.chat_id, Some(full_input));
+    session.events.emit(AgentEvent::User {
+        text: full_input.to_string(),
+    });
+    let message = full_input;
+    let full_input = session.vars.expand(full_input);
+    for path in mentions::attachments(&full_input) {
+        if let Err(e) = attach_file(api, session, Path::new(&path)).await {
+            eprintln!("{}", format!("Could not attach {path}: {e}").yellow());
+        }
+    }
+    let mut full_input = focus::annotate(&mentions::expand(&full_input));
+    if let Some(run) = &mut session.autonomous
+        && run.start(Instant::now(), session.recipe.actions().len())
+    {
+        session.pending_notes.push(run.instructions());
+    }
+    for note in session.pending_notes.drain(..) {
+        full_input.push_str(&format!("\n\n[{note}]"));
+    }
+    for update in session.pinned.refresh() {
+        full_input.push_str(&format!("\n\n{update}"));
+    }
+    if let Some(note) = format::constraints_note(
+        session.format,
+        session.max_output_tokens,
+        session.output_schema.as_ref(),
+    ) {
+        full_input.push_str(&format!("\n\n[{note}]"));
+    }
+    if let Some(style) = session.style {
+        full_input.push_str(&format!("\n\n[{}]", style.instruction()));
+    }
+
+    // Prepend system prompt only on the very first message
+    let message_tokens = estimate_tokens(&full_input);
+    let prompt = if session.parent_id.is_none() {
+        let mut system_prompt = Vec::new();
+        for (source, label, text) in session.system_prompt_parts() {
+            session
+                .context
+                .record(source, label, estimate_tokens(&text));
+            system_prompt.push(text);
+        }
+        first_prompt(&system_prompt.join("\n\n"), &full_input)
+    } else {
+        full_input
+    };
+
+    session
+        .context
+        .record(Source::Message, context::label(message), message_tokens);
+
+    let turn = LastTurn {
+        prompt,
+        files: std::mem::take(&mut session.pending_files),
+        parent_id: session.parent_id,
+        response: String::new(),
+    };
+    session.last_message = Some(LastMessage {
+        text: message.to_string(),
+        parent_id: turn.parent_id,
+        files: turn.files.clone(),
+    });
+    let response = run_turn(api, session, tx, &turn).await;
+    if session
+        .autonomous
+        .as_ref()
+        .is_some_and(AutonomousRun::is_running)
+    {
+        report_autonomous_progress(session, "Autonomous run finished");
+        println!(
+            "{}",
+            "Review the changes above; tool calls need approval again from here on.".yellow()
+        );
+        if let Some(run) = &mut session.autonomous {
+            run.finish();
+        }
+    }
+    if let Some(response) = response? {
+        session.last_turn = Some(LastTurn { response, ..turn });
+    }
+    record_named_session(session);
+    session.events.emit(AgentEvent::Done);
+    Ok(())
+}
+
+/// Asks the model to summarize the chat, leaving out the things in `leave_out`, then moves
+/// the session to a new chat that starts from the summary, with the pinned files attached
+/// again. Returns whether it moved.
+async fn compact_chat(
+    api: &Backend,
+    session: &mut Session,
+    tx: &broadcast::Sender<()>,
+    leave_out: &[String],
+) -> Result<bool> {
+    if session.parent_id.is_none() {
+        println!("Nothing to compact yet");
+        return Ok(false);
+    }
+    let before = session.stats.context_tokens();
+    if session.events.echo() {
+        println!("{}", "Summarizing the conversation...".cyan());
+    }
+    let mut prompt = COMPACT_PROMPT.to_string();
+    if !leave_out.is_empty() {
+        prompt.push_str(
+            "\n\nThe user has dropped these from the conversation; leave them and what they said out of the summary:",
+        );
+        for item in leave_out {
+            prompt.push_str(&format!("\n- {item}"));
+        }
+    }
+    let mut rx = tx.subscribe();
+    let parent_id = session.parent_id;
+    let Some(reply) = complete_with_retry(api, session, &prompt, parent_id, &[], &mut rx).await?
+    else {
+        return Ok(false);
+    };
+    if reply.truncated {
+        bail!("the summary was cut short, so the chat was left as it was");
+    }
+    let summary = reply.content.trim();
+    if summary.is_empty() {
+        bail!("the summary was empty, so the chat was left as it was");
+    }
+    let summary = summary.to_string();
+    let pinned: Vec<PathBuf> = session
+        .pinned
+        .paths()
+        .into_iter()
+        .map(Path::to_path_buf)
+        .collect();
+    let old_chat = session.chat_id.clone();
+    session.switch_to(Session::create(api).await?);
+    for path in &pinned {
+        if let Err(e) = pin_file(api, session, path).await {
+            eprintln!(
+                "{}",
+                format!("Could not attach {} again: {e}", path.display()).yellow()
+            );
+        }
+    }
+    session.context.record(
+        Source::Summary,
+        format!("of chat {old_chat}"),
+        estimate_tokens(&summary),
+    );
+    session.pending_notes.push(format!(
+        "This conversation continues chat {old_chat}, which was compacted. Summary of it so far:\n\n{summary}"
+    ));
+    if session.events.echo() {
+        println!(
+            "{}",
+            format!(
+                "Compacted ~{} tokens into a summary; carrying on in chat {}",
+                format_tokens(before),
+                session.chat_id
+            )
+            .cyan()
+        );
+    }
+    Ok(true)
+}
+
+/// Sends a prompt (with search unless `/set search off`, and thinking if the model reasons)
+/// and streams the reply.
+///
+/// Failed requests and streams are retried with exponential backoff and jitter, as set by
+/// `[retry]` in the config. Rate limits are waited out separately, for as long as the API
+/// asks. Returns `None` if the user interrupted; a reply whose stream ended before the
+/// final message comes back truncated, and /continue can finish it.
+async fn complete_with_retry(
+    api: &Backend,
+    session: &mut Session,
+    prompt: &str,
+    parent_id: Option<i64>,
+    files: &[String],
+    ctrl_rx: &mut broadcast::Receiver<()>,
+) -> Result<Option<Reply>> {
+    let retry = &config::get().retry;
+    session.interrupted = None;
+    let mut attempt = 0;
+    let mut rate_limit_waits = 0;
+    loop {
+        tracing::debug!(
+            chat_id = %session.chat_id,
+            parent_id = ?parent_id,
+            model = %session.model,
+            prompt_bytes = prompt.len(),
+            files = files.len(),
+            attempt,
+            "completion request"
+        );
+        let started = Instant::now();
+        let stream = api.complete_stream(
+            session.chat_id.clone(),
+            prompt.to_string(),
+            parent_id,
+            session.search,
+            session.model.thinking(),
+            files.to_vec(),
+        );
+        let mut usage = RequestUsage {
+            prompt_tokens: estimate_tokens(prompt),
+            completion_tokens: 0,
+        };
+        let mut partial = String::new();
+        let mut first_chunk = None;
+        let streamed = handle_stream(
+            stream,
+            ctrl_rx,
+            &session.events,
+            session.quiet,
+            &mut usage,
+            &mut partial,
+            &mut first_chunk,
+        )
+        .await;
+        tracing::debug!(
+            elapsed_ms = started.elapsed().as_millis(),
+            completion_tokens = usage.completion_tokens,
+            ok = streamed.is_ok(),
+            "completion finished"
+        );
+        session.stats.record_stream(
+            first_chunk.map(|at| at.duration_since(started)),
+            started.elapsed(),
+        );
+        session.stats.record_request(usage);
+        let error = match streamed {
+            Ok(Some(reply)) if reply.truncated => {
+                session.context.record(
+                    Source::Reply,
+                    context::label(&reply.content),
+                    usage.completion_tokens,
+                );
+                session.interrupted = Some(LastTurn {
+                    prompt: prompt.to_string(),
+                    files: files.to_vec(),
+                    parent_id,
+                    response: partial,
+                });
+                return Ok(Some(reply));
+            }
+            Ok(None) if !partial.is_empty() => {
+                session.interrupted = Some(LastTurn {
+                    prompt: prompt.to_string(),
+                    files: files.to_vec(),
+                    parent_id,
+                    response: partial,
+                });
+                return Ok(None);
+            }
+            Ok(Some(message)) => {
+                if let Some(id) = message.message_id {
+                    session.branches.record(parent_id, id, &message.content);
+                }
+                session.context.record(
+                    Source::Reply,
+                    context::label(&message.content),
+                    usage.completion_tokens,
+                );
+                return Ok(Some(message));
+            }
+            Ok(None) => return Ok(None),
+            Err(e) => e,
+        };
+        let waited = match retry::classify(&error) {
+            Failure::RateLimited { retry_after } if rate_limit_waits < MAX_RATE_LIMIT_WAITS => {
+                rate_limit_waits += 1;
+                let delay = retry_after.unwrap_or_else(|| retry.backoff(rate_limit_waits));
+                tracing::warn!(error = %error, delay_ms = delay.as_millis(), "rate limited");
+                wait_out_rate_limit(delay, session.events.echo(), ctrl_rx).await
+            }
+            Failure::Transient | Failure::Offline if attempt < retry.attempts => {
+                attempt += 1;
+                let delay = retry::with_jitter(retry.backoff(attempt));
+                tracing::warn!(
+                    error = %error,
+                    attempt,
+                    delay_ms = delay.as_millis(),
+                    "retrying request"
+                );
+                if session.events.echo() {
+                    eprintln!(
+                        "{}",
+                        format!(
+                            "Request failed: {error}. Retrying in {:.1}s ({attempt}/{})...",
+                            delay.as_secs_f64(),
+                            retry.attempts
+                        )
+                        .yellow()
+                    );
+                }
+                tokio::select! {
+                    () = tokio::time::sleep(delay) => true,
+                    _ = ctrl_rx.recv() => false,
+                }
+            }
+            _ => return Err(exit::with_status(ExitStatus::ApiError, error)),
+        };
+        if !waited {
+            return Ok(None);
+        }
+    }
+}
+
+/// Waits for a rate limit to pass, showing a countdown. Returns `false` if the user
+/// interrupted the wait.
+async fn wait_out_rate_limit(
+    delay: Duration,
+    echo: bool,
+    ctrl_rx: &mut broadcast::Receiver<()>,
+) -> bool {
+    let deadline = tokio::time::Instant::now() + delay;
+    loop {
+        let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        if echo {
+            eprint!(
+                "\r{}",
+                format!(
+                    "Rate limited by the API, retrying in {}s (Ctrl+C to cancel)  ",
+                    remaining.as_secs_f64().ceil()
+                )
+                .yellow()
+            );
+            let _ = std::io::stderr().flush();
+        }
+        tokio::select! {
+            () = tokio::time::sleep(remaining.min(Duration::from_secs(1))) => {}
+            _ = ctrl_rx.recv() => {
+                if echo {
+                    eprintln!();
+                }
+                return false;
+            }
+        }
+    }
+    if echo {
+        eprintln!();
+    }
+    true
+}
+
+/// Sends the turn's prompt and drives the assistant through any tool calls until it stops,
+/// timing the turn for `/stats` and the footer and notifying its end if it took long.
+///
+/// Returns the content of the assistant's first reply, or `None` if the user interrupted
+/// the stream before it finished.
+async fn run_turn(
+    api: &Backend,
+    session: &mut Session,
+    tx: &broadcast::Sender<()>,
+    turn: &LastTurn,
+) -> Result<Option<String>> {
+    let started = Instant::now();
+    session.stats.start_turn(started);
+    let response = run_turn_loop(api, session, tx, turn).await;
+    if let Some(stats) = session.stats.finish_turn(Instant::now())
+        && session.turn_footer
+        && session.events.echo()
+    {
+        println!("{}", stats.render_footer().dimmed());
+    }
+    // A turn stopped with Ctrl+C ended with the user at the terminal
+    if !matches!(response, Ok(None)) {
+        notify::current().turn_finished(started.elapsed());
+    }
+    response
+}
+
+/// The untimed body of [`run_turn`].
+async fn run_turn_loop(
+    api: &Backend,
+    session: &mut Session,
+    tx: &broadcast::Sender<()>,
+    turn: &LastTurn,
+) -> Result<Option<String>> {
+    // Stream the assistant's response
+    let mut rx = tx.subscribe();
+    let final_message = complete_with_retry(
+        api,
+        session,
+        &turn.prompt,
+        turn.parent_id,
+        &turn.files,
+        &mut rx,
+    )
+    .await?;
+    let Some(mut current_msg) = final_message else {
+        // Stream was interrupted; return to input prompt silently
+        return Ok(None);
+    };
+    // A truncated reply has no id; the next message goes where it was sent
+    session.parent_id = current_msg.message_id.or(session.parent_id);
+    let first_response = current_msg.content.clone();
+    let mut iterations = 0;
+
+    loop {
+        // Reprompt an empty reply, more firmly each time, and give the turn back to the
+        // user rather than spend requests on a model that keeps sending nothing
+        let mut empty_replies = 0;
+        while current_msg.content.trim().is_empty() {
+            empty_replies += 1;
+            let Some(warning) = empty_reply_prompt(empty_replies) else {
+                tracing::warn!(empty_replies, "giving up on empty replies");
+                eprintln!(
+                    "{}",
+                    format!(
+                        "The model sent {empty_replies} empty replies in a row, so the turn was stopped. Send a message to go on, or /retry to ask again."
+                    )
+                    .yellow()
+                );
+                return Ok(Some(first_response));
+            };
+            if session.events.echo() {
+                eprintln!(
+                    "{}",
+                    format!(
+                        "Model returned an empty response, reprompting ({empty_replies}/{})...",
+                        MAX_EMPTY_REPROMPTS
+                    )
+                    .yellow()
+                );
+            }
+            let mut rx_inner = tx.subscribe();
+            let parent_id = session.parent_id;
+            let new_msg =
+                complete_with_retry(api, session, &warning, parent_id, &[], &mut rx_inner).await?;
+            match new_msg {
+                Some(msg) => {
+                    session.parent_id = msg.message_id.or(session.parent_id);
+                    current_msg = msg;
+                }
+                None => {
+                    // Stream interrupted during reprompt; go back to user input silently
+                    return Ok(Some(first_response));
+                }
+            }
+        }
+        if let Err(e) =
+            hooks::current().and_then(|hooks| hooks.on_assistant_message(&current_msg.content))
+        {
+            eprintln!("{}", format!("Hook failed: {e}").yellow());
+        }
+        // A reply cut off partway may end inside a tool call, so none of its calls are run
+        if current_msg.truncated {
+            return Ok(Some(first_response));
+        }
+
+        // Guard against a model that keeps calling tools without getting anywhere
+        if iterations >= session.max_tool_iterations
+            && !parse_tool_calls(&current_msg.content).is_empty()
+        {
+            if !continue_past_iteration_limit(session, iterations).await {
+                return Ok(Some(first_response));
+            }
+            // Continuing allows as many rounds again; a raised limit counts from the start
+            if iterations >= session.max_tool_iterations {
+                iterations = 0;
+            }
+        }
+        iterations += 1;
+
+        // Handle tool calls
+        match handle_tool_calls(api, session, current_msg, &mut rx).await? {
+            Some(new_msg) => {
+                current_msg = new_msg;
+                // parent_id already updated inside handle_tool_calls
+            }
+            None => {
+                // No more tool calls, done with this assistant turn
+                return Ok(Some(first_response));
+            }
+        }
+    }
+}
+
+/// The prompt that was interrupted, asking the model to pick its reply up after the part
+/// that had streamed.
+#[must_use]
+pub fn continuation_prompt(prompt: &str, partial: &str) -> String {
+    let fence = mentions::fence_for(partial);
+    format!(
+        "{prompt}\n\n[Your reply to this was interrupted after the part below. Continue it from exactly where it stopped, without repeating any of it.]\n\n{fence}\n{partial}\n{fence}"
+    )
+}
+
+fn default_max_tool_iterations() -> u32 {
+    config::get()
+        .max_tool_iterations
+        .filter(|&rounds| rounds > 0)
+        .unwrap_or(approval::DEFAULT_MAX_TOOL_ITERATIONS)
+}
+
+/// Asks whether to keep going after `iterations` rounds of tool calls for one message,
+/// returning whether to. Raising the limit to more rounds than have been taken counts as
+/// going on. Without a terminal to ask on the message stops, and an autonomous run, which
+/// has its own time limit, goes on.
+async fn continue_past_iteration_limit(session: &mut Session, iterations: u32) -> bool {
+    if session
+        .autonomous
+        .as_ref()
+        .is_some_and(AutonomousRun::is_running)
+    {
+        return true;
+    }
+    if !tools::is_interactive() {
+        if session.events.echo() {
+            eprintln!(
+                "{}",
+                format!("Stopped after {iterations} rounds of tool calls (see --max-iterations)")
+                    .yellow()
+            );
+        }
+        session.stopped_at_limit = Some(iterations);
+        return false;
+    }
+    println!(
+        "{}",
+        format!("The model has made {iterations} rounds of tool calls for this message.").yellow()
+    );
+    notify::current().waiting(&format!(
+        "keep going after {iterations} rounds of tool calls?"
+    ));
+    loop {
+        let reply = tokio::task::spawn_blocking(|| {
+            print!("Keep going? [c(ontinue)/A(bort)/<new limit>] ");
+            std::io::stdout().flush().ok()?;
+            let mut line = String::new();
+            match std::io::stdin().read_line(&mut line) {
+                Ok(0) | Err(_) => None,
+                Ok(_) => Some(line),
+            }
+        })
+        .await
+        .ok()
+        .flatten();
+        let Some(reply) = reply else {
+            return false;
+        };
+        match LimitAnswer::parse(&reply) {
+            Some(LimitAnswer::Continue) => return true,
+            Some(LimitAnswer::Abort) => return false,
+            Some(LimitAnswer::Raise(limit)) => {
+                session.max_tool_iterations = limit;
+                if limit > iterations {
+                    return true;
+                }
+                println!("That is no more than the rounds taken so far");
+            }
+            None => println!("Answer c, a or a number"),
+        }
+    }
+}
+
+/// Uploads an image or document to be sent with the next message, showing how long the
+/// upload has been going.
+async fn attach_file(api: &Backend, session: &mut Session, path: &Path) -> Result<()> {
+    let attachment = attach::read(path)?;
+    let size = attach::format_size(attachment.data.len());
+    // Estimated from the size, as images and documents do not count as text does
+    let tokens = attachment.data.len().div_ceil(4);
+    let echo = session.events.echo();
+    let started = Instant::now();
+    let upload = api.upload_file(attachment.data, &attachment.name, attachment.mime_type);
+    pin_mut!(upload);
+    let mut ticker = tokio::time::interval(Duration::from_millis(500));
+    let uploaded = loop {
+        tokio::select! {
+            result = &mut upload => break result,
+            _ = ticker.tick() => {
+                if echo {
+                    eprint!(
+                        "\r{}",
+                        format!(
+                            "Uploading {} ({size})... {}s",
+                            attachment.name,
+                            started.elapsed().as_secs()
+                        )
+                        .cyan()
+                    );
+                    let _ = std::io::stderr().flush();
+                }
+            }
+        }
+    };
+    if echo {
+        eprintln!();
+    }
+    let file_id = uploaded.map_err(|e| anyhow!("Upload failed: {e}"))?;
+    tracing::debug!(
+        file = %attachment.name,
+        size = %size,
+        elapsed_ms = started.elapsed().as_millis(),
+        "uploaded attachment"
+    );
+    session.pending_files.push(file_id);
+    session
+        .context
+        .record(Source::Attachment, attachment.name.clone(), tokens);
+    if echo {
+        println!(
+            "{}",
+            format!("Attached {} ({size})", attachment.name).green()
+        );
+    }
+    Ok(())
+}
+
+/// Pins a file by attaching its contents to the next message and watching it for edits.
+async fn pin_file(api: &Backend, session: &mut Session, path: &Path) -> Result<()> {
+    let path_str = path.to_string_lossy();
+    let content = fs::read_to_string(path).await?;
+    let file_id = upload_tool_output(api, &content, "read_file", &path_str).await?;
+    session.pending_files.push(file_id);
+    session.context.record(
+        Source::Pinned(path.to_path_buf()),
+        path_str,
+        estimate_tokens(&content),
+    );
+    session.pinned.pin(path, content);
+    Ok(())
+}
+
+async fn upload_tool_output(
+    api: &Backend,
+    content: &str,
+    tool_name: &str,
+    full_arg: &str,
+) -> Result<String> {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    // Generate filename based on tool type
+    let filename = match tool_name {
+        "read_file" => {
+            // Extract original filename from the path argument
+            let path_str = full_arg.lines().next().unwrap_or("");
+            Path::new(path_str)
+                .file_name()
+                .and_then(|n| n.to_str())
+                .map_or_else(
+                    || {
+                        let timestamp = SystemTime::now()
+                            .duration_since(UNIX_EPOCH)
+                            .unwrap()
+                            .as_nanos();
+                        format!("read_file_{timestamp}.txt")
+                    },
+                    ToString::to_string,
+                )
+        }
+        "fetch_url" => {
+            // Create a filename from the URL
+            let mut words = full_arg.split_whitespace();
+            let url_part = words.next().unwrap_or("url");
+            // Remove protocol and replace non-alphanumeric characters
+            let url_clean = url_part
+                .replace("https://", "")
+                .replace("http://", "")
+                .replace(|c: char| !c.is_alphanumeric() && c != '.', "_");
+            let extension = if words.next() == Some("raw") {
+                "html"
+            } else {
+                "md"
+            };
+            format!("{url_clean}.{extension}")
+        }
+        "browser_get_html" => {
+            // Try to get a descriptive name from the URL or use default
+            let url_part = full_arg.lines().next().unwrap_or("page");
+            let sanitized = url_part.replace(|c: char| !c.is_alphanumeric() && c != '.', "_");
+            format!("{sanitized}.html")
+        }
+        _ => {
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos();
+            format!("tool_result_{tool_name}_{timestamp}.txt")
+        }
+    };
+
+    let file_data = content.as_bytes().to_vec();
+    let started = Instant::now();
+    let file_id = api.upload_file(file_data, &filename, None).await?;
+    tracing::debug!(
+        file = %filename,
+        bytes = content.len(),
+        elapsed_ms = started.elapsed().as_millis(),
+        "uploaded file"
+    );
+    Ok(file_id)
+}
+
+/// Decides whether a call may run, returning the message to report instead if not. The
+/// `on_tool_call` hooks may change the call's argument first.
+async fn check_tool(call: &mut ToolCall, session: &mut Session) -> Option<String> {
+    session.events.emit(AgentEvent::ToolCall {
+        name: call.name.clone(),
+        arg: call.arg.clone(),
+    });
+    if let Some(err_msg) = &call.error {
+        emit_tool_result(session, &call.name, err_msg, false);
+        return Some(err_msg.clone());
+    }
+    // A hook that fails refuses the call, as hooks may be there to enforce a policy
+    match hooks::current().and_then(|hooks| hooks.on_tool_call(&call.name, &call.arg)) {
+        Ok(ToolCallHook::Run) => {}
+        Ok(ToolCallHook::Replace(arg)) => call.arg = arg,
+        Ok(ToolCallHook::Refuse(reason)) => {
+            let err_msg = format!("TOOL {} was not run: {reason}.", call.name);
+            emit_tool_result(session, &call.name, &err_msg, false);
+            return Some(err_msg);
+        }
+        Err(e) => {
+            let err_msg = format!("TOOL {} was not run: {e}", call.name);
+            emit_tool_result(session, &call.name, &err_msg, false);
+            return Some(err_msg);
+        }
+    }
+    let call = &*call;
+    let (tool_name, full_arg) = (call.name.as_str(), call.arg.as_str());
+    if !tool_allowed(tool_name, session.mode) {
+        let err_msg = format!(
+            "TOOL {tool_name} failed: not available in plan mode. Only read-only tools can be used until the user approves the plan."
+        );
+        emit_tool_result(session, tool_name, &err_msg, false);
+        return Some(err_msg);
+    }
+    if session
+        .tools()
+        .is_some_and(|tools| !tools.iter().any(|t| t == tool_name))
+    {
+        let limited_by = if !config::get().allows_tool(tool_name) {
+            "by the config"
+        } else if session.workflow.as_ref().is_some_and(|w| w.tools.is_some()) {
+            "for this workflow"
+        } else {
+            "for this profile"
+        };
+        let err_msg = format!("TOOL {tool_name} failed: not enabled {limited_by}.");
+        emit_tool_result(session, tool_name, &err_msg, false);
+        return Some(err_msg);
+    }
+    if let Some(run) = &session.autonomous
+        && run.is_running()
+        && !run.allows(tool_name)
+    {
+        let err_msg = format!(
+            "TOOL {tool_name} failed: not allowed in autonomous mode. Available tools: {}",
+            run.tools().join(", ")
+        );
+        emit_tool_result(session, tool_name, &err_msg, false);
+        return Some(err_msg);
+    }
+    let args = call.args();
+    if session.dry_run && tools::needs_approval(tool_name) {
+        let preview = dryrun::preview(&tools::ToolContext::current(), tool_name, full_arg);
+        if session.events.echo() {
+            println!(
+                "{} {} {}",
+                "[dry run]".yellow().bold(),
+                tool_name.bold(),
+                args.target()
+            );
+            print_colored_diff(&preview);
+        }
+        emit_tool_result(session, tool_name, "Dry run: not executed", true);
+        return Some(format!("TOOL {tool_name} was not run (dry run). {preview}"));
+    }
+    // `[[policy]]` rules apply to every tool, so one may refuse or ask for a read-only call.
+    // They match the argument as the tool reads it, so the form it is given in cannot
+    // slip a call past them.
+    let verdict = policy::current()
+        .ok()
+        .and_then(|policy| policy.check(tool_name, &args.text(), args.target()));
+    let needs_approval = tools::needs_approval(tool_name);
+    if needs_approval || verdict.is_some_and(|v| v.action != PolicyAction::Allow) {
+        if needs_approval && call.reason.is_none() {
+            let err_msg = approval::missing_reason_message(call);
+            emit_tool_result(session, tool_name, &err_msg, false);
+            return Some(err_msg);
+        }
+        let decision = approve_tool_call(session, call, &args, verdict).await;
+        if let Err(e) = approval::audit(&AuditRecord::new(&session.chat_id, call, decision))
+            && session.events.echo()
+        {
+            eprintln!("{}", format!("Failed to write the audit log: {e}").red());
+        }
+        if let (Decision::Refused, Some(verdict)) = (decision, verdict) {
+            session.policy_refusals += 1;
+            let err_msg = approval::refused_message(call, &verdict.describe());
+            emit_tool_result(session, tool_name, &err_msg, false);
+            return Some(err_msg);
+        }
+        if decision == Decision::Declined {
+            let err_msg = approval::declined_message(call);
+            emit_tool_result(session, tool_name, &err_msg, false);
+            return Some(err_msg);
+        }
+    }
+    None
+}
+
+/// Shows a call that changes files or runs commands, with the model's reason for it, and
+/// asks the user whether to run it. A `[[policy]]` rule that matches decides first;
+/// otherwise calls run without asking when there is no terminal to ask on, the user has
+/// approved all calls or the config approves the tool.
+async fn approve_tool_call(
+    session: &mut Session,
+    call: &ToolCall,
+    args: &CallArgs,
+    verdict: Option<Verdict<'_>>,
+) -> Decision {
+    let autonomous = session
+        .autonomous
+        .as_ref()
+        .is_some_and(AutonomousRun::is_running);
+    match verdict {
+        Some(verdict) => {
+            if let Some(decision) = approval::policy_decision(&verdict, tools::is_interactive()) {
+                return decision;
+            }
+        }
+        None => {
+            if autonomous
+                || session.approve_all
+                || !tools::is_interactive()
+                || config::get().auto_approve.contains(&call.name)
+            {
+                return Decision::AutoApproved;
+            }
+        }
+    }
+    notify::current().waiting(&format!("run {} {}?", call.name, args.target()));
+    println!(
+        "{} {} {}",
+        "?".magenta().bold(),
+        call.name.bold(),
+        args.target()
+    );
+    if let Some(reason) = &call.reason {
+        println!("  {} {reason}", "reason:".dimmed());
+    }
+    if let Some(verdict) = verdict {
+        println!("  {} asked by {}", "policy:".dimmed(), verdict.describe());
+    }
+    match args {
+        CallArgs::SearchReplace(args) => print_search_replace_preview(args),
+        CallArgs::EditLines(args) => print_edit_lines_preview(args),
+        CallArgs::MoveFile(args) => println!("  {} {}", "to:".dimmed(), args.to.trim()),
+        CallArgs::Command(args) if args.command.contains('\n') => {
+            println!("{}", args.command.dimmed());
+        }
+        _ if call.name == "apply_patch" => print_colored_diff(&call.arg),
+        _ => {}
+    }
+    loop {
+        let reply = tokio::task::spawn_blocking(|| {
+            print!("Run it? [y/N/a(ll)] ");
+            std::io::stdout().flush().ok()?;
+            let mut line = String::new();
+            match std::io::stdin().read_line(&mut line) {
+                Ok(0) | Err(_) => None,
+                Ok(_) => Some(line),
+            }
+        })
+        .await
+        .ok()
+        .flatten();
+        let Some(reply) = reply else {
+            return Decision::Declined;
+        };
+        match Answer::parse(&reply) {
+            Some(Answer::Yes) => return Decision::Approved,
+            Some(Answer::No) => return Decision::Declined,
+            Some(Answer::All) => {
+                session.approve_all = true;
+                return Decision::Approved;
+            }
+            None => println!("Answer y, n or a"),
+        }
+    }
+}
+
+/// Prints the changes an `apply_search_replace` call would make, block by block.
+fn print_search_replace_preview(args: &SearchReplaceArgs) {
+    for block in &args.blocks {
+        print_diff_preview(&block.search, &block.replace);
+    }
+}
+
+/// Prints the lines an `edit_lines` call would replace and what replaces them.
+fn print_edit_lines_preview(args: &EditLinesArgs) {
+    let Ok(file) = workspace::current().resolve(&args.path) else {
+        return;
+    };
+    let Ok(content) = std::fs::read_to_string(file) else {
+        return;
+    };
+    let old: Vec<&str> = content
+        .lines()
+        .skip(args.start.saturating_sub(1))
+        .take((args.end + 1).saturating_sub(args.start))
+        .collect();
+    print_diff_preview(&old.join("\n"), &args.text);
+}
+
+fn print_diff_preview(old: &str, new: &str) {
+    match unified_diff(old, new, 2) {
+        Some(diff) => print_colored_diff(&diff),
+        None => println!("{}", "(block is too large to preview)".dimmed()),
+    }
+}
+
+fn print_colored_diff(diff: &str) {
+    for line in diff.lines() {
+        match line.chars().next() {
+            Some('-') => println!("{}", line.red()),
+            Some('+') => println!("{}", line.green()),
+            Some('@') => println!("{}", line.cyan()),
+            _ => println!("{line}"),
+        }
+    }
+}
+
+/// Records a tool's outcome and turns it into the file to attach, if any, and the text to
+/// send back. A body is uploaded as a file, or sent inline if the upload fails.
+async fn finish_tool(
+    api: &Backend,
+    tool_name: &str,
+    full_arg: &str,
+    result: ToolResult,
+    session: &mut Session,
+) -> (Option<String>, String) {
+    if !result.is_success() {
+        emit_tool_result(session, tool_name, &result.summary, false);
+        return (None, result.summary);
+    }
+    session.recipe.record(tool_name, full_arg);
+    if let Some(path) = CallArgs::parse(tool_name, full_arg).edited_file()
+        && let Ok(file) = workspace::current().resolve(path)
+    {
+        session.pinned.mark_seen(&file);
+    }
+    emit_tool_result(session, tool_name, &result.summary, true);
+
+    let ToolResult {
+        summary,
+        body,
+        artifacts,
+        ..
+    } = result;
+    let mut file_id = None;
+    let mut message = summary;
+    session.context.record(
+        Source::ToolResult,
+        format!("{tool_name} {}", context::label(full_arg)),
+        estimate_tokens(&message) + body.as_deref().map_or(0, estimate_tokens),
+    );
+    if let Some(body) = body {
+        session.stats.record_attachment(tool_name, &body);
+        match upload_tool_output(api, &body, tool_name, full_arg).await {
+            Ok(id) => file_id = Some(id),
+            Err(e) => {
+                if session.events.echo() {
+                    eprintln!("Failed to upload tool output: {e}");
+                }
+                message = format!("{message}\n\n{body}");
+            }
+        }
+    }
+    for artifact in artifacts {
+        match artifact {
+            Artifact::File { file_id: id } => file_id = Some(id),
+            Artifact::Binary { data, mime_type } => {
+                // For binary data (e.g., screenshot), upload the file
+                let filename = if mime_type == "image/png" {
+                    format!("screenshot_{}.png", chrono::Utc::now().timestamp())
+                } else {
+                    format!("binary_data_{}", chrono::Utc::now().timestamp())
+                };
+                match api.upload_file(data, &filename, Some(&mime_type)).await {
+                    Ok(id) => file_id = Some(id),
+                    Err(e) => {
+                        if session.events.echo() {
+                            eprintln!("Failed to upload binary data: {e}");
+                        }
+                        message = format!("Binary data captured but upload failed: {e}");
+                    }
+                }
+            }
+        }
+    }
+    (file_id, message)
+}
+
+/// Prints a tool's status (in red if it failed), reports it to any listener and counts it
+/// in the session's stats.
+fn emit_tool_result(session: &mut Session, tool_name: &str, status: &str, success: bool) {
+    session.stats.record_tool_call(tool_name, status);
+    if let Some(run) = &mut session.autonomous {
+        run.record_result(success);
+    }
+    if session.events.echo() {
+        let status = hyperlink::linkify(status);
+        if success {
+            println!("{}", status.cyan());
+        } else {
+            eprintln!("{}", status.red());
+        }
+    }
+    session.events.emit(AgentEvent::ToolResult {
+        name: tool_name.to_string(),
+        status: status.to_string(),
+        success,
+    });
+    if let Err(e) =
+        hooks::current().and_then(|hooks| hooks.on_tool_result(tool_name, status, success))
+    {
+        eprintln!("{}", format!("Hook failed: {e}").yellow());
+    }
+}
+
+/// Runs a batch of tool calls, at most [`MAX_PARALLEL_TOOLS`] at a time, and returns their
+/// results in the order the calls were made.
+async fn run_tool_batch(
+    api: &Backend,
+    batch: &[ToolCall],
+    session: &mut Session,
+) -> Vec<(Option<String>, String)> {
+    let mut batch = batch.to_vec();
+    let mut checks = Vec::with_capacity(batch.len());
+    for call in &mut batch {
+        checks.push(check_tool(call, session).await);
+    }
+    let semaphore = Semaphore::new(MAX_PARALLEL_TOOLS);
+    let semaphore = &semaphore;
+    let running: Vec<&str> = batch
+        .iter()
+        .zip(&checks)
+        .filter(|(_, check)| check.is_none())
+        .map(|(call, _)| call.name.as_str())
+        .collect();
+    let spinner = (session.events.echo() && !running.is_empty())
+        .then(|| Spinner::start(format!("Running {}", running.join(", "))));
+    let started = Instant::now();
+    let outputs = join_all(batch.iter().zip(checks).map(|(call, check)| async move {
+        if let Some(err_msg) = check {
+            return Err(err_msg);
+        }
+        let _permit = semaphore.acquire().await;
+        Ok(execute_tool(&call.name, &call.arg).await)
+    }))
+    .await;
+    drop(spinner);
+    session.stats.record_tool_time(started.elapsed());
+    let mut results = Vec::with_capacity(batch.len());
+    for (call, output) in batch.iter().zip(outputs) {
+        results.push(match output {
+            Ok(result) => finish_tool(api, &call.name, &call.arg, result, session).await,
+            Err(err_msg) => (None, err_msg),
+        });
+    }
+    results
+}
+
+async fn handle_tool_calls(
+    api: &Backend,
+    session: &mut Session,
+    current_msg: Reply,
+    ctrl_rx: &mut broadcast::Receiver<()>,
+) -> Result<Option<Reply>> {
+    let invocations = parse_tool_calls(&current_msg.content);
+
+    if invocations.is_empty() {
+        return Ok(None);
+    }
+    if let Some(run) = &mut session.autonomous
+        && run.is_running()
+    {
+        let now = Instant::now();
+        if run.is_over(now) {
+            let names: Vec<&str> = invocations.iter().map(|c| c.name.as_str()).collect();
+            session.pending_notes.push(format!(
+                "The autonomous time limit ran out, so these tool calls were not run: {}",
+                names.join(", ")
+            ));
+            eprintln!(
+                "{}",
+                "Time is up: stopping before the next tool calls.".yellow()
+            );
+            return Ok(None);
+        }
+        if run.report_due(now) {
+            report_autonomous_progress(session, "Autonomous progress");
+        }
+    }
+
+    let mut file_ids = Vec::new();
+    let mut result_messages = Vec::new();
+
+    // Consecutive tools that can run in parallel form one batch; any other tool runs alone
+    let batches = invocations
+        .chunk_by(|a, b| tools::runs_in_parallel(&a.name) && tools::runs_in_parallel(&b.name));
+    for batch in batches {
+        for (file_id_opt, msg) in run_tool_batch(api, batch, session).await {
+            if let Some(file_id) = file_id_opt {
+                file_ids.push(file_id);
+            }
+            result_messages.push(msg);
+        }
+    }
+
+    let next_prompt = results_prompt(&result_messages);
+    let parent_id = session.parent_id;
+    let new_msg =
+        complete_with_retry(api, session, &next_prompt, parent_id, &file_ids, ctrl_rx).await?;
+    if let Some(msg) = new_msg {
+        session.parent_id = msg.message_id.or(session.parent_id);
+        Ok(Some(msg))
+    } else {
+        Ok(None)
+    }
+}
@@ -0,0 +1,170 @@
+use crate::config::PolicyAction;
+use crate::policy::Verdict;
+use crate::protocol::ToolCall;
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// The user's answer when asked to approve a tool call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Answer {
+    Yes,
+    No,
+    /// Approve this call and every later one in the session.
+    All,
+}
+
+impl Answer {
+    /// Parses a reply to the approval prompt. An empty reply declines.
+    #[must_use]
+    pub fn parse(reply: &str) -> Option<Self> {
+        match reply.trim().to_ascii_lowercase().as_str() {
+            "y" | "yes" => Some(Self::Yes),
+            "" | "n" | "no" => Some(Self::No),
+            "a" | "all" => Some(Self::All),
+            _ => None,
+        }
+    }
+}
+
+/// Rounds of tool calls one message may take before the user is asked whether to go on,
+/// when neither `--max-iterations` nor the config sets it.
+pub const DEFAULT_MAX_TOOL_ITERATIONS: u32 = 25;
+
+/// The user's answer when a message has used up its rounds of tool calls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitAnswer {
+    /// Allow as many rounds again.
+    Continue,
+    /// Stop and go back to the prompt.
+    Abort,
+    /// Raise the limit to this many rounds per message.
+    Raise(u32),
+}
+
+impl LimitAnswer {
+    /// Parses a reply to the iteration limit prompt: `c`, `a`, or a new limit, optionally
+    /// after `r`. An empty reply aborts.
+    #[must_use]
+    pub fn parse(reply: &str) -> Option<Self> {
+        let reply = reply.trim().to_ascii_lowercase();
+        match reply.as_str() {
+            "c" | "continue" => return Some(Self::Continue),
+            "" | "a" | "abort" => return Some(Self::Abort),
+            _ => {}
+        }
+        let limit = reply
+            .strip_prefix("raise")
+            .or_else(|| reply.strip_prefix('r'))
+            .unwrap_or(&reply);
+        match limit.trim().parse() {
+            Ok(limit) if limit > 0 => Some(Self::Raise(limit)),
+            _ => None,
+        }
+    }
+}
+
+/// What happened to a tool call that needed approval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+    /// The user approved it when asked.
+    Approved,
+    /// The user declined it.
+    Declined,
+    /// It ran without asking, because there is no terminal to ask on, the user approved
+    /// all calls for the session or the config approves the tool.
+    AutoApproved,
+    /// A `[[policy]]` rule refused it without asking.
+    Refused,
+}
+
+/// What a `[[policy]]` rule decides for a call by itself, or `None` when the user is to be
+/// asked. A rule that asks refuses the call when there is no terminal to ask on, even in
+/// autonomous runs and after the user approved all calls.
+#[must_use]
+pub fn policy_decision(verdict: &Verdict<'_>, interactive: bool) -> Option<Decision> {
+    match verdict.action {
+        PolicyAction::Deny => Some(Decision::Refused),
+        PolicyAction::Allow => Some(Decision::AutoApproved),
+        PolicyAction::Ask if interactive => None,
+        PolicyAction::Ask => Some(Decision::Refused),
+    }
+}
+
+/// One line of the audit log.
+#[derive(Debug, Serialize)]
+pub struct AuditRecord<'a> {
+    pub time: String,
+    pub chat_id: &'a str,
+    pub tool: &'a str,
+    pub target: String,
+    pub reason: Option<&'a str>,
+    pub decision: Decision,
+}
+
+impl<'a> AuditRecord<'a> {
+    #[must_use]
+    pub fn new(chat_id: &'a str, call: &'a ToolCall, decision: Decision) -> Self {
+        Self {
+            time: chrono::Utc::now().to_rfc3339(),
+            chat_id,
+            tool: &call.name,
+            target: call.target(),
+            reason: call.reason.as_deref(),
+            decision,
+        }
+    }
+}
+
+/// The audit log of tool calls that change files or run commands,
+/// `~/.config/deepseek-cli/audit.jsonl`.
+#[must_use]
+pub fn audit_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("deepseek-cli/audit.jsonl"))
+}
+
+/// Appends a record to the audit log.
+///
+/// # Errors
+/// Returns an error if the config directory is unavailable or the log cannot be written.
+pub fn audit(record: &AuditRecord<'_>) -> Result<()> {
+    let path = audit_path().ok_or_else(|| anyhow!("No config directory available"))?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
+
+/// The message sent back to the model for a call the user declined.
+#[must_use]
+pub fn declined_message(call: &ToolCall) -> String {
+    format!(
+        "TOOL {} failed: the user declined this call. Ask them what to do instead, or try a different approach.",
+        call.name
+    )
+}
+
+/// The message sent back to the model for a call the approval policy refused.
+#[must_use]
+pub fn refused_message(call: &ToolCall, rule: &str) -> String {
+    format!(
+        "TOOL {} failed: refused by the user's {rule}. Do not try it again; find another way, or ask the user.",
+        call.name
+    )
+}
+
+/// The message sent back to the model for a call with no reason.
+#[must_use]
+pub fn missing_reason_message(call: &ToolCall) -> String {
+    format!(
+        "TOOL {} failed: missing reason. Put a line starting with REASON: directly after the TOOL line, saying why the call is needed.",
+        call.name
+    )
+}
@@ -0,0 +1,684 @@
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::fmt::{self, Write as _};
+
+/// The arguments of a built-in tool. A call gives them as text, laid out as the tool's
+/// description says, or as a JSON object of the named arguments.
+///
+/// Tools whose whole argument is one free-form text, such as `apply_patch`, take it as it
+/// is and have no type here.
+pub trait ToolArgs: DeserializeOwned {
+    /// How the text form is laid out, shown when a call cannot be read.
+    const USAGE: &'static str;
+
+    /// Reads the text form of the arguments.
+    ///
+    /// # Errors
+    /// Returns what is wrong with the text.
+    fn from_text(arg: &str) -> Result<Self, String>;
+
+    /// Checks what both forms may get wrong, such as a missing path.
+    ///
+    /// # Errors
+    /// Returns what is wrong with the arguments.
+    fn check(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Reads the argument of a call to `tool`: a JSON object if it is one, otherwise the
+    /// text form.
+    ///
+    /// # Errors
+    /// Returns an [`ArgError`] saying what is wrong and how the arguments are written.
+    fn parse(tool: &str, arg: &str) -> Result<Self, ArgError> {
+        let args = match json_object(arg) {
+            Some(object) => serde_json::from_value(object).map_err(|e| e.to_string()),
+            None => Self::from_text(arg),
+        };
+        args.and_then(|args| args.check().map(|()| args))
+            .map_err(|problem| ArgError {
+                tool: tool.to_string(),
+                problem,
+                usage: Self::USAGE,
+            })
+    }
+}
+
+/// A tool call whose arguments could not be read. It is returned to the model with the
+/// expected format, so the next call can get it right.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArgError {
+    pub tool: String,
+    pub problem: String,
+    pub usage: &'static str,
+}
+
+impl fmt::Display for ArgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid arguments for {}: {}. Expected {}",
+            self.tool, self.problem, self.usage
+        )
+    }
+}
+
+impl std::error::Error for ArgError {}
+
+fn json_object(arg: &str) -> Option<Value> {
+    let trimmed = arg.trim();
+    if !trimmed.starts_with('{') {
+        return None;
+    }
+    serde_json::from_str::<Value>(trimmed)
+        .ok()
+        .filter(Value::is_object)
+}
+
+fn single_line(what: &str, value: &str) -> Result<(), String> {
+    if value.trim().is_empty() {
+        Err(format!("the {what} is missing"))
+    } else if value.contains('\n') {
+        Err(format!("the {what} must be on a single line"))
+    } else {
+        Ok(())
+    }
+}
+
+/// One path: `read_file`, `outline`, `create_directory` and `delete_file`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PathArgs {
+    pub path: String,
+}
+
+impl ToolArgs for PathArgs {
+    const USAGE: &'static str = "the path on a single line";
+
+    fn from_text(arg: &str) -> Result<Self, String> {
+        Ok(Self {
+            path: arg.trim().to_string(),
+        })
+    }
+
+    fn check(&self) -> Result<(), String> {
+        single_line("path", &self.path)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ListFilesArgs {
+    #[serde(default)]
+    pub directory: Option<String>,
+}
+
+impl ToolArgs for ListFilesArgs {
+    const USAGE: &'static str =
+        "a directory on a single line, or nothing for the focused or current directory";
+
+    fn from_text(arg: &str) -> Result<Self, String> {
+        let directory = arg.trim();
+        Ok(Self {
+            directory: (!directory.is_empty()).then(|| directory.to_string()),
+        })
+    }
+
+    fn check(&self) -> Result<(), String> {
+        match &self.directory {
+            Some(directory) if directory.contains('\n') => {
+                Err("the directory must be on a single line".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FindFileArgs {
+    pub name: String,
+}
+
+impl ToolArgs for FindFileArgs {
+    const USAGE: &'static str = "part of a file name or path on a single line";
+
+    fn from_text(arg: &str) -> Result<Self, String> {
+        Ok(Self {
+            name: arg.trim().to_string(),
+        })
+    }
+
+    fn check(&self) -> Result<(), String> {
+        single_line("name", &self.name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GrepArgs {
+    pub text: String,
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+impl ToolArgs for GrepArgs {
+    const USAGE: &'static str = "the text to search for on the first line and, optionally, the file or directory to search on the second";
+
+    fn from_text(arg: &str) -> Result<Self, String> {
+        let mut lines = arg.lines();
+        let text = lines.next().unwrap_or_default().to_string();
+        let path = lines
+            .next()
+            .map(str::trim)
+            .filter(|path| !path.is_empty())
+            .map(str::to_string);
+        if lines.any(|line| !line.trim().is_empty()) {
+            return Err("only two lines are read, so the text cannot span lines".to_string());
+        }
+        Ok(Self { text, path })
+    }
+
+    fn check(&self) -> Result<(), String> {
+        if self.text.is_empty() {
+            Err("the text to search for is missing".to_string())
+        } else if self.text.contains('\n') {
+            Err("the text to search for must be on a single line".to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// One search/replace block of `apply_search_replace`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Block {
+    pub search: String,
+    pub replace: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SearchReplaceArgs {
+    pub path: String,
+    pub blocks: Vec<Block>,
+}
+
+impl ToolArgs for SearchReplaceArgs {
+    const USAGE: &'static str = "the file path on the first line, then one or more blocks of <<<<<<< SEARCH, the text to find, =======, the text to put in its place and >>>>>>> REPLACE, each marker on its own line";
+
+    fn from_text(arg: &str) -> Result<Self, String> {
+        let (path, text) = arg.split_once('\n').unwrap_or((arg, ""));
+        Ok(Self {
+            path: path.trim().to_string(),
+            blocks: search_replace_blocks(text)?,
+        })
+    }
+
+    fn check(&self) -> Result<(), String> {
+        single_line("file path", &self.path)?;
+        if self.blocks.is_empty() {
+            return Err("no search/replace blocks were given".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Reads the `<<<<<<< SEARCH`, `=======` and `>>>>>>> REPLACE` blocks of the text form of
+/// `apply_search_replace`. Text around the blocks is ignored.
+fn search_replace_blocks(text: &str) -> Result<Vec<Block>, String> {
+    let mut blocks = Vec::new();
+    let mut remaining = text;
+    while let Some(search_start) = remaining.find("<<<<<<< SEARCH") {
+        let after_search = &remaining[search_start + 15..];
+        let search_end = after_search
+            .find("=======")
+            .ok_or_else(|| "a block is missing =======".to_string())?;
+        let search = after_search[..search_end].trim().to_string();
+
+        let after_eq = &after_search[search_end + 7..];
+        let replace_end = after_eq
+            .find(">>>>>>> REPLACE")
+            .ok_or_else(|| "a block is missing >>>>>>> REPLACE".to_string())?;
+        let replace = after_eq[..replace_end].trim().to_string();
+
+        blocks.push(Block { search, replace });
+        remaining = &after_eq[replace_end + 15..];
+    }
+    Ok(blocks)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EditLinesArgs {
+    pub path: String,
+    pub start: usize,
+    pub end: usize,
+    #[serde(default)]
+    pub text: String,
+}
+
+impl ToolArgs for EditLinesArgs {
+    const USAGE: &'static str = "<file_path> <start> <end> on the first line, then the replacement text on the following lines";
+
+    fn from_text(arg: &str) -> Result<Self, String> {
+        let (header, text) = arg.split_once('\n').unwrap_or((arg, ""));
+        let mut fields = header.trim().rsplitn(3, ' ');
+        let (Some(end), Some(start), Some(path)) = (fields.next(), fields.next(), fields.next())
+        else {
+            return Err("the first line must be <file_path> <start> <end>".to_string());
+        };
+        let number = |field: &str| {
+            field
+                .parse::<usize>()
+                .map_err(|_| format!("invalid line number: {field}"))
+        };
+        Ok(Self {
+            path: path.trim().to_string(),
+            start: number(start)?,
+            end: number(end)?,
+            text: text.to_string(),
+        })
+    }
+
+    fn check(&self) -> Result<(), String> {
+        single_line("file path", &self.path)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WriteFileArgs {
+    pub path: String,
+    #[serde(default)]
+    pub content: String,
+}
+
+impl ToolArgs for WriteFileArgs {
+    const USAGE: &'static str =
+        "the file path on the first line, then the content on the following lines";
+
+    fn from_text(arg: &str) -> Result<Self, String> {
+        let mut lines = arg.lines();
+        let path = lines.next().unwrap_or_default().trim().to_string();
+        let content = lines.collect::<Vec<&str>>().join("\n");
+        Ok(Self { path, content })
+    }
+
+    fn check(&self) -> Result<(), String> {
+        single_line("file path", &self.path)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MoveFileArgs {
+    pub from: String,
+    pub to: String,
+}
+
+impl ToolArgs for MoveFileArgs {
+    const USAGE: &'static str =
+        "the source path on the first line and the destination on the second";
+
+    fn from_text(arg: &str) -> Result<Self, String> {
+        let mut lines = arg.lines().map(str::trim).filter(|l| !l.is_empty());
+        match (lines.next(), lines.next(), lines.next()) {
+            (Some(from), Some(to), None) => Ok(Self {
+                from: from.to_string(),
+                to: to.to_string(),
+            }),
+            (None, ..) => Err("the source path is missing".to_string()),
+            (_, None, _) => Err("the destination is missing".to_string()),
+            _ => Err("there are more than two lines".to_string()),
+        }
+    }
+
+    fn check(&self) -> Result<(), String> {
+        single_line("source path", &self.from)?;
+        single_line("destination", &self.to)
+    }
+}
+
+/// `run_command`: the command, which may span lines.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CommandArgs {
+    pub command: String,
+}
+
+impl ToolArgs for CommandArgs {
+    const USAGE: &'static str = "the command to run, which may span lines";
+
+    fn from_text(arg: &str) -> Result<Self, String> {
+        Ok(Self {
+            command: arg.to_string(),
+        })
+    }
+}
+
+/// What `fetch_url` returns a web page as.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// The main content, converted to markdown
+    #[default]
+    Markdown,
+    /// The HTML as it was sent
+    Raw,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FetchUrlArgs {
+    pub url: String,
+    #[serde(default)]
+    pub format: Format,
+}
+
+impl ToolArgs for FetchUrlArgs {
+    const USAGE: &'static str = "the URL, optionally followed by raw or markdown";
+
+    fn from_text(arg: &str) -> Result<Self, String> {
+        let mut words = arg.split_whitespace();
+        let url = words.next().unwrap_or_default().to_string();
+        let format = match words.next() {
+            None | Some("markdown") => Format::Markdown,
+            Some("raw") => Format::Raw,
+            Some(other) => return Err(format!("unknown format {other}")),
+        };
+        if let Some(extra) = words.next() {
+            return Err(format!("unexpected {extra} after the format"));
+        }
+        Ok(Self { url, format })
+    }
+
+    fn check(&self) -> Result<(), String> {
+        single_line("URL", &self.url)
+    }
+}
+
+/// A line of a file in a pull request, written `<path>:<line>`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Location {
+    pub path: String,
+    pub line: u64,
+}
+
+impl TryFrom<String> for Location {
+    type Error = String;
+
+    fn try_from(location: String) -> Result<Self, String> {
+        location
+            .rsplit_once(':')
+            .and_then(|(path, line)| {
+                Some(Self {
+                    path: path.to_string(),
+                    line: line.parse().ok()?,
+                })
+            })
+            .filter(|location| !location.path.is_empty())
+            .ok_or_else(|| format!("expected <path>:<line> after the pull request, got {location}"))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CommentArgs {
+    pub pull_request: String,
+    #[serde(default)]
+    pub location: Option<Location>,
+    pub text: String,
+}
+
+impl ToolArgs for CommentArgs {
+    const USAGE: &'static str = "the pull request, optionally followed by <path>:<line>, on the first line, then the comment on the following lines";
+
+    fn from_text(arg: &str) -> Result<Self, String> {
+        let (header, text) = arg.split_once('\n').unwrap_or((arg, ""));
+        let mut words = header.split_whitespace();
+        let pull_request = words.next().unwrap_or_default().to_string();
+        let location = words
+            .next()
+            .map(|location| Location::try_from(location.to_string()))
+            .transpose()?;
+        if let Some(extra) = words.next() {
+            return Err(format!("unexpected {extra} on the first line"));
+        }
+        Ok(Self {
+            pull_request,
+            location,
+            text: text.trim().to_string(),
+        })
+    }
+
+    fn check(&self) -> Result<(), String> {
+        single_line("pull request", &self.pull_request)?;
+        if self.text.trim().is_empty() {
+            return Err("the comment is missing".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AskUserArgs {
+    pub question: String,
+    #[serde(default)]
+    pub options: Vec<String>,
+}
+
+impl ToolArgs for AskUserArgs {
+    const USAGE: &'static str =
+        "the question on the first line, then each answer to pick from on its own line";
+
+    fn from_text(arg: &str) -> Result<Self, String> {
+        let mut lines = arg.lines();
+        let question = lines.next().unwrap_or_default().trim().to_string();
+        let options = lines
+            .map(|l| l.trim().trim_start_matches('-').trim().to_string())
+            .filter(|l| !l.is_empty())
+            .collect();
+        Ok(Self { question, options })
+    }
+
+    fn check(&self) -> Result<(), String> {
+        single_line("question", &self.question)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BrowserTypeArgs {
+    pub selector: String,
+    pub text: String,
+}
+
+impl ToolArgs for BrowserTypeArgs {
+    const USAGE: &'static str = "the CSS selector, a space and the text to type";
+
+    fn from_text(arg: &str) -> Result<Self, String> {
+        let (selector, text) = arg.trim().split_once(' ').unwrap_or((arg.trim(), ""));
+        Ok(Self {
+            selector: selector.to_string(),
+            text: text.trim().to_string(),
+        })
+    }
+
+    fn check(&self) -> Result<(), String> {
+        if self.selector.trim().is_empty() {
+            Err("the selector is missing".to_string())
+        } else if self.text.is_empty() {
+            Err("the text to type is missing".to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// A browser tab, counted from 1.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TabArgs {
+    #[serde(default)]
+    pub index: Option<usize>,
+}
+
+impl ToolArgs for TabArgs {
+    const USAGE: &'static str = "the number of a tab, counting from 1";
+
+    fn from_text(arg: &str) -> Result<Self, String> {
+        let index = arg.trim();
+        if index.is_empty() {
+            return Ok(Self { index: None });
+        }
+        let index = index
+            .parse()
+            .map_err(|_| format!("{index} is not a tab number"))?;
+        Ok(Self { index: Some(index) })
+    }
+
+    fn check(&self) -> Result<(), String> {
+        if self.index == Some(0) {
+            return Err("tabs are counted from 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WaitArgs {
+    #[serde(default)]
+    pub timeout: Option<u64>,
+}
+
+impl ToolArgs for WaitArgs {
+    const USAGE: &'static str = "the number of seconds to wait at most, or nothing for 30 seconds";
+
+    fn from_text(arg: &str) -> Result<Self, String> {
+        let timeout = arg.trim();
+        if timeout.is_empty() {
+            return Ok(Self { timeout: None });
+        }
+        let timeout = timeout
+            .parse()
+            .map_err(|_| format!("{timeout} is not a number of seconds"))?;
+        Ok(Self {
+            timeout: Some(timeout),
+        })
+    }
+}
+
+/// A call's argument read once by the type of its tool, for what looks at a call before
+/// or after it runs: the approval prompt and its previews, `[[policy]]` rules, the audit
+/// log, pinned files and the recipe. A call reads the same whether its argument was given
+/// as text or as a JSON object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CallArgs {
+    /// `read_file`, `outline`, `create_directory` and `delete_file`
+    Path(PathArgs),
+    ListFiles(ListFilesArgs),
+    Grep(GrepArgs),
+    WriteFile(WriteFileArgs),
+    SearchReplace(SearchReplaceArgs),
+    EditLines(EditLinesArgs),
+    MoveFile(MoveFileArgs),
+    FetchUrl(FetchUrlArgs),
+    Command(CommandArgs),
+    /// The argument as it was given, for a tool with no type here or one its type cannot
+    /// read; the tool reports what is wrong with it when it runs
+    Text(String),
+}
+
+impl CallArgs {
+    #[must_use]
+    pub fn parse(tool: &str, arg: &str) -> Self {
+        let args = match tool {
+            "read_file" | "outline" | "create_directory" | "delete_file" => {
+                PathArgs::parse(tool, arg).map(Self::Path)
+            }
+            "list_files" => ListFilesArgs::parse(tool, arg).map(Self::ListFiles),
+            "grep" => GrepArgs::parse(tool, arg).map(Self::Grep),
+            "write_file" => WriteFileArgs::parse(tool, arg).map(Self::WriteFile),
+            "apply_search_replace" => SearchReplaceArgs::parse(tool, arg).map(Self::SearchReplace),
+            "edit_lines" => EditLinesArgs::parse(tool, arg).map(Self::EditLines),
+            "move_file" => MoveFileArgs::parse(tool, arg).map(Self::MoveFile),
+            "fetch_url" => FetchUrlArgs::parse(tool, arg).map(Self::FetchUrl),
+            "run_command" => CommandArgs::parse(tool, arg).map(Self::Command),
+            _ => return Self::Text(arg.to_string()),
+        };
+        args.unwrap_or_else(|_| Self::Text(arg.to_string()))
+    }
+
+    /// What the call acts on: the file or directory, the source of a move or the URL, and
+    /// otherwise the first line of the argument.
+    #[must_use]
+    pub fn target(&self) -> &str {
+        match self {
+            Self::Path(PathArgs { path })
+            | Self::WriteFile(WriteFileArgs { path, .. })
+            | Self::SearchReplace(SearchReplaceArgs { path, .. })
+            | Self::EditLines(EditLinesArgs { path, .. }) => path.trim(),
+            Self::ListFiles(args) => args.directory.as_deref().unwrap_or_default().trim(),
+            Self::Grep(args) => args.path.as_deref().unwrap_or_default().trim(),
+            Self::MoveFile(args) => args.from.trim(),
+            Self::FetchUrl(args) => args.url.trim(),
+            Self::Command(CommandArgs { command: arg }) | Self::Text(arg) => {
+                arg.lines().next().unwrap_or_default().trim()
+            }
+        }
+    }
+
+    /// The argument in the text form the tool's description gives, whichever form the call
+    /// was made in, for the `matches` of `[[policy]]` rules to be tried against.
+    #[must_use]
+    pub fn text(&self) -> String {
+        match self {
+            Self::Path(PathArgs { path }) => path.clone(),
+            Self::ListFiles(args) => args.directory.clone().unwrap_or_default(),
+            Self::Grep(GrepArgs { text, path }) => match path {
+                Some(path) => format!("{text}\n{path}"),
+                None => text.clone(),
+            },
+            Self::WriteFile(WriteFileArgs { path, content }) => format!("{path}\n{content}"),
+            Self::SearchReplace(SearchReplaceArgs { path, blocks }) => {
+                let mut text = path.clone();
+                for Block { search, replace } in blocks {
+                    let _ = write!(
+                        text,
+                        "\n<<<<<<< SEARCH\n{search}\n=======\n{replace}\n>>>>>>> REPLACE"
+                    );
+                }
+                text
+            }
+            Self::EditLines(EditLinesArgs {
+                path,
+                start,
+                end,
+                text,
+            }) => format!("{path} {start} {end}\n{text}"),
+            Self::MoveFile(MoveFileArgs { from, to }) => format!("{from}\n{to}"),
+            Self::FetchUrl(FetchUrlArgs { url, format }) => match format {
+                Format::Markdown => url.clone(),
+                Format::Raw => format!("{url} raw"),
+            },
+            Self::Command(CommandArgs { command: arg }) | Self::Text(arg) => arg.clone(),
+        }
+    }
+
+    /// The file the call changes in place, whose new contents the model has seen.
+    #[must_use]
+    pub fn edited_file(&self) -> Option<&str> {
+        match self {
+            Self::WriteFile(WriteFileArgs { path, .. })
+            | Self::SearchReplace(SearchReplaceArgs { path, .. })
+            | Self::EditLines(EditLinesArgs { path, .. }) => Some(path.trim()),
+            _ => None,
+        }
+    }
+}
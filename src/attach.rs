@@ -0,0 +1,98 @@
+use anyhow::{Result, bail};
+use std::path::Path;
+
+/// Largest file `/attach` and `@file` mentions upload.
+pub const MAX_ATTACHMENT_BYTES: u64 = 100 * 1024 * 1024;
+
+/// Extensions of the images and documents that are uploaded rather than pasted into the
+/// prompt as text, with their MIME types.
+const TYPES: &[(&str, &str)] = &[
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("bmp", "image/bmp"),
+    ("pdf", "application/pdf"),
+    ("doc", "application/msword"),
+    (
+        "docx",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    ),
+    ("xls", "application/vnd.ms-excel"),
+    (
+        "xlsx",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    ),
+    ("ppt", "application/vnd.ms-powerpoint"),
+    (
+        "pptx",
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    ),
+    ("epub", "application/epub+zip"),
+];
+
+/// A file read to be uploaded and sent with the next message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attachment {
+    /// The file name it is uploaded under
+    pub name: String,
+    pub mime_type: Option<&'static str>,
+    pub data: Vec<u8>,
+}
+
+/// A file size for people, such as `812 B`, `4.2 KB` or `1.3 MB`.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn format_size(bytes: usize) -> String {
+    match bytes {
+        0..1024 => format!("{bytes} B"),
+        1024..1_048_576 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+/// The MIME type of an image or document, going by its extension. `None` for anything
+/// else, including text files.
+#[must_use]
+pub fn mime_type(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    TYPES
+        .iter()
+        .find(|(ext, _)| *ext == extension)
+        .map(|(_, mime)| *mime)
+}
+
+/// Whether `path` is an image or document file, which is uploaded when mentioned.
+#[must_use]
+pub fn is_attachment(path: &Path) -> bool {
+    mime_type(path).is_some() && path.is_file()
+}
+
+/// Reads a file to attach. Any file can be attached; those that are not a known image or
+/// document type are uploaded without a MIME type.
+///
+/// # Errors
+/// Returns an error if the file cannot be read or is larger than [`MAX_ATTACHMENT_BYTES`].
+pub fn read(path: &Path) -> Result<Attachment> {
+    let metadata = std::fs::metadata(path)?;
+    if !metadata.is_file() {
+        bail!("{} is not a file", path.display());
+    }
+    if metadata.len() > MAX_ATTACHMENT_BYTES {
+        bail!(
+            "{} is larger than the {} MB an attachment may be",
+            path.display(),
+            MAX_ATTACHMENT_BYTES / (1024 * 1024)
+        );
+    }
+    let name = path.file_name().map_or_else(
+        || "attachment".to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    Ok(Attachment {
+        name,
+        mime_type: mime_type(path),
+        data: std::fs::read(path)?,
+    })
+}
@@ -0,0 +1,200 @@
+use crate::config::Account;
+use crate::http;
+use anyhow::{Result, anyhow, bail};
+use serde_json::Value;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Environment variable the token is read from first.
+pub const TOKEN_VAR: &str = "DEEPSEEK_TOKEN";
+
+/// The API the chats are held on. The client cannot be pointed elsewhere.
+pub const ENDPOINT: &str = "https://chat.deepseek.com";
+
+/// Service the tokens are stored under in the OS keyring.
+const KEYRING_SERVICE: &str = "deepseek-cli";
+
+/// Where the API token was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenSource {
+    Env(String),
+    /// The OS keyring, where `deepseek auth login` puts it
+    Keyring,
+    /// A plaintext file
+    File(PathBuf),
+}
+
+impl fmt::Display for TokenSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Env(var) => write!(f, "the {var} environment variable"),
+            Self::Keyring => f.write_str("the OS keyring"),
+            Self::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// The plaintext files the token may be kept in, in the order they are checked.
+#[must_use]
+pub fn token_files() -> Vec<PathBuf> {
+    [
+        dirs::config_dir().map(|d| d.join("deepseek-cli/token")),
+        dirs::home_dir().map(|h| h.join(".deepseek_token")),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Finds the API token. Without an account it is looked for in the environment, then the
+/// OS keyring, then the token files; for a named account in the variable and file the
+/// account names, with its own keyring entry in between. A keyring that cannot be
+/// reached, as on a machine without a secret service, is skipped.
+///
+/// # Errors
+/// Returns an error saying where to put the token if it is in none of those places.
+pub fn load_token(account: Option<(&str, &Account)>) -> Result<(String, TokenSource)> {
+    let (var, files) = match account {
+        None => (Some(TOKEN_VAR.to_string()), token_files()),
+        Some((_, account)) => (
+            account.token_env.clone(),
+            account
+                .token_file
+                .iter()
+                .map(|path| expand_home(path))
+                .collect(),
+        ),
+    };
+    let name = account.map(|(name, _)| name);
+    if let Some(var) = var
+        && let Ok(token) = std::env::var(&var)
+    {
+        return Ok((token, TokenSource::Env(var)));
+    }
+    if let Ok(Some(token)) = keyring_token(name) {
+        return Ok((token, TokenSource::Keyring));
+    }
+    for path in files {
+        if let Ok(content) = std::fs::read_to_string(&path) {
+            let token = content.trim();
+            if !token.is_empty() {
+                return Ok((token.to_string(), TokenSource::File(path)));
+            }
+        }
+    }
+    match name {
+        None => bail!(
+            "{TOKEN_VAR} environment variable not set, no token in the OS keyring and no token file found in:\n\
+             - ~/.config/deepseek-cli/token\n\
+             - ~/.deepseek_token\n\
+             Run `deepseek auth login` to store your API token in the keyring."
+        ),
+        Some(name) => bail!(
+            "No token found for account {name}; run `deepseek auth login --account {name}` or set token_env or token_file under [accounts.{name}]"
+        ),
+    }
+}
+
+/// The token stored in the OS keyring for an account, or without one, if any.
+///
+/// # Errors
+/// Returns an error if the keyring cannot be reached.
+pub fn keyring_token(account: Option<&str>) -> Result<Option<String>> {
+    match keyring_entry(account)?.get_password() {
+        Ok(token) => Ok(Some(token)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(anyhow!("Failed to read the OS keyring: {e}")),
+    }
+}
+
+/// Stores the token in the OS keyring, replacing any stored before for the account.
+///
+/// # Errors
+/// Returns an error if the token is empty or the keyring cannot be written.
+pub fn store_token(account: Option<&str>, token: &str) -> Result<()> {
+    let token = token.trim();
+    if token.is_empty() {
+        bail!("The token is empty");
+    }
+    keyring_entry(account)?
+        .set_password(token)
+        .map_err(|e| anyhow!("Failed to write to the OS keyring: {e}"))
+}
+
+/// Removes the account's token from the OS keyring, returning whether there was one.
+///
+/// # Errors
+/// Returns an error if the keyring cannot be written.
+pub fn remove_token(account: Option<&str>) -> Result<bool> {
+    match keyring_entry(account)?.delete_credential() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(e) => Err(anyhow!("Failed to write to the OS keyring: {e}")),
+    }
+}
+
+/// Replaces a leading `~` with the home directory.
+#[must_use]
+pub fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn keyring_entry(account: Option<&str>) -> Result<keyring::Entry> {
+    let user = account.map_or_else(|| "token".to_string(), |name| format!("token:{name}"));
+    keyring::Entry::new(KEYRING_SERVICE, &user)
+        .map_err(|e| anyhow!("Failed to open the OS keyring: {e}"))
+}
+
+/// Asks the API who the token belongs to, returning the account's email address or
+/// phone number if the API gives one.
+///
+/// # Errors
+/// Returns an error if the API cannot be reached or rejects the token.
+pub async fn verify_token(token: &str) -> Result<Option<String>> {
+    let client = http::client_builder()?
+        .timeout(Duration::from_secs(20))
+        .build()
+        .map_err(|e| anyhow!("Failed to create HTTP client: {e}"))?;
+    let response = client
+        .get(format!("{}/api/v0/users/current", http::base_url()))
+        .bearer_auth(token)
+        .send()
+        .await
+        .map_err(|e| anyhow!("Network error: {e}"))?;
+    let status = response.status();
+    if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+        bail!("The API rejected the token ({status})");
+    }
+    if !status.is_success() {
+        bail!("HTTP error {status}");
+    }
+    let body = response
+        .text()
+        .await
+        .map_err(|e| anyhow!("Failed to read response body: {e}"))?;
+    let body: Value = serde_json::from_str(&body).map_err(|e| anyhow!("Invalid response: {e}"))?;
+    user_from_response(&body)
+}
+
+/// Reads the user out of a `users/current` response.
+///
+/// # Errors
+/// Returns an error with the API's message if the response reports a failure.
+pub fn user_from_response(body: &Value) -> Result<Option<String>> {
+    if let Some(code) = body["code"].as_i64()
+        && code != 0
+    {
+        let message = body["msg"].as_str().unwrap_or("unknown error");
+        bail!("The API rejected the token: {message} (code {code})");
+    }
+    let user = &body["data"]["biz_data"];
+    Ok(["email", "mobile_number"]
+        .iter()
+        .filter_map(|key| user[key].as_str())
+        .find(|value| !value.is_empty())
+        .map(str::to_string))
+}
@@ -0,0 +1,250 @@
+use crate::recipe::RecordedAction;
+use anyhow::{Result, anyhow, bail};
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+/// How often progress is reported when `--report-every` is not given.
+pub const DEFAULT_REPORT_EVERY: Duration = Duration::from_secs(5 * 60);
+
+/// Tools an autonomous run may use when the config has no `[autonomous] tools`: reading,
+/// searching and editing files, but not running commands, the browser or `ask_user`.
+pub const DEFAULT_TOOLS: [&str; 10] = [
+    "list_files",
+    "find_file",
+    "read_file",
+    "outline",
+    "grep",
+    "search_web",
+    "fetch_url",
+    "create_directory",
+    "write_file",
+    "apply_search_replace",
+];
+
+/// Parses a duration such as `15m`, `90s`, `2h` or `1h30m`. A bare number is minutes.
+///
+/// # Errors
+/// Returns an error if the text is not a positive duration.
+pub fn parse_duration(text: &str) -> Result<Duration> {
+    let text = text.trim();
+    if let Ok(minutes) = text.parse::<u64>() {
+        return positive(Duration::from_secs(minutes.saturating_mul(60)), text);
+    }
+    let mut total = Duration::ZERO;
+    let mut number = String::new();
+    for c in text.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => bail!("Invalid duration: {text} (use e.g. 15m, 90s or 1h30m)"),
+        };
+        let value: u64 = number
+            .parse()
+            .map_err(|_| anyhow!("Invalid duration: {text} (use e.g. 15m, 90s or 1h30m)"))?;
+        total += Duration::from_secs(value.saturating_mul(unit));
+        number.clear();
+    }
+    if !number.is_empty() {
+        bail!("Invalid duration: {text} (missing a unit after {number})");
+    }
+    positive(total, text)
+}
+
+fn positive(duration: Duration, text: &str) -> Result<Duration> {
+    if duration.is_zero() {
+        bail!("Invalid duration: {text} (must be more than zero)");
+    }
+    Ok(duration)
+}
+
+/// Formats a duration as `1h05m`, `12m30s` or `45s`.
+#[must_use]
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{hours}h{minutes:02}m")
+    } else if minutes > 0 {
+        format!("{minutes}m{seconds:02}s")
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+/// A bounded stretch of work without approvals, started with `--autonomous`.
+///
+/// The clock starts with the first message. Until the time is up, calls to the allowed
+/// tools run without asking and the others are refused; progress is reported every
+/// `report_every`.
+#[derive(Debug, Clone)]
+pub struct AutonomousRun {
+    limit: Duration,
+    report_every: Duration,
+    tools: Vec<String>,
+    started: Option<Instant>,
+    next_report: Option<Instant>,
+    finished: bool,
+    /// Recipe actions recorded before the run, which its reports leave out
+    first_action: usize,
+    tool_calls: usize,
+    failed_calls: usize,
+}
+
+impl AutonomousRun {
+    #[must_use]
+    pub fn new(limit: Duration, report_every: Duration, tools: Vec<String>) -> Self {
+        Self {
+            limit,
+            report_every,
+            tools,
+            started: None,
+            next_report: None,
+            finished: false,
+            first_action: 0,
+            tool_calls: 0,
+            failed_calls: 0,
+        }
+    }
+
+    /// Starts the clock unless the run has already started, returning whether it did.
+    /// `first_action` is the number of recipe actions recorded so far.
+    pub fn start(&mut self, now: Instant, first_action: usize) -> bool {
+        if self.started.is_some() {
+            return false;
+        }
+        self.started = Some(now);
+        self.next_report = Some(now + self.report_every);
+        self.first_action = first_action;
+        true
+    }
+
+    /// The note sent with the first message to tell the model how the run works.
+    #[must_use]
+    pub fn instructions(&self) -> String {
+        format!(
+            "You are working autonomously for up to {}: the user is away, so do not ask questions, make reasonable choices and mention them at the end. Only these tools are available: {}. When the task is done, reply with a summary of what you changed.",
+            format_duration(self.limit),
+            self.tools.join(", ")
+        )
+    }
+
+    /// Whether the run has started and not yet finished.
+    #[must_use]
+    pub fn is_running(&self) -> bool {
+        self.started.is_some() && !self.finished
+    }
+
+    /// Whether the run may use the named tool.
+    #[must_use]
+    pub fn allows(&self, tool: &str) -> bool {
+        self.tools.iter().any(|t| t == tool)
+    }
+
+    #[must_use]
+    pub fn tools(&self) -> &[String] {
+        &self.tools
+    }
+
+    #[must_use]
+    pub fn limit(&self) -> Duration {
+        self.limit
+    }
+
+    #[must_use]
+    pub fn elapsed(&self, now: Instant) -> Duration {
+        self.started.map_or(Duration::ZERO, |started| {
+            now.saturating_duration_since(started)
+        })
+    }
+
+    #[must_use]
+    pub fn time_left(&self, now: Instant) -> Duration {
+        self.limit.saturating_sub(self.elapsed(now))
+    }
+
+    #[must_use]
+    pub fn is_over(&self, now: Instant) -> bool {
+        self.is_running() && self.time_left(now).is_zero()
+    }
+
+    /// Returns whether a progress report is due, scheduling the next one if so.
+    pub fn report_due(&mut self, now: Instant) -> bool {
+        match self.next_report {
+            Some(next) if self.is_running() && now >= next => {
+                self.next_report = Some(now + self.report_every);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Ends the run; later calls need approval again.
+    pub fn finish(&mut self) {
+        self.finished = true;
+    }
+
+    /// Counts a tool call made during the run.
+    pub fn record_result(&mut self, success: bool) {
+        if self.is_running() {
+            self.tool_calls += 1;
+            if !success {
+                self.failed_calls += 1;
+            }
+        }
+    }
+
+    /// The number of recipe actions recorded before the run.
+    #[must_use]
+    pub fn first_action(&self) -> usize {
+        self.first_action
+    }
+
+    /// Renders a progress report: time used, tool calls, the files changed and commands run
+    /// since the run started (`actions`), and the checkpoint taken for it, if any.
+    #[must_use]
+    pub fn report(
+        &self,
+        title: &str,
+        now: Instant,
+        actions: &[RecordedAction],
+        checkpoint: &str,
+    ) -> String {
+        let mut files = BTreeSet::new();
+        let mut commands = Vec::new();
+        for action in actions {
+            let target = action.arg.lines().next().unwrap_or_default().trim();
+            if action.tool == "run_command" {
+                commands.push(target);
+            } else {
+                files.insert(target);
+            }
+        }
+        let mut out = format!(
+            "=== {title}: {} elapsed, {} left ===\n",
+            format_duration(self.elapsed(now)),
+            format_duration(self.time_left(now))
+        );
+        let _ = writeln!(
+            out,
+            "Tool calls: {} ({} failed)",
+            self.tool_calls, self.failed_calls
+        );
+        if files.is_empty() {
+            out.push_str("Files changed: none\n");
+        } else {
+            let files: Vec<&str> = files.into_iter().collect();
+            let _ = writeln!(out, "Files changed: {}", files.join(", "));
+        }
+        if !commands.is_empty() {
+            let _ = writeln!(out, "Commands run: {}", commands.join("; "));
+        }
+        let _ = write!(out, "Checkpoint: {checkpoint}");
+        out
+    }
+}
@@ -0,0 +1,146 @@
+use crate::mock::{Recorder, Replay};
+use anyhow::Result;
+use deepseek_api::{DeepSeekAPI, StreamChunk};
+use futures_util::StreamExt;
+use futures_util::stream::{self, LocalBoxStream};
+use serde::{Deserialize, Serialize};
+
+/// A piece of a streamed reply, as the API sends it or as a fixture replays it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Chunk {
+    Thinking {
+        text: String,
+    },
+    Content {
+        text: String,
+    },
+    /// The finished reply, which ends the stream
+    Message {
+        content: String,
+        message_id: Option<i64>,
+    },
+}
+
+impl From<StreamChunk> for Chunk {
+    fn from(chunk: StreamChunk) -> Self {
+        match chunk {
+            StreamChunk::Thinking(text) => Self::Thinking {
+                text: text.to_string(),
+            },
+            StreamChunk::Content(text) => Self::Content {
+                text: text.to_string(),
+            },
+            StreamChunk::Message(message) => Self::Message {
+                content: message.content,
+                message_id: message.message_id,
+            },
+        }
+    }
+}
+
+/// Where chats and replies come from: the DeepSeek API, optionally recording the replies
+/// into a fixture, or a fixture replayed with `--mock`, which needs no token or network.
+pub enum Backend {
+    Api {
+        api: DeepSeekAPI,
+        recorder: Option<Recorder>,
+    },
+    Mock(Replay),
+}
+
+impl From<DeepSeekAPI> for Backend {
+    fn from(api: DeepSeekAPI) -> Self {
+        Self::Api {
+            api,
+            recorder: None,
+        }
+    }
+}
+
+impl From<Replay> for Backend {
+    fn from(replay: Replay) -> Self {
+        Self::Mock(replay)
+    }
+}
+
+impl Backend {
+    /// Starts a chat, returning its id.
+    ///
+    /// # Errors
+    /// Returns an error if the API request fails.
+    pub async fn create_chat(&self) -> Result<String> {
+        match self {
+            Self::Api { api, .. } => Ok(api.create_chat().await?.id),
+            Self::Mock(_) => Ok("mock".to_string()),
+        }
+    }
+
+    /// The last message of the chat `chat_id`, if it has any.
+    ///
+    /// # Errors
+    /// Returns an error if the chat does not exist or the API request fails.
+    pub async fn current_message_id(&self, chat_id: &str) -> Result<Option<i64>> {
+        match self {
+            Self::Api { api, .. } => Ok(api.get_chat_info(chat_id).await?.current_message_id),
+            Self::Mock(_) => Ok(None),
+        }
+    }
+
+    /// Uploads a file to attach to the next message, returning its id.
+    ///
+    /// # Errors
+    /// Returns an error if the upload fails.
+    pub async fn upload_file(
+        &self,
+        data: Vec<u8>,
+        filename: &str,
+        mime_type: Option<&str>,
+    ) -> Result<String> {
+        match self {
+            Self::Api { api, .. } => Ok(api.upload_file(data, filename, mime_type).await?.id),
+            Self::Mock(replay) => Ok(replay.upload()),
+        }
+    }
+
+    /// Sends `prompt` as a reply to `parent_id` and streams the answer.
+    #[must_use]
+    pub fn complete_stream(
+        &self,
+        chat_id: String,
+        prompt: String,
+        parent_id: Option<i64>,
+        search: bool,
+        thinking: bool,
+        files: Vec<String>,
+    ) -> LocalBoxStream<'_, Result<Chunk>> {
+        match self {
+            Self::Api { api, recorder } => {
+                let chunks = api
+                    .complete_stream(chat_id, prompt.clone(), parent_id, search, thinking, files)
+                    .map(|chunk| chunk.map(Chunk::from));
+                let Some(recorder) = recorder else {
+                    return chunks.boxed_local();
+                };
+                let mut recorded = Vec::new();
+                chunks
+                    .inspect(move |chunk| {
+                        if let Ok(chunk) = chunk {
+                            recorded.push(chunk.clone());
+                            if matches!(chunk, Chunk::Message { .. }) {
+                                recorder.record(&prompt, std::mem::take(&mut recorded));
+                            }
+                        }
+                    })
+                    .boxed_local()
+            }
+            Self::Mock(replay) => {
+                let chunks = match replay.next_reply(&prompt) {
+                    Ok(chunks) => chunks.into_iter().map(Ok).collect(),
+                    Err(e) => vec![Err(e)],
+                };
+                stream::iter(chunks).boxed_local()
+            }
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use crate::attach::format_size;
+use anyhow::Result;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Bytes from the start of a file looked at to tell whether it is binary.
+pub const SAMPLE_BYTES: usize = 8192;
+
+/// Archive entries listed before the rest are only counted.
+const MAX_LISTED_ENTRIES: usize = 200;
+
+/// What a binary file is, told by its first bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileType {
+    Image(&'static str),
+    Archive(&'static str),
+    Executable(&'static str),
+    Other(&'static str),
+}
+
+impl FileType {
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Image(name)
+            | Self::Archive(name)
+            | Self::Executable(name)
+            | Self::Other(name) => name,
+        }
+    }
+}
+
+/// Signatures at the start of a file and the types they mark.
+const MAGIC: &[(&[u8], FileType)] = &[
+    (b"\x89PNG\r\n\x1a\n", FileType::Image("PNG image")),
+    (b"\xff\xd8\xff", FileType::Image("JPEG image")),
+    (b"GIF87a", FileType::Image("GIF image")),
+    (b"GIF89a", FileType::Image("GIF image")),
+    (b"BM", FileType::Image("BMP image")),
+    (b"\x00\x00\x01\x00", FileType::Image("ICO icon")),
+    (b"PK\x03\x04", FileType::Archive("zip archive")),
+    (b"PK\x05\x06", FileType::Archive("zip archive")),
+    (b"\x1f\x8b", FileType::Archive("gzip-compressed file")),
+    (b"BZh", FileType::Archive("bzip2-compressed file")),
+    (b"\xfd7zXZ\x00", FileType::Archive("xz-compressed file")),
+    (
+        b"\x28\xb5\x2f\xfd",
+        FileType::Archive("zstd-compressed file"),
+    ),
+    (b"7z\xbc\xaf\x27\x1c", FileType::Archive("7z archive")),
+    (b"Rar!\x1a\x07", FileType::Archive("RAR archive")),
+    (
+        b"\x7fELF",
+        FileType::Executable("ELF executable or library"),
+    ),
+    (b"MZ", FileType::Executable("Windows executable or DLL")),
+    (
+        b"\xcf\xfa\xed\xfe",
+        FileType::Executable("Mach-O executable"),
+    ),
+    (
+        b"\xca\xfe\xba\xbe",
+        FileType::Executable("Java class file or Mach-O universal binary"),
+    ),
+    (b"\x00asm", FileType::Executable("WebAssembly module")),
+    (b"%PDF", FileType::Other("PDF document")),
+    (b"SQLite format 3\x00", FileType::Other("SQLite database")),
+    (b"OggS", FileType::Other("Ogg audio or video")),
+    (b"ID3", FileType::Other("MP3 audio")),
+    (b"fLaC", FileType::Other("FLAC audio")),
+    (b"\x00\x01\x00\x00\x00", FileType::Other("TrueType font")),
+    (b"wOFF", FileType::Other("WOFF font")),
+    (b"wOF2", FileType::Other("WOFF2 font")),
+];
+
+/// The type of file `sample`, the first bytes of a file, starts.
+#[must_use]
+pub fn file_type(sample: &[u8]) -> Option<FileType> {
+    if sample.len() > 262 && &sample[257..262] == b"ustar" {
+        return Some(FileType::Archive("tar archive"));
+    }
+    if sample.len() >= 12 && &sample[..4] == b"RIFF" {
+        return Some(match &sample[8..12] {
+            b"WEBP" => FileType::Image("WebP image"),
+            b"WAVE" => FileType::Other("WAV audio"),
+            _ => FileType::Other("RIFF media file"),
+        });
+    }
+    MAGIC
+        .iter()
+        .find(|(magic, _)| sample.starts_with(magic))
+        .map(|(_, kind)| *kind)
+}
+
+/// Whether `sample`, the first bytes of a file, looks like binary rather than text: it has
+/// a known binary signature, a NUL byte, or is not UTF-8 and full of control characters.
+#[must_use]
+pub fn is_binary(sample: &[u8]) -> bool {
+    if file_type(sample).is_some() || sample.contains(&0) {
+        return true;
+    }
+    match std::str::from_utf8(sample) {
+        Ok(_) => false,
+        // The sample may end in the middle of a character
+        Err(e) if e.error_len().is_none() => false,
+        Err(_) => {
+            let control = sample
+                .iter()
+                .filter(|&&b| b < 0x09 || (0x0e..0x20).contains(&b))
+                .count();
+            control * 10 > sample.len()
+        }
+    }
+}
+
+/// Reads the start of a file, up to [`SAMPLE_BYTES`].
+///
+/// # Errors
+/// Returns an error if the file cannot be read.
+pub fn sample(path: &Path) -> Result<Vec<u8>> {
+    let mut sample = Vec::new();
+    File::open(path)?
+        .take(SAMPLE_BYTES as u64)
+        .read_to_end(&mut sample)?;
+    Ok(sample)
+}
+
+/// Describes the file with [`describe`] if it is binary, or returns `None` for text.
+///
+/// # Errors
+/// Returns an error if the file cannot be read.
+pub fn describe_if_binary(path: &Path) -> Result<Option<String>> {
+    let sample = sample(path)?;
+    if !is_binary(&sample) {
+        return Ok(None);
+    }
+    let size = std::fs::metadata(path)?.len();
+    Ok(Some(describe(path, &sample, size)))
+}
+
+/// What `read_file` says about a binary file in place of its contents: its type and size,
+/// the entries of an archive, and what to do instead.
+#[must_use]
+pub fn describe(path: &Path, sample: &[u8], size: u64) -> String {
+    let kind = file_type(sample);
+    let size = format_size(usize::try_from(size).unwrap_or(usize::MAX));
+    let name = kind.map_or("unknown type", FileType::name);
+    let mut description = format!(
+        "{} is binary ({name}, {size}), so it cannot be read as text.",
+        path.display()
+    );
+    match kind {
+        Some(FileType::Image(_)) => description.push_str(
+            " Ask the user to /attach it if you need to see it.",
+        ),
+        Some(FileType::Archive(_)) => match archive_entries(path) {
+            Ok(Some(listing)) => {
+                description.push_str(&format!(
+                    " It contains:\n{listing}\nExtract it with run_command to read the files in it."
+                ));
+            }
+            _ => description.push_str(" Extract or decompress it with run_command to read it."),
+        },
+        Some(FileType::Executable(_)) => description.push_str(
+            " Use run_command with tools such as `file`, `strings` or `objdump` to inspect it, or read its source instead.",
+        ),
+        _ => description.push_str(
+            " Use run_command with tools such as `file`, `strings` or `xxd` to inspect it.",
+        ),
+    }
+    description
+}
+
+/// The entries of a zip or tar (optionally gzip-compressed) archive, one per line with
+/// its size, or `None` for other files.
+///
+/// # Errors
+/// Returns an error if the archive cannot be read.
+pub fn archive_entries(path: &Path) -> Result<Option<String>> {
+    let sample = sample(path)?;
+    let mut entries = Vec::new();
+    match file_type(&sample) {
+        Some(FileType::Archive("zip archive")) => {
+            let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+            for i in 0..archive.len() {
+                let entry = archive.by_index(i)?;
+                entries.push((entry.name().to_string(), entry.size()));
+            }
+        }
+        Some(FileType::Archive("tar archive")) => {
+            tar_entries(File::open(path)?, &mut entries)?;
+        }
+        Some(FileType::Archive("gzip-compressed file")) => {
+            let name = path.to_string_lossy();
+            if !(name.ends_with(".tar.gz") || name.ends_with(".tgz")) {
+                return Ok(None);
+            }
+            tar_entries(
+                flate2::read::GzDecoder::new(File::open(path)?),
+                &mut entries,
+            )?;
+        }
+        _ => return Ok(None),
+    }
+    let mut listing = String::new();
+    for (name, size) in entries.iter().take(MAX_LISTED_ENTRIES) {
+        let size = format_size(usize::try_from(*size).unwrap_or(usize::MAX));
+        listing.push_str(&format!("  {name} ({size})\n"));
+    }
+    if entries.len() > MAX_LISTED_ENTRIES {
+        listing.push_str(&format!(
+            "  ... and {} more\n",
+            entries.len() - MAX_LISTED_ENTRIES
+        ));
+    }
+    if entries.is_empty() {
+        listing.push_str("  (nothing)\n");
+    }
+    Ok(Some(listing.trim_end().to_string()))
+}
+
+fn tar_entries(reader: impl Read, entries: &mut Vec<(String, u64)>) -> Result<()> {
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let entry = entry?;
+        entries.push((
+            entry.path()?.to_string_lossy().into_owned(),
+            entry.header().size()?,
+        ));
+    }
+    Ok(())
+}
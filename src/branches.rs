@@ -0,0 +1,151 @@
+use anyhow::{Result, bail};
+
+/// Characters of a reply kept to recognize it by in listings.
+const PREVIEW_CHARS: usize = 60;
+
+/// A reply received this session, which the conversation can be forked from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Visited {
+    pub id: i64,
+    /// The message it answered
+    pub parent_id: Option<i64>,
+    /// The start of the reply, on one line
+    pub preview: String,
+}
+
+/// A line of the conversation, named so it can be switched back to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Branch {
+    pub label: String,
+    /// The latest message on the branch, which the next message replies to
+    pub head: Option<i64>,
+}
+
+/// The branches of a chat taken with `/fork`, and the messages seen along them. The API
+/// keeps every message with its parent, so a branch only needs to remember its head.
+#[derive(Debug, Clone)]
+pub struct Branches {
+    messages: Vec<Visited>,
+    branches: Vec<Branch>,
+    current: usize,
+}
+
+impl Default for Branches {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl Branches {
+    /// Starts with a single `main` branch at `head`.
+    #[must_use]
+    pub fn new(head: Option<i64>) -> Self {
+        Self {
+            messages: Vec::new(),
+            branches: vec![Branch {
+                label: "main".to_string(),
+                head,
+            }],
+            current: 0,
+        }
+    }
+
+    /// Notes a reply to `parent_id`, which moves the current branch along to it.
+    pub fn record(&mut self, parent_id: Option<i64>, id: i64, content: &str) {
+        if !self.messages.iter().any(|m| m.id == id) {
+            self.messages.push(Visited {
+                id,
+                parent_id,
+                preview: preview(content),
+            });
+        }
+        self.branches[self.current].head = Some(id);
+    }
+
+    /// The replies seen this session, oldest first.
+    #[must_use]
+    pub fn messages(&self) -> &[Visited] {
+        &self.messages
+    }
+
+    #[must_use]
+    pub fn get(&self, id: i64) -> Option<&Visited> {
+        self.messages.iter().find(|m| m.id == id)
+    }
+
+    #[must_use]
+    pub fn branches(&self) -> &[Branch] {
+        &self.branches
+    }
+
+    #[must_use]
+    pub fn current(&self) -> &Branch {
+        &self.branches[self.current]
+    }
+
+    #[must_use]
+    pub fn is_current(&self, index: usize) -> bool {
+        index == self.current
+    }
+
+    /// Starts a branch from message `from` and switches to it, named `label` or else
+    /// `branch-<n>`.
+    ///
+    /// # Errors
+    /// Returns an error if the label is taken or has spaces in it.
+    pub fn fork(&mut self, from: i64, label: Option<&str>) -> Result<&Branch> {
+        let label = match label.map(str::trim).filter(|l| !l.is_empty()) {
+            Some(label) if label.contains(char::is_whitespace) => {
+                bail!("Branch names cannot contain spaces: {label}")
+            }
+            Some(label) if label.parse::<usize>().is_ok() => {
+                bail!("Branch names cannot be numbers: {label}")
+            }
+            Some(label) if self.find(label).is_some() => {
+                bail!("There is already a branch called {label}")
+            }
+            Some(label) => label.to_string(),
+            None => (self.branches.len() + 1..)
+                .map(|n| format!("branch-{n}"))
+                .find(|label| self.find(label).is_none())
+                .unwrap_or_default(),
+        };
+        self.branches.push(Branch {
+            label,
+            head: Some(from),
+        });
+        self.current = self.branches.len() - 1;
+        Ok(self.current())
+    }
+
+    /// Switches to the branch with the given label or number (counting from 1).
+    ///
+    /// # Errors
+    /// Returns an error if there is no such branch.
+    pub fn switch(&mut self, key: &str) -> Result<&Branch> {
+        let index = match key.parse::<usize>() {
+            Ok(n) if (1..=self.branches.len()).contains(&n) => Some(n - 1),
+            Ok(_) => None,
+            Err(_) => self.find(key),
+        };
+        let Some(index) = index else {
+            bail!("Unknown branch: {key}");
+        };
+        self.current = index;
+        Ok(self.current())
+    }
+
+    fn find(&self, label: &str) -> Option<usize> {
+        self.branches.iter().position(|b| b.label == label)
+    }
+}
+
+fn preview(content: &str) -> String {
+    let line = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    if line.chars().count() > PREVIEW_CHARS {
+        let cut: String = line.chars().take(PREVIEW_CHARS).collect();
+        format!("{cut}…")
+    } else {
+        line
+    }
+}
@@ -0,0 +1,153 @@
+use crate::config::CacheConfig;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// A saved tool result.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub content: String,
+    pub status: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredEntry {
+    /// The full key, since file names are only hashes of it
+    key: String,
+    #[serde(flatten)]
+    entry: Entry,
+}
+
+/// Results of network tools kept on disk, one file per entry, so that repeating a search
+/// or fetch within the time to live does not go to the network again. Entries expire after
+/// the time to live, and the oldest are evicted beyond the size limit.
+#[derive(Debug, Clone)]
+pub struct Cache {
+    dir: PathBuf,
+    ttl: Duration,
+    max_entries: usize,
+}
+
+impl Cache {
+    #[must_use]
+    pub fn new(dir: PathBuf, ttl: Duration, max_entries: usize) -> Self {
+        Self {
+            dir,
+            ttl,
+            max_entries,
+        }
+    }
+
+    /// The cache `[cache]` in the config describes, in `~/.cache/deepseek-cli/web`, or
+    /// `None` if it is turned off or there is no cache directory.
+    #[must_use]
+    pub fn from_config(config: &CacheConfig) -> Option<Self> {
+        if !config.enabled || config.ttl_secs == 0 {
+            return None;
+        }
+        let dir = dirs::cache_dir()?.join("deepseek-cli/web");
+        Some(Self::new(
+            dir,
+            Duration::from_secs(config.ttl_secs),
+            config.max_entries,
+        ))
+    }
+
+    /// Looks up the entry for `key` among those of `kind`, such as `search`, unless it has
+    /// expired.
+    #[must_use]
+    pub fn get(&self, kind: &str, key: &str) -> Option<Entry> {
+        let path = self.path(kind, key);
+        let age = std::fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())?;
+        if age > self.ttl {
+            let _ = std::fs::remove_file(&path);
+            return None;
+        }
+        let stored: StoredEntry =
+            serde_json::from_str(&std::fs::read_to_string(&path).ok()?).ok()?;
+        (stored.key == cache_key(kind, key)).then_some(stored.entry)
+    }
+
+    /// Saves the entry for `key`, then evicts expired entries and the oldest ones beyond
+    /// the size limit.
+    ///
+    /// # Errors
+    /// Returns an error if the entry cannot be written.
+    pub fn put(&self, kind: &str, key: &str, entry: &Entry) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let stored = StoredEntry {
+            key: cache_key(kind, key),
+            entry: entry.clone(),
+        };
+        std::fs::write(self.path(kind, key), serde_json::to_string(&stored)?)?;
+        self.evict();
+        Ok(())
+    }
+
+    /// Removes expired entries, then the oldest ones until at most the size limit are left.
+    pub fn evict(&self) {
+        let Ok(dir) = std::fs::read_dir(&self.dir) else {
+            return;
+        };
+        let now = SystemTime::now();
+        let mut entries: Vec<(SystemTime, PathBuf)> = Vec::new();
+        for entry in dir.filter_map(std::result::Result::ok) {
+            let path = entry.path();
+            if path.extension().is_none_or(|e| e != "json") {
+                continue;
+            }
+            let Ok(modified) = entry.metadata().and_then(|m| m.modified()) else {
+                continue;
+            };
+            if now.duration_since(modified).is_ok_and(|age| age > self.ttl) {
+                let _ = std::fs::remove_file(&path);
+            } else {
+                entries.push((modified, path));
+            }
+        }
+        if entries.len() > self.max_entries {
+            entries.sort();
+            let excess = entries.len() - self.max_entries;
+            for (_, path) in entries.into_iter().take(excess) {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+
+    /// Removes every entry.
+    ///
+    /// # Errors
+    /// Returns an error if the directory exists but cannot be removed.
+    pub fn clear(&self) -> Result<()> {
+        if self.dir.exists() {
+            std::fs::remove_dir_all(&self.dir)?;
+        }
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, kind: &str, key: &str) -> PathBuf {
+        self.dir
+            .join(format!("{kind}-{:016x}.json", fnv1a(&cache_key(kind, key))))
+    }
+}
+
+fn cache_key(kind: &str, key: &str) -> String {
+    format!("{kind}\n{}", key.trim())
+}
+
+/// The 64-bit FNV-1a hash, which unlike `DefaultHasher` stays the same across Rust
+/// versions, so file names do too.
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
@@ -0,0 +1,286 @@
+use crate::commit::is_conventional;
+use crate::mentions::fence_for;
+use anyhow::{Result, anyhow, bail};
+use std::fmt::Write;
+use std::path::Path;
+use std::process::Command;
+
+/// Most commit text sent to the model at once. A larger range is drafted a few commits at
+/// a time.
+pub const MAX_CHUNK_BYTES: usize = 12_000;
+
+/// Most of one commit's diff sent along with its message; its `--stat` comes first, so a
+/// cut diff still lists every file.
+const MAX_COMMIT_DIFF_BYTES: usize = 3_000;
+
+/// The prompt drafting release notes starts with, in place of the agent's usual
+/// instructions.
+pub const CHANGELOG_SYSTEM_PROMPT: &str = "You are drafting the release notes of a project from the commits since its last release, a few commits at a time. Write one line for each change a user of the project would notice, in the form
+
+[type] what changed
+
+where type is breaking (a change that breaks existing use), feat (something new), fix (a bug fixed), perf (faster or lighter), docs (documentation) or other. Write for users rather than developers, in the past tense, without commit hashes or the type prefix of the commit message. Make commits that belong to one change a single line, and leave out those that change nothing for users, such as refactoring, tests and CI. Do not call any tools. If none of the commits is worth listing, reply with only: Nothing to list.";
+
+/// A commit in the range the notes are drafted from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Commit {
+    pub hash: String,
+    pub subject: String,
+    pub body: String,
+    /// `git show --stat --patch`, cut to a few KB
+    pub diff: String,
+}
+
+impl Commit {
+    /// The abbreviated hash, as git shows it.
+    #[must_use]
+    pub fn short_hash(&self) -> &str {
+        &self.hash[..self.hash.len().min(7)]
+    }
+
+    fn render(&self) -> String {
+        let mut out = format!("commit {}\n{}\n", self.short_hash(), self.subject);
+        if !self.body.trim().is_empty() {
+            let _ = write!(out, "\n{}\n", self.body.trim_end());
+        }
+        if !self.diff.trim().is_empty() {
+            let fence = fence_for(&self.diff);
+            let _ = write!(out, "\n{fence}diff\n{}\n{fence}\n", self.diff.trim_end());
+        }
+        out
+    }
+}
+
+/// The most recent tag reachable from `HEAD`, the default start of the range.
+///
+/// # Errors
+/// Returns an error if git fails, as when there are no tags.
+pub fn latest_tag(dir: &Path) -> Result<String> {
+    let tag = git(dir, &["describe", "--tags", "--abbrev=0"])
+        .map_err(|e| anyhow!("{e}; give the start of the range with --since"))?;
+    Ok(tag.trim().to_string())
+}
+
+/// The commits after `since` up to `HEAD`, oldest first and without merges, each with its
+/// diff.
+///
+/// # Errors
+/// Returns an error if git fails, as when `since` names no commit.
+pub fn commits(dir: &Path, since: &str) -> Result<Vec<Commit>> {
+    if since.starts_with('-') {
+        bail!("Not a tag or commit: {since}");
+    }
+    let range = format!("{since}..HEAD");
+    let log = git(
+        dir,
+        &[
+            "log",
+            "--no-merges",
+            "--reverse",
+            "--format=%H%x1f%s%x1f%b%x1e",
+            &range,
+        ],
+    )?;
+    let mut commits = parse_log(&log);
+    for commit in &mut commits {
+        let diff = git(
+            dir,
+            &["show", "--format=", "--stat", "--patch", &commit.hash],
+        )?;
+        commit.diff = cut(diff, MAX_COMMIT_DIFF_BYTES);
+    }
+    Ok(commits)
+}
+
+/// Reads the output of `git log --format=%H%x1f%s%x1f%b%x1e` into commits, without their
+/// diffs.
+#[must_use]
+pub fn parse_log(log: &str) -> Vec<Commit> {
+    log.split('\x1e')
+        .filter_map(|record| {
+            let mut fields = record.trim_start_matches('\n').splitn(3, '\x1f');
+            let hash = fields.next()?.trim();
+            let subject = fields.next()?.trim();
+            (!hash.is_empty()).then(|| Commit {
+                hash: hash.to_string(),
+                subject: subject.to_string(),
+                body: fields.next().unwrap_or_default().trim().to_string(),
+                diff: String::new(),
+            })
+        })
+        .collect()
+}
+
+fn cut(mut text: String, max_bytes: usize) -> String {
+    if text.len() <= max_bytes {
+        return text;
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text.truncate(end);
+    text.push_str("\n[diff cut]");
+    text
+}
+
+/// The commits whose subject does not follow Conventional Commits, which the notes cannot
+/// be grouped by as reliably.
+#[must_use]
+pub fn unconventional(commits: &[Commit]) -> Vec<&Commit> {
+    commits
+        .iter()
+        .filter(|commit| !is_conventional(&commit.subject))
+        .collect()
+}
+
+/// Some of the commits of the range, sent to the model on their own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    /// How many commits it holds
+    pub commits: usize,
+    pub text: String,
+}
+
+/// Splits the commits into chunks of at most `max_bytes`, in order. A commit larger on its
+/// own makes a chunk by itself.
+#[must_use]
+pub fn chunks(commits: &[Commit], max_bytes: usize) -> Vec<Chunk> {
+    let mut chunks: Vec<Chunk> = Vec::new();
+    for commit in commits {
+        let text = commit.render();
+        match chunks.last_mut() {
+            Some(chunk) if chunk.text.len() + text.len() + 1 <= max_bytes => {
+                chunk.text.push('\n');
+                chunk.text.push_str(&text);
+                chunk.commits += 1;
+            }
+            _ => chunks.push(Chunk { commits: 1, text }),
+        }
+    }
+    chunks
+}
+
+/// The message asking for the notes of chunk `index` (from 1) of `total`.
+#[must_use]
+pub fn chunk_prompt(chunk: &Chunk, index: usize, total: usize) -> String {
+    format!(
+        "Part {index} of {total}, {} commit(s):\n\n{}",
+        chunk.commits, chunk.text
+    )
+}
+
+/// A group of the notes, in the order they are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Section {
+    Breaking,
+    Features,
+    Fixes,
+    Performance,
+    Documentation,
+    Other,
+}
+
+impl Section {
+    fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "breaking" | "breaking change" => Some(Self::Breaking),
+            "feat" | "feature" | "added" => Some(Self::Features),
+            "fix" | "bugfix" | "fixed" => Some(Self::Fixes),
+            "perf" | "performance" => Some(Self::Performance),
+            "docs" | "doc" | "documentation" => Some(Self::Documentation),
+            "other" | "refactor" | "chore" | "build" | "ci" | "style" | "test" | "changed" => {
+                Some(Self::Other)
+            }
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn heading(self) -> &'static str {
+        match self {
+            Self::Breaking => "Breaking changes",
+            Self::Features => "Features",
+            Self::Fixes => "Bug fixes",
+            Self::Performance => "Performance",
+            Self::Documentation => "Documentation",
+            Self::Other => "Other changes",
+        }
+    }
+}
+
+/// One line of the notes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub section: Section,
+    pub text: String,
+}
+
+/// Reads the notes out of the model's reply, in the form [`CHANGELOG_SYSTEM_PROMPT`] asks
+/// for. Lines that are not notes are ignored.
+#[must_use]
+pub fn parse_entries(reply: &str) -> Vec<Entry> {
+    reply
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim().trim_start_matches(['-', '*']).trim_start();
+            let (section, text) = line.strip_prefix('[')?.split_once(']')?;
+            let section = Section::parse(section)?;
+            let text = text.trim();
+            (!text.is_empty()).then(|| Entry {
+                section,
+                text: text.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// The changelog entry for the notes: a `## title` heading and a `###` section for each
+/// group that has notes, in the order of [`Section`].
+#[must_use]
+pub fn render(title: &str, entries: &[Entry]) -> String {
+    let mut sections: Vec<Section> = entries.iter().map(|entry| entry.section).collect();
+    sections.sort();
+    sections.dedup();
+    let mut out = format!("## {title}\n");
+    for section in sections {
+        let _ = write!(out, "\n### {}\n\n", section.heading());
+        for entry in entries.iter().filter(|entry| entry.section == section) {
+            let _ = writeln!(out, "- {}", entry.text);
+        }
+    }
+    out
+}
+
+/// `changelog` with `entry` added as its newest: before the first `## ` heading, or at
+/// the end if it has none. An empty changelog gets a `# Changelog` title first.
+#[must_use]
+pub fn insert(changelog: &str, entry: &str) -> String {
+    if changelog.trim().is_empty() {
+        return format!("# Changelog\n\n{entry}");
+    }
+    let mut offset = 0;
+    for line in changelog.split_inclusive('\n') {
+        if line.starts_with("## ") {
+            return format!("{}{entry}\n{}", &changelog[..offset], &changelog[offset..]);
+        }
+        offset += line.len();
+    }
+    format!("{}\n\n{entry}", changelog.trim_end())
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| anyhow!("Failed to run git: {e}"))?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,147 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Maximum length of a title derived from the first message of a chat.
+const TITLE_MAX_CHARS: usize = 60;
+
+/// A conversation this CLI has created or resumed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatEntry {
+    pub id: String,
+    #[serde(default)]
+    pub title: String,
+    /// Unix timestamp (seconds) of the last message sent in this chat.
+    pub last_active: i64,
+}
+
+/// Local list of known chats, stored in `~/.config/deepseek-cli/chats.json`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ChatRegistry {
+    chats: Vec<ChatEntry>,
+}
+
+impl ChatRegistry {
+    #[must_use]
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|d| d.join("deepseek-cli/chats.json"))
+    }
+
+    /// Loads the registry, returning an empty one if the file does not exist yet.
+    ///
+    /// # Errors
+    /// Returns an error if the file exists but cannot be read or parsed.
+    pub fn load() -> Result<Self> {
+        let Some(path) = Self::path().filter(|p| p.exists()) else {
+            return Ok(Self::default());
+        };
+        let content = std::fs::read_to_string(&path)?;
+        serde_json::from_str(&content).map_err(|e| anyhow!("Invalid {}: {e}", path.display()))
+    }
+
+    /// # Errors
+    /// Returns an error if the config directory is unavailable or the file cannot be written.
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path().ok_or_else(|| anyhow!("No config directory available"))?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Records activity in a chat, adding it if unknown. A chat without a title is named
+    /// after `message`, which should be the first thing the user typed.
+    pub fn touch(&mut self, id: &str, message: Option<&str>) {
+        let now = chrono::Utc::now().timestamp();
+        let index = if let Some(i) = self.chats.iter().position(|c| c.id == id) {
+            i
+        } else {
+            self.chats.push(ChatEntry {
+                id: id.to_string(),
+                title: String::new(),
+                last_active: now,
+            });
+            self.chats.len() - 1
+        };
+        let entry = &mut self.chats[index];
+        entry.last_active = now;
+        if entry.title.is_empty()
+            && let Some(message) = message
+        {
+            entry.title = title_from_message(message);
+        }
+    }
+
+    /// Returns the chats, most recently active first.
+    #[must_use]
+    pub fn list(&self) -> Vec<&ChatEntry> {
+        let mut chats: Vec<&ChatEntry> = self.chats.iter().collect();
+        chats.sort_by_key(|c| std::cmp::Reverse(c.last_active));
+        chats
+    }
+
+    /// Looks a chat up by its 1-based position in [`Self::list`] or by its ID.
+    #[must_use]
+    pub fn resolve(&self, key: &str) -> Option<&ChatEntry> {
+        let chats = self.list();
+        if let Some(entry) = key
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| n.checked_sub(1))
+            .and_then(|i| chats.get(i))
+        {
+            return Some(entry);
+        }
+        chats.into_iter().find(|c| c.id == key)
+    }
+
+    /// Removes a chat from the registry, returning it if it was present.
+    pub fn remove(&mut self, key: &str) -> Option<ChatEntry> {
+        let id = self.resolve(key)?.id.clone();
+        let index = self.chats.iter().position(|c| c.id == id)?;
+        Some(self.chats.remove(index))
+    }
+
+    /// Renames a chat, returning `false` if it is unknown.
+    pub fn rename(&mut self, key: &str, title: &str) -> bool {
+        let Some(id) = self.resolve(key).map(|c| c.id.clone()) else {
+            return false;
+        };
+        self.chats
+            .iter_mut()
+            .filter(|c| c.id == id)
+            .for_each(|c| c.title = title.to_string());
+        true
+    }
+}
+
+fn title_from_message(message: &str) -> String {
+    let line = message.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+    let mut title: String = line.trim().chars().take(TITLE_MAX_CHARS).collect();
+    if line.trim().chars().count() > TITLE_MAX_CHARS {
+        title.push('…');
+    }
+    title
+}
+
+impl ChatEntry {
+    /// Formats the entry as a single line for chat listings.
+    #[must_use]
+    pub fn summary(&self) -> String {
+        let when = chrono::DateTime::from_timestamp(self.last_active, 0).map_or_else(
+            || "unknown".to_string(),
+            |t| {
+                t.with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            },
+        );
+        let title = if self.title.is_empty() {
+            "(untitled)"
+        } else {
+            self.title.as_str()
+        };
+        format!("{title} [{when}] {}", self.id)
+    }
+}
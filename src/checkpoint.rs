@@ -0,0 +1,233 @@
+use anyhow::{Result, anyhow, bail};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Refs git checkpoints are kept under, so that git does not garbage-collect them.
+const REF_PREFIX: &str = "refs/deepseek-cli/checkpoints";
+
+/// Directories a snapshot leaves out: version control data, build output and dependencies.
+const SNAPSHOT_SKIPPED_DIRS: [&str; 4] = [".git", ".jj", "target", "node_modules"];
+
+/// How checkpoints of the working tree are stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// A commit made from a separate index, kept under `refs/deepseek-cli/checkpoints`.
+    Git,
+    /// The working-copy commit of a Jujutsu repository, which `jj` records on every run.
+    Jujutsu,
+    /// A copy of the files in the data directory, for workspaces without version control.
+    Snapshot,
+}
+
+impl Backend {
+    /// Picks the backend for the workspace containing `dir`: Jujutsu in a `jj` repository
+    /// (including one colocated with git), git in a git repository and plain snapshots
+    /// otherwise. A repository whose tool is not installed is treated as a plain directory.
+    #[must_use]
+    pub fn detect(dir: &Path) -> Self {
+        for ancestor in dir.ancestors() {
+            if ancestor.join(".jj").is_dir() && succeeds(jj(dir, &["root"])) {
+                return Self::Jujutsu;
+            }
+            if ancestor.join(".git").exists()
+                && succeeds(git(dir, &["rev-parse", "--is-inside-work-tree"]))
+            {
+                return Self::Git;
+            }
+        }
+        Self::Snapshot
+    }
+
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Git => "git",
+            Self::Jujutsu => "jj",
+            Self::Snapshot => "snapshot",
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A saved state of a working tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub backend: Backend,
+    /// The commit id, or the name of the snapshot's directory
+    pub id: String,
+    /// The directory that was saved
+    pub root: PathBuf,
+    pub message: String,
+}
+
+impl Checkpoint {
+    /// The id shortened for display. [`find`] accepts it.
+    #[must_use]
+    pub fn short_id(&self) -> &str {
+        match self.backend {
+            Backend::Git | Backend::Jujutsu => &self.id[..self.id.len().min(12)],
+            Backend::Snapshot => &self.id,
+        }
+    }
+}
+
+/// Saves the working tree of `dir` with the backend detected for it, without touching the
+/// files, the branch or anything staged. With git, untracked files are included unless
+/// ignored.
+///
+/// # Errors
+/// Returns an error if a version control command fails or the snapshot cannot be written.
+pub fn create(dir: &Path, message: &str) -> Result<Checkpoint> {
+    let root = dir.canonicalize()?;
+    let backend = Backend::detect(&root);
+    let id = match backend {
+        Backend::Git => git_create(&root, message)?,
+        // Running jj records the working copy, so its commit is the checkpoint
+        Backend::Jujutsu => run(&mut jj(
+            &root,
+            &["log", "--no-graph", "-r", "@", "-T", "commit_id"],
+        ))?,
+        Backend::Snapshot => snapshot_create(&root)?,
+    };
+    Ok(Checkpoint {
+        backend,
+        id,
+        root,
+        message: message.to_string(),
+    })
+}
+
+/// Puts the files of the checkpoint's directory back as they were. Jujutsu also removes
+/// files created since; git and snapshots leave them.
+///
+/// # Errors
+/// Returns an error if a version control command fails or the snapshot cannot be copied.
+pub fn restore(checkpoint: &Checkpoint) -> Result<()> {
+    let root = &checkpoint.root;
+    match checkpoint.backend {
+        Backend::Git => {
+            run(&mut git(root, &["checkout", &checkpoint.id, "--", "."]))?;
+        }
+        Backend::Jujutsu => {
+            run(&mut jj(root, &["restore", "--from", &checkpoint.id]))?;
+        }
+        Backend::Snapshot => {
+            let dir = snapshot_dir().ok_or_else(|| anyhow!("No data directory available"))?;
+            copy_tree(&dir.join(&checkpoint.id), root)?;
+        }
+    }
+    Ok(())
+}
+
+/// Finds the latest checkpoint whose id starts with `id`.
+#[must_use]
+pub fn find<'a>(checkpoints: &'a [Checkpoint], id: &str) -> Option<&'a Checkpoint> {
+    checkpoints.iter().rev().find(|c| c.id.starts_with(id))
+}
+
+/// Where plain snapshots are kept, `~/.local/share/deepseek-cli/snapshots`.
+#[must_use]
+pub fn snapshot_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|d| d.join("deepseek-cli/snapshots"))
+}
+
+fn git_create(dir: &Path, message: &str) -> Result<String> {
+    // A separate index, so the user's staged changes are left alone
+    let index = run(&mut git(
+        dir,
+        &["rev-parse", "--git-path", "deepseek-cli-checkpoint-index"],
+    ))?;
+    let index = dir.join(index);
+    let _ = std::fs::remove_file(&index);
+    let result = git_snapshot(dir, &index, message);
+    let _ = std::fs::remove_file(&index);
+    result
+}
+
+fn git_snapshot(dir: &Path, index: &Path, message: &str) -> Result<String> {
+    run(git(dir, &["add", "--all", "."]).env("GIT_INDEX_FILE", index))?;
+    let tree = run(git(dir, &["write-tree"]).env("GIT_INDEX_FILE", index))?;
+    let head = run(&mut git(dir, &["rev-parse", "--verify", "--quiet", "HEAD"])).ok();
+    let mut args = vec!["commit-tree", tree.as_str(), "-m", message];
+    if let Some(head) = &head {
+        args.extend(["-p", head.as_str()]);
+    }
+    // Checkpoints are authored by the CLI, which also works where no identity is configured
+    let commit = run(git(dir, &args)
+        .env("GIT_AUTHOR_NAME", "deepseek-cli")
+        .env("GIT_AUTHOR_EMAIL", "deepseek-cli@localhost")
+        .env("GIT_COMMITTER_NAME", "deepseek-cli")
+        .env("GIT_COMMITTER_EMAIL", "deepseek-cli@localhost"))?;
+    let name = format!("{REF_PREFIX}/{}", timestamp());
+    run(&mut git(dir, &["update-ref", &name, &commit]))?;
+    Ok(commit)
+}
+
+fn snapshot_create(root: &Path) -> Result<String> {
+    let dir = snapshot_dir().ok_or_else(|| anyhow!("No data directory available"))?;
+    let id = timestamp();
+    copy_tree(root, &dir.join(&id))?;
+    Ok(id)
+}
+
+/// Copies the files under `from` into `to`, leaving out [`SNAPSHOT_SKIPPED_DIRS`].
+fn copy_tree(from: &Path, to: &Path) -> Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let target = to.join(entry.file_name());
+        if file_type.is_dir() {
+            let name = entry.file_name();
+            if !SNAPSHOT_SKIPPED_DIRS.contains(&name.to_string_lossy().as_ref()) {
+                copy_tree(&entry.path(), &target)?;
+            }
+        } else if file_type.is_file() {
+            std::fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+fn timestamp() -> String {
+    chrono::Utc::now().format("%Y%m%dT%H%M%S%.3f").to_string()
+}
+
+fn git(dir: &Path, args: &[&str]) -> Command {
+    let mut command = Command::new("git");
+    command.args(args).current_dir(dir);
+    command
+}
+
+fn jj(dir: &Path, args: &[&str]) -> Command {
+    let mut command = Command::new("jj");
+    command.args(args).current_dir(dir);
+    command
+}
+
+fn succeeds(mut command: Command) -> bool {
+    command.output().is_ok_and(|output| output.status.success())
+}
+
+/// Runs a version control command, returning its trimmed output.
+fn run(command: &mut Command) -> Result<String> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let output = command
+        .output()
+        .map_err(|e| anyhow!("Failed to run {program}: {e}"))?;
+    if !output.status.success() {
+        let subcommand = command.get_args().next().unwrap_or_default();
+        bail!(
+            "{program} {} failed: {}",
+            subcommand.to_string_lossy(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
@@ -0,0 +1,666 @@
+use crate::autonomous::parse_duration;
+use crate::fix::{DEFAULT_MAX_ROUNDS, FixTarget};
+use crate::format::ResponseFormat;
+use crate::import::ImportFormat;
+use crate::model::Model;
+use crate::review::ReviewSource;
+use crate::sessions::validate_name;
+use crate::watch::DEFAULT_DEBOUNCE;
+use anyhow::{Result, anyhow, bail};
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub const USAGE: &str = "\
+Usage: deepseek [chat-id] [--tui] [--share] [--quiet] [--output text|json]
+                [--format json|markdown|plain] [--max-output-tokens <n>] [--model chat|reasoner]
+                [--max-iterations <n>] [--profile <name>] [--log-dir <dir>] [--dry-run]
+                [--no-search]
+                                           start a new chat, or resume an existing one
+                [--record <fixture.json>|--mock <fixture.json>]
+                                           save the replies to a fixture, or replay one offline
+                [--autonomous <15m> [--report-every <5m>]]
+                                           work on the first task without approvals for a set time
+       deepseek --session <name> [options]  continue the chat saved under a name, created on first use
+       deepseek [chat-id] -p <prompt>      send one prompt; the reply goes to stdout, the rest to stderr
+                [--output-schema <schema.json>]
+                                           reply with JSON validated against the schema
+       deepseek new [--workflow <name>] [--var NAME=value]... [options]
+                                           start a new chat, seeded from a workflow in the config
+       deepseek observe <n|id>             watch a chat started with --share, read-only
+       deepseek chats [list|delete|rename]  manage known chats
+       deepseek import <file> [--format chatgpt|openai|aider] [--output <file.md>]
+                                           continue a conversation exported from another tool
+       deepseek run --task <task.md> [--max-turns <n>] [--report <report.md>] [options]
+                                           work on a task unattended and write a report
+       deepseek fix-tests [--cmd <command>] [--max-rounds <n>] [options]
+                                           run the tests, have the model fix the failures and
+                                           run them again until they pass
+       deepseek fix-build [--cmd <command>] [--max-rounds <n>] [options]
+                                           the same for the build, sending cargo's errors
+                                           to the model as structured messages
+       deepseek watch --on-change <prompt> [--glob <pattern>]... [--debounce <ms>] [options]
+                                           run a prompt unattended whenever files matching
+                                           the globs (all files by default) change
+       deepseek explain <path|path#symbol|symbol> [options]
+                                           explain a file, or a symbol found in the outlines
+                                           of the workspace's files, and exit
+       deepseek review [--diff <range>|--pr <url>]
+                                           review uncommitted changes, a git range or a GitHub
+                                           pull request and list the findings by file
+       deepseek changelog [--since <tag>] [--title <heading>] [--output <file>]
+                                           draft release notes from the commits since a tag
+                                           (the latest by default) and add them to the
+                                           changelog (CHANGELOG.md) once confirmed
+       deepseek web [--port <port>] [--dry-run]
+                                           serve a local web interface on 127.0.0.1
+       deepseek auth login|logout          store the API token in the OS keyring, or remove it
+       deepseek auth status                check the token with the API and show the account in use
+
+Add --account <name> to any command to use the token of an [accounts.<name>] entry in the
+config instead of the default one, and -v (or -vv for more) to log requests, tool calls and
+retries to stderr, or to a file with --log-file <path>.
+
+With -p, --output json and run, the exit code says how the task went: 0 when it finished,
+2 when it was stopped at the turn limit or interrupted, 3 when a tool call was refused by
+a [[policy]] rule, 4 when the API failed and 1 for any other error.";
+
+/// Port used by `deepseek web` when `--port` is not given.
+pub const DEFAULT_WEB_PORT: u16 = 8321;
+
+/// Where `deepseek run` writes its report when `--report` is not given.
+pub const DEFAULT_REPORT: &str = "deepseek-report.md";
+
+/// What the binary was asked to do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliCommand {
+    Help,
+    /// Start a new chat, or resume the given one.
+    Chat(ChatOptions),
+    /// Watch a shared chat, given by list index or id.
+    Observe(String),
+    /// `chats` with its remaining arguments.
+    Chats(Vec<String>),
+    /// Import a conversation into a new chat, or convert it to a local transcript if
+    /// `output` is set.
+    Import {
+        path: PathBuf,
+        format: Option<ImportFormat>,
+        output: Option<PathBuf>,
+    },
+    /// Serve the local web interface.
+    Web {
+        port: u16,
+        /// Tools that change things only report what they would do
+        dry_run: bool,
+    },
+    /// Store the API token in the OS keyring, or remove it.
+    Auth(AuthCommand),
+    /// Work on a task without a terminal and report on it.
+    Run(RunOptions),
+    /// Have the model review a diff.
+    Review(ReviewSource),
+    /// Have the model explain a file or symbol, then exit.
+    Explain(ExplainOptions),
+    /// Have the model draft release notes from the commits since a tag.
+    Changelog(ChangelogOptions),
+    /// Run the tests or the build and have the model fix them until they pass.
+    Fix(FixOptions),
+    /// Run a prompt without a terminal whenever watched files change.
+    Watch(WatchOptions),
+}
+
+/// Options for `run`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunOptions {
+    /// File holding the task, sent as the first message
+    pub task: PathBuf,
+    /// Where the report is written
+    pub report: PathBuf,
+    /// Everything else, as for a chat; `--max-turns` is its `max_iterations`
+    pub chat: ChatOptions,
+}
+
+/// Options for `fix-tests` and `fix-build`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixOptions {
+    /// Whether the tests or the build are fixed
+    pub target: FixTarget,
+    /// The test or build command, or `None` to pick one for the kind of project
+    pub command: Option<String>,
+    /// Rounds of fixes before giving up, each followed by a run of the tests
+    pub max_rounds: u32,
+    /// Everything else, as for a chat
+    pub chat: ChatOptions,
+}
+
+/// Options for `explain`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExplainOptions {
+    /// `<path>`, `<path>#<symbol>` or `<symbol>`
+    pub target: String,
+    /// Everything else, as for a chat
+    pub chat: ChatOptions,
+}
+
+/// Options for `changelog`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangelogOptions {
+    /// The tag or commit the release starts after, or `None` for the latest tag
+    pub since: Option<String>,
+    /// The heading of the entry
+    pub title: String,
+    /// The changelog the entry is added to
+    pub output: PathBuf,
+}
+
+/// Options for `watch`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchOptions {
+    /// Sent, after the list of changed files, each time they settle
+    pub prompt: String,
+    /// The files whose changes count, such as `**/*.rs`; all of them if empty
+    pub globs: Vec<String>,
+    /// How long the files must go unchanged before the prompt runs
+    pub debounce: Duration,
+    /// Everything else, as for a chat
+    pub chat: ChatOptions,
+}
+
+/// What `auth` was asked to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthCommand {
+    Login,
+    Logout,
+    /// Check the token with the API and show where it came from.
+    Status,
+}
+
+/// Options for an interactive chat.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChatOptions {
+    pub resume: Option<String>,
+    /// Continue the chat saved under this name, creating it on first use
+    pub session: Option<String>,
+    /// Use the full-screen interface instead of the line-based REPL
+    pub tui: bool,
+    /// Mirror the session to `observe` in other terminals
+    pub share: bool,
+    /// Hide the model's thinking, showing only responses and tool status
+    pub quiet: bool,
+    pub output: OutputFormat,
+    /// Seed the new chat from this `[workflows.<name>]` template
+    pub workflow: Option<String>,
+    /// Start with this profile instead of the configured default
+    pub profile: Option<String>,
+    /// Send this prompt and exit, writing only the reply to stdout
+    pub print: Option<String>,
+    /// Format replies are asked to follow
+    pub format: Option<ResponseFormat>,
+    /// Length replies are asked to stay under
+    pub max_output_tokens: Option<u32>,
+    /// Rounds of tool calls a message may take before asking whether to go on
+    pub max_iterations: Option<u32>,
+    /// JSON Schema the reply to `print` must match
+    pub output_schema: Option<PathBuf>,
+    /// Model to answer with instead of the default
+    pub model: Option<Model>,
+    /// Answer without searching the web, whatever the config says
+    pub no_search: bool,
+    /// `NAME=value` assignments for `$NAME` in prompts and workflows
+    pub vars: Vec<String>,
+    /// Work on the first task without approvals for this long
+    pub autonomous: Option<Duration>,
+    /// How often an autonomous run reports its progress
+    pub report_every: Option<Duration>,
+    /// Directory for the session's JSONL event log, instead of the config directory
+    pub log_dir: Option<PathBuf>,
+    /// Report what tools that change files or run commands would do instead of running them
+    pub dry_run: bool,
+    /// Replay the replies recorded in this fixture instead of calling the API
+    pub mock: Option<PathBuf>,
+    /// Save the replies streamed from the API to this fixture
+    pub record: Option<PathBuf>,
+}
+
+/// How a chat's output is written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    /// One JSON event per line on stdout, with messages read line by line from stdin
+    Json,
+}
+
+/// Parsed command-line arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cli {
+    pub command: CliCommand,
+    /// Use the token of this `[accounts.<name>]` entry
+    pub account: Option<String>,
+    /// How many `-v` flags were given
+    pub verbosity: u8,
+    /// Write the debug log here instead of stderr
+    pub log_file: Option<PathBuf>,
+}
+
+impl Cli {
+    /// Parses the arguments following the program name.
+    ///
+    /// # Errors
+    /// Returns an error describing the problem for unknown options or missing values.
+    pub fn parse(args: &[String]) -> Result<Self> {
+        let mut args = ArgList(args.to_vec());
+        if args.flag(&["--help", "-h"]) {
+            return Ok(Self {
+                command: CliCommand::Help,
+                account: None,
+                verbosity: 0,
+                log_file: None,
+            });
+        }
+        let account = args.value("--account")?;
+        let mut verbosity: u8 = 0;
+        while args.flag(&["-v", "--verbose"]) {
+            verbosity = verbosity.saturating_add(1);
+        }
+        while args.flag(&["-vv"]) {
+            verbosity = verbosity.saturating_add(2);
+        }
+        let log_file = args.value("--log-file")?.map(PathBuf::from);
+        let command = match args.0.first().map(String::as_str) {
+            Some("chats") => {
+                args.0.remove(0);
+                CliCommand::Chats(args.positionals()?)
+            }
+            Some("import") => {
+                args.0.remove(0);
+                let format = args
+                    .value("--format")?
+                    .map(|f| {
+                        ImportFormat::from_name(&f).ok_or_else(|| anyhow!("Unknown format: {f}"))
+                    })
+                    .transpose()?;
+                let output = args.value("--output")?.map(PathBuf::from);
+                let [path] = <[String; 1]>::try_from(args.positionals()?)
+                    .map_err(|_| anyhow!("import expects exactly one file\n{USAGE}"))?;
+                CliCommand::Import {
+                    path: PathBuf::from(path),
+                    format,
+                    output,
+                }
+            }
+            Some("observe") => {
+                args.0.remove(0);
+                let [target] = <[String; 1]>::try_from(args.positionals()?)
+                    .map_err(|_| anyhow!("observe expects exactly one chat\n{USAGE}"))?;
+                CliCommand::Observe(target)
+            }
+            Some("run") => {
+                args.0.remove(0);
+                let task = args
+                    .value("--task")?
+                    .map(PathBuf::from)
+                    .ok_or_else(|| anyhow!("run needs --task <file>\n{USAGE}"))?;
+                let max_turns = args
+                    .value("--max-turns")?
+                    .map(|n| match n.parse() {
+                        Ok(turns) if turns > 0 => Ok(turns),
+                        _ => Err(anyhow!("Invalid --max-turns: {n}")),
+                    })
+                    .transpose()?;
+                let report = args
+                    .value("--report")?
+                    .map_or_else(|| PathBuf::from(DEFAULT_REPORT), PathBuf::from);
+                let mut chat = chat_options(args)?;
+                if chat.resume.is_some()
+                    || chat.tui
+                    || chat.share
+                    || chat.print.is_some()
+                    || chat.output != OutputFormat::Text
+                    || chat.autonomous.is_some()
+                {
+                    bail!(
+                        "run starts its own chat and cannot be combined with a chat id, --tui, --share, -p, --output or --autonomous"
+                    );
+                }
+                chat.max_iterations = max_turns.or(chat.max_iterations);
+                CliCommand::Run(RunOptions { task, report, chat })
+            }
+            Some(subcommand @ ("fix-tests" | "fix-build")) => {
+                let target = if subcommand == "fix-tests" {
+                    FixTarget::Tests
+                } else {
+                    FixTarget::Build
+                };
+                args.0.remove(0);
+                let command = args.value("--cmd")?.filter(|c| !c.trim().is_empty());
+                let max_rounds = args
+                    .value("--max-rounds")?
+                    .map(|n| match n.parse() {
+                        Ok(rounds) if rounds > 0 => Ok(rounds),
+                        _ => Err(anyhow!("Invalid --max-rounds: {n}")),
+                    })
+                    .transpose()?
+                    .unwrap_or(DEFAULT_MAX_ROUNDS);
+                let chat = chat_options(args)?;
+                if chat.resume.is_some()
+                    || chat.tui
+                    || chat.share
+                    || chat.print.is_some()
+                    || chat.output != OutputFormat::Text
+                    || chat.autonomous.is_some()
+                {
+                    bail!(
+                        "{} starts its own chat and cannot be combined with a chat id, --tui, --share, -p, --output or --autonomous",
+                        target.subcommand()
+                    );
+                }
+                CliCommand::Fix(FixOptions {
+                    target,
+                    command,
+                    max_rounds,
+                    chat,
+                })
+            }
+            Some("watch") => {
+                args.0.remove(0);
+                let prompt = args
+                    .value("--on-change")?
+                    .filter(|p| !p.trim().is_empty())
+                    .ok_or_else(|| anyhow!("watch needs --on-change <prompt>\n{USAGE}"))?;
+                let mut globs = Vec::new();
+                while let Some(glob) = args.value("--glob")? {
+                    globs.push(glob);
+                }
+                let debounce = args
+                    .value("--debounce")?
+                    .map(|ms| {
+                        ms.parse().map(Duration::from_millis).map_err(|_| {
+                            anyhow!("Invalid --debounce: {ms} (expected milliseconds)")
+                        })
+                    })
+                    .transpose()?
+                    .unwrap_or(DEFAULT_DEBOUNCE);
+                let chat = chat_options(args)?;
+                if chat.resume.is_some()
+                    || chat.tui
+                    || chat.share
+                    || chat.print.is_some()
+                    || chat.output != OutputFormat::Text
+                    || chat.autonomous.is_some()
+                {
+                    bail!(
+                        "watch starts its own chat and cannot be combined with a chat id, --tui, --share, -p, --output or --autonomous"
+                    );
+                }
+                CliCommand::Watch(WatchOptions {
+                    prompt,
+                    globs,
+                    debounce,
+                    chat,
+                })
+            }
+            Some("review") => {
+                args.0.remove(0);
+                let source = match (args.value("--diff")?, args.value("--pr")?) {
+                    (Some(_), Some(_)) => bail!("--diff and --pr cannot be combined"),
+                    (range, None) => ReviewSource::Diff(range),
+                    (None, Some(url)) => ReviewSource::PullRequest(url),
+                };
+                if let Some(extra) = args.positionals()?.first() {
+                    bail!("Unexpected argument: {extra}\n{USAGE}");
+                }
+                CliCommand::Review(source)
+            }
+            Some("explain") => {
+                args.0.remove(0);
+                let mut chat = chat_options(args)?;
+                let Some(target) = chat.resume.take() else {
+                    bail!("explain expects a file or symbol\n{USAGE}");
+                };
+                if chat.session.is_some()
+                    || chat.workflow.is_some()
+                    || chat.tui
+                    || chat.share
+                    || chat.print.is_some()
+                    || chat.output != OutputFormat::Text
+                    || chat.autonomous.is_some()
+                {
+                    bail!(
+                        "explain starts its own chat and cannot be combined with --session, --workflow, --tui, --share, -p, --output or --autonomous"
+                    );
+                }
+                CliCommand::Explain(ExplainOptions { target, chat })
+            }
+            Some("changelog") => {
+                args.0.remove(0);
+                let since = args.value("--since")?;
+                let title = args
+                    .value("--title")?
+                    .unwrap_or_else(|| "Unreleased".to_string());
+                let output = args
+                    .value("--output")?
+                    .map_or_else(|| PathBuf::from("CHANGELOG.md"), PathBuf::from);
+                if let Some(extra) = args.positionals()?.first() {
+                    bail!("Unexpected argument: {extra}\n{USAGE}");
+                }
+                CliCommand::Changelog(ChangelogOptions {
+                    since,
+                    title,
+                    output,
+                })
+            }
+            Some("web") => {
+                args.0.remove(0);
+                let port = args
+                    .value("--port")?
+                    .map(|p| p.parse().map_err(|_| anyhow!("Invalid port: {p}")))
+                    .transpose()?
+                    .unwrap_or(DEFAULT_WEB_PORT);
+                let dry_run = args.flag(&["--dry-run"]);
+                if let Some(extra) = args.positionals()?.first() {
+                    bail!("Unexpected argument: {extra}\n{USAGE}");
+                }
+                CliCommand::Web { port, dry_run }
+            }
+            Some("auth") => {
+                args.0.remove(0);
+                let positionals = args.positionals()?;
+                let command = match positionals.as_slice() {
+                    [action] if action == "login" => AuthCommand::Login,
+                    [action] if action == "logout" => AuthCommand::Logout,
+                    [action] if action == "status" => AuthCommand::Status,
+                    _ => bail!("auth expects login, logout or status\n{USAGE}"),
+                };
+                CliCommand::Auth(command)
+            }
+            Some("new") => {
+                args.0.remove(0);
+                let options = chat_options(args)?;
+                if options.resume.is_some() {
+                    bail!("new starts a fresh chat and takes no chat id\n{USAGE}");
+                }
+                if options.session.is_some() {
+                    bail!("new starts a fresh chat and cannot continue a --session");
+                }
+                CliCommand::Chat(options)
+            }
+            _ => CliCommand::Chat(chat_options(args)?),
+        };
+        Ok(Self {
+            command,
+            account,
+            verbosity,
+            log_file,
+        })
+    }
+}
+
+/// Parses the options of a chat, plus the id of the chat to resume if one is given.
+fn chat_options(mut args: ArgList) -> Result<ChatOptions> {
+    let tui = args.flag(&["--tui"]);
+    let share = args.flag(&["--share"]);
+    let quiet = args.flag(&["--quiet", "-q"]);
+    let dry_run = args.flag(&["--dry-run"]);
+    let no_search = args.flag(&["--no-search"]);
+    let output = match args.value("--output")?.as_deref() {
+        None | Some("text") => OutputFormat::Text,
+        Some("json") => OutputFormat::Json,
+        Some(other) => bail!("Unknown output format: {other} (expected text or json)"),
+    };
+    if tui && (share || output != OutputFormat::Text) {
+        bail!("--tui cannot be combined with --share or --output");
+    }
+    let print = match args.value("--print")? {
+        Some(prompt) => Some(prompt),
+        None => args.value("-p")?,
+    };
+    if print.is_some() && (tui || share || output != OutputFormat::Text) {
+        bail!("-p cannot be combined with --tui, --share or --output");
+    }
+    let workflow = args.value("--workflow")?;
+    let profile = args.value("--profile")?;
+    let session = args.value("--session")?;
+    if let Some(name) = &session {
+        validate_name(name)?;
+    }
+    let format = args
+        .value("--format")?
+        .map(|f| {
+            ResponseFormat::from_name(&f)
+                .ok_or_else(|| anyhow!("Unknown format: {f} (expected json, markdown or plain)"))
+        })
+        .transpose()?;
+    let max_output_tokens = args
+        .value("--max-output-tokens")?
+        .map(|n| match n.parse() {
+            Ok(tokens) if tokens > 0 => Ok(tokens),
+            _ => Err(anyhow!("Invalid --max-output-tokens: {n}")),
+        })
+        .transpose()?;
+    let max_iterations = args
+        .value("--max-iterations")?
+        .map(|n| match n.parse() {
+            Ok(rounds) if rounds > 0 => Ok(rounds),
+            _ => Err(anyhow!("Invalid --max-iterations: {n}")),
+        })
+        .transpose()?;
+    let model = args
+        .value("--model")?
+        .map(|m| {
+            Model::from_name(&m)
+                .ok_or_else(|| anyhow!("Unknown model: {m} (expected {})", Model::names()))
+        })
+        .transpose()?;
+    let mut vars = Vec::new();
+    while let Some(assignment) = args.value("--var")? {
+        vars.push(assignment);
+    }
+    let output_schema = args.value("--output-schema")?.map(PathBuf::from);
+    let log_dir = args.value("--log-dir")?.map(PathBuf::from);
+    let mock = args.value("--mock")?.map(PathBuf::from);
+    let record = args.value("--record")?.map(PathBuf::from);
+    if mock.is_some() && record.is_some() {
+        bail!("--mock replays a fixture and cannot be combined with --record");
+    }
+    if output_schema.is_some() {
+        if print.is_none() {
+            bail!("--output-schema needs a prompt given with -p");
+        }
+        if format.is_some_and(|f| f != ResponseFormat::Json) {
+            bail!("--output-schema always replies in JSON and cannot take another --format");
+        }
+    }
+    let autonomous = args
+        .value("--autonomous")?
+        .map(|d| parse_duration(&d))
+        .transpose()?;
+    let report_every = args
+        .value("--report-every")?
+        .map(|d| parse_duration(&d))
+        .transpose()?;
+    if autonomous.is_some() && (tui || print.is_some() || output != OutputFormat::Text) {
+        bail!(
+            "--autonomous reports progress in the terminal and cannot be combined with --tui, -p or --output"
+        );
+    }
+    if report_every.is_some() && autonomous.is_none() {
+        bail!("--report-every needs --autonomous");
+    }
+    let mut positionals = args.positionals()?;
+    if positionals.len() > 1 {
+        bail!("Unexpected argument: {}\n{USAGE}", positionals[1]);
+    }
+    if workflow.is_some() && !positionals.is_empty() {
+        bail!("--workflow starts a new chat and cannot be combined with a chat id");
+    }
+    if session.is_some() && !positionals.is_empty() {
+        bail!("--session picks the chat itself and cannot be combined with a chat id");
+    }
+    Ok(ChatOptions {
+        resume: positionals.pop(),
+        session,
+        tui,
+        share,
+        quiet,
+        output,
+        workflow,
+        profile,
+        print,
+        format,
+        max_output_tokens,
+        max_iterations,
+        output_schema,
+        model,
+        no_search,
+        vars,
+        autonomous,
+        report_every,
+        log_dir,
+        dry_run,
+        mock,
+        record,
+    })
+}
+
+/// Arguments not yet consumed by the parser.
+struct ArgList(Vec<String>);
+
+impl ArgList {
+    /// Removes a boolean flag, returning whether it was present.
+    fn flag(&mut self, names: &[&str]) -> bool {
+        let Some(i) = self.0.iter().position(|a| names.contains(&a.as_str())) else {
+            return false;
+        };
+        self.0.remove(i);
+        true
+    }
+
+    /// Removes `--name value` or `--name=value`, returning the value.
+    fn value(&mut self, name: &str) -> Result<Option<String>> {
+        let prefix = format!("{name}=");
+        let Some(i) = self
+            .0
+            .iter()
+            .position(|a| a == name || a.starts_with(&prefix))
+        else {
+            return Ok(None);
+        };
+        let arg = self.0.remove(i);
+        if let Some(value) = arg.strip_prefix(&prefix) {
+            return Ok(Some(value.to_string()));
+        }
+        if i < self.0.len() {
+            return Ok(Some(self.0.remove(i)));
+        }
+        bail!("{name} requires a value");
+    }
+
+    /// Returns the remaining arguments, failing on any option nobody consumed.
+    fn positionals(self) -> Result<Vec<String>> {
+        if let Some(option) = self.0.iter().find(|a| a.starts_with("--")) {
+            bail!("Unknown option: {option}\n{USAGE}");
+        }
+        Ok(self.0)
+    }
+}
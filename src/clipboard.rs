@@ -0,0 +1,36 @@
+use anyhow::{Result, bail};
+use std::process::Command;
+
+/// Commands that print the clipboard, tried in order until one works.
+const PASTE_COMMANDS: &[(&str, &[&str])] = &[
+    ("pbpaste", &[]),
+    ("wl-paste", &["--no-newline"]),
+    ("xclip", &["-selection", "clipboard", "-o"]),
+    ("xsel", &["--clipboard", "--output"]),
+    (
+        "powershell",
+        &["-NoProfile", "-Command", "Get-Clipboard -Raw"],
+    ),
+];
+
+/// Reads text from the system clipboard using whichever clipboard tool is installed.
+///
+/// # Errors
+/// Returns an error if no clipboard tool is available or the clipboard holds no text.
+pub fn read() -> Result<String> {
+    for (program, args) in PASTE_COMMANDS {
+        // Not installed, or no clipboard of that kind in this session
+        let Ok(output) = Command::new(program).args(*args).output() else {
+            continue;
+        };
+        if !output.status.success() {
+            continue;
+        }
+        let text = String::from_utf8_lossy(&output.stdout).replace("\r\n", "\n");
+        if text.trim().is_empty() {
+            bail!("The clipboard is empty");
+        }
+        return Ok(text);
+    }
+    bail!("Could not read the clipboard: install wl-clipboard, xclip or xsel")
+}
@@ -0,0 +1,231 @@
+/// A slash command entered at the REPL prompt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SlashCommand {
+    Help,
+    Exit,
+    /// Start a fresh chat in place of the current one.
+    New,
+    /// Upload an image or document to send with the next message.
+    Attach(String),
+    /// Push a file or directory onto the focus stack, or show the stack when empty.
+    Focus(String),
+    Unfocus,
+    /// List known chats and pick one to resume, or resume the given one directly.
+    Chats(String),
+    /// Restrict the agent to read-only tools and ask for a plan.
+    Plan,
+    /// Leave plan mode and carry the plan out.
+    Execute,
+    /// Regenerate the response to the last message and show what changed.
+    Retry,
+    /// Finish the reply that was interrupted with Ctrl+C, from where it stopped.
+    Continue,
+    /// Open the last message in the editor and send it again as edited.
+    EditLast,
+    /// Branch the conversation from an earlier reply, e.g. `/fork 12 tests`, or list the
+    /// replies when empty.
+    Fork(String),
+    /// List the conversation's branches, or switch to the given one.
+    Branches(String),
+    /// Write out the session, e.g. `/export script replay.sh` or `/export html chat.html`.
+    Export(String),
+    /// Show tool and turn statistics, or with `footer`, turn the timing line after each
+    /// turn on or off.
+    Stats(String),
+    /// List, set or unset variables, e.g. `/var set TICKET=ABC-123`.
+    Var(String),
+    /// Switch the model for the following messages, or show the current one.
+    Model(String),
+    /// Change a completion parameter, e.g. `/set search off`, or show them when empty.
+    Set(String),
+    /// Show the estimated tokens used by the session's requests.
+    Usage,
+    /// Toggle hiding the model's thinking.
+    Quiet,
+    /// Send the clipboard contents, after the given text if any.
+    Paste(String),
+    /// Ask for replies in a format, e.g. `/format json`, or show the current one.
+    Format(String),
+    /// Ask for replies in a style, e.g. `/style concise`, or show the current one.
+    Style(String),
+    /// Show, send or clear messages queued while offline.
+    Queue(String),
+    /// Take, list or restore checkpoints of the working tree, e.g. `/checkpoint restore`.
+    Checkpoint(String),
+    /// Have the model write a `DEEPSEEK.md` for the project
+    Init,
+    /// Have the model write a message for the staged changes, or the session's, and commit.
+    Commit,
+    Compact,
+    /// List what the chat's context holds, or with `drop`, carry on in a new chat without
+    /// some of it, e.g. `/context drop 3,5-7`.
+    Context(String),
+    Remember(String),
+    Memory(String),
+    /// Turn dry-run mode on or off, e.g. `/dryrun on`, or show whether it is on.
+    DryRun(String),
+    /// Switch to a profile, e.g. `/profile reviewer`, or list them when empty.
+    Profile(String),
+    /// Switch the active root of a multi-root workspace, or list the roots when empty.
+    Workspace(String),
+    Unknown(String),
+}
+
+/// Summary of the available commands, shown by `/help`.
+pub const HELP: &str = "\
+/help            show this help
+/exit            quit
+/new             start a new chat
+/focus [path]    focus on a file or directory (without a path: show the focus stack)
+/unfocus         pop the most recent focus
+/attach <path>   upload an image or document to send with the next message (also @file.png)
+/chats [n|id]    list recent chats and pick one to resume
+/plan            plan mode: read-only tools, the agent proposes a plan first
+/execute         approve the plan and unlock all tools
+/retry           regenerate the last response and show a word diff against it
+/continue        finish a response interrupted with Ctrl+C from where it stopped
+/edit-last       revise the last message in $EDITOR and send it again in its place
+/fork [id [name]]
+                 branch the conversation from an earlier reply (without an id: list them)
+/branches [n|name]
+                 list the conversation's branches, or switch to one
+/compact         summarize the conversation and carry on from the summary in a new chat
+/context [drop <n,m-k>]
+                 list what the chat's context holds with estimated tokens, or drop items
+                 by moving to a new chat that starts from a summary without them
+/export script|json [path]
+                 export the commands and file edits of this session for replay
+/export md|html [path]
+                 export this session's conversation as a shareable document
+/stats [footer [on|off]]
+                 show estimated tokens injected by each tool and the timing of recent turns,
+                 or turn on a line with the timing after each turn
+/usage           show estimated prompt and completion tokens of this session
+/model [chat|reasoner]
+                 switch the model for the next messages, or show the current one
+/set [search|thinking on|off]
+                 turn web search or thinking on or off for the next messages, or show them
+/var [set NAME=value|unset NAME]
+                 list or change variables; $NAME in a message is replaced by the value
+/quiet           toggle hiding the model's thinking
+/dryrun [on|off] tools that change files or run commands only report what they would do
+/format [json|markdown|plain|off]
+                 ask for replies in a format, or show the current one
+/style [concise|detailed|explain-like-junior|code-only|off]
+                 ask for replies in a style from the next message on, or show the current one
+/paste [text]    send the clipboard contents, after the text if given
+/queue [send|clear]
+                 show, send or drop the messages queued while offline
+/checkpoint [list|restore [id]]
+                 save the working tree (git, jj or a plain snapshot), list the saved
+                 states or put the files back as they were in one (default: the latest)
+/profile [name|off]
+                 switch to a profile (a role with its own instructions and tools), or list them
+/workspace [name]
+                 switch the root relative paths are taken from, or list the roots ([roots]
+                 in the config); name:path is a path in another root
+/init            have the model write a DEEPSEEK.md for the project, then review it
+/commit          commit the staged changes (or, with none, the files changed this session)
+                 with a Conventional Commits message the model writes and you can edit
+/remember <fact> save a fact that every future session is told about
+/memory [list|forget <n>]
+                 list the remembered facts or drop one
+
+End a line with \\ to continue the message on the next line, or put \"\"\" on lines of
+their own before and after a block of text to send it as one message. @path in a message
+attaches the contents of that file.";
+
+/// Names of the commands, for completion at the prompt.
+pub const NAMES: &[&str] = &[
+    "attach",
+    "branches",
+    "chats",
+    "checkpoint",
+    "commit",
+    "compact",
+    "context",
+    "continue",
+    "dryrun",
+    "edit-last",
+    "execute",
+    "exit",
+    "export",
+    "focus",
+    "fork",
+    "format",
+    "help",
+    "init",
+    "memory",
+    "model",
+    "new",
+    "paste",
+    "plan",
+    "profile",
+    "queue",
+    "quiet",
+    "remember",
+    "retry",
+    "set",
+    "stats",
+    "style",
+    "unfocus",
+    "usage",
+    "var",
+    "workspace",
+];
+
+impl SlashCommand {
+    /// Parses a line of user input as a slash command.
+    ///
+    /// Returns `None` when the input is a regular message. Input such as `/usr/bin is missing`
+    /// is treated as a message because the first word looks like a path, not a command name.
+    #[must_use]
+    pub fn parse(input: &str) -> Option<Self> {
+        let rest = input.trim().strip_prefix('/')?;
+        let (name, arg) = rest
+            .split_once(char::is_whitespace)
+            .map_or((rest, ""), |(name, arg)| (name, arg.trim()));
+        if name.is_empty() || name.contains('/') {
+            return None;
+        }
+        let command = match name {
+            "help" => Self::Help,
+            "exit" | "quit" => Self::Exit,
+            "new" => Self::New,
+            "focus" => Self::Focus(arg.to_string()),
+            "unfocus" => Self::Unfocus,
+            "attach" => Self::Attach(arg.to_string()),
+            "chats" => Self::Chats(arg.to_string()),
+            "plan" => Self::Plan,
+            "execute" => Self::Execute,
+            "retry" => Self::Retry,
+            "continue" => Self::Continue,
+            "fork" => Self::Fork(arg.to_string()),
+            "branches" => Self::Branches(arg.to_string()),
+            "edit-last" => Self::EditLast,
+            "export" => Self::Export(arg.to_string()),
+            "stats" => Self::Stats(arg.to_string()),
+            "usage" => Self::Usage,
+            "model" => Self::Model(arg.to_string()),
+            "set" => Self::Set(arg.to_string()),
+            "var" => Self::Var(arg.to_string()),
+            "quiet" => Self::Quiet,
+            "dryrun" => Self::DryRun(arg.to_string()),
+            "paste" => Self::Paste(arg.to_string()),
+            "queue" => Self::Queue(arg.to_string()),
+            "format" => Self::Format(arg.to_string()),
+            "style" => Self::Style(arg.to_string()),
+            "checkpoint" => Self::Checkpoint(arg.to_string()),
+            "init" => Self::Init,
+            "commit" => Self::Commit,
+            "compact" => Self::Compact,
+            "context" => Self::Context(arg.to_string()),
+            "remember" => Self::Remember(arg.to_string()),
+            "memory" => Self::Memory(arg.to_string()),
+            "profile" => Self::Profile(arg.to_string()),
+            "workspace" => Self::Workspace(arg.to_string()),
+            _ => Self::Unknown(name.to_string()),
+        };
+        Some(command)
+    }
+}
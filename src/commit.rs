@@ -0,0 +1,153 @@
+use crate::mentions::fence_for;
+use anyhow::{Result, anyhow, bail};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Longest staged diff sent to the model. A larger one is cut, while `git status` still
+/// lists every file.
+pub const MAX_DIFF_BYTES: usize = 60_000;
+
+/// What `/commit` is about to commit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StagedChanges {
+    /// `git status --short` of the working tree
+    pub status: String,
+    /// `git diff --staged`, cut to [`MAX_DIFF_BYTES`]
+    pub diff: String,
+    pub truncated: bool,
+}
+
+/// Whether `dir` is inside a git working tree.
+#[must_use]
+pub fn is_repository(dir: &Path) -> bool {
+    git(dir, &["rev-parse", "--is-inside-work-tree"]).is_ok_and(|out| out.trim() == "true")
+}
+
+/// The staged changes in the repository at `dir`, or `None` if nothing is staged.
+///
+/// # Errors
+/// Returns an error if git fails.
+pub fn staged(dir: &Path) -> Result<Option<StagedChanges>> {
+    let mut diff = git(dir, &["diff", "--staged"])?;
+    if diff.trim().is_empty() {
+        return Ok(None);
+    }
+    let status = git(dir, &["status", "--short"])?;
+    let truncated = diff.len() > MAX_DIFF_BYTES;
+    if truncated {
+        let mut end = MAX_DIFF_BYTES;
+        while !diff.is_char_boundary(end) {
+            end -= 1;
+        }
+        diff.truncate(end);
+    }
+    Ok(Some(StagedChanges {
+        status,
+        diff,
+        truncated,
+    }))
+}
+
+/// Stages `paths`, including deletions, returning the ones git took. Paths it refuses,
+/// such as ignored files or ones created and deleted again, are left out.
+#[must_use]
+pub fn stage(dir: &Path, paths: &[String]) -> Vec<String> {
+    paths
+        .iter()
+        .filter(|path| git(dir, &["add", "-A", "--", path.as_str()]).is_ok())
+        .cloned()
+        .collect()
+}
+
+/// The message `/commit` sends, asking for a Conventional Commits message for `changes`.
+#[must_use]
+pub fn prompt(changes: &StagedChanges) -> String {
+    let fence = fence_for(&changes.diff);
+    let cut = if changes.truncated {
+        format!("\n(The diff was cut at {MAX_DIFF_BYTES} bytes; the status lists every file.)")
+    } else {
+        String::new()
+    };
+    format!(
+        "Write a commit message for the staged changes below, following Conventional Commits: a subject line of the form `type(scope): summary`, at most 72 characters, where type is one of feat, fix, docs, style, refactor, perf, test, build, ci or chore and the scope is optional; then, if the change needs explaining, a blank line and a short body saying what changed and why, wrapped at 72 columns. Describe only what the diff shows.\n\nDo not call any tools. Reply with only the message in a single ``` code block.\n\ngit status --short:\n```\n{}\n```\n\ngit diff --staged:{cut}\n{fence}diff\n{}\n{fence}",
+        changes.status.trim_end(),
+        changes.diff.trim_end()
+    )
+}
+
+/// Takes the message out of the model's reply to [`prompt`]: the contents of the first
+/// code block, or the whole reply if it has none.
+#[must_use]
+pub fn extract_message(reply: &str) -> String {
+    let mut block: Option<Vec<&str>> = None;
+    for line in reply.lines() {
+        let fence = line.trim_start().starts_with("```");
+        match &mut block {
+            None if fence => block = Some(Vec::new()),
+            None => {}
+            Some(lines) if fence => return lines.join("\n").trim().to_string(),
+            Some(lines) => lines.push(line),
+        }
+    }
+    reply.trim().to_string()
+}
+
+/// Whether `subject` starts like a Conventional Commits subject, `type(scope)!: summary`.
+#[must_use]
+pub fn is_conventional(subject: &str) -> bool {
+    let Some((head, summary)) = subject.split_once(": ") else {
+        return false;
+    };
+    let head = head.strip_suffix('!').unwrap_or(head);
+    let kind = match head.split_once('(') {
+        Some((kind, scope)) if scope.len() > 1 && scope.ends_with(')') => kind,
+        Some(_) => return false,
+        None => head,
+    };
+    !kind.is_empty() && kind.chars().all(|c| c.is_ascii_lowercase()) && !summary.trim().is_empty()
+}
+
+/// Commits what is staged with `message`, returning git's summary of the commit.
+///
+/// # Errors
+/// Returns an error if the message is empty or git fails, with what git said.
+pub fn commit(dir: &Path, message: &str) -> Result<String> {
+    if message.trim().is_empty() {
+        bail!("The commit message is empty");
+    }
+    let mut child = Command::new("git")
+        .args(["commit", "-F", "-"])
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow!("Failed to run git: {e}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(message.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git commit failed: {}", format!("{stderr}{stdout}").trim());
+    }
+    Ok(stdout.lines().next().unwrap_or_default().to_string())
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| anyhow!("Failed to run git: {e}"))?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
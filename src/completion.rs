@@ -0,0 +1,137 @@
+use crate::commands::NAMES;
+use crate::index;
+use rustyline::completion::{Completer, Pair};
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{CompletionType, Config, Context, Editor, Helper};
+use std::path::Path;
+
+/// Fuzzy matches offered for an `@` mention that no path starts with.
+const MAX_FUZZY_CANDIDATES: usize = 10;
+
+/// The line editor used by the REPL.
+pub type ReplEditor = Editor<ReplHelper, DefaultHistory>;
+
+/// Creates the REPL's line editor, with completion of commands and file paths.
+///
+/// # Errors
+/// Returns an error if the terminal cannot be set up.
+pub fn editor() -> rustyline::Result<ReplEditor> {
+    let config = Config::builder()
+        .completion_type(CompletionType::List)
+        .build();
+    let mut editor = Editor::with_config(config)?;
+    editor.set_helper(Some(ReplHelper));
+    Ok(editor)
+}
+
+/// Completes slash-command names, paths after `@`, and the path given to `/focus`.
+pub struct ReplHelper;
+
+impl Completer for ReplHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let (start, candidates) = complete(line, pos);
+        let pairs = candidates
+            .into_iter()
+            .map(|replacement| Pair {
+                display: replacement.clone(),
+                replacement,
+            })
+            .collect();
+        Ok((start, pairs))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+/// Completes the word before `pos`. Returns where the word starts and what it may be
+/// replaced with.
+#[must_use]
+pub fn complete(line: &str, pos: usize) -> (usize, Vec<String>) {
+    let before = &line[..pos];
+    let start = before
+        .char_indices()
+        .rfind(|(_, c)| c.is_whitespace())
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    let word = &before[start..];
+    if let Some(path) = word.strip_prefix('@') {
+        return (start + 1, complete_mention(path));
+    }
+    let leading = before.trim_start();
+    if let Some(name) = word.strip_prefix('/')
+        && start == before.len() - leading.len()
+    {
+        let names = NAMES
+            .iter()
+            .filter(|n| n.starts_with(name))
+            .map(|n| format!("/{n}"))
+            .collect();
+        return (start, names);
+    }
+    if let Some(rest) = leading.strip_prefix("/focus")
+        && rest.starts_with(char::is_whitespace)
+        && rest.trim_start() == word
+    {
+        return (start, complete_path(word));
+    }
+    (pos, Vec::new())
+}
+
+/// Completes an `@` mention: paths starting with `partial`, or failing that, the files of
+/// the project index that fuzzily match it.
+fn complete_mention(partial: &str) -> Vec<String> {
+    let candidates = complete_path(partial);
+    if !candidates.is_empty() || partial.is_empty() {
+        return candidates;
+    }
+    index::current().map_or_else(Vec::new, |index| {
+        index
+            .find(partial, MAX_FUZZY_CANDIDATES)
+            .into_iter()
+            .map(str::to_string)
+            .collect()
+    })
+}
+
+/// Files and directories whose path starts with `partial`, relative to the working
+/// directory. Directories end in `/`. Hidden entries are only offered once a `.` is typed.
+fn complete_path(partial: &str) -> Vec<String> {
+    let (dir, prefix) = match partial.rfind('/') {
+        Some(i) => (&partial[..=i], &partial[i + 1..]),
+        None => ("", partial),
+    };
+    let search = if dir.is_empty() { "." } else { dir };
+    let Ok(entries) = std::fs::read_dir(Path::new(search)) else {
+        return Vec::new();
+    };
+    let mut candidates: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+            let slash = if entry.path().is_dir() { "/" } else { "" };
+            Some(format!("{dir}{name}{slash}"))
+        })
+        .collect();
+    candidates.sort();
+    candidates
+}
@@ -0,0 +1,550 @@
+use crate::model::Model;
+use anyhow::{Result, anyhow};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::time::Duration;
+
+/// Where a project's own config lives, relative to its root.
+pub const PROJECT_FILE: &str = ".deepseek/config.toml";
+
+/// User configuration, read from `~/.config/deepseek-cli/config.toml` and overridden by the
+/// `.deepseek/config.toml` of the project the working directory is in.
+///
+/// Every field is optional; missing files result in the defaults.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Model used when `--model` is not given: `chat` or `reasoner`.
+    pub model: Option<String>,
+    /// Let the model search the web while it answers; on when unset. `--no-search` and
+    /// `/set search off` turn it off.
+    pub search: Option<bool>,
+    /// The only tools the model may use; all of them when unset.
+    pub tools: Option<Vec<String>>,
+    /// Tools that run without asking for approval.
+    pub auto_approve: Vec<String>,
+    /// Rules for tool calls that need approval, under `[[policy]]`. The first that matches
+    /// a call decides whether it runs without asking, is always asked about or is refused;
+    /// calls no rule matches are asked about as usual.
+    pub policy: Vec<PolicyRule>,
+    /// Directory that deleting and moving files is confined to; the working directory when
+    /// unset.
+    pub sandbox_root: Option<PathBuf>,
+    /// Files attached at the start of every session, as with `/focus`.
+    pub context: Vec<PathBuf>,
+    /// Roots of a workspace that spans several directories, such as
+    /// `frontend = "../web"`, under `[roots]`. Tools take relative paths from the one the
+    /// working directory is in, or the one `/workspace` switched to, and paths in the
+    /// others as `<name>:<path>`.
+    pub roots: BTreeMap<String, PathBuf>,
+    /// Shell used by `run_command` (e.g. `pwsh`, `bash`). Detected automatically when unset.
+    pub shell: Option<String>,
+    /// Retrying of requests that fail, under `[retry]`.
+    pub retry: RetryConfig,
+    /// Templates for `deepseek new --workflow <name>`, under `[workflows.<name>]`.
+    pub workflows: BTreeMap<String, Workflow>,
+    /// Profiles for `--profile <name>` and `/profile`, under `[profiles.<name>]`. These add
+    /// to the built-in `coder`, `reviewer` and `sysadmin`, or replace them when named the
+    /// same.
+    pub profiles: BTreeMap<String, Profile>,
+    /// Profile used when `--profile` is not given; none when unset.
+    pub profile: Option<String>,
+    /// Credentials for `--account <name>`, such as `work` and `personal`, under
+    /// `[accounts.<name>]`.
+    pub accounts: BTreeMap<String, Account>,
+    /// Account used when `--account` is not given; the token lookup without an account
+    /// when unset.
+    pub account: Option<String>,
+    /// Runs started with `--autonomous`, under `[autonomous]`.
+    pub autonomous: AutonomousConfig,
+    /// Rounds of tool calls one message may take before asking whether to go on; 25 when
+    /// unset.
+    pub max_tool_iterations: Option<u32>,
+    /// Share of the context window, in percent, at which a chat is compacted into a new one
+    /// before the next message; 80 when unset, 0 to only compact with `/compact`.
+    pub compact_at_percent: Option<u8>,
+    /// Print how long each turn took and the tokens it used after it, as `/stats footer on`
+    /// does.
+    pub turn_footer: bool,
+    /// Describe the OS, shell, working directory, git status and toolchain versions in
+    /// the first message of each chat; on when unset.
+    pub environment: Option<bool>,
+    /// Links on file paths and URLs in the output, under `[hyperlinks]`.
+    pub hyperlinks: HyperlinkConfig,
+    /// Providers for the `search_web` tool, under `[search]`.
+    pub search: SearchConfig,
+    /// Caching of `search_web` and `fetch_url` results, under `[cache]`.
+    pub cache: CacheConfig,
+    /// Proxy and API endpoint, under `[network]`.
+    pub network: NetworkConfig,
+    /// Access to GitHub for the `gh_*` tools and `deepseek review --pr`, under `[github]`.
+    pub github: GithubConfig,
+    /// External tools, under `[plugins.<name>]`. Executables named `deepseek-tool-<name>`
+    /// on `PATH` are tools without being declared; declaring one describes it.
+    pub plugins: BTreeMap<String, PluginConfig>,
+    /// The terminal bell or a desktop notification when a long turn ends or a tool call
+    /// waits for approval, under `[notify]`.
+    pub notify: NotifyConfig,
+    /// Root of the project whose config was merged in, against which its relative paths
+    /// are resolved.
+    #[serde(skip)]
+    pub project_root: Option<PathBuf>,
+}
+
+/// A rule of the approval policy, e.g. to run `cargo test` without asking:
+///
+/// ```toml
+/// [[policy]]
+/// tool = "run_command"
+/// matches = '^cargo (build|check|test)\b'
+/// action = "allow"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct PolicyRule {
+    /// The tool the rule is for; every tool when unset.
+    pub tool: Option<String>,
+    /// Regular expression the call's argument (for `run_command`, the command) must match.
+    pub matches: Option<String>,
+    /// Glob the path the call acts on must match, such as `src/**` or `*.lock`.
+    pub path: Option<String>,
+    pub action: PolicyAction,
+    /// Why the rule is there, shown when it refuses or asks about a call.
+    pub reason: Option<String>,
+}
+
+/// What a policy rule does with the calls it matches.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyAction {
+    /// Run without asking.
+    Allow,
+    /// Ask even when calls would otherwise run without asking, as in autonomous runs or
+    /// after answering "all".
+    #[default]
+    Ask,
+    /// Refuse without asking.
+    Deny,
+}
+
+/// Settings for reaching the network.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct NetworkConfig {
+    /// Proxy for every request, such as `http://proxy.corp:3128` or
+    /// `socks5h://127.0.0.1:1080`, in place of `HTTPS_PROXY` and the like.
+    pub proxy: Option<String>,
+    /// Base URL of the API, for a self-hosted gateway in front of it. The chat client has
+    /// its endpoint built in, so this applies to the requests the CLI makes itself, such
+    /// as `auth status`.
+    pub base_url: Option<String>,
+}
+
+/// Settings for GitHub's REST API.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct GithubConfig {
+    /// Token to authenticate with. `token_env` keeps it out of the file.
+    pub token: Option<String>,
+    /// Environment variable holding the token; `GITHUB_TOKEN` is read when neither this
+    /// nor `token` is set.
+    pub token_env: Option<String>,
+    /// API root of a GitHub Enterprise server, such as `https://github.example.com/api/v3`.
+    pub api_url: Option<String>,
+}
+
+/// An external tool, e.g. one that looks up tickets:
+///
+/// ```toml
+/// [plugins.jira]
+/// command = "scripts/jira-lookup"
+/// description = "prints the Jira ticket with the given key, such as PROJ-123"
+/// read_only = true
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct PluginConfig {
+    /// Program to run, looked up on `PATH` if it is a bare name and resolved against the
+    /// project root if it is a path; `deepseek-tool-<name>` on `PATH` when unset.
+    pub command: Option<PathBuf>,
+    /// What the tool does and what input it takes, for the model.
+    pub description: Option<String>,
+    /// Whether the tool only looks things up, so that it may be used in plan mode and
+    /// without approval.
+    pub read_only: bool,
+    /// Seconds the program may run before it is stopped; 60 when unset.
+    pub timeout_secs: Option<u64>,
+}
+
+/// Settings for the cache of web results.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    pub enabled: bool,
+    /// How long results are reused for; 0 turns the cache off.
+    pub ttl_secs: u64,
+    /// Results kept before the oldest are dropped.
+    pub max_entries: usize,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ttl_secs: 3600,
+            max_entries: 500,
+        }
+    }
+}
+
+/// Settings for web searches.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct SearchConfig {
+    /// Providers to try in order until one finds something: `brave`, `searxng`,
+    /// `duckduckgo-lite` and `duckduckgo`. When unset, the configured ones of the first two
+    /// and then DuckDuckGo.
+    pub providers: Option<Vec<String>>,
+    /// Base URL of a SearXNG instance, e.g. `https://searx.example.org`.
+    pub searxng_url: Option<String>,
+    /// Key for the Brave Search API.
+    pub brave_api_key: Option<String>,
+}
+
+/// Settings for notifications, e.g. to be told when a reply that took over a minute is
+/// ready:
+///
+/// ```toml
+/// [notify]
+/// desktop = true
+/// after_secs = 60
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct NotifyConfig {
+    /// Ring the terminal bell.
+    pub bell: bool,
+    /// Show a desktop notification.
+    pub desktop: bool,
+    /// Seconds a turn must take for its end to be notified; 30 when unset. Approvals are
+    /// notified however soon they come.
+    pub after_secs: Option<u64>,
+}
+
+/// Settings for terminal hyperlinks.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct HyperlinkConfig {
+    /// Whether to emit them; by default when stdout is a terminal that takes colors.
+    pub enabled: Option<bool>,
+    /// Link for files, with `{path}` and `{line}` filled in, e.g.
+    /// `vscode://file{path}:{line}` to open them in an editor. `file://` links when unset.
+    pub file_url: Option<String>,
+}
+
+/// Settings for `--autonomous`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct AutonomousConfig {
+    /// The tools a run may use without approval; the file tools when unset.
+    pub tools: Option<Vec<String>>,
+}
+
+/// A template that seeds a new chat for a recurring task, such as triaging a bug.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct Workflow {
+    /// Instructions added to the end of the system prompt.
+    pub system_prompt: Option<String>,
+    /// Files pinned at the start, as with `/focus`.
+    pub pin: Vec<String>,
+    /// The only tools the model may use; all of them when unset.
+    pub tools: Option<Vec<String>>,
+    /// Sent as the first message.
+    pub instruction: Option<String>,
+}
+
+/// Where the token of one of several accounts is found. The OS keyring entry that
+/// `deepseek auth login --account <name>` stores is checked after `token_env`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct Account {
+    /// Environment variable holding the token.
+    pub token_env: Option<String>,
+    /// Plaintext file holding the token.
+    pub token_file: Option<PathBuf>,
+}
+
+/// A standing role for the assistant, such as reviewing code rather than writing it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    /// Replaces the opening of the system prompt that says what the assistant is; the
+    /// instructions for calling tools always follow it.
+    pub system_prompt: Option<String>,
+    /// Added to the end of the system prompt.
+    pub instructions: Option<String>,
+    /// The only tools the model may use; all of them when unset.
+    pub tools: Option<Vec<String>>,
+}
+
+/// The profiles available without configuring any.
+fn builtin_profiles() -> BTreeMap<String, Profile> {
+    let tools = |names: &[&str]| Some(names.iter().map(|n| (*n).to_string()).collect());
+    BTreeMap::from([
+        (
+            "coder".to_string(),
+            Profile {
+                system_prompt: Some("You are a software engineer working in the user's project, using tools to read and change its code.".to_string()),
+                instructions: Some("Read the relevant code before changing it, keep changes small and in the style of the code around them, and run the build or tests after making them.".to_string()),
+                tools: None,
+            },
+        ),
+        (
+            "reviewer".to_string(),
+            Profile {
+                system_prompt: Some("You are a careful code reviewer, using tools to read the user's project.".to_string()),
+                instructions: Some("Do not change any files. Report bugs, risky changes and unclear code, most serious first, each with its file and line and a suggested fix.".to_string()),
+                tools: tools(&[
+                    "list_files",
+                    "find_file",
+                    "read_file",
+                    "outline",
+                    "grep",
+                    "run_command",
+                    "search_web",
+                    "fetch_url",
+                    "gh_issue_view",
+                    "gh_pr_view",
+                    "ask_user",
+                ]),
+            },
+        ),
+        (
+            "sysadmin".to_string(),
+            Profile {
+                system_prompt: Some("You are a system administrator, using tools to inspect and maintain the user's machine.".to_string()),
+                instructions: Some("Check the state of the system before changing it, prefer changes that can be undone, say what each command does, and do not run destructive commands unless the user asks for them.".to_string()),
+                tools: tools(&[
+                    "list_files",
+                    "find_file",
+                    "read_file",
+                    "grep",
+                    "run_command",
+                    "write_file",
+                    "edit_lines",
+                    "apply_search_replace",
+                    "create_directory",
+                    "delete_file",
+                    "move_file",
+                    "search_web",
+                    "fetch_url",
+                    "ask_user",
+                    "remember",
+                ]),
+            },
+        ),
+    ])
+}
+
+/// How requests that fail with a network or API error are retried.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RetryConfig {
+    /// Retries after the first failure; 0 disables retrying.
+    pub attempts: u32,
+    /// Delay before the first retry, doubled for each retry after it.
+    pub initial_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            attempts: 3,
+            initial_delay_ms: 1000,
+            max_delay_ms: 30_000,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Delay before retry number `attempt`, counting from 1, before any jitter is added.
+    #[must_use]
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u64.saturating_pow(attempt.saturating_sub(1));
+        Duration::from_millis(
+            self.initial_delay_ms
+                .saturating_mul(factor)
+                .min(self.max_delay_ms),
+        )
+    }
+}
+
+static CONFIG: LazyLock<Config> = LazyLock::new(|| {
+    Config::load().unwrap_or_else(|e| {
+        eprintln!("Failed to load config, using defaults: {e}");
+        Config::default()
+    })
+});
+
+/// Returns the configuration, loading it on first use.
+#[must_use]
+pub fn get() -> &'static Config {
+    &CONFIG
+}
+
+impl Config {
+    /// Location of the global config file, if a config directory exists on this platform.
+    #[must_use]
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|d| d.join("deepseek-cli/config.toml"))
+    }
+
+    /// Looks up a workflow by name.
+    ///
+    /// # Errors
+    /// Returns an error naming the defined workflows if there is none called `name`.
+    pub fn workflow(&self, name: &str) -> Result<&Workflow> {
+        self.workflows.get(name).ok_or_else(|| {
+            if self.workflows.is_empty() {
+                anyhow!("Unknown workflow: {name} (none are defined; add [workflows.{name}] to the config)")
+            } else {
+                let names: Vec<&str> = self.workflows.keys().map(String::as_str).collect();
+                anyhow!("Unknown workflow: {name} (available: {})", names.join(", "))
+            }
+        })
+    }
+
+    /// Looks up an account by name.
+    ///
+    /// # Errors
+    /// Returns an error naming the defined accounts if there is none called `name`.
+    pub fn account(&self, name: &str) -> Result<&Account> {
+        self.accounts.get(name).ok_or_else(|| {
+            if self.accounts.is_empty() {
+                anyhow!("Unknown account: {name} (none are defined; add [accounts.{name}] to the config)")
+            } else {
+                let names: Vec<&str> = self.accounts.keys().map(String::as_str).collect();
+                anyhow!("Unknown account: {name} (available: {})", names.join(", "))
+            }
+        })
+    }
+
+    /// Looks up a profile by name among the configured and built-in ones.
+    ///
+    /// # Errors
+    /// Returns an error naming the available profiles if there is none called `name`.
+    pub fn profile(&self, name: &str) -> Result<Profile> {
+        if let Some(profile) = self.profiles.get(name) {
+            return Ok(profile.clone());
+        }
+        builtin_profiles().remove(name).ok_or_else(|| {
+            anyhow!(
+                "Unknown profile: {name} (available: {})",
+                self.profile_names().join(", ")
+            )
+        })
+    }
+
+    /// The names of the configured and built-in profiles, sorted.
+    #[must_use]
+    pub fn profile_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = builtin_profiles().into_keys().collect();
+        names.extend(self.profiles.keys().cloned());
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Parses the global config with a project's config laid over it: tables are merged
+    /// key by key, and any other value in the project config replaces the global one.
+    ///
+    /// # Errors
+    /// Returns an error if either is not valid TOML or does not describe a config.
+    pub fn layered(global: &str, project: &str) -> Result<Self> {
+        let mut table: toml::Table = toml::from_str(global)?;
+        merge(&mut table, toml::from_str(project)?);
+        Ok(toml::Value::Table(table).try_into()?)
+    }
+
+    /// The model named by `model`, if it is set.
+    ///
+    /// # Errors
+    /// Returns an error if it names no known model.
+    pub fn default_model(&self) -> Result<Option<Model>> {
+        self.model
+            .as_deref()
+            .map(|name| {
+                Model::from_name(name).ok_or_else(|| {
+                    anyhow!(
+                        "Unknown model in the config: {name} (expected {})",
+                        Model::names()
+                    )
+                })
+            })
+            .transpose()
+    }
+
+    /// Whether `tools` leaves the named tool available.
+    #[must_use]
+    pub fn allows_tool(&self, name: &str) -> bool {
+        self.tools
+            .as_ref()
+            .is_none_or(|tools| tools.iter().any(|t| t == name))
+    }
+
+    /// Resolves a path from the config against the project root, or the working directory
+    /// outside a project.
+    #[must_use]
+    pub fn resolve(&self, path: &Path) -> PathBuf {
+        match &self.project_root {
+            Some(root) => root.join(path),
+            None => path.to_path_buf(),
+        }
+    }
+
+    fn load() -> Result<Self> {
+        let mut table = match Self::path().filter(|p| p.exists()) {
+            Some(path) => read_table(&path)?,
+            None => toml::Table::new(),
+        };
+        let project = std::env::current_dir()
+            .ok()
+            .and_then(|dir| find_project_config(&dir));
+        if let Some(path) = &project {
+            merge(&mut table, read_table(path)?);
+        }
+        let mut config: Self = toml::Value::Table(table)
+            .try_into()
+            .map_err(|e| anyhow!("Invalid config: {e}"))?;
+        config.project_root = project.and_then(|p| Some(p.parent()?.parent()?.to_path_buf()));
+        Ok(config)
+    }
+}
+
+/// The `.deepseek/config.toml` in `dir` or the nearest of its ancestors that has one.
+#[must_use]
+pub fn find_project_config(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(PROJECT_FILE))
+        .find(|path| path.is_file())
+}
+
+fn read_table(path: &Path) -> Result<toml::Table> {
+    let content = std::fs::read_to_string(path)?;
+    toml::from_str(&content).map_err(|e| anyhow!("Invalid config {}: {e}", path.display()))
+}
+
+fn merge(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overlay)) => merge(base, overlay),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use crate::stats::format_tokens;
+use anyhow::{Result, anyhow, bail};
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+/// Characters of a message or argument kept in its label.
+const LABEL_MAX_CHARS: usize = 60;
+
+/// Where an item in a chat's context came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// The role, tool descriptions and other standing instructions
+    SystemPrompt,
+    /// The `DEEPSEEK.md` files
+    Instructions,
+    /// Remembered facts
+    Memory,
+    /// The environment snapshot
+    Environment,
+    /// A file pinned with `/focus`, whose edits are sent as they happen
+    Pinned(PathBuf),
+    /// A file uploaded with `/attach` or an `@` mention
+    Attachment,
+    ToolResult,
+    Message,
+    Reply,
+    /// The summary a compacted chat was carried on from
+    Summary,
+}
+
+impl Source {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::SystemPrompt => "system prompt",
+            Self::Instructions => "instructions",
+            Self::Memory => "memory",
+            Self::Environment => "environment",
+            Self::Pinned(_) => "pinned file",
+            Self::Attachment => "attachment",
+            Self::ToolResult => "tool result",
+            Self::Message => "message",
+            Self::Reply => "reply",
+            Self::Summary => "summary",
+        }
+    }
+}
+
+/// A part of the first message that can be left out of the chats started after
+/// `/context drop`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Section {
+    Instructions,
+    Memory,
+    Environment,
+}
+
+/// One thing the chat's context holds, with its estimated size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextItem {
+    pub source: Source,
+    pub label: String,
+    pub tokens: usize,
+}
+
+/// What `/context drop` does with the items it was given: the sections to leave out of
+/// the next first message, the pinned files to stop watching, and the rest, which the
+/// summary the chat moves on from is asked to leave out.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct DropPlan {
+    pub sections: Vec<Section>,
+    pub unpin: Vec<PathBuf>,
+    pub forget: Vec<String>,
+}
+
+/// The items that make up the context of the current chat, shown by `/context`. The API
+/// keeps the chat itself, so this is an account of what was sent and received, with
+/// token counts estimated from the text.
+#[derive(Debug, Default)]
+pub struct ContextLedger {
+    items: Vec<ContextItem>,
+    /// Left out of the first message of chats from here on
+    omitted: BTreeSet<Section>,
+}
+
+impl ContextLedger {
+    pub fn record(&mut self, source: Source, label: impl Into<String>, tokens: usize) {
+        self.items.push(ContextItem {
+            source,
+            label: label.into(),
+            tokens,
+        });
+    }
+
+    /// Starts the account of a new chat; the sections left out stay left out.
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    #[must_use]
+    pub fn items(&self) -> &[ContextItem] {
+        &self.items
+    }
+
+    #[must_use]
+    pub fn total_tokens(&self) -> usize {
+        self.items.iter().map(|item| item.tokens).sum()
+    }
+
+    /// Whether the first message of the next chat should leave `section` out.
+    #[must_use]
+    pub fn omits(&self, section: Section) -> bool {
+        self.omitted.contains(&section)
+    }
+
+    pub fn omit(&mut self, sections: &[Section]) {
+        self.omitted.extend(sections);
+    }
+
+    /// Works out what dropping the items numbered `numbers`, counting from 1, takes.
+    ///
+    /// # Errors
+    /// Returns an error if a number is not an item or names the system prompt, which
+    /// every chat needs.
+    pub fn plan_drop(&self, numbers: &[usize]) -> Result<DropPlan> {
+        let mut plan = DropPlan::default();
+        for &number in numbers {
+            let item = number
+                .checked_sub(1)
+                .and_then(|i| self.items.get(i))
+                .ok_or_else(|| anyhow!("There is no item {number}; /context lists them"))?;
+            match &item.source {
+                Source::SystemPrompt => {
+                    bail!("The system prompt (item {number}) cannot be dropped")
+                }
+                Source::Instructions => plan.sections.push(Section::Instructions),
+                Source::Memory => plan.sections.push(Section::Memory),
+                Source::Environment => plan.sections.push(Section::Environment),
+                Source::Pinned(path) => plan.unpin.push(path.clone()),
+                _ => plan
+                    .forget
+                    .push(format!("the {} {}", item.source.kind(), item.label)),
+            }
+        }
+        Ok(plan)
+    }
+
+    /// The items as `/context` lists them, numbered for `/context drop`.
+    #[must_use]
+    pub fn render(&self) -> String {
+        if self.items.is_empty() {
+            return "Nothing has been sent in this chat yet".to_string();
+        }
+        let mut out = format!(
+            "~{} tokens in the context of this chat:",
+            format_tokens(self.total_tokens())
+        );
+        let width = self.items.len().to_string().len();
+        for (i, item) in self.items.iter().enumerate() {
+            let _ = write!(
+                out,
+                "\n{:>width$}. {:<13} ~{:>6}  {}",
+                i + 1,
+                item.source.kind(),
+                format_tokens(item.tokens),
+                item.label
+            );
+        }
+        out.push_str(
+            "\n\n/context drop <numbers>, such as 3,5-7, moves to a new chat that starts from a summary without those items.",
+        );
+        out
+    }
+}
+
+/// Parses the item numbers given to `/context drop`, such as `2`, `3,5` or `4-6`, in
+/// order and without repeats.
+///
+/// # Errors
+/// Returns an error if a number or range is malformed.
+pub fn parse_selection(selection: &str) -> Result<Vec<usize>> {
+    let number = |text: &str| {
+        text.trim()
+            .parse::<usize>()
+            .map_err(|_| anyhow!("Invalid item number: {}", text.trim()))
+    };
+    let mut numbers = BTreeSet::new();
+    for part in selection.split([',', ' ']).filter(|p| !p.trim().is_empty()) {
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (number(first)?, number(last)?);
+                if first > last {
+                    bail!("Invalid range: {part}");
+                }
+                numbers.extend(first..=last);
+            }
+            None => {
+                numbers.insert(number(part)?);
+            }
+        }
+    }
+    if numbers.is_empty() {
+        bail!("Give the numbers of the items to drop, such as /context drop 3,5-7");
+    }
+    Ok(numbers.into_iter().collect())
+}
+
+/// A short label for a message, reply or tool argument: its first line, cut at 60
+/// characters.
+#[must_use]
+pub fn label(text: &str) -> String {
+    let line = text.trim().lines().next().unwrap_or_default().trim();
+    if line.chars().count() > LABEL_MAX_CHARS {
+        let cut: String = line.chars().take(LABEL_MAX_CHARS).collect();
+        format!("{cut}…")
+    } else {
+        line.to_string()
+    }
+}
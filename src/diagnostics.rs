@@ -0,0 +1,200 @@
+use serde::Deserialize;
+use std::fmt::Write;
+
+/// The cargo subcommands that take `--message-format json`.
+const JSON_SUBCOMMANDS: [&str; 8] = ["build", "b", "check", "c", "clippy", "test", "t", "rustc"];
+
+/// A compiler message read from `cargo --message-format json`, cut down to what is needed
+/// to fix it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// `error` or `warning`
+    pub level: String,
+    /// Such as `E0308`, or a lint name such as `unused_variables`
+    pub code: Option<String>,
+    pub message: String,
+    /// Where the primary span starts
+    pub location: Option<Location>,
+    /// The label of the primary span, such as ``expected `u32`, found `&str` ``
+    pub label: Option<String>,
+    /// The notes and help attached to it, one line each
+    pub notes: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Diagnostic {
+    #[must_use]
+    pub fn is_error(&self) -> bool {
+        self.level.starts_with("error")
+    }
+
+    /// The diagnostic on one line, such as `src/main.rs:10:5: error[E0308]: mismatched
+    /// types: expected ...`, followed by its notes, indented.
+    #[must_use]
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        if let Some(location) = &self.location {
+            let _ = write!(
+                out,
+                "{}:{}:{}: ",
+                location.file, location.line, location.column
+            );
+        }
+        out.push_str(&self.level);
+        if let Some(code) = &self.code {
+            let _ = write!(out, "[{code}]");
+        }
+        let _ = write!(out, ": {}", self.message);
+        if let Some(label) = &self.label {
+            let _ = write!(out, ": {label}");
+        }
+        for note in &self.notes {
+            let _ = write!(out, "\n  {note}");
+        }
+        out
+    }
+}
+
+#[derive(Deserialize)]
+struct CargoMessage {
+    reason: String,
+    message: Option<RustcMessage>,
+}
+
+#[derive(Deserialize)]
+struct RustcMessage {
+    message: String,
+    level: String,
+    code: Option<RustcCode>,
+    #[serde(default)]
+    spans: Vec<RustcSpan>,
+    #[serde(default)]
+    children: Vec<RustcMessage>,
+}
+
+#[derive(Deserialize)]
+struct RustcCode {
+    code: String,
+}
+
+#[derive(Deserialize)]
+struct RustcSpan {
+    file_name: String,
+    line_start: usize,
+    column_start: usize,
+    is_primary: bool,
+    label: Option<String>,
+    suggested_replacement: Option<String>,
+}
+
+impl RustcMessage {
+    fn primary_span(&self) -> Option<&RustcSpan> {
+        self.spans.iter().find(|span| span.is_primary)
+    }
+
+    /// The totals rustc adds at the end, such as `aborting due to 2 previous errors`.
+    fn is_summary(&self) -> bool {
+        self.level == "failure-note"
+            || (self.spans.is_empty()
+                && (self.message.starts_with("aborting due to")
+                    || self.message.ends_with("warnings emitted")
+                    || self.message.ends_with("warning emitted")))
+    }
+
+    fn note(&self) -> String {
+        let mut note = format!("{}: {}", self.level, self.message);
+        if let Some(span) = self.primary_span() {
+            match &span.suggested_replacement {
+                Some(replacement) => {
+                    let _ = write!(
+                        note,
+                        " ({}:{}:{}: `{replacement}`)",
+                        span.file_name, span.line_start, span.column_start
+                    );
+                }
+                None => {
+                    let _ = write!(
+                        note,
+                        " ({}:{}:{})",
+                        span.file_name, span.line_start, span.column_start
+                    );
+                }
+            }
+        }
+        note
+    }
+}
+
+/// The compiler messages in the output of a cargo command run with
+/// `--message-format json`, each once: cargo repeats them for every target that builds
+/// the same file. Lines that are not JSON messages are skipped.
+#[must_use]
+pub fn parse_cargo(output: &str) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    for line in output.lines().filter(|line| line.starts_with('{')) {
+        let Ok(CargoMessage {
+            reason,
+            message: Some(message),
+        }) = serde_json::from_str(line)
+        else {
+            continue;
+        };
+        if reason != "compiler-message" || message.is_summary() {
+            continue;
+        }
+        let span = message.primary_span();
+        let diagnostic = Diagnostic {
+            location: span.map(|span| Location {
+                file: span.file_name.clone(),
+                line: span.line_start,
+                column: span.column_start,
+            }),
+            label: span.and_then(|span| span.label.clone()),
+            notes: message.children.iter().map(RustcMessage::note).collect(),
+            code: message.code.map(|code| code.code),
+            level: message.level,
+            message: message.message,
+        };
+        if !diagnostics.contains(&diagnostic) {
+            diagnostics.push(diagnostic);
+        }
+    }
+    diagnostics
+}
+
+/// `command` with `--message-format json` added, if it is a cargo command that takes the
+/// option and does not give it already; `None` for any other command.
+#[must_use]
+pub fn json_command(command: &str) -> Option<String> {
+    let mut words = command.split_whitespace();
+    if words.next() != Some("cargo")
+        || !words
+            .next()
+            .is_some_and(|sub| JSON_SUBCOMMANDS.contains(&sub))
+        || command.contains("--message-format")
+    {
+        return None;
+    }
+    // Options after `--` go to the compiler or the test binary, not to cargo
+    Some(match command.find(" -- ") {
+        Some(i) => format!("{} --message-format json{}", &command[..i], &command[i..]),
+        None => format!("{} --message-format json", command.trim_end()),
+    })
+}
+
+/// `output` without the JSON message lines, leaving what cargo writes for people, such as
+/// its own errors about the manifest or a failed build script.
+#[must_use]
+pub fn without_messages(output: &str) -> String {
+    output
+        .lines()
+        .filter(|line| !line.starts_with('{'))
+        .map(|line| format!("{line}\n"))
+        .collect()
+}
@@ -0,0 +1,165 @@
+use colored::Colorize;
+
+/// Diffs larger than this many LCS table cells are not computed.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// One word of a word-level diff. Line breaks are represented by `"\n"` words.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordChange<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+fn tokenize(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        if i > 0 {
+            tokens.push("\n");
+        }
+        tokens.extend(line.split_whitespace());
+    }
+    tokens
+}
+
+/// Computes a word-level diff between two texts using a longest common subsequence.
+///
+/// Returns `None` if the texts are too long to diff in reasonable time and memory.
+#[must_use]
+pub fn word_diff<'a>(old: &'a str, new: &'a str) -> Option<Vec<WordChange<'a>>> {
+    diff_tokens(&tokenize(old), &tokenize(new))
+}
+
+fn diff_tokens<'a>(a: &[&'a str], b: &[&'a str]) -> Option<Vec<WordChange<'a>>> {
+    let (n, m) = (a.len(), b.len());
+    if (n + 1).saturating_mul(m + 1) > MAX_DIFF_CELLS {
+        return None;
+    }
+
+    // lcs[i][j] is the LCS length of a[i..] and b[j..], stored row-major
+    let idx = |i: usize, j: usize| i * (m + 1) + j;
+    let mut lcs = vec![0u32; (n + 1) * (m + 1)];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[idx(i, j)] = if a[i] == b[j] {
+                lcs[idx(i + 1, j + 1)] + 1
+            } else {
+                lcs[idx(i + 1, j)].max(lcs[idx(i, j + 1)])
+            };
+        }
+    }
+
+    let mut changes = Vec::with_capacity(n.max(m));
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if a[i] == b[j] {
+            changes.push(WordChange::Same(a[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[idx(i + 1, j)] >= lcs[idx(i, j + 1)] {
+            changes.push(WordChange::Removed(a[i]));
+            i += 1;
+        } else {
+            changes.push(WordChange::Added(b[j]));
+            j += 1;
+        }
+    }
+    changes.extend(a[i..].iter().copied().map(WordChange::Removed));
+    changes.extend(b[j..].iter().copied().map(WordChange::Added));
+    Some(changes)
+}
+
+/// Computes a line diff in unified format, with `context` unchanged lines around each
+/// change. Returns an empty string when the texts have the same lines.
+///
+/// Returns `None` if the texts are too long to diff in reasonable time and memory.
+#[must_use]
+pub fn unified_diff(old: &str, new: &str, context: usize) -> Option<String> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let changes = diff_tokens(&a, &b)?;
+
+    // Line numbers in the old and new text at which each change starts
+    let mut positions = Vec::with_capacity(changes.len());
+    let (mut i, mut j) = (1, 1);
+    for change in &changes {
+        positions.push((i, j));
+        match change {
+            WordChange::Same(_) => {
+                i += 1;
+                j += 1;
+            }
+            WordChange::Removed(_) => i += 1,
+            WordChange::Added(_) => j += 1,
+        }
+    }
+    let changed: Vec<usize> = changes
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| !matches!(c, WordChange::Same(_)))
+        .map(|(k, _)| k)
+        .collect();
+
+    let mut out = String::new();
+    let mut k = 0;
+    while k < changed.len() {
+        // Changes separated by at most twice the context share a hunk
+        let mut last = k;
+        while last + 1 < changed.len() && changed[last + 1] - changed[last] <= 2 * context + 1 {
+            last += 1;
+        }
+        let start = changed[k].saturating_sub(context);
+        let end = (changed[last] + context + 1).min(changes.len());
+        let hunk = &changes[start..end];
+        let old_len = hunk
+            .iter()
+            .filter(|c| !matches!(c, WordChange::Added(_)))
+            .count();
+        let new_len = hunk
+            .iter()
+            .filter(|c| !matches!(c, WordChange::Removed(_)))
+            .count();
+        let (old_start, new_start) = positions[start];
+        out.push_str(&format!(
+            "@@ -{old_start},{old_len} +{new_start},{new_len} @@\n"
+        ));
+        for change in hunk {
+            let (marker, line) = match change {
+                WordChange::Same(line) => (' ', line),
+                WordChange::Removed(line) => ('-', line),
+                WordChange::Added(line) => ('+', line),
+            };
+            out.push(marker);
+            out.push_str(line);
+            out.push('\n');
+        }
+        k = last + 1;
+    }
+    Some(out)
+}
+
+/// Renders a diff for the terminal: removed words in red strikethrough, added words in green.
+#[must_use]
+pub fn render_word_diff(changes: &[WordChange<'_>]) -> String {
+    let mut out = String::new();
+    let mut at_line_start = true;
+    for change in changes {
+        let styled = match *change {
+            WordChange::Removed("\n") => continue,
+            WordChange::Same("\n") | WordChange::Added("\n") => {
+                out.push('\n');
+                at_line_start = true;
+                continue;
+            }
+            WordChange::Same(word) => word.normal(),
+            WordChange::Removed(word) => word.red().strikethrough(),
+            WordChange::Added(word) => word.green().underline(),
+        };
+        if !at_line_start {
+            out.push(' ');
+        }
+        out.push_str(&styled.to_string());
+        at_line_start = false;
+    }
+    out
+}
@@ -0,0 +1,207 @@
+use anyhow::{Result, anyhow, bail};
+use std::io::Read;
+use std::path::Path;
+
+/// A format `read_file` extracts the text of instead of reading the file as text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// Split into pages
+    Pdf,
+    /// Split into paragraphs
+    Docx,
+}
+
+impl Kind {
+    /// The kind of document `path` is, going by its extension.
+    #[must_use]
+    pub fn of(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "pdf" => Some(Self::Pdf),
+            "docx" => Some(Self::Docx),
+            _ => None,
+        }
+    }
+
+    /// What the document's parts are called.
+    #[must_use]
+    pub fn unit(self) -> &'static str {
+        match self {
+            Self::Pdf => "page",
+            Self::Docx => "paragraph",
+        }
+    }
+}
+
+/// The text of a document, by page or paragraph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document {
+    pub kind: Kind,
+    pub parts: Vec<String>,
+}
+
+impl Document {
+    /// Reads the text out of a PDF or DOCX file.
+    ///
+    /// # Errors
+    /// Returns an error if the file is of neither kind or cannot be read or parsed.
+    pub fn read(path: &Path) -> Result<Self> {
+        let kind = Kind::of(path)
+            .ok_or_else(|| anyhow!("{} is not a PDF or DOCX file", path.display()))?;
+        let bytes = std::fs::read(path)?;
+        let parts = match kind {
+            Kind::Pdf => pdf_extract::extract_text_from_mem_by_pages(&bytes)
+                .map_err(|e| anyhow!("Could not read the PDF: {e}"))?,
+            Kind::Docx => docx_paragraphs(&docx_xml(&bytes)?),
+        };
+        Ok(Self { kind, parts })
+    }
+
+    /// Renders parts `first` to `last` (counting from 1, both included) with a marker
+    /// before each, stopping before `max_bytes` is reached with a note on how to read on.
+    /// Returns the text and the last part shown.
+    ///
+    /// # Errors
+    /// Returns an error if `first` is not one of the document's parts.
+    pub fn render(
+        &self,
+        path: &str,
+        first: usize,
+        last: usize,
+        max_bytes: usize,
+    ) -> Result<(String, usize)> {
+        let unit = self.kind.unit();
+        let count = self.parts.len();
+        if first == 0 || first > count || last < first {
+            bail!("{path} has no {unit} {first} (it has {count} {unit}s)");
+        }
+        let last = last.min(count);
+        let mut output = String::new();
+        let mut shown = first - 1;
+        for (i, part) in self.parts[first - 1..last].iter().enumerate() {
+            let number = first + i;
+            let block = match self.kind {
+                Kind::Pdf => format!("--- Page {number} ---\n{}\n\n", part.trim()),
+                Kind::Docx => format!("[¶{number}] {part}\n\n"),
+            };
+            if shown >= first && output.len() + block.len() > max_bytes {
+                break;
+            }
+            output.push_str(&block);
+            shown = number;
+        }
+        if shown < last {
+            output.push_str(&format!(
+                "[Stopped at {unit} {shown} of {count} to keep this short; read on with read_file {path}#{}-{last}]",
+                shown + 1
+            ));
+        }
+        Ok((output.trim_end().to_string(), shown))
+    }
+}
+
+/// Parses a part selector such as `3` or `3-7`; `3-` runs to the end.
+#[must_use]
+pub fn parse_range(selector: &str) -> Option<(usize, usize)> {
+    match selector.split_once('-') {
+        Some((first, "")) => Some((first.trim().parse().ok()?, usize::MAX)),
+        Some((first, last)) => Some((first.trim().parse().ok()?, last.trim().parse().ok()?)),
+        None => {
+            let part = selector.trim().parse().ok()?;
+            Some((part, part))
+        }
+    }
+}
+
+/// The main part of a DOCX file, which is a zip archive.
+fn docx_xml(bytes: &[u8]) -> Result<String> {
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes))
+        .map_err(|e| anyhow!("Not a valid DOCX file: {e}"))?;
+    let mut entry = archive
+        .by_name("word/document.xml")
+        .map_err(|e| anyhow!("Not a valid DOCX file: {e}"))?;
+    let mut xml = String::new();
+    entry.read_to_string(&mut xml)?;
+    Ok(xml)
+}
+
+/// The text of each non-empty paragraph of a DOCX `document.xml`, table cells included.
+#[must_use]
+pub fn docx_paragraphs(xml: &str) -> Vec<String> {
+    let mut paragraphs = Vec::new();
+    let mut current = String::new();
+    let mut in_run = false;
+    let mut in_text = false;
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        if in_text {
+            current.push_str(&unescape(&rest[..start]));
+        }
+        let Some(end) = rest[start..].find('>') else {
+            break;
+        };
+        let tag = &rest[start + 1..start + end];
+        rest = &rest[start + end + 1..];
+        let closing = tag.starts_with('/');
+        let self_closing = tag.ends_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default();
+        match name {
+            "w:r" => in_run = !closing && !self_closing,
+            "w:t" => in_text = !closing && !self_closing,
+            // Outside a run these are tab stops and the like, not text
+            "w:tab" if in_run => current.push('\t'),
+            "w:br" | "w:cr" if in_run => current.push('\n'),
+            "w:p" if closing || self_closing => {
+                let paragraph = current.trim();
+                if !paragraph.is_empty() {
+                    paragraphs.push(paragraph.to_string());
+                }
+                current.clear();
+            }
+            _ => {}
+        }
+    }
+    paragraphs
+}
+
+fn unescape(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..end];
+        let decoded = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(std::result::Result::ok)
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                output.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                output.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
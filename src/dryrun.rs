@@ -0,0 +1,132 @@
+use crate::args::{
+    CommandArgs, EditLinesArgs, MoveFileArgs, PathArgs, SearchReplaceArgs, ToolArgs, WriteFileArgs,
+};
+use crate::attach::format_size;
+use crate::diff::unified_diff;
+use crate::tools::{self, ToolContext};
+use std::path::PathBuf;
+
+/// Unchanged lines shown around each change in dry-run diffs.
+const DIFF_CONTEXT_LINES: usize = 3;
+
+/// What a call to a tool that changes files or runs commands would do, worked out
+/// without doing it: the diff of an edit, the exact command, the file that would go. Paths
+/// are resolved in `cx` and arguments read as the tools read them, so a call the tool
+/// would take in either form is previewed.
+#[must_use]
+pub fn preview(cx: &ToolContext, name: &str, arg: &str) -> String {
+    match preview_in(cx, name, arg) {
+        Ok(preview) | Err(preview) => preview,
+    }
+}
+
+fn preview_in(cx: &ToolContext, name: &str, arg: &str) -> Result<String, String> {
+    Ok(match name {
+        "write_file" => {
+            let WriteFileArgs { path, content } = parse(name, arg)?;
+            let (file, path) = locate(cx, &path)?;
+            match std::fs::read_to_string(&file) {
+                Ok(old) => changes(&path, &old, &content, "overwrite"),
+                Err(_) => format!(
+                    "Would create {path} ({} lines):\n{}",
+                    content.lines().count(),
+                    added(&content)
+                ),
+            }
+        }
+        "apply_search_replace" => {
+            let SearchReplaceArgs { path, blocks } = parse(name, arg)?;
+            let (file, path) = locate(cx, &path)?;
+            let Ok(mut content) = std::fs::read_to_string(&file) else {
+                return Err(format!("Would fail: cannot read {path}"));
+            };
+            let old = content.clone();
+            for block in &blocks {
+                if !content.contains(&block.search) {
+                    return Err(format!(
+                        "Would fail: search string not found in {path}: {:?}",
+                        block.search
+                    ));
+                }
+                content = content.replace(&block.search, &block.replace);
+            }
+            changes(&path, &old, &content, "change")
+        }
+        "edit_lines" => {
+            let EditLinesArgs {
+                path,
+                start,
+                end,
+                text,
+            } = parse(name, arg)?;
+            let (file, path) = locate(cx, &path)?;
+            let edited = std::fs::read_to_string(&file)
+                .map_err(anyhow::Error::from)
+                .and_then(|old| Ok((tools::replace_lines(&old, start, end, &text)?, old)));
+            match edited {
+                Ok((new, old)) => changes(&path, &old, &new, "change"),
+                Err(e) => format!("Would fail: {e}"),
+            }
+        }
+        "apply_patch" => format!("Would apply this patch:\n{}", arg.trim_end()),
+        "create_directory" => {
+            let PathArgs { path } = parse(name, arg)?;
+            let (file, path) = locate(cx, &path)?;
+            if file.is_dir() {
+                format!("Would do nothing: {path} already exists")
+            } else {
+                format!("Would create the directory {path}")
+            }
+        }
+        "delete_file" => {
+            let PathArgs { path } = parse(name, arg)?;
+            let (file, path) = locate(cx, &path)?;
+            match std::fs::metadata(&file) {
+                Ok(metadata) => format!(
+                    "Would delete {path} ({})",
+                    format_size(usize::try_from(metadata.len()).unwrap_or(usize::MAX))
+                ),
+                Err(e) => format!("Would fail: cannot delete {path}: {e}"),
+            }
+        }
+        "move_file" => {
+            let MoveFileArgs { from, to } = parse(name, arg)?;
+            let (_, from) = locate(cx, &from)?;
+            let (_, to) = locate(cx, &to)?;
+            format!("Would move {from} to {to}")
+        }
+        "run_command" => {
+            let CommandArgs { command } = parse(name, arg)?;
+            format!("Would run:\n{}", command.trim_end())
+        }
+        _ => format!("Would call {name} with:\n{}", arg.trim_end()),
+    })
+}
+
+/// The arguments of a call to `tool`, read as its handler reads them.
+fn parse<T: ToolArgs>(tool: &str, arg: &str) -> Result<T, String> {
+    T::parse(tool, arg).map_err(|e| format!("Would fail: {e}"))
+}
+
+/// `path` resolved as the tools resolve it, with the name results show it by.
+fn locate(cx: &ToolContext, path: &str) -> Result<(PathBuf, String), String> {
+    let file = cx.resolve(path).map_err(|e| format!("Would fail: {e}"))?;
+    let shown = cx.show(&file);
+    Ok((file, shown))
+}
+
+fn changes(path: &str, old: &str, new: &str, verb: &str) -> String {
+    match unified_diff(old, new, DIFF_CONTEXT_LINES) {
+        Some(diff) if diff.is_empty() => format!("Would leave {path} as it is"),
+        Some(diff) => format!("Would {verb} {path}:\n{}", diff.trim_end()),
+        None => format!("Would {verb} {path} (too large to diff)"),
+    }
+}
+
+fn added(content: &str) -> String {
+    content
+        .lines()
+        .map(|line| format!("+{line}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
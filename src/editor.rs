@@ -0,0 +1,43 @@
+use anyhow::{Result, anyhow, bail};
+use std::process::Command;
+
+/// The editor messages are opened in: `VISUAL`, then `EDITOR`, then `notepad` on Windows
+/// and `vi` elsewhere.
+#[must_use]
+pub fn command() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| if cfg!(windows) { "notepad" } else { "vi" }.to_string())
+}
+
+/// Opens `text` in the user's editor and returns it as it was saved.
+///
+/// # Errors
+/// Returns an error if the editor cannot be started or exits with a failure.
+pub fn edit(text: &str) -> Result<String> {
+    edit_with(&command(), text)
+}
+
+/// Opens `text` in `editor`, a program followed by any arguments it needs to wait for the
+/// file to be closed, such as `code --wait`, and returns it as it was saved.
+///
+/// # Errors
+/// Returns an error if the editor cannot be started or exits with a failure.
+pub fn edit_with(editor: &str, text: &str) -> Result<String> {
+    let mut words = editor.split_whitespace();
+    let Some(program) = words.next() else {
+        bail!("No editor set; set VISUAL or EDITOR");
+    };
+    let path = std::env::temp_dir().join(format!("deepseek-message-{}.md", std::process::id()));
+    std::fs::write(&path, text)?;
+    let status = Command::new(program).args(words).arg(&path).status();
+    let edited = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+    let status = status.map_err(|e| anyhow!("Failed to start {program}: {e}"))?;
+    if !status.success() {
+        bail!("{program} exited with {status}");
+    }
+    Ok(edited?)
+}
@@ -0,0 +1,259 @@
+use crate::workspace::{self, Root};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::LazyLock;
+use std::time::{Duration, SystemTime};
+
+/// How long the toolchain versions found on disk are trusted before they are looked up
+/// again.
+const TOOLCHAIN_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The programs whose versions are reported, each with the names it may go by.
+const TOOLCHAINS: [(&str, &[&str]); 3] = [
+    ("cargo", &["cargo"]),
+    ("node", &["node"]),
+    ("python", &["python3", "python"]),
+];
+
+/// What the model is told about the machine at the start of a chat, so that it need not
+/// run commands to find out.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Environment {
+    /// The operating system and architecture, such as `linux (x86_64)`
+    pub os: String,
+    /// The user's shell, from `SHELL` or `ComSpec`
+    pub shell: Option<String>,
+    pub cwd: PathBuf,
+    /// The active root of the workspace
+    pub root: PathBuf,
+    /// The workspace's other roots
+    pub other_roots: Vec<Root>,
+    pub git: Option<GitStatus>,
+    pub toolchains: Vec<Toolchain>,
+}
+
+/// A summary of `git status`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GitStatus {
+    /// The branch checked out, or `None` when `HEAD` is detached
+    pub branch: Option<String>,
+    /// How the branch stands against its upstream, such as `ahead 1, behind 2`
+    pub tracking: Option<String>,
+    /// Files with staged or unstaged changes
+    pub changed: usize,
+    pub untracked: usize,
+}
+
+/// A program found on `PATH` and its version.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Toolchain {
+    pub name: String,
+    pub version: String,
+}
+
+/// The environment as it is now. The toolchain versions are looked up once a day, or
+/// when `PATH` changes, and kept in `~/.cache/deepseek-cli/toolchains.json`; the rest is
+/// read afresh, since the branch and the changes move during a session.
+#[must_use]
+pub fn snapshot() -> Environment {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let workspace = workspace::current();
+    let other_roots = workspace
+        .roots()
+        .iter()
+        .filter(|root| root.path != workspace.root())
+        .cloned()
+        .collect();
+    Environment {
+        os: format!("{} ({})", std::env::consts::OS, std::env::consts::ARCH),
+        shell: std::env::var("SHELL")
+            .or_else(|_| std::env::var("ComSpec"))
+            .ok()
+            .filter(|s| !s.is_empty()),
+        root: workspace.root().to_path_buf(),
+        other_roots,
+        git: git_status(&cwd),
+        toolchains: TOOLCHAIN_VERSIONS.clone(),
+        cwd,
+    }
+}
+
+impl Environment {
+    /// The preamble of the first message of a chat.
+    #[must_use]
+    pub fn render(&self) -> String {
+        let mut lines = vec![
+            "The environment this chat started in, so there is no need to run commands to find it out:".to_string(),
+            format!("- OS: {}", self.os),
+        ];
+        if let Some(shell) = &self.shell {
+            lines.push(format!("- Shell: {shell}"));
+        }
+        if self.root == self.cwd {
+            lines.push(format!("- Working directory: {}", self.cwd.display()));
+        } else {
+            lines.push(format!(
+                "- Working directory: {} (in the project at {})",
+                self.cwd.display(),
+                self.root.display()
+            ));
+        }
+        if !self.other_roots.is_empty() {
+            let roots: Vec<String> = self
+                .other_roots
+                .iter()
+                .map(|r| format!("{} ({})", r.name, r.path.display()))
+                .collect();
+            lines.push(format!(
+                "- Other workspace roots: {}; name a file in one as <root>:<path>, such as {}:README.md",
+                roots.join(", "),
+                self.other_roots[0].name
+            ));
+        }
+        if let Some(git) = &self.git {
+            lines.push(format!("- Git: {}", git.render()));
+        }
+        if !self.toolchains.is_empty() {
+            let toolchains: Vec<String> = self
+                .toolchains
+                .iter()
+                .map(|t| format!("{} {}", t.name, t.version))
+                .collect();
+            lines.push(format!("- Toolchains: {}", toolchains.join(", ")));
+        }
+        lines.join("\n")
+    }
+}
+
+impl GitStatus {
+    /// Parses the output of `git status --porcelain=v1 --branch`.
+    #[must_use]
+    pub fn parse(output: &str) -> Self {
+        let mut status = Self::default();
+        for line in output.lines() {
+            if let Some(header) = line.strip_prefix("## ") {
+                let (head, tracking) = match header.split_once(" [") {
+                    Some((head, tracking)) => (head, tracking.strip_suffix(']')),
+                    None => (header, None),
+                };
+                let head = head.strip_prefix("No commits yet on ").unwrap_or(head);
+                let branch = head.split_once("...").map_or(head, |(branch, _)| branch);
+                status.branch = (branch != "HEAD (no branch)").then(|| branch.to_string());
+                status.tracking = tracking.map(str::to_string);
+            } else if line.starts_with("??") {
+                status.untracked += 1;
+            } else if !line.trim().is_empty() {
+                status.changed += 1;
+            }
+        }
+        status
+    }
+
+    #[must_use]
+    pub fn render(&self) -> String {
+        let mut text = match &self.branch {
+            Some(branch) => format!("on branch {branch}"),
+            None => "detached HEAD".to_string(),
+        };
+        if let Some(tracking) = &self.tracking {
+            text.push_str(&format!(" ({tracking})"));
+        }
+        match (self.changed, self.untracked) {
+            (0, 0) => text.push_str(", no changes"),
+            (changed, 0) => text.push_str(&format!(", {changed} changed file(s)")),
+            (0, untracked) => text.push_str(&format!(", {untracked} untracked file(s)")),
+            (changed, untracked) => text.push_str(&format!(
+                ", {changed} changed and {untracked} untracked file(s)"
+            )),
+        }
+        text
+    }
+}
+
+/// The status of the repository `dir` is in, or `None` outside one or without git.
+fn git_status(dir: &Path) -> Option<GitStatus> {
+    let output = Command::new("git")
+        .args(["status", "--porcelain=v1", "--branch"])
+        .current_dir(dir)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    Some(GitStatus::parse(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Picks the version out of what `<program> --version` printed, such as `1.82.0` from
+/// `cargo 1.82.0 (8f40fc59f 2024-08-21)` or `20.11.0` from `v20.11.0`.
+#[must_use]
+pub fn parse_version(output: &str) -> Option<String> {
+    output
+        .split_whitespace()
+        .map(|word| word.strip_prefix('v').unwrap_or(word))
+        .find(|word| word.starts_with(|c: char| c.is_ascii_digit()) && word.contains('.'))
+        .map(str::to_string)
+}
+
+static TOOLCHAIN_VERSIONS: LazyLock<Vec<Toolchain>> = LazyLock::new(|| {
+    let path = std::env::var("PATH").unwrap_or_default();
+    let cache = dirs::cache_dir().map(|d| d.join("deepseek-cli/toolchains.json"));
+    if let Some(cached) = cache.as_deref().and_then(|file| read_cache(file, &path)) {
+        return cached;
+    }
+    let toolchains = probe_toolchains();
+    if let Some(file) = &cache {
+        write_cache(file, &path, &toolchains);
+    }
+    toolchains
+});
+
+#[derive(Serialize, Deserialize)]
+struct CachedToolchains {
+    /// The `PATH` they were found on
+    path: String,
+    /// When they were looked up, in seconds since the Unix epoch
+    checked: u64,
+    toolchains: Vec<Toolchain>,
+}
+
+fn read_cache(file: &Path, path: &str) -> Option<Vec<Toolchain>> {
+    let cached: CachedToolchains =
+        serde_json::from_str(&std::fs::read_to_string(file).ok()?).ok()?;
+    let checked = SystemTime::UNIX_EPOCH + Duration::from_secs(cached.checked);
+    let fresh = checked.elapsed().is_ok_and(|age| age < TOOLCHAIN_TTL);
+    (fresh && cached.path == path).then_some(cached.toolchains)
+}
+
+fn write_cache(file: &Path, path: &str, toolchains: &[Toolchain]) {
+    let cached = CachedToolchains {
+        path: path.to_string(),
+        checked: SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs()),
+        toolchains: toolchains.to_vec(),
+    };
+    let written = file
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|()| std::fs::write(file, serde_json::to_string(&cached)?));
+    if let Err(e) = written {
+        tracing::debug!(path = %file.display(), error = %e, "could not cache the toolchain versions");
+    }
+}
+
+fn probe_toolchains() -> Vec<Toolchain> {
+    TOOLCHAINS
+        .iter()
+        .filter_map(|(name, programs)| {
+            let version = programs.iter().find_map(|program| {
+                let output = Command::new(program).arg("--version").output().ok()?;
+                // Older Pythons print their version on standard error
+                let printed = [output.stdout, output.stderr].concat();
+                parse_version(&String::from_utf8_lossy(&printed))
+            })?;
+            Some(Toolchain {
+                name: (*name).to_string(),
+                version,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,130 @@
+use crate::events::AgentEvent;
+use anyhow::Result;
+use chrono::SecondsFormat;
+use serde::Serialize;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use std::time::Instant;
+
+/// A machine-readable record of what happened in a session, for auditing: one JSON object
+/// per line for each user message, tool call, tool result and assistant message, with
+/// the time and, where it applies, how long it took. Streamed chunks are left out.
+#[derive(Debug)]
+pub struct EventLog {
+    path: PathBuf,
+    state: Mutex<State>,
+}
+
+#[derive(Debug)]
+struct State {
+    file: File,
+    chat_id: String,
+    /// When the current user message was sent
+    turn_started: Option<Instant>,
+    /// When the model was last asked for a reply, at the message or after tool results
+    request_started: Option<Instant>,
+    /// Tool calls not yet answered by a result, oldest first
+    tool_calls: Vec<(String, Instant)>,
+}
+
+#[derive(Serialize)]
+struct Record<'a> {
+    time: String,
+    chat_id: &'a str,
+    #[serde(flatten)]
+    event: &'a AgentEvent,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<u128>,
+}
+
+impl EventLog {
+    /// Where logs go without `--log-dir`: `~/.config/deepseek-cli/logs`.
+    #[must_use]
+    pub fn default_dir() -> Option<PathBuf> {
+        dirs::config_dir().map(|d| d.join("deepseek-cli/logs"))
+    }
+
+    /// Starts a log in `dir`, named after the time and the chat.
+    ///
+    /// # Errors
+    /// Returns an error if the directory or file cannot be created.
+    pub fn create(dir: &Path, chat_id: &str) -> Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let stamp = chrono::Utc::now().format("%Y%m%dT%H%M%SZ");
+        let path = dir.join(format!("{stamp}-{chat_id}.jsonl"));
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)?;
+        Ok(Self {
+            path,
+            state: Mutex::new(State {
+                file,
+                chat_id: chat_id.to_string(),
+                turn_started: None,
+                request_started: None,
+                tool_calls: Vec::new(),
+            }),
+        })
+    }
+
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Sets the chat the following records belong to, as when the session moves to a new
+    /// chat.
+    pub fn set_chat_id(&self, chat_id: &str) {
+        chat_id.clone_into(&mut self.lock().chat_id);
+    }
+
+    /// Appends a record for the event. Failing to write is not an error for the agent, so
+    /// it is ignored.
+    pub fn record(&self, event: &AgentEvent) {
+        let mut state = self.lock();
+        let now = Instant::now();
+        let duration = match event {
+            AgentEvent::Thinking { .. } | AgentEvent::Content { .. } => return,
+            AgentEvent::User { .. } => {
+                state.turn_started = Some(now);
+                state.request_started = Some(now);
+                None
+            }
+            AgentEvent::ToolCall { name, .. } => {
+                state.tool_calls.push((name.clone(), now));
+                None
+            }
+            AgentEvent::ToolResult { name, .. } => {
+                state.request_started = Some(now);
+                state
+                    .tool_calls
+                    .iter()
+                    .position(|(called, _)| called == name)
+                    .map(|i| state.tool_calls.remove(i).1)
+            }
+            AgentEvent::Message { .. } | AgentEvent::Interrupted | AgentEvent::Error { .. } => {
+                state.request_started
+            }
+            AgentEvent::Done => {
+                state.tool_calls.clear();
+                state.turn_started.take()
+            }
+        };
+        let record = Record {
+            time: chrono::Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            chat_id: &state.chat_id,
+            event,
+            duration_ms: duration.map(|started| now.duration_since(started).as_millis()),
+        };
+        if let Ok(line) = serde_json::to_string(&record) {
+            let _ = writeln!(state.file, "{line}");
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
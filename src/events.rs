@@ -0,0 +1,158 @@
+use crate::eventlog::EventLog;
+use crate::export::Conversation;
+use anyhow::Result;
+use futures_util::future::LocalBoxFuture;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+
+/// Something that happened while the agent worked on a turn.
+///
+/// Serialized as `{"type": "content", "text": ...}` and so on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AgentEvent {
+    /// A message the user sent.
+    User {
+        text: String,
+    },
+    Thinking {
+        text: String,
+    },
+    Content {
+        text: String,
+    },
+    ToolCall {
+        name: String,
+        arg: String,
+    },
+    ToolResult {
+        name: String,
+        status: String,
+        success: bool,
+    },
+    /// A complete assistant message, after its content chunks.
+    Message {
+        content: String,
+        message_id: Option<i64>,
+    },
+    Interrupted,
+    /// The turn is over and the agent is waiting for the next message.
+    Done,
+    Error {
+        message: String,
+    },
+}
+
+/// Forwards events to a listener (such as the web UI), or drops them if there is none.
+#[derive(Debug, Clone)]
+pub struct EventSink {
+    listener: Option<UnboundedSender<AgentEvent>>,
+    echo: bool,
+    /// Where the events are also kept, for exporting the conversation
+    conversation: Option<Arc<Mutex<Conversation>>>,
+    /// The session's event log, if it keeps one
+    log: Option<Arc<EventLog>>,
+    callback: Option<Callback>,
+}
+
+/// A function every event is passed to.
+#[derive(Clone)]
+struct Callback(Arc<dyn Fn(&AgentEvent) + Send + Sync>);
+
+impl std::fmt::Debug for Callback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Callback")
+    }
+}
+
+impl Default for EventSink {
+    fn default() -> Self {
+        Self {
+            listener: None,
+            echo: true,
+            conversation: None,
+            log: None,
+            callback: None,
+        }
+    }
+}
+
+impl EventSink {
+    /// Creates a sink together with the receiving end of its channel.
+    #[must_use]
+    pub fn channel() -> (Self, UnboundedReceiver<AgentEvent>) {
+        let (tx, rx) = unbounded_channel();
+        let sink = Self {
+            listener: Some(tx),
+            echo: true,
+            conversation: None,
+            log: None,
+            callback: None,
+        };
+        (sink, rx)
+    }
+
+    /// Stops the turn's output from also being printed to the terminal, for interfaces
+    /// that draw the screen themselves.
+    #[must_use]
+    pub fn without_echo(mut self) -> Self {
+        self.echo = false;
+        self
+    }
+
+    /// Also records every event into `conversation`.
+    #[must_use]
+    pub fn recording(mut self, conversation: Arc<Mutex<Conversation>>) -> Self {
+        self.conversation = Some(conversation);
+        self
+    }
+
+    /// Also appends every event to `log`.
+    #[must_use]
+    pub fn logging(mut self, log: Arc<EventLog>) -> Self {
+        self.log = Some(log);
+        self
+    }
+
+    /// Also passes every event to `callback`, as it happens, for interfaces that embed the
+    /// agent.
+    #[must_use]
+    pub fn with_callback(mut self, callback: impl Fn(&AgentEvent) + Send + Sync + 'static) -> Self {
+        self.callback = Some(Callback(Arc::new(callback)));
+        self
+    }
+
+    /// Whether the turn's output should be printed to the terminal as well.
+    #[must_use]
+    pub fn echo(&self) -> bool {
+        self.echo
+    }
+
+    pub fn emit(&self, event: AgentEvent) {
+        if let Some(log) = &self.log {
+            log.record(&event);
+        }
+        if let Some(conversation) = &self.conversation {
+            conversation
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .record(&event);
+        }
+        if let Some(Callback(callback)) = &self.callback {
+            callback(&event);
+        }
+        if let Some(tx) = &self.listener {
+            // The listener going away is not an error for the agent
+            let _ = tx.send(event);
+        }
+    }
+}
+
+/// The agent as seen by interfaces other than the line-based REPL.
+pub trait ChatBackend {
+    /// Sends a user message and runs the assistant's turn, emitting its events into `events`.
+    fn send_message(&self, message: String, events: EventSink) -> LocalBoxFuture<'_, Result<()>>;
+    /// Stops the turn in progress, if any.
+    fn interrupt(&self);
+}
@@ -0,0 +1,71 @@
+use std::fmt;
+
+/// How the program ended, as its exit code, so that scripts running `-p`, `--output json`
+/// or `deepseek run` can branch on the result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitStatus {
+    Success,
+    /// Any error not covered by the others, such as a bad argument
+    Failure,
+    /// The task was stopped before the model finished, at the turn limit or by Ctrl+C
+    Incomplete,
+    /// A tool call was refused by a `[[policy]]` rule
+    PolicyViolation,
+    /// The API could not be reached or failed the request
+    ApiError,
+}
+
+impl ExitStatus {
+    #[must_use]
+    pub fn code(self) -> u8 {
+        match self {
+            Self::Success => 0,
+            Self::Failure => 1,
+            Self::Incomplete => 2,
+            Self::PolicyViolation => 3,
+            Self::ApiError => 4,
+        }
+    }
+}
+
+/// An error that ends the program with a particular [`ExitStatus`]. It shows as the error
+/// it wraps, with the same causes.
+#[derive(Debug)]
+pub struct StatusError {
+    pub status: ExitStatus,
+    error: anyhow::Error,
+}
+
+impl fmt::Display for StatusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl std::error::Error for StatusError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.error.source()
+    }
+}
+
+/// Makes `error` end the program with `status`.
+#[must_use]
+pub fn with_status(status: ExitStatus, error: anyhow::Error) -> anyhow::Error {
+    StatusError { status, error }.into()
+}
+
+/// An error with `message` that ends the program with `status`.
+#[must_use]
+pub fn error(status: ExitStatus, message: impl fmt::Display) -> anyhow::Error {
+    with_status(status, anyhow::anyhow!("{message}"))
+}
+
+/// The status to exit with after `error`: the one it was raised with, or
+/// [`ExitStatus::Failure`].
+#[must_use]
+pub fn status_of(error: &anyhow::Error) -> ExitStatus {
+    error
+        .chain()
+        .find_map(|e| e.downcast_ref::<StatusError>())
+        .map_or(ExitStatus::Failure, |e| e.status)
+}
@@ -0,0 +1,242 @@
+use crate::index::FileIndex;
+use crate::mentions::fence_for;
+use crate::syntax::{self, Symbol};
+use crate::workspace::Workspace;
+use anyhow::{Result, anyhow, bail};
+use std::path::Path;
+
+/// Most source sent to be explained at once; a larger file is explained a symbol at a time.
+pub const MAX_EXPLAIN_BYTES: usize = 48 * 1024;
+
+/// Matches listed when a symbol is defined in several places.
+const MAX_LISTED_MATCHES: usize = 10;
+
+/// The prompt an explanation starts with, in place of the agent's usual instructions.
+pub const EXPLAIN_SYSTEM_PROMPT: &str = "You are explaining code to a developer who has not read it before. Start with one or two sentences on what it is for, then walk through how it works: the main steps, the data it reads and changes, and anything easy to miss, such as side effects, error handling, concurrency or edge cases. Name the functions and types you talk about so they can be found. Do not call any tools, do not rewrite the code, and suggest changes only where something looks wrong.";
+
+/// Code to explain: a whole file or one symbol in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Excerpt {
+    /// The file, as the tools show it
+    pub path: String,
+    /// The symbol as it was found, such as `run` or `Workspace::resolve`, or `None` for
+    /// the whole file
+    pub symbol: Option<String>,
+    /// First line, 1-based
+    pub start_line: usize,
+    /// Last line, 1-based and inclusive
+    pub end_line: usize,
+    pub text: String,
+}
+
+impl Excerpt {
+    /// What is explained, such as `` `Workspace::resolve` in src/workspace.rs (lines
+    /// 198-201) ``.
+    #[must_use]
+    pub fn describe(&self) -> String {
+        match &self.symbol {
+            Some(symbol) => format!(
+                "`{symbol}` in {} (lines {}-{})",
+                self.path, self.start_line, self.end_line
+            ),
+            None => self.path.clone(),
+        }
+    }
+}
+
+/// Loads what `deepseek explain` was given: `<path>` for a whole file, `<path>#<symbol>`
+/// for a symbol in it, or `<symbol>` alone, looked up in the outlines of the workspace's
+/// files. A symbol is named as the outline lists it, or as `<Type>::<name>` for a member
+/// of a type or impl block.
+///
+/// # Errors
+/// Returns an error if nothing by that name is found, the symbol is defined in several
+/// places, or the file cannot be read, is not text or is too large to explain whole.
+pub fn load(workspace: &Workspace, target: &str) -> Result<Excerpt> {
+    let target = target.trim();
+    if target.is_empty() {
+        bail!("Nothing to explain");
+    }
+    if let Ok(file) = workspace.resolve(target)
+        && file.is_file()
+    {
+        return whole_file(&workspace.display(&file), &read(&file)?);
+    }
+    if let Some((path, symbol)) = target.rsplit_once('#')
+        && let Ok(file) = workspace.resolve(path)
+        && file.is_file()
+    {
+        let path = workspace.display(&file);
+        let source = read(&file)?;
+        let symbols = syntax::outline(Path::new(&path), &source)
+            .ok_or_else(|| anyhow!("{path} is not in a language whose symbols can be found"))?;
+        let Some((name, symbol)) = matching(&symbols, symbol.trim()).into_iter().next() else {
+            bail!(
+                "No symbol named {} in {path}. Available: {}",
+                symbol.trim(),
+                top_level(&symbols)
+            );
+        };
+        return Ok(symbol_excerpt(path, &source, name, symbol));
+    }
+    find_symbol(workspace, target)
+}
+
+/// Looks `query` up in the outline of every indexed file whose language has a grammar.
+fn find_symbol(workspace: &Workspace, query: &str) -> Result<Excerpt> {
+    let index = FileIndex::build_workspace(workspace);
+    let mut found = Vec::new();
+    for path in index.files() {
+        if syntax::language_for(Path::new(path)).is_none() {
+            continue;
+        }
+        let Ok(file) = workspace.resolve(path) else {
+            continue;
+        };
+        let Ok(source) = std::fs::read_to_string(&file) else {
+            continue;
+        };
+        let Some(symbols) = syntax::outline(Path::new(path), &source) else {
+            continue;
+        };
+        for (name, symbol) in matching(&symbols, query) {
+            found.push(symbol_excerpt(path.clone(), &source, name, symbol));
+        }
+    }
+    match found.len() {
+        0 => bail!("No file or symbol named {query}"),
+        1 => Ok(found.remove(0)),
+        n => {
+            let listed: Vec<String> = found
+                .iter()
+                .take(MAX_LISTED_MATCHES)
+                .map(|excerpt| format!("  {}:{}", excerpt.path, excerpt.start_line))
+                .collect();
+            let more = if n > MAX_LISTED_MATCHES {
+                format!("\n  and {} more", n - MAX_LISTED_MATCHES)
+            } else {
+                String::new()
+            };
+            bail!(
+                "{query} is defined in {n} places; pick one with <path>#{query}:\n{}{more}",
+                listed.join("\n")
+            )
+        }
+    }
+}
+
+fn read(file: &Path) -> Result<String> {
+    let bytes = std::fs::read(file).map_err(|e| anyhow!("Cannot read {}: {e}", file.display()))?;
+    String::from_utf8(bytes).map_err(|_| anyhow!("{} is not a text file", file.display()))
+}
+
+fn whole_file(path: &str, source: &str) -> Result<Excerpt> {
+    if source.len() > MAX_EXPLAIN_BYTES {
+        let symbols = syntax::outline(Path::new(path), source).unwrap_or_default();
+        if symbols.is_empty() {
+            bail!(
+                "{path} is too large to explain at once ({} bytes)",
+                source.len()
+            );
+        }
+        bail!(
+            "{path} is too large to explain at once ({} bytes); pick a symbol with {path}#<symbol>: {}",
+            source.len(),
+            top_level(&symbols)
+        );
+    }
+    Ok(Excerpt {
+        path: path.to_string(),
+        symbol: None,
+        start_line: 1,
+        end_line: source.lines().count().max(1),
+        text: source.to_string(),
+    })
+}
+
+fn symbol_excerpt(path: String, source: &str, name: String, symbol: &Symbol) -> Excerpt {
+    let text: Vec<&str> = source
+        .lines()
+        .skip(symbol.start_line - 1)
+        .take(symbol.end_line + 1 - symbol.start_line)
+        .collect();
+    Excerpt {
+        path,
+        symbol: Some(name),
+        start_line: symbol.start_line,
+        end_line: symbol.end_line,
+        text: text.join("\n"),
+    }
+}
+
+/// The symbols `query` names, each with the name it goes by: its own at the top level,
+/// `<Type>::<name>` for a member.
+fn matching<'a>(symbols: &'a [Symbol], query: &str) -> Vec<(String, &'a Symbol)> {
+    let mut found = Vec::new();
+    for (i, symbol) in symbols.iter().enumerate() {
+        let owner = symbols[..i]
+            .iter()
+            .rev()
+            .find(|s| s.depth < symbol.depth)
+            .map(|s| owner_name(&s.name));
+        let qualified = match owner {
+            Some(owner) => format!("{owner}::{}", symbol.name),
+            None => symbol.name.clone(),
+        };
+        if query == symbol.name || query == qualified {
+            found.push((qualified, symbol));
+        }
+    }
+    found
+}
+
+/// The type an outline entry holds members of: `Workspace` for `impl Workspace` and
+/// `impl<T> fmt::Display for Workspace<T>`.
+fn owner_name(name: &str) -> String {
+    let mut depth = 0_usize;
+    let plain: String = name
+        .chars()
+        .filter(|&c| match c {
+            '<' => {
+                depth += 1;
+                false
+            }
+            '>' => {
+                depth = depth.saturating_sub(1);
+                false
+            }
+            _ => depth == 0,
+        })
+        .collect();
+    let last = plain.split_whitespace().last().unwrap_or_default();
+    last.rsplit("::").next().unwrap_or(last).to_string()
+}
+
+fn top_level(symbols: &[Symbol]) -> String {
+    let names: Vec<&str> = symbols
+        .iter()
+        .filter(|s| s.depth == 0)
+        .map(|s| s.name.as_str())
+        .collect();
+    names.join(", ")
+}
+
+/// The message asking for the explanation of `excerpt`.
+#[must_use]
+pub fn prompt(excerpt: &Excerpt) -> String {
+    let language = Path::new(&excerpt.path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+    let fence = fence_for(&excerpt.text);
+    let newline = if excerpt.text.ends_with('\n') {
+        ""
+    } else {
+        "\n"
+    };
+    format!(
+        "Explain {}:\n\n{fence}{language}\n{}{newline}{fence}",
+        excerpt.describe(),
+        excerpt.text
+    )
+}
@@ -0,0 +1,207 @@
+use crate::events::AgentEvent;
+use crate::mentions::fence_for;
+use std::fmt::Write;
+
+/// One part of a conversation as it is exported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Entry {
+    User(String),
+    Assistant {
+        thinking: String,
+        content: String,
+    },
+    ToolCall {
+        name: String,
+        arg: String,
+    },
+    ToolResult {
+        name: String,
+        status: String,
+        success: bool,
+    },
+}
+
+/// The turns of a session, collected from its events for `/export md` and `/export html`.
+#[derive(Debug, Clone, Default)]
+pub struct Conversation {
+    entries: Vec<Entry>,
+}
+
+impl Conversation {
+    /// Adds an event, joining streamed thinking and content into one assistant entry.
+    pub fn record(&mut self, event: &AgentEvent) {
+        match event {
+            AgentEvent::User { text } => self.entries.push(Entry::User(text.clone())),
+            AgentEvent::Thinking { text } => match self.entries.last_mut() {
+                Some(Entry::Assistant { thinking, content }) if content.is_empty() => {
+                    thinking.push_str(text);
+                }
+                _ => self.entries.push(Entry::Assistant {
+                    thinking: text.clone(),
+                    content: String::new(),
+                }),
+            },
+            AgentEvent::Content { text } => match self.entries.last_mut() {
+                Some(Entry::Assistant { content, .. }) => content.push_str(text),
+                _ => self.entries.push(Entry::Assistant {
+                    thinking: String::new(),
+                    content: text.clone(),
+                }),
+            },
+            // The complete message replaces what was streamed of it
+            AgentEvent::Message { content, .. } => match self.entries.last_mut() {
+                Some(Entry::Assistant {
+                    content: streamed, ..
+                }) => streamed.clone_from(content),
+                _ => self.entries.push(Entry::Assistant {
+                    thinking: String::new(),
+                    content: content.clone(),
+                }),
+            },
+            AgentEvent::ToolCall { name, arg } => self.entries.push(Entry::ToolCall {
+                name: name.clone(),
+                arg: arg.clone(),
+            }),
+            AgentEvent::ToolResult {
+                name,
+                status,
+                success,
+            } => self.entries.push(Entry::ToolResult {
+                name: name.clone(),
+                status: status.clone(),
+                success: *success,
+            }),
+            AgentEvent::Interrupted | AgentEvent::Done | AgentEvent::Error { .. } => {}
+        }
+    }
+
+    #[must_use]
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Messages the user sent, for reporting what was exported.
+    #[must_use]
+    pub fn user_messages(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|e| matches!(e, Entry::User(_)))
+            .count()
+    }
+
+    /// Renders the conversation as Markdown, with thinking in collapsed `<details>` blocks
+    /// and tool calls in code blocks.
+    #[must_use]
+    pub fn to_markdown(&self, title: &str) -> String {
+        let mut out = format!("# {title}\n");
+        for entry in &self.entries {
+            match entry {
+                Entry::User(text) => {
+                    let _ = write!(out, "\n## User\n\n{}\n", text.trim_end());
+                }
+                Entry::Assistant { thinking, content } => {
+                    out.push_str("\n## Assistant\n");
+                    if !thinking.trim().is_empty() {
+                        let fence = fence_for(thinking);
+                        let _ = write!(
+                            out,
+                            "\n<details>\n<summary>Thinking</summary>\n\n{fence}\n{}\n{fence}\n\n</details>\n",
+                            thinking.trim()
+                        );
+                    }
+                    if !content.trim().is_empty() {
+                        let _ = write!(out, "\n{}\n", content.trim_end());
+                    }
+                }
+                Entry::ToolCall { name, arg } => {
+                    let call = format!("TOOL: {name} {arg}");
+                    let fence = fence_for(&call);
+                    let _ = write!(out, "\n{fence}\n{}\n{fence}\n", call.trim_end());
+                }
+                Entry::ToolResult {
+                    status, success, ..
+                } => {
+                    let mark = if *success { "✓" } else { "✗" };
+                    let _ = write!(out, "\n> {mark} {}\n", status.replace('\n', "\n> "));
+                }
+            }
+        }
+        out
+    }
+
+    /// Renders the conversation as a standalone HTML page.
+    #[must_use]
+    pub fn to_html(&self, title: &str) -> String {
+        let mut body = String::new();
+        for entry in &self.entries {
+            match entry {
+                Entry::User(text) => {
+                    let _ = write!(
+                        body,
+                        "<section class=\"user\"><h2>User</h2><div class=\"text\">{}</div></section>\n",
+                        escape(text.trim_end())
+                    );
+                }
+                Entry::Assistant { thinking, content } => {
+                    body.push_str("<section class=\"assistant\"><h2>Assistant</h2>");
+                    if !thinking.trim().is_empty() {
+                        let _ = write!(
+                            body,
+                            "<details><summary>Thinking</summary><div class=\"text\">{}</div></details>",
+                            escape(thinking.trim())
+                        );
+                    }
+                    if !content.trim().is_empty() {
+                        let _ = write!(
+                            body,
+                            "<div class=\"text\">{}</div>",
+                            escape(content.trim_end())
+                        );
+                    }
+                    body.push_str("</section>\n");
+                }
+                Entry::ToolCall { name, arg } => {
+                    let _ = write!(
+                        body,
+                        "<pre class=\"tool\">TOOL: {} {}</pre>\n",
+                        escape(name),
+                        escape(arg.trim_end())
+                    );
+                }
+                Entry::ToolResult {
+                    status, success, ..
+                } => {
+                    let class = if *success { "ok" } else { "failed" };
+                    let _ = write!(body, "<p class=\"result {class}\">{}</p>\n", escape(status));
+                }
+            }
+        }
+        let title = escape(title);
+        format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n{body}</body>\n</html>\n"
+        )
+    }
+}
+
+const STYLE: &str = "body{font-family:system-ui,sans-serif;max-width:50rem;margin:2rem auto;padding:0 1rem;line-height:1.5}\
+section{border-radius:.5rem;padding:.25rem 1rem;margin:1rem 0}\
+.user{background:#eef4ff}.assistant{background:#f6f6f6}\
+h2{font-size:.9rem;text-transform:uppercase;color:#666}\
+.text{white-space:pre-wrap}\
+details{color:#666;margin-bottom:.5rem}\
+pre.tool{background:#222;color:#eee;padding:.5rem 1rem;border-radius:.5rem;overflow-x:auto}\
+.result{font-family:monospace;margin:.25rem 0}.ok{color:#2a7a2a}.failed{color:#b22}";
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,100 @@
+use anyhow::{Result, bail};
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex, PoisonError};
+use std::time::SystemTime;
+use tokio::sync::OwnedMutexGuard;
+
+/// One lock per file the tools change, so that edits to it run one after another.
+static LOCKS: LazyLock<Mutex<HashMap<PathBuf, Arc<tokio::sync::Mutex<()>>>>> =
+    LazyLock::new(Mutex::default);
+
+/// The state of each file when the model last read it or a tool last wrote it.
+static SEEN: LazyLock<Mutex<HashMap<PathBuf, Stamp>>> = LazyLock::new(Mutex::default);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Stamp {
+    modified: Option<SystemTime>,
+    len: u64,
+    hash: u64,
+}
+
+/// Waits for any other tool call changing `path` to finish, then holds it until the
+/// guard is dropped.
+pub async fn lock(path: &Path) -> OwnedMutexGuard<()> {
+    let lock = LOCKS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .entry(key(path))
+        .or_default()
+        .clone();
+    lock.lock_owned().await
+}
+
+/// Notes `content` as what the model has seen of `path`, after reading or writing it.
+pub fn record(path: &Path, content: &[u8]) {
+    let metadata = std::fs::metadata(path).ok();
+    let stamp = Stamp {
+        modified: metadata.as_ref().and_then(|m| m.modified().ok()),
+        len: content.len() as u64,
+        hash: hash(content),
+    };
+    SEEN.lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(key(path), stamp);
+}
+
+/// Forgets `path`, as after it was deleted or moved away.
+pub fn forget(path: &Path) {
+    SEEN.lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .remove(&key(path));
+}
+
+/// Checks that `path` is as the model last saw it. Files it has not read are not checked,
+/// and neither are files whose modification time changed but whose content did not.
+///
+/// # Errors
+/// Returns an error if the file was changed or deleted since it was last read.
+pub fn check_unchanged(path: &Path) -> Result<()> {
+    let key = key(path);
+    let Some(seen) = SEEN
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&key)
+        .copied()
+    else {
+        return Ok(());
+    };
+    let Ok(metadata) = std::fs::metadata(path) else {
+        bail!(
+            "{} was deleted since it was last read; check with list_files before changing it",
+            path.display()
+        );
+    };
+    if metadata.len() == seen.len && metadata.modified().ok() == seen.modified {
+        return Ok(());
+    }
+    if let Ok(content) = std::fs::read(path)
+        && hash(&content) == seen.hash
+    {
+        record(path, &content);
+        return Ok(());
+    }
+    bail!(
+        "{} was changed since it was last read, by another program or tool call; read it again before changing it",
+        path.display()
+    )
+}
+
+fn key(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn hash(content: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
@@ -0,0 +1,247 @@
+use crate::diagnostics::{self, Diagnostic};
+use std::fmt::Write;
+use std::path::Path;
+
+/// Rounds of fixes `deepseek fix-tests` and `deepseek fix-build` let the model make when
+/// `--max-rounds` is not given.
+pub const DEFAULT_MAX_ROUNDS: u32 = 5;
+
+/// Bytes of output sent to the model; longer output keeps its end, where the test runners
+/// sum up the failures.
+const MAX_OUTPUT_BYTES: usize = 16_000;
+
+/// The test command of a project, by the file that marks its kind.
+const TEST_COMMANDS: [(&str, &str); 6] = [
+    ("Cargo.toml", "cargo test"),
+    ("go.mod", "go test ./..."),
+    ("package.json", "npm test"),
+    ("pyproject.toml", "pytest"),
+    ("setup.py", "pytest"),
+    ("pytest.ini", "pytest"),
+];
+
+/// The build command of a project, by the file that marks its kind.
+const BUILD_COMMANDS: [(&str, &str); 4] = [
+    ("Cargo.toml", "cargo check"),
+    ("go.mod", "go build ./..."),
+    ("tsconfig.json", "npx tsc --noEmit"),
+    ("package.json", "npm run build"),
+];
+
+/// What the model is asked to make pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixTarget {
+    /// `deepseek fix-tests`
+    Tests,
+    /// `deepseek fix-build`
+    Build,
+}
+
+impl FixTarget {
+    /// The subcommand that fixes it.
+    #[must_use]
+    pub fn subcommand(self) -> &'static str {
+        match self {
+            Self::Tests => "fix-tests",
+            Self::Build => "fix-build",
+        }
+    }
+
+    /// The command that runs the tests or the build of the project at `root`, when
+    /// `--cmd` is not given.
+    #[must_use]
+    pub fn detect_command(self, root: &Path) -> Option<&'static str> {
+        let commands = match self {
+            Self::Tests => &TEST_COMMANDS[..],
+            Self::Build => &BUILD_COMMANDS[..],
+        };
+        commands
+            .iter()
+            .find(|(marker, _)| root.join(marker).is_file())
+            .map(|(_, command)| *command)
+    }
+
+    /// The command that is run for `command`: for the build, cargo is asked for its
+    /// messages as JSON, so the model can be sent them without the rest of the output.
+    #[must_use]
+    pub fn run_command(self, command: &str) -> String {
+        match self {
+            Self::Tests => command.to_string(),
+            Self::Build => {
+                diagnostics::json_command(command).unwrap_or_else(|| command.to_string())
+            }
+        }
+    }
+}
+
+/// What one run of the test or build command gave.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandRun {
+    /// The exit code, or `None` if the command was killed by a signal
+    pub exit_code: Option<i32>,
+    /// Standard output followed by standard error
+    pub output: String,
+}
+
+impl CommandRun {
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+
+    /// The output as the model is sent it: in full if short, else its last lines.
+    #[must_use]
+    pub fn excerpt(&self) -> String {
+        excerpt(&self.output)
+    }
+
+    fn describe(&self) -> String {
+        match self.exit_code {
+            Some(0) => "passed".to_string(),
+            Some(code) => format!("failed (exit code {code})"),
+            None => "failed (killed by a signal)".to_string(),
+        }
+    }
+}
+
+fn excerpt(output: &str) -> String {
+    let output = output.trim_end();
+    if output.len() <= MAX_OUTPUT_BYTES {
+        return output.to_string();
+    }
+    let mut start = output.len() - MAX_OUTPUT_BYTES;
+    while !output.is_char_boundary(start) {
+        start += 1;
+    }
+    // Start at a whole line
+    let start = output[start..].find('\n').map_or(start, |i| start + i + 1);
+    let left_out = output[..start].lines().count();
+    format!("[{left_out} earlier lines left out]\n{}", &output[start..])
+}
+
+/// The errors as the model is sent them, the first ones up to the size of an excerpt,
+/// with a count of the rest and of the warnings, which are left out.
+fn render_errors(diagnostics: &[Diagnostic]) -> String {
+    let mut out = String::new();
+    let errors: Vec<&Diagnostic> = diagnostics.iter().filter(|d| d.is_error()).collect();
+    let mut shown = 0;
+    for error in &errors {
+        let rendered = error.render();
+        if shown > 0 && out.len() + rendered.len() > MAX_OUTPUT_BYTES {
+            break;
+        }
+        if shown > 0 {
+            out.push_str("\n\n");
+        }
+        out.push_str(&rendered);
+        shown += 1;
+    }
+    if shown < errors.len() {
+        let _ = write!(out, "\n\n[{} more errors left out]", errors.len() - shown);
+    }
+    let warnings = diagnostics.len() - errors.len();
+    if warnings > 0 {
+        let _ = write!(out, "\n\n[{warnings} warning(s) left out]");
+    }
+    out
+}
+
+/// The message asking for round `round` of fixes after `run` failed. For the build, the
+/// errors cargo gave as JSON are sent instead of the output when there are any.
+#[must_use]
+pub fn prompt(
+    target: FixTarget,
+    command: &str,
+    run: &CommandRun,
+    round: u32,
+    max_rounds: u32,
+) -> String {
+    let opening = if round == 1 {
+        format!("`{command}` fails.")
+    } else {
+        format!("`{command}` still fails after your changes.")
+    };
+    let (what, details) = match target {
+        FixTarget::Tests => ("Its output", run.excerpt()),
+        FixTarget::Build => {
+            let found = diagnostics::parse_cargo(&run.output);
+            if found.iter().any(Diagnostic::is_error) {
+                ("The errors", render_errors(&found))
+            } else {
+                (
+                    "Its output",
+                    excerpt(&diagnostics::without_messages(&run.output)),
+                )
+            }
+        }
+    };
+    let goal = match target {
+        FixTarget::Tests => {
+            "Find the cause and fix the code so the tests pass. Change a test only if the test itself is wrong, and say so when you do."
+        }
+        FixTarget::Build => "Fix the code so it builds.",
+    };
+    format!(
+        "{opening} {what}:\n\n```\n{details}\n```\n\n{goal} When you are done, stop; `{command}` will be run again for you (round {round} of {max_rounds})."
+    )
+}
+
+/// How `deepseek fix-tests` or `deepseek fix-build` went, printed when it ends.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FixSummary {
+    pub command: String,
+    /// Each run of the command, in order
+    pub runs: Vec<CommandRun>,
+    /// Why the loop ended before the tests passed or the rounds ran out, such as an
+    /// interruption
+    pub stopped: Option<String>,
+    /// `git diff --stat` of the working tree at the end, if it is a repository
+    pub diff_stat: Option<String>,
+}
+
+impl FixSummary {
+    #[must_use]
+    pub fn new(command: &str) -> Self {
+        Self {
+            command: command.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Whether the last run of the command passed.
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.runs.last().is_some_and(CommandRun::passed)
+    }
+
+    /// The rounds of fixes the model was asked for: one after each failed run that was
+    /// followed by another.
+    #[must_use]
+    pub fn rounds(&self) -> usize {
+        self.runs.len().saturating_sub(1)
+    }
+
+    #[must_use]
+    pub fn render(&self) -> String {
+        let command = &self.command;
+        let mut out = match (self.passed(), self.rounds()) {
+            (true, 0) => format!("`{command}` already passes; there was nothing to fix"),
+            (true, 1) => format!("`{command}` passes after 1 round of fixes"),
+            (true, rounds) => format!("`{command}` passes after {rounds} rounds of fixes"),
+            (false, _) => match &self.stopped {
+                Some(reason) => format!("`{command}` still fails; stopped: {reason}"),
+                None => format!(
+                    "`{command}` still fails after {} round(s) of fixes (see --max-rounds)",
+                    self.rounds()
+                ),
+            },
+        };
+        for (i, run) in self.runs.iter().enumerate() {
+            let _ = write!(out, "\n  run {}: {}", i + 1, run.describe());
+        }
+        if let Some(stat) = self.diff_stat.as_deref().filter(|s| !s.trim().is_empty()) {
+            let _ = write!(out, "\n\nChanges:\n{}", stat.trim_end());
+        }
+        out
+    }
+}
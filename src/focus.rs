@@ -0,0 +1,73 @@
+use anyhow::{Result, bail};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+
+// Shared with the tool handlers so that search defaults follow the focus
+static FOCUS_STACK: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Pushes a file or directory onto the focus stack.
+///
+/// # Errors
+/// Returns an error if the path does not exist.
+pub fn push(path: &str) -> Result<PathBuf> {
+    let path = PathBuf::from(path);
+    if !path.exists() {
+        bail!("No such file or directory: {}", path.display());
+    }
+    FOCUS_STACK
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(path.clone());
+    Ok(path)
+}
+
+/// Pops the most recent focus, returning it.
+pub fn pop() -> Option<PathBuf> {
+    FOCUS_STACK
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .pop()
+}
+
+/// Returns the whole stack, oldest first.
+#[must_use]
+pub fn stack() -> Vec<PathBuf> {
+    FOCUS_STACK
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Returns the active (most recent) focus.
+#[must_use]
+pub fn current() -> Option<PathBuf> {
+    stack().pop()
+}
+
+/// Directory that searching tools default to: the focused directory, the parent of the
+/// focused file, or the current directory when nothing is focused.
+#[must_use]
+pub fn search_root() -> PathBuf {
+    let Some(path) = current() else {
+        return PathBuf::from(".");
+    };
+    if path.is_dir() {
+        return path;
+    }
+    path.parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .map_or_else(|| PathBuf::from("."), Path::to_path_buf)
+}
+
+/// Appends a note about the active focus to a user prompt.
+#[must_use]
+pub fn annotate(prompt: &str) -> String {
+    match current() {
+        Some(path) => format!(
+            "{prompt}\n\n[Focus: {}. Work within {} unless asked otherwise.]",
+            path.display(),
+            search_root().display()
+        ),
+        None => prompt.to_string(),
+    }
+}
@@ -0,0 +1,213 @@
+use crate::schema;
+use anyhow::{Result, anyhow, bail};
+use serde_json::Value;
+
+/// A format replies are asked to follow, set with `--format` or `/format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseFormat {
+    Json,
+    Markdown,
+    Plain,
+}
+
+impl ResponseFormat {
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "markdown" | "md" => Some(Self::Markdown),
+            "plain" | "text" => Some(Self::Plain),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Markdown => "markdown",
+            Self::Plain => "plain",
+        }
+    }
+
+    fn instruction(self) -> &'static str {
+        match self {
+            Self::Json => {
+                "Your final reply must be a single valid JSON value and nothing else: no prose and no code fences."
+            }
+            Self::Markdown => "Format your final reply as Markdown.",
+            Self::Plain => {
+                "Format your final reply as plain text, without Markdown markup or code fences."
+            }
+        }
+    }
+}
+
+/// A preset for how replies are written, set with `/style`. Unlike the format, it changes
+/// the tone and depth of the reply rather than its markup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Concise,
+    Detailed,
+    /// Explained as to a junior developer
+    Junior,
+    /// Code and nothing else
+    CodeOnly,
+}
+
+impl Style {
+    pub const ALL: [Self; 4] = [Self::Concise, Self::Detailed, Self::Junior, Self::CodeOnly];
+
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "concise" | "short" => Some(Self::Concise),
+            "detailed" | "long" => Some(Self::Detailed),
+            "explain-like-junior" | "junior" | "eli5" => Some(Self::Junior),
+            "code-only" | "code" => Some(Self::CodeOnly),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Concise => "concise",
+            Self::Detailed => "detailed",
+            Self::Junior => "explain-like-junior",
+            Self::CodeOnly => "code-only",
+        }
+    }
+
+    /// The note sent with each message while the style is on.
+    #[must_use]
+    pub fn instruction(self) -> &'static str {
+        match self {
+            Self::Concise => {
+                "Reply style: concise. Answer in as few words as will do: no preamble, no restating the question and no summary at the end."
+            }
+            Self::Detailed => {
+                "Reply style: detailed. Explain your reasoning, the alternatives you considered and any caveats, and show the relevant code in full."
+            }
+            Self::Junior => {
+                "Reply style: explain like to a junior developer. Define terms as they come up, go step by step, say why as well as what, and point out the usual pitfalls."
+            }
+            Self::CodeOnly => {
+                "Reply style: code only. Reply with the code alone, in fenced blocks, with comments in the code for anything that needs saying and no prose around it."
+            }
+        }
+    }
+
+    /// Names accepted by `/style`, for error messages.
+    #[must_use]
+    pub fn names() -> String {
+        Self::ALL.map(Self::name).join(", ")
+    }
+}
+
+/// Builds the note sent with each message to ask for the given limits, if there are any.
+/// The web chat API has no parameters for these, so they are requested in words.
+#[must_use]
+pub fn constraints_note(
+    format: Option<ResponseFormat>,
+    max_output_tokens: Option<u32>,
+    schema: Option<&Value>,
+) -> Option<String> {
+    let mut parts = Vec::new();
+    if let Some(format) = format {
+        parts.push(format.instruction().to_string());
+    }
+    if let Some(schema) = schema {
+        parts.push(format!("The JSON must match this JSON Schema: {schema}"));
+    }
+    if let Some(tokens) = max_output_tokens {
+        // Roughly 0.75 words per token
+        parts.push(format!(
+            "Keep your final reply under {tokens} tokens (about {} words).",
+            u64::from(tokens) * 3 / 4
+        ));
+    }
+    (!parts.is_empty()).then(|| parts.join(" "))
+}
+
+/// Extracts a JSON value from a reply that was asked to be JSON, repairing the usual
+/// mistakes: code fences, prose around the value and trailing commas.
+///
+/// # Errors
+/// Returns an error if no valid JSON can be recovered.
+pub fn repair_json(reply: &str) -> Result<String> {
+    let trimmed = reply.trim();
+    if serde_json::from_str::<serde_json::Value>(trimmed).is_ok() {
+        return Ok(trimmed.to_string());
+    }
+    let mut candidate = fenced_block(trimmed).unwrap_or(trimmed);
+    if let Some(start) = candidate.find(['{', '['])
+        && let Some(end) = candidate.rfind(['}', ']'])
+        && start < end
+    {
+        candidate = &candidate[start..=end];
+    }
+    let candidate = remove_trailing_commas(candidate);
+    match serde_json::from_str::<serde_json::Value>(&candidate) {
+        Ok(value) => Ok(serde_json::to_string_pretty(&value)?),
+        Err(e) => Err(anyhow!("The reply is not valid JSON: {e}")),
+    }
+}
+
+/// Recovers the JSON in a reply and checks it against `schema`, if one is given.
+///
+/// # Errors
+/// Returns an error describing what is wrong, suitable for asking the model to fix it.
+pub fn structured_reply(reply: &str, schema: Option<&Value>) -> Result<String> {
+    let json = repair_json(reply)?;
+    let Some(schema) = schema else {
+        return Ok(json);
+    };
+    let value: Value = serde_json::from_str(&json)?;
+    let errors = schema::validate(schema, &value);
+    if !errors.is_empty() {
+        bail!(
+            "The reply does not match the schema:\n- {}",
+            errors.join("\n- ")
+        );
+    }
+    Ok(json)
+}
+
+/// The contents of the first fenced code block in `text`.
+fn fenced_block(text: &str) -> Option<&str> {
+    let start = text.find("```")?;
+    let after = &text[start + 3..];
+    // Skip the info string, e.g. `json`
+    let body_start = after.find('\n')? + 1;
+    let body = &after[body_start..];
+    let end = body.find("```")?;
+    Some(&body[..end])
+}
+
+/// Drops commas directly before a closing bracket, leaving strings untouched.
+fn remove_trailing_commas(json: &str) -> String {
+    let mut out = String::with_capacity(json.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in json.chars() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if matches!(c, '}' | ']') {
+            let kept = out.trim_end().len();
+            if out[..kept].ends_with(',') {
+                out.truncate(kept - 1);
+            }
+        }
+        out.push(c);
+    }
+    out
+}
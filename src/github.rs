@@ -0,0 +1,445 @@
+use crate::config::{self, GithubConfig};
+use crate::http;
+use anyhow::{Result, anyhow, bail};
+use serde_json::{Value, json};
+use std::fmt::Write;
+use std::process::Command;
+use std::time::Duration;
+
+/// Environment variable the token is read from when `[github]` names none.
+pub const TOKEN_VAR: &str = "GITHUB_TOKEN";
+
+/// GitHub's REST API, when `[github] api_url` does not point at an Enterprise server.
+pub const API_URL: &str = "https://api.github.com";
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Comments shown with an issue or pull request; the newest are kept.
+const MAX_COMMENTS: usize = 30;
+
+/// Changed files listed for a pull request.
+const MAX_FILES: usize = 100;
+
+/// An issue or pull request in a repository on GitHub.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    pub owner: String,
+    pub repo: String,
+    pub number: u64,
+}
+
+impl Reference {
+    /// Reads `owner/repo#12`, an issue or pull request URL on github.com, or `#12` or `12`
+    /// in `default_repo`, which is usually [`origin_repo`].
+    ///
+    /// # Errors
+    /// Returns an error if the text is none of those, or a bare number is given without a
+    /// default repository.
+    pub fn parse(text: &str, default_repo: Option<(&str, &str)>) -> Result<Self> {
+        let text = text.trim();
+        let invalid = || {
+            anyhow!(
+                "Not an issue or pull request: {text} (expected owner/repo#12, a GitHub URL or #12)"
+            )
+        };
+        let number = |n: &str| n.parse::<u64>().map_err(|_| invalid());
+        let path = text
+            .strip_prefix("https://")
+            .or_else(|| text.strip_prefix("http://"))
+            .map(|path| path.strip_prefix("www.").unwrap_or(path));
+        if let Some(path) = path {
+            let rest = path.strip_prefix("github.com/").ok_or_else(invalid)?;
+            return match rest.split('/').collect::<Vec<_>>().as_slice() {
+                [owner, repo, "pull" | "issues", n, ..]
+                    if !owner.is_empty() && !repo.is_empty() =>
+                {
+                    Ok(Self::new(owner, repo, number(n)?))
+                }
+                _ => Err(invalid()),
+            };
+        }
+        if let Some((repo, n)) = text.split_once('#')
+            && let Some((owner, repo)) = repo.split_once('/')
+            && !owner.is_empty()
+            && !repo.is_empty()
+        {
+            return Ok(Self::new(owner, repo, number(n)?));
+        }
+        let n = number(text.strip_prefix('#').unwrap_or(text))?;
+        let (owner, repo) = default_repo.ok_or_else(|| {
+            anyhow!("No GitHub repository to look #{n} up in; give it as owner/repo#{n}")
+        })?;
+        Ok(Self::new(owner, repo, n))
+    }
+
+    /// Like [`Reference::parse`], with the repository of the `origin` remote for a bare
+    /// number.
+    ///
+    /// # Errors
+    /// Returns an error if the text is not a reference, or is a bare number and `origin`
+    /// is not on GitHub.
+    pub fn resolve(text: &str) -> Result<Self> {
+        let origin = origin_repo();
+        let origin = origin
+            .as_ref()
+            .map(|(owner, repo)| (owner.as_str(), repo.as_str()));
+        Self::parse(text, origin)
+    }
+
+    fn new(owner: &str, repo: &str, number: u64) -> Self {
+        Self {
+            owner: owner.to_string(),
+            repo: repo.trim_end_matches(".git").to_string(),
+            number,
+        }
+    }
+
+    fn path(&self, kind: &str) -> String {
+        format!("/repos/{}/{}/{kind}/{}", self.owner, self.repo, self.number)
+    }
+}
+
+/// The repository the `origin` remote of the working directory points at, if it is on
+/// GitHub.
+#[must_use]
+pub fn origin_repo() -> Option<(String, String)> {
+    let output = Command::new("git")
+        .args(["remote", "get-url", "origin"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_remote(String::from_utf8_lossy(&output.stdout).trim())
+}
+
+/// The owner and name of a GitHub repository from its remote URL, in the HTTPS or SSH
+/// form.
+#[must_use]
+pub fn parse_remote(url: &str) -> Option<(String, String)> {
+    let path = url
+        .strip_prefix("git@github.com:")
+        .or_else(|| url.strip_prefix("ssh://git@github.com/"))
+        .or_else(|| url.strip_prefix("https://github.com/"))
+        .or_else(|| url.strip_prefix("http://github.com/"))?;
+    let (owner, repo) = path.trim_end_matches('/').split_once('/')?;
+    let repo = repo.trim_end_matches(".git");
+    (!owner.is_empty() && !repo.is_empty() && !repo.contains('/'))
+        .then(|| (owner.to_string(), repo.to_string()))
+}
+
+/// The token from `[github]`: given there, or in the variable it names, or failing both
+/// in [`TOKEN_VAR`].
+#[must_use]
+pub fn token(config: &GithubConfig) -> Option<String> {
+    config
+        .token
+        .clone()
+        .or_else(|| {
+            config
+                .token_env
+                .as_ref()
+                .and_then(|var| std::env::var(var).ok())
+        })
+        .or_else(|| std::env::var(TOKEN_VAR).ok())
+        .map(|token| token.trim().to_string())
+        .filter(|token| !token.is_empty())
+}
+
+/// A client for GitHub's REST API, set up from `[github]`.
+pub struct Client {
+    http: reqwest::Client,
+    base: String,
+    token: Option<String>,
+}
+
+impl Client {
+    /// # Errors
+    /// Returns an error if the HTTP client cannot be built.
+    pub fn from_config() -> Result<Self> {
+        let config = &config::get().github;
+        let http = http::client_builder()?
+            .timeout(TIMEOUT)
+            .user_agent("deepseek-cli")
+            .build()
+            .map_err(|e| anyhow!("Failed to create HTTP client: {e}"))?;
+        Ok(Self {
+            http,
+            base: config
+                .api_url
+                .as_deref()
+                .filter(|url| !url.is_empty())
+                .unwrap_or(API_URL)
+                .trim_end_matches('/')
+                .to_string(),
+            token: token(config),
+        })
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self
+            .http
+            .request(method, format!("{}{path}", self.base))
+            .header("Accept", "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28");
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let response = request
+            .send()
+            .await
+            .map_err(|e| anyhow!("Network error: {e}"))?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let body: Value = response
+            .text()
+            .await
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default();
+        let message = body["message"].as_str().unwrap_or("no message");
+        let hint = match (status.as_u16(), &self.token) {
+            (401 | 403 | 404, None) => {
+                format!(
+                    " (set [github] token_env or {TOKEN_VAR} for private repositories and to comment)"
+                )
+            }
+            _ => String::new(),
+        };
+        bail!("GitHub returned {status}: {message}{hint}")
+    }
+
+    async fn get(&self, path: &str) -> Result<Value> {
+        json_body(self.send(self.request(reqwest::Method::GET, path)).await?).await
+    }
+
+    /// The items of a list, from its first page of up to 100.
+    async fn list(&self, path: &str) -> Result<Vec<Value>> {
+        let separator = if path.contains('?') { '&' } else { '?' };
+        match self.get(&format!("{path}{separator}per_page=100")).await? {
+            Value::Array(items) => Ok(items),
+            _ => bail!("Invalid response from GitHub: expected a list"),
+        }
+    }
+
+    async fn post(&self, path: &str, body: &Value) -> Result<Value> {
+        let request = self
+            .request(reqwest::Method::POST, path)
+            .header("Content-Type", "application/json")
+            .body(body.to_string());
+        json_body(self.send(request).await?).await
+    }
+
+    /// An issue with its comments, as text.
+    ///
+    /// # Errors
+    /// Returns an error if GitHub cannot be reached or refuses the request.
+    pub async fn issue(&self, issue: &Reference) -> Result<String> {
+        let body = self.get(&issue.path("issues")).await?;
+        let comments = self
+            .list(&format!("{}/comments", issue.path("issues")))
+            .await?;
+        Ok(format_issue(&body, &comments))
+    }
+
+    /// A pull request with its changed files and comments, as text.
+    ///
+    /// # Errors
+    /// Returns an error if GitHub cannot be reached or refuses the request.
+    pub async fn pull_request(&self, pr: &Reference) -> Result<String> {
+        let body = self.get(&pr.path("pulls")).await?;
+        let files = self.list(&format!("{}/files", pr.path("pulls"))).await?;
+        let mut comments = self
+            .list(&format!("{}/comments", pr.path("issues")))
+            .await?;
+        comments.extend(self.list(&format!("{}/comments", pr.path("pulls"))).await?);
+        comments.sort_by(|a, b| a["created_at"].as_str().cmp(&b["created_at"].as_str()));
+        Ok(format_pull_request(&body, &files, &comments))
+    }
+
+    /// A pull request's changes as a unified diff.
+    ///
+    /// # Errors
+    /// Returns an error if GitHub cannot be reached or refuses the request.
+    pub async fn pull_request_diff(&self, pr: &Reference) -> Result<String> {
+        let request = self
+            .request(reqwest::Method::GET, &pr.path("pulls"))
+            .header("Accept", "application/vnd.github.diff");
+        self.send(request)
+            .await?
+            .text()
+            .await
+            .map_err(|e| anyhow!("Failed to read response body: {e}"))
+    }
+
+    /// Comments on a pull request, on the given line of a file in its latest commit or
+    /// on the conversation as a whole, returning the comment's URL.
+    ///
+    /// # Errors
+    /// Returns an error if the text is empty, no token is set or GitHub refuses the
+    /// comment, such as for a line outside the diff.
+    pub async fn comment(
+        &self,
+        pr: &Reference,
+        line: Option<(&str, u64)>,
+        text: &str,
+    ) -> Result<String> {
+        if text.trim().is_empty() {
+            bail!("The comment is empty; put its text on the lines after the tool line");
+        }
+        if self.token.is_none() {
+            bail!("Commenting needs a GitHub token; set [github] token_env or {TOKEN_VAR}");
+        }
+        let comment = match line {
+            None => {
+                let path = format!("{}/comments", pr.path("issues"));
+                self.post(&path, &json!({ "body": text })).await?
+            }
+            Some((file, line)) => {
+                let head = self.get(&pr.path("pulls")).await?;
+                let commit = head["head"]["sha"].as_str().ok_or_else(|| {
+                    anyhow!("GitHub did not say what the pull request's latest commit is")
+                })?;
+                let body = json!({
+                    "body": text,
+                    "commit_id": commit,
+                    "path": file,
+                    "line": line,
+                    "side": "RIGHT",
+                });
+                let path = format!("{}/comments", pr.path("pulls"));
+                self.post(&path, &body).await?
+            }
+        };
+        Ok(comment["html_url"].as_str().unwrap_or_default().to_string())
+    }
+}
+
+async fn json_body(response: reqwest::Response) -> Result<Value> {
+    let text = response
+        .text()
+        .await
+        .map_err(|e| anyhow!("Failed to read response body: {e}"))?;
+    serde_json::from_str(&text).map_err(|e| anyhow!("Invalid response from GitHub: {e}"))
+}
+
+/// Renders an issue and its comments from the API's JSON.
+#[must_use]
+pub fn format_issue(issue: &Value, comments: &[Value]) -> String {
+    let mut out = header(issue, "Issue");
+    out.push_str(&body_section(issue));
+    out.push_str(&comments_section(comments));
+    out
+}
+
+/// Renders a pull request, its changed files and its comments from the API's JSON.
+#[must_use]
+pub fn format_pull_request(pr: &Value, files: &[Value], comments: &[Value]) -> String {
+    let mut out = header(pr, "Pull request");
+    let _ = write!(
+        out,
+        "Branches: {} <- {}",
+        pr["base"]["ref"].as_str().unwrap_or("?"),
+        pr["head"]["label"].as_str().unwrap_or("?")
+    );
+    if pr["merged"].as_bool() == Some(true) {
+        out.push_str(" (merged)");
+    } else if pr["draft"].as_bool() == Some(true) {
+        out.push_str(" (draft)");
+    }
+    out.push('\n');
+    out.push_str(&body_section(pr));
+    let _ = write!(
+        out,
+        "\nChanged files ({}, +{} -{}):\n",
+        pr["changed_files"].as_u64().unwrap_or(files.len() as u64),
+        pr["additions"].as_u64().unwrap_or_default(),
+        pr["deletions"].as_u64().unwrap_or_default()
+    );
+    for file in files.iter().take(MAX_FILES) {
+        let _ = writeln!(
+            out,
+            "- {} ({}, +{} -{})",
+            file["filename"].as_str().unwrap_or("?"),
+            file["status"].as_str().unwrap_or("modified"),
+            file["additions"].as_u64().unwrap_or_default(),
+            file["deletions"].as_u64().unwrap_or_default()
+        );
+    }
+    if files.len() > MAX_FILES {
+        let _ = writeln!(out, "... and {} more", files.len() - MAX_FILES);
+    }
+    out.push_str(&comments_section(comments));
+    out
+}
+
+fn header(item: &Value, kind: &str) -> String {
+    let mut out = format!(
+        "{kind} #{}: {}\n",
+        item["number"].as_u64().unwrap_or_default(),
+        item["title"].as_str().unwrap_or_default()
+    );
+    let _ = writeln!(
+        out,
+        "State: {}, by {}, opened {}",
+        item["state"].as_str().unwrap_or("unknown"),
+        login(&item["user"]),
+        item["created_at"].as_str().unwrap_or("?")
+    );
+    let labels: Vec<&str> = item["labels"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|label| label["name"].as_str())
+        .collect();
+    if !labels.is_empty() {
+        let _ = writeln!(out, "Labels: {}", labels.join(", "));
+    }
+    if let Some(url) = item["html_url"].as_str() {
+        let _ = writeln!(out, "URL: {url}");
+    }
+    out
+}
+
+fn body_section(item: &Value) -> String {
+    match item["body"].as_str().map(str::trim) {
+        Some(body) if !body.is_empty() => format!("\n{body}\n"),
+        _ => "\n(no description)\n".to_string(),
+    }
+}
+
+fn comments_section(comments: &[Value]) -> String {
+    if comments.is_empty() {
+        return String::new();
+    }
+    let mut out = format!("\nComments ({}):\n", comments.len());
+    let skipped = comments.len().saturating_sub(MAX_COMMENTS);
+    if skipped > 0 {
+        let _ = writeln!(out, "({skipped} older comments left out)");
+    }
+    for comment in &comments[skipped..] {
+        let at = match (comment["path"].as_str(), comment["line"].as_u64()) {
+            (Some(path), Some(line)) => format!(" on {path}:{line}"),
+            (Some(path), None) => format!(" on {path}"),
+            _ => String::new(),
+        };
+        let _ = write!(
+            out,
+            "\n--- {}{at}, {}\n{}\n",
+            login(&comment["user"]),
+            comment["created_at"].as_str().unwrap_or("?"),
+            comment["body"].as_str().unwrap_or_default().trim()
+        );
+    }
+    out
+}
+
+fn login(user: &Value) -> &str {
+    user["login"].as_str().unwrap_or("unknown")
+}
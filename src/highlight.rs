@@ -0,0 +1,136 @@
+use crate::hyperlink;
+use colored::Colorize;
+use std::sync::LazyLock;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::parsing::SyntaxSet;
+use syntect::util::as_24_bit_terminal_escaped;
+
+const THEME_NAME: &str = "base16-ocean.dark";
+
+static SYNTAX_SET: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+static THEME_SET: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
+
+fn theme() -> Option<&'static Theme> {
+    THEME_SET
+        .themes
+        .get(THEME_NAME)
+        .or_else(|| THEME_SET.themes.values().next())
+}
+
+/// Colors streamed response text, syntax-highlighting fenced code blocks by language.
+///
+/// Prose is passed through as soon as it arrives. Code is highlighted a line at a time,
+/// and the start of a line is held back only while it could still turn out to be a fence.
+pub struct StreamHighlighter {
+    /// Part of the current line that has not been emitted yet
+    pending: String,
+    /// Whether an earlier part of the current line was already emitted
+    line_started: bool,
+    /// Highlighter for the code block being streamed, if inside one
+    code: Option<HighlightLines<'static>>,
+    in_code: bool,
+}
+
+impl Default for StreamHighlighter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreamHighlighter {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            pending: String::new(),
+            line_started: false,
+            code: None,
+            in_code: false,
+        }
+    }
+
+    /// Feeds a chunk of streamed text and returns what can be printed now.
+    pub fn push(&mut self, text: &str) -> String {
+        let mut out = String::new();
+        for piece in text.split_inclusive('\n') {
+            self.pending.push_str(piece);
+            if self.pending.ends_with('\n') {
+                let line = std::mem::take(&mut self.pending);
+                out.push_str(&self.render_line(&line));
+                self.line_started = false;
+            }
+        }
+        if !self.in_code && !self.pending.is_empty() && !self.could_be_fence() {
+            // A word that is still arriving could be the start of a path or URL to link
+            let ready = if hyperlink::enabled() {
+                self.pending
+                    .char_indices()
+                    .rev()
+                    .find(|(_, c)| c.is_whitespace())
+                    .map_or(0, |(i, c)| i + c.len_utf8())
+            } else {
+                self.pending.len()
+            };
+            if ready > 0 {
+                let text: String = self.pending.drain(..ready).collect();
+                out.push_str(&hyperlink::linkify(&text).bright_white().to_string());
+                self.line_started = true;
+            }
+        }
+        out
+    }
+
+    /// Flushes anything held back, e.g. an unterminated last line, and resets the state.
+    pub fn finish(&mut self) -> String {
+        let line = std::mem::take(&mut self.pending);
+        let out = if line.is_empty() {
+            String::new()
+        } else {
+            self.render_line(&line)
+        };
+        self.line_started = false;
+        self.code = None;
+        self.in_code = false;
+        out
+    }
+
+    fn could_be_fence(&self) -> bool {
+        let line = self.pending.trim_start();
+        !self.line_started && ("```".starts_with(line) || line.starts_with("```"))
+    }
+
+    fn render_line(&mut self, line: &str) -> String {
+        let trimmed = line.trim_start();
+        if !self.line_started && trimmed.starts_with("```") {
+            if self.in_code {
+                self.in_code = false;
+                self.code = None;
+            } else {
+                let language = trimmed.trim_start_matches('`').trim();
+                self.in_code = true;
+                self.code = start_highlighting(language);
+            }
+            return line.dimmed().to_string();
+        }
+        if !self.in_code {
+            return hyperlink::linkify(line).bright_white().to_string();
+        }
+        let Some(highlighter) = self.code.as_mut() else {
+            return line.to_string();
+        };
+        match highlighter.highlight_line(line, &SYNTAX_SET) {
+            Ok(ranges) => format!("{}\x1b[0m", as_24_bit_terminal_escaped(&ranges, false)),
+            Err(_) => line.to_string(),
+        }
+    }
+}
+
+fn start_highlighting(language: &str) -> Option<HighlightLines<'static>> {
+    if !colored::control::SHOULD_COLORIZE.should_colorize() {
+        return None;
+    }
+    let syntax = SYNTAX_SET
+        .find_syntax_by_token(language)
+        .unwrap_or_else(|| SYNTAX_SET.find_syntax_plain_text());
+    Some(HighlightLines::new(syntax, theme()?))
+}
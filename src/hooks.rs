@@ -0,0 +1,224 @@
+use anyhow::{Result, anyhow};
+use rhai::{AST, CallFnOptions, Dynamic, Engine, FuncArgs, Map, Scope};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+/// Operations one hook may take before it is stopped, so that a script stuck in a loop
+/// cannot hang the session.
+pub const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// The hook scripts in the config directory, compiled once.
+static CURRENT: LazyLock<Result<Hooks, String>> = LazyLock::new(|| match dir() {
+    Some(dir) => Hooks::load(&dir).map_err(|e| e.to_string()),
+    None => Ok(Hooks::default()),
+});
+
+/// Where hook scripts are read from: every `*.rhai` file in
+/// `~/.config/deepseek-cli/hooks`, in the order of their names.
+#[must_use]
+pub fn dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("deepseek-cli/hooks"))
+}
+
+/// The loaded hook scripts.
+///
+/// # Errors
+/// Returns an error if one of them could not be read or compiled.
+pub fn current() -> Result<&'static Hooks> {
+    CURRENT.as_ref().map_err(|e| anyhow!("{e}"))
+}
+
+/// What the `on_tool_call` hooks made of a call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToolCallHook {
+    /// Run the call as the model made it
+    Run,
+    /// Run the call with this argument instead
+    Replace(String),
+    /// Refuse the call, for this reason
+    Refuse(String),
+}
+
+/// Scripts that are called as the chat goes on, each defining any of these functions:
+///
+/// - `on_user_message(text)`: return a string to send in place of the message
+/// - `on_tool_call(name, arg)`: return a string or `#{ arg: "..." }` to run the call with
+///   that argument, `false` or `#{ refuse: "why" }` to refuse it, or nothing to run it as it
+///   is
+/// - `on_tool_result(name, status, success)`
+/// - `on_assistant_message(text)`
+///
+/// Where several scripts define a hook, they are called in turn, each given what the one
+/// before returned.
+#[derive(Default)]
+pub struct Hooks {
+    engine: Engine,
+    scripts: Vec<Script>,
+}
+
+struct Script {
+    path: PathBuf,
+    ast: AST,
+}
+
+impl Hooks {
+    /// Compiles the `*.rhai` files in `dir`. A directory that does not exist has no hooks.
+    ///
+    /// # Errors
+    /// Returns an error naming the file if one cannot be read or compiled.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Ok(Self::default());
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "rhai") && path.is_file())
+            .collect();
+        paths.sort();
+        let scripts = paths
+            .into_iter()
+            .map(|path| {
+                let source = std::fs::read_to_string(&path)
+                    .map_err(|e| anyhow!("Failed to read {}: {e}", path.display()))?;
+                Ok((path, source))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Self::from_scripts(scripts)
+    }
+
+    /// Compiles scripts given with the path they are reported under.
+    ///
+    /// # Errors
+    /// Returns an error naming the script that does not compile.
+    pub fn from_scripts(scripts: impl IntoIterator<Item = (PathBuf, String)>) -> Result<Self> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        let scripts = scripts
+            .into_iter()
+            .map(|(path, source)| {
+                let ast = engine
+                    .compile(&source)
+                    .map_err(|e| anyhow!("Invalid hook script {}: {e}", path.display()))?;
+                Ok(Script { path, ast })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { engine, scripts })
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.scripts.is_empty()
+    }
+
+    /// The message to send in place of `text`, if a hook changed it.
+    ///
+    /// # Errors
+    /// Returns an error if a hook fails or returns something other than a string.
+    pub fn on_user_message(&self, text: &str) -> Result<Option<String>> {
+        let mut current = None::<String>;
+        for script in self.defining("on_user_message", 1) {
+            let text = current.clone().unwrap_or_else(|| text.to_string());
+            let result = self.call(script, "on_user_message", (text,))?;
+            if !result.is_unit() {
+                current = Some(result.into_string().map_err(|kind| {
+                    anyhow!(
+                        "on_user_message in {} returned {kind}, not a string",
+                        script.path.display()
+                    )
+                })?);
+            }
+        }
+        Ok(current)
+    }
+
+    /// Whether to run a call to `name` with `arg`, and with what argument.
+    ///
+    /// # Errors
+    /// Returns an error if a hook fails or returns something it may not.
+    pub fn on_tool_call(&self, name: &str, arg: &str) -> Result<ToolCallHook> {
+        let mut current = arg.to_string();
+        for script in self.defining("on_tool_call", 2) {
+            let result = self.call(script, "on_tool_call", (name.to_string(), current.clone()))?;
+            let refused = || format!("refused by the hook in {}", script.path.display());
+            if result.is_unit() || result.as_bool() == Ok(true) {
+                continue;
+            }
+            if result.as_bool() == Ok(false) {
+                return Ok(ToolCallHook::Refuse(refused()));
+            }
+            if result.is_string() {
+                current = result.into_string().unwrap_or_default();
+                continue;
+            }
+            let type_name = result.type_name();
+            let Some(map) = result.try_cast::<Map>() else {
+                anyhow::bail!(
+                    "on_tool_call in {} returned {type_name}; expected a string, a bool or a map",
+                    script.path.display()
+                );
+            };
+            if let Some(reason) = map.get("refuse") {
+                return Ok(ToolCallHook::Refuse(match reason.clone().into_string() {
+                    Ok(reason) => format!("{} ({reason})", refused()),
+                    Err(_) => refused(),
+                }));
+            }
+            if let Some(arg) = map
+                .get("arg")
+                .and_then(|arg| arg.clone().into_string().ok())
+            {
+                current = arg;
+            }
+        }
+        Ok(if current == arg {
+            ToolCallHook::Run
+        } else {
+            ToolCallHook::Replace(current)
+        })
+    }
+
+    /// Tells the hooks how a tool call went; `status` is what was reported for it.
+    ///
+    /// # Errors
+    /// Returns an error if a hook fails.
+    pub fn on_tool_result(&self, name: &str, status: &str, success: bool) -> Result<()> {
+        for script in self.defining("on_tool_result", 3) {
+            self.call(
+                script,
+                "on_tool_result",
+                (name.to_string(), status.to_string(), success),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Shows the hooks a reply of the model.
+    ///
+    /// # Errors
+    /// Returns an error if a hook fails.
+    pub fn on_assistant_message(&self, text: &str) -> Result<()> {
+        for script in self.defining("on_assistant_message", 1) {
+            self.call(script, "on_assistant_message", (text.to_string(),))?;
+        }
+        Ok(())
+    }
+
+    fn defining(&self, hook: &str, params: usize) -> impl Iterator<Item = &Script> {
+        let hook = hook.to_string();
+        self.scripts.iter().filter(move |script| {
+            script
+                .ast
+                .iter_functions()
+                .any(|f| f.name == hook && f.params.len() == params)
+        })
+    }
+
+    fn call(&self, script: &Script, hook: &str, args: impl FuncArgs) -> Result<Dynamic> {
+        // Only the hook function runs, not the statements at the top level of the script
+        let options = CallFnOptions::new().eval_ast(false);
+        self.engine
+            .call_fn_with_options(options, &mut Scope::new(), &script.ast, hook, args)
+            .map_err(|e| anyhow!("{hook} in {} failed: {e}", script.path.display()))
+    }
+}
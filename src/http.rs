@@ -0,0 +1,67 @@
+use crate::auth::ENDPOINT;
+use crate::config::{self, NetworkConfig};
+use anyhow::{Result, anyhow};
+
+/// Environment variables reqwest, and with it the chat client, takes the proxy from.
+const PROXY_VARS: [&str; 2] = ["HTTPS_PROXY", "HTTP_PROXY"];
+
+/// A client builder for the tools' own requests, going through `[network] proxy` if it is
+/// set. Without it, reqwest uses `HTTPS_PROXY`, `HTTP_PROXY`, `ALL_PROXY` and `NO_PROXY`
+/// from the environment.
+///
+/// # Errors
+/// Returns an error if the proxy URL is invalid.
+pub fn client_builder() -> Result<reqwest::ClientBuilder> {
+    with_proxy(reqwest::Client::builder(), &config::get().network)
+}
+
+/// Applies the proxy of `config` to `builder`, if it has one.
+///
+/// # Errors
+/// Returns an error if the proxy URL is invalid or has an unsupported scheme.
+pub fn with_proxy(
+    builder: reqwest::ClientBuilder,
+    config: &NetworkConfig,
+) -> Result<reqwest::ClientBuilder> {
+    let Some(url) = config.proxy.as_deref().filter(|url| !url.is_empty()) else {
+        return Ok(builder);
+    };
+    if !["http://", "https://", "socks5://", "socks5h://"]
+        .iter()
+        .any(|scheme| url.starts_with(scheme))
+    {
+        return Err(anyhow!(
+            "Unsupported proxy {url} (expected an http://, https://, socks5:// or socks5h:// URL)"
+        ));
+    }
+    let proxy = reqwest::Proxy::all(url).map_err(|e| anyhow!("Invalid proxy {url}: {e}"))?;
+    Ok(builder.proxy(proxy))
+}
+
+/// The base URL of the API, from `[network] base_url` or the default endpoint.
+#[must_use]
+pub fn base_url() -> String {
+    config::get()
+        .network
+        .base_url
+        .as_deref()
+        .filter(|url| !url.is_empty())
+        .unwrap_or(ENDPOINT)
+        .trim_end_matches('/')
+        .to_string()
+}
+
+/// Puts `[network] proxy` into the proxy environment variables, in place of any already
+/// set, so that the chat client, which builds its own HTTP client, goes through it too.
+///
+/// # Safety
+/// Must be called before any other threads are started, since it changes the environment.
+pub unsafe fn export_proxy() {
+    let Some(url) = config::get().network.proxy.as_deref() else {
+        return;
+    };
+    for var in PROXY_VARS {
+        // SAFETY: the caller guarantees that no other threads are running
+        unsafe { std::env::set_var(var, url) };
+    }
+}
@@ -0,0 +1,130 @@
+use crate::config;
+use std::fmt::Write;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// Characters around a path or URL that are not part of it, such as quotes and brackets.
+const LEADING: &[char] = &['`', '\'', '"', '(', '[', '<', '*', '_'];
+const TRAILING: &[char] = &[
+    '`', '\'', '"', ')', ']', '>', '*', '_', ',', '.', ';', ':', '!', '?',
+];
+
+static ENABLED: LazyLock<bool> = LazyLock::new(|| {
+    config::get().hyperlinks.enabled.unwrap_or_else(|| {
+        std::io::stdout().is_terminal()
+            && colored::control::SHOULD_COLORIZE.should_colorize()
+            && std::env::var_os("TERM").is_none_or(|term| term != "dumb")
+    })
+});
+
+/// Whether output to the terminal gets hyperlinks: under `[hyperlinks] enabled` in the
+/// config, or by default when stdout is a terminal that takes colors.
+#[must_use]
+pub fn enabled() -> bool {
+    *ENABLED
+}
+
+/// Wraps `text` in an OSC 8 escape sequence that makes it a link to `url` in terminals that
+/// support them. Other terminals show just the text.
+#[must_use]
+pub fn osc8(url: &str, text: &str) -> String {
+    format!("\x1b]8;;{url}\x1b\\{text}\x1b]8;;\x1b\\")
+}
+
+/// The link for a file, optionally at a line. `template` is `[hyperlinks] file_url` from
+/// the config, such as `vscode://file{path}:{line}` to open files in an editor; without
+/// one, the link is a `file://` URL that terminals usually pass to the file manager or the
+/// default application.
+#[must_use]
+pub fn file_url(path: &Path, line: Option<u32>, template: Option<&str>) -> Option<String> {
+    let path = path.canonicalize().ok()?;
+    let encoded = percent_encode(&path.to_string_lossy());
+    Some(match template {
+        Some(template) => template
+            .replace("{path}", &encoded)
+            .replace("{line}", &line.unwrap_or(1).to_string()),
+        None => format!("file://{encoded}"),
+    })
+}
+
+/// Turns the URLs and existing file paths in `text` into hyperlinks, if [`enabled`].
+#[must_use]
+pub fn linkify(text: &str) -> String {
+    if enabled() {
+        linkify_with(text, config::get().hyperlinks.file_url.as_deref())
+    } else {
+        text.to_string()
+    }
+}
+
+/// Turns the URLs and existing file paths in `text` into hyperlinks, with `template` as in
+/// [`file_url`]. A path may end in `:line` or `:line:column`; relative paths are taken from
+/// the working directory.
+#[must_use]
+pub fn linkify_with(text: &str, template: Option<&str>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while !rest.is_empty() {
+        let word_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        out.push_str(&link_word(&rest[..word_end], template));
+        rest = &rest[word_end..];
+        let space_end = rest
+            .find(|c: char| !c.is_whitespace())
+            .unwrap_or(rest.len());
+        out.push_str(&rest[..space_end]);
+        rest = &rest[space_end..];
+    }
+    out
+}
+
+fn link_word(word: &str, template: Option<&str>) -> String {
+    let core = word.trim_start_matches(LEADING);
+    let prefix = &word[..word.len() - core.len()];
+    let core = core.trim_end_matches(TRAILING);
+    let suffix = &word[prefix.len() + core.len()..];
+    if core.is_empty() {
+        return word.to_string();
+    }
+    let url = if core.starts_with("https://") || core.starts_with("http://") {
+        Some(core.to_string())
+    } else {
+        let (path, line) = split_line(core);
+        let looks_like_path = path.contains('/') || path.contains('.');
+        if looks_like_path && Path::new(path).exists() {
+            file_url(Path::new(path), line, template)
+        } else {
+            None
+        }
+    };
+    match url {
+        Some(url) => format!("{prefix}{}{suffix}", osc8(&url, core)),
+        None => word.to_string(),
+    }
+}
+
+/// Splits `src/main.rs:12` or `src/main.rs:12:5` into the path and the line.
+fn split_line(text: &str) -> (&str, Option<u32>) {
+    let mut parts = text.rsplitn(3, ':');
+    let last = parts.next().unwrap_or_default();
+    let Some(middle) = parts.next() else {
+        return (text, None);
+    };
+    match (parts.next(), middle.parse(), last.parse::<u32>()) {
+        (Some(path), Ok(line), Ok(_)) => (path, Some(line)),
+        (_, _, Ok(line)) => (&text[..text.len() - last.len() - 1], Some(line)),
+        _ => (text, None),
+    }
+}
+
+fn percent_encode(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-._~".contains(&byte) {
+            out.push(char::from(byte));
+        } else {
+            let _ = write!(out, "%{byte:02X}");
+        }
+    }
+    out
+}
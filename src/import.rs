@@ -0,0 +1,236 @@
+use anyhow::{Result, anyhow, bail};
+use serde_json::Value;
+use std::fmt;
+use std::path::Path;
+
+/// Conversation formats that can be imported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// `conversations.json` from a ChatGPT data export.
+    ChatGpt,
+    /// A list of `{"role": ..., "content": ...}` messages, as used by the OpenAI API.
+    OpenAi,
+    /// An aider `.aider.chat.history.md` file.
+    Aider,
+}
+
+impl ImportFormat {
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "chatgpt" => Some(Self::ChatGpt),
+            "openai" => Some(Self::OpenAi),
+            "aider" => Some(Self::Aider),
+            _ => None,
+        }
+    }
+
+    fn detect(path: &Path, content: &str) -> Self {
+        if path.extension().is_some_and(|e| e == "md") {
+            Self::Aider
+        } else if content.contains("\"mapping\"") {
+            Self::ChatGpt
+        } else {
+            Self::OpenAi
+        }
+    }
+}
+
+impl fmt::Display for ImportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::ChatGpt => "ChatGPT",
+            Self::OpenAi => "OpenAI",
+            Self::Aider => "aider",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+}
+
+impl Role {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "system" => Some(Self::System),
+            "user" => Some(Self::User),
+            "assistant" => Some(Self::Assistant),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedMessage {
+    pub role: Role,
+    pub content: String,
+}
+
+/// A conversation read from another tool's export.
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    pub title: Option<String>,
+    pub format: ImportFormat,
+    pub messages: Vec<ImportedMessage>,
+}
+
+/// Reads a conversation export, detecting its format from the file when `format` is `None`.
+///
+/// # Errors
+/// Returns an error if the file cannot be read, does not match the format, or contains no
+/// messages.
+pub fn load(path: &Path, format: Option<ImportFormat>) -> Result<Transcript> {
+    let content = std::fs::read_to_string(path)?;
+    let format = format.unwrap_or_else(|| ImportFormat::detect(path, &content));
+    let transcript = parse(&content, format)?;
+    if transcript.messages.is_empty() {
+        bail!("No messages found in {}", path.display());
+    }
+    Ok(transcript)
+}
+
+/// Parses a conversation export in the given format.
+///
+/// # Errors
+/// Returns an error if JSON formats are not valid JSON or lack the expected structure.
+pub fn parse(content: &str, format: ImportFormat) -> Result<Transcript> {
+    let (title, messages) = match format {
+        ImportFormat::ChatGpt => parse_chatgpt(&serde_json::from_str(content)?)?,
+        ImportFormat::OpenAi => (None, parse_openai(&serde_json::from_str(content)?)?),
+        ImportFormat::Aider => (None, parse_aider(content)),
+    };
+    Ok(Transcript {
+        title,
+        format,
+        messages,
+    })
+}
+
+// Exports contain every edited branch; following parents from `current_node` yields the
+// conversation as it was last shown.
+fn parse_chatgpt(value: &Value) -> Result<(Option<String>, Vec<ImportedMessage>)> {
+    let conversation = match value {
+        Value::Array(conversations) => conversations
+            .first()
+            .ok_or_else(|| anyhow!("Export contains no conversations"))?,
+        other => other,
+    };
+    let mapping = conversation
+        .get("mapping")
+        .and_then(Value::as_object)
+        .ok_or_else(|| anyhow!("Missing \"mapping\" in ChatGPT export"))?;
+    let title = conversation
+        .get("title")
+        .and_then(Value::as_str)
+        .map(ToString::to_string);
+
+    let mut messages = Vec::new();
+    let mut node_id = conversation
+        .get("current_node")
+        .and_then(Value::as_str)
+        .map(ToString::to_string);
+    while let Some(node) = node_id.and_then(|id| mapping.get(&id)) {
+        if let Some(message) = node.get("message").and_then(chatgpt_message) {
+            messages.push(message);
+        }
+        node_id = node
+            .get("parent")
+            .and_then(Value::as_str)
+            .map(ToString::to_string);
+    }
+    messages.reverse();
+    Ok((title, messages))
+}
+
+fn chatgpt_message(message: &Value) -> Option<ImportedMessage> {
+    let role = Role::from_name(message.pointer("/author/role")?.as_str()?)?;
+    let content = message
+        .pointer("/content/parts")?
+        .as_array()?
+        .iter()
+        .filter_map(Value::as_str)
+        .collect::<Vec<_>>()
+        .join("\n");
+    (!content.trim().is_empty()).then_some(ImportedMessage { role, content })
+}
+
+fn parse_openai(value: &Value) -> Result<Vec<ImportedMessage>> {
+    let items = value
+        .as_array()
+        .or_else(|| value.get("messages").and_then(Value::as_array))
+        .ok_or_else(|| anyhow!("Expected a list of messages or an object with \"messages\""))?;
+    let messages = items
+        .iter()
+        .filter_map(|item| {
+            let role = Role::from_name(item.get("role")?.as_str()?)?;
+            let content = match item.get("content")? {
+                Value::String(text) => text.clone(),
+                // Multi-part content: keep the text parts only
+                Value::Array(parts) => parts
+                    .iter()
+                    .filter_map(|p| p.get("text").and_then(Value::as_str))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                _ => return None,
+            };
+            (!content.trim().is_empty()).then_some(ImportedMessage { role, content })
+        })
+        .collect();
+    Ok(messages)
+}
+
+// aider writes user messages as `#### ` lines, command output as `> ` quotes and the
+// assistant's replies as plain markdown.
+fn parse_aider(content: &str) -> Vec<ImportedMessage> {
+    let mut messages: Vec<ImportedMessage> = Vec::new();
+    for line in content.lines() {
+        if line.starts_with("# aider chat started") || line.starts_with('>') {
+            continue;
+        }
+        let (role, text) = match line.strip_prefix("####") {
+            Some(text) => (Role::User, text.strip_prefix(' ').unwrap_or(text)),
+            None => (Role::Assistant, line),
+        };
+        match messages.last_mut() {
+            Some(last) if last.role == role => {
+                last.content.push('\n');
+                last.content.push_str(text);
+            }
+            _ if text.trim().is_empty() => {}
+            _ => messages.push(ImportedMessage {
+                role,
+                content: text.to_string(),
+            }),
+        }
+    }
+    for message in &mut messages {
+        message.content = message.content.trim().to_string();
+    }
+    messages.retain(|m| !m.content.is_empty());
+    messages
+}
+
+impl Transcript {
+    /// Renders the transcript as a Markdown document.
+    #[must_use]
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!(
+            "# {}\n\n_Imported from {}_\n",
+            self.title.as_deref().unwrap_or("Imported conversation"),
+            self.format
+        );
+        for message in &self.messages {
+            let speaker = match message.role {
+                Role::System => "System",
+                Role::User => "User",
+                Role::Assistant => "Assistant",
+            };
+            out.push_str(&format!("\n**{speaker}:**\n\n{}\n", message.content));
+        }
+        out
+    }
+}
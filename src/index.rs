@@ -0,0 +1,317 @@
+use crate::workspace::{self, Workspace};
+use std::path::Path;
+use std::process::Command;
+use std::sync::{Arc, LazyLock, PoisonError, RwLock};
+use std::time::Duration;
+
+/// How often the index of the working directory is rebuilt in the background.
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Files beyond this many are left out of the index.
+const MAX_FILES: usize = 100_000;
+
+/// Directories never indexed, and without a `.gitignore`, build output and dependencies.
+const SKIPPED_DIRS: [&str; 3] = [".git", ".jj", ".hg"];
+const DEFAULT_IGNORED_DIRS: [&str; 2] = ["target", "node_modules"];
+
+static CURRENT: LazyLock<RwLock<Option<Arc<FileIndex>>>> = LazyLock::new(|| RwLock::new(None));
+
+/// The files of a project, by path relative to its root with `/` separators. An index of
+/// a workspace gives the files of its other roots as `<name>:<path>`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileIndex {
+    files: Vec<String>,
+}
+
+impl FileIndex {
+    /// Indexes the files under `root` that are not ignored. In a git repository the list
+    /// comes from git, which applies every `.gitignore` and the global excludes; elsewhere
+    /// the directory is walked, skipping what the root `.gitignore` lists (negated patterns
+    /// are not supported) or, without one, `target` and `node_modules`.
+    #[must_use]
+    pub fn build(root: &Path) -> Self {
+        let mut files = git_files(root).unwrap_or_else(|| {
+            let ignore = Ignore::read(root);
+            let mut files = Vec::new();
+            walk(root, "", &ignore, &mut files);
+            files
+        });
+        files.truncate(MAX_FILES);
+        files.sort();
+        Self { files }
+    }
+
+    /// Indexes every root of `workspace` as [`FileIndex::build`] does, up to the same
+    /// limit on files in all.
+    #[must_use]
+    pub fn build_workspace(workspace: &Workspace) -> Self {
+        let mut files = Vec::new();
+        for root in workspace.roots() {
+            let index = Self::build(&root.path);
+            if root.path == workspace.root() {
+                files.extend(index.files);
+            } else {
+                let prefixed = index.files.iter().map(|f| format!("{}:{f}", root.name));
+                files.extend(prefixed);
+            }
+        }
+        files.truncate(MAX_FILES);
+        Self::from_files(files)
+    }
+
+    #[must_use]
+    pub fn from_files(mut files: Vec<String>) -> Self {
+        files.sort();
+        Self { files }
+    }
+
+    #[must_use]
+    pub fn files(&self) -> &[String] {
+        &self.files
+    }
+
+    /// The indexed paths that fuzzily match `query`, best first: its characters must
+    /// appear in order, and matches in the file name, at word starts and in runs rank
+    /// higher.
+    #[must_use]
+    pub fn find(&self, query: &str, limit: usize) -> Vec<&str> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let mut scored: Vec<(i64, &str)> = self
+            .files
+            .iter()
+            .filter_map(|path| Some((score(path, &query)?, path.as_str())))
+            .collect();
+        scored.sort_by(|a, b| {
+            b.0.cmp(&a.0)
+                .then(a.1.len().cmp(&b.1.len()))
+                .then(a.1.cmp(b.1))
+        });
+        scored
+            .into_iter()
+            .take(limit)
+            .map(|(_, path)| path)
+            .collect()
+    }
+}
+
+/// Builds the index of the workspace, then keeps it up to date on a background thread.
+/// Does nothing if it has already been started.
+pub fn start() {
+    static STARTED: std::sync::Once = std::sync::Once::new();
+    STARTED.call_once(|| {
+        std::thread::spawn(|| {
+            loop {
+                let index = Arc::new(FileIndex::build_workspace(&workspace::current()));
+                *CURRENT.write().unwrap_or_else(PoisonError::into_inner) = Some(index);
+                std::thread::sleep(REFRESH_INTERVAL);
+            }
+        });
+    });
+}
+
+/// Drops the latest index, as when the active root of the workspace changes, so that the
+/// next [`current_or_build`] builds it afresh.
+pub fn invalidate() {
+    *CURRENT.write().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+/// The latest index of the workspace, if [`start`] has finished building one.
+#[must_use]
+pub fn current() -> Option<Arc<FileIndex>> {
+    CURRENT
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// The latest index, or a fresh one if none has been built yet.
+#[must_use]
+pub fn current_or_build() -> Arc<FileIndex> {
+    current().unwrap_or_else(|| Arc::new(FileIndex::build_workspace(&workspace::current())))
+}
+
+fn git_files(root: &Path) -> Option<Vec<String>> {
+    let output = Command::new("git")
+        .args([
+            "ls-files",
+            "-z",
+            "--cached",
+            "--others",
+            "--exclude-standard",
+        ])
+        .current_dir(root)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    Some(
+        text.split('\0')
+            .filter(|path| !path.is_empty() && root.join(path).is_file())
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+fn walk(dir: &Path, prefix: &str, ignore: &Ignore, files: &mut Vec<String>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(Result::ok) {
+        if files.len() >= MAX_FILES {
+            return;
+        }
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        let path = format!("{prefix}{name}");
+        let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+        if ignore.is_ignored(&path, is_dir) {
+            continue;
+        }
+        if is_dir {
+            if !SKIPPED_DIRS.contains(&name.as_str()) {
+                walk(&entry.path(), &format!("{path}/"), ignore, files);
+            }
+        } else {
+            files.push(path);
+        }
+    }
+}
+
+/// The patterns of a `.gitignore`.
+struct Ignore {
+    patterns: Vec<IgnorePattern>,
+}
+
+struct IgnorePattern {
+    glob: String,
+    /// Matched against the whole path rather than each name in it
+    anchored: bool,
+    dir_only: bool,
+}
+
+impl Ignore {
+    fn read(root: &Path) -> Self {
+        let Ok(text) = std::fs::read_to_string(root.join(".gitignore")) else {
+            let patterns = DEFAULT_IGNORED_DIRS
+                .iter()
+                .map(|dir| IgnorePattern {
+                    glob: (*dir).to_string(),
+                    anchored: false,
+                    dir_only: true,
+                })
+                .collect();
+            return Self { patterns };
+        };
+        let patterns = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with('!'))
+            .map(|line| {
+                let dir_only = line.ends_with('/');
+                let line = line.trim_end_matches('/');
+                let anchored = line.contains('/');
+                IgnorePattern {
+                    glob: line.trim_start_matches('/').to_string(),
+                    anchored,
+                    dir_only,
+                }
+            })
+            .collect();
+        Self { patterns }
+    }
+
+    fn is_ignored(&self, path: &str, is_dir: bool) -> bool {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        self.patterns.iter().any(|pattern| {
+            (is_dir || !pattern.dir_only)
+                && if pattern.anchored {
+                    glob_match(&pattern.glob, path)
+                } else {
+                    glob_match(&pattern.glob, name)
+                }
+        })
+    }
+}
+
+/// Matches `*` within a path component, `**` across them and `?` as any one character.
+#[must_use]
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    if let Some(rest) = pattern.strip_prefix("**") {
+        let rest = rest.strip_prefix('/').unwrap_or(rest);
+        // A trailing `**` takes everything left, across components
+        if rest.is_empty() {
+            return true;
+        }
+        return (0..=text.len())
+            .filter(|&i| text.is_char_boundary(i) && (i == 0 || text[..i].ends_with('/')))
+            .any(|i| glob_match(rest, &text[i..]));
+    }
+    let mut chars = pattern.chars();
+    match chars.next() {
+        None => text.is_empty(),
+        Some('*') => {
+            let rest = chars.as_str();
+            (0..=text.len())
+                .filter(|&i| text.is_char_boundary(i) && !text[..i].contains('/'))
+                .any(|i| glob_match(rest, &text[i..]))
+        }
+        Some(c) => {
+            let mut text_chars = text.chars();
+            match text_chars.next() {
+                Some(t) if (c == '?' && t != '/') || c == t => {
+                    glob_match(chars.as_str(), text_chars.as_str())
+                }
+                _ => false,
+            }
+        }
+    }
+}
+
+/// Scores how well `path` matches the lowercase `query`, or `None` if it does not.
+fn score(path: &str, query: &str) -> Option<i64> {
+    let lower = path.to_lowercase();
+    let name_start = lower.rfind('/').map_or(0, |i| i + 1);
+    let name = &lower[name_start..];
+    // Matching within the file name counts for more than matching across directories
+    if !query.contains('/')
+        && let Some(score) = subsequence_score(name, query)
+    {
+        let exact = if name == query {
+            100
+        } else if name.starts_with(query) {
+            30
+        } else {
+            0
+        };
+        return Some(score + 20 + exact);
+    }
+    subsequence_score(&lower, query)
+}
+
+fn subsequence_score(text: &str, query: &str) -> Option<i64> {
+    let mut score = 0;
+    let mut previous: Option<usize> = None;
+    let mut from = 0;
+    for q in query.chars() {
+        let offset = text[from..].find(q)?;
+        let at = from + offset;
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == at) {
+            score += 5;
+        }
+        let word_start = text[..at]
+            .chars()
+            .next_back()
+            .is_none_or(|c| matches!(c, '/' | '_' | '-' | '.' | ' '));
+        if word_start {
+            score += 8;
+        }
+        previous = Some(at);
+        from = at + q.len_utf8();
+    }
+    Some(score)
+}
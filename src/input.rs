@@ -0,0 +1,57 @@
+/// Marks the start and end of a block of lines sent as one message.
+pub const BLOCK_DELIMITER: &str = "\"\"\"";
+
+/// Joins lines typed at the prompt into one message. A line ending in `\` continues on
+/// the next line, and everything between two `"""` lines is taken as is, so pasted
+/// code is not sent line by line.
+#[derive(Debug, Default)]
+pub struct MultilineInput {
+    lines: Vec<String>,
+    in_block: bool,
+}
+
+impl MultilineInput {
+    /// Adds a line of input, returning the whole message once it is complete.
+    pub fn push(&mut self, line: &str) -> Option<String> {
+        if self.in_block {
+            if let Some(last) = line.trim_end().strip_suffix(BLOCK_DELIMITER) {
+                if !last.trim().is_empty() {
+                    self.lines.push(last.to_string());
+                }
+                return Some(self.finish());
+            }
+            self.lines.push(line.to_string());
+            return None;
+        }
+        if self.lines.is_empty()
+            && let Some(first) = line.trim_start().strip_prefix(BLOCK_DELIMITER)
+        {
+            // `"""text"""` on one line is a block that is already closed
+            if let Some(only) = first.trim_end().strip_suffix(BLOCK_DELIMITER) {
+                return Some(only.to_string());
+            }
+            self.in_block = true;
+            if !first.trim().is_empty() {
+                self.lines.push(first.to_string());
+            }
+            return None;
+        }
+        if let Some(continued) = line.strip_suffix('\\') {
+            self.lines.push(continued.to_string());
+            return None;
+        }
+        self.lines.push(line.to_string());
+        Some(self.finish())
+    }
+
+    /// Whether earlier lines are waiting for the rest of the message.
+    #[must_use]
+    pub fn is_continuing(&self) -> bool {
+        self.in_block || !self.lines.is_empty()
+    }
+
+    fn finish(&mut self) -> String {
+        self.in_block = false;
+        std::mem::take(&mut self.lines).join("\n")
+    }
+}
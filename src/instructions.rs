@@ -0,0 +1,212 @@
+use crate::workspace::REPOSITORY_MARKERS;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// Name of the files holding instructions for the model.
+pub const FILE_NAME: &str = "DEEPSEEK.md";
+
+/// A `DEEPSEEK.md` that was found, with its contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstructionFile {
+    pub path: PathBuf,
+    pub content: String,
+}
+
+/// The global instructions, `~/.config/deepseek-cli/DEEPSEEK.md`.
+#[must_use]
+pub fn global_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("deepseek-cli").join(FILE_NAME))
+}
+
+/// Finds the `DEEPSEEK.md` files that apply in `dir`, from the most general to the most
+/// specific: the global one, then one in each directory from the repository root down to
+/// `dir`. Outside a repository only `dir` itself is looked in. Empty files are skipped.
+#[must_use]
+pub fn discover(dir: &Path, global: Option<&Path>) -> Vec<InstructionFile> {
+    let dirs: Vec<&Path> = match dir
+        .ancestors()
+        .position(|d| REPOSITORY_MARKERS.iter().any(|m| d.join(m).exists()))
+    {
+        Some(root) => dir.ancestors().take(root + 1).collect(),
+        None => vec![dir],
+    };
+    global
+        .map(Path::to_path_buf)
+        .into_iter()
+        .chain(dirs.into_iter().rev().map(|d| d.join(FILE_NAME)))
+        .filter_map(|path| {
+            let content = std::fs::read_to_string(&path).ok()?;
+            let content = content.trim();
+            (!content.is_empty()).then(|| InstructionFile {
+                path,
+                content: content.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// The instructions that apply in the working directory, as a section of the system prompt,
+/// or `None` if there are none.
+#[must_use]
+pub fn load() -> Option<String> {
+    let dir = std::env::current_dir().ok()?;
+    prompt_section(&discover(&dir, global_path().as_deref()))
+}
+
+/// Puts the instruction files together for the system prompt, each under its path.
+#[must_use]
+pub fn prompt_section(files: &[InstructionFile]) -> Option<String> {
+    if files.is_empty() {
+        return None;
+    }
+    let mut section = format!(
+        "Instructions from {FILE_NAME} files, from the most general to the most specific. Follow them; where they disagree, the later ones win."
+    );
+    for file in files {
+        let _ = write!(
+            section,
+            "\n\n## {}\n\n{}",
+            file.path.display(),
+            file.content
+        );
+    }
+    Some(section)
+}
+
+/// Files describing how a project is built, shown to the model by `/init`.
+const BUILD_FILES: [&str; 16] = [
+    "Cargo.toml",
+    "package.json",
+    "pyproject.toml",
+    "setup.py",
+    "requirements.txt",
+    "go.mod",
+    "pom.xml",
+    "build.gradle",
+    "build.gradle.kts",
+    "CMakeLists.txt",
+    "Makefile",
+    "justfile",
+    "Gemfile",
+    "composer.json",
+    "deno.json",
+    "flake.nix",
+];
+
+const README_FILES: [&str; 4] = ["README.md", "README", "README.rst", "README.txt"];
+
+/// Bytes of each file put in the overview; longer files are cut off.
+const OVERVIEW_FILE_BYTES: usize = 6000;
+
+/// Subdirectories listed under each top-level directory in the overview.
+const OVERVIEW_SUBDIRS: usize = 12;
+
+/// Describes the project in `root` for `/init`: its build files and README, cut off when
+/// long, and the layout of `files`, the project's files relative to the root.
+#[must_use]
+pub fn project_overview(root: &Path, files: &[String]) -> String {
+    let mut overview = String::from("Directory layout:\n");
+    let mut dirs: BTreeMap<&str, (usize, BTreeSet<&str>)> = BTreeMap::new();
+    for file in files {
+        let mut parts = file.split('/');
+        let (Some(top), rest) = (parts.next(), parts.collect::<Vec<_>>()) else {
+            continue;
+        };
+        if rest.is_empty() {
+            let _ = writeln!(overview, "  {top}");
+            continue;
+        }
+        let (count, subdirs) = dirs.entry(top).or_default();
+        *count += 1;
+        if rest.len() > 1 {
+            subdirs.insert(rest[0]);
+        }
+    }
+    for (dir, (count, subdirs)) in &dirs {
+        let _ = write!(overview, "  {dir}/ ({count} files)");
+        if !subdirs.is_empty() {
+            let mut names: Vec<String> = subdirs
+                .iter()
+                .take(OVERVIEW_SUBDIRS)
+                .map(|name| format!("{name}/"))
+                .collect();
+            if subdirs.len() > OVERVIEW_SUBDIRS {
+                names.push("...".to_string());
+            }
+            let _ = write!(overview, ": {}", names.join(" "));
+        }
+        overview.push('\n');
+    }
+    let readme = README_FILES.iter().find(|name| root.join(name).is_file());
+    for name in BUILD_FILES.iter().chain(readme).chain([&FILE_NAME]) {
+        let Ok(content) = std::fs::read_to_string(root.join(name)) else {
+            continue;
+        };
+        let mut end = content.len().min(OVERVIEW_FILE_BYTES);
+        while !content.is_char_boundary(end) {
+            end -= 1;
+        }
+        let cut = if end < content.len() {
+            "\n[... cut off]"
+        } else {
+            ""
+        };
+        let _ = write!(
+            overview,
+            "\n{name}:\n```\n{}{cut}\n```\n",
+            content[..end].trim_end()
+        );
+    }
+    overview
+}
+
+/// The message `/init` sends, asking for a `DEEPSEEK.md` for the project `overview`
+/// describes.
+#[must_use]
+pub fn init_prompt(overview: &str) -> String {
+    format!(
+        "Write a {FILE_NAME} for this project: instructions that are sent to you at the start of every chat in it. Cover what the project is, how to build, test and lint it, how the code is laid out, and the conventions to follow (naming, error handling, tests, formatting) as far as the files below show them. Keep it short and specific to this project; leave out generic advice. If a {FILE_NAME} already exists, improve it rather than starting over.\n\nDo not call any tools. Reply with the file's contents in a single ```markdown code block.\n\n{overview}"
+    )
+}
+
+/// Takes the file out of the model's reply to [`init_prompt`]: the contents of the last
+/// `markdown` code block, or of the whole reply if it has none. Code blocks inside it,
+/// such as build commands, are kept.
+#[must_use]
+pub fn extract_markdown(reply: &str) -> String {
+    let mut block: Option<Vec<&str>> = None;
+    let mut last = None;
+    let mut nested = 0;
+    for line in reply.lines() {
+        let trimmed = line.trim();
+        let ticks = trimmed.len() - trimmed.trim_start_matches('`').len();
+        let info = trimmed[ticks..].trim();
+        match &mut block {
+            None => {
+                if ticks >= 3 && matches!(info, "markdown" | "md") {
+                    block = Some(Vec::new());
+                    nested = 0;
+                }
+            }
+            Some(lines) => {
+                if ticks >= 3 && info.is_empty() && nested == 0 {
+                    last = block.take();
+                    continue;
+                }
+                if ticks >= 3 {
+                    if info.is_empty() {
+                        nested -= 1;
+                    } else {
+                        nested += 1;
+                    }
+                }
+                lines.push(line);
+            }
+        }
+    }
+    match last.or(block) {
+        Some(lines) => lines.join("\n").trim().to_string() + "\n",
+        None => reply.trim().to_string() + "\n",
+    }
+}
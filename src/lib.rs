@@ -1 +1,76 @@
+pub mod agent;
+pub mod approval;
+pub mod args;
+pub mod attach;
+pub mod auth;
+pub mod autonomous;
+pub mod backend;
+pub mod binary;
+pub mod branches;
+pub mod cache;
+pub mod changelog;
+pub mod chats;
+pub mod checkpoint;
+pub mod cli;
+pub mod clipboard;
+pub mod commands;
+pub mod commit;
+pub mod completion;
+pub mod config;
+pub mod context;
+pub mod diagnostics;
+pub mod diff;
+pub mod documents;
+pub mod dryrun;
+pub mod editor;
+pub mod envinfo;
+pub mod eventlog;
+pub mod events;
+pub mod exit;
+pub mod explain;
+pub mod export;
+pub mod filelock;
+pub mod fix;
+pub mod focus;
+pub mod format;
+pub mod github;
+pub mod highlight;
+pub mod hooks;
+pub mod http;
+pub mod hyperlink;
+pub mod import;
+pub mod index;
+pub mod input;
+pub mod instructions;
+pub mod logging;
+pub mod memory;
+pub mod mentions;
+pub mod mock;
+pub mod model;
+pub mod notify;
+pub mod observe;
+pub mod patch;
+pub mod pins;
+pub mod pipe;
+pub mod plugins;
+pub mod policy;
+pub mod protocol;
+pub mod recipe;
+pub mod report;
+pub mod retry;
+pub mod review;
+pub mod sandbox;
+pub mod schema;
+pub mod search;
+pub mod sensitive;
+pub mod sessions;
+pub mod settings;
+pub mod spinner;
+pub mod stats;
+pub mod syntax;
 pub mod tools;
+pub mod tui;
+pub mod vars;
+pub mod watch;
+pub mod web;
+pub mod workspace;
@@ -0,0 +1,53 @@
+use anyhow::{Result, anyhow};
+use std::path::Path;
+use std::sync::Mutex;
+use tracing_subscriber::EnvFilter;
+
+/// Environment variable that overrides the filter `--verbose` picks, with `RUST_LOG`
+/// syntax such as `deepseek_cli::tools=trace`.
+pub const FILTER_VAR: &str = "DEEPSEEK_LOG";
+
+/// The filter for a number of `-v` flags: requests, tools and retries at one, and also
+/// each stream chunk and the HTTP clients' own logs at two or more.
+#[must_use]
+pub fn filter_for(verbosity: u8) -> &'static str {
+    match verbosity {
+        0 | 1 => "warn,deepseek=debug,deepseek_cli=debug",
+        _ => "info,deepseek=trace,deepseek_cli=trace,deepseek_api=debug,reqwest=debug",
+    }
+}
+
+/// Starts writing debug logs to stderr, or to `file` (appending) if it is given. Does
+/// nothing without `-v`, a log file or [`FILTER_VAR`].
+///
+/// # Errors
+/// Returns an error if the log file or the filter in [`FILTER_VAR`] is invalid.
+pub fn init(verbosity: u8, file: Option<&Path>) -> Result<()> {
+    let from_env = std::env::var(FILTER_VAR).ok();
+    if verbosity == 0 && file.is_none() && from_env.is_none() {
+        return Ok(());
+    }
+    let filter = match &from_env {
+        Some(filter) => EnvFilter::try_new(filter)
+            .map_err(|e| anyhow!("Invalid {FILTER_VAR} filter {filter}: {e}"))?,
+        None => EnvFilter::new(filter_for(verbosity)),
+    };
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_target(true);
+    let result = match file {
+        Some(path) => {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| anyhow!("Failed to open log file {}: {e}", path.display()))?;
+            builder
+                .with_ansi(false)
+                .with_writer(Mutex::new(file))
+                .try_init()
+        }
+        None => builder.with_writer(std::io::stderr).try_init(),
+    };
+    result.map_err(|e| anyhow!("Failed to start logging: {e}"))
+}
@@ -1,120 +1,217 @@
-use anyhow::{Result, anyhow};
-use deepseek_api::{DeepSeekAPI, StreamChunk, models::Message};
+use anyhow::{Result, anyhow, bail};
+use deepseek_api::DeepSeekAPI;
 
-use futures_util::{Stream, StreamExt, pin_mut};
+use std::collections::BTreeSet;
 use std::env;
-use std::io::Write;
+use std::io::{IsTerminal, Write};
 use std::path::Path;
+use std::time::{Duration, Instant};
 
 use colored::Colorize;
-use deepseek_cli::tools;
-use rustyline::{DefaultEditor, error::ReadlineError};
+use deepseek_cli::agent::{
+    Agent, LastTurn, Session, SharedSession, continuation_prompt, record_chat_activity,
+};
+use deepseek_cli::auth::{self, TokenSource};
+use deepseek_cli::autonomous::{self, AutonomousRun};
+use deepseek_cli::backend::Backend;
+use deepseek_cli::branches::Branches;
+use deepseek_cli::changelog::{self, CHANGELOG_SYSTEM_PROMPT, Commit};
+use deepseek_cli::chats::ChatRegistry;
+use deepseek_cli::checkpoint;
+use deepseek_cli::cli::{
+    AuthCommand, ChangelogOptions, ChatOptions, Cli, CliCommand, FixOptions, OutputFormat,
+    RunOptions, USAGE, WatchOptions,
+};
+use deepseek_cli::commands::{HELP, SlashCommand};
+use deepseek_cli::commit;
+use deepseek_cli::completion::{self, ReplEditor};
+use deepseek_cli::config::Account;
+use deepseek_cli::context;
+use deepseek_cli::diff::{WordChange, render_word_diff, word_diff};
+use deepseek_cli::editor;
+use deepseek_cli::events::{AgentEvent, EventSink};
+use deepseek_cli::exit::{self, ExitStatus};
+use deepseek_cli::explain::{self, EXPLAIN_SYSTEM_PROMPT, Excerpt};
+use deepseek_cli::fix::{self, CommandRun, FixSummary, FixTarget};
+use deepseek_cli::format::{self, ResponseFormat, Style};
+use deepseek_cli::hooks;
+use deepseek_cli::http;
+use deepseek_cli::import::{self, Transcript};
+use deepseek_cli::input::MultilineInput;
+use deepseek_cli::logging;
+use deepseek_cli::memory::{self, Memory};
+use deepseek_cli::mock::{Recorder, Replay};
+use deepseek_cli::model::Model;
+use deepseek_cli::observe;
+use deepseek_cli::pipe::{PipeRouter, Target};
+use deepseek_cli::policy;
+use deepseek_cli::report::{Outcome, RunReport};
+use deepseek_cli::retry::{self, Failure};
+use deepseek_cli::review::{self, Finding, REVIEW_SYSTEM_PROMPT, ReviewSource, Severity};
+use deepseek_cli::sessions::SessionRegistry;
+use deepseek_cli::settings::{self, Setting};
+use deepseek_cli::{
+    clipboard, config, focus, index, instructions, mentions, schema, tools, tui, watch, web,
+    workspace,
+};
+use rustyline::error::ReadlineError;
 use std::sync::{Arc, Mutex};
 use tokio::fs;
-use tokio::sync::broadcast;
-use tools::{SYSTEM_PROMPT, ToolOutput, execute_tool};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tools::AgentMode;
+
+/// Times a JSON reply that fails to parse or validate is sent back to be fixed.
+const MAX_STRUCTURED_REPROMPTS: usize = 3;
+
+const CHATS_USAGE: &str = "\
+Usage: deepseek chats                      list chats and pick one to resume
+       deepseek chats list                 list chats
+       deepseek chats delete <n|id>        remove a chat from the list
+       deepseek chats rename <n|id> <title>";
 
 enum UserInput {
     Message(String),
+    Command(SlashCommand),
     Exit,
     Interrupted,
 }
 
-async fn handle_stream<S>(
-    stream: S,
-    ctrl_rx: &mut broadcast::Receiver<()>,
-) -> Result<Option<Message>>
-where
-    S: Stream<Item = Result<StreamChunk>>,
-{
-    pin_mut!(stream);
-    let mut final_message = None;
-    let mut thinking_started = false;
-    let mut content_started = false;
-    loop {
-        tokio::select! {
-            maybe_chunk = stream.next() => {
-                match maybe_chunk {
-                    Some(chunk) => {
-                        match chunk? {
-                            StreamChunk::Thinking(thought) => {
-                                if !thinking_started {
-                                    println!("{}", "--- Thinking ---".yellow());
-                                    thinking_started = true;
-                                }
-                                print!("{}", thought.dimmed());
-                                std::io::stdout().flush()?;
-                            }
-                            StreamChunk::Content(text) => {
-                                if !content_started {
-                                    if thinking_started {
-                                        println!("\n{}", "--- End of thinking ---".yellow());
-                                    }
-                                    println!("{}", "--- Response ---".green());
-                                    content_started = true;
-                                }
-                                print!("{}", text.bright_white());
-                                std::io::stdout().flush()?;
-                            }
-                            StreamChunk::Message(msg) => {
-                                if thinking_started && !content_started {
-                                    println!("\n{}", "--- End of thinking ---".yellow());
-                                }
-                                final_message = Some(msg);
-                                println!(); // newline after content
-                            }
-                        }
-                    }
-                    None => break,
-                }
-            }
-            _ = ctrl_rx.recv() => {
-                println!("\n{}", "Stream interrupted by user".yellow());
-                return Ok(None);
-            }
-        }
-    }
-    Ok(final_message)
+/// The account named with `--account`, or failing that in the config, if any.
+fn selected_account(name: Option<String>) -> Result<Option<(String, Account)>> {
+    let config = config::get();
+    let Some(name) = name.or_else(|| config.account.clone()) else {
+        return Ok(None);
+    };
+    let account = config.account(&name)?.clone();
+    Ok(Some((name, account)))
 }
 
-async fn load_token() -> Result<String> {
-    // Try environment variable first
-    if let Ok(token) = env::var("DEEPSEEK_TOKEN") {
-        return Ok(token);
+async fn load_token(account: Option<&(String, Account)>) -> Result<(String, TokenSource)> {
+    let account = account.cloned();
+    let (token, source) = tokio::task::spawn_blocking(move || {
+        auth::load_token(
+            account
+                .as_ref()
+                .map(|(name, account)| (name.as_str(), account)),
+        )
+    })
+    .await??;
+    if let TokenSource::File(path) = &source {
+        eprintln!("Loaded token from {}", path.display());
     }
+    Ok((token, source))
+}
 
-    // Try config file locations
-    let paths = [
-        dirs::config_dir().map(|d| d.join("deepseek-cli/token")),
-        dirs::home_dir().map(|h| h.join(".deepseek_token")),
-    ];
-
-    for path_opt in paths.iter().flatten() {
-        if path_opt.exists() {
-            let content = fs::read_to_string(path_opt).await?;
-            let token = content.trim().to_string();
-            if !token.is_empty() {
-                println!("Loaded token from {}", path_opt.display());
-                return Ok(token);
+/// Runs `deepseek auth login`, `logout` or `status`.
+async fn run_auth_command(command: AuthCommand, account: Option<(String, Account)>) -> Result<()> {
+    let name = account.as_ref().map(|(name, _)| name.clone());
+    let keyring_label = name
+        .as_ref()
+        .map_or_else(String::new, |name| format!(" for account {name}"));
+    match command {
+        AuthCommand::Login => {
+            let token = tokio::task::spawn_blocking(|| -> Result<String> {
+                if std::io::stdin().is_terminal() {
+                    print!("Paste your DeepSeek API token: ");
+                    std::io::stdout().flush()?;
+                }
+                let mut line = String::new();
+                std::io::stdin().read_line(&mut line)?;
+                Ok(line)
+            })
+            .await??;
+            let keyring_account = name.clone();
+            tokio::task::spawn_blocking(move || {
+                auth::store_token(keyring_account.as_deref(), &token)
+            })
+            .await??;
+            println!(
+                "{}",
+                format!("Token stored in the OS keyring{keyring_label}").green()
+            );
+            let plaintext = match &account {
+                None => auth::token_files(),
+                Some((_, account)) => account
+                    .token_file
+                    .iter()
+                    .map(|path| auth::expand_home(path))
+                    .collect(),
+            };
+            for path in plaintext.iter().filter(|p| p.exists()) {
+                println!(
+                    "{}",
+                    format!(
+                        "A plaintext token is still in {}; delete it once the keyring works for you",
+                        path.display()
+                    )
+                    .yellow()
+                );
+            }
+        }
+        AuthCommand::Logout => {
+            let keyring_account = name.clone();
+            if tokio::task::spawn_blocking(move || auth::remove_token(keyring_account.as_deref()))
+                .await??
+            {
+                println!(
+                    "{}",
+                    format!("Token removed from the OS keyring{keyring_label}").green()
+                );
+            } else {
+                println!("No token is stored in the OS keyring{keyring_label}");
+            }
+        }
+        AuthCommand::Status => {
+            println!("Account:  {}", name.as_deref().unwrap_or("default"));
+            println!("Endpoint: {}", http::base_url());
+            if let Some(proxy) = ["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy"]
+                .iter()
+                .find_map(|var| env::var(var).ok())
+            {
+                println!("Proxy:    {proxy}");
+            }
+            let (token, source) = load_token(account.as_ref()).await?;
+            println!("Token:    from {source}");
+            match auth::verify_token(&token).await? {
+                Some(user) => println!("{}", format!("Signed in as {user}").green()),
+                None => println!("{}", "The API accepted the token".green()),
             }
         }
     }
-
-    Err(anyhow!(
-        "DEEPSEEK_TOKEN environment variable not set and no token file found in:\n\
-         - ~/.config/deepseek-cli/token\n\
-         - ~/.deepseek_token\n\
-         Please create one with your API token."
-    ))
+    Ok(())
 }
 
-async fn collect_user_input(rl: Arc<Mutex<DefaultEditor>>) -> UserInput {
-    let prompt = format!("{}", "> ".cyan().bold());
+async fn collect_user_input(rl: Arc<Mutex<ReplEditor>>, session: &Session) -> UserInput {
+    let mut prompt = format!("{}", "> ".cyan().bold());
+    if session.offline {
+        let offline = format!("[offline: {} queued]", session.offline_queue.len());
+        prompt = format!("{} {prompt}", offline.red());
+    }
+    if let Some(style) = session.style {
+        prompt = format!("{} {prompt}", format!("[{}]", style.name()).yellow());
+    }
+    if session.mode == AgentMode::Plan {
+        prompt = format!("{} {prompt}", "[plan]".magenta());
+    }
+    if let Some(name) = focus::current().and_then(|p| p.file_name().map(ToOwned::to_owned)) {
+        prompt = format!(
+            "{} {prompt}",
+            format!("[{}]", name.to_string_lossy()).blue()
+        );
+    }
 
-    // Read a single line (which may contain newlines if Shift+Enter was used)
+    // Read until the message is complete; a single line may already contain newlines if
+    // Shift+Enter was used
+    let continuation = format!("{}", "... ".dimmed());
+    let mut multiline = MultilineInput::default();
+    let mut lines_read = 0;
     let line = loop {
         let rl_clone = rl.clone();
-        let prompt_clone = prompt.clone();
+        let prompt_clone = if multiline.is_continuing() {
+            continuation.clone()
+        } else {
+            prompt.clone()
+        };
         let line_result = tokio::task::spawn_blocking(move || {
             let mut rl_guard = rl_clone.lock().unwrap();
             rl_guard.readline(&prompt_clone)
@@ -122,9 +219,15 @@ async fn collect_user_input(rl: Arc<Mutex<DefaultEditor>>) -> UserInput {
         .await;
 
         match line_result {
-            Ok(Ok(l)) => break l,
+            Ok(Ok(l)) => {
+                lines_read += 1;
+                if let Some(message) = multiline.push(&l) {
+                    break message;
+                }
+            }
             Ok(Err(ReadlineError::Eof)) => return UserInput::Exit,
             Ok(Err(ReadlineError::Interrupted)) => {
+                // Also drops any lines typed so far
                 println!();
                 return UserInput::Interrupted;
             }
@@ -140,356 +243,1718 @@ async fn collect_user_input(rl: Arc<Mutex<DefaultEditor>>) -> UserInput {
     };
 
     let trimmed = line.trim();
-    if trimmed == "/exit" {
-        UserInput::Exit
-    } else if trimmed.is_empty() {
+    if trimmed.is_empty() {
         // ignore empty input and restart
-        UserInput::Interrupted
+        return UserInput::Interrupted;
+    }
+    // Add full input to history as a single entry
+    if let Err(e) = rl.lock().unwrap().add_history_entry(&line) {
+        eprintln!("Failed to add history entry: {e}");
+    }
+    // Only a single line typed on its own can be a command
+    if lines_read == 1
+        && !trimmed.contains('\n')
+        && let Some(command) = SlashCommand::parse(trimmed)
+    {
+        UserInput::Command(command)
     } else {
         UserInput::Message(line)
     }
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let token = load_token().await?;
-    let api = DeepSeekAPI::new(token).await?;
+fn main() -> std::process::ExitCode {
+    // SAFETY: no other threads have been started yet; the runtime is built afterwards
+    unsafe { http::export_proxy() };
+    let result = tokio::runtime::Runtime::new()
+        .map_err(anyhow::Error::from)
+        .and_then(|runtime| runtime.block_on(run()));
+    match result {
+        Ok(()) => std::process::ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {e:?}");
+            exit::status_of(&e).code().into()
+        }
+    }
+}
+
+async fn run() -> Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let cli = Cli::parse(&args)?;
+    logging::init(cli.verbosity, cli.log_file.as_deref())?;
+    policy::current()?;
+    hooks::current()?;
+
+    let mut options = ChatOptions::default();
+    let mut imported = None;
+    let mut web_port = None;
+    let mut headless = None;
+    let mut to_review = None;
+    let mut to_explain = None;
+    let mut release = None;
+    let mut watching = None;
+    let mut fixing = None;
+    match cli.command {
+        CliCommand::Help => {
+            println!("{USAGE}");
+            return Ok(());
+        }
+        CliCommand::Chat(chat_options) => options = chat_options,
+        CliCommand::Observe(target) => {
+            let id = ChatRegistry::load()?
+                .resolve(&target)
+                .map_or(target, |entry| entry.id.clone());
+            return observe::observe(&id).await;
+        }
+        CliCommand::Chats(args) => match run_chats_command(&args)? {
+            Some(id) => options.resume = Some(id),
+            None => return Ok(()),
+        },
+        CliCommand::Import {
+            path,
+            format,
+            output,
+        } => {
+            let transcript = import::load(&path, format)?;
+            if let Some(output) = output {
+                fs::write(&output, transcript.to_markdown()).await?;
+                println!(
+                    "Wrote {} messages to {}",
+                    transcript.messages.len(),
+                    output.display()
+                );
+                return Ok(());
+            }
+            imported = Some(transcript);
+        }
+        CliCommand::Web { port, dry_run } => {
+            web_port = Some(port);
+            options.dry_run = dry_run;
+        }
+        CliCommand::Run(run) => {
+            options = run.chat.clone();
+            headless = Some(run);
+        }
+        CliCommand::Fix(fix) => {
+            options = fix.chat.clone();
+            fixing = Some(fix);
+        }
+        CliCommand::Watch(watch) => {
+            options = watch.chat.clone();
+            watching = Some(watch);
+        }
+        CliCommand::Review(source) => {
+            let chunks = review::chunks(&source.diff().await?, review::MAX_CHUNK_BYTES);
+            if chunks.is_empty() {
+                println!("Nothing to review in {source}");
+                return Ok(());
+            }
+            to_review = Some((source, chunks));
+        }
+        CliCommand::Changelog(changelog) => {
+            let root = std::env::current_dir()?;
+            if !commit::is_repository(&root) {
+                bail!("changelog needs a git repository");
+            }
+            let since = match &changelog.since {
+                Some(since) => since.clone(),
+                None => changelog::latest_tag(&root)?,
+            };
+            let commits = changelog::commits(&root, &since)?;
+            if commits.is_empty() {
+                println!("No commits since {since}");
+                return Ok(());
+            }
+            release = Some((changelog, since, commits));
+        }
+        CliCommand::Explain(explain) => {
+            // Fail on a missing or ambiguous symbol before creating a chat
+            to_explain = Some(explain::load(&workspace::current(), &explain.target)?);
+            options = explain.chat;
+        }
+        CliCommand::Auth(command) => {
+            return run_auth_command(command, selected_account(cli.account)?).await;
+        }
+    }
+
+    // Fail on a bad schema before creating a chat
+    let output_schema = options
+        .output_schema
+        .as_deref()
+        .map(schema::load)
+        .transpose()?;
+    let autonomous = options
+        .autonomous
+        .map(|limit| autonomous_run(limit, options.report_every))
+        .transpose()?;
+    let api = if let Some(path) = &options.mock {
+        Backend::Mock(Replay::load(path)?)
+    } else {
+        let recorder = options
+            .record
+            .as_deref()
+            .map(Recorder::create)
+            .transpose()?;
+        let (token, _) = load_token(selected_account(cli.account)?.as_ref()).await?;
+        let api = DeepSeekAPI::new(token)
+            .await
+            .map_err(|e| exit::with_status(ExitStatus::ApiError, e))?;
+        Backend::Api { api, recorder }
+    };
+    let agent = Agent::new(api);
+    let api = agent.api();
 
-    let args: Vec<String> = env::args().collect();
-    let (chat_id, parent_id) = if args.len() > 1 {
-        let id = args[1].clone();
-        println!("Resuming chat with ID: {}", &id);
-        let chat = api.get_chat_info(&id).await?;
-        (id, chat.current_message_id)
+    let mut initial_message = None;
+    let mut session = if let Some(transcript) = imported {
+        let (session, message) = start_imported_chat(api, &transcript).await?;
+        initial_message = Some(message);
+        session
+    } else if let Some(id) = options.resume {
+        Session::resume(api, id).await?
+    } else if let Some(name) = &options.session {
+        open_named_session(api, name).await?
     } else {
-        let chat = api.create_chat().await?;
-        let id = chat.id;
-        println!("Chat created with ID: {id}");
-        (id, None)
+        Session::create(api).await?
     };
-    println!("System prompt loaded. Type your messages (type '/exit' to quit):");
+    match session.start_event_log(options.log_dir.as_deref()) {
+        Err(e) if options.log_dir.is_some() => return Err(e),
+        Err(e) => eprintln!("{}", format!("Not keeping an event log: {e}").yellow()),
+        Ok(()) => {}
+    }
+    for assignment in &options.vars {
+        session.vars.set(assignment)?;
+    }
+    let config = config::get();
+    for path in &config.context {
+        let path = config.resolve(path);
+        if let Err(e) = agent.pin_file(&mut session, &path).await {
+            eprintln!(
+                "{}",
+                format!("Could not attach {} from the config: {e}", path.display()).yellow()
+            );
+        }
+    }
+    if let Some(name) = options.profile.as_ref().or(config::get().profile.as_ref()) {
+        session.set_profile(Some(name.as_str()))?;
+    }
+    if let Some(name) = &options.workflow {
+        initial_message = session.apply_workflow(api, name).await?;
+    }
+    session.format = options.format;
+    session.max_output_tokens = options.max_output_tokens;
+    if let Some(rounds) = options.max_iterations {
+        session.max_tool_iterations = rounds;
+    }
+    if let Some(model) = options.model.or(config::get().default_model()?) {
+        session.model = model;
+    }
+    if options.no_search {
+        session.search = false;
+    }
+    if output_schema.is_some() {
+        session.format = Some(ResponseFormat::Json);
+        session.output_schema = output_schema;
+    }
+    if let Some(run) = autonomous {
+        eprintln!(
+            "{}",
+            format!(
+                "Autonomous mode: the first task runs without approvals for up to {}, using {}",
+                autonomous::format_duration(run.limit()),
+                run.tools().join(", ")
+            )
+            .yellow()
+        );
+        session.autonomous = Some(run);
+    }
+    // Set before any interface is started, so none of them runs tools for real in a dry run
+    session.quiet = options.quiet;
+    session.set_dry_run(options.dry_run);
+    index::start();
+    if let Some(port) = web_port {
+        return web::serve(port, SharedSession::new(agent, session)).await;
+    }
+    if options.tui {
+        tools::set_interactive(false);
+        return tui::run(SharedSession::new(agent, session)).await;
+    }
+    if options.share {
+        session.share()?;
+    }
+    if let Some(run) = headless {
+        tools::set_interactive(false);
+        return run_headless(agent, session, initial_message, run).await;
+    }
+    if let Some(fix) = fixing {
+        tools::set_interactive(false);
+        return run_fix(agent, session, initial_message, fix).await;
+    }
+    if let Some(watch) = watching {
+        tools::set_interactive(false);
+        return run_watch(agent, session, initial_message, watch).await;
+    }
+    if let Some((source, chunks)) = to_review {
+        tools::set_interactive(false);
+        return run_review(agent, session, &source, &chunks).await;
+    }
+    if let Some((changelog, since, commits)) = release {
+        tools::set_interactive(false);
+        return run_changelog(agent, session, &changelog, &since, &commits).await;
+    }
+    if let Some(excerpt) = to_explain {
+        tools::set_interactive(false);
+        return run_explain(agent, session, &excerpt).await;
+    }
+    if let Some(prompt) = options.print {
+        tools::set_interactive(false);
+        return run_print(agent, session, initial_message, prompt).await;
+    }
+    if options.output == OutputFormat::Json {
+        tools::set_interactive(false);
+        return run_json(agent, session, initial_message).await;
+    }
+    println!(
+        "System prompt loaded. Type your messages (type '/exit' to quit, '/help' for commands):"
+    );
 
     // Setup rustyline editor for line editing with arrow keys (in-memory history only)
-    let rl = Arc::new(Mutex::new(DefaultEditor::new()?));
+    let rl = Arc::new(Mutex::new(completion::editor()?));
+
+    run_chat(agent, session, rl, initial_message).await
+}
+
+/// Creates a chat seeded with an imported transcript. Returns the session and the message
+/// that hands the transcript to the model.
+async fn start_imported_chat(api: &Backend, transcript: &Transcript) -> Result<(Session, String)> {
+    let mut session = Session::create(api).await?;
+    let file_id = api
+        .upload_file(
+            transcript.to_markdown().into_bytes(),
+            "imported_conversation.md",
+            None,
+        )
+        .await?;
+    session.pending_files.push(file_id);
+    let title = transcript.title.as_deref().unwrap_or("conversation");
+    record_chat_activity(&session.chat_id, Some(&format!("Imported: {title}")));
+    let message = format!(
+        "The attached file is a transcript of an earlier conversation ({} messages, imported from {}). Treat it as context for this chat and briefly summarize where it left off.",
+        transcript.messages.len(),
+        transcript.format
+    );
+    Ok((session, message))
+}
+
+/// Handles `deepseek chats ...`. Returns the ID of a chat to resume, if one was picked.
+fn run_chats_command(args: &[String]) -> Result<Option<String>> {
+    let mut registry = ChatRegistry::load()?;
+    match args.first().map(String::as_str) {
+        None => {
+            print_chats(&registry);
+            if registry.list().is_empty() {
+                return Ok(None);
+            }
+            print!("Resume which chat? (number, Enter to cancel): ");
+            std::io::stdout().flush()?;
+            let mut line = String::new();
+            std::io::stdin().read_line(&mut line)?;
+            let key = line.trim();
+            if key.is_empty() {
+                return Ok(None);
+            }
+            let entry = registry
+                .resolve(key)
+                .ok_or_else(|| anyhow!("Unknown chat: {key}"))?;
+            Ok(Some(entry.id.clone()))
+        }
+        Some("list") => {
+            print_chats(&registry);
+            Ok(None)
+        }
+        Some("delete") => {
+            let key = args.get(1).ok_or_else(|| anyhow!(CHATS_USAGE))?;
+            let entry = registry
+                .remove(key)
+                .ok_or_else(|| anyhow!("Unknown chat: {key}"))?;
+            registry.save()?;
+            println!("Removed chat {} from the list", entry.id);
+            Ok(None)
+        }
+        Some("rename") => {
+            let key = args.get(1).ok_or_else(|| anyhow!(CHATS_USAGE))?;
+            let title = args.get(2..).unwrap_or_default().join(" ");
+            if title.is_empty() {
+                bail!(CHATS_USAGE);
+            }
+            if !registry.rename(key, &title) {
+                bail!("Unknown chat: {key}");
+            }
+            registry.save()?;
+            println!("Renamed chat to: {title}");
+            Ok(None)
+        }
+        Some(other) => bail!("Unknown chats subcommand: {other}\n{CHATS_USAGE}"),
+    }
+}
 
-    run_chat(api, chat_id, parent_id, rl).await
+fn print_chats(registry: &ChatRegistry) {
+    let chats = registry.list();
+    if chats.is_empty() {
+        println!("No chats yet");
+    }
+    for (i, chat) in chats.iter().enumerate() {
+        println!("{}. {}", (i + 1).to_string().cyan(), chat.summary());
+    }
+}
+
+/// Lists the replies seen this session, for `/fork`.
+fn print_replies(branches: &Branches) {
+    if branches.messages().is_empty() {
+        println!("No replies yet");
+        return;
+    }
+    for reply in branches.messages() {
+        println!("{:>6}  {}", reply.id.to_string().cyan(), reply.preview);
+    }
+    println!("Branch from one with /fork <id> [name]");
+}
+
+fn print_branches(branches: &Branches) {
+    for (i, branch) in branches.branches().iter().enumerate() {
+        let marker = if branches.is_current(i) { "*" } else { " " };
+        let head = match branch.head {
+            Some(id) => match branches.get(id) {
+                Some(reply) => format!("at {id}: {}", reply.preview),
+                None => format!("at {id}"),
+            },
+            None => "(no messages yet)".to_string(),
+        };
+        println!(
+            "{marker} {}. {} {}",
+            (i + 1).to_string().cyan(),
+            branch.label,
+            head.dimmed()
+        );
+    }
+}
+
+/// Resumes the chat saved under `name`, or starts one and saves it under the name.
+async fn open_named_session(api: &Backend, name: &str) -> Result<Session> {
+    let mut registry = SessionRegistry::load()?;
+    let mut session = match registry.get(name).cloned() {
+        Some(saved) => {
+            eprintln!("Continuing session {name}");
+            let mut session = Session::resume(api, saved.chat_id).await?;
+            session.parent_id = session.parent_id.or(saved.parent_id);
+            session
+        }
+        None => {
+            let session = Session::create(api).await?;
+            eprintln!("Saved the new chat as session {name}");
+            session
+        }
+    };
+    registry.set(name, &session.chat_id, session.parent_id);
+    registry.save()?;
+    session.name = Some(name.to_string());
+    Ok(session)
+}
+
+/// Reads one line from the user outside the main prompt (e.g. for a picker).
+async fn read_line(rl: &Arc<Mutex<ReplEditor>>, prompt: &str) -> Option<String> {
+    let rl = rl.clone();
+    let prompt = prompt.to_string();
+    tokio::task::spawn_blocking(move || rl.lock().unwrap().readline(&prompt))
+        .await
+        .ok()?
+        .ok()
 }
 
 async fn run_chat(
-    api: DeepSeekAPI,
-    chat_id: String,
-    mut parent_id: Option<i64>,
-    rl: Arc<Mutex<DefaultEditor>>,
+    agent: Agent,
+    mut session: Session,
+    rl: Arc<Mutex<ReplEditor>>,
+    initial_message: Option<String>,
 ) -> Result<()> {
-    // Setup Ctrl+C handling using broadcast so each round gets a fresh receiver
-    let (tx, _) = broadcast::channel(1);
-    let tx_task = tx.clone();
-    tokio::spawn(async move {
-        loop {
-            if tokio::signal::ctrl_c().await.is_ok() {
-                let _ = tx_task.send(());
+    agent.interrupt_on_ctrl_c();
+
+    if let Some(message) = initial_message
+        && let Err(e) = agent.send_message(&mut session, &message).await
+    {
+        eprintln!("{}", format!("Request failed: {e}").red());
+    }
+
+    loop {
+        match collect_user_input(rl.clone(), &session).await {
+            UserInput::Exit | UserInput::Command(SlashCommand::Exit) => {
+                if !session.stats.requests().is_empty() {
+                    println!("{}", session.stats.render_usage());
+                }
+                break;
+            }
+            UserInput::Interrupted => {}
+            UserInput::Command(command) => {
+                if let Err(e) = handle_command(&agent, &mut session, &rl, command).await {
+                    eprintln!("{}", format!("Command failed: {e}").red());
+                }
+            }
+            UserInput::Message(full_input) => {
+                if session.offline {
+                    if agent
+                        .api()
+                        .current_message_id(&session.chat_id)
+                        .await
+                        .is_err()
+                    {
+                        session.offline_queue.push(full_input);
+                        println!(
+                            "{}",
+                            format!(
+                                "Still offline, message queued ({} waiting)",
+                                session.offline_queue.len()
+                            )
+                            .yellow()
+                        );
+                        continue;
+                    }
+                    session.offline = false;
+                    offer_offline_queue(&agent, &mut session, &rl).await;
+                }
+                send_or_queue(&agent, &mut session, full_input).await;
             }
         }
+    }
+    Ok(())
+}
+
+/// Sets up `--autonomous` with the tools allowed by `[autonomous]` in the config.
+fn autonomous_run(limit: Duration, report_every: Option<Duration>) -> Result<AutonomousRun> {
+    let tools = match &config::get().autonomous.tools {
+        Some(tools) => tools.clone(),
+        None => autonomous::DEFAULT_TOOLS.map(String::from).to_vec(),
+    };
+    if let Some(unknown) = tools.iter().find(|t| !tools::tool_exists(t)) {
+        bail!("Unknown tool in [autonomous] tools: {unknown}");
+    }
+    Ok(AutonomousRun::new(
+        limit,
+        report_every.unwrap_or(autonomous::DEFAULT_REPORT_EVERY),
+        tools,
+    ))
+}
+
+/// Sends a message, queueing it instead if the API turns out to be unreachable. Other
+/// errors are reported and the chat carries on from the last reply.
+async fn send_or_queue(agent: &Agent, session: &mut Session, message: String) {
+    let parent_before = session.parent_id;
+    let Err(e) = agent.send_message(session, &message).await else {
+        return;
+    };
+    // Only a message that never reached the API can be sent again as is
+    if retry::classify(&e) == Failure::Offline && session.parent_id == parent_before {
+        session.offline = true;
+        session.offline_queue.push(message);
+        eprintln!(
+            "{}",
+            format!(
+                "The API is unreachable ({e}). You are offline: keep typing, messages are queued until the connection is back."
+            )
+            .yellow()
+        );
+    } else {
+        eprintln!("{}", format!("Request failed: {e}").red());
+    }
+}
+
+/// Called when the connection is back: asks whether to send the queued messages now.
+async fn offer_offline_queue(agent: &Agent, session: &mut Session, rl: &Arc<Mutex<ReplEditor>>) {
+    if session.offline_queue.is_empty() {
+        return;
+    }
+    let question = format!(
+        "Connection restored. Send the {} queued messages first? [Y/n] ",
+        session.offline_queue.len()
+    );
+    let answer = read_line(rl, &question).await.unwrap_or_default();
+    if answer.trim().eq_ignore_ascii_case("n") {
+        println!("Kept them queued; use /queue send or /queue clear later");
+        return;
+    }
+    send_offline_queue(agent, session).await;
+}
+
+/// Sends queued messages in order, stopping if the connection drops again.
+async fn send_offline_queue(agent: &Agent, session: &mut Session) {
+    let queued = std::mem::take(&mut session.offline_queue);
+    let mut queued = queued.into_iter();
+    for message in queued.by_ref() {
+        println!("{} {message}", "Sending queued message:".cyan());
+        send_or_queue(agent, session, message).await;
+        if session.offline {
+            break;
+        }
+    }
+    session.offline_queue.extend(queued);
+}
+
+/// Runs the chat for `--output json`: each stdin line is sent as a message, and every
+/// event is written to stdout as one JSON object per line. `/exit` or end of input stops.
+async fn run_json(
+    agent: Agent,
+    mut session: Session,
+    initial_message: Option<String>,
+) -> Result<()> {
+    agent.interrupt_on_ctrl_c();
+    let (events, mut rx) = EventSink::channel();
+    session.events = session.attach(events.without_echo());
+    let writer = tokio::spawn(async move {
+        let mut stdout = tokio::io::stdout();
+        while let Some(event) = rx.recv().await {
+            let mut line = serde_json::to_vec(&event)?;
+            line.push(b'\n');
+            stdout.write_all(&line).await?;
+            stdout.flush().await?;
+        }
+        anyhow::Ok(())
     });
 
-    'outer: loop {
-        match collect_user_input(rl.clone()).await {
-            UserInput::Exit => break 'outer,
-            UserInput::Interrupted => {}
-            UserInput::Message(full_input) => {
-                if full_input.is_empty() {
-                    continue;
+    let result = async {
+        if let Some(message) = initial_message {
+            agent.send_message(&mut session, &message).await?;
+        }
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+        while let Some(line) = lines.next_line().await? {
+            let line = line.trim();
+            if matches!(SlashCommand::parse(line), Some(SlashCommand::Exit)) {
+                break;
+            }
+            agent.send_message(&mut session, line).await?;
+        }
+        anyhow::Ok(())
+    }
+    .await;
+    if let Err(e) = &result {
+        session.events.emit(AgentEvent::Error {
+            message: e.to_string(),
+        });
+    }
+    let outcome = unattended_outcome(&session);
+    // Closing the channel lets the writer finish what is queued
+    drop(session);
+    writer.await??;
+    result?;
+    outcome_result(&outcome)
+}
+
+/// Sends one prompt for `-p`. Only the reply is written to stdout, as plain text, while
+/// thinking and tool activity go to stderr, so the output can be piped or redirected.
+///
+/// With `--format json` or `--output-schema` the reply is held back until it is complete,
+/// then repaired and validated. A reply that is still not valid is sent back to the model
+/// with what is wrong, so that stdout only ever gets valid JSON.
+async fn run_print(
+    agent: Agent,
+    mut session: Session,
+    initial_message: Option<String>,
+    prompt: String,
+) -> Result<()> {
+    agent.interrupt_on_ctrl_c();
+    let (events, mut rx) = EventSink::channel();
+    session.events = session.attach(events.without_echo());
+    let mut router = PipeRouter::new(session.quiet);
+    let hold_back = session.format == Some(ResponseFormat::Json);
+    let (replies_tx, mut replies) = tokio::sync::mpsc::unbounded_channel();
+    let writer = tokio::spawn(async move {
+        let mut stdout = tokio::io::stdout();
+        let mut stderr = tokio::io::stderr();
+        let mut held = String::new();
+        while let Some(event) = rx.recv().await {
+            // Each turn ends with `Done`, so the held reply is complete
+            if event == AgentEvent::Done && hold_back {
+                let _ = replies_tx.send(std::mem::take(&mut held));
+            }
+            for (target, text) in router.route(&event) {
+                match target {
+                    Target::Stdout if hold_back => held.push_str(&text),
+                    Target::Stdout => {
+                        stdout.write_all(text.as_bytes()).await?;
+                        stdout.flush().await?;
+                    }
+                    Target::Stderr => {
+                        stderr.write_all(text.as_bytes()).await?;
+                        stderr.flush().await?;
+                    }
                 }
-                // Add full input to history as a single entry
-                if let Err(e) = rl.lock().unwrap().add_history_entry(&full_input) {
-                    eprintln!("Failed to add history entry: {e}");
+            }
+        }
+        anyhow::Ok(())
+    });
+
+    let result = async {
+        if let Some(message) = initial_message {
+            agent.send_message(&mut session, &message).await?;
+            if hold_back {
+                // Only the reply to the prompt is output
+                replies.recv().await;
+            }
+        }
+        agent.send_message(&mut session, &prompt).await?;
+        if !hold_back {
+            return Ok(None);
+        }
+        let mut reprompts = 0;
+        loop {
+            let reply = replies.recv().await.unwrap_or_default();
+            match format::structured_reply(&reply, session.output_schema.as_ref()) {
+                Ok(json) => return Ok(Some(json)),
+                Err(problem) if reprompts < MAX_STRUCTURED_REPROMPTS => {
+                    reprompts += 1;
+                    eprintln!(
+                        "{}",
+                        format!("{problem}\nAsking for a corrected reply ({reprompts}/{MAX_STRUCTURED_REPROMPTS})...")
+                            .yellow()
+                    );
+                    let fix = format!(
+                        "{problem}\n\nReply again with only the corrected JSON, and nothing else."
+                    );
+                    agent.send_message(&mut session, &fix).await?;
                 }
+                Err(problem) => return Err(problem),
+            }
+        }
+    }
+    .await;
+    let outcome = unattended_outcome(&session);
+    // The error is returned to main, which reports it
+    drop(session);
+    writer.await??;
+    if let Some(json) = result? {
+        println!("{json}");
+    }
+    outcome_result(&outcome)
+}
 
-                // Prepend system prompt only on the very first message
-                let prompt = if parent_id.is_none() {
-                    format!("{}\n\nUser:\n{}", SYSTEM_PROMPT.as_str(), full_input)
-                } else {
-                    full_input.clone()
-                };
+/// Runs `deepseek run`: sends the task file as the first message and lets the model work
+/// on it without a terminal, so tool calls are approved unless the policy says otherwise.
+/// When it finishes, is stopped at `--max-turns` or fails, a report of what it did is
+/// written, and the run fails unless it completed.
+async fn run_headless(
+    agent: Agent,
+    mut session: Session,
+    initial_message: Option<String>,
+    run: RunOptions,
+) -> Result<()> {
+    let task = fs::read_to_string(&run.task)
+        .await
+        .map_err(|e| anyhow!("Failed to read task file {}: {e}", run.task.display()))?;
+    if task.trim().is_empty() {
+        bail!("Task file {} is empty", run.task.display());
+    }
+    agent.interrupt_on_ctrl_c();
+    let started = Instant::now();
+    let result = async {
+        if let Some(message) = initial_message {
+            agent.send_message(&mut session, &message).await?;
+        }
+        agent.send_message(&mut session, task.trim()).await
+    }
+    .await;
+    let outcome = match &result {
+        Err(e) => Outcome::Failed(e.to_string()),
+        Ok(()) => unattended_outcome(&session),
+    };
+    let report = RunReport {
+        task: run.task.display().to_string(),
+        outcome,
+        duration: started.elapsed(),
+        entries: session.conversation.lock().unwrap().entries().to_vec(),
+        actions: session.recipe.actions().to_vec(),
+        diff_stat: git_diff_stat().await,
+    };
+    fs::write(&run.report, report.to_markdown())
+        .await
+        .map_err(|e| anyhow!("Failed to write report {}: {e}", run.report.display()))?;
+    eprintln!("Wrote report to {}", run.report.display());
+    result?;
+    outcome_result(&report.outcome)
+}
 
-                // Stream the assistant's response
-                let stream = api.complete_stream(
-                    chat_id.clone(),
-                    prompt,
-                    parent_id,
-                    true,   // search
-                    true,   // thinking
-                    vec![], // ref_file_ids
-                );
-                let mut rx = tx.subscribe();
-                let final_message = handle_stream(stream, &mut rx).await?;
-                let Some(mut current_msg) = final_message else {
-                    // Stream was interrupted; return to input prompt silently
-                    continue;
+/// Runs `deepseek fix-tests` and `deepseek fix-build`: runs the command in the workspace
+/// root and, while it fails, sends its output (for the build, the errors cargo gives as
+/// JSON) to the model to fix, as `run` lets it work, then runs it again. It stops when it
+/// passes, after `--max-rounds` rounds of fixes or on Ctrl+C, prints how it went with the
+/// diff of the changes, and fails unless the command passes.
+async fn run_fix(
+    agent: Agent,
+    mut session: Session,
+    initial_message: Option<String>,
+    fix: FixOptions,
+) -> Result<()> {
+    let root = workspace::current().root().to_path_buf();
+    let command = match fix.command {
+        Some(command) => command,
+        None => fix
+            .target
+            .detect_command(&root)
+            .ok_or_else(|| {
+                let what = match fix.target {
+                    FixTarget::Tests => "run the tests of",
+                    FixTarget::Build => "build",
                 };
-                parent_id = current_msg.message_id;
-
-                loop {
-                    // Ensure non-empty response
-                    while current_msg.content.trim().is_empty() {
-                        eprintln!(
-                            "{}",
-                            "Model returned empty response, reprompting with warning...".yellow()
-                        );
-                        let warning = "WARNING: Your previous response was empty. Please provide a meaningful response or use tools as appropriate.\n\nContinue with the next step or provide the final answer.";
-                        let stream = api.complete_stream(
-                            chat_id.clone(),
-                            warning.to_string(),
-                            parent_id,
-                            true,
-                            true,
-                            vec![], // ref_file_ids
-                        );
-                        let mut rx_inner = tx.subscribe();
-                        let new_msg = handle_stream(stream, &mut rx_inner).await?;
-                        match new_msg {
-                            Some(msg) => {
-                                parent_id = msg.message_id;
-                                current_msg = msg;
-                            }
-                            None => {
-                                // Stream interrupted during reprompt; go back to user input silently
-                                continue 'outer;
-                            }
-                        }
-                    }
+                anyhow!(
+                    "Cannot tell how to {what} {}; give the command with --cmd",
+                    root.display()
+                )
+            })?
+            .to_string(),
+    };
+    let run_command = fix.target.run_command(&command);
+    agent.interrupt_on_ctrl_c();
+    if let Some(message) = initial_message {
+        agent.send_message(&mut session, &message).await?;
+    }
+    let mut summary = FixSummary::new(&command);
+    loop {
+        eprintln!("{}", format!("Running {command}").cyan());
+        let output = tools::shell_command(&run_command)
+            .current_dir(&root)
+            .output()
+            .await
+            .map_err(|e| anyhow!("Failed to run {command}: {e}"))?;
+        let run = CommandRun {
+            exit_code: output.status.code(),
+            output: format!(
+                "{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            ),
+        };
+        let round = u32::try_from(summary.runs.len() + 1).unwrap_or(u32::MAX);
+        let prompt = (!run.passed() && round <= fix.max_rounds)
+            .then(|| fix::prompt(fix.target, &command, &run, round, fix.max_rounds));
+        summary.runs.push(run);
+        let Some(prompt) = prompt else { break };
+        if let Err(e) = agent.send_message(&mut session, &prompt).await {
+            summary.stopped = Some(e.to_string());
+            break;
+        }
+        if session.interrupted.is_some() {
+            summary.stopped = Some("interrupted".to_string());
+            break;
+        }
+    }
+    summary.diff_stat = git_diff_stat().await;
+    println!("{}", summary.render());
+    if summary.passed() {
+        Ok(())
+    } else {
+        Err(exit::error(
+            ExitStatus::Incomplete,
+            format!("{command} still fails"),
+        ))
+    }
+}
 
-                    // Handle tool calls
-                    match handle_tool_calls(&api, &chat_id, current_msg, &mut parent_id, &mut rx)
-                        .await?
-                    {
-                        Some(new_msg) => {
-                            current_msg = new_msg;
-                            // parent_id already updated inside handle_tool_calls
-                        }
-                        None => {
-                            // No more tool calls, done with this assistant turn
-                            break;
-                        }
-                    }
+/// Runs `deepseek watch`: waits for files matching the globs to change anywhere in the
+/// workspace, lets them settle for the debounce time, then sends the prompt with the list
+/// of changed files and lets the model work on it as `run` does. All runs share one chat,
+/// so the model remembers what it fixed before. Changes made while a run is under way,
+/// which are mostly its own edits, are discarded rather than setting off another run.
+/// Ctrl+C interrupts a run, and stops watching between runs.
+async fn run_watch(
+    agent: Agent,
+    mut session: Session,
+    initial_message: Option<String>,
+    watch: WatchOptions,
+) -> Result<()> {
+    let workspace = workspace::current();
+    let filter = watch::ChangeFilter::new(watch.globs);
+    let (_watcher, mut changes) = watch::watch(&workspace)?;
+    agent.interrupt_on_ctrl_c();
+    if let Some(message) = initial_message {
+        agent.send_message(&mut session, &message).await?;
+    }
+    let roots: Vec<String> = workspace
+        .roots()
+        .iter()
+        .map(|root| root.path.display().to_string())
+        .collect();
+    eprintln!(
+        "{}",
+        format!(
+            "Watching {} for changes to {} (Ctrl+C to stop)",
+            roots.join(", "),
+            filter.globs().join(", ")
+        )
+        .cyan()
+    );
+    loop {
+        let mut stop = agent.subscribe();
+        let mut changed = BTreeSet::new();
+        while changed.is_empty() {
+            tokio::select! {
+                path = changes.recv() => {
+                    let Some(path) = path else { return Ok(()) };
+                    changed.extend(filter.matches(&workspace, &path));
                 }
+                _ = stop.recv() => return Ok(()),
             }
         }
+        // Wait for the burst of changes a save or checkout makes to end
+        while let Ok(Some(path)) = tokio::time::timeout(watch.debounce, changes.recv()).await {
+            changed.extend(filter.matches(&workspace, &path));
+        }
+        let files: Vec<&str> = changed.iter().map(String::as_str).collect();
+        eprintln!("{}", format!("Changed: {}", files.join(", ")).cyan());
+        let message = watch::message(&watch.prompt, &changed);
+        if let Err(e) = agent.send_message(&mut session, &message).await {
+            eprintln!("{}", format!("The run failed: {e}").red());
+        }
+        while changes.try_recv().is_ok() {}
+        eprintln!("{}", "Watching for changes".cyan());
     }
+}
+
+/// Runs `deepseek review`: each chunk of the diff is sent in turn, the first after the
+/// review prompt in place of the agent's, and once all are reviewed the findings are
+/// printed by file. Ctrl+C stops early and prints what was found so far.
+async fn run_review(
+    agent: Agent,
+    mut session: Session,
+    source: &ReviewSource,
+    chunks: &[review::Chunk],
+) -> Result<()> {
+    agent.interrupt_on_ctrl_c();
+    session.events = session.attach(EventSink::default().without_echo());
+    let total = chunks.len();
+    eprintln!("{}", format!("Reviewing {source} in {total} parts").cyan());
+    let mut findings = Vec::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        eprintln!("{}", format!("[{}/{total}] {}", i + 1, chunk.path).dimmed());
+        let mut prompt = review::chunk_prompt(chunk, i + 1, total);
+        if session.parent_id.is_none() {
+            prompt = format!("{REVIEW_SYSTEM_PROMPT}\n\n{prompt}");
+        }
+        let parent_id = session.parent_id;
+        let Some(reply) = agent
+            .complete(&mut session, &prompt, parent_id, &[])
+            .await?
+        else {
+            eprintln!(
+                "{}",
+                "Review interrupted; showing the findings so far".yellow()
+            );
+            break;
+        };
+        session.parent_id = reply.message_id.or(parent_id);
+        findings.extend(review::parse_findings(&reply.content, &chunk.path));
+    }
+    print_findings(&findings);
     Ok(())
 }
 
-async fn upload_tool_output(
-    api: &DeepSeekAPI,
-    content: &str,
-    tool_name: &str,
-    full_arg: &str,
-) -> Result<String> {
-    use std::time::{SystemTime, UNIX_EPOCH};
-
-    // Generate filename based on tool type
-    let filename = match tool_name {
-        "read_file" => {
-            // Extract original filename from the path argument
-            let path_str = full_arg.lines().next().unwrap_or("");
-            Path::new(path_str)
-                .file_name()
-                .and_then(|n| n.to_str())
-                .map_or_else(
-                    || {
-                        let timestamp = SystemTime::now()
-                            .duration_since(UNIX_EPOCH)
-                            .unwrap()
-                            .as_nanos();
-                        format!("read_file_{timestamp}.txt")
-                    },
-                    ToString::to_string,
-                )
+/// Runs `deepseek explain`: sends the code with the explanation prompt in place of the
+/// agent's, without tools or web search, and prints the reply as the chat does.
+async fn run_explain(agent: Agent, mut session: Session, excerpt: &Excerpt) -> Result<()> {
+    agent.interrupt_on_ctrl_c();
+    session.search = false;
+    eprintln!("{}", format!("Explaining {}", excerpt.describe()).cyan());
+    let prompt = format!("{EXPLAIN_SYSTEM_PROMPT}\n\n{}", explain::prompt(excerpt));
+    let parent_id = session.parent_id;
+    match agent
+        .complete(&mut session, &prompt, parent_id, &[])
+        .await?
+    {
+        Some(_) => Ok(()),
+        None => outcome_result(&Outcome::Interrupted),
+    }
+}
+
+/// Runs `deepseek changelog`: warns about commit subjects that do not follow Conventional
+/// Commits, sends the commits a chunk at a time, the first after the changelog prompt in
+/// place of the agent's, then shows the notes grouped by type and, once confirmed or
+/// edited, adds them to the changelog as its newest entry.
+async fn run_changelog(
+    agent: Agent,
+    mut session: Session,
+    options: &ChangelogOptions,
+    since: &str,
+    commits: &[Commit],
+) -> Result<()> {
+    let unconventional = changelog::unconventional(commits);
+    if !unconventional.is_empty() {
+        eprintln!(
+            "{}",
+            format!(
+                "{} of {} commits do not follow Conventional Commits:",
+                unconventional.len(),
+                commits.len()
+            )
+            .yellow()
+        );
+        for commit in unconventional {
+            eprintln!("  {} {}", commit.short_hash(), commit.subject);
+        }
+    }
+    agent.interrupt_on_ctrl_c();
+    session.events = session.attach(EventSink::default().without_echo());
+    let chunks = changelog::chunks(commits, changelog::MAX_CHUNK_BYTES);
+    let total = chunks.len();
+    eprintln!(
+        "{}",
+        format!(
+            "Drafting release notes for {} commits since {since} in {total} parts",
+            commits.len()
+        )
+        .cyan()
+    );
+    let mut entries = Vec::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        eprintln!(
+            "{}",
+            format!("[{}/{total}] {} commit(s)", i + 1, chunk.commits).dimmed()
+        );
+        let mut prompt = changelog::chunk_prompt(chunk, i + 1, total);
+        if session.parent_id.is_none() {
+            prompt = format!("{CHANGELOG_SYSTEM_PROMPT}\n\n{prompt}");
         }
-        "fetch_url" => {
-            // Create a filename from the URL
-            let url_part = full_arg.lines().next().unwrap_or("url");
-            // Remove protocol and replace non-alphanumeric characters
-            let url_clean = url_part
-                .replace("https://", "")
-                .replace("http://", "")
-                .replace(|c: char| !c.is_alphanumeric() && c != '.', "_");
-            format!("{url_clean}.html")
-        }
-        "browser_get_html" => {
-            // Try to get a descriptive name from the URL or use default
-            let url_part = full_arg.lines().next().unwrap_or("page");
-            let sanitized = url_part.replace(|c: char| !c.is_alphanumeric() && c != '.', "_");
-            format!("{sanitized}.html")
-        }
-        _ => {
-            let timestamp = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_nanos();
-            format!("tool_result_{tool_name}_{timestamp}.txt")
+        let parent_id = session.parent_id;
+        let Some(reply) = agent
+            .complete(&mut session, &prompt, parent_id, &[])
+            .await?
+        else {
+            return outcome_result(&Outcome::Interrupted);
+        };
+        session.parent_id = reply.message_id.or(parent_id);
+        entries.extend(changelog::parse_entries(&reply.content));
+    }
+    if entries.is_empty() {
+        println!("Nothing in the commits since {since} to list in the changelog");
+        return Ok(());
+    }
+    let path = &options.output;
+    let mut entry = changelog::render(&options.title, &entries);
+    loop {
+        println!("\n{entry}");
+        let question = format!("Add this to {}? [y/N/e(dit)] ", path.display());
+        let answer = tokio::task::spawn_blocking(move || {
+            print!("{question}");
+            std::io::stdout().flush().ok()?;
+            let mut line = String::new();
+            match std::io::stdin().read_line(&mut line) {
+                Ok(0) | Err(_) => None,
+                Ok(_) => Some(line),
+            }
+        })
+        .await?;
+        match answer.as_deref().map(str::trim) {
+            Some("y" | "Y" | "yes") => break,
+            Some("e" | "E" | "edit") => entry = format!("{}\n", editor::edit(&entry)?.trim()),
+            _ => {
+                println!("Did not change {}", path.display());
+                return Ok(());
+            }
         }
+    }
+    let existing = match fs::read_to_string(path).await {
+        Ok(existing) => existing,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => bail!("Cannot read {}: {e}", path.display()),
     };
+    fs::write(path, changelog::insert(&existing, &entry)).await?;
+    println!(
+        "{}",
+        format!("Added the release notes to {}", path.display()).green()
+    );
+    Ok(())
+}
+
+fn print_findings(findings: &[Finding]) {
+    if findings.is_empty() {
+        println!("{}", "No issues found".green());
+        return;
+    }
+    for (path, group) in review::by_file(findings) {
+        println!("\n{}", path.bold());
+        for finding in group {
+            let severity = match finding.severity {
+                Severity::High => finding.severity.name().red().bold(),
+                Severity::Medium => finding.severity.name().yellow(),
+                Severity::Low => finding.severity.name().cyan(),
+            };
+            let line = finding
+                .line
+                .map(|line| format!("line {line}: "))
+                .unwrap_or_default();
+            println!("  [{severity}] {line}{}", finding.message);
+        }
+    }
+    let count = |severity| findings.iter().filter(|f| f.severity == severity).count();
+    println!(
+        "\n{} findings: {} high, {} medium, {} low",
+        findings.len(),
+        count(Severity::High),
+        count(Severity::Medium),
+        count(Severity::Low)
+    );
+}
 
-    let file_data = content.as_bytes().to_vec();
-    let file_info = api.upload_file(file_data, &filename, None).await?;
-    Ok(file_info.id)
-}
-
-fn parse_tool_invocations(content: &str) -> Vec<(String, String)> {
-    let lines: Vec<&str> = content.lines().collect();
-    let mut i = 0;
-    let mut invocations = Vec::new();
-    while i < lines.len() {
-        let line = lines[i].trim();
-        if let Some(stripped) = line.strip_prefix("TOOL:") {
-            let tool_line = stripped.trim();
-            let mut tool_parts = tool_line.splitn(2, ' ');
-            let tool_name = tool_parts.next().unwrap_or("").to_string();
-            let first_arg = tool_parts.next().unwrap_or("").to_string();
-
-            let mut body_lines = Vec::new();
-            i += 1;
-            while i < lines.len() && !lines[i].trim().starts_with("TOOL:") {
-                body_lines.push(lines[i]);
-                i += 1;
-            }
-            let body = body_lines.join("\n");
-
-            let full_arg = if body.is_empty() {
-                first_arg
-            } else if first_arg.is_empty() {
-                body
+/// How a message sent without a terminal went, once it has been answered.
+fn unattended_outcome(session: &Session) -> Outcome {
+    if session.policy_refusals > 0 {
+        Outcome::Refused(session.policy_refusals)
+    } else if let Some(turns) = session.stopped_at_limit {
+        Outcome::TurnLimit(turns)
+    } else if session.interrupted.is_some() {
+        Outcome::Interrupted
+    } else {
+        Outcome::Completed
+    }
+}
+
+/// Fails with the outcome's exit status unless it completed.
+fn outcome_result(outcome: &Outcome) -> Result<()> {
+    if outcome.is_completed() {
+        return Ok(());
+    }
+    Err(exit::error(outcome.status(), outcome.describe()))
+}
+
+/// `git diff --stat` for the working tree, or `None` outside a repository.
+async fn git_diff_stat() -> Option<String> {
+    let output = tokio::process::Command::new("git")
+        .args(["diff", "--stat", "HEAD"])
+        .output()
+        .await
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+async fn handle_command(
+    agent: &Agent,
+    session: &mut Session,
+    rl: &Arc<Mutex<ReplEditor>>,
+    command: SlashCommand,
+) -> Result<()> {
+    match command {
+        SlashCommand::Help => println!("{HELP}"),
+        SlashCommand::Exit => {}
+        SlashCommand::New => {
+            // The system prompt is sent again with the first message since there is no parent yet
+            session.switch_to(Session::create(agent.api()).await?);
+        }
+        SlashCommand::Plan => {
+            if session.mode == AgentMode::Plan {
+                println!("Already in plan mode");
+                return Ok(());
+            }
+            session.mode = AgentMode::Plan;
+            if session.parent_id.is_some() {
+                session
+                    .pending_notes
+                    .push(tools::PLAN_MODE_INSTRUCTIONS.to_string());
+            }
+            println!(
+                "{}",
+                "Plan mode: only read-only tools are available. Use /execute to approve the plan."
+                    .magenta()
+            );
+        }
+        SlashCommand::Execute => {
+            if session.mode != AgentMode::Plan {
+                println!("Not in plan mode");
+                return Ok(());
+            }
+            session.mode = AgentMode::Normal;
+            println!("{}", "All tools unlocked".magenta());
+            if session.parent_id.is_none() {
+                return Ok(());
+            }
+            // The chat may have started with the plan-mode prompt, so list every tool again
+            let message = format!(
+                "The plan is approved and plan mode is over. All tools are available again:\n{}\n\nCarry out the plan now.",
+                tools::tool_descriptions(AgentMode::Normal, session.tools().as_deref())
+            );
+            agent.send_message(session, &message).await?;
+        }
+        SlashCommand::Focus(path) if path.is_empty() => {
+            let stack = focus::stack();
+            if stack.is_empty() {
+                println!("Nothing is focused");
+            }
+            for (i, path) in stack.iter().enumerate().rev() {
+                println!("{}. {}", i + 1, path.display());
+            }
+        }
+        SlashCommand::Focus(path) => {
+            let path = focus::push(&path)?;
+            println!("{}", format!("Focused on {}", path.display()).cyan());
+            if path.is_file() {
+                agent.pin_file(session, &path).await?;
+                println!(
+                    "{}",
+                    "File contents will be attached to your next message".cyan()
+                );
+            }
+        }
+        SlashCommand::Attach(path) => {
+            if path.is_empty() {
+                println!("Usage: /attach <path>");
+                return Ok(());
+            }
+            agent.attach_file(session, Path::new(&path)).await?;
+            println!("{}", "It will be sent with your next message".cyan());
+        }
+        SlashCommand::Unfocus => match focus::pop() {
+            Some(path) => {
+                session.pinned.unpin(&path);
+                let now = focus::current()
+                    .map_or_else(|| "nothing".to_string(), |p| p.display().to_string());
+                println!(
+                    "{}",
+                    format!("Unfocused {}, now focused on {now}", path.display()).cyan()
+                );
+            }
+            None => println!("Nothing is focused"),
+        },
+        SlashCommand::Chats(key) => {
+            let registry = ChatRegistry::load()?;
+            let key = if key.is_empty() {
+                print_chats(&registry);
+                if registry.list().is_empty() {
+                    return Ok(());
+                }
+                match read_line(rl, "Resume which chat? (number, Enter to cancel): ").await {
+                    Some(line) if !line.trim().is_empty() => line.trim().to_string(),
+                    _ => return Ok(()),
+                }
             } else {
-                format!("{first_arg}\n{body}")
+                key
             };
-            invocations.push((tool_name, full_arg));
-        } else {
-            i += 1;
-        }
-    }
-    invocations
-}
-
-async fn process_single_tool(
-    api: &DeepSeekAPI,
-    tool_name: &str,
-    full_arg: &str,
-) -> (Option<String>, String) {
-    // Validate single-line path tools
-    let single_line_path_tools = ["read_file", "create_directory", "list_files"];
-    if single_line_path_tools.contains(&tool_name) && full_arg.contains('\n') {
-        let err_msg = format!("TOOL {tool_name} failed: path argument must be on a single line (no newlines)");
-        eprintln!("{}", err_msg.red());
-        return (None, err_msg);
-    }
-    match execute_tool(tool_name, full_arg).await {
-        Ok(tool_output) => {
-            // Print status for all variants
-            let status = match &tool_output {
-                ToolOutput::Text { status, .. }
-                | ToolOutput::Binary { status, .. }
-                | ToolOutput::FileReference { status, .. }
-                | ToolOutput::StatusOnly { status } => status,
+            let entry = registry
+                .resolve(&key)
+                .ok_or_else(|| anyhow!("Unknown chat: {key}"))?;
+            session.switch_to(Session::resume(agent.api(), entry.id.clone()).await?);
+        }
+        SlashCommand::Retry => {
+            let Some(last) = session.last_turn.take() else {
+                println!("Nothing to retry yet");
+                return Ok(());
+            };
+            println!("{}", "Regenerating the last response...".cyan());
+            let Some(response) = agent.run_turn(session, &last).await? else {
+                session.last_turn = Some(last);
+                return Ok(());
+            };
+            println!("{}", "--- Changes from previous response ---".yellow());
+            match word_diff(&last.response, &response) {
+                Some(changes) if changes.iter().all(|c| matches!(c, WordChange::Same(_))) => {
+                    println!("(no differences)");
+                }
+                Some(changes) => println!("{}", render_word_diff(&changes)),
+                None => println!("(responses are too long to diff)"),
+            }
+            session.last_turn = Some(LastTurn { response, ..last });
+        }
+        SlashCommand::Continue => {
+            let Some(interrupted) = session.interrupted.take() else {
+                println!(
+                    "Nothing to continue; /continue finishes a response interrupted with Ctrl+C or cut short"
+                );
+                return Ok(());
+            };
+            println!("{}", "Continuing the interrupted response...".cyan());
+            let turn = LastTurn {
+                prompt: continuation_prompt(&interrupted.prompt, &interrupted.response),
+                response: String::new(),
+                ..interrupted
+            };
+            if let Some(response) = agent.run_turn(session, &turn).await? {
+                session.last_turn = Some(LastTurn { response, ..turn });
+            }
+        }
+        SlashCommand::EditLast => {
+            let Some(last) = session.last_message.clone() else {
+                println!("No message to edit yet");
+                return Ok(());
+            };
+            let edited = editor::edit(&last.text)?;
+            let edited = edited.trim();
+            if edited.is_empty() || edited == last.text.trim() {
+                println!("Message unchanged; nothing sent");
+                return Ok(());
+            }
+            // Sent in place of the original, as another reply to the same message
+            session.parent_id = last.parent_id;
+            session.pending_files.splice(0..0, last.files);
+            println!("{}", "Sending the edited message...".cyan());
+            agent.send_message(session, edited).await?;
+        }
+        SlashCommand::Fork(arg) => {
+            let mut words = arg.split_whitespace();
+            let Some(id) = words.next() else {
+                print_replies(&session.branches);
+                return Ok(());
             };
-            println!("{}", status.cyan());
-
-            match tool_output {
-                ToolOutput::Text { content, status } => {
-                    // Tools that should upload their output as files
-                    let upload_tools = [
-                        "read_file",
-                        "fetch_url",
-                        "list_files",
-                        "run_command",
-                        "search_web",
-                        "browser_get_html",
-                    ];
-                    if upload_tools.contains(&tool_name) {
-                        // Upload the content
-                        match upload_tool_output(api, &content, tool_name, full_arg).await {
-                            Ok(file_id) => (Some(file_id), status),
-                            Err(e) => {
-                                eprintln!("Failed to upload tool output: {e}");
-                                (None, format!("{status}\n\n{content}"))
-                            }
-                        }
+            let id: i64 = id.parse().map_err(|_| anyhow!("Not a message id: {id}"))?;
+            if session.branches.get(id).is_none() {
+                println!(
+                    "{}",
+                    format!("Message {id} was not seen this session; forking from it anyway")
+                        .yellow()
+                );
+            }
+            let label = session.branches.fork(id, words.next())?.label.clone();
+            session.move_to(Some(id));
+            println!(
+                "{}",
+                format!("On new branch {label}; the next message replies to message {id}").cyan()
+            );
+        }
+        SlashCommand::Branches(key) => {
+            if key.is_empty() {
+                print_branches(&session.branches);
+                return Ok(());
+            }
+            let branch = session.branches.switch(&key)?.clone();
+            session.move_to(branch.head);
+            println!("{}", format!("Switched to branch {}", branch.label).cyan());
+        }
+        SlashCommand::Paste(text) => {
+            let pasted = clipboard::read()?;
+            let fence = mentions::fence_for(&pasted);
+            let newline = if pasted.ends_with('\n') { "" } else { "\n" };
+            let block = format!("{fence}\n{pasted}{newline}{fence}");
+            println!(
+                "{}",
+                format!("Pasted {} lines from the clipboard", pasted.lines().count()).cyan()
+            );
+            let message = if text.is_empty() {
+                block
+            } else {
+                format!("{text}\n\n{block}")
+            };
+            agent.send_message(session, &message).await?;
+        }
+        SlashCommand::Export(arg) => {
+            let mut parts = arg.split_whitespace();
+            let format = parts.next().unwrap_or("");
+            let path = parts.next();
+            let title = session
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("Chat {}", session.chat_id));
+            let conversation = session.conversation.lock().unwrap().clone();
+            let (output, exported) = match format {
+                "script" => (
+                    session.recipe.to_shell_script(),
+                    format!("{} recorded action(s)", session.recipe.actions().len()),
+                ),
+                "json" => (
+                    session.recipe.to_json()?,
+                    format!("{} recorded action(s)", session.recipe.actions().len()),
+                ),
+                "md" | "markdown" => (
+                    conversation.to_markdown(&title),
+                    format!("{} message(s)", conversation.user_messages()),
+                ),
+                "html" => (
+                    conversation.to_html(&title),
+                    format!("{} message(s)", conversation.user_messages()),
+                ),
+                _ => bail!("Usage: /export script|json|md|html [path]"),
+            };
+            match path {
+                Some(path) => {
+                    fs::write(path, &output).await?;
+                    println!("{}", format!("Exported {exported} to {path}").cyan());
+                }
+                None => println!("{output}"),
+            }
+        }
+        SlashCommand::Stats(arg) => match arg.as_str() {
+            "" => {
+                println!("{}", session.stats.render());
+                println!("\n{}", session.stats.render_turns());
+            }
+            "footer" | "footer on" | "footer off" => {
+                session.turn_footer = match arg.as_str() {
+                    "footer" => !session.turn_footer,
+                    setting => setting == "footer on",
+                };
+                let state = if session.turn_footer { "on" } else { "off" };
+                println!("{}", format!("Turn timing footer is {state}").magenta());
+            }
+            other => {
+                bail!("Unknown /stats argument: {other} (expected footer, footer on or footer off)")
+            }
+        },
+        SlashCommand::Var(arg) => {
+            let (action, rest) = arg.split_once(' ').unwrap_or((arg.as_str(), ""));
+            match action {
+                "" | "list" => {
+                    if session.vars.is_empty() {
+                        println!("No variables set");
+                    }
+                    for (name, value) in session.vars.entries() {
+                        println!("{name}={value}");
+                    }
+                }
+                "set" => {
+                    session.vars.set(rest)?;
+                    println!("{}", format!("Set {}", rest.trim()).magenta());
+                }
+                "unset" => {
+                    let name = rest.trim();
+                    if session.vars.unset(name) {
+                        println!("{}", format!("Unset {name}").magenta());
                     } else {
-                        // Just return the status as the message, no file upload
-                        (None, status)
+                        println!("{name} is not set");
                     }
                 }
-                ToolOutput::Binary {
-                    data,
-                    mime_type,
-                    status,
-                } => {
-                    // For binary data (e.g., screenshot), upload the file
-                    let filename = if mime_type == "image/png" {
-                        format!("screenshot_{}.png", chrono::Utc::now().timestamp())
+                other => bail!("Unknown /var action: {other} (expected set or unset)"),
+            }
+        }
+        SlashCommand::Model(name) => {
+            if !name.is_empty() {
+                session.model = Model::from_name(&name).ok_or_else(|| {
+                    anyhow!("Unknown model: {name} (expected {})", Model::names())
+                })?;
+            }
+            println!("{}", format!("Model: {}", session.model).magenta());
+        }
+        SlashCommand::Set(arg) => {
+            if !arg.is_empty() {
+                match Setting::parse(&arg)? {
+                    Setting::Search(on) => session.search = on,
+                    Setting::Model(model) => session.model = model,
+                }
+            }
+            println!(
+                "{}",
+                settings::render(session.search, session.model).magenta()
+            );
+        }
+        SlashCommand::Usage => println!("{}", session.stats.render_usage()),
+        SlashCommand::Queue(action) => match action.as_str() {
+            "" => {
+                if session.offline_queue.is_empty() {
+                    println!("No queued messages");
+                }
+                for (i, message) in session.offline_queue.iter().enumerate() {
+                    println!("{}. {message}", i + 1);
+                }
+            }
+            "send" => {
+                session.offline = false;
+                send_offline_queue(agent, session).await;
+            }
+            "clear" => {
+                println!("Dropped {} queued messages", session.offline_queue.len());
+                session.offline_queue.clear();
+            }
+            other => bail!("Unknown /queue action: {other} (expected send or clear)"),
+        },
+        SlashCommand::Format(name) => {
+            match name.as_str() {
+                "" => {}
+                "off" | "none" => session.format = None,
+                name => {
+                    session.format = Some(ResponseFormat::from_name(name).ok_or_else(|| {
+                        anyhow!("Unknown format: {name} (expected json, markdown, plain or off)")
+                    })?);
+                }
+            }
+            let current = session.format.map_or("any", ResponseFormat::name);
+            println!("{}", format!("Reply format: {current}").magenta());
+        }
+        SlashCommand::Style(name) => {
+            match name.as_str() {
+                "" => {}
+                "off" | "none" => session.style = None,
+                name => {
+                    session.style = Some(Style::from_name(name).ok_or_else(|| {
+                        anyhow!("Unknown style: {name} (expected {} or off)", Style::names())
+                    })?);
+                }
+            }
+            let current = session.style.map_or("default", Style::name);
+            println!("{}", format!("Reply style: {current}").magenta());
+        }
+        SlashCommand::Quiet => {
+            session.quiet = !session.quiet;
+            if session.quiet {
+                println!("{}", "Thinking hidden".magenta());
+            } else {
+                println!("{}", "Thinking shown".magenta());
+            }
+        }
+        SlashCommand::DryRun(arg) => {
+            match arg.as_str() {
+                "" => {}
+                "on" => session.set_dry_run(true),
+                "off" => session.set_dry_run(false),
+                other => bail!("Unknown /dryrun setting: {other} (expected on or off)"),
+            }
+            if session.dry_run {
+                println!(
+                    "{}",
+                    "Dry run is on: file changes and commands are only previewed".magenta()
+                );
+            } else {
+                println!("{}", "Dry run is off".magenta());
+            }
+        }
+        SlashCommand::Checkpoint(arg) => handle_checkpoint_command(session, &arg)?,
+        SlashCommand::Profile(name) => {
+            match name.as_str() {
+                "" => {
+                    for name in config::get().profile_names() {
+                        let current = session.profile.as_ref().is_some_and(|(n, _)| *n == name);
+                        println!("{}{name}", if current { "* " } else { "  " });
+                    }
+                    return Ok(());
+                }
+                "off" | "none" => session.set_profile(None)?,
+                name => session.set_profile(Some(name))?,
+            }
+            let current = session.profile.as_ref().map_or("none", |(name, _)| name);
+            println!("{}", format!("Profile: {current}").magenta());
+        }
+        SlashCommand::Workspace(name) => {
+            if name.is_empty() {
+                let workspace = workspace::current();
+                for root in workspace.roots() {
+                    let marker = if root.path == workspace.root() {
+                        "* "
                     } else {
-                        format!("binary_data_{}", chrono::Utc::now().timestamp())
+                        "  "
                     };
-                    match api.upload_file(data, &filename, Some(&mime_type)).await {
-                        Ok(file_info) => (Some(file_info.id), status),
-                        Err(e) => {
-                            eprintln!("Failed to upload binary data: {e}");
-                            (None, format!("Binary data captured but upload failed: {e}"))
-                        }
-                    }
+                    println!("{marker}{} {}", root.name, root.path.display());
                 }
-                ToolOutput::FileReference { file_id, status } => (Some(file_id), status),
-                ToolOutput::StatusOnly { status } => (None, status),
+                return Ok(());
             }
+            let root = workspace::switch(&name)?;
+            index::invalidate();
+            if session.parent_id.is_some() {
+                session.pending_notes.push(format!(
+                    "The active workspace root is now {} ({}): relative paths in tool arguments are taken from there.",
+                    root.name,
+                    root.path.display()
+                ));
+            }
+            println!(
+                "{}",
+                format!("Workspace root: {} ({})", root.name, root.path.display()).magenta()
+            );
         }
-        Err(e) => {
-            eprintln!("{}", format!("Tool {tool_name} failed: {e}").red());
-            (None, format!("TOOL {tool_name} failed: {e}"))
+        SlashCommand::Init => handle_init_command(agent, session, rl).await?,
+        SlashCommand::Commit => handle_commit_command(agent, session, rl).await?,
+        SlashCommand::Compact => {
+            agent.compact(session, &[]).await?;
+        }
+        SlashCommand::Context(arg) => match arg.split_once(' ').unwrap_or((&arg, "")) {
+            ("", _) => println!("{}", session.context.render()),
+            ("drop", selection) => {
+                let plan = session
+                    .context
+                    .plan_drop(&context::parse_selection(selection)?)?;
+                for path in &plan.unpin {
+                    session.pinned.unpin(path);
+                }
+                session.context.omit(&plan.sections);
+                if agent.compact(session, &plan.forget).await? {
+                    println!("{}", "Dropped the items from the new chat's context".cyan());
+                }
+            }
+            (other, _) => bail!("Unknown /context action: {other} (expected drop)"),
+        },
+        SlashCommand::Remember(text) => {
+            let mut memory = Memory::load()?;
+            let fact = memory.add(&text, memory::Source::User)?;
+            println!("{}", format!("Remembered: {}", fact.text).cyan());
+            if session.parent_id.is_some() {
+                session
+                    .pending_notes
+                    .push(format!("Remember from now on: {}", fact.text));
+            }
+        }
+        SlashCommand::Memory(arg) => {
+            let mut memory = Memory::load()?;
+            match arg.split_once(' ').unwrap_or((&arg, "")) {
+                ("" | "list", _) if memory.facts().is_empty() => {
+                    println!("Nothing remembered yet; save facts with /remember <fact>");
+                }
+                ("" | "list", _) => println!("{}", memory.list()),
+                ("forget", number) => {
+                    let number = number
+                        .trim()
+                        .parse()
+                        .map_err(|_| anyhow!("Usage: /memory forget <number>"))?;
+                    let fact = memory.forget(number)?;
+                    println!("{}", format!("Forgot: {}", fact.text).cyan());
+                }
+                _ => println!("Usage: /memory [list|forget <n>]"),
+            }
+        }
+        SlashCommand::Unknown(name) => {
+            eprintln!("Unknown command: /{name} (type /help for a list of commands)");
         }
     }
+    Ok(())
 }
 
-async fn handle_tool_calls(
-    api: &DeepSeekAPI,
-    chat_id: &str,
-    current_msg: Message,
-    parent_id: &mut Option<i64>,
-    ctrl_rx: &mut broadcast::Receiver<()>,
-) -> Result<Option<Message>> {
-    let invocations = parse_tool_invocations(&current_msg.content);
-
-    if invocations.is_empty() {
-        return Ok(None);
+/// Handles `/checkpoint`: with no argument, takes a checkpoint of the working tree.
+/// Sends the model an overview of the project, asks for a `DEEPSEEK.md` and writes the
+/// reply once the user agrees.
+async fn handle_init_command(
+    agent: &Agent,
+    session: &mut Session,
+    rl: &Arc<Mutex<ReplEditor>>,
+) -> Result<()> {
+    let root = std::env::current_dir()?;
+    let path = root.join(instructions::FILE_NAME);
+    println!("{}", "Scanning the project...".cyan());
+    // Only this project's files, not those of the workspace's other roots
+    let files = index::FileIndex::build(&root);
+    let overview = instructions::project_overview(&root, files.files());
+    let previous = session.last_turn.take();
+    agent
+        .send_message(session, &instructions::init_prompt(&overview))
+        .await?;
+    let Some(reply) = session.last_turn.as_ref().map(|turn| turn.response.clone()) else {
+        session.last_turn = previous;
+        println!("No {} was written", instructions::FILE_NAME);
+        return Ok(());
+    };
+    let content = instructions::extract_markdown(&reply);
+    let action = if path.exists() { "Replace" } else { "Write" };
+    let question = format!("{action} {} with this? [y/N] ", path.display());
+    match read_line(rl, &question).await {
+        Some(answer) if matches!(answer.trim(), "y" | "Y" | "yes") => {
+            std::fs::write(&path, content)?;
+            println!("{}", format!("Wrote {}", path.display()).green());
+        }
+        _ => println!("Did not write {}", instructions::FILE_NAME),
     }
+    Ok(())
+}
 
-    let mut file_ids = Vec::new();
-    let mut result_messages = Vec::new();
-
-    for (tool_name, full_arg) in invocations {
-        let (file_id_opt, msg) = process_single_tool(api, &tool_name, &full_arg).await;
-        if let Some(file_id) = file_id_opt {
-            file_ids.push(file_id);
+/// Runs `/commit`: the model drafts a Conventional Commits message for the staged changes,
+/// which is shown for approval or editing before `git commit` runs. With nothing staged,
+/// the files changed this session are staged first.
+async fn handle_commit_command(
+    agent: &Agent,
+    session: &mut Session,
+    rl: &Arc<Mutex<ReplEditor>>,
+) -> Result<()> {
+    let root = std::env::current_dir()?;
+    if !commit::is_repository(&root) {
+        bail!("/commit needs a git repository");
+    }
+    let mut changes = commit::staged(&root)?;
+    if changes.is_none() && !session.dry_run {
+        let staged = commit::stage(&root, &session.recipe.changed_files());
+        if !staged.is_empty() {
+            println!(
+                "{}",
+                format!(
+                    "Staged the files changed this session: {}",
+                    staged.join(", ")
+                )
+                .cyan()
+            );
+            changes = commit::staged(&root)?;
+        }
+    }
+    let Some(changes) = changes else {
+        println!("Nothing to commit: stage changes with git add, or make some in this session");
+        return Ok(());
+    };
+    println!("{}", "Writing a commit message...".cyan());
+    let previous = session.last_turn.take();
+    agent
+        .send_message(session, &commit::prompt(&changes))
+        .await?;
+    let Some(reply) = session.last_turn.as_ref().map(|turn| turn.response.clone()) else {
+        session.last_turn = previous;
+        println!("No commit message was written");
+        return Ok(());
+    };
+    let mut message = commit::extract_message(&reply);
+    loop {
+        println!("\n{message}\n");
+        if !message.lines().next().is_some_and(commit::is_conventional) {
+            println!(
+                "{}",
+                "The subject line does not follow Conventional Commits".yellow()
+            );
         }
-        result_messages.push(msg);
+        let answer = read_line(rl, "Commit with this message? [y/N/e(dit)] ").await;
+        match answer.as_deref().map(str::trim) {
+            Some("y" | "Y" | "yes") => break,
+            Some("e" | "E" | "edit") => message = editor::edit(&message)?.trim().to_string(),
+            _ => {
+                println!("Did not commit");
+                return Ok(());
+            }
+        }
+    }
+    if session.dry_run {
+        println!("{}", "Dry run: git commit was not run".yellow());
+        return Ok(());
     }
+    let summary = commit::commit(&root, &message)?;
+    println!("{}", summary.green());
+    Ok(())
+}
 
-    let next_prompt = format!(
-        "{}\n\nContinue with the next step or provide the final answer.",
-        result_messages.join("\n\n")
-    );
-    let stream = api.complete_stream(
-        chat_id.to_string(),
-        next_prompt,
-        *parent_id,
-        true,
-        true,
-        file_ids,
-    );
-    let new_msg = handle_stream(stream, ctrl_rx).await?;
-    if let Some(msg) = new_msg {
-        *parent_id = msg.message_id;
-        Ok(Some(msg))
-    } else {
-        Ok(None)
+fn handle_checkpoint_command(session: &mut Session, arg: &str) -> Result<()> {
+    let (action, id) = arg.split_once(' ').unwrap_or((arg, ""));
+    match action {
+        "" => {
+            let checkpoint = checkpoint::create(Path::new("."), "deepseek-cli: /checkpoint")?;
+            println!(
+                "{}",
+                format!(
+                    "Saved {} checkpoint {}",
+                    checkpoint.backend,
+                    checkpoint.short_id()
+                )
+                .magenta()
+            );
+            session.checkpoints.push(checkpoint);
+        }
+        "list" => {
+            if session.checkpoints.is_empty() {
+                println!("No checkpoints taken this session");
+            }
+            for checkpoint in &session.checkpoints {
+                println!(
+                    "{} {:<8} {}",
+                    checkpoint.short_id(),
+                    checkpoint.backend.name(),
+                    checkpoint.message
+                );
+            }
+        }
+        "restore" => {
+            let id = id.trim();
+            let checkpoint = if id.is_empty() {
+                session.checkpoints.last()
+            } else {
+                checkpoint::find(&session.checkpoints, id)
+            }
+            .ok_or_else(|| anyhow!("No checkpoint to restore (see /checkpoint list)"))?;
+            checkpoint::restore(checkpoint)?;
+            println!(
+                "{}",
+                format!("Restored checkpoint {}", checkpoint.short_id()).magenta()
+            );
+        }
+        other => bail!("Unknown /checkpoint action: {other} (expected list or restore)"),
     }
+    Ok(())
 }
@@ -0,0 +1,149 @@
+use anyhow::{Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+
+/// Who saved a fact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    /// With `/remember`
+    User,
+    /// With the `remember` tool
+    Model,
+}
+
+/// Something to keep in mind in every session, such as "we use pnpm, not npm".
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fact {
+    pub text: String,
+    pub source: Source,
+    /// Unix timestamp (seconds) of when the fact was saved.
+    pub saved_at: i64,
+}
+
+/// Facts saved across sessions, one JSON object per line in
+/// `~/.config/deepseek-cli/memory.jsonl`.
+#[derive(Debug, Default)]
+pub struct Memory {
+    path: PathBuf,
+    facts: Vec<Fact>,
+}
+
+impl Memory {
+    #[must_use]
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|d| d.join("deepseek-cli/memory.jsonl"))
+    }
+
+    /// Loads the facts from the default location.
+    ///
+    /// # Errors
+    /// Returns an error if there is no config directory or the file cannot be read.
+    pub fn load() -> Result<Self> {
+        let path = Self::path().ok_or_else(|| anyhow!("No config directory available"))?;
+        Self::open(&path)
+    }
+
+    /// Loads the facts stored at `path`, which need not exist yet. Lines that cannot be
+    /// parsed are skipped.
+    ///
+    /// # Errors
+    /// Returns an error if the file exists but cannot be read.
+    pub fn open(path: &Path) -> Result<Self> {
+        let facts = match std::fs::read_to_string(path) {
+            Ok(content) => content
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            facts,
+        })
+    }
+
+    #[must_use]
+    pub fn facts(&self) -> &[Fact] {
+        &self.facts
+    }
+
+    /// Saves a fact, unless the same one is already known.
+    ///
+    /// # Errors
+    /// Returns an error if the fact is empty or the file cannot be written.
+    pub fn add(&mut self, text: &str, source: Source) -> Result<&Fact> {
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.is_empty() {
+            bail!("Nothing to remember");
+        }
+        if let Some(i) = self.facts.iter().position(|f| f.text == text) {
+            return Ok(&self.facts[i]);
+        }
+        let fact = Fact {
+            text,
+            source,
+            saved_at: chrono::Utc::now().timestamp(),
+        };
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(&fact)?)?;
+        self.facts.push(fact);
+        Ok(&self.facts[self.facts.len() - 1])
+    }
+
+    /// Removes the fact numbered `number` in [`Memory::list`], counting from 1.
+    ///
+    /// # Errors
+    /// Returns an error if there is no such fact or the file cannot be written.
+    pub fn forget(&mut self, number: usize) -> Result<Fact> {
+        if number == 0 || number > self.facts.len() {
+            bail!("No fact {number}; /memory list shows them");
+        }
+        let fact = self.facts.remove(number - 1);
+        let mut content = String::new();
+        for fact in &self.facts {
+            content.push_str(&serde_json::to_string(fact)?);
+            content.push('\n');
+        }
+        std::fs::write(&self.path, content)?;
+        Ok(fact)
+    }
+
+    /// The facts, numbered for [`Memory::forget`].
+    #[must_use]
+    pub fn list(&self) -> String {
+        let mut out = String::new();
+        for (i, fact) in self.facts.iter().enumerate() {
+            let by = match fact.source {
+                Source::User => "",
+                Source::Model => " (saved by the model)",
+            };
+            let _ = writeln!(out, "{}. {}{by}", i + 1, fact.text);
+        }
+        out.trim_end().to_string()
+    }
+
+    /// The facts as a section of the system prompt, or `None` if there are none.
+    #[must_use]
+    pub fn prompt_section(&self) -> Option<String> {
+        if self.facts.is_empty() {
+            return None;
+        }
+        let mut section = String::from(
+            "Facts the user asked you to remember from earlier sessions; keep to them:",
+        );
+        for fact in &self.facts {
+            let _ = write!(section, "\n- {}", fact.text);
+        }
+        Some(section)
+    }
+}
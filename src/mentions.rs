@@ -0,0 +1,105 @@
+use crate::attach;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Most of a file included for one `@path` mention; longer files are cut off.
+pub const MAX_MENTION_BYTES: usize = 64 * 1024;
+
+/// Characters that may follow a mention without being part of the path, as in
+/// "look at @src/main.rs, then".
+const TRAILING_PUNCTUATION: &[char] = &[',', '.', ';', ':', '!', '?', ')', ']', '"', '\'', '`'];
+
+/// Appends the contents of every file mentioned as `@path` to the prompt, each in a fenced
+/// block. Mentions of paths that are not files are left alone, so e-mail addresses and
+/// @-handles pass through unchanged, and so are images and documents, which are uploaded
+/// instead (see [`attachments`]).
+#[must_use]
+pub fn expand(input: &str) -> String {
+    let mut seen = Vec::new();
+    let mut output = input.to_string();
+    for token in input.split_whitespace() {
+        let Some(path) = token.strip_prefix('@').and_then(mentioned_file) else {
+            continue;
+        };
+        if seen.contains(&path) || attach::is_attachment(Path::new(&path)) {
+            continue;
+        }
+        output.push_str("\n\n");
+        output.push_str(&render(&path));
+        seen.push(path);
+    }
+    output
+}
+
+/// The images and documents mentioned as `@path`, to be uploaded with the message.
+#[must_use]
+pub fn attachments(input: &str) -> Vec<String> {
+    let mut paths = Vec::new();
+    for token in input.split_whitespace() {
+        if let Some(path) = token.strip_prefix('@').and_then(mentioned_file)
+            && attach::is_attachment(Path::new(&path))
+            && !paths.contains(&path)
+        {
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+/// The file a mention refers to, trying it with trailing punctuation removed too.
+fn mentioned_file(mention: &str) -> Option<String> {
+    let mut candidate = mention;
+    loop {
+        if !candidate.is_empty() && Path::new(candidate).is_file() {
+            return Some(candidate.to_string());
+        }
+        candidate = candidate.strip_suffix(TRAILING_PUNCTUATION)?;
+    }
+}
+
+fn render(path: &str) -> String {
+    let mut bytes = Vec::new();
+    let read = File::open(path).and_then(|file| {
+        file.take(MAX_MENTION_BYTES as u64 + 1)
+            .read_to_end(&mut bytes)
+    });
+    if let Err(e) = read {
+        return format!("[Could not read @{path}: {e}]");
+    }
+    let truncated = bytes.len() > MAX_MENTION_BYTES;
+    bytes.truncate(MAX_MENTION_BYTES);
+    let content = match String::from_utf8(bytes) {
+        Ok(content) => content,
+        // Cutting the file off may have split a character in two
+        Err(e) if truncated && e.utf8_error().error_len().is_none() => {
+            let valid = e.utf8_error().valid_up_to();
+            let mut bytes = e.into_bytes();
+            bytes.truncate(valid);
+            String::from_utf8(bytes).unwrap_or_default()
+        }
+        Err(_) => return format!("[@{path} is not a text file, so it was not included]"),
+    };
+    let header = if truncated {
+        format!(
+            "[First {} KB of {path}, which is longer; use read_file for the rest]",
+            MAX_MENTION_BYTES / 1024
+        )
+    } else {
+        format!("[Contents of {path}]")
+    };
+    let language = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+    let fence = fence_for(&content);
+    let newline = if content.ends_with('\n') { "" } else { "\n" };
+    format!("{header}\n{fence}{language}\n{content}{newline}{fence}")
+}
+
+/// A backtick fence longer than any run of backticks in the content.
+#[must_use]
+pub fn fence_for(content: &str) -> String {
+    let longest = content.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
@@ -0,0 +1,193 @@
+use crate::backend::Chunk;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// A recorded session: the prompt of each request to the API and the chunks its reply
+/// streamed, in order. `--record <fixture.json>` writes one from a real session and
+/// `--mock <fixture.json>` replays it without a token or network.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fixture {
+    pub exchanges: Vec<Exchange>,
+}
+
+/// One request and the reply it streamed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Exchange {
+    pub prompt: String,
+    pub chunks: Vec<Chunk>,
+}
+
+impl Exchange {
+    /// A reply that streams `content` in one piece and then finishes as message
+    /// `message_id`, for writing fixtures by hand.
+    #[must_use]
+    pub fn reply(prompt: &str, content: &str, message_id: i64) -> Self {
+        Self {
+            prompt: prompt.to_string(),
+            chunks: vec![
+                Chunk::Content {
+                    text: content.to_string(),
+                },
+                Chunk::Message {
+                    content: content.to_string(),
+                    message_id: Some(message_id),
+                },
+            ],
+        }
+    }
+}
+
+impl Fixture {
+    /// # Errors
+    /// Returns an error if the file cannot be read or is not a fixture.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Cannot read fixture {}: {e}", path.display()))?;
+        serde_json::from_str(&content)
+            .map_err(|e| anyhow!("Invalid fixture {}: {e}", path.display()))
+    }
+
+    /// # Errors
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Plays a fixture's replies back, one per request, in the order they were recorded.
+/// Prompts are not matched against the recorded ones, so a fixture still replays after
+/// the system prompt or tool output changes; they are kept, for tests to check what the
+/// agent sent. Clones share their place in the fixture.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    exchanges: Arc<[Exchange]>,
+    state: Arc<Mutex<ReplayState>>,
+}
+
+#[derive(Debug, Default)]
+struct ReplayState {
+    /// Prompts sent so far, one per reply played
+    prompts: Vec<String>,
+    uploads: usize,
+}
+
+impl Replay {
+    #[must_use]
+    pub fn new(fixture: Fixture) -> Self {
+        Self {
+            exchanges: fixture.exchanges.into(),
+            state: Arc::default(),
+        }
+    }
+
+    /// # Errors
+    /// Returns an error if the file cannot be read or is not a fixture.
+    pub fn load(path: &Path) -> Result<Self> {
+        Fixture::load(path).map(Self::new)
+    }
+
+    /// The chunks of the next recorded reply, given the prompt sent for it.
+    ///
+    /// # Errors
+    /// Returns an error once every recorded reply has been played.
+    pub fn next_reply(&self, prompt: &str) -> Result<Vec<Chunk>> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let Some(exchange) = self.exchanges.get(state.prompts.len()) else {
+            return Err(Exhausted {
+                request: state.prompts.len() + 1,
+                recorded: self.exchanges.len(),
+            }
+            .into());
+        };
+        if exchange.prompt != prompt {
+            tracing::debug!(
+                request = state.prompts.len() + 1,
+                "prompt differs from the recorded one"
+            );
+        }
+        state.prompts.push(prompt.to_string());
+        Ok(exchange.chunks.clone())
+    }
+
+    /// The prompts sent so far, oldest first.
+    #[must_use]
+    pub fn prompts(&self) -> Vec<String> {
+        self.state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .prompts
+            .clone()
+    }
+
+    /// An id for an uploaded file, which is otherwise dropped.
+    #[must_use]
+    pub fn upload(&self) -> String {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.uploads += 1;
+        format!("mock-file-{}", state.uploads)
+    }
+}
+
+/// The error for a request made after a fixture's last reply was played. It is not
+/// retried.
+#[derive(Debug)]
+pub struct Exhausted {
+    pub request: usize,
+    pub recorded: usize,
+}
+
+impl fmt::Display for Exhausted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The fixture has no reply for request {} (it recorded {})",
+            self.request, self.recorded
+        )
+    }
+}
+
+impl std::error::Error for Exhausted {}
+
+/// Adds each reply streamed from the API to a fixture, saved after every reply so that
+/// a session cut short still leaves one that replays up to there. Replies whose stream
+/// ended without the final message are left out.
+#[derive(Debug)]
+pub struct Recorder {
+    path: PathBuf,
+    fixture: Mutex<Fixture>,
+}
+
+impl Recorder {
+    /// Starts an empty fixture at `path`, replacing any file there.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be written.
+    pub fn create(path: &Path) -> Result<Self> {
+        let fixture = Fixture::default();
+        fixture
+            .save(path)
+            .map_err(|e| anyhow!("Cannot write fixture {}: {e}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            fixture: Mutex::new(fixture),
+        })
+    }
+
+    pub fn record(&self, prompt: &str, chunks: Vec<Chunk>) {
+        let mut fixture = self.fixture.lock().unwrap_or_else(PoisonError::into_inner);
+        fixture.exchanges.push(Exchange {
+            prompt: prompt.to_string(),
+            chunks,
+        });
+        if let Err(e) = fixture.save(&self.path) {
+            tracing::warn!(path = %self.path.display(), error = %e, "could not save the fixture");
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use std::fmt;
+
+/// Tokens a chat's context holds, for both models.
+pub const CONTEXT_WINDOW_TOKENS: usize = 128_000;
+
+/// The DeepSeek model replies come from.
+///
+/// The chat API has no model parameter: the reasoner is what answers when thinking is
+/// switched on for a completion, and the chat model otherwise. A chat can therefore
+/// switch models from one message to the next.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Model {
+    /// `deepseek-chat`, which answers directly.
+    Chat,
+    /// `deepseek-reasoner`, which thinks before answering.
+    #[default]
+    Reasoner,
+}
+
+impl Model {
+    pub const ALL: [Self; 2] = [Self::Chat, Self::Reasoner];
+
+    /// Parses a model name, with or without the `deepseek-` prefix.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        match name.strip_prefix("deepseek-").unwrap_or(&name) {
+            "chat" | "v3" => Some(Self::Chat),
+            "reasoner" | "r1" => Some(Self::Reasoner),
+            _ => None,
+        }
+    }
+
+    /// Whether completions with this model are requested with thinking enabled.
+    #[must_use]
+    pub fn thinking(self) -> bool {
+        self == Self::Reasoner
+    }
+
+    /// Names accepted by `--model` and `/model`, for error messages.
+    #[must_use]
+    pub fn names() -> String {
+        Self::ALL.map(|m| m.to_string()).join(", ")
+    }
+}
+
+impl fmt::Display for Model {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Chat => "deepseek-chat",
+            Self::Reasoner => "deepseek-reasoner",
+        })
+    }
+}
@@ -0,0 +1,84 @@
+use crate::autonomous::format_duration;
+use crate::config::{self, NotifyConfig};
+use std::io::Write;
+use std::time::Duration;
+
+/// How long a turn must take for its end to be notified when `after_secs` is not set.
+pub const DEFAULT_AFTER: Duration = Duration::from_secs(30);
+
+/// What the desktop notifications are titled.
+const TITLE: &str = "DeepSeek";
+
+/// Lets the user know, with the terminal bell or a desktop notification, that the agent
+/// wants them back: a long turn has ended or a tool call waits for approval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Notifier {
+    pub bell: bool,
+    pub desktop: bool,
+    /// How long a turn must take for its end to be notified
+    pub after: Duration,
+}
+
+/// The notifier the config sets up.
+#[must_use]
+pub fn current() -> Notifier {
+    Notifier::from_config(&config::get().notify)
+}
+
+impl Notifier {
+    #[must_use]
+    pub fn from_config(config: &NotifyConfig) -> Self {
+        Self {
+            bell: config.bell,
+            desktop: config.desktop,
+            after: config.after_secs.map_or(DEFAULT_AFTER, Duration::from_secs),
+        }
+    }
+
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.bell || self.desktop
+    }
+
+    /// What to say about the end of a turn that took `elapsed`, if it took long enough to
+    /// say anything.
+    #[must_use]
+    pub fn turn_finished_message(&self, elapsed: Duration) -> Option<String> {
+        (self.is_enabled() && elapsed >= self.after)
+            .then(|| format!("The reply is ready after {}", format_duration(elapsed)))
+    }
+
+    /// Notifies the end of a turn that took `elapsed`, if it took long enough.
+    pub fn turn_finished(&self, elapsed: Duration) {
+        if let Some(message) = self.turn_finished_message(elapsed) {
+            self.send(message);
+        }
+    }
+
+    /// Notifies that the agent waits for an answer to `question`, such as whether to run
+    /// a tool call.
+    pub fn waiting(&self, question: &str) {
+        if self.is_enabled() {
+            self.send(format!("Waiting for you: {question}"));
+        }
+    }
+
+    fn send(&self, message: String) {
+        if self.bell {
+            let mut stderr = std::io::stderr();
+            let _ = stderr.write_all(b"\x07").and_then(|()| stderr.flush());
+        }
+        if self.desktop {
+            // Showing one can wait on the notification service, which must not hold up the turn
+            std::thread::spawn(move || {
+                if let Err(e) = notify_rust::Notification::new()
+                    .summary(TITLE)
+                    .body(&message)
+                    .show()
+                {
+                    tracing::warn!(error = %e, "Failed to show a desktop notification");
+                }
+            });
+        }
+    }
+}
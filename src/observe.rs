@@ -0,0 +1,276 @@
+use crate::events::{AgentEvent, EventSink};
+use crate::highlight::StreamHighlighter;
+use crate::hyperlink;
+use anyhow::{Result, anyhow};
+use colored::Colorize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+/// Where the socket for sharing `chat_id` lives.
+#[must_use]
+pub fn socket_path(chat_id: &str) -> Option<PathBuf> {
+    dirs::config_dir().map(|d| {
+        d.join("deepseek-cli/observe")
+            .join(format!("{chat_id}.sock"))
+    })
+}
+
+/// Lines sent so far, and the channel new lines go out on. Both sit behind one lock so an
+/// observer joining mid-turn sees every line exactly once.
+struct Transcript {
+    lines: Vec<String>,
+    live: broadcast::Sender<String>,
+}
+
+type Shared = Arc<Mutex<Transcript>>;
+
+/// Mirrors a session's events to observers connected to its socket. Observers only ever
+/// receive; nothing they send is read, so they cannot drive the session.
+pub struct Broadcaster {
+    shared: Shared,
+    socket: PathBuf,
+    forwarder: JoinHandle<()>,
+    listener: JoinHandle<()>,
+}
+
+impl Broadcaster {
+    /// Starts sharing the chat. Events emitted into the returned sink reach observers.
+    ///
+    /// # Errors
+    /// Returns an error if the socket cannot be created.
+    pub fn start(chat_id: &str) -> Result<(Self, EventSink)> {
+        let socket =
+            socket_path(chat_id).ok_or_else(|| anyhow!("Could not determine config directory"))?;
+        let shared = Arc::new(Mutex::new(Transcript {
+            lines: Vec::new(),
+            live: broadcast::channel(1024).0,
+        }));
+        let listener = listen(&socket, shared.clone())?;
+
+        let (sink, mut rx) = EventSink::channel();
+        let forward_to = shared.clone();
+        let forwarder = tokio::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                let Ok(line) = serde_json::to_string(&event) else {
+                    continue;
+                };
+                let mut transcript = forward_to.lock().unwrap_or_else(PoisonError::into_inner);
+                // No observers connected is fine
+                let _ = transcript.live.send(line.clone());
+                transcript.lines.push(line);
+            }
+        });
+        let broadcaster = Self {
+            shared,
+            socket,
+            forwarder,
+            listener,
+        };
+        Ok((broadcaster, sink))
+    }
+
+    /// Moves sharing to another chat. Observers of the previous chat are disconnected.
+    ///
+    /// # Errors
+    /// Returns an error if the new socket cannot be created.
+    pub fn rebind(&mut self, chat_id: &str) -> Result<()> {
+        let socket =
+            socket_path(chat_id).ok_or_else(|| anyhow!("Could not determine config directory"))?;
+        self.listener.abort();
+        let _ = std::fs::remove_file(&self.socket);
+        {
+            let mut transcript = self.shared.lock().unwrap_or_else(PoisonError::into_inner);
+            transcript.lines.clear();
+            // Dropping the old sender ends the old observers' streams
+            transcript.live = broadcast::channel(1024).0;
+        }
+        self.listener = listen(&socket, self.shared.clone())?;
+        self.socket = socket;
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn socket(&self) -> &Path {
+        &self.socket
+    }
+}
+
+impl Drop for Broadcaster {
+    fn drop(&mut self) {
+        self.forwarder.abort();
+        self.listener.abort();
+        let _ = std::fs::remove_file(&self.socket);
+    }
+}
+
+#[cfg(unix)]
+fn listen(socket: &Path, shared: Shared) -> Result<JoinHandle<()>> {
+    use std::os::unix::fs::DirBuilderExt;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::UnixListener;
+
+    if let Some(dir) = socket.parent() {
+        // Only the owner may connect, or read the transcript
+        std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)?;
+    }
+    // A socket left behind by a session that crashed would make bind fail
+    let _ = std::fs::remove_file(socket);
+    let listener = UnixListener::bind(socket)?;
+    Ok(tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let (backlog, mut live) = {
+                let transcript = shared.lock().unwrap_or_else(PoisonError::into_inner);
+                (transcript.lines.clone(), transcript.live.subscribe())
+            };
+            tokio::spawn(async move {
+                for line in backlog {
+                    if stream
+                        .write_all(format!("{line}\n").as_bytes())
+                        .await
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+                loop {
+                    match live.recv().await {
+                        Ok(line) => {
+                            if stream
+                                .write_all(format!("{line}\n").as_bytes())
+                                .await
+                                .is_err()
+                            {
+                                return;
+                            }
+                        }
+                        // A slow observer misses some output rather than stalling the session
+                        Err(broadcast::error::RecvError::Lagged(_)) => {}
+                        Err(broadcast::error::RecvError::Closed) => return,
+                    }
+                }
+            });
+        }
+    }))
+}
+
+#[cfg(not(unix))]
+fn listen(_socket: &Path, _shared: Shared) -> Result<JoinHandle<()>> {
+    anyhow::bail!("Sharing a session needs Unix domain sockets, which this platform lacks");
+}
+
+#[cfg(unix)]
+async fn connect(socket: &Path) -> Result<Box<dyn AsyncRead + Unpin>> {
+    Ok(Box::new(tokio::net::UnixStream::connect(socket).await?))
+}
+
+#[cfg(not(unix))]
+async fn connect(_socket: &Path) -> Result<Box<dyn AsyncRead + Unpin>> {
+    anyhow::bail!("Observing a session needs Unix domain sockets, which this platform lacks");
+}
+
+/// Connects to a shared session and prints its transcript until the session ends.
+///
+/// # Errors
+/// Returns an error if the session is not being shared or the connection fails.
+pub async fn observe(chat_id: &str) -> Result<()> {
+    let socket =
+        socket_path(chat_id).ok_or_else(|| anyhow!("Could not determine config directory"))?;
+    let stream = connect(&socket).await.map_err(|e| {
+        anyhow!(
+            "Chat {chat_id} is not being shared ({e}). Start it with `deepseek {chat_id} --share`."
+        )
+    })?;
+    println!(
+        "{}",
+        format!("Observing chat {chat_id} (read-only, Ctrl+C to stop)").cyan()
+    );
+    let mut lines = BufReader::new(stream).lines();
+    let mut view = ObserverView::default();
+    while let Some(line) = lines.next_line().await? {
+        // Skip events from a newer version this one doesn't know
+        if let Ok(event) = serde_json::from_str::<AgentEvent>(&line) {
+            view.show(&event)?;
+        }
+    }
+    println!("\n{}", "The session ended".yellow());
+    Ok(())
+}
+
+/// Prints another session's events the way its own terminal shows them.
+#[derive(Default)]
+struct ObserverView {
+    thinking_started: bool,
+    /// Set while a response is streaming
+    highlighter: Option<StreamHighlighter>,
+}
+
+impl ObserverView {
+    fn show(&mut self, event: &AgentEvent) -> Result<()> {
+        match event {
+            AgentEvent::User { text } => println!("{} {}", ">".cyan().bold(), text.bold()),
+            AgentEvent::Thinking { text } => {
+                if !self.thinking_started {
+                    println!("{}", "--- Thinking ---".yellow());
+                    self.thinking_started = true;
+                }
+                print!("{}", text.dimmed());
+            }
+            AgentEvent::Content { text } => {
+                let highlighter = self.highlighter.get_or_insert_with(|| {
+                    if self.thinking_started {
+                        println!("\n{}", "--- End of thinking ---".yellow());
+                    }
+                    println!("{}", "--- Response ---".green());
+                    StreamHighlighter::new()
+                });
+                print!("{}", highlighter.push(text));
+            }
+            AgentEvent::Message { .. } => {
+                if self.thinking_started && self.highlighter.is_none() {
+                    println!("\n{}", "--- End of thinking ---".yellow());
+                }
+                self.finish_stream();
+                println!();
+            }
+            AgentEvent::ToolCall { name, arg } => {
+                let detail = arg.lines().next().unwrap_or_default();
+                println!("{}", format!("TOOL {name} {detail}").magenta());
+            }
+            AgentEvent::ToolResult {
+                status, success, ..
+            } => {
+                let status = hyperlink::linkify(status);
+                if *success {
+                    println!("{}", status.cyan());
+                } else {
+                    println!("{}", status.red());
+                }
+            }
+            AgentEvent::Interrupted => {
+                self.finish_stream();
+                println!("\n{}", "Stream interrupted by user".yellow());
+            }
+            AgentEvent::Error { message } => {
+                self.finish_stream();
+                println!("{}", format!("Error: {message}").red());
+            }
+            AgentEvent::Done => {}
+        }
+        std::io::stdout().flush()?;
+        Ok(())
+    }
+
+    fn finish_stream(&mut self) {
+        if let Some(mut highlighter) = self.highlighter.take() {
+            print!("{}", highlighter.finish());
+        }
+        self.thinking_started = false;
+    }
+}
@@ -0,0 +1,306 @@
+use anyhow::{Result, anyhow, bail};
+
+/// Context lines that may be dropped from each end of a hunk that does not match as it
+/// is, as with `patch --fuzz 2`.
+const MAX_FUZZ: usize = 2;
+
+/// The changes a unified diff makes to one file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilePatch {
+    /// The file before the change, or `None` if the patch creates it
+    pub old_path: Option<String>,
+    /// The file after the change, or `None` if the patch deletes it
+    pub new_path: Option<String>,
+    pub hunks: Vec<Hunk>,
+}
+
+/// One `@@` section of a file's diff.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Hunk {
+    /// Line the hunk starts at in the old file, counting from 1
+    pub old_start: usize,
+    pub lines: Vec<HunkLine>,
+    /// Set by `\ No newline at end of file` after the hunk's last new line
+    pub no_newline_at_end: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HunkLine {
+    Context(String),
+    Removed(String),
+    Added(String),
+}
+
+impl Hunk {
+    fn old_lines(&self) -> impl Iterator<Item = &str> {
+        self.lines.iter().filter_map(|line| match line {
+            HunkLine::Context(text) | HunkLine::Removed(text) => Some(text.as_str()),
+            HunkLine::Added(_) => None,
+        })
+    }
+
+    fn new_lines(&self) -> impl Iterator<Item = &str> {
+        self.lines.iter().filter_map(|line| match line {
+            HunkLine::Context(text) | HunkLine::Added(text) => Some(text.as_str()),
+            HunkLine::Removed(_) => None,
+        })
+    }
+}
+
+impl FilePatch {
+    /// The path the patch is about, after the change unless it deletes the file.
+    #[must_use]
+    pub fn path(&self) -> &str {
+        self.new_path
+            .as_deref()
+            .or(self.old_path.as_deref())
+            .unwrap_or_default()
+    }
+}
+
+/// Parses a unified diff, such as `diff -u` or `git diff` writes, that may cover several
+/// files. The `a/` and `b/` prefixes of git diffs are removed from the paths, and the line
+/// counts in `@@` headers are not relied on, since hand-written patches often get them
+/// wrong.
+///
+/// # Errors
+/// Returns an error if there are no file headers or a file has no hunks.
+pub fn parse(text: &str) -> Result<Vec<FilePatch>> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut patches: Vec<FilePatch> = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        if let Some(old) = line.strip_prefix("--- ")
+            && let Some(new) = lines.get(i + 1).and_then(|l| l.strip_prefix("+++ "))
+        {
+            patches.push(FilePatch {
+                old_path: header_path(old),
+                new_path: header_path(new),
+                hunks: Vec::new(),
+            });
+            i += 2;
+            continue;
+        }
+        if line.starts_with("@@") {
+            let patch = patches
+                .last_mut()
+                .ok_or_else(|| anyhow!("Hunk before any ---/+++ file header"))?;
+            let mut hunk = Hunk {
+                old_start: hunk_start(line)?,
+                ..Hunk::default()
+            };
+            i += 1;
+            while let Some(&line) = lines.get(i) {
+                if line.starts_with("@@")
+                    || line.starts_with("diff ")
+                    || (line.starts_with("--- ")
+                        && lines.get(i + 1).is_some_and(|l| l.starts_with("+++ ")))
+                {
+                    break;
+                }
+                match line.chars().next() {
+                    Some(' ') => hunk.lines.push(HunkLine::Context(line[1..].to_string())),
+                    // Blank context lines often lose their leading space
+                    None => hunk.lines.push(HunkLine::Context(String::new())),
+                    Some('-') => hunk.lines.push(HunkLine::Removed(line[1..].to_string())),
+                    Some('+') => hunk.lines.push(HunkLine::Added(line[1..].to_string())),
+                    Some('\\') => {
+                        if !matches!(hunk.lines.last(), Some(HunkLine::Removed(_))) {
+                            hunk.no_newline_at_end = true;
+                        }
+                    }
+                    _ => break,
+                }
+                i += 1;
+            }
+            // Blank lines between the hunk and whatever follows are not part of it
+            while matches!(hunk.lines.last(), Some(HunkLine::Context(text)) if text.is_empty()) {
+                hunk.lines.pop();
+            }
+            patch.hunks.push(hunk);
+            continue;
+        }
+        i += 1;
+    }
+    if patches.is_empty() {
+        bail!("No ---/+++ file headers found; give a unified diff");
+    }
+    if let Some(patch) = patches.iter().find(|p| p.hunks.is_empty()) {
+        bail!("No @@ hunks for {}", patch.path());
+    }
+    Ok(patches)
+}
+
+/// Returns the path-prefix level `patch -p` needs for the diff: 1 for git-style `a/` and
+/// `b/` paths, otherwise 0.
+#[must_use]
+pub fn strip_level(text: &str) -> usize {
+    let git_style = text.lines().any(|line| {
+        line.strip_prefix("--- ")
+            .is_some_and(|p| p.starts_with("a/"))
+    });
+    usize::from(git_style)
+}
+
+fn header_path(header: &str) -> Option<String> {
+    // Timestamps follow the path after a tab
+    let path = header.split('\t').next().unwrap_or_default().trim();
+    if path == "/dev/null" {
+        return None;
+    }
+    let path = path
+        .strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path);
+    Some(path.to_string())
+}
+
+/// Reads the old start line from `@@ -12,5 +12,7 @@`.
+fn hunk_start(header: &str) -> Result<usize> {
+    header
+        .trim_start_matches('@')
+        .split_whitespace()
+        .next()
+        .and_then(|range| range.strip_prefix('-'))
+        .and_then(|range| range.split(',').next())
+        .and_then(|start| start.parse().ok())
+        .ok_or_else(|| anyhow!("Invalid hunk header: {header}"))
+}
+
+/// Applies a file's hunks to its contents, in order.
+///
+/// Each hunk is looked for near the line its header gives, adjusted for the lines earlier
+/// hunks added or removed, and then further away. A hunk that does not match exactly is
+/// tried ignoring trailing whitespace, and then with up to [`MAX_FUZZ`] context lines
+/// dropped from each end. The file's line endings are kept.
+///
+/// # Errors
+/// Returns an error naming the first hunk that cannot be placed.
+pub fn apply(content: &str, hunks: &[Hunk]) -> Result<String> {
+    let newline = if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let mut trailing_newline = content.is_empty() || content.ends_with('\n');
+    let mut delta = 0isize;
+    let mut from = 0;
+    for (n, hunk) in hunks.iter().enumerate() {
+        let expected = hunk
+            .old_start
+            .saturating_sub(1)
+            .saturating_add_signed(delta)
+            .min(lines.len());
+        let (at, old_len, new) = place(&lines, hunk, expected, from).ok_or_else(|| {
+            anyhow!(
+                "Hunk {} does not match the file (expected near line {})",
+                n + 1,
+                hunk.old_start
+            )
+        })?;
+        let new_len = new.len();
+        lines.splice(at..at + old_len, new);
+        if hunk.no_newline_at_end && at + new_len == lines.len() {
+            trailing_newline = false;
+        }
+        delta += isize::try_from(new_len).unwrap_or(isize::MAX)
+            - isize::try_from(old_len).unwrap_or(isize::MAX);
+        from = at + new_len;
+    }
+    let mut result = lines.join(newline);
+    if trailing_newline && !result.is_empty() {
+        result.push_str(newline);
+    }
+    Ok(result)
+}
+
+/// Finds where a hunk goes, returning the position, the number of lines it replaces
+/// there and the lines that replace them.
+fn place(
+    lines: &[String],
+    hunk: &Hunk,
+    expected: usize,
+    from: usize,
+) -> Option<(usize, usize, Vec<String>)> {
+    let leading = hunk
+        .lines
+        .iter()
+        .take_while(|l| matches!(l, HunkLine::Context(_)))
+        .count();
+    let trailing = hunk
+        .lines
+        .iter()
+        .rev()
+        .take_while(|l| matches!(l, HunkLine::Context(_)))
+        .count();
+    // Drop as few context lines as possible, trying each end on its own first
+    let mut skips: Vec<(usize, usize)> = (0..=leading.min(MAX_FUZZ))
+        .flat_map(|start| (0..=trailing.min(MAX_FUZZ)).map(move |end| (start, end)))
+        .collect();
+    skips.sort_by_key(|&(start, end)| (start + end, start.max(end)));
+    let has_old = hunk.old_lines().next().is_some();
+    for (skip_start, skip_end) in skips {
+        if skip_start + skip_end >= hunk.lines.len() {
+            continue;
+        }
+        let body = Hunk {
+            lines: hunk.lines[skip_start..hunk.lines.len() - skip_end].to_vec(),
+            ..Hunk::default()
+        };
+        let old: Vec<&str> = body.old_lines().collect();
+        // Without any old lines left, the hunk could go anywhere
+        if has_old && old.is_empty() {
+            continue;
+        }
+        for loose in [false, true] {
+            let start = expected.saturating_add(skip_start);
+            if let Some(at) = find(lines, &old, start, from, loose) {
+                let new = body.new_lines().map(str::to_string).collect();
+                return Some((at, old.len(), new));
+            }
+        }
+    }
+    None
+}
+
+/// Finds `old` in `lines` at or after `from`, as close to `expected` as possible.
+fn find(
+    lines: &[String],
+    old: &[&str],
+    expected: usize,
+    from: usize,
+    loose: bool,
+) -> Option<usize> {
+    let last = lines.len().checked_sub(old.len())?;
+    if from > last {
+        return None;
+    }
+    let matches_at = |at: usize| {
+        lines[at..at + old.len()]
+            .iter()
+            .zip(old)
+            .all(|(line, old)| {
+                if loose {
+                    line.trim_end() == old.trim_end()
+                } else {
+                    line == old
+                }
+            })
+    };
+    let expected = expected.clamp(from, last);
+    for distance in 0..=last - from {
+        if let Some(at) = expected.checked_add(distance).filter(|&at| at <= last)
+            && matches_at(at)
+        {
+            return Some(at);
+        }
+        if let Some(at) = expected.checked_sub(distance).filter(|&at| at >= from)
+            && matches_at(at)
+        {
+            return Some(at);
+        }
+    }
+    None
+}
@@ -0,0 +1,116 @@
+use crate::diff::unified_diff;
+use crate::workspace;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Unchanged lines shown around each change in update diffs.
+const DIFF_CONTEXT_LINES: usize = 3;
+
+/// A file whose contents were attached to the conversation.
+#[derive(Debug, Clone)]
+struct PinnedFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    /// The contents the model last saw
+    content: String,
+}
+
+/// Files attached with `/focus`, remembered so that later edits on disk can be sent as a
+/// diff instead of letting the model work from stale contents.
+#[derive(Debug, Default)]
+pub struct PinnedFiles {
+    files: Vec<PinnedFile>,
+}
+
+impl PinnedFiles {
+    /// Remembers `content` as what the model has seen of `path`.
+    pub fn pin(&mut self, path: &Path, content: String) {
+        self.unpin(path);
+        self.files.push(PinnedFile {
+            path: path.to_path_buf(),
+            modified: modified(path),
+            content,
+        });
+    }
+
+    pub fn unpin(&mut self, path: &Path) {
+        self.files.retain(|f| f.path != path);
+    }
+
+    pub fn clear(&mut self) {
+        self.files.clear();
+    }
+
+    #[must_use]
+    pub fn paths(&self) -> Vec<&Path> {
+        self.files.iter().map(|f| f.path.as_path()).collect()
+    }
+
+    /// Takes the current contents of `path` as seen, if it is pinned. Used after the model
+    /// edits a file itself, so its own changes are not reported back to it. The path may be
+    /// named differently from when it was pinned, as a tool resolves it against the
+    /// workspace rather than the current directory.
+    pub fn mark_seen(&mut self, path: &Path) {
+        let wanted = absolute(path);
+        if let Some(file) = self.files.iter_mut().find(|f| absolute(&f.path) == wanted)
+            && let Ok(content) = fs::read_to_string(path)
+        {
+            file.modified = modified(path);
+            file.content = content;
+        }
+    }
+
+    /// Checks the pinned files for changes on disk since the model last saw them.
+    ///
+    /// Returns a note for each changed file, to be added to the next prompt, and takes the
+    /// current contents as seen. Deleted files are unpinned.
+    pub fn refresh(&mut self) -> Vec<String> {
+        let mut notes = Vec::new();
+        self.files.retain_mut(|file| {
+            let now = modified(&file.path);
+            if now.is_some() && now == file.modified {
+                return true;
+            }
+            let Ok(content) = fs::read_to_string(&file.path) else {
+                notes.push(format!(
+                    "Pinned file {} was deleted or can no longer be read.",
+                    file.path.display()
+                ));
+                return false;
+            };
+            file.modified = now;
+            if content == file.content {
+                return true;
+            }
+            let path = file.path.display();
+            // A diff longer than the file is not worth sending
+            notes.push(
+                match unified_diff(&file.content, &content, DIFF_CONTEXT_LINES) {
+                    Some(diff) if diff.len() < content.len() => format!(
+                        "Pinned file {path} was changed on disk since you saw it:\n```diff\n{diff}```"
+                    ),
+                    _ => format!(
+                        "Pinned file {path} was changed on disk since you saw it. Its current contents:\n```\n{content}\n```"
+                    ),
+                },
+            );
+            file.content = content;
+            true
+        });
+        notes
+    }
+}
+
+/// `path` from the root, with `.` and `..` worked out, for comparing paths named
+/// differently.
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path)
+        .ok()
+        .and_then(|path| workspace::normalize(&path))
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
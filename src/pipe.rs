@@ -0,0 +1,117 @@
+use crate::events::AgentEvent;
+use crate::protocol::{self, JSON_FENCE};
+
+/// Which stream a piece of output belongs on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    /// The assistant's reply, as plain text.
+    Stdout,
+    /// Thinking, tool activity and anything else meant for the person at the terminal.
+    Stderr,
+}
+
+/// Splits a turn's events between stdout and stderr for `-p`, so that only the reply
+/// itself reaches a pipe. Replies that call tools go to stderr too.
+#[derive(Debug, Default)]
+pub struct PipeRouter {
+    quiet: bool,
+    /// Start of the current reply, held back until it is clear whether it calls tools
+    pending: String,
+    target: Option<Target>,
+    /// Whether the last text on stderr was thinking, which needs a line break after it
+    thinking: bool,
+    stdout_at_line_start: bool,
+}
+
+impl PipeRouter {
+    /// With `quiet` set, thinking is dropped instead of going to stderr.
+    #[must_use]
+    pub fn new(quiet: bool) -> Self {
+        Self {
+            quiet,
+            stdout_at_line_start: true,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the text an event produces, with the stream each part goes to.
+    pub fn route(&mut self, event: &AgentEvent) -> Vec<(Target, String)> {
+        let mut out = Vec::new();
+        match event {
+            AgentEvent::User { .. } | AgentEvent::Done => {}
+            AgentEvent::Thinking { text } => {
+                if !self.quiet {
+                    self.thinking = true;
+                    out.push((Target::Stderr, text.clone()));
+                }
+            }
+            AgentEvent::Content { text } => {
+                self.end_thinking(&mut out);
+                if let Some(target) = self.target {
+                    self.write(&mut out, target, text.clone());
+                } else {
+                    self.pending.push_str(text);
+                    let start = self.pending.trim_start();
+                    if start.len() >= JSON_FENCE.len() || start.contains('\n') {
+                        self.decide(&mut out);
+                    }
+                }
+            }
+            AgentEvent::Message { .. } => self.end_reply(&mut out),
+            AgentEvent::ToolCall { name, arg } => {
+                let detail = arg.lines().next().unwrap_or_default();
+                out.push((Target::Stderr, format!("TOOL {name} {detail}\n")));
+            }
+            AgentEvent::ToolResult { status, .. } => {
+                out.push((Target::Stderr, format!("{status}\n")));
+            }
+            AgentEvent::Interrupted => {
+                self.end_reply(&mut out);
+                out.push((Target::Stderr, "Interrupted\n".to_string()));
+            }
+            AgentEvent::Error { message } => {
+                self.end_reply(&mut out);
+                out.push((Target::Stderr, format!("Error: {message}\n")));
+            }
+        }
+        out
+    }
+
+    /// Picks the stream for the current reply and writes whatever was held back.
+    fn decide(&mut self, out: &mut Vec<(Target, String)>) {
+        let target = *self.target.get_or_insert_with(|| {
+            if protocol::starts_with_tool_call(&self.pending) {
+                Target::Stderr
+            } else {
+                Target::Stdout
+            }
+        });
+        if !self.pending.is_empty() {
+            let pending = std::mem::take(&mut self.pending);
+            self.write(out, target, pending);
+        }
+    }
+
+    fn write(&mut self, out: &mut Vec<(Target, String)>, target: Target, text: String) {
+        if target == Target::Stdout && !text.is_empty() {
+            self.stdout_at_line_start = text.ends_with('\n');
+        }
+        out.push((target, text));
+    }
+
+    fn end_thinking(&mut self, out: &mut Vec<(Target, String)>) {
+        if std::mem::take(&mut self.thinking) {
+            out.push((Target::Stderr, "\n".to_string()));
+        }
+    }
+
+    /// Finishes the current reply, so whatever comes next starts on a line of its own.
+    fn end_reply(&mut self, out: &mut Vec<(Target, String)>) {
+        self.end_thinking(out);
+        self.decide(out);
+        if self.target == Some(Target::Stdout) && !self.stdout_at_line_start {
+            self.write(out, Target::Stdout, "\n".to_string());
+        }
+        self.target = None;
+    }
+}
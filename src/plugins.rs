@@ -0,0 +1,213 @@
+use crate::config::{self, PluginConfig};
+use crate::tools::ToolOutput;
+use anyhow::{Result, anyhow, bail};
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::LazyLock;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Start of the name of an executable on `PATH` that is registered as a tool, named after
+/// the rest: `deepseek-tool-jira` becomes the `jira` tool.
+pub const PREFIX: &str = "deepseek-tool-";
+
+/// How long a plugin may run when its `timeout_secs` is not set.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// An external program that is used as a tool: the argument of a call is written to its
+/// standard input, and what it prints is the result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plugin {
+    pub name: String,
+    pub program: PathBuf,
+    /// The tool's entry in the system prompt, `name <input> : what it does`
+    pub description: String,
+    pub read_only: bool,
+    pub timeout: Duration,
+}
+
+static PLUGINS: LazyLock<Vec<Plugin>> = LazyLock::new(|| {
+    let config = config::get();
+    let discovered = std::env::var_os("PATH")
+        .map(|path| discover(&path))
+        .unwrap_or_default();
+    load(&config.plugins, &discovered, |path| config.resolve(path))
+});
+
+/// The plugins found on `PATH` and declared under `[plugins.<name>]`, found on first use.
+#[must_use]
+pub fn all() -> &'static [Plugin] {
+    &PLUGINS
+}
+
+/// The `deepseek-tool-*` executables in the directories of a `PATH` value, by tool name.
+/// Where two directories have one of the same name, the first wins, as it would in a shell.
+#[must_use]
+pub fn discover(path: &OsStr) -> BTreeMap<String, PathBuf> {
+    let mut found = BTreeMap::new();
+    for dir in std::env::split_paths(path) {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let file_name = entry.file_name();
+            let Some(name) = file_name
+                .to_str()
+                .and_then(|name| name.strip_prefix(PREFIX))
+                .map(|name| {
+                    name.strip_suffix(std::env::consts::EXE_SUFFIX)
+                        .unwrap_or(name)
+                })
+            else {
+                continue;
+            };
+            let path = entry.path();
+            if is_valid_name(name) && is_executable(&path) && !found.contains_key(name) {
+                found.insert(name.to_string(), path);
+            }
+        }
+    }
+    found
+}
+
+/// Combines the discovered executables with the declared plugins. A declaration adds a
+/// description, a timeout or the right to run in plan mode to the executable of its name,
+/// or names a program of its own with `command`, which is resolved with `resolve` if it is
+/// a path. Declarations whose program cannot be found are left out with a warning.
+#[must_use]
+pub fn load(
+    declared: &BTreeMap<String, PluginConfig>,
+    discovered: &BTreeMap<String, PathBuf>,
+    resolve: impl Fn(&Path) -> PathBuf,
+) -> Vec<Plugin> {
+    let mut plugins = Vec::new();
+    for (name, path) in discovered {
+        if !declared.contains_key(name) {
+            plugins.push(Plugin::new(name, path.clone(), &PluginConfig::default()));
+        }
+    }
+    for (name, plugin) in declared {
+        if !is_valid_name(name) {
+            tracing::warn!(
+                plugin = name,
+                "Plugin names may only use letters, digits, - and _"
+            );
+            continue;
+        }
+        let program = match &plugin.command {
+            Some(command) if command.components().count() > 1 => Some(resolve(command)),
+            Some(command) => Some(command.clone()),
+            None => discovered.get(name).cloned(),
+        };
+        match program {
+            Some(program) => plugins.push(Plugin::new(name, program, plugin)),
+            None => tracing::warn!(
+                plugin = name,
+                "No {PREFIX}{name} on PATH and no command given; leaving the plugin out"
+            ),
+        }
+    }
+    plugins.sort_by(|a, b| a.name.cmp(&b.name));
+    plugins
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path)
+        .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+impl Plugin {
+    fn new(name: &str, program: PathBuf, config: &PluginConfig) -> Self {
+        let description = match config.description.as_deref().map(str::trim) {
+            Some(description) if !description.is_empty() => {
+                format!("{name} <input> : {description}")
+            }
+            _ => format!(
+                "{name} <input> : runs the external program {}, passing the input on its standard input and returning what it prints.",
+                program.display()
+            ),
+        };
+        Self {
+            name: name.to_string(),
+            program,
+            description,
+            read_only: config.read_only,
+            timeout: config
+                .timeout_secs
+                .map_or(DEFAULT_TIMEOUT, Duration::from_secs),
+        }
+    }
+
+    /// Runs the program with `arg` on its standard input, in the working directory.
+    ///
+    /// # Errors
+    /// Returns an error if the program cannot be started, exits with a failure, or runs
+    /// longer than its timeout, in which case it is killed.
+    pub async fn run(&self, arg: &str) -> Result<ToolOutput> {
+        let mut child = Command::new(&self.program)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| anyhow!("Failed to run {}: {e}", self.program.display()))?;
+        let mut stdin = child.stdin.take();
+        let input = async move {
+            if let Some(stdin) = &mut stdin {
+                // A program that does not read its input closes the pipe early, which is fine
+                let _ = stdin.write_all(arg.as_bytes()).await;
+            }
+        };
+        let (_, output) = tokio::time::timeout(self.timeout, async {
+            tokio::join!(input, child.wait_with_output())
+        })
+        .await
+        .map_err(|_| {
+            anyhow!(
+                "{} did not finish within {} seconds and was stopped",
+                self.name,
+                self.timeout.as_secs()
+            )
+        })?;
+        let output = output?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let exit = output.status.code().map_or_else(
+                || "a signal".to_string(),
+                |code| format!("exit code {code}"),
+            );
+            bail!(
+                "{} failed ({exit}): {}",
+                self.name,
+                format!("{stderr}{stdout}").trim()
+            );
+        }
+        let content = if stdout.trim().is_empty() {
+            "The tool printed nothing".to_string()
+        } else {
+            stdout.into_owned()
+        };
+        Ok(ToolOutput::Text {
+            content,
+            status: format!("{} finished", self.name),
+        })
+    }
+}
@@ -0,0 +1,102 @@
+use crate::config::{self, PolicyAction, PolicyRule};
+use crate::index::glob_match;
+use anyhow::{Result, anyhow};
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// The policy of the loaded config, compiled once.
+static CURRENT: LazyLock<Result<Policy, String>> =
+    LazyLock::new(|| Policy::new(&config::get().policy).map_err(|e| e.to_string()));
+
+/// The `[[policy]]` rules of the config, ready to check calls against.
+#[derive(Debug, Default)]
+pub struct Policy {
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug)]
+struct Rule {
+    tool: Option<String>,
+    matches: Option<Regex>,
+    path: Option<String>,
+    action: PolicyAction,
+    reason: Option<String>,
+}
+
+/// The rule that decided a call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Verdict<'a> {
+    pub action: PolicyAction,
+    /// Which rule it was, counting from 1 in the order of the config
+    pub rule: usize,
+    pub reason: Option<&'a str>,
+}
+
+impl Verdict<'_> {
+    /// The rule and its reason, for messages such as "refused by policy rule 2 (...)".
+    #[must_use]
+    pub fn describe(&self) -> String {
+        match self.reason {
+            Some(reason) => format!("policy rule {} ({reason})", self.rule),
+            None => format!("policy rule {}", self.rule),
+        }
+    }
+}
+
+impl Policy {
+    /// Compiles the rules.
+    ///
+    /// # Errors
+    /// Returns an error if a rule's `matches` is not a valid regular expression.
+    pub fn new(rules: &[PolicyRule]) -> Result<Self> {
+        let rules = rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                let matches = rule
+                    .matches
+                    .as_deref()
+                    .map(Regex::new)
+                    .transpose()
+                    .map_err(|e| anyhow!("Invalid regex in [[policy]] rule {}: {e}", i + 1))?;
+                Ok(Rule {
+                    tool: rule.tool.clone(),
+                    matches,
+                    path: rule.path.clone(),
+                    action: rule.action,
+                    reason: rule.reason.clone(),
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { rules })
+    }
+
+    /// The first rule that matches a call to `tool` with `arg`, acting on `target`.
+    #[must_use]
+    pub fn check(&self, tool: &str, arg: &str, target: &str) -> Option<Verdict<'_>> {
+        let target = target.strip_prefix("./").unwrap_or(target);
+        self.rules
+            .iter()
+            .position(|rule| {
+                rule.tool.as_deref().is_none_or(|t| t == tool)
+                    && rule.matches.as_ref().is_none_or(|re| re.is_match(arg))
+                    && rule
+                        .path
+                        .as_deref()
+                        .is_none_or(|glob| glob_match(glob, target))
+            })
+            .map(|i| Verdict {
+                action: self.rules[i].action,
+                rule: i + 1,
+                reason: self.rules[i].reason.as_deref(),
+            })
+    }
+}
+
+/// The policy of the loaded config.
+///
+/// # Errors
+/// Returns an error if one of its rules is invalid.
+pub fn current() -> Result<&'static Policy> {
+    CURRENT.as_ref().map_err(|e| anyhow!("{e}"))
+}
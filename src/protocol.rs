@@ -0,0 +1,280 @@
+use crate::args::CallArgs;
+use crate::tools;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+/// Starts the line after a `TOOL:` line that says why the call is made. Tools that change
+/// files or run commands must have one.
+pub const REASON_PREFIX: &str = "REASON:";
+
+/// Opens a fenced block holding a tool call as JSON.
+pub const JSON_FENCE: &str = "```tool";
+
+/// A tool invocation found in a reply.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolCall {
+    pub name: String,
+    pub arg: String,
+    /// Why the model is making the call, from its `REASON:` line.
+    pub reason: Option<String>,
+    /// Why the call could not be understood; it is reported back instead of run.
+    pub error: Option<String>,
+}
+
+impl ToolCall {
+    /// The argument, read by the type of the tool.
+    #[must_use]
+    pub fn args(&self) -> CallArgs {
+        CallArgs::parse(&self.name, &self.arg)
+    }
+
+    /// The file, command or URL the call acts on, whichever form its argument was given
+    /// in; see [`CallArgs::target`].
+    #[must_use]
+    pub fn target(&self) -> String {
+        self.args().target().to_string()
+    }
+}
+
+/// Whether a reply starts with a tool call, in either format.
+#[must_use]
+pub fn starts_with_tool_call(text: &str) -> bool {
+    let text = text.trim_start();
+    text.starts_with("TOOL:") || text.starts_with(JSON_FENCE)
+}
+
+/// Finds the tool calls in a reply.
+///
+/// A call is either a `TOOL: <name> [arg]` line, optionally followed by a `REASON:` line,
+/// that takes the lines up to the next call as the rest of its argument; or a fenced
+/// ```` ```tool ```` block holding `{"name": ..., "args": {...}, "reason": ...}`, whose
+/// argument cannot be cut short by lines that look like calls.
+///
+/// Lines inside other fenced code blocks are never calls, so a reply can show the call
+/// syntax without running it.
+#[must_use]
+pub fn parse_tool_calls(content: &str) -> Vec<ToolCall> {
+    let lines: Vec<&str> = content.lines().collect();
+    let starts_call = |line: &str| {
+        let line = line.trim();
+        line.starts_with("TOOL:") || line == JSON_FENCE
+    };
+    let mut fence = Fence::default();
+    let mut i = 0;
+    let mut calls = Vec::new();
+    while i < lines.len() {
+        let line = lines[i].trim();
+        if !fence.is_open() && line == JSON_FENCE {
+            let end = lines[i + 1..]
+                .iter()
+                .position(|l| l.trim() == "```")
+                .map_or(lines.len(), |n| i + 1 + n);
+            calls.push(parse_json_call(&lines[i + 1..end].join("\n")));
+            i = end + 1;
+        } else if fence.step(line) {
+            i += 1;
+        } else if let Some(stripped) = line.strip_prefix("TOOL:") {
+            let tool_line = stripped.trim();
+            let mut tool_parts = tool_line.splitn(2, ' ');
+            let name = tool_parts.next().unwrap_or("").to_string();
+            let first_arg = tool_parts.next().unwrap_or("").to_string();
+            i += 1;
+
+            let reason = lines
+                .get(i)
+                .and_then(|l| l.trim().strip_prefix(REASON_PREFIX))
+                .map(|r| r.trim().to_string());
+            if reason.is_some() {
+                i += 1;
+            }
+
+            let mut body_lines = Vec::new();
+            // A code block in the argument, such as file content, is part of it
+            while i < lines.len() && (fence.is_open() || !starts_call(lines[i])) {
+                fence.step(lines[i].trim());
+                body_lines.push(lines[i]);
+                i += 1;
+            }
+            let body = body_lines.join("\n");
+
+            let arg = if body.is_empty() {
+                first_arg
+            } else if first_arg.is_empty() {
+                body
+            } else {
+                format!("{first_arg}\n{body}")
+            };
+            calls.push(ToolCall {
+                name,
+                arg,
+                reason: reason.filter(|r| !r.is_empty()),
+                error: None,
+            });
+        } else {
+            i += 1;
+        }
+    }
+    calls
+}
+
+/// Follows the fenced code blocks of Markdown text line by line.
+#[derive(Debug, Default)]
+struct Fence {
+    /// The fence character and length of the open block
+    open: Option<(char, usize)>,
+}
+
+impl Fence {
+    fn is_open(&self) -> bool {
+        self.open.is_some()
+    }
+
+    /// Takes in the next (trimmed) line. Returns whether it opens, closes or is inside a
+    /// block.
+    fn step(&mut self, line: &str) -> bool {
+        let marker = match line.chars().next() {
+            Some(c @ ('`' | '~')) => c,
+            _ => return self.is_open(),
+        };
+        let length = line.chars().take_while(|&c| c == marker).count();
+        match self.open {
+            None if length >= 3 => {
+                self.open = Some((marker, length));
+                true
+            }
+            // A closing fence is at least as long as the opening one and has no info string
+            Some((open, open_length))
+                if open == marker && length >= open_length && line[length..].trim().is_empty() =>
+            {
+                self.open = None;
+                true
+            }
+            _ => self.is_open(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct JsonToolCall {
+    name: String,
+    #[serde(default)]
+    args: Value,
+    reason: Option<String>,
+}
+
+fn parse_json_call(json: &str) -> ToolCall {
+    let call: JsonToolCall = match serde_json::from_str(json) {
+        Ok(call) => call,
+        Err(e) => {
+            let name = serde_json::from_str::<Value>(json)
+                .ok()
+                .and_then(|v| v.get("name")?.as_str().map(str::to_string))
+                .unwrap_or_else(|| "tool".to_string());
+            return ToolCall {
+                error: Some(format!("TOOL {name} failed: invalid JSON tool call: {e}")),
+                name,
+                ..ToolCall::default()
+            };
+        }
+    };
+    let (arg, error) = match json_arg(&call.name, call.args) {
+        Ok(arg) => (arg, None),
+        Err(e) => (
+            String::new(),
+            Some(format!("TOOL {} failed: {e}", call.name)),
+        ),
+    };
+    ToolCall {
+        name: call.name,
+        arg,
+        reason: call.reason.filter(|r| !r.trim().is_empty()),
+        error,
+    }
+}
+
+/// Turns the `args` of a JSON tool call into the text argument the tool takes.
+///
+/// A string is used as it is and a list gives one line per item. An object gives the
+/// tool's arguments in order, each on its own line, except for those the tool takes on
+/// its first line: the selector and text of `browser_type`, the path and line range of
+/// `edit_lines`, and the pull request and location of `gh_pr_comment`.
+fn json_arg(tool: &str, args: Value) -> Result<String, String> {
+    let map = match args {
+        Value::Null => return Ok(String::new()),
+        Value::Object(map) => map,
+        other => return value_text(&other),
+    };
+    let params = tools::params(tool).ok_or_else(|| format!("Unknown tool: {tool}"))?;
+    if let Some(unknown) = map.keys().find(|key| !params.contains(&key.as_str())) {
+        return Err(if params.is_empty() {
+            format!("unknown argument \"{unknown}\"; {tool} takes none")
+        } else {
+            format!(
+                "unknown argument \"{unknown}\"; {tool} takes {}",
+                params.join(", ")
+            )
+        });
+    }
+    let mut parts = Vec::new();
+    for param in params {
+        match map.get(*param) {
+            Some(value) if *param == "blocks" => parts.push(search_replace_blocks(value)?),
+            Some(value) => parts.push(value_text(value)?),
+            None => parts.push(String::new()),
+        }
+    }
+    while parts.last().is_some_and(String::is_empty) {
+        parts.pop();
+    }
+    let inline = match tool {
+        "browser_type" | "fetch_url" => parts.len(),
+        "edit_lines" => parts.len().min(3),
+        "gh_pr_comment" => parts.len().min(2),
+        _ => 1,
+    };
+    let rest = parts.split_off(inline.max(1).min(parts.len()));
+    let mut arg = parts.join(" ");
+    for part in rest {
+        arg.push('\n');
+        arg.push_str(&part);
+    }
+    Ok(arg)
+}
+
+fn value_text(value: &Value) -> Result<String, String> {
+    match value {
+        Value::Null => Ok(String::new()),
+        Value::String(s) => Ok(s.clone()),
+        Value::Number(n) => Ok(n.to_string()),
+        Value::Bool(b) => Ok(b.to_string()),
+        Value::Array(items) => {
+            let lines: Result<Vec<String>, String> = items.iter().map(value_text).collect();
+            Ok(lines?.join("\n"))
+        }
+        Value::Object(_) => Err("arguments must be strings, numbers or lists".to_string()),
+    }
+}
+
+/// Writes `[{"search": ..., "replace": ...}]` in the SEARCH/REPLACE markers
+/// `apply_search_replace` reads. A string is taken to be in that format already.
+fn search_replace_blocks(value: &Value) -> Result<String, String> {
+    let Value::Array(blocks) = value else {
+        return value_text(value);
+    };
+    let field = |block: &Map<String, Value>, name: &str| match block.get(name) {
+        Some(Value::String(s)) => Ok(s.clone()),
+        _ => Err(format!("each block needs a \"{name}\" string")),
+    };
+    let mut out = Vec::new();
+    for block in blocks {
+        let Value::Object(block) = block else {
+            return Err("blocks must be {\"search\": ..., \"replace\": ...} objects".to_string());
+        };
+        out.push(format!(
+            "<<<<<<< SEARCH\n{}\n=======\n{}\n>>>>>>> REPLACE",
+            field(block, "search")?,
+            field(block, "replace")?
+        ));
+    }
+    Ok(out.join("\n"))
+}
@@ -0,0 +1,180 @@
+use crate::args::{
+    Block, CallArgs, CommandArgs, EditLinesArgs, MoveFileArgs, SearchReplaceArgs, WriteFileArgs,
+};
+use crate::patch;
+use anyhow::Result;
+use serde::Serialize;
+
+/// Tools whose effects are captured by a recipe.
+const REPLAYABLE_TOOLS: [&str; 8] = [
+    "run_command",
+    "write_file",
+    "apply_search_replace",
+    "apply_patch",
+    "edit_lines",
+    "create_directory",
+    "delete_file",
+    "move_file",
+];
+
+/// A mutating tool call that succeeded during the session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RecordedAction {
+    pub tool: String,
+    pub arg: String,
+}
+
+/// The commands and file edits made during a session, in order, so they can be replayed
+/// on another checkout or reviewed as a change plan.
+#[derive(Debug, Default)]
+pub struct Recipe {
+    actions: Vec<RecordedAction>,
+}
+
+impl Recipe {
+    /// Records a successful tool call. Read-only tools are ignored.
+    pub fn record(&mut self, tool: &str, arg: &str) {
+        if REPLAYABLE_TOOLS.contains(&tool) {
+            self.actions.push(RecordedAction {
+                tool: tool.to_string(),
+                arg: arg.to_string(),
+            });
+        }
+    }
+
+    #[must_use]
+    pub fn actions(&self) -> &[RecordedAction] {
+        &self.actions
+    }
+
+    /// The files changed so far, as [`changed_files`] gives them.
+    #[must_use]
+    pub fn changed_files(&self) -> Vec<String> {
+        changed_files(&self.actions)
+    }
+
+    /// Serializes the recorded actions as a JSON array of `{"tool", "arg"}` objects.
+    ///
+    /// # Errors
+    /// Returns an error if serialization fails.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&self.actions)?)
+    }
+
+    /// Renders the recorded actions as a POSIX shell script.
+    #[must_use]
+    pub fn to_shell_script(&self) -> String {
+        let mut script = String::from(
+            "#!/bin/sh\n# Replays the commands and file edits made in a deepseek-cli session.\nset -e\n",
+        );
+        for (i, action) in self.actions.iter().enumerate() {
+            script.push_str(&format!("\n# {}. {}\n", i + 1, action.tool));
+            match (
+                action.tool.as_str(),
+                CallArgs::parse(&action.tool, &action.arg),
+            ) {
+                ("run_command", CallArgs::Command(CommandArgs { command })) => {
+                    script.push_str(&command);
+                    script.push('\n');
+                }
+                ("create_directory", CallArgs::Path(args)) => {
+                    script.push_str(&format!("mkdir -p {}\n", shell_quote(&args.path)));
+                }
+                ("write_file", CallArgs::WriteFile(WriteFileArgs { path, content })) => {
+                    let path = shell_quote(&path);
+                    script.push_str(&format!("mkdir -p \"$(dirname {path})\"\n"));
+                    script.push_str(&format!("printf '%s' {} > {path}\n", shell_quote(&content)));
+                }
+                ("delete_file", CallArgs::Path(args)) => {
+                    script.push_str(&format!("rm -- {}\n", shell_quote(&args.path)));
+                }
+                ("move_file", CallArgs::MoveFile(MoveFileArgs { from, to })) => {
+                    let to = shell_quote(&to);
+                    script.push_str(&format!("mkdir -p \"$(dirname {to})\"\n"));
+                    script.push_str(&format!("mv -- {} {to}\n", shell_quote(&from)));
+                }
+                ("apply_patch", _) => {
+                    script.push_str(&format!(
+                        "patch -p{} --forward <<'DEEPSEEK_EOF'\n{}\nDEEPSEEK_EOF\n",
+                        patch::strip_level(&action.arg),
+                        action.arg.trim_end()
+                    ));
+                }
+                ("edit_lines", CallArgs::EditLines(args)) => {
+                    script.push_str(&edit_lines_script(&args));
+                }
+                (
+                    "apply_search_replace",
+                    CallArgs::SearchReplace(SearchReplaceArgs { path, blocks }),
+                ) => {
+                    script.push_str(&search_replace_script(&path, &blocks));
+                }
+                _ => script.push_str("# skipped, could not read the arguments\n"),
+            }
+        }
+        script
+    }
+}
+
+/// The files written, edited, created, moved or deleted by `actions`, each once, in the
+/// order they were first touched.
+#[must_use]
+pub fn changed_files(actions: &[RecordedAction]) -> Vec<String> {
+    let mut files: Vec<String> = Vec::new();
+    for action in actions {
+        for path in touched_paths(action) {
+            if !files.contains(&path) {
+                files.push(path);
+            }
+        }
+    }
+    files
+}
+
+fn touched_paths(action: &RecordedAction) -> Vec<String> {
+    if action.tool == "apply_patch" {
+        return patch::parse(&action.arg)
+            .map(|patches| patches.iter().map(|file| file.path().to_string()).collect())
+            .unwrap_or_default();
+    }
+    match CallArgs::parse(&action.tool, &action.arg) {
+        CallArgs::MoveFile(MoveFileArgs { from, to }) => vec![from, to],
+        args @ (CallArgs::Path(_)
+        | CallArgs::WriteFile(_)
+        | CallArgs::SearchReplace(_)
+        | CallArgs::EditLines(_)) => vec![args.target().to_string()],
+        _ => Vec::new(),
+    }
+}
+
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+fn edit_lines_script(args: &EditLinesArgs) -> String {
+    let EditLinesArgs {
+        path,
+        start,
+        end,
+        text,
+    } = args;
+    let lines = serde_json::to_string(&text.lines().collect::<Vec<&str>>()).unwrap_or_default();
+    format!(
+        "python3 - {} <<'DEEPSEEK_EOF'\nimport sys\npath = sys.argv[1]\nwith open(path) as f:\n    lines = f.read().splitlines(keepends=True)\nif {start} < 1 or {end} > len(lines) or {end} < {start} - 1:\n    sys.exit(\"Lines {start}-{end} are not in \" + path)\nlines[{start} - 1:{end}] = [line + \"\\n\" for line in {lines}]\nwith open(path, \"w\") as f:\n    f.write(\"\".join(lines))\nDEEPSEEK_EOF\n",
+        shell_quote(path)
+    )
+}
+
+// There is no portable shell equivalent of an exact multi-line replacement, so this is
+// delegated to Python. JSON string literals are valid Python string literals.
+fn search_replace_script(path: &str, blocks: &[Block]) -> String {
+    let mut script = format!("python3 - {} <<'DEEPSEEK_EOF'\n", shell_quote(path));
+    script.push_str("import sys\npath = sys.argv[1]\nwith open(path) as f:\n    content = f.read()\nfor search, replace in [\n");
+    for Block { search, replace } in blocks {
+        let search = serde_json::to_string(search).unwrap_or_default();
+        let replace = serde_json::to_string(replace).unwrap_or_default();
+        script.push_str(&format!("    ({search}, {replace}),\n"));
+    }
+    script.push_str("]:\n    if search not in content:\n        sys.exit(\"Search string not found in \" + path + \": \" + repr(search))\n    content = content.replace(search, replace)\nwith open(path, \"w\") as f:\n    f.write(content)\nDEEPSEEK_EOF\n");
+    script
+}
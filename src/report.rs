@@ -0,0 +1,153 @@
+use crate::autonomous::format_duration;
+use crate::exit::ExitStatus;
+use crate::export::Entry;
+use crate::mentions::fence_for;
+use crate::recipe::{self, RecordedAction};
+use std::fmt::Write;
+use std::time::Duration;
+
+/// How an unattended run ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// The model answered without asking for more tool calls
+    Completed,
+    /// The model was stopped after this many rounds of tool calls
+    TurnLimit(u32),
+    /// The reply was stopped with Ctrl+C
+    Interrupted,
+    /// This many tool calls were refused by the policy
+    Refused(usize),
+    Failed(String),
+}
+
+impl Outcome {
+    #[must_use]
+    pub fn is_completed(&self) -> bool {
+        *self == Self::Completed
+    }
+
+    /// The status to exit with. A failure is [`ExitStatus::Failure`] here; the error that
+    /// caused it may say more.
+    #[must_use]
+    pub fn status(&self) -> ExitStatus {
+        match self {
+            Self::Completed => ExitStatus::Success,
+            Self::TurnLimit(_) | Self::Interrupted => ExitStatus::Incomplete,
+            Self::Refused(_) => ExitStatus::PolicyViolation,
+            Self::Failed(_) => ExitStatus::Failure,
+        }
+    }
+
+    #[must_use]
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Completed => "Completed".to_string(),
+            Self::TurnLimit(turns) => {
+                format!("Stopped after {turns} rounds of tool calls (see --max-turns)")
+            }
+            Self::Interrupted => "Interrupted".to_string(),
+            Self::Refused(1) => "A tool call was refused by the policy".to_string(),
+            Self::Refused(calls) => format!("{calls} tool calls were refused by the policy"),
+            Self::Failed(error) => format!("Failed: {error}"),
+        }
+    }
+}
+
+/// What `deepseek run` did, written out when it finishes so it can be reviewed later.
+#[derive(Debug, Clone)]
+pub struct RunReport {
+    /// Where the task was read from
+    pub task: String,
+    pub outcome: Outcome,
+    pub duration: Duration,
+    /// The conversation, for the tool calls and the final answer
+    pub entries: Vec<Entry>,
+    /// Commands and file edits that succeeded, in order
+    pub actions: Vec<RecordedAction>,
+    /// `git diff --stat` of the working tree at the end, if it is a repository
+    pub diff_stat: Option<String>,
+}
+
+impl RunReport {
+    /// The content of the last reply, which is the model's answer when the run completed.
+    #[must_use]
+    pub fn final_answer(&self) -> Option<&str> {
+        self.entries.iter().rev().find_map(|entry| match entry {
+            Entry::Assistant { content, .. } if !content.trim().is_empty() => {
+                Some(content.as_str())
+            }
+            _ => None,
+        })
+    }
+
+    /// Tool calls that succeeded and failed.
+    #[must_use]
+    pub fn tool_results(&self) -> (usize, usize) {
+        self.entries
+            .iter()
+            .fold((0, 0), |(ok, failed), entry| match entry {
+                Entry::ToolResult { success: true, .. } => (ok + 1, failed),
+                Entry::ToolResult { success: false, .. } => (ok, failed + 1),
+                _ => (ok, failed),
+            })
+    }
+
+    /// The files written, edited, created, moved or deleted, each once, in the order they
+    /// were first touched.
+    #[must_use]
+    pub fn changed_files(&self) -> Vec<String> {
+        recipe::changed_files(&self.actions)
+    }
+
+    /// Commands that were run, in order.
+    #[must_use]
+    pub fn commands(&self) -> Vec<&str> {
+        self.actions
+            .iter()
+            .filter(|action| action.tool == "run_command")
+            .map(|action| action.arg.trim())
+            .collect()
+    }
+
+    #[must_use]
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::from("# deepseek run report\n\n");
+        let (ok, failed) = self.tool_results();
+        let _ = writeln!(out, "- Task: {}", self.task);
+        let _ = writeln!(out, "- Outcome: {}", self.outcome.describe());
+        let _ = writeln!(out, "- Duration: {}", format_duration(self.duration));
+        let _ = writeln!(out, "- Tool calls: {ok} succeeded, {failed} failed");
+
+        out.push_str("\n## Files changed\n\n");
+        let files = self.changed_files();
+        if files.is_empty() {
+            out.push_str("None.\n");
+        }
+        for file in files {
+            let _ = writeln!(out, "- `{file}`");
+        }
+        if let Some(stat) = self.diff_stat.as_deref().filter(|s| !s.trim().is_empty()) {
+            let _ = write!(out, "\n```\n{}\n```\n", stat.trim_end());
+        }
+
+        out.push_str("\n## Commands run\n\n");
+        let commands = self.commands();
+        if commands.is_empty() {
+            out.push_str("None.\n");
+        }
+        for command in commands {
+            let fence = fence_for(command);
+            let _ = writeln!(out, "{fence}sh\n{command}\n{fence}");
+        }
+
+        out.push_str("\n## Final answer\n\n");
+        match self.final_answer() {
+            Some(answer) => {
+                out.push_str(answer.trim());
+                out.push('\n');
+            }
+            None => out.push_str("No answer was given.\n"),
+        }
+        out
+    }
+}
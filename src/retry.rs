@@ -0,0 +1,110 @@
+use crate::mock::Exhausted;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How a failed request should be handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    /// The API asked us to slow down, possibly saying for how long.
+    RateLimited { retry_after: Option<Duration> },
+    /// The API could not be reached at all, e.g. the network is down.
+    Offline,
+    /// Retrying cannot help, e.g. the token was rejected.
+    Fatal,
+    /// Anything else, such as a dropped connection or a server error.
+    Transient,
+}
+
+/// Classifies an error from the API client.
+///
+/// The client reports failures as plain errors, so besides HTTP status codes this looks at
+/// the wording of the message.
+#[must_use]
+pub fn classify(error: &anyhow::Error) -> Failure {
+    // A fixture that has run out of replies stays out of them
+    if error.chain().any(|e| e.is::<Exhausted>()) {
+        return Failure::Fatal;
+    }
+    let http_error = error
+        .chain()
+        .find_map(|e| e.downcast_ref::<reqwest::Error>());
+    let status = http_error
+        .and_then(reqwest::Error::status)
+        .map(|s| s.as_u16());
+    let message = format!("{error:#}").to_lowercase();
+    if status == Some(429)
+        || has_code(&message, "429")
+        || ["rate limit", "rate-limit", "too many requests"]
+            .iter()
+            .any(|m| message.contains(m))
+    {
+        return Failure::RateLimited {
+            retry_after: retry_after(&message),
+        };
+    }
+    if matches!(status, Some(401 | 403))
+        || has_code(&message, "401")
+        || has_code(&message, "403")
+        || ["unauthorized", "forbidden", "invalid token"]
+            .iter()
+            .any(|m| message.contains(m))
+    {
+        return Failure::Fatal;
+    }
+    if http_error.is_some_and(|e| e.is_connect() || e.is_timeout())
+        || [
+            "error sending request",
+            "connection refused",
+            "dns error",
+            "failed to lookup address",
+            "network is unreachable",
+            "no route to host",
+            "timed out",
+        ]
+        .iter()
+        .any(|m| message.contains(m))
+    {
+        return Failure::Offline;
+    }
+    Failure::Transient
+}
+
+/// Whether `code` appears in the message as a number of its own, not inside a longer one.
+fn has_code(message: &str, code: &str) -> bool {
+    message.match_indices(code).any(|(i, _)| {
+        let before = message[..i].chars().next_back();
+        let after = message[i + code.len()..].chars().next();
+        !before.is_some_and(|c| c.is_ascii_digit()) && !after.is_some_and(|c| c.is_ascii_digit())
+    })
+}
+
+/// Finds a delay such as "retry after 20s", "Retry-After: 20" or "try again in 1.5 seconds"
+/// in a lowercase error message.
+fn retry_after(message: &str) -> Option<Duration> {
+    ["retry-after", "retry after", "try again in"]
+        .iter()
+        .find_map(|marker| {
+            let rest = &message[message.find(marker)? + marker.len()..];
+            let rest = rest.trim_start_matches([':', ' ', '=']);
+            let end = rest
+                .find(|c: char| !c.is_ascii_digit() && c != '.')
+                .unwrap_or(rest.len());
+            let value: f64 = rest[..end].parse().ok()?;
+            let seconds = if rest[end..].trim_start().starts_with("ms") {
+                value / 1000.0
+            } else {
+                value
+            };
+            Duration::try_from_secs_f64(seconds).ok()
+        })
+}
+
+/// Adds up to 25% random jitter to a delay, so that clients failing together do not
+/// retry together.
+#[must_use]
+pub fn with_jitter(delay: Duration) -> Duration {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos();
+    delay + delay.mul_f64(f64::from(nanos % 1000) / 4000.0)
+}
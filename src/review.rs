@@ -0,0 +1,272 @@
+use crate::github::{self, Reference};
+use crate::mentions::fence_for;
+use anyhow::{Result, anyhow, bail};
+use std::fmt;
+use std::process::Command;
+
+/// Most diff text sent to the model for review at once. Larger files are reviewed a few
+/// hunks at a time, and a larger hunk in pieces.
+pub const MAX_CHUNK_BYTES: usize = 12_000;
+
+/// The prompt a review starts with, in place of the agent's usual instructions.
+pub const REVIEW_SYSTEM_PROMPT: &str = "You are reviewing a code change, one part of its diff at a time. Look for bugs, security problems, data loss, race conditions, missing error handling, and code that does not do what it appears to intend; mention style only where it hurts readability. Do not call any tools, do not restate what the change does, and do not praise it.
+
+Reply with one finding per line, in the form
+
+[severity] line N: what is wrong and how to fix it
+
+where severity is high (a bug or vulnerability that will bite), medium (likely to cause trouble) or low (worth a look), and N is the line number in the new version of the file, or leave out \"line N: \" if the finding is about the change as a whole. If there is nothing worth reporting, reply with only: No issues.";
+
+/// What `deepseek review` reviews.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReviewSource {
+    /// `git diff` of a range, such as `main...HEAD`, or of the working tree against `HEAD`
+    Diff(Option<String>),
+    /// A GitHub pull request, by URL, `owner/repo#12` or `#12` in the `origin` repository
+    PullRequest(String),
+}
+
+impl ReviewSource {
+    /// Fetches the diff to review.
+    ///
+    /// # Errors
+    /// Returns an error if git fails, the URL is not a pull request or GitHub cannot be
+    /// reached.
+    pub async fn diff(&self) -> Result<String> {
+        match self {
+            Self::Diff(range) => git_diff(range.as_deref()),
+            Self::PullRequest(pr) => {
+                let pr = Reference::resolve(pr)?;
+                github::Client::from_config()?.pull_request_diff(&pr).await
+            }
+        }
+    }
+}
+
+impl fmt::Display for ReviewSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Diff(None) => f.write_str("uncommitted changes"),
+            Self::Diff(Some(range)) => write!(f, "git diff {range}"),
+            Self::PullRequest(url) => f.write_str(url),
+        }
+    }
+}
+
+fn git_diff(range: Option<&str>) -> Result<String> {
+    let output = Command::new("git")
+        .arg("diff")
+        .arg(range.unwrap_or("HEAD"))
+        .output()
+        .map_err(|e| anyhow!("Failed to run git: {e}"))?;
+    if !output.status.success() {
+        bail!(
+            "git diff failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Part of a diff that is reviewed on its own: some of the hunks of one file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    pub path: String,
+    /// The hunks, `@@` headers included
+    pub diff: String,
+}
+
+/// Splits a unified diff into chunks of at most `max_bytes` of hunks, none spanning two
+/// files. A hunk that is larger on its own is split between its lines, with its header
+/// repeated on each part. Files without hunks, such as binary ones, are left out.
+#[must_use]
+pub fn chunks(diff: &str, max_bytes: usize) -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    for (path, hunks) in files(diff) {
+        let mut current = String::new();
+        for hunk in hunks {
+            for piece in split_hunk(&hunk, max_bytes) {
+                if !current.is_empty() && current.len() + piece.len() > max_bytes {
+                    chunks.push(Chunk {
+                        path: path.clone(),
+                        diff: std::mem::take(&mut current),
+                    });
+                }
+                current.push_str(&piece);
+            }
+        }
+        if !current.is_empty() {
+            chunks.push(Chunk {
+                path,
+                diff: current,
+            });
+        }
+    }
+    chunks
+}
+
+/// The files of a diff, each with its hunks as text.
+fn files(diff: &str) -> Vec<(String, Vec<String>)> {
+    let lines: Vec<&str> = diff.lines().collect();
+    let mut files: Vec<(String, Vec<String>)> = Vec::new();
+    let mut in_hunk = false;
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        if line.starts_with("diff ") {
+            in_hunk = false;
+        } else if let Some(old) = line.strip_prefix("--- ")
+            && let Some(new) = lines.get(i + 1).and_then(|l| l.strip_prefix("+++ "))
+        {
+            let path = [new, old]
+                .into_iter()
+                .map(header_path)
+                .find(|path| path != "/dev/null")
+                .unwrap_or_default();
+            files.push((path, Vec::new()));
+            in_hunk = false;
+            i += 2;
+            continue;
+        } else if line.starts_with("@@") {
+            if let Some((_, hunks)) = files.last_mut() {
+                hunks.push(format!("{line}\n"));
+                in_hunk = true;
+            }
+        } else if in_hunk
+            && let Some(hunk) = files.last_mut().and_then(|(_, hunks)| hunks.last_mut())
+        {
+            hunk.push_str(line);
+            hunk.push('\n');
+        }
+        i += 1;
+    }
+    files.retain(|(_, hunks)| !hunks.is_empty());
+    files
+}
+
+fn header_path(header: &str) -> String {
+    let path = header.split('\t').next().unwrap_or_default().trim();
+    path.strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path)
+        .to_string()
+}
+
+fn split_hunk(hunk: &str, max_bytes: usize) -> Vec<String> {
+    if hunk.len() <= max_bytes {
+        return vec![hunk.to_string()];
+    }
+    let (header, body) = hunk.split_once('\n').unwrap_or((hunk, ""));
+    let mut pieces = Vec::new();
+    let mut piece = format!("{header}\n");
+    for line in body.lines() {
+        if piece.len() > header.len() + 1 && piece.len() + line.len() + 1 > max_bytes {
+            pieces.push(std::mem::replace(
+                &mut piece,
+                format!("{header} (continued)\n"),
+            ));
+        }
+        piece.push_str(line);
+        piece.push('\n');
+    }
+    pieces.push(piece);
+    pieces
+}
+
+/// The message asking for a review of chunk `index` (from 1) of `total`.
+#[must_use]
+pub fn chunk_prompt(chunk: &Chunk, index: usize, total: usize) -> String {
+    let fence = fence_for(&chunk.diff);
+    format!(
+        "Part {index} of {total}: {}\n\n{fence}diff\n{}{fence}",
+        chunk.path, chunk.diff
+    )
+}
+
+/// How much a finding matters, most serious first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    High,
+    Medium,
+    Low,
+}
+
+impl Severity {
+    fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "high" | "critical" | "error" => Some(Self::High),
+            "medium" | "warning" => Some(Self::Medium),
+            "low" | "info" | "nit" => Some(Self::Low),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::High => "high",
+            Self::Medium => "medium",
+            Self::Low => "low",
+        }
+    }
+}
+
+/// One problem the model found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub severity: Severity,
+    pub path: String,
+    /// Line in the new version of the file, if the finding is about one
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+/// Reads the findings out of the model's reply about `path`, in the form
+/// [`REVIEW_SYSTEM_PROMPT`] asks for. Lines that are not findings are ignored.
+#[must_use]
+pub fn parse_findings(reply: &str, path: &str) -> Vec<Finding> {
+    reply
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim().trim_start_matches(['-', '*']).trim_start();
+            let (severity, rest) = line.strip_prefix('[')?.split_once(']')?;
+            let severity = Severity::parse(severity)?;
+            let rest = rest.trim_start();
+            let (line, message) = match rest
+                .strip_prefix("line ")
+                .or_else(|| rest.strip_prefix("Line "))
+                .and_then(|r| r.split_once(':'))
+            {
+                Some((number, message)) => match number.trim().parse() {
+                    Ok(number) => (Some(number), message),
+                    Err(_) => (None, rest),
+                },
+                None => (None, rest),
+            };
+            let message = message.trim();
+            (!message.is_empty()).then(|| Finding {
+                severity,
+                path: path.to_string(),
+                line,
+                message: message.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Findings grouped by file, in the order the files came in the diff, each group most
+/// serious first and then by line.
+#[must_use]
+pub fn by_file(findings: &[Finding]) -> Vec<(&str, Vec<&Finding>)> {
+    let mut groups: Vec<(&str, Vec<&Finding>)> = Vec::new();
+    for finding in findings {
+        match groups.iter_mut().find(|(path, _)| *path == finding.path) {
+            Some((_, group)) => group.push(finding),
+            None => groups.push((&finding.path, vec![finding])),
+        }
+    }
+    for (_, group) in &mut groups {
+        group.sort_by_key(|finding| (finding.severity, finding.line));
+    }
+    groups
+}
@@ -0,0 +1,92 @@
+use crate::config;
+use anyhow::{Result, anyhow, bail};
+use std::path::{Component, Path, PathBuf};
+
+/// Directories the file tools never delete or move things in or out of, since that would
+/// damage the repository's history.
+const PROTECTED_DIRS: [&str; 3] = [".git", ".jj", ".hg"];
+
+/// Resolves `path` for a tool that deletes or moves files, relative to the working
+/// directory, and checks it against the config's `sandbox_root` or, without one, the
+/// working directory.
+///
+/// # Errors
+/// Returns an error if the path is outside that directory or in a protected directory.
+pub fn check(path: &str) -> Result<PathBuf> {
+    check_in(&std::env::current_dir()?, path)
+}
+
+/// Checks `path` as [`check`] does, with `cwd` as the working directory.
+///
+/// # Errors
+/// Returns an error if the path is outside the sandbox or in a protected directory.
+pub fn check_in(cwd: &Path, path: &str) -> Result<PathBuf> {
+    let config = config::get();
+    match &config.sandbox_root {
+        Some(root) if !path.trim().is_empty() => {
+            let path = cwd.join(path.trim());
+            resolve(&cwd.join(config.resolve(root)), &path.to_string_lossy())
+        }
+        _ => resolve(&cwd, path),
+    }
+}
+
+/// Resolves `path` against `root`, following symlinks in the part that exists, and checks
+/// that it stays inside `root` and out of version control directories such as `.git`.
+/// The path need not exist yet.
+///
+/// # Errors
+/// Returns an error if the path is empty, outside `root`, is `root` itself or is in a
+/// protected directory.
+pub fn resolve(root: &Path, path: &str) -> Result<PathBuf> {
+    let path = path.trim();
+    if path.is_empty() {
+        bail!("Missing path");
+    }
+    let root = root.canonicalize()?;
+    let resolved = canonicalize_existing(&root.join(path))?;
+    let relative = resolved
+        .strip_prefix(&root)
+        .map_err(|_| anyhow!("{path} is outside the working directory"))?;
+    if relative.as_os_str().is_empty() {
+        bail!("{path} is the working directory itself");
+    }
+    if let Some(dir) = relative
+        .components()
+        .find_map(|c| PROTECTED_DIRS.iter().find(|d| c.as_os_str() == **d))
+    {
+        bail!("{path} is inside {dir}, which the file tools leave alone");
+    }
+    Ok(resolved)
+}
+
+/// Canonicalizes the longest existing prefix of `path` and appends the rest, so that
+/// neither symlinks nor `..` can lead out of a directory unnoticed.
+fn canonicalize_existing(path: &Path) -> Result<PathBuf> {
+    let mut existing = path;
+    let mut rest = Vec::new();
+    while !existing.exists() {
+        let (Some(last), Some(parent)) = (existing.components().next_back(), existing.parent())
+        else {
+            break;
+        };
+        rest.push(last.as_os_str().to_os_string());
+        existing = parent;
+    }
+    let mut resolved = existing.canonicalize()?;
+    for name in rest.iter().rev() {
+        resolved.push(name);
+    }
+    // A `..` in the part that does not exist yet is resolved by hand
+    let mut normalized = PathBuf::new();
+    for component in resolved.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => {}
+            other => normalized.push(other),
+        }
+    }
+    Ok(normalized)
+}
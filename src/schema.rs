@@ -0,0 +1,226 @@
+use anyhow::{Result, anyhow, bail};
+use serde_json::{Map, Value};
+use std::path::Path;
+
+/// Reads a JSON Schema from a file.
+///
+/// # Errors
+/// Returns an error if the file cannot be read or is not a JSON object.
+pub fn load(path: &Path) -> Result<Value> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("Cannot read schema {}: {e}", path.display()))?;
+    let schema: Value = serde_json::from_str(&content)
+        .map_err(|e| anyhow!("Schema {} is not valid JSON: {e}", path.display()))?;
+    if !schema.is_object() {
+        bail!("Schema {} must be a JSON object", path.display());
+    }
+    Ok(schema)
+}
+
+/// Checks `value` against a JSON Schema, describing each place it does not match.
+///
+/// Covers the keywords structured output normally uses: `type`, `enum`, `const`,
+/// `properties`, `required`, `additionalProperties`, `items`, the length and range
+/// limits, and `allOf`/`anyOf`/`oneOf`. Others, such as `$ref`, are ignored.
+#[must_use]
+pub fn validate(schema: &Value, value: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    check(schema, value, "", &mut errors);
+    errors
+}
+
+fn check(schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+    let at = if path.is_empty() { "(root)" } else { path };
+    let schema = match schema {
+        Value::Object(schema) => schema,
+        Value::Bool(false) => {
+            errors.push(format!("{at}: no value is allowed here"));
+            return;
+        }
+        _ => return,
+    };
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|name| has_type(value, name)) {
+            errors.push(format!(
+                "{at}: expected {}, got {}",
+                types.join(" or "),
+                type_name(value)
+            ));
+            return;
+        }
+    }
+    if let Some(Value::Array(allowed)) = schema.get("enum")
+        && !allowed.contains(value)
+    {
+        errors.push(format!(
+            "{at}: must be one of {}",
+            Value::Array(allowed.clone())
+        ));
+    }
+    if let Some(expected) = schema.get("const")
+        && expected != value
+    {
+        errors.push(format!("{at}: must be {expected}"));
+    }
+    match value {
+        Value::Object(map) => check_object(schema, map, path, errors),
+        Value::Array(items) => {
+            check_limit(schema, "minItems", items.len(), at, "items", errors);
+            check_limit(schema, "maxItems", items.len(), at, "items", errors);
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    check(item_schema, item, &format!("{path}/{i}"), errors);
+                }
+            }
+        }
+        Value::String(s) => {
+            let length = s.chars().count();
+            check_limit(schema, "minLength", length, at, "characters", errors);
+            check_limit(schema, "maxLength", length, at, "characters", errors);
+        }
+        Value::Number(n) => check_range(schema, n.as_f64().unwrap_or_default(), at, errors),
+        Value::Bool(_) | Value::Null => {}
+    }
+    if let Some(Value::Array(all)) = schema.get("allOf") {
+        for sub in all {
+            check(sub, value, path, errors);
+        }
+    }
+    if let Some(Value::Array(any)) = schema.get("anyOf")
+        && !any
+            .iter()
+            .any(|sub| validate_at(sub, value, path).is_empty())
+    {
+        errors.push(format!("{at}: does not match any of the allowed schemas"));
+    }
+    if let Some(Value::Array(one)) = schema.get("oneOf") {
+        let matching = one
+            .iter()
+            .filter(|sub| validate_at(sub, value, path).is_empty())
+            .count();
+        if matching != 1 {
+            errors.push(format!(
+                "{at}: must match exactly one of the allowed schemas, matches {matching}"
+            ));
+        }
+    }
+}
+
+fn validate_at(schema: &Value, value: &Value, path: &str) -> Vec<String> {
+    let mut errors = Vec::new();
+    check(schema, value, path, &mut errors);
+    errors
+}
+
+fn check_object(
+    schema: &Map<String, Value>,
+    map: &Map<String, Value>,
+    path: &str,
+    errors: &mut Vec<String>,
+) {
+    let at = if path.is_empty() { "(root)" } else { path };
+    if let Some(Value::Array(required)) = schema.get("required") {
+        for name in required.iter().filter_map(Value::as_str) {
+            if !map.contains_key(name) {
+                errors.push(format!("{at}: missing required property \"{name}\""));
+            }
+        }
+    }
+    let properties = schema.get("properties").and_then(Value::as_object);
+    for (key, item) in map {
+        let child = format!("{path}/{key}");
+        match properties.and_then(|p| p.get(key)) {
+            Some(property) => check(property, item, &child, errors),
+            None => match schema.get("additionalProperties") {
+                Some(Value::Bool(false)) => {
+                    errors.push(format!("{child}: property is not allowed"));
+                }
+                Some(additional @ Value::Object(_)) => check(additional, item, &child, errors),
+                _ => {}
+            },
+        }
+    }
+}
+
+fn check_limit(
+    schema: &Map<String, Value>,
+    keyword: &str,
+    actual: usize,
+    at: &str,
+    unit: &str,
+    errors: &mut Vec<String>,
+) {
+    let Some(limit) = schema.get(keyword).and_then(Value::as_u64) else {
+        return;
+    };
+    let actual = u64::try_from(actual).unwrap_or(u64::MAX);
+    let (broken, bound) = if keyword.starts_with("min") {
+        (actual < limit, "at least")
+    } else {
+        (actual > limit, "at most")
+    };
+    if broken {
+        errors.push(format!(
+            "{at}: must have {bound} {limit} {unit}, has {actual}"
+        ));
+    }
+}
+
+fn check_range(schema: &Map<String, Value>, n: f64, at: &str, errors: &mut Vec<String>) {
+    let bound = |keyword: &str| schema.get(keyword).and_then(Value::as_f64);
+    if let Some(min) = bound("minimum")
+        && n < min
+    {
+        errors.push(format!("{at}: must be at least {min}"));
+    }
+    if let Some(max) = bound("maximum")
+        && n > max
+    {
+        errors.push(format!("{at}: must be at most {max}"));
+    }
+    if let Some(min) = bound("exclusiveMinimum")
+        && n <= min
+    {
+        errors.push(format!("{at}: must be greater than {min}"));
+    }
+    if let Some(max) = bound("exclusiveMaximum")
+        && n >= max
+    {
+        errors.push(format!("{at}: must be less than {max}"));
+    }
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => {
+            value.is_i64()
+                || value.is_u64()
+                || value
+                    .as_f64()
+                    .is_some_and(|n| n.fract().abs() < f64::EPSILON)
+        }
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Object(_) => "object",
+        Value::Array(_) => "array",
+        Value::String(_) => "string",
+        Value::Number(_) => "number",
+        Value::Bool(_) => "boolean",
+        Value::Null => "null",
+    }
+}
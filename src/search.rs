@@ -0,0 +1,331 @@
+use crate::config::SearchConfig;
+use crate::http;
+use anyhow::{Result, anyhow, bail};
+use scraper::{ElementRef, Html, Selector};
+use serde_json::Value;
+use std::fmt::Write;
+use std::time::Duration;
+use urlencoding::encode;
+
+/// Results asked of each provider.
+const MAX_RESULTS: usize = 10;
+
+/// How long a provider may take before the next one is tried.
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+/// The names `[search] providers` accepts.
+pub const PROVIDER_NAMES: [&str; 4] = ["brave", "searxng", "duckduckgo-lite", "duckduckgo"];
+
+/// One web search result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    pub title: String,
+    pub url: String,
+    pub snippet: String,
+}
+
+/// A web search backend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Provider {
+    /// The Brave Search API, with the key from `[search] brave_api_key`.
+    Brave { api_key: String },
+    /// A SearXNG instance's JSON API; the instance must allow `format=json`.
+    Searxng { url: String },
+    /// The lightweight DuckDuckGo page, whose table markup rarely changes.
+    DuckDuckGoLite,
+    /// The HTML DuckDuckGo page.
+    DuckDuckGo,
+}
+
+impl Provider {
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Brave { .. } => "brave",
+            Self::Searxng { .. } => "searxng",
+            Self::DuckDuckGoLite => "duckduckgo-lite",
+            Self::DuckDuckGo => "duckduckgo",
+        }
+    }
+
+    async fn search(&self, client: &reqwest::Client, query: &str) -> Result<Vec<SearchResult>> {
+        let encoded = encode(query);
+        match self {
+            Self::Brave { api_key } => {
+                let url = format!(
+                    "https://api.search.brave.com/res/v1/web/search?q={encoded}&count={MAX_RESULTS}"
+                );
+                let request = client
+                    .get(url)
+                    .header("Accept", "application/json")
+                    .header("X-Subscription-Token", api_key);
+                parse_brave(&fetch(request).await?)
+            }
+            Self::Searxng { url } => {
+                let url = format!(
+                    "{}/search?q={encoded}&format=json",
+                    url.trim_end_matches('/')
+                );
+                parse_searxng(&fetch(client.get(url)).await?)
+            }
+            Self::DuckDuckGoLite => {
+                let url = format!("https://lite.duckduckgo.com/lite/?q={encoded}");
+                Ok(parse_duckduckgo_lite(&fetch(client.get(url)).await?))
+            }
+            Self::DuckDuckGo => {
+                let url = format!("https://html.duckduckgo.com/html/?q={encoded}");
+                Ok(parse_duckduckgo(&fetch(client.get(url)).await?))
+            }
+        }
+    }
+}
+
+/// The providers to try, in order. Those named in `[search] providers` are used as given;
+/// without that list, Brave and SearXNG come first when configured, followed by both
+/// DuckDuckGo pages.
+///
+/// # Errors
+/// Returns an error for an unknown provider name, or one that is named but not configured.
+pub fn providers(config: &SearchConfig) -> Result<Vec<Provider>> {
+    let brave = || {
+        config
+            .brave_api_key
+            .clone()
+            .filter(|key| !key.is_empty())
+            .map(|api_key| Provider::Brave { api_key })
+    };
+    let searxng = || {
+        config
+            .searxng_url
+            .clone()
+            .filter(|url| !url.is_empty())
+            .map(|url| Provider::Searxng { url })
+    };
+    let Some(names) = &config.providers else {
+        let mut providers: Vec<Provider> = [brave(), searxng()].into_iter().flatten().collect();
+        providers.extend([Provider::DuckDuckGoLite, Provider::DuckDuckGo]);
+        return Ok(providers);
+    };
+    names
+        .iter()
+        .map(|name| match name.as_str() {
+            "brave" => {
+                brave().ok_or_else(|| anyhow!("The brave provider needs [search] brave_api_key"))
+            }
+            "searxng" => {
+                searxng().ok_or_else(|| anyhow!("The searxng provider needs [search] searxng_url"))
+            }
+            "duckduckgo-lite" => Ok(Provider::DuckDuckGoLite),
+            "duckduckgo" => Ok(Provider::DuckDuckGo),
+            other => Err(anyhow!(
+                "Unknown search provider {other} (expected one of {})",
+                PROVIDER_NAMES.join(", ")
+            )),
+        })
+        .collect()
+}
+
+/// Searches with each provider in turn until one returns results, returning its name with
+/// them. If every provider fails or finds nothing, the results are empty when at least
+/// one of them answered.
+///
+/// # Errors
+/// Returns an error listing each provider's failure if none of them answered.
+pub async fn search(
+    providers: &[Provider],
+    query: &str,
+) -> Result<(&'static str, Vec<SearchResult>)> {
+    let client = http::client_builder()?
+        .timeout(TIMEOUT)
+        .build()
+        .map_err(|e| anyhow!("Failed to create HTTP client: {e}"))?;
+    let mut answered = None;
+    let mut failures = Vec::new();
+    for provider in providers {
+        match provider.search(&client, query).await {
+            Ok(results) if !results.is_empty() => return Ok((provider.name(), results)),
+            Ok(_) => {
+                answered.get_or_insert(provider.name());
+            }
+            Err(e) => {
+                tracing::debug!(provider = provider.name(), error = %e, "search provider failed");
+                failures.push(format!("{}: {e}", provider.name()));
+            }
+        }
+    }
+    match answered {
+        Some(name) => Ok((name, Vec::new())),
+        None if failures.is_empty() => bail!("No search providers are configured"),
+        None => bail!("Every search provider failed: {}", failures.join("; ")),
+    }
+}
+
+/// Lists results for the model, one block per result.
+#[must_use]
+pub fn format_results(results: &[SearchResult]) -> String {
+    let mut out = String::new();
+    for result in results {
+        let _ = writeln!(
+            out,
+            "Title: {}\nURL: {}\nSnippet: {}\n---",
+            result.title, result.url, result.snippet
+        );
+    }
+    out.trim_end().to_string()
+}
+
+async fn fetch(request: reqwest::RequestBuilder) -> Result<String> {
+    let response = request
+        .send()
+        .await
+        .map_err(|e| anyhow!("Network error: {e}"))?;
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| anyhow!("Failed to read response body: {e}"))?;
+    if !status.is_success() {
+        if body.to_lowercase().contains("anomaly-modal") {
+            bail!("The search engine is blocking requests; try again later");
+        }
+        bail!("HTTP error {status}");
+    }
+    Ok(body)
+}
+
+/// Reads the results of a Brave Search API response.
+///
+/// # Errors
+/// Returns an error if the response is not JSON.
+pub fn parse_brave(body: &str) -> Result<Vec<SearchResult>> {
+    let value: Value =
+        serde_json::from_str(body).map_err(|e| anyhow!("Invalid Brave response: {e}"))?;
+    Ok(json_results(&value["web"]["results"], "description"))
+}
+
+/// Reads the results of a SearXNG JSON response.
+///
+/// # Errors
+/// Returns an error if the response is not JSON, as when the instance does not allow the
+/// JSON format.
+pub fn parse_searxng(body: &str) -> Result<Vec<SearchResult>> {
+    let value: Value = serde_json::from_str(body).map_err(|e| {
+        anyhow!("Invalid SearXNG response ({e}); is format=json enabled on the instance?")
+    })?;
+    Ok(json_results(&value["results"], "content"))
+}
+
+fn json_results(results: &Value, snippet_key: &str) -> Vec<SearchResult> {
+    let text = |result: &Value, key: &str| strip_tags(result[key].as_str().unwrap_or_default());
+    results
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .map(|result| SearchResult {
+            title: text(result, "title"),
+            url: result["url"].as_str().unwrap_or_default().to_string(),
+            snippet: text(result, snippet_key),
+        })
+        .filter(|result| !result.title.is_empty() && !result.url.is_empty())
+        .take(MAX_RESULTS)
+        .collect()
+}
+
+/// Reads the results of a `lite.duckduckgo.com` page, where each result is a table row
+/// with a `result-link` followed by a row with its `result-snippet`.
+#[must_use]
+pub fn parse_duckduckgo_lite(html: &str) -> Vec<SearchResult> {
+    let document = Html::parse_document(html);
+    let (Ok(link_selector), Ok(snippet_selector)) = (
+        Selector::parse("a.result-link"),
+        Selector::parse("td.result-snippet"),
+    ) else {
+        return Vec::new();
+    };
+    let snippets: Vec<String> = document
+        .select(&snippet_selector)
+        .map(element_text)
+        .collect();
+    document
+        .select(&link_selector)
+        .enumerate()
+        .map(|(n, link)| SearchResult {
+            title: element_text(link),
+            url: result_url(link.value().attr("href").unwrap_or_default()),
+            snippet: snippets.get(n).cloned().unwrap_or_default(),
+        })
+        .filter(|result| !result.title.is_empty() && !result.url.is_empty())
+        .take(MAX_RESULTS)
+        .collect()
+}
+
+/// Reads the results of an `html.duckduckgo.com` page.
+#[must_use]
+pub fn parse_duckduckgo(html: &str) -> Vec<SearchResult> {
+    let document = Html::parse_document(html);
+    let (Ok(result_selector), Ok(link_selector), Ok(snippet_selector)) = (
+        Selector::parse("div.result"),
+        Selector::parse("a.result__a"),
+        Selector::parse("a.result__snippet"),
+    ) else {
+        return Vec::new();
+    };
+    document
+        .select(&result_selector)
+        .filter_map(|result| {
+            let link = result.select(&link_selector).next()?;
+            Some(SearchResult {
+                title: element_text(link),
+                url: result_url(link.value().attr("href").unwrap_or_default()),
+                snippet: result
+                    .select(&snippet_selector)
+                    .next()
+                    .map(element_text)
+                    .unwrap_or_default(),
+            })
+        })
+        .filter(|result| !result.title.is_empty() && !result.url.is_empty())
+        .take(MAX_RESULTS)
+        .collect()
+}
+
+fn element_text(element: ElementRef<'_>) -> String {
+    element
+        .text()
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Makes a result link absolute, and takes the target out of DuckDuckGo's `/l/?uddg=`
+/// redirect links.
+fn result_url(href: &str) -> String {
+    let Ok(url) = reqwest::Url::parse("https://duckduckgo.com/").and_then(|base| base.join(href))
+    else {
+        return String::new();
+    };
+    if url.domain().is_some_and(|d| d.ends_with("duckduckgo.com"))
+        && url.path().starts_with("/l/")
+        && let Some((_, target)) = url.query_pairs().find(|(key, _)| key == "uddg")
+    {
+        return target.into_owned();
+    }
+    url.to_string()
+}
+
+/// Removes the `<strong>` and similar tags APIs put around matched words.
+fn strip_tags(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out.trim().to_string()
+}
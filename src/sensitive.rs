@@ -0,0 +1,94 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+
+/// Files that the read tools keep from the model unless the user allows each one.
+/// Patterns without a `/` match the file name; others match the end of the path. `*`
+/// matches any run of characters within one path component.
+pub const DEFAULT_PATTERNS: &[&str] = &[
+    ".env",
+    ".env.*",
+    "*.pem",
+    "*.key",
+    "*.p12",
+    "*.pfx",
+    "*.jks",
+    "*.keystore",
+    "id_rsa",
+    "id_dsa",
+    "id_ecdsa",
+    "id_ed25519",
+    ".netrc",
+    ".pgpass",
+    ".git-credentials",
+    ".aws/credentials",
+    ".aws/config",
+    ".azure/accessTokens.json",
+    ".azure/msal_token_cache.json",
+    ".config/gcloud/credentials.db",
+    ".config/gcloud/access_tokens.db",
+    "application_default_credentials.json",
+    ".docker/config.json",
+    ".kube/config",
+];
+
+// Files the user has allowed this session, so they are only asked once per file
+static ALLOWED: Mutex<BTreeSet<PathBuf>> = Mutex::new(BTreeSet::new());
+
+/// Returns the pattern `path` matches, if it looks like a file holding secrets.
+#[must_use]
+pub fn matching_pattern(path: &Path) -> Option<&'static str> {
+    let components: Vec<&str> = path
+        .components()
+        .filter_map(|c| c.as_os_str().to_str())
+        .collect();
+    DEFAULT_PATTERNS.iter().copied().find(|pattern| {
+        let parts: Vec<&str> = pattern.split('/').collect();
+        parts.len() <= components.len()
+            && parts
+                .iter()
+                .zip(&components[components.len() - parts.len()..])
+                .all(|(part, component)| wildcard_match(part, component))
+    })
+}
+
+/// Whether the user has allowed the model to see this file.
+#[must_use]
+pub fn is_allowed(path: &Path) -> bool {
+    ALLOWED
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .contains(&key(path))
+}
+
+/// Allows the model to see this file for the rest of the session.
+pub fn allow(path: &Path) {
+    ALLOWED
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(key(path));
+}
+
+/// Returns the pattern `path` matches if it is sensitive and has not been allowed.
+#[must_use]
+pub fn guarded(path: &Path) -> Option<&'static str> {
+    matching_pattern(path).filter(|_| !is_allowed(path))
+}
+
+fn key(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == text,
+        Some((prefix, rest)) => {
+            let Some(text) = text.strip_prefix(prefix) else {
+                return false;
+            };
+            (0..=text.len())
+                .filter(|&i| text.is_char_boundary(i))
+                .any(|i| wildcard_match(rest, &text[i..]))
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use anyhow::{Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Where a named session left off.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamedSession {
+    pub chat_id: String,
+    /// The last message of the chat this CLI saw, which the next one replies to
+    #[serde(default)]
+    pub parent_id: Option<i64>,
+    /// Unix timestamp (seconds) of the last message sent in the session.
+    pub last_active: i64,
+}
+
+/// Names given to chats with `--session`, stored in `~/.config/deepseek-cli/sessions.json`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SessionRegistry {
+    sessions: BTreeMap<String, NamedSession>,
+}
+
+impl SessionRegistry {
+    #[must_use]
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|d| d.join("deepseek-cli/sessions.json"))
+    }
+
+    /// Loads the registry, returning an empty one if the file does not exist yet.
+    ///
+    /// # Errors
+    /// Returns an error if the file exists but cannot be read or parsed.
+    pub fn load() -> Result<Self> {
+        let Some(path) = Self::path().filter(|p| p.exists()) else {
+            return Ok(Self::default());
+        };
+        let content = std::fs::read_to_string(&path)?;
+        serde_json::from_str(&content).map_err(|e| anyhow!("Invalid {}: {e}", path.display()))
+    }
+
+    /// # Errors
+    /// Returns an error if the config directory is unavailable or the file cannot be written.
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path().ok_or_else(|| anyhow!("No config directory available"))?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&NamedSession> {
+        self.sessions.get(name)
+    }
+
+    /// Points `name` at a chat and the message it has got to, adding it if unknown.
+    pub fn set(&mut self, name: &str, chat_id: &str, parent_id: Option<i64>) {
+        self.sessions.insert(
+            name.to_string(),
+            NamedSession {
+                chat_id: chat_id.to_string(),
+                parent_id,
+                last_active: chrono::Utc::now().timestamp(),
+            },
+        );
+    }
+
+    /// Removes a session, returning it if it was present.
+    pub fn remove(&mut self, name: &str) -> Option<NamedSession> {
+        self.sessions.remove(name)
+    }
+
+    /// The sessions by name, in alphabetical order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &NamedSession)> {
+        self.sessions.iter().map(|(name, s)| (name.as_str(), s))
+    }
+}
+
+/// Checks that a session name is something one would type: letters, digits, `-`, `_` and
+/// `.`.
+///
+/// # Errors
+/// Returns an error describing what is wrong with the name.
+pub fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty() {
+        bail!("Session names cannot be empty");
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '.')))
+    {
+        bail!("Session names are made of letters, digits, -, _ and .; {name:?} has {c:?}");
+    }
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use crate::model::Model;
+use anyhow::{Result, bail};
+
+/// Parameters other APIs take for sampling or reasoning that the chat API does not.
+const UNSUPPORTED: [&str; 6] = [
+    "temperature",
+    "top_p",
+    "top-p",
+    "seed",
+    "reasoning_effort",
+    "reasoning-effort",
+];
+
+/// A change `/set` makes to how completions are requested. The chat API takes only two
+/// such parameters: whether the model may search the web, and whether it thinks before
+/// answering, which is the choice of model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    Search(bool),
+    Model(Model),
+}
+
+impl Setting {
+    /// Parses the argument of `/set`, such as `search off` or `thinking on`.
+    ///
+    /// # Errors
+    /// Returns an error for an unknown name or value, and says so for sampling parameters
+    /// such as `temperature` that the API has no way to pass.
+    pub fn parse(arg: &str) -> Result<Self> {
+        let (name, value) = arg
+            .trim()
+            .split_once(char::is_whitespace)
+            .map_or((arg.trim(), ""), |(name, value)| (name, value.trim()));
+        let name = name.to_ascii_lowercase();
+        if UNSUPPORTED.contains(&name.as_str()) {
+            bail!(
+                "{name} cannot be set: the DeepSeek chat API takes no sampling parameters, only whether to search the web and whether to think (/set search off, /set thinking off)"
+            );
+        }
+        let switch = || match value {
+            "on" | "true" | "yes" => Ok(true),
+            "off" | "false" | "no" => Ok(false),
+            "" => bail!("/set {name} needs on or off"),
+            other => bail!("Invalid value for {name}: {other} (expected on or off)"),
+        };
+        match name.as_str() {
+            "search" => Ok(Self::Search(switch()?)),
+            "thinking" => Ok(Self::Model(if switch()? {
+                Model::Reasoner
+            } else {
+                Model::Chat
+            })),
+            other => bail!("Unknown setting: {other} (expected search or thinking)"),
+        }
+    }
+}
+
+/// The settings as `/set` without an argument shows them.
+#[must_use]
+pub fn render(search: bool, model: Model) -> String {
+    let state = |on: bool| if on { "on" } else { "off" };
+    format!(
+        "search    {}\nthinking  {} ({model})",
+        state(search),
+        state(model.thinking())
+    )
+}
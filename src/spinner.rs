@@ -0,0 +1,65 @@
+use indicatif::{ProgressBar, ProgressStyle};
+use std::borrow::Cow;
+use std::io::IsTerminal;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+/// How often the spinner turns and its elapsed time is redrawn.
+const TICK: Duration = Duration::from_millis(100);
+
+/// The spinner on screen, if any, so that prompts can hide it while they wait for an answer.
+static ACTIVE: Mutex<Option<ProgressBar>> = Mutex::new(None);
+
+/// A spinner with the time elapsed, shown on standard error while the agent waits for the
+/// model or for tools, so that a long wait does not look like a hang. It is cleared when
+/// dropped, before the output that ends the wait is printed. Nothing is drawn when
+/// standard error is not a terminal.
+pub struct Spinner {
+    bar: Option<ProgressBar>,
+}
+
+impl Spinner {
+    #[must_use]
+    pub fn start(message: impl Into<Cow<'static, str>>) -> Self {
+        if !std::io::stderr().is_terminal() {
+            return Self { bar: None };
+        }
+        let bar = ProgressBar::new_spinner();
+        if let Ok(style) = ProgressStyle::with_template("{spinner:.cyan} {msg} {elapsed:.dim}") {
+            bar.set_style(style);
+        }
+        bar.set_message(message);
+        bar.enable_steady_tick(TICK);
+        *ACTIVE.lock().unwrap_or_else(PoisonError::into_inner) = Some(bar.clone());
+        Self { bar: Some(bar) }
+    }
+
+    /// Changes what the spinner says it is waiting for; the elapsed time keeps counting.
+    pub fn set_message(&self, message: impl Into<Cow<'static, str>>) {
+        if let Some(bar) = &self.bar {
+            bar.set_message(message);
+        }
+    }
+}
+
+impl Drop for Spinner {
+    fn drop(&mut self) {
+        if let Some(bar) = self.bar.take() {
+            bar.finish_and_clear();
+            *ACTIVE.lock().unwrap_or_else(PoisonError::into_inner) = None;
+        }
+    }
+}
+
+/// Runs `f` with the spinner, if one is shown, taken off the screen, for prompts that
+/// print a question and read the answer.
+pub fn suspend<R>(f: impl FnOnce() -> R) -> R {
+    let active = ACTIVE
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    match active {
+        Some(bar) => bar.suspend(f),
+        None => f(),
+    }
+}
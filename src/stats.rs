@@ -0,0 +1,314 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+/// Turns `/stats` shows the timing of, most recent last.
+const TURNS_SHOWN: usize = 10;
+
+/// Rough token count for text sent to the model, at about four bytes per token.
+#[must_use]
+pub fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(4)
+}
+
+/// How much one tool has added to the prompts of a session.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ToolUsage {
+    pub calls: usize,
+    /// Estimated tokens of results and attached output, over all calls
+    pub tokens: usize,
+    /// Estimated tokens of the largest single result
+    pub largest: usize,
+}
+
+/// Tokens one request to the API used, estimated from the text since the API does not
+/// report usage.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RequestUsage {
+    /// The prompt as sent, without attached files or the earlier messages of the chat
+    pub prompt_tokens: usize,
+    /// Thinking and response
+    pub completion_tokens: usize,
+}
+
+/// Where the time of one turn went, from sending the message to the final reply.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TurnStats {
+    /// Requests to the API, including retries and those after tool calls
+    pub requests: usize,
+    /// From sending the first request to the first thinking or content that came back
+    pub first_token: Option<Duration>,
+    /// Spent streaming replies, over all requests
+    pub streaming: Duration,
+    /// Spent running tools, not counting the wait for approvals
+    pub tools: Duration,
+    pub total: Duration,
+    pub usage: RequestUsage,
+}
+
+impl TurnStats {
+    /// The turn on one line, as shown after it when the footer is on.
+    #[must_use]
+    pub fn render_footer(&self) -> String {
+        let first_token = self
+            .first_token
+            .map_or_else(|| "-".to_string(), format_seconds);
+        format!(
+            "first token {first_token} · streaming {} · tools {} · total {} · ~{} prompt + ~{} completion tokens",
+            format_seconds(self.streaming),
+            format_seconds(self.tools),
+            format_seconds(self.total),
+            format_tokens(self.usage.prompt_tokens),
+            format_tokens(self.usage.completion_tokens),
+        )
+    }
+}
+
+/// Formats a duration in seconds with one decimal, e.g. `0.8s` or `12.4s`.
+#[must_use]
+pub fn format_seconds(duration: Duration) -> String {
+    format!("{:.1}s", duration.as_secs_f64())
+}
+
+/// Counters shown by `/stats` and `/usage`.
+#[derive(Debug, Default)]
+pub struct SessionStats {
+    tools: HashMap<String, ToolUsage>,
+    requests: Vec<RequestUsage>,
+    /// Estimated tokens in the context of the current chat
+    context_tokens: usize,
+    turns: Vec<TurnStats>,
+    /// The turn under way, with when it started
+    turn: Option<(Instant, TurnStats)>,
+}
+
+impl SessionStats {
+    /// Records a tool call whose result message is `result`.
+    pub fn record_tool_call(&mut self, tool: &str, result: &str) {
+        let usage = self.tools.entry(tool.to_string()).or_default();
+        let tokens = estimate_tokens(result);
+        usage.calls += 1;
+        usage.tokens += tokens;
+        usage.largest = usage.largest.max(tokens);
+    }
+
+    /// Starts counting the context of a new chat, such as one that replaced a compacted
+    /// conversation.
+    pub fn start_chat(&mut self) {
+        self.context_tokens = 0;
+    }
+
+    /// Estimated tokens the current chat's context holds: every prompt, attachment and
+    /// reply since it started.
+    #[must_use]
+    pub fn context_tokens(&self) -> usize {
+        self.context_tokens
+    }
+
+    /// Whether the current chat's context has reached `percent` of `window` tokens.
+    #[must_use]
+    pub fn needs_compaction(&self, window: usize, percent: u8) -> bool {
+        percent > 0 && self.context_tokens * 100 >= window * usize::from(percent)
+    }
+
+    /// Records output a tool call attached to the prompt as a file, in addition to its
+    /// result message.
+    pub fn record_attachment(&mut self, tool: &str, content: &str) {
+        let usage = self.tools.entry(tool.to_string()).or_default();
+        let tokens = estimate_tokens(content);
+        usage.tokens += tokens;
+        usage.largest = usage.largest.max(tokens);
+        self.context_tokens += tokens;
+    }
+
+    /// Records a request to the API, including failed attempts that were retried.
+    pub fn record_request(&mut self, usage: RequestUsage) {
+        self.context_tokens += usage.prompt_tokens + usage.completion_tokens;
+        self.requests.push(usage);
+        if let Some((_, turn)) = &mut self.turn {
+            turn.requests += 1;
+            turn.usage.prompt_tokens += usage.prompt_tokens;
+            turn.usage.completion_tokens += usage.completion_tokens;
+        }
+    }
+
+    /// Starts timing a turn at `now`; one that was not finished is dropped.
+    pub fn start_turn(&mut self, now: Instant) {
+        self.turn = Some((now, TurnStats::default()));
+    }
+
+    /// Records how long a request's reply took to stream and, if anything came back, to
+    /// its first thinking or content. The first request of a turn that streams anything
+    /// gives the turn's time to first token.
+    pub fn record_stream(&mut self, first_token: Option<Duration>, streaming: Duration) {
+        if let Some((_, turn)) = &mut self.turn {
+            if turn.first_token.is_none() {
+                turn.first_token = first_token;
+            }
+            turn.streaming += streaming;
+        }
+    }
+
+    /// Records time spent running a batch of tool calls.
+    pub fn record_tool_time(&mut self, elapsed: Duration) {
+        if let Some((_, turn)) = &mut self.turn {
+            turn.tools += elapsed;
+        }
+    }
+
+    /// Ends the turn under way at `now`, returning what it took.
+    pub fn finish_turn(&mut self, now: Instant) -> Option<TurnStats> {
+        let (started, mut turn) = self.turn.take()?;
+        turn.total = now.saturating_duration_since(started);
+        self.turns.push(turn);
+        Some(turn)
+    }
+
+    /// The finished turns, oldest first.
+    #[must_use]
+    pub fn turns(&self) -> &[TurnStats] {
+        &self.turns
+    }
+
+    #[must_use]
+    pub fn requests(&self) -> &[RequestUsage] {
+        &self.requests
+    }
+
+    /// Total usage over all requests of the session.
+    #[must_use]
+    pub fn total_usage(&self) -> RequestUsage {
+        self.requests
+            .iter()
+            .fold(RequestUsage::default(), |total, request| RequestUsage {
+                prompt_tokens: total.prompt_tokens + request.prompt_tokens,
+                completion_tokens: total.completion_tokens + request.completion_tokens,
+            })
+    }
+
+    /// Token usage of the session, for `/usage` and the summary at exit.
+    #[must_use]
+    pub fn render_usage(&self) -> String {
+        let Some(last) = self.requests.last() else {
+            return "No requests yet".to_string();
+        };
+        let total = self.total_usage();
+        let requests = self.requests.len();
+        format!(
+            "Token usage (estimated): {requests} {}, ~{} prompt + ~{} completion = ~{} tokens\n  last request: ~{} prompt + ~{} completion",
+            if requests == 1 { "request" } else { "requests" },
+            format_tokens(total.prompt_tokens),
+            format_tokens(total.completion_tokens),
+            format_tokens(total.prompt_tokens + total.completion_tokens),
+            format_tokens(last.prompt_tokens),
+            format_tokens(last.completion_tokens),
+        )
+    }
+
+    /// Tools ordered from the most tokens injected to the least.
+    #[must_use]
+    pub fn tools_by_cost(&self) -> Vec<(&str, ToolUsage)> {
+        let mut tools: Vec<_> = self
+            .tools
+            .iter()
+            .map(|(name, usage)| (name.as_str(), *usage))
+            .collect();
+        tools.sort_by(|a, b| b.1.tokens.cmp(&a.1.tokens).then(a.0.cmp(b.0)));
+        tools
+    }
+
+    /// The per-tool breakdown as a table.
+    #[must_use]
+    pub fn render(&self) -> String {
+        let tools = self.tools_by_cost();
+        if tools.is_empty() {
+            return "No tool calls yet".to_string();
+        }
+        let total: usize = tools.iter().map(|(_, usage)| usage.tokens).sum();
+        let mut out = format!(
+            "Tool output injected into prompts: ~{} tokens (estimated)\n",
+            format_tokens(total)
+        );
+        for (name, usage) in tools {
+            let _ = writeln!(
+                out,
+                "  {name:<24} {:>4} {:<5}  ~{:>6} tokens  largest ~{}",
+                usage.calls,
+                if usage.calls == 1 { "call" } else { "calls" },
+                format_tokens(usage.tokens),
+                format_tokens(usage.largest),
+            );
+        }
+        out.truncate(out.trim_end().len());
+        out
+    }
+
+    /// The timing of the last turns as a table, with the average over all of them.
+    #[must_use]
+    pub fn render_turns(&self) -> String {
+        if self.turns.is_empty() {
+            return "No turns yet".to_string();
+        }
+        let mut out = format!(
+            "Turn timing (the last {}):\n  {:>4}  {:>11}  {:>9}  {:>8}  {:>8}  {:>8}  {:>10}\n",
+            self.turns.len().min(TURNS_SHOWN),
+            "turn",
+            "first token",
+            "streaming",
+            "tools",
+            "total",
+            "requests",
+            "tokens"
+        );
+        let skipped = self.turns.len().saturating_sub(TURNS_SHOWN);
+        for (i, turn) in self.turns.iter().enumerate().skip(skipped) {
+            let _ = writeln!(
+                out,
+                "  {:>4}  {:>11}  {:>9}  {:>8}  {:>8}  {:>8}  {:>10}",
+                i + 1,
+                turn.first_token
+                    .map_or_else(|| "-".to_string(), format_seconds),
+                format_seconds(turn.streaming),
+                format_seconds(turn.tools),
+                format_seconds(turn.total),
+                turn.requests,
+                format!(
+                    "~{}",
+                    format_tokens(turn.usage.prompt_tokens + turn.usage.completion_tokens)
+                ),
+            );
+        }
+        let count = u32::try_from(self.turns.len()).unwrap_or(u32::MAX);
+        let first_tokens: Vec<Duration> = self.turns.iter().filter_map(|t| t.first_token).collect();
+        let average = |total: Duration| format_seconds(total / count);
+        let _ = write!(
+            out,
+            "  average: first token {}, streaming {}, tools {}, total {}",
+            u32::try_from(first_tokens.len())
+                .ok()
+                .filter(|&n| n > 0)
+                .map_or_else(
+                    || "-".to_string(),
+                    |n| format_seconds(first_tokens.iter().sum::<Duration>() / n)
+                ),
+            average(self.turns.iter().map(|t| t.streaming).sum()),
+            average(self.turns.iter().map(|t| t.tools).sum()),
+            average(self.turns.iter().map(|t| t.total).sum()),
+        );
+        out
+    }
+}
+
+/// Formats a token count compactly, e.g. `950`, `12.3k` or `1.2M`.
+#[must_use]
+pub fn format_tokens(tokens: usize) -> String {
+    #[allow(clippy::cast_precision_loss)]
+    let value = tokens as f64;
+    if tokens < 1000 {
+        tokens.to_string()
+    } else if tokens < 1_000_000 {
+        format!("{:.1}k", value / 1000.0)
+    } else {
+        format!("{:.1}M", value / 1_000_000.0)
+    }
+}
@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+use std::path::Path;
+use tree_sitter::{Language, Node, Parser};
+
+/// Top-level node kinds that are gathered into a single `imports` chunk.
+const IMPORT_KINDS: [&str; 5] = [
+    "use_declaration",
+    "extern_crate_declaration",
+    "import_statement",
+    "import_from_statement",
+    "import_declaration",
+];
+
+/// Node kinds listed by [`outline`]. Functions are listed without their contents; the
+/// members of the others are listed under them.
+const SYMBOL_KINDS: [&str; 26] = [
+    // Rust
+    "function_item",
+    "function_signature_item",
+    "struct_item",
+    "enum_item",
+    "union_item",
+    "trait_item",
+    "impl_item",
+    "mod_item",
+    "type_item",
+    "const_item",
+    "static_item",
+    "macro_definition",
+    // Python
+    "function_definition",
+    "class_definition",
+    // JavaScript and TypeScript
+    "function_declaration",
+    "generator_function_declaration",
+    "class_declaration",
+    "abstract_class_declaration",
+    "method_definition",
+    "interface_declaration",
+    "type_alias_declaration",
+    "enum_declaration",
+    // Go
+    "method_declaration",
+    "type_declaration",
+    "const_declaration",
+    "var_declaration",
+];
+
+/// Nodes that are not symbols themselves but hold some, such as the body of an impl block.
+const CONTAINER_KINDS: [&str; 5] = [
+    "declaration_list",
+    "class_body",
+    "block",
+    "export_statement",
+    "decorated_definition",
+];
+
+/// A contiguous range of lines in a source file, named after the item it contains.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    /// Symbol name (`execute_tool`, `impl BrowserState`) or `partN` for plain line chunks.
+    pub name: String,
+    /// Syntax node kind such as `function_item`, or `lines` for plain line chunks.
+    pub kind: String,
+    /// First line, 1-based.
+    pub start_line: usize,
+    /// Last line, 1-based and inclusive.
+    pub end_line: usize,
+}
+
+/// An item listed in a file's outline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    /// Symbol name, e.g. `execute_tool` or `impl BrowserState`.
+    pub name: String,
+    /// Kind of item, e.g. `function` or `struct`.
+    pub kind: String,
+    /// First line, 1-based.
+    pub start_line: usize,
+    /// Last line, 1-based and inclusive.
+    pub end_line: usize,
+    /// 0 for top-level items, 1 for their members, and so on.
+    pub depth: usize,
+}
+
+/// Lists the functions, types, impl blocks and other items of a file, with the members of
+/// types and impl blocks under them. Returns `None` if the file's language has no grammar.
+#[must_use]
+pub fn outline(path: &Path, source: &str) -> Option<Vec<Symbol>> {
+    let mut parser = Parser::new();
+    parser.set_language(&language_for(path)?).ok()?;
+    let tree = parser.parse(source, None)?;
+    let mut symbols = Vec::new();
+    outline_children(tree.root_node(), source, 0, &mut symbols);
+    Some(symbols)
+}
+
+fn outline_children(node: Node<'_>, source: &str, depth: usize, symbols: &mut Vec<Symbol>) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        let kind = child.kind();
+        if SYMBOL_KINDS.contains(&kind) {
+            symbols.push(Symbol {
+                name: symbol_name(child, source).unwrap_or_else(|| kind.to_string()),
+                kind: symbol_kind(kind).to_string(),
+                start_line: child.start_position().row + 1,
+                end_line: child.end_position().row + 1,
+                depth,
+            });
+            if !kind.starts_with("function") && !kind.starts_with("method") {
+                outline_children(child, source, depth + 1, symbols);
+            }
+        } else if CONTAINER_KINDS.contains(&kind) {
+            outline_children(child, source, depth, symbols);
+        }
+    }
+}
+
+/// `function_item` -> `function`, `class_declaration` -> `class`, ...
+fn symbol_kind(kind: &str) -> &str {
+    ["_item", "_declaration", "_definition"]
+        .iter()
+        .find_map(|suffix| kind.strip_suffix(suffix))
+        .unwrap_or(kind)
+}
+
+/// Returns the tree-sitter grammar for a file, based on its extension.
+#[must_use]
+pub fn language_for(path: &Path) -> Option<Language> {
+    let language = match path.extension()?.to_str()? {
+        "rs" => tree_sitter_rust::LANGUAGE,
+        "py" => tree_sitter_python::LANGUAGE,
+        "js" | "jsx" | "mjs" | "cjs" => tree_sitter_javascript::LANGUAGE,
+        "ts" | "mts" | "cts" => tree_sitter_typescript::LANGUAGE_TYPESCRIPT,
+        "tsx" => tree_sitter_typescript::LANGUAGE_TSX,
+        "go" => tree_sitter_go::LANGUAGE,
+        _ => return None,
+    };
+    Some(language.into())
+}
+
+/// Splits a file into chunks along top-level items (functions, types, impl blocks, ...)
+/// when its language is supported, or into chunks of `max_lines` lines otherwise.
+///
+/// Lines between items, such as comments and attributes, belong to the following item.
+#[must_use]
+pub fn chunk_source(path: &Path, source: &str, max_lines: usize) -> Vec<Chunk> {
+    let mut chunks = syntax_chunks(path, source).unwrap_or_default();
+    if chunks.is_empty() {
+        chunks = line_chunks(source, max_lines);
+    }
+    make_names_unique(&mut chunks);
+    chunks
+}
+
+fn syntax_chunks(path: &Path, source: &str) -> Option<Vec<Chunk>> {
+    let mut parser = Parser::new();
+    parser.set_language(&language_for(path)?).ok()?;
+    let tree = parser.parse(source, None)?;
+    let root = tree.root_node();
+    let total_lines = source.lines().count().max(1);
+
+    let mut chunks: Vec<Chunk> = Vec::new();
+    let mut cursor = root.walk();
+    for node in root.named_children(&mut cursor) {
+        // Comments and attributes are attached to the item that follows them
+        if node.kind().contains("comment") || node.kind() == "attribute_item" {
+            continue;
+        }
+        let start_line = chunks.last().map_or(1, |c| c.end_line + 1);
+        let end_line = (node.end_position().row + 1).max(start_line);
+        if IMPORT_KINDS.contains(&node.kind()) {
+            match chunks.last_mut() {
+                Some(last) if last.kind == "imports" => last.end_line = end_line,
+                _ => chunks.push(Chunk {
+                    name: "imports".to_string(),
+                    kind: "imports".to_string(),
+                    start_line,
+                    end_line,
+                }),
+            }
+            continue;
+        }
+        chunks.push(Chunk {
+            name: symbol_name(node, source).unwrap_or_else(|| node.kind().to_string()),
+            kind: node.kind().to_string(),
+            start_line,
+            end_line,
+        });
+    }
+    // Trailing comments and blank lines
+    if let Some(last) = chunks.last_mut() {
+        last.end_line = total_lines.max(last.end_line);
+    }
+    Some(chunks)
+}
+
+/// Extracts a readable name for a top-level item.
+#[must_use]
+pub fn symbol_name(node: Node<'_>, source: &str) -> Option<String> {
+    let text = |n: Node<'_>| n.utf8_text(source.as_bytes()).ok().map(str::to_string);
+    if let Some(name) = node.child_by_field_name("name") {
+        return text(name);
+    }
+    match node.kind() {
+        // Rust `impl Trait for Type` / `impl Type`
+        "impl_item" => {
+            let ty = text(node.child_by_field_name("type")?)?;
+            Some(match node.child_by_field_name("trait").and_then(text) {
+                Some(tr) => format!("impl {tr} for {ty}"),
+                None => format!("impl {ty}"),
+            })
+        }
+        // JS/TS `export ...`, Python decorators
+        "export_statement" | "decorated_definition" => {
+            let inner = node
+                .child_by_field_name("declaration")
+                .or_else(|| node.child_by_field_name("definition"))?;
+            symbol_name(inner, source)
+        }
+        // `const x = ...`, Go `type (...)` and similar: use the first declared name
+        _ => {
+            let mut cursor = node.walk();
+            let mut children = node.named_children(&mut cursor);
+            children.find_map(|child| child.child_by_field_name("name").and_then(text))
+        }
+    }
+}
+
+fn line_chunks(source: &str, max_lines: usize) -> Vec<Chunk> {
+    let total_lines = source.lines().count().max(1);
+    let max_lines = max_lines.max(1);
+    (0..total_lines.div_ceil(max_lines))
+        .map(|i| Chunk {
+            name: format!("part{}", i + 1),
+            kind: "lines".to_string(),
+            start_line: i * max_lines + 1,
+            end_line: ((i + 1) * max_lines).min(total_lines),
+        })
+        .collect()
+}
+
+// Overloaded names (several `impl Foo` blocks) get a `~2`, `~3`, ... suffix
+fn make_names_unique(chunks: &mut [Chunk]) {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for chunk in chunks {
+        let count = seen.entry(chunk.name.clone()).or_insert(0);
+        *count += 1;
+        if *count > 1 {
+            chunk.name = format!("{}~{count}", chunk.name);
+        }
+    }
+}
+
+/// Returns the text of the given chunk.
+#[must_use]
+pub fn chunk_text(source: &str, chunk: &Chunk) -> String {
+    source
+        .lines()
+        .skip(chunk.start_line - 1)
+        .take(chunk.end_line + 1 - chunk.start_line)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
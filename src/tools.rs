@@ -1,20 +1,33 @@
+use crate::args::{
+    AskUserArgs, BrowserTypeArgs, CommandArgs, CommentArgs, EditLinesArgs, FetchUrlArgs,
+    FindFileArgs, Format, GrepArgs, ListFilesArgs, MoveFileArgs, PathArgs, SearchReplaceArgs,
+    TabArgs, ToolArgs, WaitArgs, WriteFileArgs,
+};
+use crate::documents::{self, Document, Kind};
+use crate::workspace::Workspace;
+use crate::{
+    binary, cache, config, filelock, focus, github, http, index, memory, notify, patch, plugins,
+    protocol, sandbox, search, sensitive, spinner, syntax, workspace,
+};
 use anyhow::{Result, anyhow};
 use chromiumoxide::page::ScreenshotParams;
 use chromiumoxide::{Browser, BrowserConfig, Page};
+use colored::Colorize;
 use futures_util::StreamExt;
 use once_cell::sync::OnceCell;
-use scraper::{Html, Selector};
+use scraper::{ElementRef, Html, Node, Selector};
+use serde::Serialize;
 use std::collections::HashMap;
 use std::future::Future;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
-use std::sync::Arc;
 use std::sync::LazyLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, PoisonError, RwLock};
 use tokio::fs;
 use tokio::process::Command;
 use tokio::sync::Mutex;
 use tokio::time::{Duration, timeout};
-use urlencoding::encode;
 
 /// Represents the result of executing a tool.
 #[derive(Debug)]
@@ -33,24 +46,269 @@ pub enum ToolOutput {
     StatusOnly { status: String },
 }
 
-struct Tool {
-    description: &'static str,
+/// How a tool call ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolStatus {
+    Succeeded,
+    Failed,
+}
+
+/// Something besides text that a tool call produced, attached to the message to the model.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Artifact {
+    /// Data to upload, such as a screenshot. Only its type is serialized.
+    Binary {
+        #[serde(skip)]
+        data: Vec<u8>,
+        mime_type: String,
+    },
+    /// A file that is uploaded already.
+    File { file_id: String },
+}
+
+/// What a tool call came to, in the same shape for every tool, so that the interfaces
+/// show it and the logs record it alike.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ToolResult {
+    pub status: ToolStatus,
+    /// One line saying what happened, such as `Read file at src/main.rs`, or why the call
+    /// failed.
+    pub summary: String,
+    /// The text the tool returned, such as the contents of a file or a command's output.
+    pub body: Option<String>,
+    pub artifacts: Vec<Artifact>,
+}
+
+impl ToolResult {
+    /// The result of a call to `tool` that failed with `error`.
+    #[must_use]
+    pub fn failed(tool: &str, error: impl std::fmt::Display) -> Self {
+        Self {
+            status: ToolStatus::Failed,
+            summary: format!("TOOL {tool} failed: {error}"),
+            body: None,
+            artifacts: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn is_success(&self) -> bool {
+        self.status == ToolStatus::Succeeded
+    }
+}
+
+impl From<ToolOutput> for ToolResult {
+    fn from(output: ToolOutput) -> Self {
+        let (summary, body, artifacts) = match output {
+            ToolOutput::Text { content, status } => (status, Some(content), Vec::new()),
+            ToolOutput::Binary {
+                data,
+                mime_type,
+                status,
+            } => (status, None, vec![Artifact::Binary { data, mime_type }]),
+            ToolOutput::FileReference { file_id, status } => {
+                (status, None, vec![Artifact::File { file_id }])
+            }
+            ToolOutput::StatusOnly { status } => (status, None, Vec::new()),
+        };
+        Self {
+            status: ToolStatus::Succeeded,
+            summary,
+            body,
+            artifacts,
+        }
+    }
+}
+
+/// A tool the agent can call, with what the system prompt and the approval checks need to
+/// know about it.
+pub struct Tool {
+    /// `name <args> : what it does`, as listed in the system prompt
+    description: String,
+    /// Whether the tool only inspects things, so it may be used in plan mode.
+    read_only: bool,
+    /// Whether calls to it need a reason and the user's approval.
+    needs_approval: bool,
+    /// Names of the arguments in a JSON tool call, in the order they make up the text
+    /// argument.
+    params: &'static [&'static str],
     handler: ToolHandler,
 }
 
-type ToolHandler = Box<dyn for<'a> Fn(&'a str) -> ToolFuture<'a> + Send + Sync>;
+impl Tool {
+    /// A tool that changes files or runs commands: it is left out of plan mode, and calls
+    /// to it need a reason and approval.
+    #[must_use]
+    pub fn new(
+        description: impl Into<String>,
+        handler: impl for<'a> Fn(&'a ToolContext, &'a str) -> ToolFuture<'a> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            description: description.into(),
+            read_only: false,
+            needs_approval: true,
+            params: &[],
+            handler: Box::new(handler),
+        }
+    }
+
+    /// Marks the tool as only inspecting things, so it is available in plan mode and runs
+    /// without approval.
+    #[must_use]
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self.needs_approval = false;
+        self
+    }
+
+    /// Lets calls run without approval although the tool is not read-only, as for the
+    /// browser tools, which only act on the browser they opened.
+    #[must_use]
+    pub fn without_approval(mut self) -> Self {
+        self.needs_approval = false;
+        self
+    }
+
+    /// Names the arguments of a JSON tool call, in the order they make up the text
+    /// argument.
+    #[must_use]
+    pub fn params(mut self, params: &'static [&'static str]) -> Self {
+        self.params = params;
+        self
+    }
+
+    #[must_use]
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    #[must_use]
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    #[must_use]
+    pub fn needs_approval(&self) -> bool {
+        self.needs_approval
+    }
+}
+
+/// The tools by name. Tools may be registered and removed while the agent runs; the
+/// system prompt is built from the ones registered at the time.
+pub struct ToolRegistry {
+    tools: RwLock<HashMap<String, Arc<Tool>>>,
+}
+
+/// Which tools the agent may use.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AgentMode {
+    /// All tools are available.
+    #[default]
+    Normal,
+    /// Only read-only tools are available and the model is asked to produce a plan first.
+    Plan,
+}
+
+impl AgentMode {
+    fn allows(self, tool: &Tool) -> bool {
+        self == Self::Normal || tool.read_only
+    }
+
+    /// The system prompt listing this mode's tools, as registered now.
+    #[must_use]
+    pub fn system_prompt(self) -> String {
+        build_system_prompt(self, None, None)
+    }
+}
+
+type ToolHandler = Box<dyn for<'a> Fn(&'a ToolContext, &'a str) -> ToolFuture<'a> + Send + Sync>;
+
+/// What a tool's handler returns: the future of its output, borrowing the context and the
+/// argument.
+pub type ToolFuture<'a> = Pin<Box<dyn Future<Output = Result<ToolOutput>> + Send + 'a>>;
+
+/// Where a tool call runs: relative paths in its argument are taken from the active root
+/// of the workspace and may not leave it, `<name>:<path>` is a path in another root, and
+/// `run_command` runs in the active root. The agent uses the workspace it was started in;
+/// tests point it at a directory of their own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolContext {
+    pub workspace: Workspace,
+}
+
+impl ToolContext {
+    /// A context with the one root `root`.
+    #[must_use]
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            workspace: Workspace::single(root),
+        }
+    }
+
+    /// The workspace as configured and switched with `/workspace`; see
+    /// [`workspace::current`].
+    #[must_use]
+    pub fn current() -> Self {
+        Self {
+            workspace: workspace::current(),
+        }
+    }
+
+    /// The active root.
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        self.workspace.root()
+    }
+
+    /// `path` as the tool opens it, with `.` and `..` worked out; see
+    /// [`Workspace::resolve`].
+    ///
+    /// # Errors
+    /// Returns an error if the path is empty or a relative path leads outside its root.
+    pub fn resolve(&self, path: &str) -> Result<PathBuf> {
+        self.workspace.resolve(path)
+    }
+
+    /// The directory a searching tool looks in: `directory` if given, otherwise the
+    /// focused directory, or the root when nothing is focused.
+    ///
+    /// # Errors
+    /// Returns an error if `directory` leads outside the root.
+    pub fn search_root(&self, directory: Option<&str>) -> Result<PathBuf> {
+        match directory {
+            Some(directory) => self.resolve(directory),
+            None if focus::current().is_some() => Ok(std::path::absolute(focus::search_root())?),
+            None => Ok(self.root().to_path_buf()),
+        }
+    }
 
-type ToolFuture<'a> = Pin<Box<dyn Future<Output = Result<ToolOutput>> + Send + 'a>>;
+    /// A resolved path as results show it; see [`Workspace::display`].
+    #[must_use]
+    pub fn show(&self, path: &Path) -> String {
+        self.workspace.display(path)
+    }
 
-async fn list_files_handler(arg: &str) -> Result<ToolOutput> {
-    if arg.contains('\n') {
-        anyhow::bail!("list_files: path argument must be on a single line (no newlines)");
+    /// Resolves `path` for a tool that deletes or moves files and checks it against the
+    /// sandbox, as [`sandbox::check`] does for the current directory, with the root the
+    /// path is in as that directory.
+    ///
+    /// # Errors
+    /// Returns an error if the path is outside the sandbox or in a protected directory.
+    pub fn sandboxed(&self, path: &str) -> Result<PathBuf> {
+        let (root, path) = self.workspace.locate(path);
+        sandbox::check_in(&root.path, path)
     }
-    let path = Path::new(arg);
+}
+
+async fn list_files_handler(cx: &ToolContext, arg: &str) -> Result<ToolOutput> {
+    let args = ListFilesArgs::parse("list_files", arg)?;
+    let path = cx.search_root(args.directory.as_deref())?;
     if !path.is_dir() {
-        anyhow::bail!("Not a directory: {arg}");
+        anyhow::bail!("Not a directory: {}", cx.show(&path));
     }
-    let mut entries = fs::read_dir(path).await?;
+    let mut entries = fs::read_dir(&path).await?;
     let mut names = Vec::new();
     while let Some(entry) = entries.next_entry().await? {
         if let Some(name) = entry.file_name().to_str() {
@@ -59,76 +317,530 @@ async fn list_files_handler(arg: &str) -> Result<ToolOutput> {
     }
     names.sort();
     let content = names.join("\n");
-    let status = format!("Listed {} files in {}", names.len(), arg);
+    let status = format!("Listed {} files in {}", names.len(), cx.show(&path));
+    Ok(ToolOutput::Text { content, status })
+}
+
+/// Paths `find_file` returns at most.
+const FIND_FILE_MAX_RESULTS: usize = 50;
+
+async fn find_file_handler(arg: &str) -> Result<ToolOutput> {
+    let query = FindFileArgs::parse("find_file", arg)?.name;
+    let index = tokio::task::spawn_blocking(index::current_or_build).await?;
+    let found = index.find(&query, FIND_FILE_MAX_RESULTS);
+    let status = format!("Found {} files matching {query}", found.len());
+    let content = if found.is_empty() {
+        format!("No indexed file matches {query}")
+    } else {
+        found.join("\n")
+    };
     Ok(ToolOutput::Text { content, status })
 }
 
-async fn read_file_handler(arg: &str) -> Result<ToolOutput> {
-    if arg.contains('\n') {
-        anyhow::bail!("read_file: path argument must be on a single line (no newlines)");
+/// Files larger than this are returned as an index of chunks instead of in full.
+const READ_FILE_MAX_BYTES: usize = 64 * 1024;
+/// Chunk size used for files whose language has no grammar.
+const READ_FILE_CHUNK_LINES: usize = 400;
+
+async fn read_file_handler(cx: &ToolContext, arg: &str) -> Result<ToolOutput> {
+    let arg = PathArgs::parse("read_file", arg)?.path;
+    // `path#symbol` selects one chunk of a large file
+    let (path, selector) = match arg.rsplit_once('#') {
+        Some((path, selector)) if !cx.resolve(&arg).is_ok_and(|file| file.exists()) => {
+            (path, Some(selector.trim()))
+        }
+        _ => (arg.as_str(), None),
+    };
+    confirm_sensitive("read_file", Path::new(path)).await?;
+    let file = cx.resolve(path)?;
+    let shown = cx.show(&file);
+    let path = shown.as_str();
+    if let Some(kind) = Kind::of(Path::new(path)) {
+        return read_document_handler(path, file, kind, selector).await;
+    }
+    let binary_file = file.clone();
+    if let Some(content) =
+        tokio::task::spawn_blocking(move || binary::describe_if_binary(&binary_file)).await??
+    {
+        let status = format!("{path} is a binary file, described it instead");
+        return Ok(ToolOutput::Text { content, status });
+    }
+    let bytes = fs::read(&file).await?;
+    filelock::record(&file, &bytes);
+    // Text in another encoding than UTF-8 is read with the odd character replaced
+    let content = match String::from_utf8(bytes) {
+        Ok(content) => content,
+        Err(e) => String::from_utf8_lossy(e.as_bytes()).into_owned(),
+    };
+
+    if let Some(selector) = selector {
+        let chunks = syntax::chunk_source(Path::new(path), &content, READ_FILE_CHUNK_LINES);
+        let chunk = chunks.iter().find(|c| c.name == selector).ok_or_else(|| {
+            let names: Vec<&str> = chunks.iter().map(|c| c.name.as_str()).collect();
+            anyhow!(
+                "No chunk named '{selector}' in {path}. Available: {}",
+                names.join(", ")
+            )
+        })?;
+        let status = format!(
+            "Read {} of {path} (lines {}-{})",
+            chunk.name, chunk.start_line, chunk.end_line
+        );
+        let content = syntax::chunk_text(&content, chunk);
+        return Ok(ToolOutput::Text { content, status });
+    }
+
+    if content.len() <= READ_FILE_MAX_BYTES {
+        let status = format!("Read file at {path}");
+        return Ok(ToolOutput::Text { content, status });
+    }
+
+    let chunks = syntax::chunk_source(Path::new(path), &content, READ_FILE_CHUNK_LINES);
+    let mut index = format!(
+        "{path} is too large to read at once ({} bytes). It has been split into the chunks below; read one with `read_file {path}#<name>`.\n",
+        content.len()
+    );
+    for chunk in &chunks {
+        index.push_str(&format!(
+            "\n{} ({}, lines {}-{})",
+            chunk.name, chunk.kind, chunk.start_line, chunk.end_line
+        ));
+    }
+    let status = format!(
+        "File at {path} is too large, returned an index of {} chunks",
+        chunks.len()
+    );
+    Ok(ToolOutput::Text {
+        content: index,
+        status,
+    })
+}
+
+/// Reads the text of a PDF or DOCX file, all of it up to the size limit or the pages or
+/// paragraphs `selector` picks, such as `3-5`.
+async fn read_document_handler(
+    path: &str,
+    file: PathBuf,
+    kind: Kind,
+    selector: Option<&str>,
+) -> Result<ToolOutput> {
+    let unit = kind.unit();
+    let (first, last) = match selector {
+        Some(selector) => documents::parse_range(selector).ok_or_else(|| {
+            anyhow!("Expected a {unit} or range of {unit}s after #, such as {path}#3 or {path}#3-5")
+        })?,
+        None => (1, usize::MAX),
+    };
+    let document = tokio::task::spawn_blocking(move || Document::read(&file)).await??;
+    if document.parts.is_empty() {
+        let status = format!("Found no text in {path}");
+        return Ok(ToolOutput::Text {
+            content: format!(
+                "{path} has no text that could be extracted (it may be scanned images)"
+            ),
+            status,
+        });
     }
-    let content = fs::read_to_string(arg).await?;
-    let status = format!("Read file at {arg}");
+    let (content, shown) = document.render(path, first, last, READ_FILE_MAX_BYTES)?;
+    let status = format!(
+        "Read {unit}s {first}-{shown} of {} in {path}",
+        document.parts.len()
+    );
     Ok(ToolOutput::Text { content, status })
 }
 
-async fn create_directory_handler(arg: &str) -> Result<ToolOutput> {
-    if arg.contains('\n') {
-        anyhow::bail!("create_directory: path argument must be on a single line (no newlines)");
+async fn outline_handler(cx: &ToolContext, arg: &str) -> Result<ToolOutput> {
+    let path = PathArgs::parse("outline", arg)?.path;
+    let path = path.as_str();
+    confirm_sensitive("outline", Path::new(path)).await?;
+    let file = cx.resolve(path)?;
+    let source = fs::read_to_string(&file).await?;
+    let shown = cx.show(&file);
+    let path = shown.as_str();
+    let symbols = syntax::outline(Path::new(path), &source).ok_or_else(|| {
+        anyhow!("No outline for {path}: supported languages are Rust, Python, JavaScript, TypeScript and Go")
+    })?;
+    let mut content = format!("{path} ({} lines)", source.lines().count());
+    for symbol in &symbols {
+        // `impl Foo` already says what it is
+        let label = if symbol.name.starts_with(&format!("{} ", symbol.kind)) {
+            symbol.name.clone()
+        } else {
+            format!("{} {}", symbol.kind, symbol.name)
+        };
+        content.push_str(&format!(
+            "\n{}{label} (lines {}-{})",
+            "  ".repeat(symbol.depth),
+            symbol.start_line,
+            symbol.end_line
+        ));
     }
-    fs::create_dir_all(arg).await?;
-    let status = format!("Directory created: {arg}");
+    let status = format!("Outlined {} symbols in {path}", symbols.len());
+    Ok(ToolOutput::Text { content, status })
+}
+
+async fn remember_handler(arg: &str) -> Result<ToolOutput> {
+    let mut memory = memory::Memory::load()?;
+    let fact = memory.add(arg, memory::Source::Model)?;
+    let status = format!("Remembered: {}", fact.text);
     Ok(ToolOutput::StatusOnly { status })
 }
 
-async fn apply_search_replace_handler(arg: &str) -> Result<ToolOutput> {
-    let mut lines = arg.lines();
-    let file_path = lines
-        .next()
-        .ok_or_else(|| anyhow!("Missing file path"))?
-        .to_string();
-    let block_text: String = lines.collect::<Vec<&str>>().join("\n");
+const GREP_MAX_MATCHES: usize = 200;
+const GREP_SKIPPED_DIRS: [&str; 2] = ["target", "node_modules"];
 
-    let mut blocks = Vec::new();
-    let mut remaining = block_text.as_str();
-    while let Some(search_start) = remaining.find("<<<<<<< SEARCH") {
-        let after_search = &remaining[search_start + 15..];
-        let search_end = after_search
-            .find("=======")
-            .ok_or_else(|| anyhow!("Missing ======="))?;
-        let search = after_search[..search_end].trim().to_string();
+async fn grep_handler(cx: &ToolContext, arg: &str) -> Result<ToolOutput> {
+    let GrepArgs {
+        text: pattern,
+        path,
+    } = GrepArgs::parse("grep", arg)?;
+    let root = cx.search_root(path.as_deref())?;
+    if !root.exists() {
+        anyhow::bail!("No such file or directory: {}", cx.show(&root));
+    }
+    // A sensitive file searched directly is asked about; in a directory it is skipped
+    if root.is_file() {
+        confirm_sensitive("grep", &root).await?;
+    }
+
+    let search_root = root.clone();
+    let workspace = cx.workspace.clone();
+    let (matches, skipped) = tokio::task::spawn_blocking(move || {
+        let mut matches = Vec::new();
+        let mut skipped = 0;
+        grep_path(
+            &pattern,
+            &search_root,
+            &workspace,
+            &mut matches,
+            &mut skipped,
+        );
+        (matches, skipped)
+    })
+    .await?;
+
+    let truncated = if matches.len() >= GREP_MAX_MATCHES {
+        " (truncated)"
+    } else {
+        ""
+    };
+    let mut status = format!(
+        "Found {} matches in {}{truncated}",
+        matches.len(),
+        cx.show(&root)
+    );
+    if skipped > 0 {
+        status.push_str(&format!(
+            ", skipped {skipped} sensitive file(s); read_file one to ask the user for it"
+        ));
+    }
+    let content = if matches.is_empty() {
+        "No matches found".to_string()
+    } else {
+        matches.join("\n")
+    };
+    Ok(ToolOutput::Text { content, status })
+}
+
+// Hidden entries, build output directories and sensitive files are skipped below the root;
+// matches are shown with their paths as the workspace shows them
+fn grep_path(
+    pattern: &str,
+    path: &Path,
+    workspace: &Workspace,
+    matches: &mut Vec<String>,
+    skipped: &mut usize,
+) {
+    if matches.len() >= GREP_MAX_MATCHES {
+        return;
+    }
+    if path.is_dir() {
+        let Ok(entries) = std::fs::read_dir(path) else {
+            return;
+        };
+        let mut children: Vec<PathBuf> = entries.filter_map(|e| e.ok().map(|e| e.path())).collect();
+        children.sort();
+        for child in children {
+            let name = child.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            if name.starts_with('.') || GREP_SKIPPED_DIRS.contains(&name) {
+                continue;
+            }
+            if child.is_file() && sensitive::guarded(&child).is_some() {
+                *skipped += 1;
+                continue;
+            }
+            grep_path(pattern, &child, workspace, matches, skipped);
+        }
+    } else if let Ok(content) = std::fs::read_to_string(path) {
+        for (i, line) in content.lines().enumerate() {
+            if line.contains(pattern) {
+                matches.push(format!(
+                    "{}:{}: {}",
+                    workspace.display(path),
+                    i + 1,
+                    line.trim()
+                ));
+                if matches.len() >= GREP_MAX_MATCHES {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+async fn create_directory_handler(cx: &ToolContext, arg: &str) -> Result<ToolOutput> {
+    let path = PathArgs::parse("create_directory", arg)?.path;
+    let directory = cx.resolve(&path)?;
+    fs::create_dir_all(&directory).await?;
+    let status = format!("Directory created: {}", cx.show(&directory));
+    Ok(ToolOutput::StatusOnly { status })
+}
 
-        let after_eq = &after_search[search_end + 7..];
-        let replace_end = after_eq
-            .find(">>>>>>> REPLACE")
-            .ok_or_else(|| anyhow!("Missing >>>>>>> REPLACE"))?;
-        let replace = after_eq[..replace_end].trim().to_string();
+async fn apply_search_replace_handler(cx: &ToolContext, arg: &str) -> Result<ToolOutput> {
+    let SearchReplaceArgs {
+        path: file_path,
+        blocks,
+    } = SearchReplaceArgs::parse("apply_search_replace", arg)?;
 
-        blocks.push((search, replace));
-        remaining = &after_eq[replace_end + 15..];
+    let file = cx.resolve(&file_path)?;
+    let _guard = filelock::lock(&file).await;
+    filelock::check_unchanged(&file)?;
+    let mut content = fs::read_to_string(&file).await?;
+    for block in &blocks {
+        if !content.contains(&block.search) {
+            anyhow::bail!("Search string not found in {file_path}: {:?}", block.search);
+        }
+        content = content.replace(&block.search, &block.replace);
     }
+    fs::write(&file, &content).await?;
+    filelock::record(&file, content.as_bytes());
+    let status = format!("Applied {} block(s) to {}", blocks.len(), cx.show(&file));
+    Ok(ToolOutput::StatusOnly { status })
+}
 
-    if blocks.is_empty() {
-        anyhow::bail!("No valid search/replace blocks found");
+/// Replaces lines `start` to `end` of `content`, counting from 1 and including both, with
+/// `replacement`. An `end` of `start - 1` inserts before line `start` without replacing
+/// anything, and an empty replacement deletes the lines. The file's line endings and final
+/// newline are kept.
+///
+/// # Errors
+/// Returns an error if the range is not within the file.
+pub fn replace_lines(content: &str, start: usize, end: usize, replacement: &str) -> Result<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    if start == 0 || end + 1 < start || end > lines.len() {
+        anyhow::bail!(
+            "Invalid line range {start}-{end}: the file has {} lines, numbered from 1",
+            lines.len()
+        );
+    }
+    let newline = if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut out: Vec<&str> = lines[..start - 1].to_vec();
+    if !replacement.is_empty() {
+        out.extend(replacement.lines());
     }
+    out.extend(&lines[end..]);
+    let mut result = out.join(newline);
+    if !result.is_empty() && (content.ends_with('\n') || content.is_empty()) {
+        result.push_str(newline);
+    }
+    Ok(result)
+}
+
+async fn edit_lines_handler(cx: &ToolContext, arg: &str) -> Result<ToolOutput> {
+    let EditLinesArgs {
+        path,
+        start,
+        end,
+        text: replacement,
+    } = EditLinesArgs::parse("edit_lines", arg)?;
+    let file = cx.resolve(&path)?;
+    let _guard = filelock::lock(&file).await;
+    filelock::check_unchanged(&file)?;
+    let content = fs::read_to_string(&file).await?;
+    let path = cx.show(&file);
+    let edited = replace_lines(&content, start, end, &replacement)?;
+    fs::write(&file, &edited).await?;
+    filelock::record(&file, edited.as_bytes());
+    let count = replacement.lines().count();
+    let status = if end < start {
+        format!("Inserted {count} line(s) before line {start} of {path}")
+    } else {
+        format!("Replaced lines {start}-{end} of {path} with {count} line(s)")
+    };
+    Ok(ToolOutput::StatusOnly { status })
+}
 
-    let mut content = fs::read_to_string(&file_path).await?;
-    for (search, replace) in &blocks {
-        if !content.contains(search) {
-            anyhow::bail!("Search string not found in {file_path}: {search:?}");
+async fn apply_patch_handler(cx: &ToolContext, arg: &str) -> Result<ToolOutput> {
+    let patches = patch::parse(arg)?;
+    let mut paths = Vec::new();
+    for file in &patches {
+        for path in [&file.old_path, &file.new_path].into_iter().flatten() {
+            paths.push(cx.sandboxed(path)?);
+        }
+    }
+    // Locked in a fixed order, so two patches of the same files cannot wait on each other
+    paths.sort();
+    paths.dedup();
+    let mut guards = Vec::with_capacity(paths.len());
+    for path in &paths {
+        guards.push(filelock::lock(path).await);
+        filelock::check_unchanged(path)?;
+    }
+    // Every file is patched in memory first, so a hunk that fails changes nothing
+    let mut writes = Vec::new();
+    let mut removals = Vec::new();
+    for file in &patches {
+        let old = file
+            .old_path
+            .as_deref()
+            .map(|path| cx.sandboxed(path))
+            .transpose()?;
+        let new = file
+            .new_path
+            .as_deref()
+            .map(|path| cx.sandboxed(path))
+            .transpose()?;
+        let content = match &old {
+            Some(path) => fs::read_to_string(path)
+                .await
+                .map_err(|e| anyhow!("Cannot read {}: {e}", file.path()))?,
+            None if new.as_ref().is_some_and(|path| path.exists()) => {
+                anyhow::bail!("{} already exists", file.path());
+            }
+            None => String::new(),
+        };
+        let patched =
+            patch::apply(&content, &file.hunks).map_err(|e| anyhow!("{}: {e}", file.path()))?;
+        match (old, new) {
+            (old, Some(new)) => {
+                if let Some(old) = old.filter(|old| *old != new) {
+                    removals.push(old);
+                }
+                writes.push((new, patched));
+            }
+            (Some(old), None) => removals.push(old),
+            (None, None) => anyhow::bail!("A file's headers are both /dev/null"),
+        }
+    }
+    for (path, content) in &writes {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
         }
-        content = content.replace(search, replace);
+        fs::write(path, content).await?;
+        filelock::record(path, content.as_bytes());
+    }
+    for path in &removals {
+        fs::remove_file(path).await?;
+        filelock::forget(path);
     }
-    fs::write(&file_path, &content).await?;
-    let status = format!("Applied {} block(s) to {}", blocks.len(), file_path);
+    let names: Vec<&str> = patches.iter().map(patch::FilePatch::path).collect();
+    let status = format!("Patched {} file(s): {}", patches.len(), names.join(", "));
     Ok(ToolOutput::StatusOnly { status })
 }
 
-async fn run_command_handler(arg: &str) -> Result<ToolOutput> {
-    #[cfg(windows)]
-    let output = Command::new("cmd").args(&["/c", arg]).output().await?;
-    #[cfg(not(windows))]
-    let output = Command::new("sh").args(["-c", arg]).output().await?;
+/// The shell `run_command` hands its argument to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Shell {
+    /// A POSIX-style shell invoked as `<program> -c <script>`.
+    Posix(String),
+    /// Windows PowerShell or PowerShell Core.
+    PowerShell(String),
+    /// The classic Windows `cmd.exe`.
+    Cmd,
+}
+
+impl Shell {
+    fn from_program(program: &str) -> Self {
+        let name = Path::new(program)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or(program)
+            .to_ascii_lowercase();
+        match name.as_str() {
+            "pwsh" | "powershell" => Self::PowerShell(program.to_string()),
+            "cmd" => Self::Cmd,
+            _ => Self::Posix(program.to_string()),
+        }
+    }
+
+    /// Uses the configured shell if any; otherwise prefers PowerShell over `cmd` on Windows
+    /// and falls back to `sh` everywhere else.
+    fn detect() -> Self {
+        if let Some(program) = &config::get().shell {
+            return Self::from_program(program);
+        }
+        if cfg!(windows) {
+            ["pwsh", "powershell"]
+                .into_iter()
+                .find(|p| find_in_path(p))
+                .map_or(Self::Cmd, Self::from_program)
+        } else {
+            Self::Posix("sh".to_string())
+        }
+    }
+
+    fn command(&self, script: &str) -> Command {
+        match self {
+            Self::Posix(program) => {
+                let mut command = Command::new(program);
+                command.args(["-c", script]);
+                command
+            }
+            Self::PowerShell(program) => {
+                let mut command = Command::new(program);
+                command.args(["-NoProfile", "-NonInteractive", "-Command", script]);
+                command
+            }
+            Self::Cmd => {
+                let mut command = Command::new("cmd");
+                command.arg("/C");
+                // cmd.exe does its own parsing, so the script must reach it unescaped
+                #[cfg(windows)]
+                command.raw_arg(script);
+                #[cfg(not(windows))]
+                command.arg(script);
+                command
+            }
+        }
+    }
+
+    fn prompt_guidance(&self) -> String {
+        match self {
+            Self::Posix(program) => format!(
+                "run_command executes commands with `{program} -c`. Use POSIX shell syntax and quoting (single quotes for literal strings)."
+            ),
+            Self::PowerShell(program) => format!(
+                "run_command executes commands with PowerShell (`{program}`). Use PowerShell syntax: cmdlets such as Get-ChildItem, `;` to chain commands, single quotes for literal strings, double quotes for interpolated strings and the backtick (`) as the escape character."
+            ),
+            Self::Cmd => "run_command executes commands with cmd.exe (`cmd /C`). Use cmd syntax: double quotes around arguments containing spaces, `&&` to chain commands and `^` to escape special characters.".to_string(),
+        }
+    }
+}
+
+fn find_in_path(program: &str) -> bool {
+    let file_name = format!("{program}{}", std::env::consts::EXE_SUFFIX);
+    std::env::var_os("PATH").is_some_and(|paths| {
+        std::env::split_paths(&paths).any(|dir| dir.join(&file_name).is_file())
+    })
+}
+
+static SHELL: LazyLock<Shell> = LazyLock::new(Shell::detect);
+
+/// A command that runs `script` in the shell `run_command` uses.
+#[must_use]
+pub fn shell_command(script: &str) -> Command {
+    SHELL.command(script)
+}
+
+async fn run_command_handler(cx: &ToolContext, arg: &str) -> Result<ToolOutput> {
+    let CommandArgs { command: script } = CommandArgs::parse("run_command", arg)?;
+    let mut command = SHELL.command(&script);
+    command.current_dir(cx.root());
+    let output = command.output().await?;
     let stdout = String::from_utf8_lossy(&output.stdout);
     let stderr = String::from_utf8_lossy(&output.stderr);
     let exit_code = output.status.code().unwrap_or(-1);
@@ -158,128 +870,722 @@ async fn run_command_handler(arg: &str) -> Result<ToolOutput> {
     })
 }
 
-async fn write_file_handler(arg: &str) -> Result<ToolOutput> {
-    let mut lines = arg.lines();
-    let file_path = lines
-        .next()
-        .ok_or_else(|| anyhow!("Missing file path"))?
-        .to_string();
-    let content: String = lines.collect::<Vec<&str>>().join("\n");
+async fn write_file_handler(cx: &ToolContext, arg: &str) -> Result<ToolOutput> {
+    let WriteFileArgs {
+        path: file_path,
+        content,
+    } = WriteFileArgs::parse("write_file", arg)?;
 
-    if let Some(parent) = Path::new(&file_path).parent() {
+    let file = cx.resolve(&file_path)?;
+    if let Some(parent) = file.parent() {
         fs::create_dir_all(parent).await?;
     }
 
-    fs::write(&file_path, &content).await?;
-    let status = format!("File written: {file_path}");
+    let _guard = filelock::lock(&file).await;
+    filelock::check_unchanged(&file)?;
+    fs::write(&file, &content).await?;
+    filelock::record(&file, content.as_bytes());
+    let status = format!("File written: {}", cx.show(&file));
     Ok(ToolOutput::StatusOnly { status })
 }
 
-async fn fetch_url_handler(arg: &str) -> Result<ToolOutput> {
-    let url = arg.trim();
-    if url.is_empty() {
-        anyhow::bail!("URL cannot be empty");
+async fn delete_file_handler(cx: &ToolContext, arg: &str) -> Result<ToolOutput> {
+    let arg = PathArgs::parse("delete_file", arg)?.path;
+    let path = cx.sandboxed(&arg)?;
+    let metadata = fs::symlink_metadata(&path)
+        .await
+        .map_err(|e| anyhow!("Cannot delete {arg}: {e}"))?;
+    if metadata.is_dir() {
+        anyhow::bail!("{arg} is a directory; delete_file only deletes files");
+    }
+    let _guard = filelock::lock(&path).await;
+    filelock::check_unchanged(&path)?;
+    fs::remove_file(&path).await?;
+    filelock::forget(&path);
+    let status = format!("File deleted: {}", cx.show(&path));
+    Ok(ToolOutput::StatusOnly { status })
+}
+
+async fn move_file_handler(cx: &ToolContext, arg: &str) -> Result<ToolOutput> {
+    let MoveFileArgs { from, to } = MoveFileArgs::parse("move_file", arg)?;
+    let source = cx.sandboxed(&from)?;
+    let destination = cx.sandboxed(&to)?;
+    let _guard = filelock::lock(&source).await;
+    filelock::check_unchanged(&source)?;
+    if fs::symlink_metadata(&source).await.is_err() {
+        anyhow::bail!("{from} does not exist");
+    }
+    if fs::symlink_metadata(&destination).await.is_ok() {
+        anyhow::bail!("{to} already exists; delete it first to replace it");
+    }
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    fs::rename(&source, &destination).await?;
+    filelock::forget(&source);
+    let status = format!("Moved {} to {}", cx.show(&source), cx.show(&destination));
+    Ok(ToolOutput::StatusOnly { status })
+}
+
+/// How long fetch_url waits for a response, including the body.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+/// Redirects fetch_url follows before giving up.
+const FETCH_MAX_REDIRECTS: usize = 5;
+/// Bytes of a response body fetch_url reads; the rest is cut off with a notice.
+const FETCH_MAX_BYTES: usize = 2 * 1024 * 1024;
+
+/// Returns the cached result of a network tool, or runs `fetch` and caches what it
+/// returns.
+async fn cached(
+    kind: &str,
+    key: &str,
+    fetch: impl Future<Output = Result<ToolOutput>>,
+) -> Result<ToolOutput> {
+    static CACHE: LazyLock<Option<cache::Cache>> =
+        LazyLock::new(|| cache::Cache::from_config(&config::get().cache));
+    let Some(cache) = CACHE.as_ref() else {
+        return fetch.await;
+    };
+    if let Some(entry) = cache.get(kind, key) {
+        return Ok(ToolOutput::Text {
+            content: entry.content,
+            status: format!("{} (cached)", entry.status),
+        });
     }
-    let response = reqwest::get(url).await?;
+    let output = fetch.await?;
+    if let ToolOutput::Text { content, status } = &output {
+        let entry = cache::Entry {
+            content: content.clone(),
+            status: status.clone(),
+        };
+        let _ = cache.put(kind, key, &entry);
+    }
+    Ok(output)
+}
+
+async fn fetch_url_handler(arg: &str) -> Result<ToolOutput> {
+    let FetchUrlArgs { url, format } = FetchUrlArgs::parse("fetch_url", arg)?;
+    let raw = format == Format::Raw;
+    let key = if raw {
+        format!("{url} raw")
+    } else {
+        url.clone()
+    };
+    cached("fetch", &key, fetch_url(&url, raw)).await
+}
+
+async fn fetch_url(url: &str, raw: bool) -> Result<ToolOutput> {
+    let client = http::client_builder()?
+        .timeout(FETCH_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::limited(FETCH_MAX_REDIRECTS))
+        .build()
+        .map_err(|e| anyhow!("Failed to create HTTP client: {e}"))?;
+    let mut response = client.get(url).send().await.map_err(|e| {
+        if e.is_timeout() {
+            anyhow!(
+                "Timed out after {}s fetching {url}",
+                FETCH_TIMEOUT.as_secs()
+            )
+        } else if e.is_redirect() {
+            anyhow!("Too many redirects (more than {FETCH_MAX_REDIRECTS}) fetching {url}")
+        } else {
+            anyhow!("Failed to fetch {url}: {e}")
+        }
+    })?;
     let status_code = response.status();
     if !status_code.is_success() {
         anyhow::bail!("HTTP error {status_code}: {url}");
     }
-    let content = response.text().await?;
-    let size = content.len();
-    let status = format!("Fetched URL: {url} ({size} bytes)");
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_lowercase();
+    let length = response.content_length();
+    if !content_type.is_empty() && !is_text_content_type(&content_type) {
+        let size = length.map_or_else(|| "unknown size".to_string(), |n| format!("{n} bytes"));
+        let content = format!(
+            "{url} is binary content ({content_type}, {size}), which cannot be shown as text."
+        );
+        let status = format!("Fetched URL: {url} ({content_type}, {size}, not shown)");
+        return Ok(ToolOutput::Text { content, status });
+    }
+    let final_url = response.url().to_string();
+    let mut bytes = Vec::new();
+    let mut truncated = false;
+    loop {
+        let chunk = match timeout(FETCH_TIMEOUT, response.chunk()).await {
+            Ok(chunk) => chunk.map_err(|e| anyhow!("Failed to read the body of {url}: {e}"))?,
+            Err(_) => anyhow::bail!("Timed out reading the body of {url}"),
+        };
+        let Some(chunk) = chunk else {
+            break;
+        };
+        let room = FETCH_MAX_BYTES - bytes.len();
+        if chunk.len() > room {
+            bytes.extend_from_slice(&chunk[..room]);
+            truncated = true;
+            break;
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    let body = String::from_utf8_lossy(&bytes).into_owned();
+    let size = bytes.len();
+    let mut content = if raw {
+        body
+    } else if content_type.contains("html") || (content_type.is_empty() && looks_like_html(&body)) {
+        html_to_markdown(&body, Some(&final_url))
+    } else if content_type.contains("json") && !truncated {
+        pretty_json(&body).unwrap_or(body)
+    } else {
+        body
+    };
+    let mut status = format!("Fetched URL: {url} ({size} bytes)");
+    if truncated {
+        let total = length.map_or_else(String::new, |n| format!(" of {n}"));
+        content.push_str(&format!(
+            "\n\n[Truncated: only the first {FETCH_MAX_BYTES}{total} bytes were read]"
+        ));
+        status = format!("Fetched URL: {url} (first {size}{total} bytes)");
+    }
     Ok(ToolOutput::Text { content, status })
 }
 
+/// Whether a `Content-Type` is text that fetch_url can return, rather than an image,
+/// archive or other binary data.
+#[must_use]
+pub fn is_text_content_type(content_type: &str) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+    mime.starts_with("text/")
+        || mime.ends_with("json")
+        || mime.ends_with("xml")
+        || mime.ends_with("javascript")
+        || mime.ends_with("ecmascript")
+        || matches!(
+            mime.as_str(),
+            "application/x-yaml" | "application/yaml" | "application/toml" | "application/x-sh"
+        )
+}
+
+/// Re-indents a JSON document for reading, or returns `None` if it is not valid JSON.
+#[must_use]
+pub fn pretty_json(body: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(body).ok()?;
+    serde_json::to_string_pretty(&value).ok()
+}
+
+fn looks_like_html(body: &str) -> bool {
+    let start = body
+        .trim_start()
+        .get(..15)
+        .unwrap_or_default()
+        .to_lowercase();
+    start.starts_with("<!doctype html") || start.starts_with("<html")
+}
+
+/// Elements that never hold a page's text.
+const NON_CONTENT_TAGS: [&str; 14] = [
+    "script", "style", "noscript", "template", "svg", "canvas", "iframe", "form", "button",
+    "select", "input", "textarea", "head", "dialog",
+];
+
+/// Page chrome, left out when the page does not mark up its main content.
+const CHROME_TAGS: [&str; 4] = ["nav", "header", "footer", "aside"];
+const CHROME_ROLES: [&str; 5] = [
+    "navigation",
+    "banner",
+    "contentinfo",
+    "complementary",
+    "search",
+];
+
+const BLOCK_TAGS: [&str; 35] = [
+    "address",
+    "article",
+    "blockquote",
+    "body",
+    "dd",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "li",
+    "main",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "summary",
+    "table",
+    "ul",
+    "nav",
+    "header",
+    "footer",
+    "aside",
+    "tr",
+    "td",
+    "th",
+    "br",
+];
+
+/// Turns a web page into markdown for reading: the main content (`<main>`, `<article>` or
+/// else the body) with scripts, styles, forms and navigation left out, and headings,
+/// paragraphs, lists, links, code, quotes and tables kept. Relative links are resolved
+/// against `base_url`.
+#[must_use]
+pub fn html_to_markdown(html: &str, base_url: Option<&str>) -> String {
+    let document = Html::parse_document(html);
+    let select_first = |selector: &str| {
+        Selector::parse(selector)
+            .ok()
+            .and_then(|selector| document.select(&selector).next())
+    };
+    let main = select_first("main")
+        .or_else(|| select_first("[role=main]"))
+        .or_else(|| select_first("article"));
+    let extractor = Extractor {
+        base: base_url.and_then(|url| reqwest::Url::parse(url).ok()),
+        skip_chrome: main.is_none(),
+    };
+    let root = main
+        .or_else(|| select_first("body"))
+        .unwrap_or_else(|| document.root_element());
+    let mut blocks = Vec::new();
+    extractor.blocks(root, &mut blocks);
+    let title = select_first("title")
+        .map(|title| collapse_whitespace(&title.text().collect::<String>()))
+        .filter(|title| !title.is_empty());
+    if let Some(title) = title
+        && !blocks.first().is_some_and(|first| first.contains(&title))
+    {
+        blocks.insert(0, format!("# {title}"));
+    }
+    blocks.join("\n\n")
+}
+
+struct Extractor {
+    base: Option<reqwest::Url>,
+    /// Whether to leave out navigation, headers and footers
+    skip_chrome: bool,
+}
+
+impl Extractor {
+    fn is_skipped(&self, element: ElementRef<'_>) -> bool {
+        let value = element.value();
+        if NON_CONTENT_TAGS.contains(&value.name())
+            || value.attr("hidden").is_some()
+            || value.attr("aria-hidden") == Some("true")
+        {
+            return true;
+        }
+        self.skip_chrome
+            && (CHROME_TAGS.contains(&value.name())
+                || value
+                    .attr("role")
+                    .is_some_and(|role| CHROME_ROLES.contains(&role)))
+    }
+
+    /// Renders the children of `element` as markdown blocks, with runs of text and inline
+    /// elements between them as paragraphs.
+    fn blocks(&self, element: ElementRef<'_>, blocks: &mut Vec<String>) {
+        let mut paragraph = String::new();
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => paragraph.push_str(text),
+                Node::Element(value) => {
+                    let Some(child) = ElementRef::wrap(child) else {
+                        continue;
+                    };
+                    if self.is_skipped(child) {
+                        continue;
+                    }
+                    if BLOCK_TAGS.contains(&value.name()) {
+                        push_paragraph(&mut paragraph, blocks);
+                        self.block(child, blocks);
+                    } else {
+                        paragraph.push_str(&self.inline(child));
+                    }
+                }
+                _ => {}
+            }
+        }
+        push_paragraph(&mut paragraph, blocks);
+    }
+
+    fn block(&self, element: ElementRef<'_>, blocks: &mut Vec<String>) {
+        let name = element.value().name();
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let text = collapse_whitespace(&self.inline_children(element));
+                if !text.is_empty() {
+                    let level = name[1..].parse().unwrap_or(1);
+                    blocks.push(format!("{} {text}", "#".repeat(level)));
+                }
+            }
+            "pre" => {
+                let code: String = element.text().collect();
+                let code = code.trim_matches('\n');
+                if !code.trim().is_empty() {
+                    blocks.push(format!("```\n{code}\n```"));
+                }
+            }
+            "hr" => blocks.push("---".to_string()),
+            "br" => {}
+            "ul" | "ol" => {
+                let mut items = Vec::new();
+                let children = element.children().filter_map(ElementRef::wrap);
+                for item in children.filter(|c| c.value().name() == "li") {
+                    let marker = if name == "ol" {
+                        format!("{}. ", items.len() + 1)
+                    } else {
+                        "- ".to_string()
+                    };
+                    let mut item_blocks = Vec::new();
+                    self.blocks(item, &mut item_blocks);
+                    let indent = " ".repeat(marker.len());
+                    let text = item_blocks.join("\n").replace('\n', &format!("\n{indent}"));
+                    items.push(format!("{marker}{text}"));
+                }
+                if !items.is_empty() {
+                    blocks.push(items.join("\n"));
+                }
+            }
+            "blockquote" => {
+                let mut quoted = Vec::new();
+                self.blocks(element, &mut quoted);
+                if !quoted.is_empty() {
+                    let text = quoted.join("\n\n");
+                    blocks.push(
+                        text.lines()
+                            .map(|line| format!("> {line}").trim_end().to_string())
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                    );
+                }
+            }
+            "table" => {
+                if let Some(table) = self.table(element) {
+                    blocks.push(table);
+                }
+            }
+            _ => self.blocks(element, blocks),
+        }
+    }
+
+    fn table(&self, table: ElementRef<'_>) -> Option<String> {
+        let row_selector = Selector::parse("tr").ok()?;
+        let rows: Vec<Vec<String>> = table
+            .select(&row_selector)
+            .map(|row| {
+                row.children()
+                    .filter_map(ElementRef::wrap)
+                    .filter(|cell| matches!(cell.value().name(), "td" | "th"))
+                    .map(|cell| {
+                        collapse_whitespace(&self.inline_children(cell)).replace('|', "\\|")
+                    })
+                    .collect()
+            })
+            .filter(|cells: &Vec<String>| !cells.is_empty())
+            .collect();
+        let columns = rows.iter().map(Vec::len).max()?;
+        let mut lines = Vec::new();
+        for (n, row) in rows.iter().enumerate() {
+            let mut cells = row.clone();
+            cells.resize(columns, String::new());
+            lines.push(format!("| {} |", cells.join(" | ")));
+            if n == 0 {
+                lines.push(format!("|{}", " --- |".repeat(columns)));
+            }
+        }
+        Some(lines.join("\n"))
+    }
+
+    fn inline_children(&self, element: ElementRef<'_>) -> String {
+        let mut text = String::new();
+        for child in element.children() {
+            match child.value() {
+                Node::Text(t) => text.push_str(t),
+                Node::Element(_) => {
+                    if let Some(child) = ElementRef::wrap(child)
+                        && !self.is_skipped(child)
+                    {
+                        text.push_str(&self.inline(child));
+                    }
+                }
+                _ => {}
+            }
+        }
+        text
+    }
+
+    fn inline(&self, element: ElementRef<'_>) -> String {
+        let value = element.value();
+        match value.name() {
+            "br" => " ".to_string(),
+            "code" | "kbd" | "samp" => {
+                let code = collapse_whitespace(&element.text().collect::<String>());
+                if code.is_empty() {
+                    String::new()
+                } else {
+                    format!("`{code}`")
+                }
+            }
+            "a" => {
+                let text = collapse_whitespace(&self.inline_children(element));
+                let url = value
+                    .attr("href")
+                    .filter(|href| !href.starts_with('#') && !href.starts_with("javascript:"))
+                    .and_then(|href| match &self.base {
+                        Some(base) => base.join(href).ok().map(String::from),
+                        None => Some(href.to_string()),
+                    });
+                match url {
+                    Some(url) if !text.is_empty() => format!("[{text}]({url})"),
+                    _ => text,
+                }
+            }
+            "img" => match value.attr("alt").map(str::trim) {
+                Some(alt) if !alt.is_empty() => format!("[image: {alt}]"),
+                _ => String::new(),
+            },
+            name => {
+                let text = self.inline_children(element);
+                let marker = match name {
+                    "strong" | "b" => "**",
+                    "em" | "i" => "*",
+                    _ => return text,
+                };
+                let trimmed = collapse_whitespace(&text);
+                if trimmed.is_empty() {
+                    return text;
+                }
+                // Keep the spaces around the text outside the markers
+                let before = if text.starts_with(char::is_whitespace) {
+                    " "
+                } else {
+                    ""
+                };
+                let after = if text.ends_with(char::is_whitespace) {
+                    " "
+                } else {
+                    ""
+                };
+                format!("{before}{marker}{trimmed}{marker}{after}")
+            }
+        }
+    }
+}
+
+fn push_paragraph(paragraph: &mut String, blocks: &mut Vec<String>) {
+    let text = collapse_whitespace(paragraph);
+    if !text.is_empty() {
+        blocks.push(text);
+    }
+    paragraph.clear();
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
 async fn search_web_handler(arg: &str) -> Result<ToolOutput> {
     let query = arg.trim();
     if query.is_empty() {
         anyhow::bail!("Search query cannot be empty");
     }
-    let encoded = encode(query);
-    let url = format!("https://html.duckduckgo.com/html/?q={encoded}");
+    cached("search", query, search_web(query)).await
+}
 
-    let client = reqwest::Client::builder()
-        .build()
-        .map_err(|e| anyhow!("Failed to create HTTP client: {e}"))?;
+async fn search_web(query: &str) -> Result<ToolOutput> {
+    let providers = search::providers(&config::get().search)?;
+    let (provider, results) = search::search(&providers, query).await?;
+    let content = if results.is_empty() {
+        "No results found for the query.".to_string()
+    } else {
+        search::format_results(&results)
+    };
+    let status = format!(
+        "Executed tool: search_web - found {} results via {provider}",
+        results.len()
+    );
+    Ok(ToolOutput::Text { content, status })
+}
 
-    let response = client
-        .get(&url)
-        .send()
-        .await
-        .map_err(|e| anyhow!("Network error while searching: {e}"))?;
-    let status_code = response.status();
-    let html = response
-        .text()
-        .await
-        .map_err(|e| anyhow!("Failed to read response body: {e}"))?;
+async fn gh_issue_view_handler(arg: &str) -> Result<ToolOutput> {
+    let issue = github::Reference::resolve(arg)?;
+    let content = github::Client::from_config()?.issue(&issue).await?;
+    let status = format!("Read issue {}/{}#{}", issue.owner, issue.repo, issue.number);
+    Ok(ToolOutput::Text { content, status })
+}
 
-    if !status_code.is_success() {
-        let lower = html.to_lowercase();
-        if lower.contains("anomaly-modal") {
-            anyhow::bail!("Search engine is blocking the request. Please try again later.");
-        }
-        anyhow::bail!("HTTP error {status_code} while searching");
-    }
-
-    let document = Html::parse_document(&html);
-    let result_selector =
-        Selector::parse("div.result").map_err(|e| anyhow!("Invalid result selector: {e}"))?;
-    let title_selector =
-        Selector::parse("a.result__a").map_err(|e| anyhow!("Invalid title selector: {e}"))?;
-    let url_selector =
-        Selector::parse("a.result__a").map_err(|e| anyhow!("Invalid URL selector: {e}"))?;
-    let snippet_selector = Selector::parse("a.result__snippet")
-        .map_err(|e| anyhow!("Invalid snippet selector: {e}"))?;
-
-    let base_url = reqwest::Url::parse(&url).map_err(|e| anyhow!("Invalid base URL: {e}"))?;
-    let mut results = Vec::new();
-    for result in document.select(&result_selector) {
-        let title_elem = result.select(&title_selector).next();
-        let url_elem = result.select(&url_selector).next();
-        let snippet_elem = result.select(&snippet_selector).next();
-
-        let title = title_elem
-            .map(|e| e.text().collect::<String>())
-            .unwrap_or_default();
-        let href = url_elem.and_then(|e| e.value().attr("href")).unwrap_or("");
-        let absolute_url = base_url
-            .join(href)
-            .ok()
-            .map(|u| u.to_string())
-            .unwrap_or_default();
-        let snippet = snippet_elem
-            .map(|e| e.text().collect::<String>())
-            .unwrap_or_default();
-
-        if !title.is_empty() && !absolute_url.is_empty() {
-            results.push(format!(
-                "Title: {}\nURL: {}\nSnippet: {}\n---",
-                title.trim(),
-                absolute_url,
-                snippet.trim()
-            ));
-        }
+async fn gh_pr_view_handler(arg: &str) -> Result<ToolOutput> {
+    let pr = github::Reference::resolve(arg)?;
+    let content = github::Client::from_config()?.pull_request(&pr).await?;
+    let status = format!("Read pull request {}/{}#{}", pr.owner, pr.repo, pr.number);
+    Ok(ToolOutput::Text { content, status })
+}
+
+async fn gh_pr_comment_handler(arg: &str) -> Result<ToolOutput> {
+    let args = CommentArgs::parse("gh_pr_comment", arg)?;
+    let pr = github::Reference::resolve(&args.pull_request)?;
+    let line = args
+        .location
+        .as_ref()
+        .map(|location| (location.path.as_str(), location.line));
+    let url = github::Client::from_config()?
+        .comment(&pr, line, &args.text)
+        .await?;
+    let on = line.map_or_else(String::new, |(path, line)| format!(" on {path}:{line}"));
+    let status = format!(
+        "Commented on {}/{}#{}{on}: {url}",
+        pr.owner, pr.repo, pr.number
+    );
+    Ok(ToolOutput::StatusOnly { status })
+}
+
+/// Whether tools may prompt on the terminal. Interfaces that draw the screen themselves
+/// turn this off.
+static INTERACTIVE: AtomicBool = AtomicBool::new(true);
+
+/// Allows or forbids tools that read answers from the terminal, such as `ask_user`.
+pub fn set_interactive(interactive: bool) {
+    INTERACTIVE.store(interactive, Ordering::Relaxed);
+}
+
+/// Whether tools may read answers from the terminal.
+#[must_use]
+pub fn is_interactive() -> bool {
+    INTERACTIVE.load(Ordering::Relaxed)
+}
+
+async fn ask_user_handler(arg: &str) -> Result<ToolOutput> {
+    let AskUserArgs { question, options } = AskUserArgs::parse("ask_user", arg)?;
+    if !INTERACTIVE.load(Ordering::Relaxed) {
+        anyhow::bail!("ask_user is not available in this interface; ask in your reply instead");
     }
 
-    let content = if results.is_empty() {
-        if html.contains("No results") || html.contains("no results found") {
-            "No results found for the query.".to_string()
+    notify::current().waiting(&question);
+    let choices = options.clone();
+    let answer = tokio::task::spawn_blocking(move || {
+        spinner::suspend(|| prompt_user_choice(&question, &choices))
+    })
+    .await
+    .map_err(|e| anyhow!("Failed to read user answer: {e}"))??;
+    let status = format!("User answered: {answer}");
+    Ok(ToolOutput::StatusOnly { status })
+}
+
+/// Shows a question with numbered options on the terminal and blocks until the user
+/// picks one. Typing something other than a valid number is accepted as a free-form answer.
+fn prompt_user_choice(question: &str, options: &[String]) -> Result<String> {
+    use std::io::{BufRead, Write};
+
+    println!("{} {}", "?".magenta().bold(), question.bold());
+    for (i, option) in options.iter().enumerate() {
+        println!("  {}. {}", (i + 1).to_string().cyan(), option);
+    }
+    let stdin = std::io::stdin();
+    loop {
+        if options.is_empty() {
+            print!("Answer: ");
         } else {
-            "No results could be extracted from the search page. The page structure may have changed.".to_string()
+            print!("Choose 1-{} (or type your own answer): ", options.len());
         }
-    } else {
-        results.join("\n")
+        std::io::stdout().flush()?;
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            anyhow::bail!("No answer provided (end of input)");
+        }
+        let input = line.trim();
+        if input.is_empty() {
+            continue;
+        }
+        if let Ok(n) = input.parse::<usize>()
+            && !options.is_empty()
+        {
+            match n.checked_sub(1).and_then(|i| options.get(i)) {
+                Some(option) => return Ok(option.clone()),
+                None => {
+                    println!("{}", "Invalid choice".red());
+                    continue;
+                }
+            }
+        }
+        return Ok(input.to_string());
+    }
+}
+
+/// Asks the user before a read tool shows the model a file that looks like it holds
+/// secrets (see [`sensitive::DEFAULT_PATTERNS`]). Without a terminal to ask on, such
+/// files are refused.
+async fn confirm_sensitive(tool: &str, path: &Path) -> Result<()> {
+    let Some(pattern) = sensitive::guarded(path) else {
+        return Ok(());
     };
-    let status = if results.is_empty() {
-        "Executed tool: search_web - found 0 results".to_string()
-    } else {
-        format!(
-            "Executed tool: search_web - found {} results",
-            results.len()
+    let refused = || {
+        anyhow!(
+            "{tool} refused: {} looks like a sensitive file (matches {pattern}) and the user has not allowed it to be shared",
+            path.display()
         )
     };
-    Ok(ToolOutput::Text { content, status })
+    if !INTERACTIVE.load(Ordering::Relaxed) {
+        return Err(refused());
+    }
+    notify::current().waiting(&format!("share {}?", path.display()));
+    let file = path.to_path_buf();
+    let allowed = tokio::task::spawn_blocking(move || {
+        spinner::suspend(|| confirm_sensitive_read(&file, pattern))
+    })
+    .await
+    .map_err(|e| anyhow!("Failed to read user answer: {e}"))??;
+    if allowed { Ok(()) } else { Err(refused()) }
+}
+
+// Parallel reads ask one at a time
+static SENSITIVE_PROMPT: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+fn confirm_sensitive_read(path: &Path, pattern: &str) -> Result<bool> {
+    use std::io::{BufRead, Write};
+
+    let _prompt = SENSITIVE_PROMPT
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    // Another call may have asked about the same file while this one waited
+    if sensitive::is_allowed(path) {
+        return Ok(true);
+    }
+    print!(
+        "{} {} [y/N] ",
+        "?".magenta().bold(),
+        format!(
+            "The agent wants to read {}, which looks like a sensitive file (matches {pattern}). Share it?",
+            path.display()
+        )
+        .bold()
+    );
+    std::io::stdout().flush()?;
+    let mut line = String::new();
+    std::io::stdin().lock().read_line(&mut line)?;
+    let allowed = matches!(line.trim().to_ascii_lowercase().as_str(), "y" | "yes");
+    if allowed {
+        sensitive::allow(path);
+    }
+    Ok(allowed)
 }
 
 // Browser automation state
@@ -385,25 +1691,17 @@ fn browser_click_handler(arg: &str) -> ToolFuture<'_> {
 
 fn browser_type_handler(arg: &str) -> ToolFuture<'_> {
     Box::pin(async move {
-        let mut parts = arg.splitn(2, ' ');
-        let selector = parts
-            .next()
-            .ok_or_else(|| anyhow!("Missing selector"))?
-            .trim();
-        let text = parts.next().ok_or_else(|| anyhow!("Missing text"))?.trim();
-        if selector.is_empty() || text.is_empty() {
-            return Err(anyhow!("Selector and text are required"));
-        }
+        let BrowserTypeArgs { selector, text } = BrowserTypeArgs::parse("browser_type", arg)?;
         let state_arc = ensure_browser_initialized().await?;
         let mut guard = state_arc.lock().await;
         let state = guard.as_mut().unwrap();
 
         let element = state
             .current_page()
-            .find_element(selector)
+            .find_element(selector.as_str())
             .await
             .map_err(|_| anyhow!("Element '{selector}' not found"))?;
-        element.type_str(text).await?;
+        element.type_str(&text).await?;
         let status = format!("Typed '{text}' into {selector}");
         Ok(ToolOutput::StatusOnly { status })
     })
@@ -490,25 +1788,19 @@ fn browser_new_tab_handler(arg: &str) -> ToolFuture<'_> {
 
 fn browser_close_tab_handler(arg: &str) -> ToolFuture<'_> {
     Box::pin(async move {
+        let index = TabArgs::parse("browser_close_tab", arg)?.index;
         let state_arc = ensure_browser_initialized().await?;
         let mut guard = state_arc.lock().await;
         let state = guard.as_mut().unwrap();
         if state.pages.len() <= 1 {
             return Err(anyhow!("Cannot close the last tab"));
         }
-        let idx = if arg.trim().is_empty() {
-            state.current_idx
-        } else {
-            let idx = arg
-                .trim()
-                .parse::<usize>()
-                .map_err(|_| anyhow!("Invalid tab index"))?
-                .checked_sub(1)
-                .ok_or_else(|| anyhow!("Tab index must be >= 1"))?;
-            if idx >= state.pages.len() {
+        let idx = match index {
+            None => state.current_idx,
+            Some(index) if index > state.pages.len() => {
                 return Err(anyhow!("Tab index out of range"));
             }
-            idx
+            Some(index) => index - 1,
         };
         state.pages.remove(idx);
         if state.current_idx >= idx {
@@ -525,12 +1817,10 @@ fn browser_close_tab_handler(arg: &str) -> ToolFuture<'_> {
 
 fn browser_switch_tab_handler(arg: &str) -> ToolFuture<'_> {
     Box::pin(async move {
-        let idx = arg
-            .trim()
-            .parse::<usize>()
-            .map_err(|_| anyhow!("Invalid tab index"))?
-            .checked_sub(1)
-            .ok_or_else(|| anyhow!("Tab index must be >= 1"))?;
+        let idx = TabArgs::parse("browser_switch_tab", arg)?
+            .index
+            .ok_or_else(|| anyhow!("Give the number of the tab to switch to"))?
+            - 1;
         let state_arc = ensure_browser_initialized().await?;
         let mut guard = state_arc.lock().await;
         let state = guard.as_mut().unwrap();
@@ -583,7 +1873,9 @@ fn browser_quit_handler(_arg: &str) -> ToolFuture<'_> {
 
 fn browser_wait_for_navigation_handler(arg: &str) -> ToolFuture<'_> {
     Box::pin(async move {
-        let timeout_secs = arg.trim().parse::<u64>().unwrap_or(30);
+        let timeout_secs = WaitArgs::parse("browser_wait_for_navigation", arg)?
+            .timeout
+            .unwrap_or(30);
         let state_arc = ensure_browser_initialized().await?;
         let mut guard = state_arc.lock().await;
         let state = guard.as_mut().unwrap();
@@ -623,190 +1915,572 @@ fn browser_screenshot_handler(_arg: &str) -> ToolFuture<'_> {
     })
 }
 
-static TOOLS: LazyLock<HashMap<&'static str, Tool>> = LazyLock::new(|| {
-    let mut m = HashMap::new();
-    m.insert(
+static REGISTRY: LazyLock<ToolRegistry> = LazyLock::new(ToolRegistry::with_builtins);
+
+/// The tools the agent uses: the built-in ones and the plugins, and any registered since.
+#[must_use]
+pub fn registry() -> &'static ToolRegistry {
+    &REGISTRY
+}
+
+fn register_builtins(registry: &ToolRegistry) {
+    registry.insert(
         "list_files",
-        Tool {
-            description: "list_files <directory> : lists all files and directories in the given directory (non‑recursive)",
-            handler: Box::new(|s| Box::pin(list_files_handler(s))),
-        },
+        Tool::new(
+            "list_files [directory] : lists all files and directories in the given directory (non‑recursive). Defaults to the focused directory, or the current directory.",
+            |cx, s| Box::pin(list_files_handler(cx, s)),
+        )
+        .read_only()
+        .params(&["directory"]),
+    );
+    registry.insert(
+        "find_file",
+        Tool::new(
+            "find_file <name> : finds files in the project by a fuzzy name or partial path (e.g. \"mainrs\" or \"src/tool\"), best matches first, skipping ignored files. Much faster than walking directories with list_files.",
+            |_, s| Box::pin(find_file_handler(s)),
+        )
+        .read_only()
+        .params(&["name"]),
     );
-    m.insert(
+    registry.insert(
         "read_file",
-        Tool {
-            description: "read_file <file_path> : outputs the text contents of a file",
-            handler: Box::new(|s| Box::pin(read_file_handler(s))),
-        },
+        Tool::new(
+            "read_file <file_path> : outputs the text contents of a file. Files too large to read at once return an index of chunks (functions, types, ...) instead; read a single chunk with read_file <file_path>#<chunk name>. The text of PDF and DOCX files is extracted, marked by page or paragraph; read some of them with read_file <file_path>#3 or #3-5.",
+            |cx, s| Box::pin(read_file_handler(cx, s)),
+        )
+        .read_only()
+        .params(&["path"]),
+    );
+    registry.insert(
+        "outline",
+        Tool::new(
+            "outline <file_path> : lists the functions, types, impl blocks, classes and methods of a source file with their line ranges, without the code. Use it to find your way around a large file, then read_file one chunk or grep for details.",
+            |cx, s| Box::pin(outline_handler(cx, s)),
+        )
+        .read_only()
+        .params(&["path"]),
     );
-    m.insert(
+    registry.insert(
+        "remember",
+        Tool::new(
+            "remember <fact> : saves a fact that is given to you in every future session, such as a lasting preference or project convention the user tells you about (\"we use pnpm, not npm\"). Only for things that stay true; not for the current task.",
+            |_, s| Box::pin(remember_handler(s)),
+        )
+        .params(&["fact"]),
+    );
+    registry.insert(
+        "grep",
+        Tool::new(
+            "grep <text> : searches files recursively for lines containing the text (case-sensitive, no regex) and returns them as path:line: content. An optional second line gives the file or directory to search; it defaults to the focused directory, or the current directory. Hidden files, target/ and node_modules/ are skipped.",
+            |cx, s| Box::pin(grep_handler(cx, s)),
+        )
+        .read_only()
+        .params(&["text", "path"]),
+    );
+    registry.insert(
         "create_directory",
-        Tool {
-            description: "create_directory <dir> : creates a directory (and any missing parents)",
-            handler: Box::new(|s| Box::pin(create_directory_handler(s))),
-        },
+        Tool::new(
+            "create_directory <dir> : creates a directory (and any missing parents)",
+            |cx, s| Box::pin(create_directory_handler(cx, s)),
+        )
+        .params(&["path"]),
     );
-    m.insert(
+    registry.insert(
         "apply_search_replace",
-        Tool {
-            description: "apply_search_replace <file_path> : applies one or more search/replace blocks to a file.\n  The blocks must be placed on the lines following the tool line, using the markers:\n      <<<<<<< SEARCH\n      (text to search for)\n      =======\n      (replacement text)\n      >>>>>>> REPLACE\n  Multiple blocks can be concatenated; each will be applied sequentially.\n  The search must match exactly, including whitespace and indentation.",
-            handler: Box::new(|s| Box::pin(apply_search_replace_handler(s))),
-        },
+        Tool::new(
+            "apply_search_replace <file_path> : applies one or more search/replace blocks to a file.\n  The blocks must be placed on the lines following the tool line, using the markers:\n      <<<<<<< SEARCH\n      (text to search for)\n      =======\n      (replacement text)\n      >>>>>>> REPLACE\n  Multiple blocks can be concatenated; each will be applied sequentially.\n  The search must match exactly, including whitespace and indentation.",
+            |cx, s| Box::pin(apply_search_replace_handler(cx, s)),
+        )
+        .params(&["path", "blocks"]),
+    );
+    registry.insert(
+        "apply_patch",
+        Tool::new(
+            "apply_patch : applies a unified diff, as written by diff -u or git diff, given on the lines after the tool line. It may change several files, create them (--- /dev/null) and delete them (+++ /dev/null). Hunks are found by their context lines, so line numbers may be approximate; nothing is changed unless every hunk applies.",
+            |cx, s| Box::pin(apply_patch_handler(cx, s)),
+        )
+        .params(&["patch"]),
+    );
+    registry.insert(
+        "edit_lines",
+        Tool::new(
+            "edit_lines <file_path> <start> <end> : replaces lines start to end of a file (counting from 1, both included) with the text on the following lines; no text deletes them, and an end of start - 1 inserts the text before line start. Line numbers are those grep and read_file report. Prefer apply_search_replace, and use this when its blocks fail to match.",
+            |cx, s| Box::pin(edit_lines_handler(cx, s)),
+        )
+        .params(&["path", "start", "end", "text"]),
     );
-    m.insert(
+    registry.insert(
         "run_command",
-        Tool {
-            description: "run_command <command_string> : runs a shell command using the system's default shell and returns its stdout/stderr. Use with caution.",
-            handler: Box::new(|s| Box::pin(run_command_handler(s))),
-        },
+        Tool::new(
+            "run_command <command_string> : runs a shell command (see the shell notes below) and returns its stdout/stderr. Use with caution.",
+            |cx, s| Box::pin(run_command_handler(cx, s)),
+        )
+        .params(&["command"]),
     );
-    m.insert(
+    registry.insert(
         "write_file",
-        Tool {
-            description: "write_file <file_path> : writes the provided content to the file, creating any necessary parent directories. If the file exists, it is overwritten. The content should follow the file path on subsequent lines.",
-            handler: Box::new(|s| Box::pin(write_file_handler(s))),
-        },
+        Tool::new(
+            "write_file <file_path> : writes the provided content to the file, creating any necessary parent directories. If the file exists, it is overwritten. The content should follow the file path on subsequent lines.",
+            |cx, s| Box::pin(write_file_handler(cx, s)),
+        )
+        .params(&["path", "content"]),
+    );
+    registry.insert(
+        "delete_file",
+        Tool::new(
+            "delete_file <file_path> : deletes a file inside the working directory. Use this rather than rm in run_command.",
+            |cx, s| Box::pin(delete_file_handler(cx, s)),
+        )
+        .params(&["path"]),
     );
-    m.insert(
+    registry.insert(
+        "move_file",
+        Tool::new(
+            "move_file <from> : moves or renames a file or directory inside the working directory to the path on the next line, creating missing parent directories. Fails if the destination exists. Use this rather than mv in run_command.",
+            |cx, s| Box::pin(move_file_handler(cx, s)),
+        )
+        .params(&["from", "to"]),
+    );
+    registry.insert(
         "search_web",
-        Tool {
-            description: "search_web <query> : performs a web search using DuckDuckGo and returns a list of results with titles, URLs, and snippets. DO NOT quote the query string.",
-            handler: Box::new(|s| Box::pin(search_web_handler(s))),
-        },
+        Tool::new(
+            "search_web <query> : performs a web search (with the providers set under [search] in the config, DuckDuckGo by default) and returns a list of results with titles, URLs, and snippets. DO NOT quote the query string.",
+            |_, s| Box::pin(search_web_handler(s)),
+        )
+        .read_only()
+        .params(&["query"]),
     );
-    m.insert(
+    registry.insert(
         "fetch_url",
-        Tool {
-            description: "fetch_url <url> : fetches the content from the given URL and returns it as text (HTML, JSON, etc.). Useful for browsing the internet for information.",
-            handler: Box::new(|s| Box::pin(fetch_url_handler(s))),
-        },
+        Tool::new(
+            "fetch_url <url> [raw] : fetches the content from the given URL. Web pages come back as markdown of their main content, without scripts, navigation and other boilerplate; add raw to get the HTML as it is. JSON is pretty-printed, other text is returned unchanged and binary content is only described. Large responses are cut off at 2 MB. Useful for browsing the internet for information.",
+            |_, s| Box::pin(fetch_url_handler(s)),
+        )
+        .read_only()
+        .params(&["url", "format"]),
+    );
+    registry.insert(
+        "gh_issue_view",
+        Tool::new(
+            "gh_issue_view <issue> : shows a GitHub issue with its labels, description and comments. Give the issue as owner/repo#12, its URL, or #12 for the repository the origin remote points at. Prefer this to fetch_url for GitHub issues.",
+            |_, s| Box::pin(gh_issue_view_handler(s)),
+        )
+        .read_only()
+        .params(&["issue"]),
+    );
+    registry.insert(
+        "gh_pr_view",
+        Tool::new(
+            "gh_pr_view <pull_request> : shows a GitHub pull request with its branches, description, changed files and comments, including those on lines of the diff. Give it as for gh_issue_view.",
+            |_, s| Box::pin(gh_pr_view_handler(s)),
+        )
+        .read_only()
+        .params(&["pull_request"]),
+    );
+    registry.insert(
+        "gh_pr_comment",
+        Tool::new(
+            "gh_pr_comment <pull_request> [<path>:<line>] : posts the text on the following lines as a comment on a GitHub pull request, given as for gh_issue_view. With a path and line, the comment goes on that line of the file in the pull request's latest commit, which must be part of the diff; without, on the conversation.",
+            |_, s| Box::pin(gh_pr_comment_handler(s)),
+        )
+        .params(&["pull_request", "location", "text"]),
     );
-    m.insert(
+    registry.insert(
+        "ask_user",
+        Tool::new(
+            "ask_user <question> : asks the user a question and waits for the answer. Put each possible answer on its own line after the tool line to present them as a numbered picker; the user may still reply with free text. Use this to resolve ambiguities instead of guessing.",
+            |_, s| Box::pin(ask_user_handler(s)),
+        )
+        .read_only()
+        .params(&["question", "options"]),
+    );
+    registry.insert(
         "browser_open",
-        Tool {
-            description: "browser_open <url> : Opens a URL in a visible Chrome/Chromium browser window.",
-            handler: Box::new(|s| Box::pin(browser_open_handler(s))),
-        },
+        Tool::new(
+            "browser_open <url> : Opens a URL in a visible Chrome/Chromium browser window.",
+            |_, s| Box::pin(browser_open_handler(s)),
+        )
+        .without_approval()
+        .params(&["url"]),
     );
-    m.insert(
+    registry.insert(
         "browser_click",
-        Tool {
-            description: "browser_click <selector> : Clicks an element matching the CSS selector.",
-            handler: Box::new(|s| Box::pin(browser_click_handler(s))),
-        },
+        Tool::new(
+            "browser_click <selector> : Clicks an element matching the CSS selector.",
+            |_, s| Box::pin(browser_click_handler(s)),
+        )
+        .without_approval()
+        .params(&["selector"]),
     );
-    m.insert(
+    registry.insert(
         "browser_type",
-        Tool {
-            description: "browser_type <selector> <text> : Types the specified text into an input field identified by the CSS selector.",
-            handler: Box::new(|s| Box::pin(browser_type_handler(s))),
-        },
+        Tool::new(
+            "browser_type <selector> <text> : Types the specified text into an input field identified by the CSS selector.",
+            |_, s| Box::pin(browser_type_handler(s)),
+        )
+        .without_approval()
+        .params(&["selector", "text"]),
     );
-    m.insert(
+    registry.insert(
         "browser_get_html",
-        Tool {
-            description: "browser_get_html : Returns the HTML content of the current page.",
-            handler: Box::new(|s| Box::pin(browser_get_html_handler(s))),
-        },
+        Tool::new(
+            "browser_get_html : Returns the HTML content of the current page.",
+            |_, s| Box::pin(browser_get_html_handler(s)),
+        )
+        .without_approval(),
     );
-    m.insert(
+    registry.insert(
         "browser_go_back",
-        Tool {
-            description: "browser_go_back : Navigates back in the browser history.",
-            handler: Box::new(|s| Box::pin(browser_go_back_handler(s))),
-        },
+        Tool::new(
+            "browser_go_back : Navigates back in the browser history.",
+            |_, s| Box::pin(browser_go_back_handler(s)),
+        )
+        .without_approval(),
     );
-    m.insert(
+    registry.insert(
         "browser_refresh",
-        Tool {
-            description: "browser_refresh : Reloads the current page.",
-            handler: Box::new(|s| Box::pin(browser_refresh_handler(s))),
-        },
+        Tool::new("browser_refresh : Reloads the current page.", |_, s| {
+            Box::pin(browser_refresh_handler(s))
+        })
+        .without_approval(),
     );
-    m.insert(
+    registry.insert(
         "browser_evaluate",
-        Tool {
-            description: "browser_evaluate <javascript> : Executes JavaScript code in the browser page and returns the result.",
-            handler: Box::new(|s| Box::pin(browser_evaluate_handler(s))),
-        },
+        Tool::new(
+            "browser_evaluate <javascript> : Executes JavaScript code in the browser page and returns the result.",
+            |_, s| Box::pin(browser_evaluate_handler(s)),
+        )
+        .without_approval()
+        .params(&["javascript"]),
     );
-    m.insert(
+    registry.insert(
         "browser_new_tab",
-        Tool {
-            description: "browser_new_tab [url] : Opens a new browser tab. If URL is provided, navigates to it; otherwise opens about:blank.",
-            handler: Box::new(|s| Box::pin(browser_new_tab_handler(s))),
-        },
+        Tool::new(
+            "browser_new_tab [url] : Opens a new browser tab. If URL is provided, navigates to it; otherwise opens about:blank.",
+            |_, s| Box::pin(browser_new_tab_handler(s)),
+        )
+        .without_approval()
+        .params(&["url"]),
     );
-    m.insert(
+    registry.insert(
         "browser_close_tab",
-        Tool {
-            description: "browser_close_tab [index] : Closes the specified tab (1-based). If no index provided, closes the current tab. Cannot close the last tab.",
-            handler: Box::new(|s| Box::pin(browser_close_tab_handler(s))),
-        },
+        Tool::new(
+            "browser_close_tab [index] : Closes the specified tab (1-based). If no index provided, closes the current tab. Cannot close the last tab.",
+            |_, s| Box::pin(browser_close_tab_handler(s)),
+        )
+        .without_approval()
+        .params(&["index"]),
     );
-    m.insert(
+    registry.insert(
         "browser_switch_tab",
-        Tool {
-            description: "browser_switch_tab <index> : Switches to the tab with the given 1-based index.",
-            handler: Box::new(|s| Box::pin(browser_switch_tab_handler(s))),
-        },
+        Tool::new(
+            "browser_switch_tab <index> : Switches to the tab with the given 1-based index.",
+            |_, s| Box::pin(browser_switch_tab_handler(s)),
+        )
+        .without_approval()
+        .params(&["index"]),
     );
-    m.insert(
+    registry.insert(
         "browser_list_tabs",
-        Tool {
-            description: "browser_list_tabs : Lists all open tabs with their URLs and indicates the current tab.",
-            handler: Box::new(|s| Box::pin(browser_list_tabs_handler(s))),
-        },
+        Tool::new(
+            "browser_list_tabs : Lists all open tabs with their URLs and indicates the current tab.",
+            |_, s| Box::pin(browser_list_tabs_handler(s)),
+        )
+        .without_approval(),
     );
-    m.insert(
+    registry.insert(
         "browser_quit",
-        Tool {
-            description: "browser_quit : Closes the browser and all tabs, shutting down the browser process.",
-            handler: Box::new(|s| Box::pin(browser_quit_handler(s))),
-        },
+        Tool::new(
+            "browser_quit : Closes the browser and all tabs, shutting down the browser process.",
+            |_, s| Box::pin(browser_quit_handler(s)),
+        )
+        .without_approval(),
     );
-    m.insert(
+    registry.insert(
         "browser_wait_for_navigation",
-        Tool {
-            description: "browser_wait_for_navigation [timeout] : Waits for the current page to finish loading. Optional timeout in seconds (default 30).",
-            handler: Box::new(|s| Box::pin(browser_wait_for_navigation_handler(s))),
-        },
+        Tool::new(
+            "browser_wait_for_navigation [timeout] : Waits for the current page to finish loading. Optional timeout in seconds (default 30).",
+            |_, s| Box::pin(browser_wait_for_navigation_handler(s)),
+        )
+        .without_approval()
+        .params(&["timeout"]),
     );
-    m.insert(
+    registry.insert(
         "browser_screenshot",
-        Tool {
-            description: "browser_screenshot : Provides you with a screenshot of the current page.",
-            handler: Box::new(|s| Box::pin(browser_screenshot_handler(s))),
-        },
-    );
-    m
-});
-
-// Build the system prompt dynamically from the tool registry
-pub static SYSTEM_PROMPT: LazyLock<String> = LazyLock::new(|| {
-    let header = r#"You are an assistant that uses tools to get accurate information.
-To use a tool, output a line starting with "TOOL:" followed by the tool name and its argument(s). For tools that require multiple pieces of data, the argument(s) may span multiple lines. You may make multiple tool calls per response.
+        Tool::new(
+            "browser_screenshot : Provides you with a screenshot of the current page.",
+            |_, s| Box::pin(browser_screenshot_handler(s)),
+        )
+        .without_approval(),
+    );
+    for plugin in plugins::all() {
+        if registry.contains(&plugin.name) {
+            tracing::warn!(
+                plugin = plugin.name,
+                "A built-in tool has this name; leaving the plugin out"
+            );
+            continue;
+        }
+        let mut tool = Tool::new(plugin.description.as_str(), move |_, s| {
+            Box::pin(plugin.run(s))
+        })
+        .params(&["input"]);
+        if plugin.read_only {
+            tool = tool.read_only();
+        }
+        registry.insert(plugin.name.as_str(), tool);
+    }
+}
+
+/// Appended to the system prompt in plan mode.
+pub const PLAN_MODE_INSTRUCTIONS: &str = "You are in plan mode: only read-only tools are available. Investigate as needed, then reply with a numbered, step-by-step plan of the changes you intend to make. Do not try to modify files or run commands; the user will review the plan and then unlock the remaining tools.";
+
+impl Default for ToolRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ToolRegistry {
+    /// A registry without any tools.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            tools: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// A registry with the built-in tools and the plugins.
+    #[must_use]
+    pub fn with_builtins() -> Self {
+        let registry = Self::new();
+        register_builtins(&registry);
+        registry
+    }
+
+    /// Adds a tool that changes files or runs commands, so that calls to it need a reason
+    /// and approval. Use [`ToolRegistry::insert`] for one that only looks things up.
+    pub fn register(
+        &self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        handler: impl for<'a> Fn(&'a ToolContext, &'a str) -> ToolFuture<'a> + Send + Sync + 'static,
+    ) {
+        self.insert(name, Tool::new(description, handler));
+    }
+
+    /// Adds a tool, or replaces the one of the same name. Returns whether it replaced one.
+    pub fn insert(&self, name: impl Into<String>, tool: Tool) -> bool {
+        self.write().insert(name.into(), Arc::new(tool)).is_some()
+    }
+
+    /// Removes a tool, returning whether there was one of that name.
+    pub fn remove(&self, name: &str) -> bool {
+        self.write().remove(name).is_some()
+    }
+
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<Arc<Tool>> {
+        self.read().get(name).cloned()
+    }
+
+    #[must_use]
+    pub fn contains(&self, name: &str) -> bool {
+        self.read().contains_key(name)
+    }
+
+    /// The names of the tools, sorted.
+    #[must_use]
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.read().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Lists the tools available in a mode, one `- name : description` line each, followed
+    /// by the argument names for JSON tool calls. `only` further limits the list to the
+    /// named tools.
+    #[must_use]
+    pub fn descriptions(&self, mode: AgentMode, only: Option<&[String]>) -> String {
+        let mut tool_lines: Vec<String> = self
+            .read()
+            .iter()
+            .filter(|(name, tool)| mode.allows(tool) && only.is_none_or(|only| only.contains(name)))
+            .map(|(name, tool)| {
+                if tool.params.is_empty() {
+                    format!("- {} : {}", name, tool.description)
+                } else {
+                    format!(
+                        "- {} : {} (JSON args: {})",
+                        name,
+                        tool.description,
+                        tool.params.join(", ")
+                    )
+                }
+            })
+            .collect();
+        tool_lines.sort(); // consistent order
+        tool_lines.join("\n")
+    }
+
+    /// Builds the system prompt for a mode from the tools registered now, listing only the
+    /// tools in `only` if it is set and opening with `role` instead of [`DEFAULT_ROLE`] if
+    /// that is.
+    #[must_use]
+    pub fn system_prompt(
+        &self,
+        mode: AgentMode,
+        only: Option<&[String]>,
+        role: Option<&str>,
+    ) -> String {
+        let role = role.unwrap_or(DEFAULT_ROLE);
+        let header = r#"To use a tool, output a line starting with "TOOL:" followed by the tool name and its argument(s). For tools that require multiple pieces of data, the argument(s) may span multiple lines. You may make multiple tool calls per response.
 After making a tool call, you will receive the tool's result in a subsequent prompt. Do not guess information that could be obtained via a tool call; instead, use the appropriate tool to get accurate data.
 Do not include any other text before or after the tool call(s). Do not try to provide the tool's result yourself.
 If a tool call fails, read the error message and correct the call if needed.
+A tool call can also be written as a fenced code block with the info string "tool" holding a JSON object, such as:
+```tool
+{"name": "write_file", "args": {"path": "notes.txt", "content": "first line\nsecond line"}, "reason": "save the notes"}
+```
+"args" holds the arguments named after each tool below; the "blocks" of apply_search_replace are a list of {"search": ..., "replace": ...} objects. Use this form when an argument contains a line starting with "TOOL:".
 
 Available tools:
 "#;
-    let mut tool_lines: Vec<String> = TOOLS
-        .iter()
-        .map(|(name, tool)| format!("- {} : {}", name, tool.description))
-        .collect();
-    tool_lines.sort(); // consistent order
-    header.to_string() + &tool_lines.join("\n")
-});
-
-/// Executes a tool by name with the given argument.
-///
-/// # Errors
-/// Returns an error if the tool is unknown or if the tool's handler fails.
-pub async fn execute_tool(name: &str, arg: &str) -> Result<ToolOutput> {
-    match TOOLS.get(name) {
-        Some(tool) => (tool.handler)(arg).await,
-        None => anyhow::bail!("Unknown tool: {name}"),
+        let mut prompt = format!("{role}\n{header}{}", self.descriptions(mode, only));
+        let mut gated: Vec<String> = self
+            .read()
+            .iter()
+            .filter(|(name, tool)| {
+                mode.allows(tool)
+                    && only.is_none_or(|only| only.contains(name))
+                    && tool.needs_approval
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+        if !gated.is_empty() {
+            gated.sort_unstable();
+            prompt.push_str(&format!(
+                "\n\nTools that change files or run commands ({}) need a reason: put a line \"{} <why the call is needed>\" directly after the TOOL line, or a \"reason\" in a JSON tool call. The user reads it when deciding whether to approve the call.",
+                gated.join(", "),
+                protocol::REASON_PREFIX
+            ));
+        }
+        prompt.push_str("\n\n");
+        prompt.push_str(&SHELL.prompt_guidance());
+        prompt.push_str(
+            "\nRelative paths in tool arguments are taken from the root of the project, where run_command also runs, and may not lead out of it with \"..\"; name files outside the project by their absolute path.",
+        );
+        if mode == AgentMode::Plan {
+            prompt.push_str("\n\n");
+            prompt.push_str(PLAN_MODE_INSTRUCTIONS);
+        }
+        prompt
+    }
+
+    /// Executes a tool by name with the given argument, in the workspace of the current
+    /// directory. A tool that is unknown or fails gives a [`ToolStatus::Failed`] result
+    /// saying why.
+    pub async fn execute(&self, name: &str, arg: &str) -> ToolResult {
+        self.execute_in(&ToolContext::current(), name, arg).await
     }
+
+    /// Executes a tool as [`ToolRegistry::execute`] does, with paths taken from the
+    /// context's root.
+    pub async fn execute_in(&self, cx: &ToolContext, name: &str, arg: &str) -> ToolResult {
+        // The registry is not kept locked while the tool runs, so tools may register others
+        let Some(tool) = self.get(name) else {
+            return ToolResult::failed(name, format!("Unknown tool: {name}"));
+        };
+        let started = std::time::Instant::now();
+        let result = (tool.handler)(cx, arg).await;
+        let elapsed_ms = started.elapsed().as_millis();
+        match result {
+            Ok(output) => {
+                let result = ToolResult::from(output);
+                tracing::debug!(
+                    tool = name,
+                    arg_bytes = arg.len(),
+                    elapsed_ms,
+                    summary = %result.summary,
+                    body_bytes = result.body.as_ref().map_or(0, String::len),
+                    artifacts = result.artifacts.len(),
+                    "tool finished"
+                );
+                result
+            }
+            Err(e) => {
+                tracing::debug!(tool = name, arg_bytes = arg.len(), elapsed_ms, error = %e, "tool failed");
+                ToolResult::failed(name, e)
+            }
+        }
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, HashMap<String, Arc<Tool>>> {
+        self.tools.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, HashMap<String, Arc<Tool>>> {
+        self.tools.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Lists the tools of the [`registry`] available in a mode; see
+/// [`ToolRegistry::descriptions`].
+#[must_use]
+pub fn tool_descriptions(mode: AgentMode, only: Option<&[String]>) -> String {
+    registry().descriptions(mode, only)
+}
+
+/// Builds the system prompt for a mode from the [`registry`]; see
+/// [`ToolRegistry::system_prompt`].
+#[must_use]
+pub fn build_system_prompt(mode: AgentMode, only: Option<&[String]>, role: Option<&str>) -> String {
+    registry().system_prompt(mode, only, role)
+}
+
+/// The opening of the system prompt, unless a profile replaces it.
+pub const DEFAULT_ROLE: &str = "You are an assistant that uses tools to get accurate information.";
+
+/// Returns whether the named tool may be used in the given mode. Unknown tools are
+/// reported by [`execute_tool`] instead.
+#[must_use]
+pub fn tool_allowed(name: &str, mode: AgentMode) -> bool {
+    registry().get(name).is_none_or(|tool| mode.allows(&tool))
+}
+
+/// Returns whether the named tool can run at the same time as other such tools from the
+/// same reply. That holds for read-only tools, except `ask_user`, which needs the
+/// terminal, and the browser tools, which share one page.
+#[must_use]
+pub fn runs_in_parallel(name: &str) -> bool {
+    name != "ask_user"
+        && !name.starts_with("browser_")
+        && registry().get(name).is_some_and(|tool| tool.read_only)
+}
+
+/// Returns whether the named tool changes files or runs commands, so that calls to it must
+/// give a reason and are confirmed by the user. The browser tools are not included: they
+/// only act on the browser they opened.
+#[must_use]
+pub fn needs_approval(name: &str) -> bool {
+    registry().get(name).is_some_and(|tool| tool.needs_approval)
+}
+
+/// Names of the arguments a JSON tool call to the named tool may give, or `None` if there
+/// is no such tool.
+#[must_use]
+pub fn params(name: &str) -> Option<&'static [&'static str]> {
+    registry().get(name).map(|tool| tool.params)
+}
+
+/// Returns whether a tool with this name exists.
+#[must_use]
+pub fn tool_exists(name: &str) -> bool {
+    registry().contains(name)
+}
+
+/// Executes a tool of the [`registry`] by name with the given argument. A tool that is
+/// unknown or fails gives a [`ToolStatus::Failed`] result saying why.
+pub async fn execute_tool(name: &str, arg: &str) -> ToolResult {
+    registry().execute(name, arg).await
 }
@@ -0,0 +1,352 @@
+use crate::commands::SlashCommand;
+use crate::events::{AgentEvent, ChatBackend, EventSink};
+use anyhow::Result;
+use crossterm::event::{Event, EventStream, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use futures_util::StreamExt;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::rc::Rc;
+use tokio::sync::mpsc;
+use tokio::task::LocalSet;
+use unicode_width::UnicodeWidthChar;
+
+const KEYS_HELP: &str =
+    "Enter send · Esc interrupt · Ctrl+T thinking pane · PgUp/PgDn scroll · Ctrl+D quit";
+
+/// A block of the conversation pane.
+enum Entry {
+    User(String),
+    Assistant(String),
+    Notice(String),
+}
+
+/// A tool call shown in the sidebar.
+struct ToolActivity {
+    name: String,
+    /// First line of the argument, usually the path or command
+    detail: String,
+    result: Option<(bool, String)>,
+}
+
+/// What a key press asks the event loop to do.
+enum Action {
+    Send(String),
+    Interrupt,
+}
+
+#[derive(Default)]
+struct App {
+    entries: Vec<Entry>,
+    /// Thinking of the current turn
+    thinking: String,
+    tools: Vec<ToolActivity>,
+    input: String,
+    /// Rows scrolled up from the bottom of the conversation; 0 follows new output
+    scroll: usize,
+    hide_thinking: bool,
+    /// Whether content is streaming into the last assistant entry
+    streaming: bool,
+    busy: bool,
+    quit: bool,
+}
+
+/// Runs the full-screen interface until the user quits.
+///
+/// # Errors
+/// Returns an error if the terminal cannot be drawn or read.
+pub async fn run(backend: Rc<dyn ChatBackend>) -> Result<()> {
+    let mut terminal = ratatui::init();
+    // Turns hold the session across awaits, so they run on this thread
+    let result = LocalSet::new()
+        .run_until(event_loop(&mut terminal, backend))
+        .await;
+    ratatui::restore();
+    result
+}
+
+async fn event_loop(terminal: &mut DefaultTerminal, backend: Rc<dyn ChatBackend>) -> Result<()> {
+    let mut app = App::default();
+    app.entries.push(Entry::Notice(KEYS_HELP.to_string()));
+    let mut keys = EventStream::new();
+    let (events, mut agent_rx) = EventSink::channel();
+    let events = events.without_echo();
+    let (done_tx, mut done_rx) = mpsc::unbounded_channel();
+    while !app.quit {
+        terminal.draw(|frame| app.draw(frame))?;
+        tokio::select! {
+            key = keys.next() => match key {
+                Some(Ok(Event::Key(key))) => match app.handle_key(key) {
+                    Some(Action::Send(message)) => {
+                        let backend = backend.clone();
+                        let events = events.clone();
+                        let done_tx = done_tx.clone();
+                        tokio::task::spawn_local(async move {
+                            if let Err(e) = backend.send_message(message, events.clone()).await {
+                                events.emit(AgentEvent::Error { message: e.to_string() });
+                            }
+                            let _ = done_tx.send(());
+                        });
+                    }
+                    Some(Action::Interrupt) => backend.interrupt(),
+                    None => {}
+                },
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e.into()),
+                None => break,
+            },
+            Some(event) = agent_rx.recv() => app.apply(event),
+            Some(()) = done_rx.recv() => app.busy = false,
+        }
+    }
+    Ok(())
+}
+
+impl App {
+    fn handle_key(&mut self, key: KeyEvent) -> Option<Action> {
+        if key.kind != KeyEventKind::Press {
+            return None;
+        }
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char('c') if ctrl => {
+                if self.busy {
+                    return Some(Action::Interrupt);
+                }
+                self.quit = true;
+            }
+            KeyCode::Char('d') if ctrl && self.input.is_empty() => self.quit = true,
+            KeyCode::Char('t') if ctrl => self.hide_thinking = !self.hide_thinking,
+            KeyCode::Esc if self.busy => return Some(Action::Interrupt),
+            KeyCode::Enter => return self.submit(),
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Char(c) if !ctrl => self.input.push(c),
+            KeyCode::PageUp => self.scroll += 10,
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_sub(10),
+            KeyCode::Up => self.scroll += 1,
+            KeyCode::Down => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::End => self.scroll = 0,
+            _ => {}
+        }
+        None
+    }
+
+    fn submit(&mut self) -> Option<Action> {
+        let input = self.input.trim().to_string();
+        if input.is_empty() || self.busy {
+            return None;
+        }
+        self.input.clear();
+        match SlashCommand::parse(&input) {
+            Some(SlashCommand::Exit) => self.quit = true,
+            Some(SlashCommand::Help) => self.entries.push(Entry::Notice(KEYS_HELP.to_string())),
+            Some(_) => self.entries.push(Entry::Notice(format!(
+                "{input} is only available in the line-based interface"
+            ))),
+            None => {
+                self.entries.push(Entry::User(input.clone()));
+                self.thinking.clear();
+                self.scroll = 0;
+                self.busy = true;
+                return Some(Action::Send(input));
+            }
+        }
+        None
+    }
+
+    fn apply(&mut self, event: AgentEvent) {
+        match event {
+            // Already shown when the user submitted it, or tracked by the event loop
+            AgentEvent::User { .. } | AgentEvent::Done => {}
+            AgentEvent::Thinking { text } => self.thinking.push_str(&text),
+            AgentEvent::Content { text } => match self.entries.last_mut() {
+                Some(Entry::Assistant(content)) if self.streaming => content.push_str(&text),
+                _ => {
+                    self.entries.push(Entry::Assistant(text));
+                    self.streaming = true;
+                }
+            },
+            AgentEvent::Message { content, .. } => {
+                match self.entries.last_mut() {
+                    Some(Entry::Assistant(streamed)) if self.streaming => *streamed = content,
+                    _ if !content.trim().is_empty() => self.entries.push(Entry::Assistant(content)),
+                    _ => {}
+                }
+                self.streaming = false;
+            }
+            AgentEvent::ToolCall { name, arg } => self.tools.push(ToolActivity {
+                name,
+                detail: arg.lines().next().unwrap_or_default().to_string(),
+                result: None,
+            }),
+            AgentEvent::ToolResult {
+                name,
+                status,
+                success,
+            } => {
+                if let Some(activity) = self
+                    .tools
+                    .iter_mut()
+                    .rev()
+                    .find(|a| a.name == name && a.result.is_none())
+                {
+                    activity.result = Some((success, status));
+                }
+            }
+            AgentEvent::Interrupted => {
+                self.entries.push(Entry::Notice("Interrupted".to_string()));
+                self.streaming = false;
+            }
+            AgentEvent::Error { message } => {
+                self.entries
+                    .push(Entry::Notice(format!("Error: {message}")));
+                self.streaming = false;
+            }
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, input_area] =
+            Layout::vertical([Constraint::Min(3), Constraint::Length(3)]).areas(frame.area());
+        let [left, sidebar] =
+            Layout::horizontal([Constraint::Percentage(70), Constraint::Percentage(30)])
+                .areas(main);
+        let conversation_area = if self.hide_thinking || self.thinking.is_empty() {
+            left
+        } else {
+            let [thinking_area, conversation_area] =
+                Layout::vertical([Constraint::Percentage(30), Constraint::Percentage(70)])
+                    .areas(left);
+            let style = Style::default().fg(Color::DarkGray);
+            let rows = styled_rows(&self.thinking, inner_width(thinking_area), style);
+            render_tail(frame, thinking_area, rows, 0, "Thinking (Ctrl+T to hide)");
+            conversation_area
+        };
+
+        let width = inner_width(conversation_area);
+        let mut rows = Vec::new();
+        for entry in &self.entries {
+            match entry {
+                Entry::User(text) => {
+                    let style = Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD);
+                    rows.extend(styled_rows(&format!("> {text}"), width, style));
+                }
+                Entry::Assistant(text) => rows.extend(styled_rows(text, width, Style::default())),
+                Entry::Notice(text) => {
+                    let style = Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::ITALIC);
+                    rows.extend(styled_rows(text, width, style));
+                }
+            }
+            rows.push(Line::default());
+        }
+        let height = usize::from(conversation_area.height.saturating_sub(2));
+        self.scroll = self.scroll.min(rows.len().saturating_sub(height));
+        let title = if self.scroll > 0 {
+            "Conversation (scrolled, End to follow)"
+        } else {
+            "Conversation"
+        };
+        render_tail(frame, conversation_area, rows, self.scroll, title);
+
+        let width = inner_width(sidebar);
+        let mut rows = Vec::new();
+        for activity in &self.tools {
+            let (marker, color) = match &activity.result {
+                None => ("…", Color::Blue),
+                Some((true, _)) => ("✓", Color::Green),
+                Some((false, _)) => ("✗", Color::Red),
+            };
+            let summary = format!("{marker} {} {}", activity.name, activity.detail);
+            rows.extend(styled_rows(&summary, width, Style::default().fg(color)));
+            if let Some((_, status)) = &activity.result {
+                let style = Style::default().fg(Color::DarkGray);
+                rows.extend(styled_rows(status, width, style));
+            }
+        }
+        render_tail(frame, sidebar, rows, 0, "Tools");
+
+        let title = if self.busy {
+            "Working... (Esc to interrupt)"
+        } else {
+            "Message"
+        };
+        // Show the end of the input when it is wider than the box
+        let room = usize::from(input_area.width.saturating_sub(3));
+        let mut visible = String::new();
+        let mut visible_width = 0;
+        for c in self.input.chars().rev() {
+            let w = c.width().unwrap_or(0);
+            if visible_width + w > room {
+                break;
+            }
+            visible.insert(0, c);
+            visible_width += w;
+        }
+        frame.render_widget(
+            Paragraph::new(visible).block(Block::bordered().title(title)),
+            input_area,
+        );
+        let cursor_x = input_area.x + 1 + u16::try_from(visible_width).unwrap_or(0);
+        frame.set_cursor_position((cursor_x, input_area.y + 1));
+    }
+}
+
+fn inner_width(area: Rect) -> usize {
+    usize::from(area.width.saturating_sub(2))
+}
+
+fn styled_rows(text: &str, width: usize, style: Style) -> Vec<Line<'static>> {
+    wrap(text, width)
+        .into_iter()
+        .map(|row| Line::styled(row, style))
+        .collect()
+}
+
+/// Draws the rows that fit in `area`, ending `scroll` rows above the last one.
+fn render_tail(
+    frame: &mut Frame,
+    area: Rect,
+    rows: Vec<Line<'static>>,
+    scroll: usize,
+    title: &str,
+) {
+    let height = usize::from(area.height.saturating_sub(2));
+    let end = rows.len().saturating_sub(scroll);
+    let start = end.saturating_sub(height);
+    let visible: Vec<Line> = rows.into_iter().skip(start).take(end - start).collect();
+    frame.render_widget(
+        Paragraph::new(visible).block(Block::bordered().title(title.to_string())),
+        area,
+    );
+}
+
+/// Splits `text` into rows at most `width` columns wide, breaking at newlines and
+/// wherever a row is full.
+#[must_use]
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut rows = Vec::new();
+    for line in text.replace('\t', "    ").split('\n') {
+        let mut row = String::new();
+        let mut row_width = 0;
+        for c in line.chars() {
+            let w = c.width().unwrap_or(0);
+            if row_width + w > width && !row.is_empty() {
+                rows.push(std::mem::take(&mut row));
+                row_width = 0;
+            }
+            row.push(c);
+            row_width += w;
+        }
+        rows.push(row);
+    }
+    rows
+}
@@ -0,0 +1,89 @@
+use anyhow::{Result, anyhow, bail};
+use std::collections::BTreeMap;
+
+/// Variables set with `/var set` or `--var`, substituted for `$NAME` or `${NAME}` in
+/// prompts and workflow templates.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Variables {
+    values: BTreeMap<String, String>,
+}
+
+impl Variables {
+    /// Sets a variable from `NAME=value`.
+    ///
+    /// # Errors
+    /// Returns an error if there is no `=` or the name is not a valid identifier.
+    pub fn set(&mut self, assignment: &str) -> Result<()> {
+        let (name, value) = assignment
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Expected NAME=value, got {assignment}"))?;
+        let name = name.trim();
+        if !is_name(name) {
+            bail!("Invalid variable name: {name} (use letters, digits and _)");
+        }
+        self.values
+            .insert(name.to_string(), value.trim().to_string());
+        Ok(())
+    }
+
+    /// Removes a variable, returning whether it was set.
+    pub fn unset(&mut self, name: &str) -> bool {
+        self.values.remove(name).is_some()
+    }
+
+    /// The variables in name order.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.values.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Replaces `$NAME` and `${NAME}` with the values of variables that are set. Anything
+    /// else, such as `$HOME` in a shell snippet when no `HOME` variable is set, is kept.
+    #[must_use]
+    pub fn expand(&self, text: &str) -> String {
+        if self.values.is_empty() {
+            return text.to_string();
+        }
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(i) = rest.find('$') {
+            out.push_str(&rest[..i]);
+            let after = &rest[i + 1..];
+            let (name, consumed) = if let Some(braced) = after.strip_prefix('{') {
+                match braced.find('}') {
+                    Some(end) => (&braced[..end], end + 2),
+                    None => ("", 0),
+                }
+            } else {
+                let end = after
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(after.len());
+                (&after[..end], end)
+            };
+            match self.values.get(name) {
+                Some(value) if is_name(name) => {
+                    out.push_str(value);
+                    rest = &after[consumed..];
+                }
+                _ => {
+                    out.push('$');
+                    rest = after;
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
@@ -0,0 +1,105 @@
+use crate::index::glob_match;
+use crate::workspace::Workspace;
+use ::notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use anyhow::{Result, anyhow};
+use std::collections::BTreeSet;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc::{UnboundedReceiver, unbounded_channel};
+
+/// How long `deepseek watch` waits after a change for the files to settle before it runs
+/// the prompt, when `--debounce` is not given.
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_secs(1);
+
+/// The files watched when no `--glob` is given.
+pub const DEFAULT_GLOB: &str = "**/*";
+
+/// Directories whose changes never set the prompt off: the repositories themselves, and
+/// build output and dependencies, which the prompt's own commands write to.
+const IGNORED_DIRS: [&str; 5] = [".git", ".jj", ".hg", "target", "node_modules"];
+
+/// Which changed files set off the prompt of `deepseek watch`: those matching one of the
+/// globs, such as `**/*.rs`, by their path within the workspace root they are in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangeFilter {
+    globs: Vec<String>,
+}
+
+impl ChangeFilter {
+    /// A filter for `globs`, or for every file if there are none.
+    #[must_use]
+    pub fn new(globs: Vec<String>) -> Self {
+        if globs.is_empty() {
+            return Self {
+                globs: vec![DEFAULT_GLOB.to_string()],
+            };
+        }
+        Self { globs }
+    }
+
+    #[must_use]
+    pub fn globs(&self) -> &[String] {
+        &self.globs
+    }
+
+    /// The path the tools would name `path` by, if it is in the workspace, outside the
+    /// ignored directories, and matches a glob.
+    #[must_use]
+    pub fn matches(&self, workspace: &Workspace, path: &Path) -> Option<String> {
+        let (_, relative) = workspace.within(path)?;
+        let mut parts = Vec::new();
+        for component in relative.components() {
+            let Component::Normal(name) = component else {
+                return None;
+            };
+            let name = name.to_str()?;
+            if IGNORED_DIRS.contains(&name) {
+                return None;
+            }
+            parts.push(name);
+        }
+        let relative = parts.join("/");
+        self.globs
+            .iter()
+            .any(|glob| glob_match(glob, &relative))
+            .then(|| workspace.display(path))
+    }
+}
+
+/// The message a run of the prompt is sent as: the files that changed, then the prompt.
+#[must_use]
+pub fn message(prompt: &str, changed: &BTreeSet<String>) -> String {
+    let files: Vec<&str> = changed.iter().map(String::as_str).collect();
+    format!(
+        "These files just changed: {}\n\n{}",
+        files.join(", "),
+        prompt.trim()
+    )
+}
+
+/// Watches every root of `workspace` for files being created, changed or removed. The
+/// paths arrive on the receiver for as long as the returned watcher is kept.
+///
+/// # Errors
+/// Returns an error if a root cannot be watched.
+pub fn watch(workspace: &Workspace) -> Result<(RecommendedWatcher, UnboundedReceiver<PathBuf>)> {
+    let (tx, rx) = unbounded_channel();
+    let mut watcher = ::notify::recommended_watcher(move |event: ::notify::Result<Event>| {
+        match event {
+            // Reading a file is not a change
+            Ok(event) if event.kind.is_access() => {}
+            Ok(event) => {
+                for path in event.paths {
+                    let _ = tx.send(path);
+                }
+            }
+            Err(e) => tracing::debug!(error = %e, "file watch error"),
+        }
+    })?;
+    for root in workspace.roots() {
+        watcher
+            .watch(&root.path, RecursiveMode::Recursive)
+            .map_err(|e| anyhow!("Failed to watch {}: {e}", root.path.display()))?;
+    }
+    Ok((watcher, rx))
+}
@@ -0,0 +1,222 @@
+use crate::events::{AgentEvent, ChatBackend, EventSink};
+use anyhow::{Result, bail};
+use serde::Deserialize;
+use std::rc::Rc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::LocalSet;
+
+const INDEX_HTML: &str = include_str!("web/index.html");
+const MAX_HEADER_BYTES: usize = 64 * 1024;
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+/// Header the page sends with every API call. Browsers will not add it to cross-site
+/// requests without a CORS preflight, which this server never approves.
+pub const API_HEADER: &str = "x-deepseek-cli";
+
+/// A parsed HTTP request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Returns the first header with the given name, compared case-insensitively.
+    #[must_use]
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Whether the request is addressed to this machine. Rejecting other hosts keeps pages
+    /// on other sites from reaching the server through DNS rebinding.
+    #[must_use]
+    pub fn is_local(&self) -> bool {
+        let Some(host) = self.header("host") else {
+            return false;
+        };
+        let name = host
+            .rsplit_once(':')
+            .filter(|(_, port)| port.chars().all(|c| c.is_ascii_digit()))
+            .map_or(host, |(name, _)| name);
+        matches!(name, "127.0.0.1" | "localhost" | "[::1]")
+    }
+}
+
+#[derive(Deserialize)]
+struct SendRequest {
+    message: String,
+}
+
+/// Serves the web interface on `127.0.0.1:port` until an error occurs.
+///
+/// # Errors
+/// Returns an error if the port cannot be bound or accepting connections fails.
+pub async fn serve(port: u16, backend: Rc<dyn ChatBackend>) -> Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+    println!("Web interface running at http://127.0.0.1:{port} (press Ctrl+C to stop)");
+    // Turns hold the session across awaits, so connections run on this thread
+    LocalSet::new()
+        .run_until(accept_loop(listener, backend))
+        .await
+}
+
+async fn accept_loop(listener: TcpListener, backend: Rc<dyn ChatBackend>) -> Result<()> {
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let backend = backend.clone();
+        tokio::task::spawn_local(async move {
+            if let Err(e) = handle_connection(stream, backend).await {
+                eprintln!("Web request failed: {e}");
+            }
+        });
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream, backend: Rc<dyn ChatBackend>) -> Result<()> {
+    let request = read_request(&mut stream).await?;
+    if !request.is_local() {
+        return write_response(&mut stream, "403 Forbidden", "text/plain", b"Forbidden").await;
+    }
+    let is_api = request.path.starts_with("/api/");
+    if is_api && (request.method != "POST" || request.header(API_HEADER).is_none()) {
+        return write_response(&mut stream, "403 Forbidden", "text/plain", b"Forbidden").await;
+    }
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/") => {
+            write_response(
+                &mut stream,
+                "200 OK",
+                "text/html; charset=utf-8",
+                INDEX_HTML.as_bytes(),
+            )
+            .await
+        }
+        (_, "/api/send") => {
+            let message = serde_json::from_slice::<SendRequest>(&request.body)?.message;
+            stream_turn(stream, backend, message).await
+        }
+        (_, "/api/stop") => {
+            backend.interrupt();
+            write_response(&mut stream, "204 No Content", "text/plain", b"").await
+        }
+        _ => write_response(&mut stream, "404 Not Found", "text/plain", b"Not found").await,
+    }
+}
+
+/// Runs a turn, writing its events to the connection as newline-delimited JSON.
+async fn stream_turn(
+    mut stream: TcpStream,
+    backend: Rc<dyn ChatBackend>,
+    message: String,
+) -> Result<()> {
+    stream
+        .write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: application/x-ndjson\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+        )
+        .await?;
+    let (events, mut rx) = EventSink::channel();
+    let errors = events.clone();
+    let turn = async move {
+        if let Err(e) = backend.send_message(message, events).await {
+            errors.emit(AgentEvent::Error {
+                message: e.to_string(),
+            });
+        }
+    };
+    let forward = async {
+        while let Some(event) = rx.recv().await {
+            let mut line = serde_json::to_vec(&event)?;
+            line.push(b'\n');
+            // The turn keeps running if the browser goes away
+            if stream.write_all(&line).await.is_err() {
+                break;
+            }
+        }
+        anyhow::Ok(())
+    };
+    let ((), forwarded) = tokio::join!(turn, forward);
+    forwarded?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Reads one request head and its body, as sized by `Content-Length`.
+async fn read_request(stream: &mut TcpStream) -> Result<Request> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let head_end = loop {
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        if buf.len() > MAX_HEADER_BYTES {
+            bail!("Request headers too large");
+        }
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            bail!("Connection closed before the request was complete");
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+    let mut request = parse_head(&String::from_utf8_lossy(&buf[..head_end]))?;
+    let length = request
+        .header("content-length")
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(0);
+    if length > MAX_BODY_BYTES {
+        bail!("Request body too large");
+    }
+    let mut body = buf[head_end..].to_vec();
+    while body.len() < length {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            bail!("Connection closed before the request body was complete");
+        }
+        body.extend_from_slice(&chunk[..n]);
+    }
+    body.truncate(length);
+    request.body = body;
+    Ok(request)
+}
+
+/// Parses a request line and headers. The body is left empty.
+///
+/// # Errors
+/// Returns an error if the request line is malformed.
+pub fn parse_head(head: &str) -> Result<Request> {
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let (Some(method), Some(path)) = (request_line.next(), request_line.next()) else {
+        bail!("Malformed request line");
+    };
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect();
+    Ok(Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        headers,
+        body: Vec::new(),
+    })
+}
+
+async fn write_response(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+) -> Result<()> {
+    let head = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
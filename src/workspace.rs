@@ -0,0 +1,256 @@
+use crate::config;
+use anyhow::{Result, bail};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{LazyLock, PoisonError, RwLock};
+
+/// Directories whose presence marks the root of a repository.
+pub const REPOSITORY_MARKERS: [&str; 3] = [".git", ".jj", ".hg"];
+
+/// The root of the project `dir` is in: the nearest directory from `dir` up that holds a
+/// repository, or `dir` itself outside one.
+#[must_use]
+pub fn find_root(dir: &Path) -> PathBuf {
+    dir.ancestors()
+        .find(|d| REPOSITORY_MARKERS.iter().any(|m| d.join(m).exists()))
+        .unwrap_or(dir)
+        .to_path_buf()
+}
+
+/// Works out the `.` and `..` in `path` without looking at the file system. Returns `None`
+/// if a relative path climbs above where it starts; `..` at the root of an absolute path
+/// stays there.
+#[must_use]
+pub fn normalize(path: &Path) -> Option<PathBuf> {
+    let mut normalized = PathBuf::new();
+    let mut depth = 0usize;
+    for component in path.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir => normalized.push(component),
+            Component::CurDir => {}
+            Component::ParentDir if depth > 0 => {
+                normalized.pop();
+                depth -= 1;
+            }
+            Component::ParentDir if path.is_absolute() => {}
+            Component::ParentDir => return None,
+            Component::Normal(name) => {
+                normalized.push(name);
+                depth += 1;
+            }
+        }
+    }
+    Some(normalized)
+}
+
+/// Resolves a path from a tool argument in the workspace `root`. A relative path is taken
+/// from the root and may not lead out of it with `..`; an absolute path is only
+/// normalized, so files elsewhere can still be named in full.
+///
+/// # Errors
+/// Returns an error if the path is empty or a relative path leads outside the root.
+pub fn resolve(root: &Path, path: &str) -> Result<PathBuf> {
+    let path = path.trim();
+    if path.is_empty() {
+        bail!("Missing path");
+    }
+    let given = Path::new(path);
+    match normalize(given) {
+        Some(normalized) if given.is_absolute() => Ok(normalized),
+        Some(normalized) => Ok(root.join(normalized)),
+        None => bail!(
+            "{path} leads outside the workspace {}; name files outside it by their absolute path",
+            root.display()
+        ),
+    }
+}
+
+/// A resolved path as the tools report it: relative to the root inside the workspace, and
+/// in full outside it.
+#[must_use]
+pub fn display(root: &Path, path: &Path) -> String {
+    match path.strip_prefix(root) {
+        Ok(relative) if relative.as_os_str().is_empty() => ".".to_string(),
+        Ok(relative) => relative.display().to_string(),
+        Err(_) => path.display().to_string(),
+    }
+}
+
+/// The workspace the tools work in, once worked out from the config and the current
+/// directory, and the root `/workspace` switched to since.
+static CURRENT: LazyLock<RwLock<Workspace>> = LazyLock::new(|| {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let config = config::get();
+    let configured: Vec<Root> = config
+        .roots
+        .iter()
+        .map(|(name, path)| Root::new(name, config.resolve(path)))
+        .collect();
+    RwLock::new(Workspace::detect(&cwd, configured))
+});
+
+/// One root of a workspace, with the name paths in it are given by from the others.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Root {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl Root {
+    /// A root at `path`, made absolute and normalized.
+    #[must_use]
+    pub fn new(name: &str, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let path = std::path::absolute(&path).unwrap_or(path);
+        Self {
+            name: name.to_string(),
+            path: normalize(&path).unwrap_or(path),
+        }
+    }
+}
+
+/// The roots the tools work in, such as a frontend and a backend repository, configured
+/// under `[roots]`. Relative paths are taken from the active root, `/workspace` switches
+/// it, and `<name>:<path>` names a file in another root, which is how results show such
+/// files too.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Workspace {
+    roots: Vec<Root>,
+    active: usize,
+}
+
+impl Workspace {
+    /// A workspace of the one root `path`.
+    #[must_use]
+    pub fn single(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let name = path
+            .file_name()
+            .map_or_else(|| "root".to_string(), |n| n.to_string_lossy().into_owned());
+        Self {
+            roots: vec![Root::new(&name, path)],
+            active: 0,
+        }
+    }
+
+    /// The workspace of `cwd`: the `configured` roots, with the one holding the project
+    /// `cwd` is in active. A project outside all of them is added as a root of its own,
+    /// named after its directory.
+    #[must_use]
+    pub fn detect(cwd: &Path, configured: Vec<Root>) -> Self {
+        let project = Self::single(find_root(cwd));
+        let mut workspace = Self {
+            roots: configured,
+            active: 0,
+        };
+        match workspace.containing(project.root()) {
+            Some(i) => workspace.active = i,
+            None => workspace.roots.insert(0, project.active().clone()),
+        }
+        workspace
+    }
+
+    #[must_use]
+    pub fn roots(&self) -> &[Root] {
+        &self.roots
+    }
+
+    #[must_use]
+    pub fn active(&self) -> &Root {
+        &self.roots[self.active]
+    }
+
+    /// The directory relative paths are taken from and commands run in.
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.active().path
+    }
+
+    /// Makes the root named `name` the active one.
+    ///
+    /// # Errors
+    /// Returns an error if there is no such root.
+    pub fn switch(&mut self, name: &str) -> Result<&Root> {
+        let Some(i) = self.roots.iter().position(|r| r.name == name) else {
+            bail!("No workspace root named {name} (expected {})", self.names());
+        };
+        self.active = i;
+        Ok(self.active())
+    }
+
+    /// The root a tool path belongs to and the path within it: `<name>:<path>` for the
+    /// root of that name, anything else for the active root.
+    #[must_use]
+    pub fn locate<'a>(&self, path: &'a str) -> (&Root, &'a str) {
+        let path = path.trim();
+        path.split_once(':')
+            .and_then(|(name, rest)| {
+                let root = self.roots.iter().find(|r| r.name == name)?;
+                // `<name>:` alone is the root itself
+                Some((root, if rest.is_empty() { "." } else { rest }))
+            })
+            .unwrap_or((self.active(), path))
+    }
+
+    /// Resolves a tool path as [`resolve`] does, in the root it belongs to.
+    ///
+    /// # Errors
+    /// Returns an error if the path is empty or a relative path leads outside its root.
+    pub fn resolve(&self, path: &str) -> Result<PathBuf> {
+        let (root, path) = self.locate(path);
+        resolve(&root.path, path)
+    }
+
+    /// A resolved path as the tools report it: relative to the active root inside it,
+    /// `<name>:<path>` inside another root, and in full outside them all.
+    #[must_use]
+    pub fn display(&self, path: &Path) -> String {
+        match self.containing(path) {
+            Some(i) if i == self.active => display(self.root(), path),
+            Some(i) => {
+                let root = &self.roots[i];
+                format!("{}:{}", root.name, display(&root.path, path))
+            }
+            None => path.display().to_string(),
+        }
+    }
+
+    /// The innermost root `path` is in and the path within it.
+    #[must_use]
+    pub fn within<'a>(&self, path: &'a Path) -> Option<(&Root, &'a Path)> {
+        let root = &self.roots[self.containing(path)?];
+        Some((root, path.strip_prefix(&root.path).ok()?))
+    }
+
+    /// The index of the innermost root `path` is in.
+    fn containing(&self, path: &Path) -> Option<usize> {
+        self.roots
+            .iter()
+            .enumerate()
+            .filter(|(_, root)| path.starts_with(&root.path))
+            .max_by_key(|(_, root)| root.path.components().count())
+            .map(|(i, _)| i)
+    }
+
+    fn names(&self) -> String {
+        let names: Vec<&str> = self.roots.iter().map(|r| r.name.as_str()).collect();
+        names.join(", ")
+    }
+}
+
+/// The workspace as it stands.
+#[must_use]
+pub fn current() -> Workspace {
+    CURRENT
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Switches the active root of the workspace for the rest of the session.
+///
+/// # Errors
+/// Returns an error if there is no root named `name`.
+pub fn switch(name: &str) -> Result<Root> {
+    let mut workspace = CURRENT.write().unwrap_or_else(PoisonError::into_inner);
+    workspace.switch(name).cloned()
+}
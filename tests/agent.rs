@@ -0,0 +1,28 @@
+use deepseek_cli::agent::{self, CONTINUE_PROMPT, MAX_EMPTY_REPROMPTS};
+
+#[test]
+fn test_prompts() {
+    assert_eq!(
+        agent::first_prompt("You are helpful.", "Hi"),
+        "You are helpful.\n\nUser:\nHi"
+    );
+    assert_eq!(
+        agent::results_prompt(&["File read".to_string(), "Command succeeded".to_string()]),
+        format!("File read\n\nCommand succeeded\n\n{CONTINUE_PROMPT}")
+    );
+}
+
+#[test]
+fn test_empty_reply_prompts_escalate_then_stop() {
+    let prompts: Vec<String> = (1..=MAX_EMPTY_REPROMPTS)
+        .map(|attempt| agent::empty_reply_prompt(attempt).unwrap())
+        .collect();
+    assert!(prompts[0].starts_with("WARNING: Your previous response was empty."));
+    assert!(prompts[0].ends_with(CONTINUE_PROMPT));
+    assert!(prompts[1].contains("last two responses"));
+    // The last one asks for plain text, so it must not ask for the next step
+    let last = prompts.last().unwrap();
+    assert!(last.contains("Do not call any tools"));
+    assert!(!last.contains(CONTINUE_PROMPT));
+    assert_eq!(agent::empty_reply_prompt(MAX_EMPTY_REPROMPTS + 1), None);
+}
@@ -0,0 +1,23 @@
+use deepseek_cli::approval::{Answer, LimitAnswer};
+
+#[test]
+fn test_answer_parse() {
+    assert_eq!(Answer::parse("y\n"), Some(Answer::Yes));
+    assert_eq!(Answer::parse("YES"), Some(Answer::Yes));
+    assert_eq!(Answer::parse(""), Some(Answer::No));
+    assert_eq!(Answer::parse("n"), Some(Answer::No));
+    assert_eq!(Answer::parse("a"), Some(Answer::All));
+    assert_eq!(Answer::parse("maybe"), None);
+}
+
+#[test]
+fn test_limit_answer_parse() {
+    assert_eq!(LimitAnswer::parse("c\n"), Some(LimitAnswer::Continue));
+    assert_eq!(LimitAnswer::parse(""), Some(LimitAnswer::Abort));
+    assert_eq!(LimitAnswer::parse("Abort"), Some(LimitAnswer::Abort));
+    assert_eq!(LimitAnswer::parse("50"), Some(LimitAnswer::Raise(50)));
+    assert_eq!(LimitAnswer::parse("r 40"), Some(LimitAnswer::Raise(40)));
+    assert_eq!(LimitAnswer::parse("raise 40"), Some(LimitAnswer::Raise(40)));
+    assert_eq!(LimitAnswer::parse("0"), None);
+    assert_eq!(LimitAnswer::parse("more"), None);
+}
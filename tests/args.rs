@@ -0,0 +1,138 @@
+use deepseek_cli::args::{
+    Block, CallArgs, CommentArgs, EditLinesArgs, FetchUrlArgs, Format, GrepArgs, Location,
+    MoveFileArgs, PathArgs, SearchReplaceArgs, TabArgs, ToolArgs, WriteFileArgs,
+};
+
+#[test]
+fn test_text_form() {
+    assert_eq!(
+        EditLinesArgs::parse("edit_lines", "my file.rs 2 3\nnew\ntext").unwrap(),
+        EditLinesArgs {
+            path: "my file.rs".to_string(),
+            start: 2,
+            end: 3,
+            text: "new\ntext".to_string(),
+        }
+    );
+    assert_eq!(
+        WriteFileArgs::parse("write_file", "notes.md\n# Notes\n").unwrap(),
+        WriteFileArgs {
+            path: "notes.md".to_string(),
+            content: "# Notes".to_string(),
+        }
+    );
+    assert_eq!(
+        GrepArgs::parse("grep", "fn main\nsrc").unwrap(),
+        GrepArgs {
+            text: "fn main".to_string(),
+            path: Some("src".to_string()),
+        }
+    );
+    assert_eq!(
+        FetchUrlArgs::parse("fetch_url", "https://example.com raw")
+            .unwrap()
+            .format,
+        Format::Raw
+    );
+    assert_eq!(
+        CommentArgs::parse("gh_pr_comment", "#3 src/a.rs:10\nCheck for None here.")
+            .unwrap()
+            .location,
+        Some(Location {
+            path: "src/a.rs".to_string(),
+            line: 10,
+        })
+    );
+    assert_eq!(TabArgs::parse("browser_close_tab", "").unwrap().index, None);
+}
+
+#[test]
+fn test_json_form() {
+    assert_eq!(
+        SearchReplaceArgs::parse(
+            "apply_search_replace",
+            r#"{"path": "a.rs", "blocks": [{"search": "old", "replace": "new"}]}"#
+        )
+        .unwrap(),
+        SearchReplaceArgs {
+            path: "a.rs".to_string(),
+            blocks: vec![Block {
+                search: "old".to_string(),
+                replace: "new".to_string(),
+            }],
+        }
+    );
+    assert_eq!(
+        CommentArgs::parse(
+            "gh_pr_comment",
+            r##"{"pull_request": "#3", "location": "src/a.rs:10", "text": "Why?"}"##
+        )
+        .unwrap()
+        .location
+        .map(|location| location.line),
+        Some(10)
+    );
+    let error = MoveFileArgs::parse("move_file", r#"{"from": "a", "dest": "b"}"#).unwrap_err();
+    assert!(error.problem.contains("unknown field `dest`"), "{error}");
+}
+
+#[test]
+fn test_errors_explain_the_format() {
+    let error = PathArgs::parse("read_file", "src/main.rs\nsrc/lib.rs").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid arguments for read_file: the path must be on a single line. Expected the path on a single line"
+    );
+    let error = MoveFileArgs::parse("move_file", "a.txt").unwrap_err();
+    assert_eq!(error.problem, "the destination is missing");
+    assert!(error.usage.contains("destination on the second"));
+    assert!(EditLinesArgs::parse("edit_lines", "a.rs 2").is_err());
+    assert!(EditLinesArgs::parse("edit_lines", "a.rs two 3").is_err());
+    assert!(CommentArgs::parse("gh_pr_comment", "#3 src/a.rs\ntext").is_err());
+    assert!(CommentArgs::parse("gh_pr_comment", "#3").is_err());
+    assert!(FetchUrlArgs::parse("fetch_url", "https://example.com pdf").is_err());
+    assert_eq!(
+        TabArgs::parse("browser_switch_tab", "0")
+            .unwrap_err()
+            .problem,
+        "tabs are counted from 1"
+    );
+}
+
+#[test]
+fn test_call_args_read_both_forms_alike() {
+    let text = CallArgs::parse("write_file", "notes/today.md\n# Today");
+    let json = CallArgs::parse(
+        "write_file",
+        r##"{"path": "notes/today.md", "content": "# Today"}"##,
+    );
+    assert_eq!(text, json);
+    assert_eq!(json.target(), "notes/today.md");
+    assert_eq!(json.edited_file(), Some("notes/today.md"));
+    assert_eq!(json.text(), "notes/today.md\n# Today");
+    assert_eq!(
+        CallArgs::parse("run_command", r#"{"command": "cargo test\ncargo build"}"#).text(),
+        "cargo test\ncargo build"
+    );
+
+    let moved = CallArgs::parse("move_file", r#"{"from": "a.txt", "to": "b/a.txt"}"#);
+    assert_eq!(moved.target(), "a.txt");
+    assert_eq!(moved.edited_file(), None);
+    assert_eq!(
+        CallArgs::parse(
+            "fetch_url",
+            r#"{"url": "https://example.com", "format": "raw"}"#
+        )
+        .target(),
+        "https://example.com"
+    );
+    // Arguments the tool cannot read are kept as they were given
+    assert_eq!(
+        CallArgs::parse("edit_lines", "a.rs two 3\ntext"),
+        CallArgs::Text("a.rs two 3\ntext".to_string())
+    );
+    assert_eq!(
+        CallArgs::parse("browser_open", "https://example.com").target(),
+        "https://example.com"
+    );
+}
@@ -0,0 +1,51 @@
+use deepseek_cli::attach::{format_size, is_attachment, mime_type, read};
+use deepseek_cli::mentions::{attachments, expand};
+use std::path::Path;
+
+#[test]
+fn test_mime_types() {
+    assert_eq!(mime_type(Path::new("shot.PNG")), Some("image/png"));
+    assert_eq!(
+        mime_type(Path::new("docs/spec.pdf")),
+        Some("application/pdf")
+    );
+    assert_eq!(mime_type(Path::new("photo.jpeg")), Some("image/jpeg"));
+    assert_eq!(mime_type(Path::new("src/main.rs")), None);
+    assert_eq!(mime_type(Path::new("Makefile")), None);
+}
+
+#[test]
+fn test_format_size() {
+    assert_eq!(format_size(812), "812 B");
+    assert_eq!(format_size(4300), "4.2 KB");
+    assert_eq!(format_size(1_400_000), "1.3 MB");
+}
+
+#[test]
+fn test_attachments_are_uploaded_not_inlined() {
+    let dir = std::env::temp_dir().join(format!("deepseek-attach-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let image = dir.join("error.png");
+    std::fs::write(&image, [0x89, b'P', b'N', b'G']).unwrap();
+    let notes = dir.join("notes.txt");
+    std::fs::write(&notes, "Seen on staging\n").unwrap();
+    let (image, notes) = (image.display().to_string(), notes.display().to_string());
+
+    assert!(is_attachment(Path::new(&image)));
+    assert!(!is_attachment(Path::new(&notes)));
+    assert!(!is_attachment(&dir.join("missing.png")));
+
+    let prompt = format!("What is wrong in @{image}? See @{notes} and @{image}.");
+    assert_eq!(attachments(&prompt), [image.clone()]);
+    let expanded = expand(&prompt);
+    assert!(expanded.contains("[Contents of"), "{expanded}");
+    assert!(!expanded.contains("not a text file"), "{expanded}");
+
+    let attachment = read(Path::new(&image)).unwrap();
+    assert_eq!(attachment.name, "error.png");
+    assert_eq!(attachment.mime_type, Some("image/png"));
+    assert_eq!(attachment.data.len(), 4);
+    assert_eq!(read(Path::new(&notes)).unwrap().mime_type, None);
+    assert!(read(&dir).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
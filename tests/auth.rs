@@ -0,0 +1,48 @@
+use deepseek_cli::auth::{TokenSource, expand_home, load_token, user_from_response};
+use deepseek_cli::config::Account;
+use serde_json::json;
+use std::path::Path;
+
+#[test]
+fn test_account_token_from_file() {
+    let path = std::env::temp_dir().join(format!("deepseek_auth_{}", std::process::id()));
+    std::fs::write(&path, "  secret-token\n").unwrap();
+    let account = Account {
+        token_env: Some("DEEPSEEK_TEST_UNSET_TOKEN_VAR".to_string()),
+        token_file: Some(path.clone()),
+    };
+    let (token, source) = load_token(Some(("deepseek-cli-test", &account))).unwrap();
+    assert_eq!(token, "secret-token");
+    assert_eq!(source, TokenSource::File(path.clone()));
+    std::fs::remove_file(&path).unwrap();
+
+    let error = load_token(Some(("deepseek-cli-test", &account)))
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("account deepseek-cli-test"), "{error}");
+}
+
+#[test]
+fn test_expand_home() {
+    assert_eq!(
+        expand_home(Path::new("/etc/token")),
+        Path::new("/etc/token")
+    );
+    if let Some(home) = dirs::home_dir() {
+        assert_eq!(expand_home(Path::new("~/.token")), home.join(".token"));
+    }
+}
+
+#[test]
+fn test_user_from_response() {
+    let body = json!({"code": 0, "data": {"biz_data": {"email": "", "mobile_number": "+1 555"}}});
+    assert_eq!(
+        user_from_response(&body).unwrap().as_deref(),
+        Some("+1 555")
+    );
+    assert_eq!(user_from_response(&json!({"code": 0})).unwrap(), None);
+    let error = user_from_response(&json!({"code": 40003, "msg": "INVALID_TOKEN"}))
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("INVALID_TOKEN"), "{error}");
+}
@@ -0,0 +1,59 @@
+use deepseek_cli::autonomous::{AutonomousRun, format_duration, parse_duration};
+use deepseek_cli::recipe::RecordedAction;
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_parse_duration() {
+    assert_eq!(parse_duration("15m").unwrap(), Duration::from_secs(900));
+    assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+    assert_eq!(parse_duration("90s").unwrap(), Duration::from_secs(90));
+    assert_eq!(parse_duration("10").unwrap(), Duration::from_secs(600));
+    assert!(parse_duration("0m").is_err());
+    assert!(parse_duration("15").is_ok());
+    assert!(parse_duration("1h30").is_err());
+    assert!(parse_duration("m").is_err());
+    assert_eq!(format_duration(Duration::from_secs(3900)), "1h05m");
+    assert_eq!(format_duration(Duration::from_secs(750)), "12m30s");
+}
+
+#[test]
+fn test_autonomous_run() {
+    let tools = vec!["read_file".to_string(), "write_file".to_string()];
+    let mut run = AutonomousRun::new(Duration::from_secs(600), Duration::from_secs(120), tools);
+    let start = Instant::now();
+    assert!(!run.is_running());
+    assert!(run.start(start, 1));
+    assert!(!run.start(start, 5));
+    assert!(run.allows("write_file"));
+    assert!(!run.allows("run_command"));
+
+    assert!(!run.report_due(start + Duration::from_secs(60)));
+    assert!(run.report_due(start + Duration::from_secs(130)));
+    assert!(!run.report_due(start + Duration::from_secs(200)));
+    assert!(!run.is_over(start + Duration::from_secs(599)));
+    assert!(run.is_over(start + Duration::from_secs(600)));
+
+    run.record_result(true);
+    run.record_result(false);
+    let actions = [
+        RecordedAction {
+            tool: "write_file".to_string(),
+            arg: "src/lib.rs\ncontent".to_string(),
+        },
+        RecordedAction {
+            tool: "run_command".to_string(),
+            arg: "cargo test".to_string(),
+        },
+    ];
+    assert_eq!(
+        run.report(
+            "Progress",
+            start + Duration::from_secs(150),
+            &actions,
+            "abc123"
+        ),
+        "=== Progress: 2m30s elapsed, 7m30s left ===\nTool calls: 2 (1 failed)\nFiles changed: src/lib.rs\nCommands run: cargo test\nCheckpoint: abc123"
+    );
+    run.finish();
+    assert!(!run.is_running());
+}
@@ -0,0 +1,60 @@
+use deepseek_cli::binary::{FileType, describe, file_type, is_binary};
+use std::io::Write;
+
+#[test]
+fn test_detect_binary() {
+    assert_eq!(
+        file_type(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"),
+        Some(FileType::Image("PNG image"))
+    );
+    assert_eq!(
+        file_type(b"\x7fELF\x02\x01\x01"),
+        Some(FileType::Executable("ELF executable or library"))
+    );
+    assert_eq!(
+        file_type(b"RIFF\0\0\0\0WEBPVP8 "),
+        Some(FileType::Image("WebP image"))
+    );
+    assert_eq!(file_type(b"fn main() {}"), None);
+
+    assert!(is_binary(b"\x89PNG\r\n\x1a\n"));
+    assert!(is_binary(b"abc\0def"));
+    assert!(is_binary(&[0xc3, 0x28, 0x01, 0x02, 0x03, 0x04, 0x05]));
+    assert!(!is_binary("Grüße aus München\n".as_bytes()));
+    // Latin-1 text is not UTF-8, but is still text
+    assert!(!is_binary(b"Gr\xfc\xdfe aus M\xfcnchen\n"));
+    // Cut off in the middle of a character
+    assert!(!is_binary(&"é".repeat(10).as_bytes()[..19]));
+}
+
+#[test]
+fn test_describe_archive() {
+    let path = std::env::temp_dir().join(format!("deepseek-binary-{}.zip", std::process::id()));
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+    let options = zip::write::SimpleFileOptions::default();
+    zip.start_file("src/lib.rs", options).unwrap();
+    zip.write_all(b"pub fn f() {}\n").unwrap();
+    zip.start_file("README.md", options).unwrap();
+    zip.write_all(b"# Demo\n").unwrap();
+    zip.finish().unwrap();
+
+    let sample = std::fs::read(&path).unwrap();
+    let size = sample.len() as u64;
+    let description = describe(&path, &sample, size);
+    assert!(
+        description.contains("is binary (zip archive, "),
+        "{description}"
+    );
+    assert!(
+        description.contains("  src/lib.rs (14 B)\n  README.md (7 B)"),
+        "{description}"
+    );
+    assert!(description.contains("run_command"), "{description}");
+    std::fs::remove_file(&path).unwrap();
+
+    let executable = describe(std::path::Path::new("app"), b"\x7fELF\x02", 2_000_000);
+    assert!(
+        executable.starts_with("app is binary (ELF executable or library, 1.9 MB)"),
+        "{executable}"
+    );
+}
@@ -0,0 +1,41 @@
+use deepseek_cli::branches::Branches;
+
+#[test]
+fn test_fork_and_switch_branches() {
+    let mut branches = Branches::new(Some(2));
+    branches.record(Some(2), 4, "Here is the plan:\n\n1. Read the config");
+    branches.record(Some(5), 6, "Done.");
+    assert_eq!(branches.current().head, Some(6));
+    assert_eq!(branches.messages().len(), 2);
+    assert_eq!(
+        branches.get(4).unwrap().preview,
+        "Here is the plan: 1. Read the config"
+    );
+
+    let fork = branches.fork(4, None).unwrap();
+    assert_eq!((fork.label.as_str(), fork.head), ("branch-2", Some(4)));
+    branches.record(Some(4), 8, "Another approach");
+    assert_eq!(branches.current().head, Some(8));
+
+    // The branch that was left keeps its head
+    assert_eq!(branches.switch("main").unwrap().head, Some(6));
+    assert_eq!(branches.switch("2").unwrap().head, Some(8));
+    assert!(branches.is_current(1));
+
+    branches.fork(6, Some("tests")).unwrap();
+    assert_eq!(branches.branches().len(), 3);
+    assert!(branches.fork(6, Some("tests")).is_err());
+    assert!(branches.fork(6, Some("two words")).is_err());
+    assert!(branches.fork(6, Some("7")).is_err());
+    assert!(branches.switch("4").is_err());
+    assert!(branches.switch("nope").is_err());
+}
+
+#[test]
+fn test_long_previews_are_cut() {
+    let mut branches = Branches::default();
+    branches.record(None, 2, &"word ".repeat(40));
+    let preview = &branches.get(2).unwrap().preview;
+    assert_eq!(preview.chars().count(), 61);
+    assert!(preview.ends_with('…'));
+}
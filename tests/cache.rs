@@ -0,0 +1,38 @@
+use deepseek_cli::cache::{Cache, Entry};
+use std::time::Duration;
+
+fn entry(content: &str) -> Entry {
+    Entry {
+        content: content.to_string(),
+        status: format!("Fetched {content}"),
+    }
+}
+
+#[test]
+fn test_cache_get_put_and_evict() {
+    let dir = std::env::temp_dir().join(format!("deepseek-cache-{}", std::process::id()));
+    let cache = Cache::new(dir.clone(), Duration::from_secs(3600), 2);
+    cache.clear().unwrap();
+    assert_eq!(cache.get("search", "rust"), None);
+
+    cache.put("search", "rust", &entry("a")).unwrap();
+    assert_eq!(cache.get("search", " rust "), Some(entry("a")));
+    assert_eq!(cache.get("fetch", "rust"), None);
+
+    // Beyond two entries, the oldest goes
+    std::thread::sleep(Duration::from_millis(20));
+    cache.put("search", "tokio", &entry("b")).unwrap();
+    std::thread::sleep(Duration::from_millis(20));
+    cache.put("fetch", "https://serde.rs", &entry("c")).unwrap();
+    assert_eq!(cache.get("search", "rust"), None);
+    assert_eq!(cache.get("search", "tokio"), Some(entry("b")));
+    assert_eq!(cache.get("fetch", "https://serde.rs"), Some(entry("c")));
+
+    // Entries older than the time to live are gone
+    let expiring = Cache::new(dir.clone(), Duration::from_millis(10), 10);
+    std::thread::sleep(Duration::from_millis(30));
+    assert_eq!(expiring.get("search", "tokio"), None);
+
+    cache.clear().unwrap();
+    assert!(!dir.exists());
+}
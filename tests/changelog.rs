@@ -0,0 +1,95 @@
+use deepseek_cli::changelog::{
+    Commit, Entry, Section, chunk_prompt, chunks, insert, parse_entries, parse_log, render,
+    unconventional,
+};
+
+fn commit(hash: &str, subject: &str, diff: &str) -> Commit {
+    Commit {
+        hash: hash.to_string(),
+        subject: subject.to_string(),
+        body: String::new(),
+        diff: diff.to_string(),
+    }
+}
+
+#[test]
+fn test_parse_log() {
+    let log = "0123456789abcdef\x1ffeat: add export\x1fExports chats as Markdown.\n\nCloses #4\n\x1e\nfedcba9876543210\x1ffix typo\x1f\x1e\n";
+    let commits = parse_log(log);
+    assert_eq!(
+        commits,
+        vec![
+            Commit {
+                hash: "0123456789abcdef".to_string(),
+                subject: "feat: add export".to_string(),
+                body: "Exports chats as Markdown.\n\nCloses #4".to_string(),
+                diff: String::new(),
+            },
+            commit("fedcba9876543210", "fix typo", ""),
+        ]
+    );
+    assert_eq!(commits[0].short_hash(), "0123456");
+    let flagged: Vec<&str> = unconventional(&commits)
+        .iter()
+        .map(|c| c.subject.as_str())
+        .collect();
+    assert_eq!(flagged, vec!["fix typo"]);
+    assert!(parse_log("").is_empty());
+}
+
+#[test]
+fn test_chunks() {
+    let commits = vec![
+        commit("aaaaaaaa", "feat: one", " src/a.rs | 1 +\n"),
+        commit("bbbbbbbb", "fix: two", ""),
+        commit("cccccccc", "docs: three", &"x".repeat(300)),
+    ];
+    let chunks = chunks(&commits, 200);
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(chunks[0].commits, 2);
+    assert_eq!(
+        chunks[0].text,
+        "commit aaaaaaa\nfeat: one\n\n```diff\n src/a.rs | 1 +\n```\n\ncommit bbbbbbb\nfix: two\n"
+    );
+    // A commit larger than a chunk goes on its own
+    assert_eq!(chunks[1].commits, 1);
+    assert!(chunks[1].text.starts_with("commit ccccccc\ndocs: three\n"));
+    assert!(
+        chunk_prompt(&chunks[1], 2, 2).starts_with("Part 2 of 2, 1 commit(s):\n\ncommit ccccccc")
+    );
+}
+
+#[test]
+fn test_parse_and_render_entries() {
+    let reply = "Here are the notes:\n\n- [feat] Chats can be exported as Markdown.\n[FIX] Pasting a long line no longer hangs.\n[breaking] The `--json` flag was renamed to `--output json`.\n[feat] Added `/style`.\n[whatever] ignored\n[docs]\n";
+    let entries = parse_entries(reply);
+    assert_eq!(entries.len(), 4);
+    assert_eq!(
+        entries[1],
+        Entry {
+            section: Section::Fixes,
+            text: "Pasting a long line no longer hangs.".to_string(),
+        }
+    );
+    assert_eq!(
+        render("v0.2.0", &entries),
+        "## v0.2.0\n\n### Breaking changes\n\n- The `--json` flag was renamed to `--output json`.\n\n### Features\n\n- Chats can be exported as Markdown.\n- Added `/style`.\n\n### Bug fixes\n\n- Pasting a long line no longer hangs.\n"
+    );
+}
+
+#[test]
+fn test_insert() {
+    let entry = "## v0.2.0\n\n### Features\n\n- New\n";
+    assert_eq!(insert("", entry), format!("# Changelog\n\n{entry}"));
+    assert_eq!(
+        insert(
+            "# Changelog\n\nAll notable changes.\n\n## v0.1.0\n\n- First\n",
+            entry
+        ),
+        "# Changelog\n\nAll notable changes.\n\n## v0.2.0\n\n### Features\n\n- New\n\n## v0.1.0\n\n- First\n"
+    );
+    assert_eq!(
+        insert("# Changelog\n", entry),
+        "# Changelog\n\n## v0.2.0\n\n### Features\n\n- New\n"
+    );
+}
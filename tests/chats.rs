@@ -0,0 +1,31 @@
+use deepseek_cli::chats::ChatRegistry;
+
+#[test]
+fn test_chat_registry_resolve_rename_remove() {
+    let mut registry = ChatRegistry::default();
+    registry.touch("chat-a", Some("How do I parse TOML in Rust?"));
+    registry.touch("chat-b", None);
+
+    assert_eq!(registry.list().len(), 2);
+    let chat_a = registry.resolve("chat-a").expect("chat-a should be known");
+    assert_eq!(chat_a.title, "How do I parse TOML in Rust?");
+
+    // Later messages don't overwrite the title
+    registry.touch("chat-a", Some("follow-up question"));
+    assert_eq!(
+        registry.resolve("chat-a").unwrap().title,
+        "How do I parse TOML in Rust?"
+    );
+
+    assert!(registry.rename("chat-b", "Release prep"));
+    assert_eq!(registry.resolve("chat-b").unwrap().title, "Release prep");
+    assert!(!registry.rename("missing", "whatever"));
+
+    // Positional keys are 1-based
+    assert!(registry.resolve("1").is_some());
+    assert!(registry.resolve("3").is_none());
+
+    let removed = registry.remove("chat-a").expect("chat-a should be removed");
+    assert_eq!(removed.id, "chat-a");
+    assert_eq!(registry.list().len(), 1);
+}
@@ -0,0 +1,32 @@
+use deepseek_cli::checkpoint::{self, Backend, Checkpoint};
+use std::path::PathBuf;
+
+fn checkpoint(backend: Backend, id: &str) -> Checkpoint {
+    Checkpoint {
+        backend,
+        id: id.to_string(),
+        root: PathBuf::from("."),
+        message: String::new(),
+    }
+}
+
+#[test]
+fn test_detect_plain_directory() {
+    let dir = std::env::temp_dir().join(format!("deepseek-checkpoint-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    // The temp dir is not inside a repository
+    assert_eq!(Backend::detect(&dir), Backend::Snapshot);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_short_id_and_find() {
+    let commit = checkpoint(Backend::Git, "0123456789abcdef0123456789abcdef01234567");
+    assert_eq!(commit.short_id(), "0123456789ab");
+    let snapshot = checkpoint(Backend::Snapshot, "20260101T120000.000");
+    assert_eq!(snapshot.short_id(), "20260101T120000.000");
+
+    let checkpoints = [commit.clone(), snapshot];
+    assert_eq!(checkpoint::find(&checkpoints, "0123"), Some(&commit));
+    assert_eq!(checkpoint::find(&checkpoints, "ffff"), None);
+}
@@ -0,0 +1,426 @@
+use deepseek_cli::cli::{
+    AuthCommand, ChangelogOptions, ChatOptions, Cli, CliCommand, ExplainOptions, FixOptions,
+    OutputFormat, RunOptions, WatchOptions,
+};
+use deepseek_cli::fix::FixTarget;
+use deepseek_cli::format::ResponseFormat;
+use deepseek_cli::import::ImportFormat;
+use deepseek_cli::model::Model;
+use deepseek_cli::review::ReviewSource;
+use std::path::PathBuf;
+use std::time::Duration;
+
+fn parse(args: &[&str]) -> anyhow::Result<Cli> {
+    let args: Vec<String> = args.iter().map(ToString::to_string).collect();
+    Cli::parse(&args)
+}
+
+#[test]
+fn test_cli_parse() {
+    assert_eq!(
+        parse(&[]).unwrap().command,
+        CliCommand::Chat(ChatOptions::default())
+    );
+    assert_eq!(
+        parse(&["--tui"]).unwrap().command,
+        CliCommand::Chat(ChatOptions {
+            tui: true,
+            ..ChatOptions::default()
+        })
+    );
+    assert_eq!(
+        parse(&["abc-123"]).unwrap().command,
+        CliCommand::Chat(ChatOptions {
+            resume: Some("abc-123".to_string()),
+            ..ChatOptions::default()
+        })
+    );
+    assert_eq!(
+        parse(&["chats", "rename", "2", "Release", "prep"])
+            .unwrap()
+            .command,
+        CliCommand::Chats(vec![
+            "rename".to_string(),
+            "2".to_string(),
+            "Release".to_string(),
+            "prep".to_string()
+        ])
+    );
+    assert_eq!(
+        parse(&[
+            "import",
+            "--format=aider",
+            "history.md",
+            "--output",
+            "out.md"
+        ])
+        .unwrap()
+        .command,
+        CliCommand::Import {
+            path: PathBuf::from("history.md"),
+            format: Some(ImportFormat::Aider),
+            output: Some(PathBuf::from("out.md")),
+        }
+    );
+    assert_eq!(
+        parse(&["2", "--share", "-q"]).unwrap().command,
+        CliCommand::Chat(ChatOptions {
+            resume: Some("2".to_string()),
+            share: true,
+            quiet: true,
+            ..ChatOptions::default()
+        })
+    );
+    assert_eq!(
+        parse(&["--output", "json"]).unwrap().command,
+        CliCommand::Chat(ChatOptions {
+            output: OutputFormat::Json,
+            ..ChatOptions::default()
+        })
+    );
+    assert_eq!(
+        parse(&["observe", "abc-123"]).unwrap().command,
+        CliCommand::Observe("abc-123".to_string())
+    );
+    assert_eq!(
+        parse(&["web"]).unwrap().command,
+        CliCommand::Web {
+            port: 8321,
+            dry_run: false
+        }
+    );
+    assert_eq!(
+        parse(&["web", "--port", "9000", "--dry-run"])
+            .unwrap()
+            .command,
+        CliCommand::Web {
+            port: 9000,
+            dry_run: true
+        }
+    );
+    assert_eq!(
+        parse(&["review"]).unwrap().command,
+        CliCommand::Review(ReviewSource::Diff(None))
+    );
+    assert_eq!(
+        parse(&["review", "--diff", "main...HEAD"]).unwrap().command,
+        CliCommand::Review(ReviewSource::Diff(Some("main...HEAD".to_string())))
+    );
+    assert_eq!(
+        parse(&["review", "--pr", "https://github.com/o/r/pull/7"])
+            .unwrap()
+            .command,
+        CliCommand::Review(ReviewSource::PullRequest(
+            "https://github.com/o/r/pull/7".to_string()
+        ))
+    );
+    assert_eq!(
+        parse(&["run", "--task", "task.md", "--max-turns", "20", "--dry-run"])
+            .unwrap()
+            .command,
+        CliCommand::Run(RunOptions {
+            task: PathBuf::from("task.md"),
+            report: PathBuf::from("deepseek-report.md"),
+            chat: ChatOptions {
+                max_iterations: Some(20),
+                dry_run: true,
+                ..ChatOptions::default()
+            },
+        })
+    );
+    assert_eq!(
+        parse(&["fix-tests", "--cmd", "npm test", "--max-rounds", "3"])
+            .unwrap()
+            .command,
+        CliCommand::Fix(FixOptions {
+            target: FixTarget::Tests,
+            command: Some("npm test".to_string()),
+            max_rounds: 3,
+            chat: ChatOptions::default(),
+        })
+    );
+    assert_eq!(
+        parse(&["fix-tests"]).unwrap().command,
+        CliCommand::Fix(FixOptions {
+            target: FixTarget::Tests,
+            command: None,
+            max_rounds: 5,
+            chat: ChatOptions::default(),
+        })
+    );
+    assert_eq!(
+        parse(&["changelog"]).unwrap().command,
+        CliCommand::Changelog(ChangelogOptions {
+            since: None,
+            title: "Unreleased".to_string(),
+            output: PathBuf::from("CHANGELOG.md"),
+        })
+    );
+    assert_eq!(
+        parse(&[
+            "changelog",
+            "--since",
+            "v0.1.0",
+            "--title",
+            "v0.2.0",
+            "--output",
+            "docs/CHANGES.md"
+        ])
+        .unwrap()
+        .command,
+        CliCommand::Changelog(ChangelogOptions {
+            since: Some("v0.1.0".to_string()),
+            title: "v0.2.0".to_string(),
+            output: PathBuf::from("docs/CHANGES.md"),
+        })
+    );
+    assert_eq!(
+        parse(&["explain", "src/lib.rs#Counter::new", "--model", "reasoner"])
+            .unwrap()
+            .command,
+        CliCommand::Explain(ExplainOptions {
+            target: "src/lib.rs#Counter::new".to_string(),
+            chat: ChatOptions {
+                model: Some(Model::Reasoner),
+                ..ChatOptions::default()
+            },
+        })
+    );
+    assert_eq!(
+        parse(&["fix-build", "--cmd", "cargo clippy"])
+            .unwrap()
+            .command,
+        CliCommand::Fix(FixOptions {
+            target: FixTarget::Build,
+            command: Some("cargo clippy".to_string()),
+            max_rounds: 5,
+            chat: ChatOptions::default(),
+        })
+    );
+    assert_eq!(
+        parse(&[
+            "watch",
+            "--on-change",
+            "run cargo check and fix errors",
+            "--glob",
+            "**/*.rs",
+            "--glob",
+            "Cargo.toml",
+            "--debounce",
+            "250",
+            "-q"
+        ])
+        .unwrap()
+        .command,
+        CliCommand::Watch(WatchOptions {
+            prompt: "run cargo check and fix errors".to_string(),
+            globs: vec!["**/*.rs".to_string(), "Cargo.toml".to_string()],
+            debounce: Duration::from_millis(250),
+            chat: ChatOptions {
+                quiet: true,
+                ..ChatOptions::default()
+            },
+        })
+    );
+
+    assert_eq!(
+        parse(&["new", "--workflow", "release-prep", "-q"])
+            .unwrap()
+            .command,
+        CliCommand::Chat(ChatOptions {
+            quiet: true,
+            workflow: Some("release-prep".to_string()),
+            ..ChatOptions::default()
+        })
+    );
+
+    assert_eq!(
+        parse(&["abc-123", "-p", "Summarize the diff"])
+            .unwrap()
+            .command,
+        CliCommand::Chat(ChatOptions {
+            resume: Some("abc-123".to_string()),
+            print: Some("Summarize the diff".to_string()),
+            ..ChatOptions::default()
+        })
+    );
+
+    assert_eq!(
+        parse(&[
+            "-p",
+            "List the crates",
+            "--format=json",
+            "--max-output-tokens",
+            "200",
+            "--max-iterations=10"
+        ])
+        .unwrap()
+        .command,
+        CliCommand::Chat(ChatOptions {
+            print: Some("List the crates".to_string()),
+            format: Some(ResponseFormat::Json),
+            max_output_tokens: Some(200),
+            max_iterations: Some(10),
+            ..ChatOptions::default()
+        })
+    );
+
+    assert_eq!(
+        parse(&["--model", "deepseek-chat"]).unwrap().command,
+        CliCommand::Chat(ChatOptions {
+            model: Some(Model::Chat),
+            ..ChatOptions::default()
+        })
+    );
+
+    assert_eq!(
+        parse(&[
+            "new",
+            "--workflow",
+            "triage",
+            "--var",
+            "TICKET=ABC-1",
+            "--var=AREA=auth"
+        ])
+        .unwrap()
+        .command,
+        CliCommand::Chat(ChatOptions {
+            workflow: Some("triage".to_string()),
+            vars: vec!["TICKET=ABC-1".to_string(), "AREA=auth".to_string()],
+            ..ChatOptions::default()
+        })
+    );
+    assert_eq!(
+        parse(&["--autonomous", "15m", "--report-every=2m"])
+            .unwrap()
+            .command,
+        CliCommand::Chat(ChatOptions {
+            autonomous: Some(Duration::from_secs(15 * 60)),
+            report_every: Some(Duration::from_secs(2 * 60)),
+            ..ChatOptions::default()
+        })
+    );
+
+    assert_eq!(
+        parse(&["--session", "myproject", "--quiet"])
+            .unwrap()
+            .command,
+        CliCommand::Chat(ChatOptions {
+            session: Some("myproject".to_string()),
+            quiet: true,
+            ..ChatOptions::default()
+        })
+    );
+
+    assert_eq!(
+        parse(&["--dry-run"]).unwrap().command,
+        CliCommand::Chat(ChatOptions {
+            dry_run: true,
+            ..ChatOptions::default()
+        })
+    );
+    assert_eq!(
+        parse(&["--no-search", "--model", "chat"]).unwrap().command,
+        CliCommand::Chat(ChatOptions {
+            no_search: true,
+            model: Some(Model::Chat),
+            ..ChatOptions::default()
+        })
+    );
+    assert_eq!(
+        parse(&["-p", "hi", "--mock", "tests/fixture.json"])
+            .unwrap()
+            .command,
+        CliCommand::Chat(ChatOptions {
+            print: Some("hi".to_string()),
+            mock: Some(PathBuf::from("tests/fixture.json")),
+            ..ChatOptions::default()
+        })
+    );
+    assert!(parse(&["--mock", "a.json", "--record", "b.json"]).is_err());
+    assert_eq!(
+        parse(&["--log-dir", "/var/log/deepseek"]).unwrap().command,
+        CliCommand::Chat(ChatOptions {
+            log_dir: Some(PathBuf::from("/var/log/deepseek")),
+            ..ChatOptions::default()
+        })
+    );
+    assert_eq!(
+        parse(&["new", "--profile=reviewer"]).unwrap().command,
+        CliCommand::Chat(ChatOptions {
+            profile: Some("reviewer".to_string()),
+            ..ChatOptions::default()
+        })
+    );
+
+    assert_eq!(
+        parse(&["auth", "login"]).unwrap().command,
+        CliCommand::Auth(AuthCommand::Login)
+    );
+    assert_eq!(
+        parse(&["auth", "logout"]).unwrap().command,
+        CliCommand::Auth(AuthCommand::Logout)
+    );
+    let cli = parse(&["--account", "work", "auth", "status"]).unwrap();
+    assert_eq!(cli.command, CliCommand::Auth(AuthCommand::Status));
+    assert_eq!(cli.account.as_deref(), Some("work"));
+    let cli = parse(&["abc-123", "--account=personal"]).unwrap();
+    assert_eq!(cli.account.as_deref(), Some("personal"));
+    assert_eq!(parse(&["-q"]).unwrap().account, None);
+    let cli = parse(&["-v", "--verbose", "--log-file", "debug.log", "-q"]).unwrap();
+    assert_eq!(cli.verbosity, 2);
+    assert_eq!(cli.log_file, Some(PathBuf::from("debug.log")));
+    assert_eq!(parse(&["-vv", "auth", "status"]).unwrap().verbosity, 2);
+
+    assert!(parse(&["--bogus"]).is_err());
+    assert!(parse(&["web", "--port", "http"]).is_err());
+    assert!(
+        parse(&[
+            "review",
+            "--diff",
+            "main",
+            "--pr",
+            "https://github.com/o/r/pull/1"
+        ])
+        .is_err()
+    );
+    assert!(parse(&["review", "HEAD~1"]).is_err());
+    assert!(parse(&["run"]).is_err());
+    assert!(parse(&["run", "--task", "t.md", "--max-turns", "0"]).is_err());
+    assert!(parse(&["run", "--task", "t.md", "--tui"]).is_err());
+    assert!(parse(&["run", "--task", "t.md", "abc-123"]).is_err());
+    assert!(parse(&["fix-tests", "--max-rounds", "0"]).is_err());
+    assert!(parse(&["fix-tests", "-p", "hi"]).is_err());
+    assert!(parse(&["fix-build", "--tui"]).is_err());
+    assert!(parse(&["explain"]).is_err());
+    assert!(parse(&["changelog", "v0.1.0"]).is_err());
+    assert!(parse(&["changelog", "--model", "chat"]).is_err());
+    assert!(parse(&["explain", "run", "-p", "hi"]).is_err());
+    assert!(parse(&["explain", "run", "main"]).is_err());
+    assert!(parse(&["watch"]).is_err());
+    assert!(parse(&["watch", "--on-change", "fix it", "--debounce", "1s"]).is_err());
+    assert!(parse(&["watch", "--on-change", "fix it", "--tui"]).is_err());
+    assert!(parse(&["import"]).is_err());
+    assert!(parse(&["observe"]).is_err());
+    assert!(parse(&["new", "abc-123"]).is_err());
+    assert!(parse(&["abc-123", "--workflow", "triage"]).is_err());
+    assert!(parse(&["--output", "yaml"]).is_err());
+    assert!(parse(&["--format", "yaml"]).is_err());
+    assert!(parse(&["--model", "gpt-4"]).is_err());
+    assert!(parse(&["--max-output-tokens", "0"]).is_err());
+    assert!(parse(&["--max-iterations", "many"]).is_err());
+    assert!(parse(&["--output-schema", "schema.json"]).is_err());
+    assert!(parse(&["-p", "hi", "--output-schema", "s.json", "--format", "plain"]).is_err());
+    assert!(parse(&["--tui", "--output", "json"]).is_err());
+    assert!(parse(&["--autonomous", "soon"]).is_err());
+    assert!(parse(&["--autonomous", "15m", "--tui"]).is_err());
+    assert!(parse(&["--report-every", "5m"]).is_err());
+    assert!(parse(&["-p", "hi", "--output", "json"]).is_err());
+    assert!(parse(&["import", "a.json", "--format"]).is_err());
+    assert!(parse(&["abc-123", "--session", "myproject"]).is_err());
+    assert!(parse(&["new", "--session", "myproject"]).is_err());
+    assert!(parse(&["auth"]).is_err());
+    assert!(parse(&["auth", "whoami"]).is_err());
+    assert!(parse(&["--account"]).is_err());
+    assert!(parse(&["--session", "my project"]).is_err());
+}
@@ -0,0 +1,36 @@
+use deepseek_cli::commands::{NAMES, SlashCommand};
+
+#[test]
+fn test_parse_slash_commands() {
+    assert_eq!(SlashCommand::parse("/exit"), Some(SlashCommand::Exit));
+    assert_eq!(
+        SlashCommand::parse("  /focus src/tools.rs  "),
+        Some(SlashCommand::Focus("src/tools.rs".to_string()))
+    );
+    assert_eq!(
+        SlashCommand::parse("/focus"),
+        Some(SlashCommand::Focus(String::new()))
+    );
+    assert_eq!(
+        SlashCommand::parse("/frobnicate now"),
+        Some(SlashCommand::Unknown("frobnicate".to_string()))
+    );
+
+    // Regular messages, including ones that start with an absolute path
+    assert_eq!(SlashCommand::parse("hello"), None);
+    assert_eq!(SlashCommand::parse("/usr/bin is missing"), None);
+    assert_eq!(SlashCommand::parse("/"), None);
+}
+
+#[test]
+fn test_command_names_parse() {
+    for name in NAMES {
+        assert!(
+            !matches!(
+                SlashCommand::parse(&format!("/{name}")),
+                None | Some(SlashCommand::Unknown(_))
+            ),
+            "/{name}"
+        );
+    }
+}
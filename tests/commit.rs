@@ -0,0 +1,77 @@
+use deepseek_cli::commit::{self, StagedChanges};
+use std::path::Path;
+use std::process::Command;
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {args:?} failed");
+}
+
+#[test]
+fn test_extract_commit_message() {
+    let reply = "Here is the message:\n\n```\nfix(cli): reject --max-turns 0\n\nA run with no turns could never finish.\n```\n";
+    assert_eq!(
+        commit::extract_message(reply),
+        "fix(cli): reject --max-turns 0\n\nA run with no turns could never finish."
+    );
+    assert_eq!(
+        commit::extract_message("  docs: fix typo\n"),
+        "docs: fix typo"
+    );
+}
+
+#[test]
+fn test_is_conventional() {
+    assert!(commit::is_conventional("feat: add /commit"));
+    assert!(commit::is_conventional("fix(tools): keep line endings"));
+    assert!(commit::is_conventional(
+        "refactor(api)!: drop the v0 client"
+    ));
+    assert!(!commit::is_conventional("Add /commit"));
+    assert!(!commit::is_conventional("feat(): empty scope"));
+    assert!(!commit::is_conventional("Feat: capitalized"));
+    assert!(!commit::is_conventional("feat: "));
+}
+
+#[test]
+fn test_commit_prompt() {
+    let prompt = commit::prompt(&StagedChanges {
+        status: "M  src/cli.rs\n".to_string(),
+        diff: "-old\n+new\n".to_string(),
+        truncated: true,
+    });
+    assert!(prompt.contains("Conventional Commits"));
+    assert!(prompt.contains("```\nM  src/cli.rs\n```"));
+    assert!(prompt.contains("```diff\n-old\n+new\n```"));
+    assert!(prompt.contains("The diff was cut"));
+}
+
+#[test]
+fn test_stage_and_commit() {
+    let dir = std::env::temp_dir().join(format!("deepseek-commit-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    git(&dir, &["init", "-q"]);
+    git(&dir, &["config", "user.name", "Test"]);
+    git(&dir, &["config", "user.email", "test@example.com"]);
+    assert!(commit::is_repository(&dir));
+    assert_eq!(commit::staged(&dir).unwrap(), None);
+
+    std::fs::write(dir.join("notes.txt"), "hello\n").unwrap();
+    let staged = commit::stage(&dir, &["notes.txt".to_string(), "gone.txt".to_string()]);
+    assert_eq!(staged, ["notes.txt"], "paths git does not know are skipped");
+    let changes = commit::staged(&dir).unwrap().unwrap();
+    assert!(changes.diff.contains("+hello"));
+    assert!(changes.status.contains("notes.txt"));
+    assert!(!changes.truncated);
+
+    assert!(commit::commit(&dir, "  ").is_err());
+    let summary = commit::commit(&dir, "docs: add notes").unwrap();
+    assert!(summary.contains("docs: add notes"), "{summary}");
+    assert_eq!(commit::staged(&dir).unwrap(), None);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
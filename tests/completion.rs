@@ -0,0 +1,34 @@
+use deepseek_cli::completion::complete;
+
+#[test]
+fn test_complete_commands() {
+    assert_eq!(
+        complete("/e", 2),
+        (
+            0,
+            vec![
+                "/execute".to_string(),
+                "/exit".to_string(),
+                "/export".to_string()
+            ]
+        )
+    );
+    assert_eq!(complete("  /unf", 6), (2, vec!["/unfocus".to_string()]));
+    // Only the first word can be a command
+    assert_eq!(complete("see /e", 6).1, Vec::<String>::new());
+}
+
+#[test]
+fn test_complete_paths() {
+    // Tests run from the crate root
+    let line = "explain @src/mai";
+    assert_eq!(
+        complete(line, line.len()),
+        (9, vec!["src/main.rs".to_string()])
+    );
+    let line = "@Cargo.t and more";
+    assert_eq!(complete(line, 8), (1, vec!["Cargo.toml".to_string()]));
+    let line = "/focus sr";
+    assert_eq!(complete(line, line.len()), (7, vec!["src/".to_string()]));
+    assert!(complete("no mention", 10).1.is_empty());
+}
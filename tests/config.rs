@@ -0,0 +1,169 @@
+use deepseek_cli::config::{
+    Config, PROJECT_FILE, Profile, RetryConfig, Workflow, find_project_config,
+};
+use deepseek_cli::model::Model;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[test]
+fn test_retry_backoff() {
+    let retry = RetryConfig::default();
+    assert_eq!(retry.backoff(1), Duration::from_secs(1));
+    assert_eq!(retry.backoff(2), Duration::from_secs(2));
+    assert_eq!(retry.backoff(3), Duration::from_secs(4));
+    assert_eq!(retry.backoff(10), Duration::from_secs(30));
+    assert_eq!(retry.backoff(200), Duration::from_secs(30));
+}
+
+#[test]
+fn test_workflows() {
+    let config: Config = toml::from_str(
+        r#"
+[workflows.release-prep]
+system_prompt = "You are preparing a release."
+pin = ["CHANGELOG.md", "Cargo.toml"]
+tools = ["read_file", "run_command"]
+instruction = "Draft the changelog entry for the next version."
+
+[workflows.triage]
+instruction = "Help me triage a new bug report."
+"#,
+    )
+    .unwrap();
+    let release = config.workflow("release-prep").unwrap();
+    assert_eq!(release.pin, ["CHANGELOG.md", "Cargo.toml"]);
+    assert_eq!(
+        release.tools.as_deref(),
+        Some(&["read_file".to_string(), "run_command".to_string()][..])
+    );
+    assert_eq!(
+        config.workflow("triage").unwrap(),
+        &Workflow {
+            instruction: Some("Help me triage a new bug report.".to_string()),
+            ..Workflow::default()
+        }
+    );
+    let error = config.workflow("deploy").unwrap_err().to_string();
+    assert!(error.contains("release-prep, triage"), "{error}");
+}
+
+#[test]
+fn test_profiles() {
+    let config: Config = toml::from_str(
+        r#"
+profile = "reviewer"
+
+[profiles.reviewer]
+instructions = "Only comment on security problems."
+tools = ["read_file", "grep"]
+
+[profiles.writer]
+system_prompt = "You are a technical writer."
+"#,
+    )
+    .unwrap();
+    assert_eq!(config.profile.as_deref(), Some("reviewer"));
+    let reviewer = config.profile("reviewer").unwrap();
+    assert_eq!(
+        reviewer,
+        Profile {
+            instructions: Some("Only comment on security problems.".to_string()),
+            tools: Some(vec!["read_file".to_string(), "grep".to_string()]),
+            ..Profile::default()
+        }
+    );
+    let sysadmin = config.profile("sysadmin").unwrap();
+    assert!(sysadmin.tools.unwrap().contains(&"run_command".to_string()));
+    assert_eq!(
+        config.profile_names(),
+        ["coder", "reviewer", "sysadmin", "writer"]
+    );
+    let error = config.profile("dba").unwrap_err().to_string();
+    assert!(
+        error.contains("coder, reviewer, sysadmin, writer"),
+        "{error}"
+    );
+}
+
+#[test]
+fn test_project_config_overrides_global() {
+    let config = Config::layered(
+        r#"
+model = "chat"
+auto_approve = ["read_file"]
+
+[retry]
+attempts = 5
+max_delay_ms = 10000
+
+[workflows.triage]
+instruction = "Help me triage a new bug report."
+"#,
+        r#"
+model = "reasoner"
+tools = ["read_file", "grep", "edit_lines"]
+auto_approve = ["edit_lines"]
+sandbox_root = "src"
+context = ["docs/ARCHITECTURE.md"]
+
+[retry]
+attempts = 1
+"#,
+    )
+    .unwrap();
+    assert_eq!(config.default_model().unwrap(), Some(Model::Reasoner));
+    assert_eq!(config.auto_approve, ["edit_lines"]);
+    assert!(config.allows_tool("grep"));
+    assert!(!config.allows_tool("run_command"));
+    assert_eq!(config.sandbox_root.as_deref(), Some(Path::new("src")));
+    assert_eq!(config.context, [PathBuf::from("docs/ARCHITECTURE.md")]);
+    // Tables are merged rather than replaced
+    assert_eq!(config.retry.attempts, 1);
+    assert_eq!(config.retry.max_delay_ms, 10_000);
+    assert!(config.workflow("triage").is_ok());
+
+    let config = Config::layered("", "model = \"gpt-4\"").unwrap();
+    assert!(config.default_model().is_err());
+    assert!(Config::layered("", "tools = \"read_file\"").is_err());
+}
+
+#[test]
+fn test_find_project_config() {
+    let root = std::env::temp_dir().join(format!("deepseek_project_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    let nested = root.join("crates/core/src");
+    std::fs::create_dir_all(&nested).unwrap();
+    std::fs::create_dir_all(root.join(".deepseek")).unwrap();
+    std::fs::write(root.join(PROJECT_FILE), "model = \"chat\"\n").unwrap();
+
+    assert_eq!(find_project_config(&nested), Some(root.join(PROJECT_FILE)));
+    assert_eq!(find_project_config(&root), Some(root.join(PROJECT_FILE)));
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_accounts() {
+    let config: Config = toml::from_str(
+        r#"
+account = "work"
+
+[accounts.work]
+token_env = "DEEPSEEK_WORK_TOKEN"
+
+[accounts.personal]
+token_file = "~/.deepseek_personal"
+"#,
+    )
+    .unwrap();
+    assert_eq!(config.account.as_deref(), Some("work"));
+    assert_eq!(
+        config.account("work").unwrap().token_env.as_deref(),
+        Some("DEEPSEEK_WORK_TOKEN")
+    );
+    assert_eq!(
+        config.account("personal").unwrap().token_file,
+        Some(PathBuf::from("~/.deepseek_personal"))
+    );
+    let error = config.account("ci").unwrap_err().to_string();
+    assert!(error.contains("personal, work"), "{error}");
+}
@@ -0,0 +1,78 @@
+use deepseek_cli::context::{ContextLedger, DropPlan, Section, Source, label, parse_selection};
+use std::path::PathBuf;
+
+fn ledger() -> ContextLedger {
+    let mut ledger = ContextLedger::default();
+    ledger.record(Source::SystemPrompt, "role and tools", 2100);
+    ledger.record(Source::Environment, "OS, shell, working directory", 80);
+    ledger.record(
+        Source::Pinned(PathBuf::from("src/main.rs")),
+        "src/main.rs",
+        9000,
+    );
+    ledger.record(Source::Message, "Why does the build fail?", 12);
+    ledger.record(Source::ToolResult, "run_command cargo build", 3400);
+    ledger.record(Source::Reply, "The linker is missing.", 40);
+    ledger
+}
+
+#[test]
+fn test_render_lists_numbered_items() {
+    let ledger = ledger();
+    assert_eq!(ledger.total_tokens(), 14_632);
+    let rendered = ledger.render();
+    let lines: Vec<&str> = rendered.lines().collect();
+    assert_eq!(lines[0], "~14.6k tokens in the context of this chat:");
+    assert_eq!(lines[3], "3. pinned file   ~  9.0k  src/main.rs");
+    assert_eq!(
+        lines[5],
+        "5. tool result   ~  3.4k  run_command cargo build"
+    );
+    assert!(lines.last().unwrap().starts_with("/context drop"));
+
+    assert_eq!(
+        ContextLedger::default().render(),
+        "Nothing has been sent in this chat yet"
+    );
+}
+
+#[test]
+fn test_plan_drop() {
+    let mut ledger = ledger();
+    let plan = ledger.plan_drop(&[2, 3, 5]).unwrap();
+    assert_eq!(
+        plan,
+        DropPlan {
+            sections: vec![Section::Environment],
+            unpin: vec![PathBuf::from("src/main.rs")],
+            forget: vec!["the tool result run_command cargo build".to_string()],
+        }
+    );
+    let error = ledger.plan_drop(&[1]).unwrap_err().to_string();
+    assert!(error.contains("cannot be dropped"), "{error}");
+    assert!(ledger.plan_drop(&[7]).is_err());
+
+    // What is left out stays left out in the chats that follow
+    ledger.omit(&plan.sections);
+    ledger.clear();
+    assert!(ledger.items().is_empty());
+    assert!(ledger.omits(Section::Environment));
+    assert!(!ledger.omits(Section::Memory));
+}
+
+#[test]
+fn test_parse_selection() {
+    assert_eq!(parse_selection("3").unwrap(), [3]);
+    assert_eq!(parse_selection("5-7, 2,3").unwrap(), [2, 3, 5, 6, 7]);
+    assert_eq!(parse_selection("4 4").unwrap(), [4]);
+    assert!(parse_selection("").is_err());
+    assert!(parse_selection("7-5").is_err());
+    assert!(parse_selection("two").is_err());
+}
+
+#[test]
+fn test_label() {
+    assert_eq!(label("  Fix the build\nIt fails on CI  "), "Fix the build");
+    let long = "x".repeat(80);
+    assert_eq!(label(&long), format!("{}…", "x".repeat(60)));
+}
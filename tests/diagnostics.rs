@@ -0,0 +1,52 @@
+use deepseek_cli::diagnostics::{Diagnostic, Location, json_command, parse_cargo};
+
+const ERROR: &str = r#"{"reason":"compiler-message","package_id":"demo 0.1.0","target":{"name":"demo"},"message":{"rendered":"error[E0308]: mismatched types\n...","message":"mismatched types","level":"error","code":{"code":"E0308","explanation":"..."},"spans":[{"file_name":"src/main.rs","line_start":3,"line_end":3,"column_start":18,"column_end":25,"is_primary":true,"label":"expected `u32`, found `&str`","suggested_replacement":null,"text":[]}],"children":[{"message":"try using a conversion method","level":"help","code":null,"spans":[{"file_name":"src/main.rs","line_start":3,"column_start":18,"is_primary":true,"label":null,"suggested_replacement":"\"1\".parse()"}],"children":[]}]}}"#;
+
+#[test]
+fn test_parse_cargo() {
+    let summary = r#"{"reason":"compiler-message","message":{"message":"aborting due to 1 previous error","level":"error","code":null,"spans":[],"children":[]}}"#;
+    let artifact = r#"{"reason":"compiler-artifact","package_id":"dep 1.0.0"}"#;
+    let output = format!(
+        "{artifact}\n{ERROR}\n{ERROR}\n{summary}\n{{\"reason\":\"build-finished\",\"success\":false}}\nerror: could not compile `demo`\n"
+    );
+    let diagnostics = parse_cargo(&output);
+    assert_eq!(
+        diagnostics,
+        vec![Diagnostic {
+            level: "error".to_string(),
+            code: Some("E0308".to_string()),
+            message: "mismatched types".to_string(),
+            location: Some(Location {
+                file: "src/main.rs".to_string(),
+                line: 3,
+                column: 18,
+            }),
+            label: Some("expected `u32`, found `&str`".to_string()),
+            notes: vec![
+                "help: try using a conversion method (src/main.rs:3:18: `\"1\".parse()`)"
+                    .to_string()
+            ],
+        }]
+    );
+    assert!(diagnostics[0].is_error());
+    assert_eq!(
+        diagnostics[0].render(),
+        "src/main.rs:3:18: error[E0308]: mismatched types: expected `u32`, found `&str`\n  help: try using a conversion method (src/main.rs:3:18: `\"1\".parse()`)"
+    );
+    assert!(parse_cargo("error: could not find `Cargo.toml`").is_empty());
+}
+
+#[test]
+fn test_json_command() {
+    assert_eq!(
+        json_command("cargo check").as_deref(),
+        Some("cargo check --message-format json")
+    );
+    assert_eq!(
+        json_command("cargo clippy --all-targets -- -D warnings").as_deref(),
+        Some("cargo clippy --all-targets --message-format json -- -D warnings")
+    );
+    assert_eq!(json_command("cargo check --message-format=short"), None);
+    assert_eq!(json_command("cargo fmt"), None);
+    assert_eq!(json_command("go build ./..."), None);
+}
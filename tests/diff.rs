@@ -0,0 +1,37 @@
+use deepseek_cli::diff::{WordChange, unified_diff, word_diff};
+
+#[test]
+fn test_word_diff() {
+    let changes = word_diff("use a TOML file", "use a YAML file\nplease").unwrap();
+    assert_eq!(
+        changes,
+        vec![
+            WordChange::Same("use"),
+            WordChange::Same("a"),
+            WordChange::Removed("TOML"),
+            WordChange::Added("YAML"),
+            WordChange::Same("file"),
+            WordChange::Added("\n"),
+            WordChange::Added("please"),
+        ]
+    );
+
+    let unchanged = word_diff("same  text", "same text").unwrap();
+    assert!(unchanged.iter().all(|c| matches!(c, WordChange::Same(_))));
+}
+
+#[test]
+fn test_unified_diff() {
+    let old = "a\nb\nc\nd\ne\nf\ng\nh\n";
+    let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\n";
+    assert_eq!(
+        unified_diff(old, new, 1).unwrap(),
+        "@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n@@ -8,1 +8,2 @@\n h\n+i\n"
+    );
+    // Nearby changes share one hunk
+    assert_eq!(
+        unified_diff("a\nb\nc\n", "A\nb\nC\n", 1).unwrap(),
+        "@@ -1,3 +1,3 @@\n-a\n+A\n b\n-c\n+C\n"
+    );
+    assert_eq!(unified_diff(old, old, 3).unwrap(), "");
+}
@@ -0,0 +1,75 @@
+use deepseek_cli::documents::{Document, Kind, docx_paragraphs, parse_range};
+use std::io::Write;
+use std::path::Path;
+
+const DOCUMENT_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>
+<w:p><w:pPr><w:tabs><w:tab w:val="left" w:pos="720"/></w:tabs></w:pPr><w:r><w:t>Requirements</w:t></w:r></w:p>
+<w:p/>
+<w:p><w:r><w:t xml:space="preserve">Latency &lt; 200 ms </w:t></w:r><w:r><w:t>&amp; no data loss</w:t></w:r></w:p>
+<w:tbl><w:tr><w:tc><w:p><w:r><w:t>Owner</w:t><w:tab/><w:t>Ops&#x2014;team</w:t></w:r></w:p></w:tc></w:tr></w:tbl>
+</w:body></w:document>"#;
+
+#[test]
+fn test_docx_paragraphs() {
+    assert_eq!(
+        docx_paragraphs(DOCUMENT_XML),
+        [
+            "Requirements",
+            "Latency < 200 ms & no data loss",
+            "Owner\tOps\u{2014}team"
+        ]
+    );
+}
+
+#[test]
+fn test_read_docx() {
+    let path = std::env::temp_dir().join(format!("deepseek-spec-{}.docx", std::process::id()));
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+    zip.start_file(
+        "word/document.xml",
+        zip::write::SimpleFileOptions::default(),
+    )
+    .unwrap();
+    zip.write_all(DOCUMENT_XML.as_bytes()).unwrap();
+    zip.finish().unwrap();
+
+    let document = Document::read(&path).unwrap();
+    assert_eq!(document.kind, Kind::Docx);
+    let (text, last) = document.render("spec.docx", 2, 3, 1024).unwrap();
+    assert_eq!(
+        text,
+        "[¶2] Latency < 200 ms & no data loss\n\n[¶3] Owner\tOps\u{2014}team"
+    );
+    assert_eq!(last, 3);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_render_stops_at_the_limit() {
+    let document = Document {
+        kind: Kind::Pdf,
+        parts: vec!["a".repeat(40), "b".repeat(40), "c".repeat(40)],
+    };
+    let (text, last) = document.render("spec.pdf", 1, usize::MAX, 80).unwrap();
+    assert!(text.starts_with("--- Page 1 ---\naaa"), "{text}");
+    assert_eq!(last, 1);
+    assert!(
+        text.ends_with("read on with read_file spec.pdf#2-3]"),
+        "{text}"
+    );
+    // The first page is shown even if it is longer than the limit
+    assert_eq!(document.render("spec.pdf", 3, 3, 10).unwrap().1, 3);
+    assert!(document.render("spec.pdf", 4, 4, 80).is_err());
+    assert!(document.render("spec.pdf", 0, 2, 80).is_err());
+}
+
+#[test]
+fn test_parse_range() {
+    assert_eq!(parse_range("3"), Some((3, 3)));
+    assert_eq!(parse_range("3-7"), Some((3, 7)));
+    assert_eq!(parse_range("3-"), Some((3, usize::MAX)));
+    assert_eq!(parse_range("intro"), None);
+    assert_eq!(Kind::of(Path::new("Spec.PDF")), Some(Kind::Pdf));
+    assert_eq!(Kind::of(Path::new("notes.txt")), None);
+}
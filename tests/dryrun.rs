@@ -0,0 +1,82 @@
+use deepseek_cli::dryrun::preview;
+use deepseek_cli::tools::ToolContext;
+
+#[test]
+fn test_dry_run_previews() {
+    let dir = std::env::temp_dir().join(format!("deepseek-dryrun-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let cx = ToolContext::new(&dir);
+    let path = dir.join("config.toml");
+    std::fs::write(&path, "[server]\nport = 8080\nhost = \"localhost\"\n").unwrap();
+
+    // Relative paths are taken from the workspace, not the current directory
+    let replace = "config.toml\n<<<<<<< SEARCH\nport = 8080\n=======\nport = 9090\n>>>>>>> REPLACE";
+    assert_eq!(
+        preview(&cx, "apply_search_replace", replace),
+        "Would change config.toml:\n@@ -1,3 +1,3 @@\n [server]\n-port = 8080\n+port = 9090\n host = \"localhost\""
+    );
+    let missing = "config.toml\n<<<<<<< SEARCH\nport = 1\n=======\nport = 2\n>>>>>>> REPLACE";
+    assert!(
+        preview(&cx, "apply_search_replace", missing)
+            .starts_with("Would fail: search string not found")
+    );
+    assert_eq!(
+        preview(&cx, "edit_lines", "config.toml 2 2\nport = 9090"),
+        "Would change config.toml:\n@@ -1,3 +1,3 @@\n [server]\n-port = 8080\n+port = 9090\n host = \"localhost\""
+    );
+
+    assert_eq!(
+        preview(&cx, "write_file", "new.txt\nhello\nworld"),
+        "Would create new.txt (2 lines):\n+hello\n+world"
+    );
+    assert_eq!(
+        preview(&cx, "delete_file", &path.display().to_string()),
+        "Would delete config.toml (40 B)"
+    );
+    assert_eq!(
+        preview(&cx, "move_file", "config.toml\nold/config.toml"),
+        "Would move config.toml to old/config.toml"
+    );
+    assert!(preview(&cx, "delete_file", "../elsewhere.txt").starts_with("Would fail: "));
+    // Arguments given as a JSON object are read as the tools read them
+    assert_eq!(
+        preview(
+            &cx,
+            "apply_search_replace",
+            r#"{"path": "config.toml", "blocks": [{"search": "port = 8080", "replace": "port = 9090"}]}"#
+        ),
+        preview(&cx, "apply_search_replace", replace)
+    );
+    assert_eq!(
+        preview(
+            &cx,
+            "edit_lines",
+            r#"{"path": "config.toml", "start": 2, "end": 2, "text": "port = 9090"}"#
+        ),
+        preview(&cx, "edit_lines", "config.toml 2 2\nport = 9090")
+    );
+    assert_eq!(
+        preview(
+            &cx,
+            "write_file",
+            r#"{"path": "new.txt", "content": "hello\nworld"}"#
+        ),
+        "Would create new.txt (2 lines):\n+hello\n+world"
+    );
+    assert_eq!(
+        preview(&cx, "run_command", "cargo test --workspace\n"),
+        "Would run:\ncargo test --workspace"
+    );
+    assert_eq!(
+        preview(
+            &cx,
+            "run_command",
+            r#"{"command": "cargo test --workspace"}"#
+        ),
+        "Would run:\ncargo test --workspace"
+    );
+    // Nothing was changed on disk
+    assert!(!dir.join("new.txt").exists());
+    assert!(std::fs::read_to_string(&path).unwrap().contains("8080"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,20 @@
+use deepseek_cli::editor::edit_with;
+
+#[cfg(unix)]
+#[test]
+fn test_edit_with_returns_saved_text() {
+    // `true` leaves the file as it was written
+    assert_eq!(edit_with("true", "Fix the typo").unwrap(), "Fix the typo");
+    // Extra words are passed before the file, like `code --wait`
+    assert_eq!(
+        edit_with("true --wait", "Fix the typo").unwrap(),
+        "Fix the typo"
+    );
+    assert!(edit_with("false", "Fix the typo").is_err());
+    assert!(edit_with("deepseek-no-such-editor", "Fix the typo").is_err());
+}
+
+#[test]
+fn test_edit_with_needs_an_editor() {
+    assert!(edit_with("  ", "Fix the typo").is_err());
+}
@@ -0,0 +1,65 @@
+use deepseek_cli::envinfo::{Environment, GitStatus, Toolchain, parse_version};
+use deepseek_cli::workspace::Root;
+use std::path::PathBuf;
+
+#[test]
+fn test_parse_git_status() {
+    let status = GitStatus::parse(
+        "## main...origin/main [ahead 1, behind 2]\n M src/main.rs\nA  src/envinfo.rs\n?? notes.txt\n",
+    );
+    assert_eq!(status.branch.as_deref(), Some("main"));
+    assert_eq!(status.tracking.as_deref(), Some("ahead 1, behind 2"));
+    assert_eq!((status.changed, status.untracked), (2, 1));
+    assert_eq!(
+        status.render(),
+        "on branch main (ahead 1, behind 2), 2 changed and 1 untracked file(s)"
+    );
+
+    let status = GitStatus::parse("## No commits yet on trunk\n");
+    assert_eq!(status.render(), "on branch trunk, no changes");
+    let status = GitStatus::parse("## HEAD (no branch)\n?? a\n");
+    assert_eq!(status.render(), "detached HEAD, 1 untracked file(s)");
+}
+
+#[test]
+fn test_parse_version() {
+    assert_eq!(
+        parse_version("cargo 1.82.0 (8f40fc59f 2024-08-21)").as_deref(),
+        Some("1.82.0")
+    );
+    assert_eq!(parse_version("v20.11.0\n").as_deref(), Some("20.11.0"));
+    assert_eq!(parse_version("Python 3.12.1").as_deref(), Some("3.12.1"));
+    assert_eq!(parse_version("command not found"), None);
+}
+
+#[test]
+fn test_render_environment() {
+    let environment = Environment {
+        os: "linux (x86_64)".to_string(),
+        shell: Some("/bin/zsh".to_string()),
+        cwd: PathBuf::from("/work/app/crates/core"),
+        root: PathBuf::from("/work/app"),
+        other_roots: vec![Root::new("web", "/work/web")],
+        git: Some(GitStatus {
+            branch: Some("main".to_string()),
+            ..GitStatus::default()
+        }),
+        toolchains: vec![Toolchain {
+            name: "cargo".to_string(),
+            version: "1.82.0".to_string(),
+        }],
+    };
+    let rendered = environment.render();
+    let lines: Vec<&str> = rendered.lines().skip(1).collect();
+    assert_eq!(
+        lines,
+        [
+            "- OS: linux (x86_64)",
+            "- Shell: /bin/zsh",
+            "- Working directory: /work/app/crates/core (in the project at /work/app)",
+            "- Other workspace roots: web (/work/web); name a file in one as <root>:<path>, such as web:README.md",
+            "- Git: on branch main, no changes",
+            "- Toolchains: cargo 1.82.0",
+        ]
+    );
+}
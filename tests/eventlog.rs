@@ -0,0 +1,58 @@
+use deepseek_cli::eventlog::EventLog;
+use deepseek_cli::events::AgentEvent;
+use serde_json::Value;
+
+#[test]
+fn test_event_log_records_turns() {
+    let dir = std::env::temp_dir().join(format!("deepseek_eventlog_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let log = EventLog::create(&dir, "chat-1").unwrap();
+    for event in [
+        AgentEvent::User {
+            text: "List the files".to_string(),
+        },
+        AgentEvent::Thinking {
+            text: "I should".to_string(),
+        },
+        AgentEvent::ToolCall {
+            name: "list_files".to_string(),
+            arg: ".".to_string(),
+        },
+        AgentEvent::ToolResult {
+            name: "list_files".to_string(),
+            status: "Listed .".to_string(),
+            success: true,
+        },
+    ] {
+        log.record(&event);
+    }
+    log.set_chat_id("chat-2");
+    log.record(&AgentEvent::Message {
+        content: "Here they are".to_string(),
+        message_id: Some(4),
+    });
+    log.record(&AgentEvent::Done);
+
+    let records: Vec<Value> = std::fs::read_to_string(log.path())
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let types: Vec<&str> = records
+        .iter()
+        .map(|r| r["type"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        types,
+        ["user", "tool_call", "tool_result", "message", "done"],
+        "thinking chunks are left out"
+    );
+    assert_eq!(records[0]["text"], "List the files");
+    assert_eq!(records[0]["chat_id"], "chat-1");
+    assert!(records[0]["time"].as_str().unwrap().ends_with('Z'));
+    assert!(records[0].get("duration_ms").is_none());
+    assert!(records[2]["duration_ms"].is_u64());
+    assert_eq!(records[3]["chat_id"], "chat-2");
+    assert!(records[4]["duration_ms"].is_u64());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
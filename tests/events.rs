@@ -0,0 +1,43 @@
+use deepseek_cli::events::{AgentEvent, EventSink};
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn test_event_wire_format() {
+    let event = AgentEvent::ToolCall {
+        name: "read_file".to_string(),
+        arg: "src/main.rs".to_string(),
+    };
+    let line = serde_json::to_string(&event).unwrap();
+    assert_eq!(
+        line,
+        r#"{"type":"tool_call","name":"read_file","arg":"src/main.rs"}"#
+    );
+    assert_eq!(serde_json::from_str::<AgentEvent>(&line).unwrap(), event);
+
+    let line = serde_json::to_string(&AgentEvent::Interrupted).unwrap();
+    assert_eq!(line, r#"{"type":"interrupted"}"#);
+}
+
+#[test]
+fn test_callback_sees_every_event() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let (sink, mut rx) = EventSink::channel();
+    let sink = sink.with_callback({
+        let seen = Arc::clone(&seen);
+        move |event| seen.lock().unwrap().push(event.clone())
+    });
+    sink.clone().emit(AgentEvent::Content {
+        text: "Hello".to_string(),
+    });
+    sink.emit(AgentEvent::Done);
+    assert_eq!(
+        *seen.lock().unwrap(),
+        [
+            AgentEvent::Content {
+                text: "Hello".to_string()
+            },
+            AgentEvent::Done
+        ]
+    );
+    assert_eq!(rx.try_recv().unwrap(), seen.lock().unwrap()[0]);
+}
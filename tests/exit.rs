@@ -0,0 +1,43 @@
+use anyhow::anyhow;
+use deepseek_cli::exit::{self, ExitStatus};
+use deepseek_cli::report::Outcome;
+
+#[test]
+fn test_exit_status_of_errors() {
+    assert_eq!(
+        exit::status_of(&anyhow!("bad argument")),
+        ExitStatus::Failure
+    );
+
+    let error = exit::with_status(
+        ExitStatus::ApiError,
+        anyhow!("connection reset").context("Request failed"),
+    );
+    assert_eq!(exit::status_of(&error), ExitStatus::ApiError);
+    assert_eq!(error.to_string(), "Request failed");
+    assert_eq!(format!("{error:#}"), "Request failed: connection reset");
+
+    // Context added on the way out does not hide the status
+    let error = exit::error(ExitStatus::Incomplete, "Stopped").context("Run failed");
+    assert_eq!(exit::status_of(&error), ExitStatus::Incomplete);
+}
+
+#[test]
+fn test_exit_codes() {
+    let codes: Vec<u8> = [
+        Outcome::Completed,
+        Outcome::Failed("oops".to_string()),
+        Outcome::TurnLimit(20),
+        Outcome::Interrupted,
+        Outcome::Refused(2),
+    ]
+    .iter()
+    .map(|outcome| outcome.status().code())
+    .collect();
+    assert_eq!(codes, [0, 1, 2, 2, 3]);
+    assert_eq!(ExitStatus::ApiError.code(), 4);
+    assert_eq!(
+        Outcome::Refused(2).describe(),
+        "2 tool calls were refused by the policy"
+    );
+}
@@ -0,0 +1,101 @@
+use deepseek_cli::explain::{Excerpt, MAX_EXPLAIN_BYTES, load, prompt};
+use deepseek_cli::workspace::Workspace;
+
+const LIB: &str = "\
+pub struct Counter {
+    count: u32,
+}
+
+impl Counter {
+    pub fn new() -> Self {
+        Self { count: 0 }
+    }
+}
+
+pub fn run() {}
+";
+
+const MAIN: &str = "\
+struct Parser;
+
+impl<T> Default for Wrapper<T> {
+    fn new() -> Self {
+        todo!()
+    }
+}
+
+fn main() {}
+";
+
+fn project(name: &str) -> std::path::PathBuf {
+    let root = std::env::temp_dir().join(format!("deepseek-explain-{name}-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(root.join("src/lib.rs"), LIB).unwrap();
+    std::fs::write(root.join("src/main.rs"), MAIN).unwrap();
+    root
+}
+
+#[test]
+fn test_load_file_and_symbol() {
+    let root = project("load");
+    let workspace = Workspace::single(&root);
+
+    let file = load(&workspace, "src/lib.rs").unwrap();
+    assert_eq!(file.path, "src/lib.rs");
+    assert_eq!(file.symbol, None);
+    assert_eq!((file.start_line, file.end_line), (1, 11));
+    assert_eq!(file.text, LIB);
+
+    let new = load(&workspace, "src/lib.rs#Counter::new").unwrap();
+    assert_eq!(
+        new,
+        Excerpt {
+            path: "src/lib.rs".to_string(),
+            symbol: Some("Counter::new".to_string()),
+            start_line: 6,
+            end_line: 8,
+            text: "    pub fn new() -> Self {\n        Self { count: 0 }\n    }".to_string(),
+        }
+    );
+    assert_eq!(new.describe(), "`Counter::new` in src/lib.rs (lines 6-8)");
+    let error = load(&workspace, "src/lib.rs#missing")
+        .unwrap_err()
+        .to_string();
+    assert!(
+        error.starts_with("No symbol named missing in src/lib.rs"),
+        "{error}"
+    );
+
+    // A symbol alone is looked up in every file
+    let run = load(&workspace, "run").unwrap();
+    assert_eq!((run.path.as_str(), run.start_line), ("src/lib.rs", 11));
+    let wrapper = load(&workspace, "Wrapper::new").unwrap();
+    assert_eq!(wrapper.path, "src/main.rs");
+    let error = load(&workspace, "new").unwrap_err().to_string();
+    assert!(error.starts_with("new is defined in 2 places"), "{error}");
+    assert!(load(&workspace, "nothing_here").is_err());
+
+    // A large file has to be explained a symbol at a time
+    std::fs::write(
+        root.join("src/big.rs"),
+        "// filler\n".repeat(MAX_EXPLAIN_BYTES),
+    )
+    .unwrap();
+    assert!(load(&workspace, "src/big.rs").is_err());
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_prompt() {
+    let excerpt = Excerpt {
+        path: "src/lib.rs".to_string(),
+        symbol: Some("run".to_string()),
+        start_line: 11,
+        end_line: 11,
+        text: "pub fn run() {}".to_string(),
+    };
+    assert_eq!(
+        prompt(&excerpt),
+        "Explain `run` in src/lib.rs (lines 11-11):\n\n```rs\npub fn run() {}\n```"
+    );
+}
@@ -0,0 +1,82 @@
+use deepseek_cli::events::AgentEvent;
+use deepseek_cli::export::{Conversation, Entry};
+
+fn conversation() -> Conversation {
+    let mut conversation = Conversation::default();
+    for event in [
+        AgentEvent::User {
+            text: "What is in <main.rs>?".to_string(),
+        },
+        AgentEvent::Thinking {
+            text: "Let me ".to_string(),
+        },
+        AgentEvent::Thinking {
+            text: "look.".to_string(),
+        },
+        AgentEvent::Content {
+            text: "I'll read it.".to_string(),
+        },
+        AgentEvent::Message {
+            content: "I'll read it.".to_string(),
+            message_id: Some(2),
+        },
+        AgentEvent::ToolCall {
+            name: "read_file".to_string(),
+            arg: "src/main.rs".to_string(),
+        },
+        AgentEvent::ToolResult {
+            name: "read_file".to_string(),
+            status: "Read src/main.rs".to_string(),
+            success: true,
+        },
+        AgentEvent::Content {
+            text: "It holds `fn main`.".to_string(),
+        },
+        AgentEvent::Done,
+    ] {
+        conversation.record(&event);
+    }
+    conversation
+}
+
+#[test]
+fn test_conversation_joins_streamed_chunks() {
+    let conversation = conversation();
+    assert_eq!(conversation.user_messages(), 1);
+    assert_eq!(conversation.entries().len(), 5);
+    assert_eq!(
+        conversation.entries()[1],
+        Entry::Assistant {
+            thinking: "Let me look.".to_string(),
+            content: "I'll read it.".to_string(),
+        }
+    );
+    assert_eq!(
+        conversation.entries()[4],
+        Entry::Assistant {
+            thinking: String::new(),
+            content: "It holds `fn main`.".to_string(),
+        }
+    );
+}
+
+#[test]
+fn test_markdown_collapses_thinking_and_fences_tool_calls() {
+    let markdown = conversation().to_markdown("Chat 1");
+    assert!(markdown.starts_with("# Chat 1\n"));
+    assert!(markdown.contains("## User\n\nWhat is in <main.rs>?"));
+    assert!(markdown.contains("<details>\n<summary>Thinking</summary>\n\n```\nLet me look.\n```"));
+    assert!(markdown.contains("```\nTOOL: read_file src/main.rs\n```"));
+    assert!(markdown.contains("> ✓ Read src/main.rs"));
+}
+
+#[test]
+fn test_html_escapes_text() {
+    let html = conversation().to_html("Chat <1>");
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<title>Chat &lt;1&gt;</title>"));
+    assert!(html.contains("What is in &lt;main.rs&gt;?"));
+    assert!(!html.contains("<main.rs>"));
+    assert!(html.contains("<details><summary>Thinking</summary>"));
+    assert!(html.contains("<pre class=\"tool\">TOOL: read_file src/main.rs</pre>"));
+}
@@ -0,0 +1,60 @@
+use deepseek_cli::filelock::{check_unchanged, forget, lock, record};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime};
+
+#[test]
+fn test_check_unchanged_since_read() {
+    let dir = std::env::temp_dir().join(format!("deepseek-filelock-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("notes.md");
+    std::fs::write(&path, "first\n").unwrap();
+
+    // Files that were never read are not checked
+    check_unchanged(&dir.join("unread.md")).unwrap();
+    record(&path, b"first\n");
+    check_unchanged(&path).unwrap();
+
+    // Touched but not changed
+    let file = std::fs::File::options().write(true).open(&path).unwrap();
+    file.set_modified(SystemTime::now() + Duration::from_secs(5))
+        .unwrap();
+    check_unchanged(&path).unwrap();
+
+    std::fs::write(&path, "edited elsewhere\n").unwrap();
+    let error = check_unchanged(&path).unwrap_err().to_string();
+    assert!(
+        error.contains("was changed since it was last read"),
+        "{error}"
+    );
+
+    record(&path, b"edited elsewhere\n");
+    check_unchanged(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(check_unchanged(&path).is_err());
+    forget(&path);
+    check_unchanged(&path).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_lock_serializes_edits() {
+    let dir = std::env::temp_dir().join(format!("deepseek-filelock-lock-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("shared.txt");
+    let active = Arc::new(AtomicUsize::new(0));
+    let mut tasks = Vec::new();
+    for _ in 0..4 {
+        let (path, active) = (path.clone(), active.clone());
+        tasks.push(tokio::spawn(async move {
+            let _guard = lock(&path).await;
+            assert_eq!(active.fetch_add(1, Ordering::SeqCst), 0);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            active.fetch_sub(1, Ordering::SeqCst);
+        }));
+    }
+    for task in tasks {
+        task.await.unwrap();
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
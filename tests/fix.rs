@@ -0,0 +1,110 @@
+use deepseek_cli::fix::{CommandRun, FixSummary, FixTarget, prompt};
+
+fn run(exit_code: i32, output: &str) -> CommandRun {
+    CommandRun {
+        exit_code: Some(exit_code),
+        output: output.to_string(),
+    }
+}
+
+#[test]
+fn test_detect_command() {
+    let root = std::env::temp_dir().join(format!("deepseek-fix-{}", std::process::id()));
+    std::fs::create_dir_all(&root).unwrap();
+    assert_eq!(FixTarget::Tests.detect_command(&root), None);
+    assert_eq!(FixTarget::Build.detect_command(&root), None);
+    std::fs::write(root.join("package.json"), "{}").unwrap();
+    assert_eq!(FixTarget::Tests.detect_command(&root), Some("npm test"));
+    assert_eq!(
+        FixTarget::Build.detect_command(&root),
+        Some("npm run build")
+    );
+    std::fs::write(root.join("Cargo.toml"), "").unwrap();
+    assert_eq!(FixTarget::Tests.detect_command(&root), Some("cargo test"));
+    assert_eq!(FixTarget::Build.detect_command(&root), Some("cargo check"));
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_prompt_and_excerpt() {
+    let failed = run(101, "test parse ... FAILED\n\nfailures:\n    parse\n");
+    let first = prompt(FixTarget::Tests, "cargo test", &failed, 1, 5);
+    assert!(first.starts_with("`cargo test` fails. Its output:\n\n```\ntest parse ... FAILED"));
+    assert!(first.ends_with("(round 1 of 5)."), "{first}");
+    assert!(
+        prompt(FixTarget::Tests, "cargo test", &failed, 2, 5)
+            .starts_with("`cargo test` still fails")
+    );
+
+    // Long output keeps its end, from the start of a line
+    let long: String = (0..5000).map(|i| format!("line {i}\n")).collect();
+    let excerpt = run(1, &long).excerpt();
+    assert!(excerpt.len() < long.len());
+    assert!(excerpt.starts_with('['), "{excerpt}");
+    assert!(excerpt.ends_with("line 4999"));
+    let first_kept = excerpt.lines().nth(1).unwrap();
+    assert!(first_kept.starts_with("line "), "{first_kept}");
+}
+
+#[test]
+fn test_build_prompt() {
+    assert_eq!(
+        FixTarget::Build.run_command("cargo check"),
+        "cargo check --message-format json"
+    );
+    assert_eq!(FixTarget::Tests.run_command("cargo test"), "cargo test");
+
+    // The errors cargo gave as JSON are sent without the rest of the output
+    let error = r#"{"reason":"compiler-message","message":{"message":"mismatched types","level":"error","code":{"code":"E0308"},"spans":[{"file_name":"src/main.rs","line_start":3,"column_start":18,"is_primary":true,"label":"expected `u32`, found `&str`","suggested_replacement":null}],"children":[]}}"#;
+    let warning = r#"{"reason":"compiler-message","message":{"message":"unused variable: `x`","level":"warning","code":{"code":"unused_variables"},"spans":[],"children":[]}}"#;
+    let failed = run(
+        101,
+        &format!("{error}\n{warning}\n    Checking demo v0.1.0\nerror: could not compile `demo`\n"),
+    );
+    assert_eq!(
+        prompt(FixTarget::Build, "cargo check", &failed, 1, 5),
+        "`cargo check` fails. The errors:\n\n```\nsrc/main.rs:3:18: error[E0308]: mismatched types: expected `u32`, found `&str`\n\n[1 warning(s) left out]\n```\n\nFix the code so it builds. When you are done, stop; `cargo check` will be run again for you (round 1 of 5)."
+    );
+
+    // Without compiler errors, the output cargo wrote for people is sent
+    let failed = run(
+        101,
+        &format!("{warning}\nerror: failed to parse manifest at `Cargo.toml`\n"),
+    );
+    let text = prompt(FixTarget::Build, "cargo check", &failed, 1, 5);
+    assert!(
+        text.starts_with(
+            "`cargo check` fails. Its output:\n\n```\nerror: failed to parse manifest at `Cargo.toml`\n```"
+        ),
+        "{text}"
+    );
+}
+
+#[test]
+fn test_summary() {
+    let mut summary = FixSummary::new("cargo test");
+    summary.runs.push(run(0, ""));
+    assert!(summary.passed());
+    assert_eq!(
+        summary.render(),
+        "`cargo test` already passes; there was nothing to fix\n  run 1: passed"
+    );
+
+    let mut summary = FixSummary::new("cargo test");
+    summary.runs = vec![run(101, ""), run(101, ""), run(0, "")];
+    summary.diff_stat = Some(" src/lib.rs | 2 +-\n".to_string());
+    assert_eq!(summary.rounds(), 2);
+    assert_eq!(
+        summary.render(),
+        "`cargo test` passes after 2 rounds of fixes\n  run 1: failed (exit code 101)\n  run 2: failed (exit code 101)\n  run 3: passed\n\nChanges:\n src/lib.rs | 2 +-"
+    );
+
+    summary.runs.pop();
+    summary.stopped = Some("interrupted".to_string());
+    assert!(!summary.passed());
+    assert!(
+        summary
+            .render()
+            .starts_with("`cargo test` still fails; stopped: interrupted")
+    );
+}
@@ -0,0 +1,45 @@
+use deepseek_cli::format::{ResponseFormat, Style, constraints_note, repair_json};
+
+#[test]
+fn test_constraints_note() {
+    assert_eq!(constraints_note(None, None, None), None);
+    let note = constraints_note(Some(ResponseFormat::Plain), Some(400), None).unwrap();
+    assert!(note.contains("plain text"), "{note}");
+    assert!(
+        note.contains("under 400 tokens (about 300 words)"),
+        "{note}"
+    );
+}
+
+#[test]
+fn test_repair_json() {
+    assert_eq!(repair_json(" {\"a\": 1}\n").unwrap(), "{\"a\": 1}");
+    assert_eq!(
+        repair_json(
+            "Here you go:\n```json\n{\"crates\": [\"tokio\", \"serde\",],}\n```\nAnything else?"
+        )
+        .unwrap(),
+        "{\n  \"crates\": [\n    \"tokio\",\n    \"serde\"\n  ]\n}"
+    );
+    // Commas inside strings are left alone
+    assert_eq!(
+        repair_json("[\"a,]\", \"b\\\",}\",]").unwrap(),
+        "[\n  \"a,]\",\n  \"b\\\",}\"\n]"
+    );
+    assert!(repair_json("I could not find any crates.").is_err());
+}
+
+#[test]
+fn test_style_names() {
+    for style in Style::ALL {
+        assert_eq!(Style::from_name(style.name()), Some(style));
+        assert!(style.instruction().starts_with("Reply style: "));
+    }
+    assert_eq!(Style::from_name("Junior"), Some(Style::Junior));
+    assert_eq!(Style::from_name("code"), Some(Style::CodeOnly));
+    assert_eq!(Style::from_name("verbose"), None);
+    assert_eq!(
+        Style::names(),
+        "concise, detailed, explain-like-junior, code-only"
+    );
+}
@@ -0,0 +1,107 @@
+use deepseek_cli::github::{self, Reference};
+use deepseek_cli::protocol::parse_tool_calls;
+use serde_json::json;
+
+fn reference(owner: &str, repo: &str, number: u64) -> Reference {
+    Reference {
+        owner: owner.to_string(),
+        repo: repo.to_string(),
+        number,
+    }
+}
+
+#[test]
+fn test_parse_reference() {
+    let origin = Some(("me", "project"));
+    for (text, expected) in [
+        ("owner/repo#42", reference("owner", "repo", 42)),
+        (
+            "https://github.com/owner/repo/pull/42/files",
+            reference("owner", "repo", 42),
+        ),
+        (
+            "https://www.github.com/owner/repo/issues/7",
+            reference("owner", "repo", 7),
+        ),
+        ("#12", reference("me", "project", 12)),
+        (" 12 ", reference("me", "project", 12)),
+    ] {
+        assert_eq!(Reference::parse(text, origin).unwrap(), expected, "{text}");
+    }
+    assert!(Reference::parse("#12", None).is_err());
+    assert!(Reference::parse("owner/repo#latest", origin).is_err());
+    assert!(Reference::parse("https://gitlab.com/owner/repo/pull/42", origin).is_err());
+    assert!(Reference::parse("https://github.com/owner/repo/commits/main", origin).is_err());
+}
+
+#[test]
+fn test_parse_remote() {
+    let expected = Some(("owner".to_string(), "repo".to_string()));
+    assert_eq!(
+        github::parse_remote("git@github.com:owner/repo.git"),
+        expected
+    );
+    assert_eq!(
+        github::parse_remote("https://github.com/owner/repo"),
+        expected
+    );
+    assert_eq!(
+        github::parse_remote("ssh://git@github.com/owner/repo.git"),
+        expected
+    );
+    assert_eq!(
+        github::parse_remote("https://gitlab.com/owner/repo.git"),
+        None
+    );
+}
+
+#[test]
+fn test_format_pull_request() {
+    let pr = json!({
+        "number": 42,
+        "title": "Fix the parser",
+        "state": "open",
+        "draft": true,
+        "user": {"login": "alice"},
+        "created_at": "2026-01-02T03:04:05Z",
+        "labels": [{"name": "bug"}],
+        "html_url": "https://github.com/owner/repo/pull/42",
+        "body": "Closes #7.",
+        "base": {"ref": "main"},
+        "head": {"label": "alice:fix-parser"},
+        "changed_files": 1,
+        "additions": 3,
+        "deletions": 1,
+    });
+    let files =
+        [json!({"filename": "src/parse.rs", "status": "modified", "additions": 3, "deletions": 1})];
+    let comments = [json!({
+        "user": {"login": "bob"},
+        "created_at": "2026-01-03T00:00:00Z",
+        "path": "src/parse.rs",
+        "line": 10,
+        "body": "This can panic.",
+    })];
+    let text = github::format_pull_request(&pr, &files, &comments);
+    assert!(text.starts_with("Pull request #42: Fix the parser\nState: open, by alice"));
+    assert!(text.contains("Labels: bug\n"));
+    assert!(text.contains("Branches: main <- alice:fix-parser (draft)\n"));
+    assert!(text.contains("\nCloses #7.\n"));
+    assert!(text.contains("- src/parse.rs (modified, +3 -1)\n"));
+    assert!(text.contains("--- bob on src/parse.rs:10, 2026-01-03T00:00:00Z\nThis can panic.\n"));
+
+    let issue = github::format_issue(
+        &json!({"number": 7, "title": "Crash", "state": "closed"}),
+        &[],
+    );
+    assert!(issue.contains("(no description)"));
+    assert!(!issue.contains("Comments"));
+}
+
+#[test]
+fn test_pr_comment_json_call() {
+    let calls = parse_tool_calls(
+        "```tool\n{\"name\": \"gh_pr_comment\", \"args\": {\"pull_request\": \"#3\", \"location\": \"src/a.rs:10\", \"text\": \"Check for None here.\"}, \"reason\": \"review\"}\n```",
+    );
+    assert_eq!(calls[0].arg, "#3 src/a.rs:10\nCheck for None here.");
+}
@@ -0,0 +1,25 @@
+use deepseek_cli::highlight::StreamHighlighter;
+
+#[test]
+fn test_stream_highlighter_passes_text_through_without_colors() {
+    colored::control::set_override(false);
+    let text =
+        "Here is the fix:\n\n```rust\nfn main() {\n    println!(\"hi\");\n}\n```\nDone, `x` works.";
+
+    // Feed the text in awkward pieces, splitting fences and lines
+    let mut highlighter = StreamHighlighter::new();
+    let mut out = String::new();
+    for piece in [
+        "Here is",
+        " the fix:\n\n`",
+        "``ru",
+        "st\nfn main() {\n  ",
+        "  println!(\"hi\");\n}",
+        "\n``",
+        "`\nDone, `x` works.",
+    ] {
+        out.push_str(&highlighter.push(piece));
+    }
+    out.push_str(&highlighter.finish());
+    assert_eq!(out, text);
+}
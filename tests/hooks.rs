@@ -0,0 +1,114 @@
+use deepseek_cli::hooks::{Hooks, ToolCallHook};
+use std::path::PathBuf;
+
+fn hooks(scripts: &[(&str, &str)]) -> Hooks {
+    Hooks::from_scripts(
+        scripts
+            .iter()
+            .map(|(name, source)| (PathBuf::from(name), (*source).to_string())),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_on_tool_call() {
+    let hooks = hooks(&[
+        (
+            "policy.rhai",
+            r#"
+            fn on_tool_call(name, arg) {
+                if name == "run_command" && arg.contains("rm -rf") {
+                    return #{ refuse: "no recursive deletes" };
+                }
+                if name == "delete_file" {
+                    return false;
+                }
+            }
+            "#,
+        ),
+        (
+            "rewrite.rhai",
+            r#"
+            fn on_tool_call(name, arg) {
+                if name == "run_command" && arg.starts_with("npm ") {
+                    return "pnpm " + arg.sub_string(4);
+                }
+            }
+            "#,
+        ),
+    ]);
+    assert_eq!(
+        hooks.on_tool_call("read_file", "src/main.rs").unwrap(),
+        ToolCallHook::Run
+    );
+    assert_eq!(
+        hooks.on_tool_call("run_command", "npm test").unwrap(),
+        ToolCallHook::Replace("pnpm test".to_string())
+    );
+    assert_eq!(
+        hooks.on_tool_call("run_command", "rm -rf target").unwrap(),
+        ToolCallHook::Refuse(
+            "refused by the hook in policy.rhai (no recursive deletes)".to_string()
+        )
+    );
+    assert_eq!(
+        hooks.on_tool_call("delete_file", "notes.md").unwrap(),
+        ToolCallHook::Refuse("refused by the hook in policy.rhai".to_string())
+    );
+}
+
+#[test]
+fn test_on_user_message_chains_scripts() {
+    let hooks = hooks(&[
+        (
+            "a.rhai",
+            r#"fn on_user_message(text) { text + " (be brief)" }"#,
+        ),
+        ("b.rhai", "fn on_assistant_message(text) {}"),
+        (
+            "c.rhai",
+            r#"fn on_user_message(text) { if text.contains("TODO") { text.replace("TODO", "task"); text } }"#,
+        ),
+    ]);
+    assert_eq!(
+        hooks.on_user_message("Fix the TODO").unwrap().as_deref(),
+        Some("Fix the task (be brief)")
+    );
+    hooks.on_assistant_message("Done").unwrap();
+    // Hooks no script defines do nothing
+    hooks
+        .on_tool_result("read_file", "File read", true)
+        .unwrap();
+}
+
+#[test]
+fn test_hook_errors() {
+    let error =
+        Hooks::from_scripts([(PathBuf::from("broken.rhai"), "fn on_tool_call(".to_string())])
+            .err()
+            .unwrap()
+            .to_string();
+    assert!(
+        error.starts_with("Invalid hook script broken.rhai"),
+        "{error}"
+    );
+
+    let hooks = hooks(&[
+        ("loop.rhai", "fn on_assistant_message(text) { loop {} }"),
+        ("typed.rhai", "fn on_tool_call(name, arg) { 42 }"),
+    ]);
+    let error = hooks.on_assistant_message("Done").unwrap_err().to_string();
+    assert!(
+        error.starts_with("on_assistant_message in loop.rhai failed"),
+        "{error}"
+    );
+    let error = hooks
+        .on_tool_call("read_file", "a")
+        .unwrap_err()
+        .to_string();
+    assert!(
+        error.contains("expected a string, a bool or a map"),
+        "{error}"
+    );
+    assert!(Hooks::default().is_empty());
+}
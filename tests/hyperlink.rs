@@ -0,0 +1,38 @@
+use deepseek_cli::hyperlink::{file_url, linkify_with, osc8};
+use std::path::Path;
+
+#[test]
+fn test_linkify_urls_and_paths() {
+    assert_eq!(
+        linkify_with("See https://example.com/docs.", None),
+        format!(
+            "See {}.",
+            osc8("https://example.com/docs", "https://example.com/docs")
+        )
+    );
+
+    let manifest = file_url(Path::new("Cargo.toml"), None, None).unwrap();
+    assert!(manifest.starts_with("file:///"));
+    assert!(manifest.ends_with("/Cargo.toml"));
+    assert_eq!(
+        linkify_with("Edited `Cargo.toml`, done", None),
+        format!("Edited `{}`, done", osc8(&manifest, "Cargo.toml"))
+    );
+
+    // Lines go into the template, and text that is not an existing file is left alone
+    let editor = file_url(
+        Path::new("src/lib.rs"),
+        Some(3),
+        Some("vscode://file{path}:{line}"),
+    )
+    .unwrap();
+    assert!(editor.starts_with("vscode://file/") && editor.ends_with("/src/lib.rs:3"));
+    assert_eq!(
+        linkify_with("in src/lib.rs:3:7\n", Some("vscode://file{path}:{line}")),
+        format!("in {}\n", osc8(&editor, "src/lib.rs:3:7"))
+    );
+    assert_eq!(
+        linkify_with("e.g. missing/file.rs or 1.5", None),
+        "e.g. missing/file.rs or 1.5"
+    );
+}
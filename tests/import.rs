@@ -0,0 +1,57 @@
+use deepseek_cli::import::{ImportFormat, ImportedMessage, Role, parse};
+
+#[test]
+fn test_import_chatgpt_follows_current_branch() {
+    let export = r#"[{
+        "title": "Config formats",
+        "current_node": "c",
+        "mapping": {
+            "root": {"message": null, "parent": null},
+            "a": {"message": {"author": {"role": "user"}, "content": {"parts": ["TOML or YAML?"]}}, "parent": "root"},
+            "b": {"message": {"author": {"role": "assistant"}, "content": {"parts": ["An abandoned edit"]}}, "parent": "a"},
+            "c": {"message": {"author": {"role": "assistant"}, "content": {"parts": ["TOML."]}}, "parent": "a"}
+        }
+    }]"#;
+    let transcript = parse(export, ImportFormat::ChatGpt).unwrap();
+    assert_eq!(transcript.title.as_deref(), Some("Config formats"));
+    assert_eq!(
+        transcript.messages,
+        vec![
+            ImportedMessage {
+                role: Role::User,
+                content: "TOML or YAML?".to_string()
+            },
+            ImportedMessage {
+                role: Role::Assistant,
+                content: "TOML.".to_string()
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_import_aider_history() {
+    let history = "\
+# aider chat started at 2024-05-01 10:00:00
+
+> Added src/main.rs to the chat.
+
+#### Rename the function
+#### and update callers
+
+Sure, here is the change:
+
+```rust
+fn renamed() {}
+```
+";
+    let transcript = parse(history, ImportFormat::Aider).unwrap();
+    assert_eq!(transcript.messages.len(), 2);
+    assert_eq!(transcript.messages[0].role, Role::User);
+    assert_eq!(
+        transcript.messages[0].content,
+        "Rename the function\nand update callers"
+    );
+    assert_eq!(transcript.messages[1].role, Role::Assistant);
+    assert!(transcript.messages[1].content.ends_with("```"));
+}
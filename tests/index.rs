@@ -0,0 +1,46 @@
+use deepseek_cli::index::FileIndex;
+
+#[test]
+fn test_find_ranks_file_names_first() {
+    let index = FileIndex::from_files(vec![
+        "src/main.rs".to_string(),
+        "src/tools.rs".to_string(),
+        "tests/tools.rs".to_string(),
+        "docs/maintaining.md".to_string(),
+        "examples/domain/rules.txt".to_string(),
+    ]);
+    // "domain/" matches too, but only across directories
+    assert_eq!(
+        index.find("main", 5),
+        vec![
+            "src/main.rs",
+            "docs/maintaining.md",
+            "examples/domain/rules.txt"
+        ]
+    );
+    assert_eq!(index.find("mainrs", 1), vec!["src/main.rs"]);
+    assert_eq!(index.find("tests/tool", 5), vec!["tests/tools.rs"]);
+    assert_eq!(
+        index.find("TOOLS", 5),
+        vec!["src/tools.rs", "tests/tools.rs"]
+    );
+    assert!(index.find("zzz", 5).is_empty());
+    assert!(index.find("  ", 5).is_empty());
+}
+
+#[test]
+fn test_build_skips_ignored_files() {
+    let root = std::env::temp_dir().join(format!("deepseek-index-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::create_dir_all(root.join("build/out")).unwrap();
+    std::fs::write(root.join(".gitignore"), "# generated\n/build/\n*.log\n").unwrap();
+    std::fs::write(root.join("src/lib.rs"), "").unwrap();
+    std::fs::write(root.join("src/debug.log"), "").unwrap();
+    std::fs::write(root.join("build/out/app"), "").unwrap();
+
+    let index = FileIndex::build(&root);
+    assert_eq!(index.files(), [".gitignore", "src/lib.rs"]);
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
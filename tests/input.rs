@@ -0,0 +1,38 @@
+use deepseek_cli::input::MultilineInput;
+
+fn feed(lines: &[&str]) -> Vec<String> {
+    let mut input = MultilineInput::default();
+    lines.iter().filter_map(|line| input.push(line)).collect()
+}
+
+#[test]
+fn test_multiline_input() {
+    assert_eq!(feed(&["hello"]), ["hello"]);
+    assert_eq!(
+        feed(&["first \\", "second\\", "third"]),
+        ["first \nsecond\nthird"]
+    );
+    assert_eq!(
+        feed(&[
+            "\"\"\"",
+            "fn main() {",
+            "    println!(\"hi\\n\"); \\",
+            "}",
+            "\"\"\"",
+            "next"
+        ]),
+        ["fn main() {\n    println!(\"hi\\n\"); \\\n}", "next"]
+    );
+    assert_eq!(
+        feed(&["\"\"\"Review this:", "", "let x = 1;\"\"\""]),
+        ["Review this:\n\nlet x = 1;"]
+    );
+    assert_eq!(feed(&["\"\"\"one line\"\"\""]), ["one line"]);
+
+    let mut input = MultilineInput::default();
+    assert_eq!(input.push("\"\"\""), None);
+    assert!(input.is_continuing());
+    assert_eq!(input.push("/exit"), None);
+    assert_eq!(input.push("\"\"\"").as_deref(), Some("/exit"));
+    assert!(!input.is_continuing());
+}
@@ -0,0 +1,89 @@
+use deepseek_cli::instructions::{
+    FILE_NAME, InstructionFile, discover, extract_markdown, project_overview, prompt_section,
+};
+use std::fs;
+
+#[test]
+fn test_discover_from_global_to_working_directory() {
+    let root = std::env::temp_dir().join(format!("deepseek-instructions-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let repo = root.join("repo");
+    let sub = repo.join("crates/app");
+    fs::create_dir_all(&sub).unwrap();
+    fs::create_dir_all(repo.join(".git")).unwrap();
+    // Above the repository root, so not read
+    fs::write(root.join(FILE_NAME), "outside").unwrap();
+    fs::write(root.join("global.md"), "Be brief.\n").unwrap();
+    fs::write(repo.join(FILE_NAME), "Run cargo fmt.").unwrap();
+    fs::write(repo.join("crates").join(FILE_NAME), "  \n").unwrap();
+    fs::write(sub.join(FILE_NAME), "This crate is the CLI.").unwrap();
+
+    let files = discover(&sub, Some(&root.join("global.md")));
+    let contents: Vec<&str> = files.iter().map(|f| f.content.as_str()).collect();
+    assert_eq!(
+        contents,
+        ["Be brief.", "Run cargo fmt.", "This crate is the CLI."]
+    );
+    assert_eq!(files[2].path, sub.join(FILE_NAME));
+
+    // Outside a repository, only the directory itself
+    fs::remove_dir_all(repo.join(".git")).unwrap();
+    let files = discover(&sub, None);
+    assert_eq!(files.len(), 1);
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_prompt_section() {
+    assert_eq!(prompt_section(&[]), None);
+    let section = prompt_section(&[
+        InstructionFile {
+            path: "/home/me/.config/deepseek-cli/DEEPSEEK.md".into(),
+            content: "Be brief.".to_string(),
+        },
+        InstructionFile {
+            path: "/src/app/DEEPSEEK.md".into(),
+            content: "Use tabs.".to_string(),
+        },
+    ])
+    .unwrap();
+    assert!(section.starts_with("Instructions from DEEPSEEK.md files"));
+    assert!(section.ends_with(
+        "## /home/me/.config/deepseek-cli/DEEPSEEK.md\n\nBe brief.\n\n## /src/app/DEEPSEEK.md\n\nUse tabs."
+    ));
+}
+
+#[test]
+fn test_project_overview() {
+    let root = std::env::temp_dir().join(format!("deepseek-overview-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("Cargo.toml"), "[package]\nname = \"demo\"\n").unwrap();
+    fs::write(root.join("README.md"), "x".repeat(10_000)).unwrap();
+    let files: Vec<String> = [
+        "Cargo.toml",
+        "README.md",
+        "src/main.rs",
+        "src/cli/args.rs",
+        "tests/cli.rs",
+    ]
+    .map(String::from)
+    .to_vec();
+    let overview = project_overview(&root, &files);
+    assert!(overview.starts_with(
+        "Directory layout:\n  Cargo.toml\n  README.md\n  src/ (2 files): cli/\n  tests/ (1 files)\n"
+    ));
+    assert!(overview.contains("Cargo.toml:\n```\n[package]\nname = \"demo\"\n```"));
+    assert!(overview.contains("[... cut off]"));
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_extract_markdown() {
+    let reply = "Here it is:\n\n```markdown\n# Demo\n\nBuild with:\n\n```sh\ncargo build\n```\n\nDone.\n```\n";
+    assert_eq!(
+        extract_markdown(reply),
+        "# Demo\n\nBuild with:\n\n```sh\ncargo build\n```\n\nDone.\n"
+    );
+    assert_eq!(extract_markdown("# Demo\n"), "# Demo\n");
+}
@@ -0,0 +1,14 @@
+use deepseek_cli::logging::filter_for;
+use tracing_subscriber::EnvFilter;
+
+#[test]
+fn test_verbosity_filters_parse() {
+    for verbosity in 0..=3 {
+        assert!(
+            EnvFilter::try_new(filter_for(verbosity)).is_ok(),
+            "{verbosity}"
+        );
+    }
+    assert_ne!(filter_for(1), filter_for(2));
+    assert_eq!(filter_for(2), filter_for(5));
+}
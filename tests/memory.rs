@@ -0,0 +1,34 @@
+use deepseek_cli::memory::{Memory, Source};
+
+#[test]
+fn test_memory_add_forget_and_reload() {
+    let dir = std::env::temp_dir().join(format!("deepseek-memory-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let path = dir.join("memory.jsonl");
+    let mut memory = Memory::open(&path).unwrap();
+    assert_eq!(memory.prompt_section(), None);
+
+    memory.add("we use  pnpm, not npm", Source::User).unwrap();
+    memory.add("tests live in tests/", Source::Model).unwrap();
+    memory.add("we use pnpm, not npm", Source::Model).unwrap();
+    assert!(memory.add("  ", Source::User).is_err());
+    assert_eq!(
+        memory.list(),
+        "1. we use pnpm, not npm\n2. tests live in tests/ (saved by the model)"
+    );
+
+    let memory = Memory::open(&path).unwrap();
+    assert_eq!(memory.facts().len(), 2);
+    assert_eq!(
+        memory.prompt_section().unwrap(),
+        "Facts the user asked you to remember from earlier sessions; keep to them:\n- we use pnpm, not npm\n- tests live in tests/"
+    );
+
+    let mut memory = memory;
+    assert!(memory.forget(3).is_err());
+    assert_eq!(memory.forget(1).unwrap().text, "we use pnpm, not npm");
+    let memory = Memory::open(&path).unwrap();
+    assert_eq!(memory.facts().len(), 1);
+    assert_eq!(memory.facts()[0].source, Source::Model);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,32 @@
+use deepseek_cli::mentions::{MAX_MENTION_BYTES, expand, fence_for};
+
+#[test]
+fn test_expand_mentions() {
+    let dir = std::env::temp_dir().join(format!("deepseek-mentions-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let small = dir.join("small.rs");
+    std::fs::write(&small, "fn main() {}\n").unwrap();
+    let big = dir.join("big.log");
+    std::fs::write(&big, "x".repeat(MAX_MENTION_BYTES + 10)).unwrap();
+    let small = small.display().to_string();
+    let big = big.display().to_string();
+
+    let prompt = format!("Why does @{small}, unlike @{small}, fail? Mail me@example.com");
+    assert_eq!(
+        expand(&prompt),
+        format!("{prompt}\n\n[Contents of {small}]\n```rs\nfn main() {{}}\n```")
+    );
+
+    let expanded = expand(&format!("See @{big}"));
+    assert!(expanded.contains("[First 64 KB of"), "{expanded}");
+    assert!(expanded.len() < MAX_MENTION_BYTES + 200);
+
+    assert_eq!(expand("@nowhere/missing.rs"), "@nowhere/missing.rs");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_fence_for() {
+    assert_eq!(fence_for("plain"), "```");
+    assert_eq!(fence_for("a ```rust block```"), "````");
+}
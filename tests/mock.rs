@@ -0,0 +1,122 @@
+use deepseek_cli::agent::{Agent, CONTINUE_PROMPT, Session};
+use deepseek_cli::backend::{Backend, Chunk};
+use deepseek_cli::events::{AgentEvent, EventSink};
+use deepseek_cli::mock::{Exchange, Fixture, Recorder, Replay};
+use futures_util::StreamExt;
+use std::sync::{Arc, Mutex};
+
+fn fixture() -> Fixture {
+    Fixture {
+        exchanges: vec![
+            Exchange::reply(
+                "What is the package called?",
+                "Let me look.\nTOOL: read_file Cargo.toml",
+                1,
+            ),
+            Exchange::reply("File read", "The package is deepseek-cli.", 2),
+        ],
+    }
+}
+
+#[tokio::test]
+async fn test_backend_replays_a_fixture() {
+    let replay = Replay::new(fixture());
+    let backend = Backend::from(replay.clone());
+    assert_eq!(backend.create_chat().await.unwrap(), "mock");
+    let reply = |prompt: &str| {
+        backend
+            .complete_stream(
+                "mock".to_string(),
+                prompt.to_string(),
+                None,
+                false,
+                false,
+                Vec::new(),
+            )
+            .collect::<Vec<_>>()
+    };
+    let chunks = reply("What is the package called?").await;
+    assert_eq!(
+        chunks.last().unwrap().as_ref().unwrap(),
+        &Chunk::Message {
+            content: "Let me look.\nTOOL: read_file Cargo.toml".to_string(),
+            message_id: Some(1),
+        }
+    );
+    assert!(reply("File read").await.iter().all(Result::is_ok));
+    assert_eq!(
+        replay.prompts(),
+        vec!["What is the package called?", "File read"]
+    );
+
+    let chunks = reply("And the version?").await;
+    assert_eq!(
+        chunks[0].as_ref().unwrap_err().to_string(),
+        "The fixture has no reply for request 3 (it recorded 2)"
+    );
+}
+
+#[tokio::test]
+async fn test_agent_runs_the_tool_calls_of_a_fixture() {
+    let replay = Replay::new(fixture());
+    let agent = Agent::new(Backend::from(replay.clone()));
+    let mut session = Session::new("mock".to_string(), None);
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let events = EventSink::default().without_echo().with_callback({
+        let seen = Arc::clone(&seen);
+        move |event| seen.lock().unwrap().push(event.clone())
+    });
+    session.events = session.attach(events);
+    agent
+        .send_message(&mut session, "What is the package called?")
+        .await
+        .unwrap();
+    assert_eq!(session.parent_id, Some(2));
+
+    // The tool ran for real and its result went back in the second request
+    let prompts = replay.prompts();
+    assert_eq!(prompts.len(), 2);
+    assert!(prompts[0].ends_with("User:\nWhat is the package called?"));
+    assert!(prompts[1].ends_with(CONTINUE_PROMPT), "{}", prompts[1]);
+    let seen = seen.lock().unwrap();
+    assert!(seen.iter().any(|event| matches!(
+        event,
+        AgentEvent::ToolResult { name, success: true, .. } if name == "read_file"
+    )));
+    assert!(seen.contains(&AgentEvent::Message {
+        content: "The package is deepseek-cli.".to_string(),
+        message_id: Some(2),
+    }));
+    assert_eq!(seen.last(), Some(&AgentEvent::Done));
+}
+
+#[test]
+fn test_recorded_fixture_round_trips() {
+    let path = std::env::temp_dir().join(format!("deepseek-mock-{}.json", std::process::id()));
+    let recorder = Recorder::create(&path).unwrap();
+    assert_eq!(Fixture::load(&path).unwrap(), Fixture::default());
+    for exchange in fixture().exchanges {
+        let mut chunks = vec![Chunk::Thinking {
+            text: "Hmm.".to_string(),
+        }];
+        chunks.extend(exchange.chunks);
+        recorder.record(&exchange.prompt, chunks);
+    }
+    let loaded = Fixture::load(&path).unwrap();
+    assert_eq!(loaded.exchanges.len(), 2);
+    assert_eq!(loaded.exchanges[1].prompt, "File read");
+    assert_eq!(
+        loaded.exchanges[1].chunks.last(),
+        Some(&Chunk::Message {
+            content: "The package is deepseek-cli.".to_string(),
+            message_id: Some(2),
+        })
+    );
+
+    let replay = Replay::load(&path).unwrap();
+    assert_eq!(replay.next_reply("anything").unwrap().len(), 3);
+    std::fs::write(&path, "[]").unwrap();
+    let error = Replay::load(&path).unwrap_err().to_string();
+    assert!(error.starts_with("Invalid fixture"), "{error}");
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,13 @@
+use deepseek_cli::model::Model;
+
+#[test]
+fn test_model_names() {
+    assert_eq!(Model::from_name("chat"), Some(Model::Chat));
+    assert_eq!(Model::from_name("DeepSeek-Reasoner"), Some(Model::Reasoner));
+    assert_eq!(Model::from_name("r1"), Some(Model::Reasoner));
+    assert_eq!(Model::from_name("coder"), None);
+    assert_eq!(Model::default(), Model::Reasoner);
+    assert!(Model::Reasoner.thinking());
+    assert!(!Model::Chat.thinking());
+    assert_eq!(Model::names(), "deepseek-chat, deepseek-reasoner");
+}
@@ -0,0 +1,28 @@
+use deepseek_cli::config::NotifyConfig;
+use deepseek_cli::notify::{DEFAULT_AFTER, Notifier};
+use std::time::Duration;
+
+#[test]
+fn test_turn_finished_message() {
+    let off = Notifier::from_config(&NotifyConfig::default());
+    assert!(!off.is_enabled());
+    assert_eq!(off.after, DEFAULT_AFTER);
+    assert_eq!(off.turn_finished_message(Duration::from_secs(600)), None);
+
+    let notifier = Notifier::from_config(&NotifyConfig {
+        bell: true,
+        after_secs: Some(60),
+        ..NotifyConfig::default()
+    });
+    assert!(notifier.is_enabled());
+    assert_eq!(
+        notifier.turn_finished_message(Duration::from_secs(59)),
+        None
+    );
+    assert_eq!(
+        notifier
+            .turn_finished_message(Duration::from_secs(125))
+            .as_deref(),
+        Some("The reply is ready after 2m05s")
+    );
+}
@@ -0,0 +1,66 @@
+use deepseek_cli::patch::{self, HunkLine};
+
+const PATCH: &str = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,4 +1,4 @@
+ fn one() {}
+-fn two() {}
++fn two() -> u8 { 2 }
+ fn three() {}
+
+@@ -10,3 +10,4 @@ fn nine() {}
+ fn ten() {}
++fn ten_and_a_half() {}
+ fn eleven() {}
+--- /dev/null
++++ b/NOTES.md
+@@ -0,0 +1,2 @@
++# Notes
++No newline here
+\\ No newline at end of file
+";
+
+#[test]
+fn test_parse_unified_diff() {
+    let patches = patch::parse(PATCH).unwrap();
+    assert_eq!(patches.len(), 2);
+    assert_eq!(patches[0].old_path.as_deref(), Some("src/lib.rs"));
+    assert_eq!(patches[0].hunks.len(), 2);
+    assert_eq!(patches[0].hunks[1].old_start, 10);
+    assert_eq!(
+        patches[0].hunks[0].lines[1],
+        HunkLine::Removed("fn two() {}".to_string())
+    );
+    // The blank line before the next hunk is not context
+    assert_eq!(patches[0].hunks[0].lines.len(), 4);
+    assert_eq!(patches[1].old_path, None);
+    assert_eq!(patches[1].path(), "NOTES.md");
+    assert!(patches[1].hunks[0].no_newline_at_end);
+    assert_eq!(patch::strip_level(PATCH), 1);
+
+    assert!(patch::parse("just some text").is_err());
+    assert!(patch::parse("--- a.rs\n+++ a.rs\n").is_err());
+}
+
+#[test]
+fn test_apply_with_offsets_and_fuzz() {
+    let patches = patch::parse(PATCH).unwrap();
+    // Two lines were added at the top since the diff was made, and line eleven changed
+    let content = "// header\n\nfn one() {}\nfn two() {}\nfn three() {}\nfn four() {}\nfn five() {}\nfn six() {}\nfn seven() {}\nfn eight() {}\nfn nine() {}\nfn ten() {}\nfn eleven(x: u8) {}\n";
+    let patched = patch::apply(content, &patches[0].hunks).unwrap();
+    assert_eq!(
+        patched,
+        "// header\n\nfn one() {}\nfn two() -> u8 { 2 }\nfn three() {}\nfn four() {}\nfn five() {}\nfn six() {}\nfn seven() {}\nfn eight() {}\nfn nine() {}\nfn ten() {}\nfn ten_and_a_half() {}\nfn eleven(x: u8) {}\n"
+    );
+
+    assert_eq!(
+        patch::apply("", &patches[1].hunks).unwrap(),
+        "# Notes\nNo newline here"
+    );
+
+    let err = patch::apply("fn one() {}\n", &patches[0].hunks).unwrap_err();
+    assert!(err.to_string().contains("Hunk 1"));
+}
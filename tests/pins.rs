@@ -0,0 +1,56 @@
+use deepseek_cli::pins::PinnedFiles;
+use std::fs::{self, File};
+use std::time::{Duration, SystemTime};
+
+#[test]
+fn test_refresh_reports_changes() {
+    let dir = std::env::temp_dir().join(format!("deepseek-pins-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("notes.txt");
+    let original: String = (1..=20).map(|i| format!("line {i}\n")).collect();
+    fs::write(&path, &original).unwrap();
+
+    let mut pins = PinnedFiles::default();
+    pins.pin(&path, original.clone());
+    assert!(pins.refresh().is_empty());
+
+    fs::write(&path, original.replace("line 10\n", "line ten\n")).unwrap();
+    // Make sure the change is visible even on filesystems with coarse timestamps
+    File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_modified(SystemTime::now() + Duration::from_secs(10))
+        .unwrap();
+    let notes = pins.refresh();
+    assert_eq!(notes.len(), 1);
+    assert!(notes[0].contains("-line 10\n+line ten\n"), "{}", notes[0]);
+    assert!(pins.refresh().is_empty());
+
+    fs::remove_file(&path).unwrap();
+    assert_eq!(pins.refresh().len(), 1);
+    assert!(pins.paths().is_empty());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_mark_seen_matches_the_file_however_it_is_named() {
+    let dir = std::env::temp_dir().join(format!("deepseek-pins-seen-{}", std::process::id()));
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    let path = dir.join("notes.txt");
+    fs::write(&path, "before\n").unwrap();
+
+    let mut pins = PinnedFiles::default();
+    pins.pin(&dir.join("sub/../notes.txt"), "before\n".to_string());
+    fs::write(&path, "after\n").unwrap();
+    File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_modified(SystemTime::now() + Duration::from_secs(10))
+        .unwrap();
+    // The edit was the model's own, made through the path a tool resolved
+    pins.mark_seen(&path);
+    assert!(pins.refresh().is_empty());
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,79 @@
+use deepseek_cli::events::AgentEvent;
+use deepseek_cli::pipe::{PipeRouter, Target};
+
+fn route_all(router: &mut PipeRouter, events: &[AgentEvent]) -> (String, String) {
+    let (mut stdout, mut stderr) = (String::new(), String::new());
+    for event in events {
+        for (target, text) in router.route(event) {
+            match target {
+                Target::Stdout => stdout.push_str(&text),
+                Target::Stderr => stderr.push_str(&text),
+            }
+        }
+    }
+    (stdout, stderr)
+}
+
+fn content(text: &str) -> AgentEvent {
+    AgentEvent::Content {
+        text: text.to_string(),
+    }
+}
+
+fn message(content: &str) -> AgentEvent {
+    AgentEvent::Message {
+        content: content.to_string(),
+        message_id: None,
+    }
+}
+
+#[test]
+fn test_pipe_routing() {
+    let mut router = PipeRouter::new(false);
+    let (stdout, stderr) = route_all(
+        &mut router,
+        &[
+            AgentEvent::Thinking {
+                text: "Need the file".to_string(),
+            },
+            content("TO"),
+            content("OL: read_file\nsrc/main.rs\n"),
+            message("TOOL: read_file\nsrc/main.rs\n"),
+            AgentEvent::ToolCall {
+                name: "read_file".to_string(),
+                arg: "src/main.rs".to_string(),
+            },
+            AgentEvent::ToolResult {
+                name: "read_file".to_string(),
+                status: "Read src/main.rs".to_string(),
+                success: true,
+            },
+            content("It"),
+            content(" prints hello."),
+            message("It prints hello."),
+            AgentEvent::Done,
+        ],
+    );
+    assert_eq!(stdout, "It prints hello.\n");
+    assert_eq!(
+        stderr,
+        "Need the file\nTOOL: read_file\nsrc/main.rs\nTOOL read_file src/main.rs\nRead src/main.rs\n"
+    );
+}
+
+#[test]
+fn test_pipe_quiet_drops_thinking() {
+    let mut router = PipeRouter::new(true);
+    let (stdout, stderr) = route_all(
+        &mut router,
+        &[
+            AgentEvent::Thinking {
+                text: "hmm".to_string(),
+            },
+            content("Yes"),
+            message("Yes"),
+        ],
+    );
+    assert_eq!(stdout, "Yes\n");
+    assert_eq!(stderr, "");
+}
@@ -0,0 +1,133 @@
+use deepseek_cli::config::PluginConfig;
+use deepseek_cli::plugins::{self, DEFAULT_TIMEOUT, Plugin};
+use deepseek_cli::tools::ToolOutput;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[cfg(unix)]
+fn script(dir: &Path, name: &str, body: &str) -> PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+    let path = dir.join(name);
+    std::fs::write(&path, format!("#!/bin/sh\n{body}\n")).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
+
+#[cfg(unix)]
+#[test]
+fn test_discover() {
+    let dir = std::env::temp_dir().join(format!("deepseek-plugins-{}", std::process::id()));
+    let (first, second) = (dir.join("first"), dir.join("second"));
+    std::fs::create_dir_all(&first).unwrap();
+    std::fs::create_dir_all(&second).unwrap();
+    let jira = script(&first, "deepseek-tool-jira", "cat");
+    script(&second, "deepseek-tool-jira", "exit 1");
+    script(&second, "deepseek-tool-lint", "cat");
+    script(&second, "other-tool", "cat");
+    std::fs::write(second.join("deepseek-tool-notes"), "not executable").unwrap();
+
+    let path = std::env::join_paths([&first, &second, &dir.join("missing")]).unwrap();
+    let found = plugins::discover(&path);
+    assert_eq!(
+        found.keys().collect::<Vec<_>>(),
+        ["jira", "lint"],
+        "{found:?}"
+    );
+    assert_eq!(found["jira"], jira, "the first directory on PATH wins");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_load() {
+    let discovered = BTreeMap::from([
+        ("jira".to_string(), PathBuf::from("/bin/deepseek-tool-jira")),
+        ("lint".to_string(), PathBuf::from("/bin/deepseek-tool-lint")),
+    ]);
+    let declared = BTreeMap::from([
+        (
+            "jira".to_string(),
+            PluginConfig {
+                description: Some("looks up a ticket by key".to_string()),
+                read_only: true,
+                timeout_secs: Some(5),
+                ..PluginConfig::default()
+            },
+        ),
+        (
+            "deploy".to_string(),
+            PluginConfig {
+                command: Some(PathBuf::from("scripts/deploy")),
+                ..PluginConfig::default()
+            },
+        ),
+        ("missing".to_string(), PluginConfig::default()),
+        (
+            "bad name".to_string(),
+            PluginConfig {
+                command: Some(PathBuf::from("true")),
+                ..PluginConfig::default()
+            },
+        ),
+    ]);
+    let loaded = plugins::load(&declared, &discovered, |path| {
+        Path::new("/project").join(path)
+    });
+    let names: Vec<&str> = loaded.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["deploy", "jira", "lint"]);
+
+    assert_eq!(loaded[0].program, Path::new("/project/scripts/deploy"));
+    assert!(!loaded[0].read_only);
+    assert_eq!(loaded[0].timeout, DEFAULT_TIMEOUT);
+    assert_eq!(
+        loaded[1],
+        Plugin {
+            name: "jira".to_string(),
+            program: PathBuf::from("/bin/deepseek-tool-jira"),
+            description: "jira <input> : looks up a ticket by key".to_string(),
+            read_only: true,
+            timeout: Duration::from_secs(5),
+        }
+    );
+    assert!(
+        loaded[2]
+            .description
+            .starts_with("lint <input> : runs the external program")
+    );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_run() {
+    let dir = std::env::temp_dir().join(format!("deepseek-plugins-run-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let plugin = |name: &str, body: &str| Plugin {
+        name: name.to_string(),
+        program: script(&dir, name, body),
+        description: String::new(),
+        read_only: false,
+        timeout: Duration::from_millis(500),
+    };
+
+    let output = plugin("upper", "tr a-z A-Z").run("hello\n").await.unwrap();
+    let ToolOutput::Text { content, status } = output else {
+        panic!("expected text, got {output:?}");
+    };
+    assert_eq!(content, "HELLO\n");
+    assert_eq!(status, "upper finished");
+
+    let error = plugin("fail", "echo 'no such ticket' >&2; exit 3")
+        .run("PROJ-1")
+        .await
+        .unwrap_err()
+        .to_string();
+    assert_eq!(error, "fail failed (exit code 3): no such ticket");
+
+    let error = plugin("slow", "sleep 5")
+        .run("")
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("did not finish"), "{error}");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,96 @@
+use deepseek_cli::protocol::{ToolCall, parse_tool_calls};
+
+#[test]
+fn test_parse_tool_calls_with_reason() {
+    let reply = "TOOL: read_file src/main.rs\nTOOL: write_file notes.txt\nREASON: record the findings\nfirst line\nsecond line";
+    let calls = parse_tool_calls(reply);
+    assert_eq!(
+        calls,
+        vec![
+            ToolCall {
+                name: "read_file".to_string(),
+                arg: "src/main.rs".to_string(),
+                reason: None,
+                error: None,
+            },
+            ToolCall {
+                name: "write_file".to_string(),
+                arg: "notes.txt\nfirst line\nsecond line".to_string(),
+                reason: Some("record the findings".to_string()),
+                error: None,
+            },
+        ]
+    );
+    assert_eq!(calls[1].target(), "notes.txt");
+}
+
+#[test]
+fn test_target_of_a_json_argument() {
+    let calls =
+        parse_tool_calls("TOOL: delete_file {\"path\": \"Cargo.lock\"}\nREASON: regenerate it");
+    assert_eq!(calls[0].target(), "Cargo.lock");
+}
+
+#[test]
+fn test_reason_only_counts_directly_after_the_tool_line() {
+    let calls = parse_tool_calls("TOOL: write_file a.txt\nREASON:\ntext\nREASON: not a reason");
+    assert_eq!(calls[0].reason, None);
+    assert_eq!(calls[0].arg, "a.txt\ntext\nREASON: not a reason");
+}
+
+#[test]
+fn test_parse_json_tool_calls() {
+    let reply = r#"```tool
+{"name": "write_file", "args": {"path": "notes.md", "content": "TOOL: read_file x\nend"}, "reason": "explain the syntax"}
+```
+```tool
+{"name": "apply_search_replace", "args": {"path": "a.rs", "blocks": [{"search": "old", "replace": "new"}]}, "reason": "rename"}
+```
+```tool
+{"name": "edit_lines", "args": {"path": "a.rs", "start": 3, "end": 4, "text": "fn main() {}"}, "reason": "fix"}
+```
+TOOL: grep needle"#;
+    let calls = parse_tool_calls(reply);
+    assert_eq!(calls.len(), 4);
+    assert_eq!(calls[0].name, "write_file");
+    assert_eq!(calls[0].arg, "notes.md\nTOOL: read_file x\nend");
+    assert_eq!(calls[0].reason.as_deref(), Some("explain the syntax"));
+    assert_eq!(
+        calls[1].arg,
+        "a.rs\n<<<<<<< SEARCH\nold\n=======\nnew\n>>>>>>> REPLACE"
+    );
+    assert_eq!(calls[2].arg, "a.rs 3 4\nfn main() {}");
+    assert_eq!(calls[3].arg, "needle");
+    assert!(calls.iter().all(|c| c.error.is_none()));
+}
+
+#[test]
+fn test_invalid_json_tool_calls() {
+    let calls =
+        parse_tool_calls("```tool\n{\"name\": \"read_file\", \"args\": {\"file\": \"a\"}}\n```");
+    assert_eq!(
+        calls[0].error.as_deref(),
+        Some("TOOL read_file failed: unknown argument \"file\"; read_file takes path")
+    );
+    let calls = parse_tool_calls("```tool\n{\"name\": \"read_file\",\n```");
+    assert!(
+        calls[0]
+            .error
+            .as_deref()
+            .unwrap()
+            .starts_with("TOOL tool failed: invalid JSON")
+    );
+}
+
+#[test]
+fn test_calls_in_code_blocks_are_not_run() {
+    let reply = "To read a file, write:\n```\nTOOL: read_file <path>\n```\n~~~text\nTOOL: run_command rm -rf /\n~~~\nTOOL: write_file README.md\nREASON: add an example\n````markdown\n```\nTOOL: read_file x\n```\n````\nTOOL: read_file src/lib.rs";
+    let calls = parse_tool_calls(reply);
+    let names: Vec<&str> = calls.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["write_file", "read_file"]);
+    assert_eq!(
+        calls[0].arg,
+        "README.md\n````markdown\n```\nTOOL: read_file x\n```\n````"
+    );
+    assert_eq!(calls[1].arg, "src/lib.rs");
+}
@@ -0,0 +1,70 @@
+use deepseek_cli::recipe::Recipe;
+use std::process::Command;
+
+#[cfg(not(windows))]
+#[test]
+fn test_recipe_shell_script_replays_file_writes() {
+    let dir = std::env::temp_dir().join(format!("deepseek_recipe_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let mut recipe = Recipe::default();
+    recipe.record("create_directory", "out/nested");
+    recipe.record(
+        "write_file",
+        "out/notes.txt\nit's \"quoted\"\n$HOME stays literal",
+    );
+    recipe.record("read_file", "out/notes.txt");
+    recipe.record("run_command", "echo done > out/done.txt");
+    recipe.record("write_file", "out/scratch.txt\ntemporary");
+    recipe.record("delete_file", "out/scratch.txt");
+    recipe.record("move_file", "out/done.txt\nout/moved/done.txt");
+    recipe.record("write_file", "out/list.txt\na\nb\nc\n");
+    recipe.record("edit_lines", "out/list.txt 2 2\nB1\nB2");
+    recipe.record(
+        "apply_search_replace",
+        r#"{"path": "out/list.txt", "blocks": [{"search": "B2", "replace": "B two"}]}"#,
+    );
+    assert_eq!(
+        recipe.actions().len(),
+        9,
+        "read-only tools are not recorded"
+    );
+
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(recipe.to_shell_script())
+        .current_dir(&dir)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert!(dir.join("out/nested").is_dir());
+    assert_eq!(
+        std::fs::read_to_string(dir.join("out/notes.txt")).unwrap(),
+        "it's \"quoted\"\n$HOME stays literal"
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.join("out/moved/done.txt")).unwrap(),
+        "done\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.join("out/list.txt")).unwrap(),
+        // As write_file wrote it, without the final newline
+        "a\nB1\nB two\nc"
+    );
+    assert!(!dir.join("out/done.txt").exists());
+    assert!(!dir.join("out/scratch.txt").exists());
+    assert_eq!(
+        recipe.changed_files(),
+        [
+            "out/nested",
+            "out/notes.txt",
+            "out/scratch.txt",
+            "out/done.txt",
+            "out/moved/done.txt",
+            "out/list.txt"
+        ]
+    );
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
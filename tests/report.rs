@@ -0,0 +1,81 @@
+use deepseek_cli::export::Entry;
+use deepseek_cli::recipe::RecordedAction;
+use deepseek_cli::report::{Outcome, RunReport};
+use std::time::Duration;
+
+fn action(tool: &str, arg: &str) -> RecordedAction {
+    RecordedAction {
+        tool: tool.to_string(),
+        arg: arg.to_string(),
+    }
+}
+
+#[test]
+fn test_run_report_markdown() {
+    let report = RunReport {
+        task: "task.md".to_string(),
+        outcome: Outcome::Completed,
+        duration: Duration::from_secs(95),
+        entries: vec![
+            Entry::User("Fix the build".to_string()),
+            Entry::Assistant {
+                thinking: String::new(),
+                content: "Looking at the error.".to_string(),
+            },
+            Entry::ToolResult {
+                name: "run_command".to_string(),
+                status: "exit 0".to_string(),
+                success: true,
+            },
+            Entry::ToolResult {
+                name: "read_file".to_string(),
+                status: "not found".to_string(),
+                success: false,
+            },
+            Entry::Assistant {
+                thinking: "done".to_string(),
+                content: "The build passes now.".to_string(),
+            },
+        ],
+        actions: vec![
+            action("write_file", "src/a.rs\nfn main() {}"),
+            action("edit_lines", "src/b.rs 1 2\nx"),
+            action("run_command", "cargo build"),
+            action("write_file", "src/a.rs\nfn main() { }"),
+            action("move_file", "old.txt\nnew.txt"),
+        ],
+        diff_stat: Some(" src/a.rs | 1 +\n".to_string()),
+    };
+    assert_eq!(report.final_answer(), Some("The build passes now."));
+    assert_eq!(report.tool_results(), (1, 1));
+    assert_eq!(
+        report.changed_files(),
+        ["src/a.rs", "src/b.rs", "old.txt", "new.txt"]
+    );
+    assert_eq!(report.commands(), ["cargo build"]);
+
+    let markdown = report.to_markdown();
+    assert!(markdown.contains("- Outcome: Completed\n"));
+    assert!(markdown.contains("- Tool calls: 1 succeeded, 1 failed\n"));
+    assert!(markdown.contains("- `src/b.rs`\n"));
+    assert!(markdown.contains("```\n src/a.rs | 1 +\n```"));
+    assert!(markdown.contains("```sh\ncargo build\n```"));
+    assert!(markdown.ends_with("## Final answer\n\nThe build passes now.\n"));
+}
+
+#[test]
+fn test_run_report_for_an_unfinished_run() {
+    let report = RunReport {
+        task: "task.md".to_string(),
+        outcome: Outcome::TurnLimit(20),
+        duration: Duration::ZERO,
+        entries: Vec::new(),
+        actions: Vec::new(),
+        diff_stat: None,
+    };
+    assert!(!report.outcome.is_completed());
+    let markdown = report.to_markdown();
+    assert!(markdown.contains("Stopped after 20 rounds of tool calls"));
+    assert!(markdown.contains("## Commands run\n\nNone.\n"));
+    assert!(markdown.ends_with("No answer was given.\n"));
+}
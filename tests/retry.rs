@@ -0,0 +1,50 @@
+use anyhow::anyhow;
+use deepseek_cli::retry::{Failure, classify};
+use std::time::Duration;
+
+#[test]
+fn test_classify_errors() {
+    assert_eq!(
+        classify(&anyhow!("HTTP 429 Too Many Requests: retry after 20s")),
+        Failure::RateLimited {
+            retry_after: Some(Duration::from_secs(20))
+        }
+    );
+    assert_eq!(
+        classify(&anyhow!(
+            "Rate limit reached. Please try again in 1.5 seconds"
+        )),
+        Failure::RateLimited {
+            retry_after: Some(Duration::from_millis(1500))
+        }
+    );
+    assert_eq!(
+        classify(&anyhow!("rate limited (Retry-After: 500 ms)")),
+        Failure::RateLimited {
+            retry_after: Some(Duration::from_millis(500))
+        }
+    );
+    assert_eq!(
+        classify(&anyhow!("too many requests")),
+        Failure::RateLimited { retry_after: None }
+    );
+    assert_eq!(classify(&anyhow!("401 Unauthorized")), Failure::Fatal);
+    assert_eq!(
+        classify(&anyhow!(
+            "error sending request for url (https://chat.deepseek.com/): dns error"
+        )),
+        Failure::Offline
+    );
+    assert_eq!(
+        classify(&anyhow!("error decoding response body")),
+        Failure::Transient
+    );
+}
+
+#[test]
+fn test_status_codes_need_word_boundaries() {
+    assert_eq!(
+        classify(&anyhow!("stream ended at message 14012")),
+        Failure::Transient
+    );
+}
@@ -0,0 +1,88 @@
+use deepseek_cli::review::{self, Chunk, Severity};
+
+const DIFF: &str = "\
+diff --git a/src/a.rs b/src/a.rs
+index 1111111..2222222 100644
+--- a/src/a.rs
++++ b/src/a.rs
+@@ -1,2 +1,2 @@
+ fn main() {
+-    old();
++    new();
+@@ -10,1 +10,2 @@
+ }
++// trailing
+diff --git a/notes.txt b/notes.txt
+new file mode 100644
+--- /dev/null
++++ b/notes.txt
+@@ -0,0 +1 @@
++hello
+diff --git a/logo.png b/logo.png
+Binary files a/logo.png and b/logo.png differ
+";
+
+#[test]
+fn test_chunks_group_hunks_by_file() {
+    let chunks = review::chunks(DIFF, review::MAX_CHUNK_BYTES);
+    assert_eq!(
+        chunks,
+        [
+            Chunk {
+                path: "src/a.rs".to_string(),
+                diff: "@@ -1,2 +1,2 @@\n fn main() {\n-    old();\n+    new();\n@@ -10,1 +10,2 @@\n }\n+// trailing\n".to_string(),
+            },
+            Chunk {
+                path: "notes.txt".to_string(),
+                diff: "@@ -0,0 +1 @@\n+hello\n".to_string(),
+            },
+        ]
+    );
+
+    // A small budget puts each hunk in its own chunk
+    let chunks = review::chunks(DIFF, 60);
+    let paths: Vec<&str> = chunks.iter().map(|c| c.path.as_str()).collect();
+    assert_eq!(paths, ["src/a.rs", "src/a.rs", "notes.txt"]);
+}
+
+#[test]
+fn test_chunks_split_large_hunks() {
+    let diff = format!(
+        "--- a/f\n+++ b/f\n@@ -1 +1,4 @@\n{}",
+        "+0123456789\n".repeat(4)
+    );
+    let chunks = review::chunks(&diff, 40);
+    assert_eq!(chunks.len(), 3);
+    assert!(chunks[0].diff.starts_with("@@ -1 +1,4 @@\n+0123456789\n"));
+    assert!(chunks[1].diff.starts_with("@@ -1 +1,4 @@ (continued)\n"));
+    assert!(chunks.iter().all(|c| c.diff.len() <= 40));
+}
+
+#[test]
+fn test_parse_findings() {
+    let reply = "- [high] line 12: the lock is never released\n[Low] naming is inconsistent\n[medium] Line 3: unchecked unwrap\nNo issues.\n[unsure] something";
+    let findings = review::parse_findings(reply, "src/a.rs");
+    let parsed: Vec<(Severity, Option<usize>, &str)> = findings
+        .iter()
+        .map(|f| (f.severity, f.line, f.message.as_str()))
+        .collect();
+    assert_eq!(
+        parsed,
+        [
+            (Severity::High, Some(12), "the lock is never released"),
+            (Severity::Low, None, "naming is inconsistent"),
+            (Severity::Medium, Some(3), "unchecked unwrap"),
+        ]
+    );
+    assert!(review::parse_findings("No issues.", "src/a.rs").is_empty());
+
+    let mut all = review::parse_findings("[low] line 1: b", "b.rs");
+    all.extend(findings);
+    let groups = review::by_file(&all);
+    assert_eq!(groups[0].0, "b.rs");
+    let severities: Vec<Severity> = groups[1].1.iter().map(|f| f.severity).collect();
+    assert_eq!(
+        severities,
+        [Severity::High, Severity::Medium, Severity::Low]
+    );
+}
@@ -0,0 +1,29 @@
+use deepseek_cli::sandbox::resolve;
+
+#[test]
+fn test_resolve_stays_in_root() {
+    let root = std::env::temp_dir().join(format!("deepseek-sandbox-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::create_dir_all(root.join(".git")).unwrap();
+    std::fs::write(root.join("src/old.rs"), "").unwrap();
+    let canonical = root.canonicalize().unwrap();
+
+    assert_eq!(
+        resolve(&root, "src/old.rs").unwrap(),
+        canonical.join("src/old.rs")
+    );
+    // Paths that do not exist yet, e.g. a move's destination, are fine too
+    assert_eq!(
+        resolve(&root, "src/new/../lib.rs").unwrap(),
+        canonical.join("src/lib.rs")
+    );
+
+    assert!(resolve(&root, "").is_err());
+    assert!(resolve(&root, ".").is_err());
+    assert!(resolve(&root, "../elsewhere.rs").is_err());
+    assert!(resolve(&root, "src/new/../../../elsewhere.rs").is_err());
+    assert!(resolve(&root, "/etc/hosts").is_err());
+    assert!(resolve(&root, ".git/config").is_err());
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,63 @@
+use deepseek_cli::format::structured_reply;
+use deepseek_cli::schema::validate;
+use serde_json::json;
+
+fn schema() -> serde_json::Value {
+    json!({
+        "type": "object",
+        "required": ["title", "labels", "severity"],
+        "additionalProperties": false,
+        "properties": {
+            "title": { "type": "string", "minLength": 3 },
+            "labels": { "type": "array", "items": { "type": "string" }, "maxItems": 2 },
+            "severity": { "enum": ["low", "medium", "high"] },
+            "estimate": { "type": ["integer", "null"], "minimum": 1 }
+        }
+    })
+}
+
+#[test]
+fn test_validate() {
+    let valid =
+        json!({ "title": "Crash on start", "labels": ["bug"], "severity": "high", "estimate": 3 });
+    assert_eq!(validate(&schema(), &valid), Vec::<String>::new());
+
+    let invalid = json!({
+        "title": "no",
+        "labels": ["bug", 7, "ui"],
+        "estimate": 0,
+        "owner": "me"
+    });
+    let mut errors = validate(&schema(), &invalid);
+    errors.sort();
+    assert_eq!(
+        errors,
+        [
+            "(root): missing required property \"severity\"",
+            "/estimate: must be at least 1",
+            "/labels/1: expected string, got number",
+            "/labels: must have at most 2 items, has 3",
+            "/owner: property is not allowed",
+            "/title: must have at least 3 characters, has 2",
+        ]
+    );
+    assert_eq!(
+        validate(&schema(), &json!([])),
+        ["(root): expected object, got array"]
+    );
+}
+
+#[test]
+fn test_structured_reply() {
+    let reply =
+        "```json\n{\"title\": \"Crash on start\", \"labels\": [], \"severity\": \"low\",}\n```";
+    assert!(structured_reply(reply, Some(&schema())).is_ok());
+    let error = structured_reply("{\"title\": \"Crash\"}", Some(&schema()))
+        .unwrap_err()
+        .to_string();
+    assert!(
+        error.contains("missing required property \"labels\""),
+        "{error}"
+    );
+    assert!(structured_reply("[1, 2]", None).is_ok());
+}
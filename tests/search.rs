@@ -0,0 +1,103 @@
+use deepseek_cli::config::SearchConfig;
+use deepseek_cli::search::{
+    Provider, SearchResult, format_results, parse_brave, parse_duckduckgo_lite, parse_searxng,
+    providers,
+};
+
+fn result(title: &str, url: &str, snippet: &str) -> SearchResult {
+    SearchResult {
+        title: title.to_string(),
+        url: url.to_string(),
+        snippet: snippet.to_string(),
+    }
+}
+
+#[test]
+fn test_default_providers() {
+    assert_eq!(
+        providers(&SearchConfig::default()).unwrap(),
+        [Provider::DuckDuckGoLite, Provider::DuckDuckGo]
+    );
+    let config = SearchConfig {
+        searxng_url: Some("https://searx.example.org".to_string()),
+        brave_api_key: Some("key".to_string()),
+        ..SearchConfig::default()
+    };
+    assert_eq!(
+        providers(&config).unwrap(),
+        [
+            Provider::Brave {
+                api_key: "key".to_string()
+            },
+            Provider::Searxng {
+                url: "https://searx.example.org".to_string()
+            },
+            Provider::DuckDuckGoLite,
+            Provider::DuckDuckGo,
+        ]
+    );
+}
+
+#[test]
+fn test_configured_providers() {
+    let config = SearchConfig {
+        providers: Some(vec!["duckduckgo".to_string(), "brave".to_string()]),
+        ..SearchConfig::default()
+    };
+    assert!(
+        providers(&config)
+            .unwrap_err()
+            .to_string()
+            .contains("brave_api_key")
+    );
+    let config = SearchConfig {
+        providers: Some(vec!["bing".to_string()]),
+        ..SearchConfig::default()
+    };
+    assert!(
+        providers(&config)
+            .unwrap_err()
+            .to_string()
+            .starts_with("Unknown search provider bing")
+    );
+}
+
+#[test]
+fn test_parse_duckduckgo_lite() {
+    let html = r#"<html><body><table>
+<tr><td>1.</td><td><a rel="nofollow" href="//duckduckgo.com/l/?uddg=https%3A%2F%2Fwww.rust-lang.org%2F&amp;rut=abc" class="result-link">Rust   Programming Language</a></td></tr>
+<tr><td></td><td class="result-snippet">A language empowering <b>everyone</b>.</td></tr>
+<tr><td>2.</td><td><a href="https://doc.rust-lang.org/book/" class="result-link">The Book</a></td></tr>
+<tr><td></td><td class="result-snippet">Learn Rust.</td></tr>
+</table></body></html>"#;
+    assert_eq!(
+        parse_duckduckgo_lite(html),
+        [
+            result(
+                "Rust Programming Language",
+                "https://www.rust-lang.org/",
+                "A language empowering everyone."
+            ),
+            result("The Book", "https://doc.rust-lang.org/book/", "Learn Rust."),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_json_providers() {
+    let brave = r#"{"web":{"results":[{"title":"Tokio","url":"https://tokio.rs/","description":"An <strong>async</strong> runtime"}]}}"#;
+    assert_eq!(
+        parse_brave(brave).unwrap(),
+        [result("Tokio", "https://tokio.rs/", "An async runtime")]
+    );
+    let searxng = r#"{"results":[{"title":"Serde","url":"https://serde.rs/","content":"Serialization"},{"title":"","url":"https://x"}]}"#;
+    assert_eq!(
+        parse_searxng(searxng).unwrap(),
+        [result("Serde", "https://serde.rs/", "Serialization")]
+    );
+    assert!(parse_searxng("<html>Forbidden</html>").is_err());
+    assert_eq!(
+        format_results(&[result("Serde", "https://serde.rs/", "Serialization")]),
+        "Title: Serde\nURL: https://serde.rs/\nSnippet: Serialization\n---"
+    );
+}
@@ -0,0 +1,34 @@
+use deepseek_cli::sensitive::{allow, guarded, matching_pattern};
+use std::path::Path;
+
+#[test]
+fn test_sensitive_patterns() {
+    assert_eq!(matching_pattern(Path::new(".env")), Some(".env"));
+    assert_eq!(
+        matching_pattern(Path::new("app/.env.local")),
+        Some(".env.*")
+    );
+    assert_eq!(
+        matching_pattern(Path::new("certs/server.pem")),
+        Some("*.pem")
+    );
+    assert_eq!(
+        matching_pattern(Path::new("/home/u/.ssh/id_rsa")),
+        Some("id_rsa")
+    );
+    assert_eq!(
+        matching_pattern(Path::new("/home/u/.aws/credentials")),
+        Some(".aws/credentials")
+    );
+    assert_eq!(matching_pattern(Path::new("/home/u/.ssh/id_rsa.pub")), None);
+    assert_eq!(matching_pattern(Path::new("credentials")), None);
+    assert_eq!(matching_pattern(Path::new("src/main.rs")), None);
+}
+
+#[test]
+fn test_allowed_files_are_not_guarded() {
+    let path = Path::new("tests/fixtures-that-do-not-exist/.env");
+    assert!(guarded(path).is_some());
+    allow(path);
+    assert!(guarded(path).is_none());
+}
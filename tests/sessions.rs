@@ -0,0 +1,27 @@
+use deepseek_cli::sessions::{SessionRegistry, validate_name};
+
+#[test]
+fn test_session_registry() {
+    let mut registry = SessionRegistry::default();
+    assert!(registry.get("myproject").is_none());
+
+    registry.set("myproject", "chat-a", None);
+    registry.set("docs", "chat-b", Some(7));
+    registry.set("myproject", "chat-a", Some(12));
+    let session = registry.get("myproject").unwrap();
+    assert_eq!(session.chat_id, "chat-a");
+    assert_eq!(session.parent_id, Some(12));
+
+    let names: Vec<&str> = registry.iter().map(|(name, _)| name).collect();
+    assert_eq!(names, ["docs", "myproject"]);
+    assert_eq!(registry.remove("docs").unwrap().chat_id, "chat-b");
+    assert!(registry.get("docs").is_none());
+}
+
+#[test]
+fn test_validate_session_name() {
+    assert!(validate_name("my-project_2.0").is_ok());
+    assert!(validate_name("").is_err());
+    assert!(validate_name("my project").is_err());
+    assert!(validate_name("../etc").is_err());
+}
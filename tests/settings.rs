@@ -0,0 +1,43 @@
+use deepseek_cli::model::Model;
+use deepseek_cli::settings::{Setting, render};
+
+#[test]
+fn test_parse_settings() {
+    assert_eq!(
+        Setting::parse("search off").unwrap(),
+        Setting::Search(false)
+    );
+    assert_eq!(
+        Setting::parse(" Search  on ").unwrap(),
+        Setting::Search(true)
+    );
+    assert_eq!(
+        Setting::parse("thinking off").unwrap(),
+        Setting::Model(Model::Chat)
+    );
+    assert_eq!(
+        Setting::parse("thinking yes").unwrap(),
+        Setting::Model(Model::Reasoner)
+    );
+    assert!(Setting::parse("search").is_err());
+    assert!(Setting::parse("search maybe").is_err());
+    assert!(Setting::parse("colour on").is_err());
+
+    // Sampling parameters are refused with the reason rather than ignored
+    let error = Setting::parse("temperature 0.2").unwrap_err().to_string();
+    assert!(
+        error.starts_with(
+            "temperature cannot be set: the DeepSeek chat API takes no sampling parameters"
+        ),
+        "{error}"
+    );
+    assert!(Setting::parse("top_p 0.9").is_err());
+}
+
+#[test]
+fn test_render_settings() {
+    assert_eq!(
+        render(false, Model::Reasoner),
+        "search    off\nthinking  on (deepseek-reasoner)"
+    );
+}
@@ -0,0 +1,11 @@
+use deepseek_cli::spinner::{self, Spinner};
+
+#[test]
+fn test_suspend_runs_the_prompt() {
+    assert_eq!(spinner::suspend(|| 42), 42);
+    let waiting = Spinner::start("Waiting for the response");
+    waiting.set_message("Thinking");
+    assert_eq!(spinner::suspend(|| "yes"), "yes");
+    drop(waiting);
+    assert_eq!(spinner::suspend(|| 1), 1);
+}
@@ -0,0 +1,118 @@
+use deepseek_cli::stats::{RequestUsage, SessionStats, estimate_tokens, format_tokens};
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_tools_by_cost() {
+    let mut stats = SessionStats::default();
+    stats.record_tool_call("read_file", "Executed tool: read_file");
+    stats.record_attachment("read_file", &"x".repeat(4000));
+    stats.record_tool_call("list_files", "Executed tool: list_files");
+    stats.record_tool_call("read_file", "Executed tool: read_file");
+
+    let tools = stats.tools_by_cost();
+    assert_eq!(tools[0].0, "read_file");
+    assert_eq!(tools[0].1.calls, 2);
+    assert_eq!(tools[0].1.largest, 1000);
+    assert_eq!(tools[1].0, "list_files");
+    assert!(stats.render().contains("read_file"));
+}
+
+#[test]
+fn test_token_formatting() {
+    assert_eq!(estimate_tokens(""), 0);
+    assert_eq!(estimate_tokens("hello"), 2);
+    assert_eq!(format_tokens(950), "950");
+    assert_eq!(format_tokens(12_345), "12.3k");
+    assert_eq!(format_tokens(1_200_000), "1.2M");
+}
+
+#[test]
+fn test_usage_totals() {
+    let mut stats = SessionStats::default();
+    assert_eq!(stats.render_usage(), "No requests yet");
+    stats.record_request(RequestUsage {
+        prompt_tokens: 1500,
+        completion_tokens: 300,
+    });
+    stats.record_request(RequestUsage {
+        prompt_tokens: 200,
+        completion_tokens: 50,
+    });
+    assert_eq!(
+        stats.total_usage(),
+        RequestUsage {
+            prompt_tokens: 1700,
+            completion_tokens: 350,
+        }
+    );
+    let rendered = stats.render_usage();
+    assert!(
+        rendered.starts_with(
+            "Token usage (estimated): 2 requests, ~1.7k prompt + ~350 completion = ~2.0k tokens"
+        ),
+        "{rendered}"
+    );
+    assert!(rendered.ends_with("last request: ~200 prompt + ~50 completion"));
+}
+
+#[test]
+fn test_context_tokens_and_compaction() {
+    let mut stats = SessionStats::default();
+    stats.record_request(RequestUsage {
+        prompt_tokens: 600,
+        completion_tokens: 100,
+    });
+    stats.record_attachment("read_file", &"x".repeat(400));
+    assert_eq!(stats.context_tokens(), 800);
+    assert!(!stats.needs_compaction(1000, 90));
+    assert!(stats.needs_compaction(1000, 80));
+    assert!(!stats.needs_compaction(1000, 0));
+
+    // A new chat starts empty, but the session totals stay
+    stats.start_chat();
+    assert_eq!(stats.context_tokens(), 0);
+    assert_eq!(stats.total_usage().prompt_tokens, 600);
+}
+
+#[test]
+fn test_turn_timing() {
+    let mut stats = SessionStats::default();
+    assert_eq!(stats.render_turns(), "No turns yet");
+    // Outside a turn, timings are not kept
+    stats.record_tool_time(Duration::from_secs(9));
+    assert!(stats.finish_turn(Instant::now()).is_none());
+
+    let started = Instant::now();
+    stats.start_turn(started);
+    stats.record_stream(None, Duration::from_millis(300));
+    stats.record_request(RequestUsage {
+        prompt_tokens: 100,
+        completion_tokens: 0,
+    });
+    stats.record_stream(Some(Duration::from_millis(1200)), Duration::from_secs(3));
+    stats.record_request(RequestUsage {
+        prompt_tokens: 40,
+        completion_tokens: 60,
+    });
+    stats.record_tool_time(Duration::from_millis(1500));
+    stats.record_stream(Some(Duration::from_millis(400)), Duration::from_secs(1));
+    let turn = stats.finish_turn(started + Duration::from_secs(6)).unwrap();
+    assert_eq!(turn.requests, 2);
+    assert_eq!(turn.first_token, Some(Duration::from_millis(1200)));
+    assert_eq!(turn.streaming, Duration::from_millis(4300));
+    assert_eq!(turn.tools, Duration::from_millis(1500));
+    assert_eq!(
+        turn.render_footer(),
+        "first token 1.2s · streaming 4.3s · tools 1.5s · total 6.0s · ~140 prompt + ~60 completion tokens"
+    );
+    assert_eq!(stats.turns(), [turn]);
+    let rendered = stats.render_turns();
+    assert!(
+        rendered.starts_with("Turn timing (the last 1):"),
+        "{rendered}"
+    );
+    assert!(
+        rendered.ends_with("average: first token 1.2s, streaming 4.3s, tools 1.5s, total 6.0s"),
+        "{rendered}"
+    );
+}
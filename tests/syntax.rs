@@ -0,0 +1,99 @@
+use deepseek_cli::syntax::{Chunk, Symbol, chunk_source, chunk_text, outline};
+use std::path::Path;
+
+fn chunk(name: &str, kind: &str, start_line: usize, end_line: usize) -> Chunk {
+    Chunk {
+        name: name.to_string(),
+        kind: kind.to_string(),
+        start_line,
+        end_line,
+    }
+}
+
+#[test]
+fn test_chunk_rust_source_by_item() {
+    let source = "\
+use std::fmt;
+use std::io;
+
+/// Doc comment
+#[derive(Debug)]
+struct Foo;
+
+impl Foo {
+    fn a() {}
+}
+
+fn main() {}
+";
+    let chunks = chunk_source(Path::new("lib.rs"), source, 400);
+    assert_eq!(
+        chunks,
+        vec![
+            chunk("imports", "imports", 1, 2),
+            chunk("Foo", "struct_item", 3, 6),
+            chunk("impl Foo", "impl_item", 7, 10),
+            chunk("main", "function_item", 11, 12),
+        ]
+    );
+    assert_eq!(
+        chunk_text(source, &chunks[2]),
+        "\nimpl Foo {\n    fn a() {}\n}"
+    );
+}
+
+#[test]
+fn test_chunk_unknown_language_by_lines() {
+    let source = "a\nb\nc\nd\ne\n";
+    let chunks = chunk_source(Path::new("notes.txt"), source, 2);
+    assert_eq!(
+        chunks,
+        vec![
+            chunk("part1", "lines", 1, 2),
+            chunk("part2", "lines", 3, 4),
+            chunk("part3", "lines", 5, 5),
+        ]
+    );
+}
+
+#[test]
+fn test_outline_lists_items_and_their_methods() {
+    let source = "\
+use std::fmt;
+
+struct Foo {
+    a: u32,
+}
+
+impl Foo {
+    fn new() -> Self {
+        fn helper() {}
+        Foo { a: 0 }
+    }
+
+    fn get(&self) -> u32 {
+        self.a
+    }
+}
+
+fn main() {}
+";
+    let symbol = |name: &str, kind: &str, start_line, end_line, depth| Symbol {
+        name: name.to_string(),
+        kind: kind.to_string(),
+        start_line,
+        end_line,
+        depth,
+    };
+    assert_eq!(
+        outline(Path::new("lib.rs"), source),
+        Some(vec![
+            symbol("Foo", "struct", 3, 5, 0),
+            symbol("impl Foo", "impl", 7, 16, 0),
+            symbol("new", "function", 8, 11, 1),
+            symbol("get", "function", 13, 15, 1),
+            symbol("main", "function", 18, 18, 0),
+        ])
+    );
+    assert_eq!(outline(Path::new("notes.txt"), "text"), None);
+}
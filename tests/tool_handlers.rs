@@ -0,0 +1,233 @@
+use deepseek_cli::index::FileIndex;
+use deepseek_cli::protocol::parse_tool_calls;
+use deepseek_cli::tools::{self, ToolContext, ToolResult, ToolStatus, registry};
+use deepseek_cli::workspace::{Root, Workspace};
+use std::path::PathBuf;
+
+/// A fresh directory for one test to run tools in.
+fn workspace(name: &str) -> (ToolContext, PathBuf) {
+    let root =
+        std::env::temp_dir().join(format!("deepseek-handlers-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    (ToolContext::new(&root), root)
+}
+
+async fn run(cx: &ToolContext, name: &str, arg: &str) -> ToolResult {
+    registry().execute_in(cx, name, arg).await
+}
+
+#[tokio::test]
+async fn test_write_file_creates_parent_directories() {
+    let (cx, root) = workspace("write");
+    let result = run(&cx, "write_file", "notes/2024/today.md\n# Today\n- ship it").await;
+    assert!(result.is_success(), "{}", result.summary);
+    assert_eq!(result.summary, "File written: notes/2024/today.md");
+    assert_eq!(
+        std::fs::read_to_string(root.join("notes/2024/today.md")).unwrap(),
+        "# Today\n- ship it"
+    );
+    // Relative paths never land in the process's own directory
+    assert!(!PathBuf::from("notes/2024/today.md").exists());
+
+    let result = run(&cx, "read_file", "notes/2024/today.md").await;
+    assert_eq!(result.body.as_deref(), Some("# Today\n- ship it"));
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_search_replace_edge_cases() {
+    let (cx, root) = workspace("search-replace");
+    std::fs::write(root.join("a.rs"), "let x = 1;\nlet y = x;\nlet z = x;\n").unwrap();
+
+    // Blocks apply in order, each to the result of the one before, and replace every match
+    let result = run(
+        &cx,
+        "apply_search_replace",
+        "a.rs\n<<<<<<< SEARCH\nlet x = 1;\n=======\nlet w = 1;\n>>>>>>> REPLACE\n<<<<<<< SEARCH\n= x;\n=======\n= w;\n>>>>>>> REPLACE",
+    )
+    .await;
+    assert_eq!(result.summary, "Applied 2 block(s) to a.rs");
+    assert_eq!(
+        std::fs::read_to_string(root.join("a.rs")).unwrap(),
+        "let w = 1;\nlet y = w;\nlet z = w;\n"
+    );
+
+    // A block that does not match fails the call and leaves the file as it was, even
+    // when the blocks before it matched
+    let result = run(
+        &cx,
+        "apply_search_replace",
+        r#"{"path": "a.rs", "blocks": [{"search": "let w", "replace": "let v"}, {"search": "missing", "replace": "x"}]}"#,
+    )
+    .await;
+    assert_eq!(result.status, ToolStatus::Failed);
+    assert!(result.summary.contains("Search string not found in a.rs"));
+    assert!(
+        std::fs::read_to_string(root.join("a.rs"))
+            .unwrap()
+            .starts_with("let w = 1;")
+    );
+
+    let result = run(&cx, "apply_search_replace", "a.rs\nno blocks here").await;
+    assert!(!result.is_success());
+    let result = run(
+        &cx,
+        "apply_search_replace",
+        "missing.rs\n<<<<<<< SEARCH\na\n=======\nb\n>>>>>>> REPLACE",
+    )
+    .await;
+    assert!(!result.is_success());
+    assert!(!root.join("missing.rs").exists());
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_run_command_exit_codes() {
+    let (cx, root) = workspace("run-command");
+    let result = run(&cx, "run_command", "echo hello > out.txt").await;
+    assert_eq!(result.summary, "Command succeeded (exit code: 0)");
+    assert_eq!(
+        result.body.as_deref(),
+        Some("Command executed with no output")
+    );
+    // The command ran in the root
+    assert_eq!(
+        std::fs::read_to_string(root.join("out.txt"))
+            .unwrap()
+            .trim(),
+        "hello"
+    );
+
+    let result = run(&cx, "run_command", "echo oops >&2; exit 3").await;
+    // A command that fails still ran, so the call succeeds and reports the exit code
+    assert!(result.is_success());
+    assert_eq!(result.summary, "Command failed (exit code: 3)");
+    assert_eq!(result.body.as_deref().map(str::trim), Some("stderr:\noops"));
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_parsed_calls_run_in_the_root() {
+    let (cx, root) = workspace("parsed");
+    let reply = "I'll set up the project.\n\
+        TOOL: create_directory src\n\
+        REASON: the sources go there\n\
+        TOOL: write_file src/main.rs\n\
+        REASON: the entry point\n\
+        fn main() {}\n\
+        TOOL: move_file src/main.rs\n\
+        REASON: it is a library\n\
+        src/lib.rs\n\
+        TOOL: list_files src";
+    let calls = parse_tool_calls(reply);
+    let names: Vec<&str> = calls.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(
+        names,
+        ["create_directory", "write_file", "move_file", "list_files"]
+    );
+    let mut results = Vec::new();
+    for call in &calls {
+        results.push(run(&cx, &call.name, &call.arg).await);
+    }
+    assert!(results.iter().all(ToolResult::is_success), "{results:?}");
+    assert_eq!(results[2].summary, "Moved src/main.rs to src/lib.rs");
+    assert_eq!(results[3].body.as_deref(), Some("lib.rs"));
+    assert_eq!(
+        std::fs::read_to_string(root.join("src/lib.rs")).unwrap(),
+        "fn main() {}"
+    );
+
+    // Files outside the root are not deleted
+    let result = run(&cx, "delete_file", "../elsewhere.txt").await;
+    assert!(
+        result.summary.contains("outside the working directory"),
+        "{}",
+        result.summary
+    );
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_paths_are_normalized_within_the_root() {
+    let (cx, root) = workspace("paths");
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(root.join("src/lib.rs"), "pub fn f() {}\n").unwrap();
+
+    let result = run(&cx, "write_file", "./src/../notes.txt\nhello").await;
+    assert_eq!(result.summary, "File written: notes.txt");
+    let result = run(&cx, "grep", "pub fn").await;
+    assert_eq!(result.body.as_deref(), Some("src/lib.rs:1: pub fn f() {}"));
+
+    // `..` may not lead out of the root, and nothing is written there
+    let result = run(&cx, "write_file", "src/../../escaped.txt\nno").await;
+    assert_eq!(result.status, ToolStatus::Failed);
+    assert!(
+        result.summary.contains("leads outside the workspace"),
+        "{}",
+        result.summary
+    );
+    assert!(!root.with_file_name("escaped.txt").exists());
+
+    // An absolute path is taken as it is and shown normalized
+    let absolute = format!("{}/src/./../src/lib.rs", root.display());
+    let result = run(&cx, "read_file", &absolute).await;
+    assert_eq!(result.summary, "Read file at src/lib.rs");
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_tools_reach_other_roots_by_name() {
+    let (_, api) = workspace("roots-api");
+    let (_, web) = workspace("roots-web");
+    let cx = ToolContext {
+        workspace: Workspace::detect(&api, vec![Root::new("api", &api), Root::new("web", &web)]),
+    };
+    std::fs::write(api.join("main.rs"), "fn main() {}\n").unwrap();
+
+    let result = run(&cx, "write_file", "web:src/app.ts\nexport {}").await;
+    assert_eq!(result.summary, "File written: web:src/app.ts");
+    assert!(web.join("src/app.ts").exists());
+    let result = run(&cx, "move_file", "web:src/app.ts\nweb:src/index.ts").await;
+    assert_eq!(result.summary, "Moved web:src/app.ts to web:src/index.ts");
+    let result = run(&cx, "read_file", "main.rs").await;
+    assert_eq!(result.summary, "Read file at main.rs");
+
+    let index = FileIndex::build_workspace(&cx.workspace);
+    assert_eq!(index.files(), ["main.rs", "web:src/index.ts"]);
+    std::fs::remove_dir_all(&api).unwrap();
+    std::fs::remove_dir_all(&web).unwrap();
+}
+
+#[tokio::test]
+async fn test_ask_user_needs_a_question() {
+    let (cx, root) = workspace("ask-empty");
+    let result = run(&cx, "ask_user", "  \n- yes\n- no").await;
+    assert_eq!(result.status, ToolStatus::Failed);
+    assert!(
+        result.summary.contains("the question is missing"),
+        "{}",
+        result.summary
+    );
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_ask_user_fails_without_a_terminal() {
+    let (cx, root) = workspace("ask-noninteractive");
+    tools::set_interactive(false);
+    // Fails at once instead of waiting on stdin for an answer that cannot come
+    let result = tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        run(&cx, "ask_user", "Which database?\n- postgres\n- sqlite"),
+    )
+    .await
+    .expect("ask_user waited for an answer");
+    assert_eq!(result.status, ToolStatus::Failed);
+    assert!(
+        result.summary.contains("not available in this interface"),
+        "{}",
+        result.summary
+    );
+    std::fs::remove_dir_all(&root).unwrap();
+}
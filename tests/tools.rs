@@ -0,0 +1,179 @@
+use deepseek_cli::tools::{
+    AgentMode, Tool, ToolOutput, ToolRegistry, ToolResult, ToolStatus, html_to_markdown,
+    is_text_content_type, needs_approval, pretty_json, replace_lines, runs_in_parallel,
+};
+
+#[test]
+fn test_runs_in_parallel() {
+    assert!(runs_in_parallel("read_file"));
+    assert!(runs_in_parallel("grep"));
+    assert!(runs_in_parallel("fetch_url"));
+    assert!(!runs_in_parallel("write_file"));
+    assert!(!runs_in_parallel("run_command"));
+    assert!(!runs_in_parallel("ask_user"));
+    assert!(!runs_in_parallel("browser_get_html"));
+    assert!(!runs_in_parallel("no_such_tool"));
+}
+
+#[test]
+fn test_edit_lines() {
+    let content = "one\ntwo\nthree\nfour\n";
+    assert_eq!(
+        replace_lines(content, 2, 3, "2\n3").unwrap(),
+        "one\n2\n3\nfour\n"
+    );
+    assert_eq!(replace_lines(content, 2, 3, "").unwrap(), "one\nfour\n");
+    assert_eq!(
+        replace_lines(content, 1, 0, "zero").unwrap(),
+        "zero\none\ntwo\nthree\nfour\n"
+    );
+    assert_eq!(
+        replace_lines(content, 5, 4, "five").unwrap(),
+        "one\ntwo\nthree\nfour\nfive\n"
+    );
+    assert_eq!(replace_lines("a\r\nb", 2, 2, "c").unwrap(), "a\r\nc");
+    assert!(replace_lines(content, 0, 1, "x").is_err());
+    assert!(replace_lines(content, 3, 5, "x").is_err());
+    assert!(replace_lines(content, 3, 1, "x").is_err());
+}
+
+#[test]
+fn test_html_to_markdown_keeps_main_content() {
+    let html = r#"<!DOCTYPE html>
+<html>
+<head><title>Release notes</title><style>body { color: red; }</style></head>
+<body>
+  <nav><a href="/">Home</a> <a href="/docs">Docs</a></nav>
+  <main>
+    <h1>Release   notes</h1>
+    <p>Version <b>2.0</b> adds <a href="/changes#new">new tools</a>
+       and fixes <code>fetch_url</code>.</p>
+    <script>track();</script>
+    <ul><li>Faster</li><li>Smaller</li></ul>
+    <pre>cargo install
+  --force</pre>
+  </main>
+  <footer>Copyright</footer>
+</body>
+</html>"#;
+    assert_eq!(
+        html_to_markdown(html, Some("https://example.com/blog/")),
+        "# Release notes\n\n\
+         Version **2.0** adds [new tools](https://example.com/changes#new) and fixes `fetch_url`.\n\n\
+         - Faster\n- Smaller\n\n\
+         ```\ncargo install\n  --force\n```"
+    );
+}
+
+#[test]
+fn test_html_to_markdown_without_main_drops_page_chrome() {
+    let html = "<html><body><header>Site</header><div>Hello<br>world</div>\
+                <table><tr><th>a</th><th>b</th></tr><tr><td>1</td><td>2</td></tr></table>\
+                <aside>Ads</aside></body></html>";
+    assert_eq!(
+        html_to_markdown(html, None),
+        "Hello\n\nworld\n\n| a | b |\n| --- | --- |\n| 1 | 2 |"
+    );
+}
+
+#[test]
+fn test_fetch_content_types() {
+    assert!(is_text_content_type("text/html; charset=utf-8"));
+    assert!(is_text_content_type("application/json"));
+    assert!(is_text_content_type("application/vnd.api+json"));
+    assert!(is_text_content_type("image/svg+xml"));
+    assert!(!is_text_content_type("image/png"));
+    assert!(!is_text_content_type("application/octet-stream"));
+    assert!(!is_text_content_type("application/pdf"));
+    assert_eq!(
+        pretty_json(r#"{"a":[1,2]}"#).as_deref(),
+        Some("{\n  \"a\": [\n    1,\n    2\n  ]\n}")
+    );
+    assert_eq!(pretty_json("{not json"), None);
+}
+
+#[test]
+fn test_builtin_metadata() {
+    assert!(needs_approval("write_file"));
+    assert!(needs_approval("run_command"));
+    assert!(!needs_approval("read_file"));
+    assert!(
+        !needs_approval("browser_click"),
+        "browser tools act on their own browser"
+    );
+    assert!(!needs_approval("no_such_tool"));
+}
+
+#[tokio::test]
+async fn test_tool_registry() {
+    let registry = ToolRegistry::new();
+    registry.register("deploy", "deploy <env> : deploys the app", |_, arg| {
+        Box::pin(async move {
+            anyhow::Ok(ToolOutput::StatusOnly {
+                status: format!("Deployed to {arg}"),
+            })
+        })
+    });
+    let lookup = Tool::new("ticket <key> : shows a ticket", |_, _| {
+        Box::pin(async { Err(anyhow::anyhow!("no such ticket")) })
+    })
+    .read_only()
+    .params(&["key"]);
+    assert!(!registry.insert("ticket", lookup));
+    assert_eq!(registry.names(), ["deploy", "ticket"]);
+    assert!(registry.get("deploy").unwrap().needs_approval());
+    assert!(registry.get("ticket").unwrap().is_read_only());
+
+    let prompt = registry.system_prompt(AgentMode::Normal, None, None);
+    assert!(prompt.contains("- deploy : deploy <env> : deploys the app\n"));
+    assert!(prompt.contains("- ticket : ticket <key> : shows a ticket (JSON args: key)"));
+    assert!(prompt.contains("Tools that change files or run commands (deploy)"));
+    let plan = registry.descriptions(AgentMode::Plan, None);
+    assert_eq!(
+        plan,
+        "- ticket : ticket <key> : shows a ticket (JSON args: key)"
+    );
+
+    let result = registry.execute("deploy", "staging").await;
+    assert_eq!(
+        result,
+        ToolResult {
+            status: ToolStatus::Succeeded,
+            summary: "Deployed to staging".to_string(),
+            body: None,
+            artifacts: Vec::new(),
+        }
+    );
+    let result = registry.execute("ticket", "X-1").await;
+    assert_eq!(result.status, ToolStatus::Failed);
+    assert_eq!(result.summary, "TOOL ticket failed: no such ticket");
+
+    assert!(registry.remove("deploy"));
+    assert!(!registry.contains("deploy"));
+    assert!(!registry.execute("deploy", "staging").await.is_success());
+}
+
+#[test]
+fn test_tool_result_from_output() {
+    let result = ToolResult::from(ToolOutput::Binary {
+        data: vec![0x89, b'P', b'N', b'G'],
+        mime_type: "image/png".to_string(),
+        status: "Took a screenshot".to_string(),
+    });
+    assert!(result.is_success());
+    assert_eq!(
+        serde_json::to_value(&result).unwrap(),
+        serde_json::json!({
+            "status": "succeeded",
+            "summary": "Took a screenshot",
+            "body": null,
+            "artifacts": [{"type": "binary", "mime_type": "image/png"}],
+        })
+    );
+    let result = ToolResult::from(ToolOutput::Text {
+        content: "fn main() {}".to_string(),
+        status: "Read file at src/main.rs".to_string(),
+    });
+    assert_eq!(result.body.as_deref(), Some("fn main() {}"));
+    assert!(result.artifacts.is_empty());
+}
@@ -0,0 +1,10 @@
+use deepseek_cli::tui::wrap;
+
+#[test]
+fn test_wrap() {
+    assert_eq!(wrap("abcdef", 4), vec!["abcd", "ef"]);
+    assert_eq!(wrap("ab\n\ncd", 4), vec!["ab", "", "cd"]);
+    // Wide characters take two columns
+    assert_eq!(wrap("你好世界", 5), vec!["你好", "世界"]);
+    assert_eq!(wrap("", 10), vec![""]);
+}
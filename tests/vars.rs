@@ -0,0 +1,27 @@
+use deepseek_cli::vars::Variables;
+
+#[test]
+fn test_expand_variables() {
+    let mut vars = Variables::default();
+    assert_eq!(vars.expand("Fix $TICKET"), "Fix $TICKET");
+    vars.set("TICKET=ABC-123").unwrap();
+    vars.set(" branch = fix/login ").unwrap();
+    assert_eq!(
+        vars.expand("Fix $TICKET on ${branch}, see ${TICKET}s and $TICKETS"),
+        "Fix ABC-123 on fix/login, see ABC-123s and $TICKETS"
+    );
+    // Unknown names and lone dollars are kept
+    assert_eq!(
+        vars.expand("echo $HOME costs $5 ${"),
+        "echo $HOME costs $5 ${"
+    );
+    assert_eq!(
+        vars.entries().collect::<Vec<_>>(),
+        [("TICKET", "ABC-123"), ("branch", "fix/login")]
+    );
+
+    assert!(vars.unset("branch"));
+    assert!(!vars.unset("branch"));
+    assert!(vars.set("no equals sign").is_err());
+    assert!(vars.set("1ST=x").is_err());
+}
@@ -0,0 +1,55 @@
+use deepseek_cli::watch::{ChangeFilter, message};
+use deepseek_cli::workspace::{Root, Workspace};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+fn workspace() -> Workspace {
+    Workspace::detect(
+        Path::new("/work/api/src"),
+        vec![Root::new("api", "/work/api"), Root::new("web", "/work/web")],
+    )
+}
+
+#[test]
+fn test_change_filter() {
+    let workspace = workspace();
+    let rust = ChangeFilter::new(vec!["**/*.rs".to_string(), "Cargo.toml".to_string()]);
+    let matches = |path: &str| rust.matches(&workspace, Path::new(path));
+    assert_eq!(
+        matches("/work/api/src/main.rs"),
+        Some("src/main.rs".to_string())
+    );
+    assert_eq!(
+        matches("/work/api/Cargo.toml"),
+        Some("Cargo.toml".to_string())
+    );
+    assert_eq!(
+        matches("/work/web/build.rs"),
+        Some("web:build.rs".to_string())
+    );
+    assert_eq!(matches("/work/api/README.md"), None);
+    // Build output and repositories never count, nor files outside the workspace
+    assert_eq!(matches("/work/api/target/debug/build/out.rs"), None);
+    assert_eq!(matches("/work/api/.git/index"), None);
+    assert_eq!(matches("/elsewhere/lib.rs"), None);
+
+    let everything = ChangeFilter::new(Vec::new());
+    assert_eq!(everything.globs(), ["**/*"]);
+    assert_eq!(
+        everything.matches(&workspace, Path::new("/work/web/src/app.ts")),
+        Some("web:src/app.ts".to_string())
+    );
+    assert_eq!(
+        everything.matches(&workspace, Path::new("/work/web/node_modules/x/index.js")),
+        None
+    );
+}
+
+#[test]
+fn test_message() {
+    let changed = BTreeSet::from(["src/main.rs".to_string(), "src/cli.rs".to_string()]);
+    assert_eq!(
+        message(" run cargo check and fix errors\n", &changed),
+        "These files just changed: src/cli.rs, src/main.rs\n\nrun cargo check and fix errors"
+    );
+}
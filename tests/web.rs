@@ -0,0 +1,34 @@
+use deepseek_cli::web::parse_head;
+
+#[test]
+fn test_parse_head() {
+    let request = parse_head(
+        "POST /api/send HTTP/1.1\r\nHost: 127.0.0.1:8321\r\nContent-Length: 2\r\nX-Deepseek-Cli: 1\r\n\r\n",
+    )
+    .unwrap();
+    assert_eq!(request.method, "POST");
+    assert_eq!(request.path, "/api/send");
+    assert_eq!(request.header("content-length"), Some("2"));
+    assert_eq!(request.header("x-deepseek-cli"), Some("1"));
+    assert!(request.is_local());
+
+    assert!(parse_head("\r\n\r\n").is_err());
+}
+
+#[test]
+fn test_rejects_foreign_hosts() {
+    for host in [
+        "evil.example:8321",
+        "127.0.0.1.evil.example",
+        "localhost.evil",
+    ] {
+        let request = parse_head(&format!("GET / HTTP/1.1\r\nHost: {host}\r\n\r\n")).unwrap();
+        assert!(!request.is_local(), "{host}");
+    }
+    assert!(!parse_head("GET / HTTP/1.1\r\n\r\n").unwrap().is_local());
+    assert!(
+        parse_head("GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap()
+            .is_local()
+    );
+}
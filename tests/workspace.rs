@@ -0,0 +1,112 @@
+use deepseek_cli::workspace::{Root, Workspace, display, find_root, normalize, resolve};
+use std::path::{Path, PathBuf};
+
+#[test]
+fn test_normalize() {
+    assert_eq!(
+        normalize(Path::new("./src/../tests/./a.rs")),
+        Some(PathBuf::from("tests/a.rs"))
+    );
+    assert_eq!(normalize(Path::new("src/..")), Some(PathBuf::new()));
+    assert_eq!(normalize(Path::new("src/../../a.rs")), None);
+    // `..` cannot climb above the root of an absolute path
+    assert_eq!(
+        normalize(Path::new("/../etc//hosts")),
+        Some(PathBuf::from("/etc/hosts"))
+    );
+}
+
+#[test]
+fn test_resolve_and_display() {
+    let root = Path::new("/work/project");
+    let file = resolve(root, " src/./main.rs ").unwrap();
+    assert_eq!(file, root.join("src/main.rs"));
+    assert_eq!(display(root, &file), "src/main.rs");
+    assert_eq!(display(root, &resolve(root, "src/..").unwrap()), ".");
+
+    let error = resolve(root, "src/../../other/a.rs")
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("leads outside the workspace"), "{error}");
+    assert!(resolve(root, "  ").is_err());
+
+    // Absolute paths may point anywhere and are shown in full, normalized
+    let outside = resolve(root, "/tmp/../etc/hosts").unwrap();
+    assert_eq!(outside, PathBuf::from("/etc/hosts"));
+    assert_eq!(display(root, &outside), "/etc/hosts");
+    let inside = resolve(root, "/work/project/src/../Cargo.toml").unwrap();
+    assert_eq!(display(root, &inside), "Cargo.toml");
+}
+
+#[test]
+fn test_find_root() {
+    let root = std::env::temp_dir().join(format!("deepseek-workspace-{}", std::process::id()));
+    let nested = root.join("crates/core/src");
+    std::fs::create_dir_all(&nested).unwrap();
+    assert_eq!(find_root(&nested), nested);
+    std::fs::create_dir(root.join(".git")).unwrap();
+    assert_eq!(find_root(&nested), root);
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+fn two_roots() -> Workspace {
+    Workspace::detect(
+        Path::new("/work/api/src"),
+        vec![Root::new("api", "/work/api"), Root::new("web", "/work/web")],
+    )
+}
+
+#[test]
+fn test_workspace_roots() {
+    let mut workspace = two_roots();
+    assert_eq!(workspace.active().name, "api");
+    assert_eq!(
+        workspace.resolve("src/main.rs").unwrap(),
+        PathBuf::from("/work/api/src/main.rs")
+    );
+    assert_eq!(
+        workspace.resolve("web:src/app.ts").unwrap(),
+        PathBuf::from("/work/web/src/app.ts")
+    );
+    assert_eq!(
+        workspace.resolve("web:").unwrap(),
+        PathBuf::from("/work/web")
+    );
+    // Each root keeps `..` in; another root is reached by name rather than climbing
+    assert!(workspace.resolve("web:../api/secret").is_err());
+    assert!(workspace.resolve("../web/src/app.ts").is_err());
+    // A prefix that names no root is part of the path
+    assert_eq!(
+        workspace.resolve("docs:intro.md").unwrap(),
+        PathBuf::from("/work/api/docs:intro.md")
+    );
+
+    let app = Path::new("/work/web/src/app.ts");
+    assert_eq!(workspace.display(app), "web:src/app.ts");
+    assert_eq!(
+        workspace.display(Path::new("/work/api/Cargo.toml")),
+        "Cargo.toml"
+    );
+    assert_eq!(workspace.display(Path::new("/etc/hosts")), "/etc/hosts");
+
+    workspace.switch("web").unwrap();
+    assert_eq!(workspace.root(), Path::new("/work/web"));
+    assert_eq!(workspace.display(app), "src/app.ts");
+    assert_eq!(
+        workspace.display(Path::new("/work/api/Cargo.toml")),
+        "api:Cargo.toml"
+    );
+    let error = workspace.switch("docs").unwrap_err().to_string();
+    assert_eq!(error, "No workspace root named docs (expected api, web)");
+}
+
+#[test]
+fn test_project_outside_the_roots_is_added() {
+    let workspace = Workspace::detect(
+        Path::new("/work/tools"),
+        vec![Root::new("web", "/work/web")],
+    );
+    let names: Vec<&str> = workspace.roots().iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, ["tools", "web"]);
+    assert_eq!(workspace.root(), Path::new("/work/tools"));
+}